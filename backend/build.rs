@@ -128,6 +128,7 @@ fn main() {
     let text_alphabet_txt = dir.join("alphabet_94.txt");
 
     tonic_build::compile_protos("proto/input.proto").unwrap();
+    tonic_build::compile_protos("proto/control.proto").unwrap();
     println!(
         "cargo:rustc-env=POPUP_YES_TEMPLATE={}",
         popup_yes.to_str().unwrap()
@@ -1,7 +1,9 @@
 use std::{
-    cell::RefCell,
-    collections::{HashMap, HashSet, hash_map::Entry},
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet, VecDeque, hash_map::Entry},
     fmt::Debug,
+    thread,
+    time::{Duration, Instant},
 };
 
 use anyhow::{Result, bail};
@@ -15,12 +17,13 @@ use platforms::{
     input::{
         Input as PlatformInput, InputKind as PlatformInputKind,
         InputReceiver as PlatformInputReceiver, KeyKind as PlatformKeyKind,
-        KeyState as PlatformKeyState, MouseKind as PlatformMouseKind,
+        KeyState as PlatformKeyState, KeyboardLayout as PlatformKeyboardLayout,
+        MouseKind as PlatformMouseKind, set_keyboard_layout as set_platform_keyboard_layout,
     },
 };
 
 use crate::{
-    models::{CaptureMode, KeyBinding, LinkKeyBinding},
+    models::{CaptureMode, KeyBinding, KeyboardLayout, LinkKeyBinding},
     rng::Rng,
     rpc::{
         Coordinate as RpcCoordinate, InputService, Key as RpcKeyKind, KeyState as RpcKeyState,
@@ -156,6 +159,18 @@ pub enum KeyKind {
     F10,
     F11,
     F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
 
     Up,
     Down,
@@ -181,6 +196,23 @@ pub enum KeyKind {
     Shift,
     Alt,
     Backspace,
+    NumpadZero,
+    NumpadOne,
+    NumpadTwo,
+    NumpadThree,
+    NumpadFour,
+    NumpadFive,
+    NumpadSix,
+    NumpadSeven,
+    NumpadEight,
+    NumpadNine,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+    Mouse4,
+    Mouse5,
 }
 
 impl From<KeyBinding> for KeyKind {
@@ -234,6 +266,18 @@ impl From<KeyBinding> for KeyKind {
             KeyBinding::F10 => KeyKind::F10,
             KeyBinding::F11 => KeyKind::F11,
             KeyBinding::F12 => KeyKind::F12,
+            KeyBinding::F13 => KeyKind::F13,
+            KeyBinding::F14 => KeyKind::F14,
+            KeyBinding::F15 => KeyKind::F15,
+            KeyBinding::F16 => KeyKind::F16,
+            KeyBinding::F17 => KeyKind::F17,
+            KeyBinding::F18 => KeyKind::F18,
+            KeyBinding::F19 => KeyKind::F19,
+            KeyBinding::F20 => KeyKind::F20,
+            KeyBinding::F21 => KeyKind::F21,
+            KeyBinding::F22 => KeyKind::F22,
+            KeyBinding::F23 => KeyKind::F23,
+            KeyBinding::F24 => KeyKind::F24,
             KeyBinding::Up => KeyKind::Up,
             KeyBinding::Down => KeyKind::Down,
             KeyBinding::Left => KeyKind::Left,
@@ -257,6 +301,23 @@ impl From<KeyBinding> for KeyKind {
             KeyBinding::Ctrl => KeyKind::Ctrl,
             KeyBinding::Alt => KeyKind::Alt,
             KeyBinding::Backspace => KeyKind::Backspace,
+            KeyBinding::NumpadZero => KeyKind::NumpadZero,
+            KeyBinding::NumpadOne => KeyKind::NumpadOne,
+            KeyBinding::NumpadTwo => KeyKind::NumpadTwo,
+            KeyBinding::NumpadThree => KeyKind::NumpadThree,
+            KeyBinding::NumpadFour => KeyKind::NumpadFour,
+            KeyBinding::NumpadFive => KeyKind::NumpadFive,
+            KeyBinding::NumpadSix => KeyKind::NumpadSix,
+            KeyBinding::NumpadSeven => KeyKind::NumpadSeven,
+            KeyBinding::NumpadEight => KeyKind::NumpadEight,
+            KeyBinding::NumpadNine => KeyKind::NumpadNine,
+            KeyBinding::NumpadAdd => KeyKind::NumpadAdd,
+            KeyBinding::NumpadSubtract => KeyKind::NumpadSubtract,
+            KeyBinding::NumpadMultiply => KeyKind::NumpadMultiply,
+            KeyBinding::NumpadDivide => KeyKind::NumpadDivide,
+            KeyBinding::NumpadDecimal => KeyKind::NumpadDecimal,
+            KeyBinding::Mouse4 => KeyKind::Mouse4,
+            KeyBinding::Mouse5 => KeyKind::Mouse5,
         }
     }
 }
@@ -312,6 +373,18 @@ impl From<PlatformKeyKind> for KeyKind {
             PlatformKeyKind::F10 => KeyKind::F10,
             PlatformKeyKind::F11 => KeyKind::F11,
             PlatformKeyKind::F12 => KeyKind::F12,
+            PlatformKeyKind::F13 => KeyKind::F13,
+            PlatformKeyKind::F14 => KeyKind::F14,
+            PlatformKeyKind::F15 => KeyKind::F15,
+            PlatformKeyKind::F16 => KeyKind::F16,
+            PlatformKeyKind::F17 => KeyKind::F17,
+            PlatformKeyKind::F18 => KeyKind::F18,
+            PlatformKeyKind::F19 => KeyKind::F19,
+            PlatformKeyKind::F20 => KeyKind::F20,
+            PlatformKeyKind::F21 => KeyKind::F21,
+            PlatformKeyKind::F22 => KeyKind::F22,
+            PlatformKeyKind::F23 => KeyKind::F23,
+            PlatformKeyKind::F24 => KeyKind::F24,
             PlatformKeyKind::Up => KeyKind::Up,
             PlatformKeyKind::Down => KeyKind::Down,
             PlatformKeyKind::Left => KeyKind::Left,
@@ -335,6 +408,23 @@ impl From<PlatformKeyKind> for KeyKind {
             PlatformKeyKind::Shift => KeyKind::Shift,
             PlatformKeyKind::Alt => KeyKind::Alt,
             PlatformKeyKind::Backspace => KeyKind::Backspace,
+            PlatformKeyKind::NumpadZero => KeyKind::NumpadZero,
+            PlatformKeyKind::NumpadOne => KeyKind::NumpadOne,
+            PlatformKeyKind::NumpadTwo => KeyKind::NumpadTwo,
+            PlatformKeyKind::NumpadThree => KeyKind::NumpadThree,
+            PlatformKeyKind::NumpadFour => KeyKind::NumpadFour,
+            PlatformKeyKind::NumpadFive => KeyKind::NumpadFive,
+            PlatformKeyKind::NumpadSix => KeyKind::NumpadSix,
+            PlatformKeyKind::NumpadSeven => KeyKind::NumpadSeven,
+            PlatformKeyKind::NumpadEight => KeyKind::NumpadEight,
+            PlatformKeyKind::NumpadNine => KeyKind::NumpadNine,
+            PlatformKeyKind::NumpadAdd => KeyKind::NumpadAdd,
+            PlatformKeyKind::NumpadSubtract => KeyKind::NumpadSubtract,
+            PlatformKeyKind::NumpadMultiply => KeyKind::NumpadMultiply,
+            PlatformKeyKind::NumpadDivide => KeyKind::NumpadDivide,
+            PlatformKeyKind::NumpadDecimal => KeyKind::NumpadDecimal,
+            PlatformKeyKind::Mouse4 => KeyKind::Mouse4,
+            PlatformKeyKind::Mouse5 => KeyKind::Mouse5,
         }
     }
 }
@@ -390,6 +480,18 @@ impl From<KeyKind> for PlatformKeyKind {
             KeyKind::F10 => PlatformKeyKind::F10,
             KeyKind::F11 => PlatformKeyKind::F11,
             KeyKind::F12 => PlatformKeyKind::F12,
+            KeyKind::F13 => PlatformKeyKind::F13,
+            KeyKind::F14 => PlatformKeyKind::F14,
+            KeyKind::F15 => PlatformKeyKind::F15,
+            KeyKind::F16 => PlatformKeyKind::F16,
+            KeyKind::F17 => PlatformKeyKind::F17,
+            KeyKind::F18 => PlatformKeyKind::F18,
+            KeyKind::F19 => PlatformKeyKind::F19,
+            KeyKind::F20 => PlatformKeyKind::F20,
+            KeyKind::F21 => PlatformKeyKind::F21,
+            KeyKind::F22 => PlatformKeyKind::F22,
+            KeyKind::F23 => PlatformKeyKind::F23,
+            KeyKind::F24 => PlatformKeyKind::F24,
             KeyKind::Up => PlatformKeyKind::Up,
             KeyKind::Down => PlatformKeyKind::Down,
             KeyKind::Left => PlatformKeyKind::Left,
@@ -413,6 +515,23 @@ impl From<KeyKind> for PlatformKeyKind {
             KeyKind::Shift => PlatformKeyKind::Shift,
             KeyKind::Alt => PlatformKeyKind::Alt,
             KeyKind::Backspace => PlatformKeyKind::Backspace,
+            KeyKind::NumpadZero => PlatformKeyKind::NumpadZero,
+            KeyKind::NumpadOne => PlatformKeyKind::NumpadOne,
+            KeyKind::NumpadTwo => PlatformKeyKind::NumpadTwo,
+            KeyKind::NumpadThree => PlatformKeyKind::NumpadThree,
+            KeyKind::NumpadFour => PlatformKeyKind::NumpadFour,
+            KeyKind::NumpadFive => PlatformKeyKind::NumpadFive,
+            KeyKind::NumpadSix => PlatformKeyKind::NumpadSix,
+            KeyKind::NumpadSeven => PlatformKeyKind::NumpadSeven,
+            KeyKind::NumpadEight => PlatformKeyKind::NumpadEight,
+            KeyKind::NumpadNine => PlatformKeyKind::NumpadNine,
+            KeyKind::NumpadAdd => PlatformKeyKind::NumpadAdd,
+            KeyKind::NumpadSubtract => PlatformKeyKind::NumpadSubtract,
+            KeyKind::NumpadMultiply => PlatformKeyKind::NumpadMultiply,
+            KeyKind::NumpadDivide => PlatformKeyKind::NumpadDivide,
+            KeyKind::NumpadDecimal => PlatformKeyKind::NumpadDecimal,
+            KeyKind::Mouse4 => PlatformKeyKind::Mouse4,
+            KeyKind::Mouse5 => PlatformKeyKind::Mouse5,
         }
     }
 }
@@ -468,6 +587,18 @@ impl From<KeyKind> for RpcKeyKind {
             KeyKind::F10 => RpcKeyKind::F10,
             KeyKind::F11 => RpcKeyKind::F11,
             KeyKind::F12 => RpcKeyKind::F12,
+            KeyKind::F13 => RpcKeyKind::F13,
+            KeyKind::F14 => RpcKeyKind::F14,
+            KeyKind::F15 => RpcKeyKind::F15,
+            KeyKind::F16 => RpcKeyKind::F16,
+            KeyKind::F17 => RpcKeyKind::F17,
+            KeyKind::F18 => RpcKeyKind::F18,
+            KeyKind::F19 => RpcKeyKind::F19,
+            KeyKind::F20 => RpcKeyKind::F20,
+            KeyKind::F21 => RpcKeyKind::F21,
+            KeyKind::F22 => RpcKeyKind::F22,
+            KeyKind::F23 => RpcKeyKind::F23,
+            KeyKind::F24 => RpcKeyKind::F24,
             KeyKind::Up => RpcKeyKind::Up,
             KeyKind::Down => RpcKeyKind::Down,
             KeyKind::Left => RpcKeyKind::Left,
@@ -491,6 +622,23 @@ impl From<KeyKind> for RpcKeyKind {
             KeyKind::Shift => RpcKeyKind::Shift,
             KeyKind::Alt => RpcKeyKind::Alt,
             KeyKind::Backspace => RpcKeyKind::Backspace,
+            KeyKind::NumpadZero => RpcKeyKind::NumpadZero,
+            KeyKind::NumpadOne => RpcKeyKind::NumpadOne,
+            KeyKind::NumpadTwo => RpcKeyKind::NumpadTwo,
+            KeyKind::NumpadThree => RpcKeyKind::NumpadThree,
+            KeyKind::NumpadFour => RpcKeyKind::NumpadFour,
+            KeyKind::NumpadFive => RpcKeyKind::NumpadFive,
+            KeyKind::NumpadSix => RpcKeyKind::NumpadSix,
+            KeyKind::NumpadSeven => RpcKeyKind::NumpadSeven,
+            KeyKind::NumpadEight => RpcKeyKind::NumpadEight,
+            KeyKind::NumpadNine => RpcKeyKind::NumpadNine,
+            KeyKind::NumpadAdd => RpcKeyKind::NumpadAdd,
+            KeyKind::NumpadSubtract => RpcKeyKind::NumpadSubtract,
+            KeyKind::NumpadMultiply => RpcKeyKind::NumpadMultiply,
+            KeyKind::NumpadDivide => RpcKeyKind::NumpadDivide,
+            KeyKind::NumpadDecimal => RpcKeyKind::NumpadDecimal,
+            KeyKind::Mouse4 => RpcKeyKind::Mouse4,
+            KeyKind::Mouse5 => RpcKeyKind::Mouse5,
         }
     }
 }
@@ -546,6 +694,18 @@ impl From<KeyKind> for KeyBinding {
             KeyKind::F10 => KeyBinding::F10,
             KeyKind::F11 => KeyBinding::F11,
             KeyKind::F12 => KeyBinding::F12,
+            KeyKind::F13 => KeyBinding::F13,
+            KeyKind::F14 => KeyBinding::F14,
+            KeyKind::F15 => KeyBinding::F15,
+            KeyKind::F16 => KeyBinding::F16,
+            KeyKind::F17 => KeyBinding::F17,
+            KeyKind::F18 => KeyBinding::F18,
+            KeyKind::F19 => KeyBinding::F19,
+            KeyKind::F20 => KeyBinding::F20,
+            KeyKind::F21 => KeyBinding::F21,
+            KeyKind::F22 => KeyBinding::F22,
+            KeyKind::F23 => KeyBinding::F23,
+            KeyKind::F24 => KeyBinding::F24,
             KeyKind::Up => KeyBinding::Up,
             KeyKind::Down => KeyBinding::Down,
             KeyKind::Left => KeyBinding::Left,
@@ -569,6 +729,23 @@ impl From<KeyKind> for KeyBinding {
             KeyKind::Ctrl => KeyBinding::Ctrl,
             KeyKind::Alt => KeyBinding::Alt,
             KeyKind::Backspace => KeyBinding::Backspace,
+            KeyKind::NumpadZero => KeyBinding::NumpadZero,
+            KeyKind::NumpadOne => KeyBinding::NumpadOne,
+            KeyKind::NumpadTwo => KeyBinding::NumpadTwo,
+            KeyKind::NumpadThree => KeyBinding::NumpadThree,
+            KeyKind::NumpadFour => KeyBinding::NumpadFour,
+            KeyKind::NumpadFive => KeyBinding::NumpadFive,
+            KeyKind::NumpadSix => KeyBinding::NumpadSix,
+            KeyKind::NumpadSeven => KeyBinding::NumpadSeven,
+            KeyKind::NumpadEight => KeyBinding::NumpadEight,
+            KeyKind::NumpadNine => KeyBinding::NumpadNine,
+            KeyKind::NumpadAdd => KeyBinding::NumpadAdd,
+            KeyKind::NumpadSubtract => KeyBinding::NumpadSubtract,
+            KeyKind::NumpadMultiply => KeyBinding::NumpadMultiply,
+            KeyKind::NumpadDivide => KeyBinding::NumpadDivide,
+            KeyKind::NumpadDecimal => KeyBinding::NumpadDecimal,
+            KeyKind::Mouse4 => KeyBinding::Mouse4,
+            KeyKind::Mouse5 => KeyBinding::Mouse5,
         }
     }
 }
@@ -664,6 +841,14 @@ enum InputMethodInner {
     Default(PlatformInput),
 }
 
+/// Sets the keyboard layout used by [`InputMethod::Default`] to translate keys.
+pub fn set_keyboard_layout(layout: KeyboardLayout) {
+    set_platform_keyboard_layout(match layout {
+        KeyboardLayout::Active => PlatformKeyboardLayout::Active,
+        KeyboardLayout::Qwerty => PlatformKeyboardLayout::Qwerty,
+    });
+}
+
 /// States of input delay tracking.
 #[derive(Debug)]
 enum InputDelay {
@@ -681,6 +866,18 @@ pub trait Input: Debug {
     /// Overwrites the current input method with new `method`.
     fn set_method(&mut self, method: InputMethod);
 
+    /// Overwrites the current key translation table with new `remaps`.
+    ///
+    /// Each key sent or queried is translated through `remaps` before reaching the input method.
+    fn set_key_remaps(&mut self, remaps: HashMap<KeyKind, KeyKind>);
+
+    /// Overwrites the current key repeat `interval`.
+    ///
+    /// When set, held down keys are repeatedly sent down at `interval` to emulate the OS key
+    /// repeat. The effective interval is bounded below by the tick duration. Currently supports
+    /// only [`InputMethod::Default`].
+    fn set_key_repeat(&mut self, interval: Option<Duration>);
+
     /// Sends mouse `kind` to `(x, y)` relative to the client coordinate (e.g. capture area).
     ///
     /// `(0, 0)` is top-left and `(width, height)` is bottom-right.
@@ -707,6 +904,9 @@ pub trait Input: Debug {
 
     /// Whether all keys are cleared.
     fn all_keys_cleared(&self) -> bool;
+
+    /// Whether any key stroke was rejected by the input method since the last call.
+    fn take_rejected(&self) -> bool;
 }
 
 /// Default implementation of [`Input`].
@@ -716,6 +916,12 @@ pub struct DefaultInput {
     delay_rng: Rng,
     delay_mean_std_pair: (f32, f32),
     delay_map: RefCell<HashMap<KeyKind, (u32, bool)>>,
+    key_remaps: HashMap<KeyKind, KeyKind>,
+    key_repeat_interval: Option<Duration>,
+    /// Held down keys and the last time each was sent down.
+    held_keys: RefCell<HashMap<KeyKind, Instant>>,
+    /// Whether any key stroke was rejected since the last [`Input::take_rejected`].
+    rejected: Cell<bool>,
 }
 
 impl DefaultInput {
@@ -725,9 +931,25 @@ impl DefaultInput {
             delay_rng: rng,
             delay_mean_std_pair: (BASE_MEAN_MS_DELAY, BASE_STD_MS_DELAY),
             delay_map: RefCell::new(HashMap::new()),
+            key_remaps: HashMap::new(),
+            key_repeat_interval: None,
+            held_keys: RefCell::new(HashMap::new()),
+            rejected: Cell::new(false),
         }
     }
 
+    #[inline]
+    fn track_rejected(&self, result: Result<()>) {
+        if result.is_err() {
+            self.rejected.set(true);
+        }
+    }
+
+    #[inline]
+    fn remapped(&self, kind: KeyKind) -> KeyKind {
+        self.key_remaps.get(&kind).copied().unwrap_or(kind)
+    }
+
     #[inline]
     fn key_state(&self, kind: KeyKind) -> Result<KeyState> {
         match &self.kind {
@@ -771,6 +993,7 @@ impl DefaultInput {
             }
             InputMethodInner::Default(input) => {
                 if forced || !self.has_input_delay(kind) {
+                    self.held_keys.borrow_mut().remove(&kind);
                     input.send_key_up(kind.into())?;
                 }
             }
@@ -792,6 +1015,7 @@ impl DefaultInput {
             }
             InputMethodInner::Default(input) => {
                 if !self.has_input_delay(kind) {
+                    self.held_keys.borrow_mut().insert(kind, Instant::now());
                     input.send_key_down(kind.into(), repeatable)?;
                 }
             }
@@ -847,6 +1071,8 @@ impl DefaultInput {
             )
         }
 
+        self.update_key_repeat();
+
         let mut map = self.delay_map.borrow_mut();
         if map.is_empty() {
             return;
@@ -873,6 +1099,28 @@ impl DefaultInput {
         }
     }
 
+    /// Sends down strokes again for held down keys that were last sent at least the key repeat
+    /// interval ago.
+    fn update_key_repeat(&self) {
+        let (Some(interval), InputMethodInner::Default(input)) =
+            (self.key_repeat_interval, &self.kind)
+        else {
+            return;
+        };
+
+        let now = Instant::now();
+        for (kind, last_sent) in self.held_keys.borrow_mut().iter_mut() {
+            if now.duration_since(*last_sent) >= interval {
+                *last_sent = now;
+                self.track_rejected(
+                    input
+                        .send_key_down((*kind).into(), true)
+                        .map_err(Into::into),
+                );
+            }
+        }
+    }
+
     fn random_input_delay_tick_count(&self) -> (f32, u32) {
         let (mean, std) = self.delay_mean_std_pair;
         self.delay_rng
@@ -887,6 +1135,15 @@ impl Input for DefaultInput {
 
     fn set_method(&mut self, method: InputMethod) {
         self.kind = input_method_inner_from(method, self.delay_rng.rng_seed());
+        self.held_keys.borrow_mut().clear();
+    }
+
+    fn set_key_remaps(&mut self, remaps: HashMap<KeyKind, KeyKind>) {
+        self.key_remaps = remaps;
+    }
+
+    fn set_key_repeat(&mut self, interval: Option<Duration>) {
+        self.key_repeat_interval = interval;
     }
 
     fn send_mouse(&self, x: i32, y: i32, kind: MouseKind) {
@@ -923,25 +1180,30 @@ impl Input for DefaultInput {
     }
 
     fn send_key(&self, kind: KeyKind) {
-        let _ = self.send_key_inner(kind);
+        self.track_rejected(self.send_key_inner(self.remapped(kind)));
     }
 
     fn send_key_up(&self, kind: KeyKind) {
-        let _ = self.send_key_up_inner(kind, false);
+        self.track_rejected(self.send_key_up_inner(self.remapped(kind), false));
     }
 
     fn send_key_down_with_options(&self, kind: KeyKind, options: InputKeyDownOptions) {
-        let _ = self.send_key_down_inner(kind, options.repeatable);
+        self.track_rejected(self.send_key_down_inner(self.remapped(kind), options.repeatable));
     }
 
     fn is_key_cleared(&self, kind: KeyKind) -> bool {
-        !self.delay_map.borrow().contains_key(&kind)
+        !self.has_input_delay(self.remapped(kind))
     }
 
     #[inline]
     fn all_keys_cleared(&self) -> bool {
         self.delay_map.borrow().is_empty()
     }
+
+    #[inline]
+    fn take_rejected(&self) -> bool {
+        self.rejected.replace(false)
+    }
 }
 
 /// A trait for managing different capture modes.
@@ -958,12 +1220,62 @@ pub trait Capture: Debug + 'static {
     fn mode(&self) -> CaptureMode;
 
     fn set_mode(&mut self, mode: CaptureMode);
+
+    /// The maximum number of frames captured per second with `None` being the monitor refresh
+    /// rate.
+    fn frame_rate(&self) -> Option<u32>;
+
+    fn set_frame_rate(&mut self, frame_rate: Option<u32>);
+
+    /// The number of frames captured over the last second.
+    fn fps(&self) -> u32;
+}
+
+/// Measures the rate frames are captured at from the total number of captured frames.
+#[derive(Debug)]
+pub struct CaptureFpsCounter {
+    window_started: Instant,
+    window_started_frames: u64,
+    fps: u32,
+}
+
+impl Default for CaptureFpsCounter {
+    fn default() -> Self {
+        Self {
+            window_started: Instant::now(),
+            window_started_frames: 0,
+            fps: 0,
+        }
+    }
+}
+
+impl CaptureFpsCounter {
+    /// Updates the rate from the total `captured_frames` at `now` once every second.
+    pub fn update(&mut self, now: Instant, captured_frames: u64) {
+        let elapsed = now.duration_since(self.window_started);
+        if elapsed < FRAME_PACER_WINDOW {
+            return;
+        }
+
+        // Total can restart from zero when the capture is recreated
+        let frames = captured_frames.saturating_sub(self.window_started_frames);
+        self.fps = (frames as f64 / elapsed.as_secs_f64()).round() as u32;
+        self.window_started = now;
+        self.window_started_frames = captured_frames;
+    }
+
+    #[inline]
+    pub fn fps(&self) -> u32 {
+        self.fps
+    }
 }
 
 #[derive(Debug)]
 pub struct DefaultCapture {
     inner: PlatformCapture,
     mode: CaptureMode,
+    frame_rate: Option<u32>,
+    fps_counter: CaptureFpsCounter,
 }
 
 impl DefaultCapture {
@@ -971,6 +1283,8 @@ impl DefaultCapture {
         Self {
             inner: PlatformCapture::new(window).expect("supported platform"),
             mode: CaptureMode::BitBlt,
+            frame_rate: None,
+            fps_counter: CaptureFpsCounter::default(),
         }
     }
 }
@@ -978,7 +1292,11 @@ impl DefaultCapture {
 impl Capture for DefaultCapture {
     #[inline]
     fn grab(&mut self) -> Result<Frame, Error> {
-        self.inner.grab()
+        let frame = self.inner.grab();
+        self.fps_counter
+            .update(Instant::now(), self.inner.captured_frames());
+
+        frame
     }
 
     #[inline]
@@ -1000,7 +1318,9 @@ impl Capture for DefaultCapture {
     fn set_mode(&mut self, mode: CaptureMode) {
         self.mode = mode;
 
-        if cfg!(windows) {
+        // Capture modes are Windows-only, other platforms have a single capture method
+        #[cfg(windows)]
+        {
             let kind = match mode {
                 CaptureMode::BitBlt => WindowsCaptureKind::BitBlt,
                 CaptureMode::WindowsGraphicsCapture => WindowsCaptureKind::Wgc,
@@ -1009,6 +1329,108 @@ impl Capture for DefaultCapture {
             let _ = self.inner.windows_capture_kind(kind);
         }
     }
+
+    #[inline]
+    fn frame_rate(&self) -> Option<u32> {
+        self.frame_rate
+    }
+
+    fn set_frame_rate(&mut self, frame_rate: Option<u32>) {
+        self.frame_rate = frame_rate;
+        self.inner.set_min_frame_interval(
+            frame_rate.map(|fps| Duration::from_nanos(1_000_000_000 / fps.max(1) as u64)),
+        );
+    }
+
+    #[inline]
+    fn fps(&self) -> u32 {
+        self.fps_counter.fps()
+    }
+}
+
+/// Paces capturing and processing frames to a fixed frame rate.
+///
+/// When processing a frame takes longer than the frame interval, the frames that would have been
+/// captured in the meantime are skipped instead of being processed back-to-back to catch up. The
+/// next captured frame is then always the latest one.
+#[derive(Debug)]
+pub struct FramePacer {
+    interval: Duration,
+    /// The instant processing the current frame started.
+    frame_started: Instant,
+    /// The instants frames finished processing over the last [`FRAME_PACER_WINDOW`].
+    processed: VecDeque<Instant>,
+    /// The instants and number of frames skipped over the last [`FRAME_PACER_WINDOW`].
+    skipped: VecDeque<(Instant, u32)>,
+}
+
+/// The window over which [`FramePacer`] counts processed and skipped frames.
+const FRAME_PACER_WINDOW: Duration = Duration::from_secs(1);
+
+impl FramePacer {
+    pub fn new(fps: u32) -> Self {
+        Self {
+            interval: Duration::from_nanos(1_000_000_000 / fps as u64),
+            frame_started: Instant::now(),
+            processed: VecDeque::new(),
+            skipped: VecDeque::new(),
+        }
+    }
+
+    /// Waits until the next frame is due after processing the current one.
+    ///
+    /// Returns the time spent processing the current frame.
+    pub fn wait(&mut self) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.frame_started);
+        if self.record(now, elapsed) == 0 {
+            thread::sleep(self.interval.saturating_sub(elapsed));
+        }
+        self.frame_started = Instant::now();
+
+        elapsed
+    }
+
+    /// The number of frames processed over the last second.
+    #[inline]
+    pub fn fps(&self) -> u32 {
+        self.processed.len() as u32
+    }
+
+    /// The number of frames skipped over the last second because processing ran late.
+    #[inline]
+    pub fn skipped_frames(&self) -> u32 {
+        self.skipped.iter().map(|(_, count)| count).sum()
+    }
+
+    /// Records a frame finished processing at `now` after `elapsed`.
+    ///
+    /// Returns the number of frames skipped while processing.
+    fn record(&mut self, now: Instant, elapsed: Duration) -> u32 {
+        let skipped = if elapsed <= self.interval {
+            0
+        } else {
+            (elapsed.as_nanos() / self.interval.as_nanos()) as u32
+        };
+        self.processed.push_back(now);
+        if skipped > 0 {
+            self.skipped.push_back((now, skipped));
+        }
+
+        let expired = |instant: &Instant| now.duration_since(*instant) > FRAME_PACER_WINDOW;
+        while self.processed.front().is_some_and(expired) {
+            self.processed.pop_front();
+        }
+        while self
+            .skipped
+            .front()
+            .is_some_and(|(instant, _)| expired(instant))
+        {
+            self.skipped.pop_front();
+        }
+
+        skipped
+    }
 }
 
 #[inline]
@@ -1084,6 +1506,37 @@ mod tests {
         assert!(!sender.has_input_delay(KeyKind::Ctrl));
     }
 
+    #[test]
+    fn send_key_tracks_remapped_key() {
+        let mut sender = test_key_sender();
+        sender.set_key_remaps(HashMap::from([(KeyKind::Ctrl, KeyKind::Alt)]));
+
+        sender.send_key(KeyKind::Ctrl);
+
+        assert!(sender.has_input_delay(KeyKind::Alt));
+        assert!(!sender.has_input_delay(KeyKind::Ctrl));
+        assert!(!sender.is_key_cleared(KeyKind::Ctrl));
+    }
+
+    #[test]
+    fn update_key_repeat_resends_held_keys_after_interval() {
+        let mut sender = test_key_sender();
+        sender.set_key_repeat(Some(Duration::from_millis(50)));
+
+        sender.send_key_down(KeyKind::Left);
+        let sent = sender.held_keys.borrow()[&KeyKind::Left];
+
+        sender.update(0);
+        assert_eq!(sender.held_keys.borrow()[&KeyKind::Left], sent);
+
+        thread::sleep(Duration::from_millis(50));
+        sender.update(0);
+        assert!(sender.held_keys.borrow()[&KeyKind::Left] > sent);
+
+        sender.send_key_up(KeyKind::Left);
+        assert!(sender.held_keys.borrow().is_empty());
+    }
+
     #[test]
     fn update_input_delay_refresh_mean_std_pair_every_interval() {
         let mut sender = test_key_sender();
@@ -1097,4 +1550,44 @@ mod tests {
         sender.update(200);
         assert_ne!(sender.delay_mean_std_pair, original_pair);
     }
+
+    #[test]
+    fn frame_pacer_skips_frames_when_late() {
+        let mut pacer = FramePacer::new(10);
+        let start = Instant::now();
+
+        assert_eq!(pacer.record(start, Duration::from_millis(50)), 0);
+        assert_eq!(
+            pacer.record(
+                start + Duration::from_millis(300),
+                Duration::from_millis(250)
+            ),
+            2
+        );
+        assert_eq!(pacer.fps(), 2);
+        assert_eq!(pacer.skipped_frames(), 2);
+
+        // Frames older than the window are no longer counted
+        pacer.record(
+            start + Duration::from_millis(1400),
+            Duration::from_millis(50),
+        );
+        assert_eq!(pacer.fps(), 1);
+        assert_eq!(pacer.skipped_frames(), 0);
+    }
+    #[test]
+    fn capture_fps_counter_updates_once_per_window() {
+        let mut counter = CaptureFpsCounter::default();
+        let start = counter.window_started;
+
+        counter.update(start + Duration::from_millis(500), 15);
+        assert_eq!(counter.fps(), 0);
+
+        counter.update(start + Duration::from_secs(1), 30);
+        assert_eq!(counter.fps(), 30);
+
+        // Capture recreated with a restarted frame total
+        counter.update(start + Duration::from_secs(2), 10);
+        assert_eq!(counter.fps(), 0);
+    }
 }
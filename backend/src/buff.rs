@@ -4,7 +4,7 @@ use std::{
 };
 
 use anyhow::Result;
-use strum::EnumIter;
+use strum::{EnumIter, EnumString};
 
 use crate::{
     Character, Settings,
@@ -113,7 +113,7 @@ impl BuffContext {
 }
 
 // The kind of buff.
-#[derive(Clone, Copy, Debug, EnumIter)]
+#[derive(Clone, Copy, Debug, EnumIter, EnumString)]
 #[cfg_attr(test, derive(PartialEq))]
 #[repr(usize)]
 pub enum BuffKind {
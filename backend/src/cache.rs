@@ -0,0 +1,130 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// A least recently used cache bounded by the total size of its values in bytes.
+#[derive(Debug)]
+pub struct LruCache<K, V> {
+    entries: HashMap<K, LruEntry<V>>,
+    budget_bytes: usize,
+    used_bytes: usize,
+    /// Monotonically increasing counter for tracking the last access of each entry.
+    counter: u64,
+}
+
+#[derive(Debug)]
+struct LruEntry<V> {
+    value: V,
+    bytes: usize,
+    last_used: u64,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> LruCache<K, V> {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            budget_bytes,
+            used_bytes: 0,
+            counter: 0,
+        }
+    }
+
+    /// Gets the value for `key` and marks it as most recently used.
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        self.counter += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.counter;
+
+        Some(entry.value.clone())
+    }
+
+    /// Inserts `value` of size `bytes` evicting least recently used entries until it fits.
+    ///
+    /// The value is not cached if `bytes` alone exceeds the budget.
+    pub fn insert(&mut self, key: K, value: V, bytes: usize) {
+        if bytes > self.budget_bytes {
+            return;
+        }
+        if let Some(entry) = self.entries.remove(&key) {
+            self.used_bytes -= entry.bytes;
+        }
+
+        while self.used_bytes + bytes > self.budget_bytes {
+            let lru_key = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+                .expect("non-empty when over budget");
+            let entry = self.entries.remove(&lru_key).unwrap();
+            self.used_bytes -= entry.bytes;
+        }
+
+        self.counter += 1;
+        self.used_bytes += bytes;
+        self.entries.insert(
+            key,
+            LruEntry {
+                value,
+                bytes,
+                last_used: self.counter,
+            },
+        );
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used_bytes = 0;
+    }
+
+    #[inline]
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes
+    }
+
+    #[inline]
+    pub fn budget_bytes(&self) -> usize {
+        self.budget_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_evicts_least_recently_used() {
+        let mut cache = LruCache::new(10);
+        cache.insert(1, 'a', 4);
+        cache.insert(2, 'b', 4);
+        assert_eq!(cache.get(&1), Some('a'));
+
+        cache.insert(3, 'c', 4);
+
+        assert_eq!(cache.get(&1), Some('a'));
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.get(&3), Some('c'));
+        assert_eq!(cache.used_bytes(), 8);
+    }
+
+    #[test]
+    fn insert_skips_value_larger_than_budget() {
+        let mut cache = LruCache::new(10);
+        cache.insert(1, 'a', 4);
+
+        cache.insert(2, 'b', 11);
+
+        assert_eq!(cache.get(&1), Some('a'));
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.used_bytes(), 4);
+    }
+
+    #[test]
+    fn insert_replaces_existing_key() {
+        let mut cache = LruCache::new(10);
+        cache.insert(1, 'a', 4);
+
+        cache.insert(1, 'b', 6);
+
+        assert_eq!(cache.get(&1), Some('b'));
+        assert_eq!(cache.used_bytes(), 6);
+    }
+}
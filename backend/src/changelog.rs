@@ -0,0 +1,248 @@
+//! Changelog of configuration mutations.
+//!
+//! Each upsert of a map, character or settings that differs from the stored copy is recorded
+//! as a [`ConfigChange`] so that users can look back at what changed before something stopped
+//! working and revert individual changes without discarding the ones made after.
+
+use anyhow::Result;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Map as JsonMap, Value};
+
+use crate::models::{ConfigChange, ConfigChangeKind, ConfigFieldChange, Identifiable};
+
+/// Top-level fields updated by every write that are not meaningful as a change.
+const IGNORED_FIELDS: [&str; 1] = ["revision"];
+
+/// The maximum number of characters of a value shown in a change summary.
+const MAX_SUMMARY_VALUE_LEN: usize = 32;
+
+/// Creates a [`ConfigChange`] from the JSON of an entity `before` and `after` an upsert.
+///
+/// Nested objects are compared field by field while other values are compared as a whole.
+/// Returns `None` if nothing changed or either JSON is not an object.
+pub fn config_change(
+    kind: ConfigChangeKind,
+    entity_id: i64,
+    before: &str,
+    after: &str,
+    timestamp_millis: u64,
+) -> Option<ConfigChange> {
+    let (Ok(Value::Object(before)), Ok(Value::Object(after))) = (
+        serde_json::from_str::<Value>(before),
+        serde_json::from_str::<Value>(after),
+    ) else {
+        return None;
+    };
+
+    let mut fields = vec![];
+    diff_objects(&before, &after, &mut vec![], &mut fields);
+    if fields.is_empty() {
+        return None;
+    }
+
+    Some(ConfigChange {
+        id: None,
+        timestamp_millis,
+        kind,
+        entity_id,
+        entity_name: after
+            .get("name")
+            .and_then(Value::as_str)
+            .map(ToString::to_string),
+        summary: fields.iter().map(field_summary).collect(),
+        fields,
+    })
+}
+
+/// Reverts the fields changed by `change` on a copy of `data`.
+///
+/// Fields not touched by `change` are kept as they are so later changes are preserved.
+pub fn revert_config_change<T>(data: &T, change: &ConfigChange) -> Result<T>
+where
+    T: Serialize + DeserializeOwned + Identifiable,
+{
+    let mut value = serde_json::to_value(data)?;
+    for field in &change.fields {
+        revert_field(&mut value, &field.path, field.before.clone());
+    }
+
+    let mut reverted = serde_json::from_value::<T>(value)?;
+    if let Some(id) = data.id() {
+        reverted.set_id(id);
+    }
+    Ok(reverted)
+}
+
+fn diff_objects(
+    before: &JsonMap<String, Value>,
+    after: &JsonMap<String, Value>,
+    path: &mut Vec<String>,
+    fields: &mut Vec<ConfigFieldChange>,
+) {
+    let keys = before
+        .keys()
+        .chain(after.keys().filter(|key| !before.contains_key(*key)));
+    for key in keys {
+        if path.is_empty() && IGNORED_FIELDS.contains(&key.as_str()) {
+            continue;
+        }
+        let (before, after) = (before.get(key), after.get(key));
+        if before == after {
+            continue;
+        }
+
+        path.push(key.clone());
+        match (before, after) {
+            (Some(Value::Object(before)), Some(Value::Object(after))) => {
+                diff_objects(before, after, path, fields)
+            }
+            _ => fields.push(ConfigFieldChange {
+                path: path.clone(),
+                before: before.cloned(),
+                after: after.cloned(),
+            }),
+        }
+        path.pop();
+    }
+}
+
+fn revert_field(value: &mut Value, path: &[String], before: Option<Value>) {
+    let Some((key, parents)) = path.split_last() else {
+        return;
+    };
+    let Some(mut object) = value.as_object_mut() else {
+        return;
+    };
+    for parent in parents {
+        let child = object
+            .entry(parent.clone())
+            .or_insert_with(|| Value::Object(JsonMap::new()));
+        // Re-creates the parent if a later change replaced it with a non-object
+        if !child.is_object() {
+            *child = Value::Object(JsonMap::new());
+        }
+        object = child.as_object_mut().expect("is object");
+    }
+
+    match before {
+        Some(before) => {
+            object.insert(key.clone(), before);
+        }
+        None => {
+            object.remove(key);
+        }
+    }
+}
+
+fn field_summary(field: &ConfigFieldChange) -> String {
+    let name = field.path.join(".");
+    match (&field.before, &field.after) {
+        (Some(before), Some(after)) if !is_compound(before) && !is_compound(after) => {
+            format!("{name}: {} -> {}", short_value(before), short_value(after))
+        }
+        (None, Some(after)) if !is_compound(after) => {
+            format!("{name} added as {}", short_value(after))
+        }
+        (None, Some(_)) => format!("{name} added"),
+        (Some(_), None) => format!("{name} removed"),
+        _ => format!("{name} changed"),
+    }
+}
+
+fn is_compound(value: &Value) -> bool {
+    value.is_object() || value.is_array()
+}
+
+fn short_value(value: &Value) -> String {
+    let value = value.to_string();
+    if value.chars().count() > MAX_SUMMARY_VALUE_LEN {
+        let value = value
+            .chars()
+            .take(MAX_SUMMARY_VALUE_LEN)
+            .collect::<String>();
+        format!("{value}...")
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Character, KeyBinding, KeyBindingConfiguration};
+
+    fn character() -> Character {
+        Character {
+            id: Some(1),
+            name: "Character".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn change(before: &Character, after: &Character) -> Option<ConfigChange> {
+        config_change(
+            ConfigChangeKind::Character,
+            1,
+            &serde_json::to_string(before).unwrap(),
+            &serde_json::to_string(after).unwrap(),
+            0,
+        )
+    }
+
+    #[test]
+    fn config_change_none_when_only_revision_changed() {
+        let before = character();
+        let after = Character {
+            revision: before.revision + 1,
+            ..before.clone()
+        };
+
+        assert!(change(&before, &after).is_none());
+    }
+
+    #[test]
+    fn config_change_lists_nested_fields() {
+        let before = character();
+        let after = Character {
+            jump_key: KeyBindingConfiguration {
+                key: KeyBinding::Z,
+                ..before.jump_key
+            },
+            feed_pet_millis: before.feed_pet_millis + 1000,
+            ..before.clone()
+        };
+
+        let change = change(&before, &after).unwrap();
+
+        assert_eq!(change.entity_name.as_deref(), Some("Character"));
+        assert_eq!(
+            change
+                .fields
+                .iter()
+                .map(|field| field.path.join("."))
+                .collect::<Vec<_>>(),
+            vec!["feed_pet_millis".to_string(), "jump_key.key".to_string()]
+        );
+        assert_eq!(change.summary[1], "jump_key.key: \"Space\" -> \"Z\"");
+    }
+
+    #[test]
+    fn revert_config_change_keeps_later_changes() {
+        let before = character();
+        let after = Character {
+            feed_pet_millis: before.feed_pet_millis + 1000,
+            ..before.clone()
+        };
+        let change = change(&before, &after).unwrap();
+        let current = Character {
+            name: "Renamed".to_string(),
+            ..after
+        };
+
+        let reverted = revert_config_change(&current, &change).unwrap();
+
+        assert_eq!(reverted.id, Some(1));
+        assert_eq!(reverted.name, "Renamed");
+        assert_eq!(reverted.feed_pet_millis, before.feed_pet_millis);
+    }
+}
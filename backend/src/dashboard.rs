@@ -0,0 +1,253 @@
+//! Web dashboard for monitoring the bot remotely from a browser.
+//!
+//! The dashboard is a single page served at `/` that polls the current operation, position,
+//! health and actions from `/state` and shows the minimap from `/minimap.mjpg` as an MJPEG
+//! stream. It is read-only and has no authentication.
+
+use std::{
+    convert::Infallible,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use axum::{
+    Json, Router,
+    body::{Body, Bytes},
+    http::header,
+    response::{Html, IntoResponse},
+    routing::get,
+};
+use log::{info, warn};
+use opencv::{
+    core::{Mat, MatTraitConst, Vector},
+    imgcodecs::imencode_def,
+    imgproc::{COLOR_RGBA2BGR, cvt_color_def},
+};
+use serde::Serialize;
+use tokio::{
+    net::TcpListener,
+    sync::{
+        broadcast::{Receiver, error::RecvError},
+        mpsc, watch,
+    },
+    time::sleep,
+};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{BotOperation, GameState};
+
+/// The interval between two minimap frames sent to the browser.
+const FRAME_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The boundary separating JPEG parts of the MJPEG stream.
+const FRAME_BOUNDARY: &str = "frame";
+
+const DASHBOARD_HTML: &str = include_str!("../resources/dashboard.html");
+
+/// The [`GameState`] subset shown by the dashboard.
+#[derive(Clone, Default, Debug, Serialize)]
+struct DashboardState {
+    operation: String,
+    state: String,
+    position: Option<(i32, i32)>,
+    health: Option<(u32, u32)>,
+    normal_action: Option<String>,
+    priority_action: Option<String>,
+    erda_shower_state: String,
+    stranger_count: usize,
+}
+
+/// Serves the dashboard for `game_state_rx` to browsers connecting to `address`.
+///
+/// This must be called within a Tokio runtime.
+pub fn serve(address: String, mut game_state_rx: Receiver<GameState>) {
+    // Keeps only the latest state and frame so that slow browsers do not hold back the game state
+    // broadcast
+    let (state_tx, state_rx) = watch::channel(DashboardState::default());
+    let (frame_tx, frame_rx) = watch::channel(None::<Bytes>);
+    tokio::spawn(async move {
+        let mut last_frame_instant = None::<Instant>;
+        loop {
+            let state = match game_state_rx.recv().await {
+                Ok(state) => state,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            if let Some(frame) = state.frame.as_ref()
+                && last_frame_instant.is_none_or(|instant| instant.elapsed() >= FRAME_INTERVAL)
+                && let Ok(jpeg) = jpeg_from_frame(frame)
+            {
+                last_frame_instant = Some(Instant::now());
+                frame_tx.send_replace(Some(Bytes::from(jpeg)));
+            }
+            state_tx.send_replace(dashboard_state_from(state));
+        }
+    });
+
+    let app = Router::new()
+        .route("/", get(|| async { Html(DASHBOARD_HTML) }))
+        .route(
+            "/state",
+            get(move || {
+                let state = state_rx.borrow().clone();
+                async move { Json(state) }
+            }),
+        )
+        .route(
+            "/minimap.mjpg",
+            get(move || {
+                let frame_rx = frame_rx.clone();
+                async move { minimap_stream(frame_rx) }
+            }),
+        );
+
+    tokio::spawn(async move {
+        let listener = match TcpListener::bind(&address).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                warn!(target: "dashboard", "unable to serve dashboard at {address} {err}");
+                return;
+            }
+        };
+        info!(target: "dashboard", "serving dashboard at http://{address}");
+        if let Err(err) = axum::serve(listener, app).await {
+            warn!(target: "dashboard", "dashboard stopped {err}");
+        }
+    });
+}
+
+fn minimap_stream(mut frame_rx: watch::Receiver<Option<Bytes>>) -> impl IntoResponse {
+    let (tx, rx) = mpsc::channel::<Result<Bytes, Infallible>>(1);
+    tokio::spawn(async move {
+        loop {
+            let frame = frame_rx.borrow_and_update().clone();
+            if let Some(frame) = frame {
+                let part = format!(
+                    "--{FRAME_BOUNDARY}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+                    frame.len()
+                );
+                let sent = tx.send(Ok(Bytes::from(part))).await.is_ok()
+                    && tx.send(Ok(frame)).await.is_ok()
+                    && tx.send(Ok(Bytes::from_static(b"\r\n"))).await.is_ok();
+                if !sent {
+                    break;
+                }
+            }
+            if frame_rx.changed().await.is_err() {
+                break;
+            }
+            // Throttles in case the browser reads faster than frames are produced
+            sleep(FRAME_INTERVAL).await;
+        }
+    });
+
+    (
+        [(
+            header::CONTENT_TYPE,
+            format!("multipart/x-mixed-replace; boundary={FRAME_BOUNDARY}"),
+        )],
+        Body::from_stream(ReceiverStream::new(rx)),
+    )
+}
+
+/// Encodes the RGBA minimap `frame` from [`GameState::frame`] as JPEG.
+fn jpeg_from_frame((bytes, _, height): &(Vec<u8>, usize, usize)) -> Result<Vec<u8>> {
+    let rgba = Mat::from_slice(bytes)?;
+    let rgba = rgba.reshape(4, *height as i32)?;
+    let mut bgr = Mat::default();
+    cvt_color_def(&rgba, &mut bgr, COLOR_RGBA2BGR)?;
+
+    let mut jpeg = Vector::<u8>::new();
+    imencode_def(".jpg", &bgr, &mut jpeg)?;
+    Ok(jpeg.to_vec())
+}
+
+fn dashboard_state_from(state: GameState) -> DashboardState {
+    let now = Instant::now();
+    let operation = match state.operation {
+        BotOperation::Halting => "Halting".to_string(),
+        BotOperation::Running => "Running".to_string(),
+        BotOperation::TemporaryHalting(duration) => {
+            format!("Halting temporarily ({} left)", duration_text(duration))
+        }
+        BotOperation::HaltUntil(instant) => format!(
+            "Halting ({} left)",
+            duration_text(instant.saturating_duration_since(now))
+        ),
+        BotOperation::RunUntil(instant) => format!(
+            "Running ({} left)",
+            duration_text(instant.saturating_duration_since(now))
+        ),
+    };
+
+    DashboardState {
+        operation,
+        state: state.state,
+        position: state.position,
+        health: state.health,
+        normal_action: state.normal_action,
+        priority_action: state.priority_action,
+        erda_shower_state: state.erda_shower_state,
+        stranger_count: state.stranger_count,
+    }
+}
+
+pub fn duration_text(duration: Duration) -> String {
+    let seconds = duration.as_secs() % 60;
+    let minutes = (duration.as_secs() / 60) % 60;
+    let hours = (duration.as_secs() / 60) / 60;
+
+    format!("{hours:0>2}:{minutes:0>2}:{seconds:0>2}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FrameTimings;
+
+    #[test]
+    fn jpeg_from_frame_encodes_rgba_frame() {
+        let frame = (vec![255; 4 * 6 * 4], 6, 4);
+
+        let jpeg = jpeg_from_frame(&frame).unwrap();
+
+        assert_eq!(&jpeg[..2], &[0xFF, 0xD8]);
+    }
+
+    #[test]
+    fn dashboard_state_from_formats_timed_operation() {
+        let state = GameState {
+            position: Some((10, 20)),
+            health: None,
+            mana: None,
+            state: "Idle".to_string(),
+            normal_action: None,
+            priority_action: None,
+            next_priority_action: None,
+            erda_shower_state: "Detecting".to_string(),
+            destinations: vec![],
+            waypoints: vec![],
+            operation: BotOperation::TemporaryHalting(Duration::from_secs(3725)),
+            frame: None,
+            platforms_bound: None,
+            portals: vec![],
+            auto_mob_quadrant: None,
+            stranger_count: 1,
+            crowding_score: 0.0,
+            external_preset_error: None,
+            detected_character_id: None,
+            exp: None,
+            runes_solved: 0,
+            runes_failed: 0,
+            errors: vec![],
+            frame_timings: FrameTimings::default(),
+            priority_actions_dropped: vec![],
+        };
+
+        let state = dashboard_state_from(state);
+
+        assert_eq!(state.operation, "Halting temporarily (01:02:05 left)");
+        assert_eq!(state.position, Some((10, 20)));
+        assert_eq!(state.stranger_count, 1);
+    }
+}
@@ -1,14 +1,32 @@
 use std::{
+    cmp::Reverse,
+    collections::HashMap,
     env,
-    sync::{LazyLock, Mutex},
+    fmt::{self, Formatter},
+    iter, mem,
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use anyhow::{Result, bail};
-use rusqlite::{Connection, Params, Statement, types::Null};
+use anyhow::{Result, anyhow, bail};
+use log::error;
+use rusqlite::{Connection, OptionalExtension, Params, Statement, types::Null};
 use serde::{Serialize, de::DeserializeOwned};
 use tokio::sync::broadcast::{Receiver, Sender, channel};
 
-use crate::models::{Character, Identifiable, Localization, Map, NavigationPaths, Seeds, Settings};
+use crate::{
+    changelog::{self, config_change},
+    models::{
+        Character, ConfigChange, ConfigChangeKind, DailyClaims, GlobalPreset, Identifiable,
+        InputMacro, Localization, Map, NavigationPaths, OperationState, Revisioned, Seeds,
+        SessionStats, Settings,
+    },
+};
 
 const MAPS: &str = "maps";
 const NAVIGATION_PATHS: &str = "navigation_paths";
@@ -16,6 +34,22 @@ const CHARACTERS: &str = "characters";
 const SETTINGS: &str = "settings";
 const SEEDS: &str = "seeds";
 const LOCALIZATIONS: &str = "localizations";
+const DAILY_CLAIMS: &str = "daily_claims";
+const OPERATION_STATES: &str = "operation_states";
+const SESSION_STATS: &str = "session_stats";
+const GLOBAL_PRESETS: &str = "global_presets";
+const INPUT_MACROS: &str = "input_macros";
+const CONFIG_CHANGES: &str = "config_changes";
+
+/// The maximum number of recorded configuration changes with older ones discarded first.
+const MAX_CONFIG_CHANGES: i64 = 1000;
+
+/// The delay before flushing queued writes so that rapid successive upserts of the same entity
+/// are coalesced into a single write.
+const WRITE_COALESCE_DELAY: Duration = Duration::from_millis(250);
+
+/// The number of times a queued write can fail before it is dropped.
+const MAX_WRITE_ATTEMPTS: u32 = 3;
 
 static CONNECTION: LazyLock<Mutex<Connection>> = LazyLock::new(|| {
     let path = env::current_exe()
@@ -52,6 +86,30 @@ static CONNECTION: LazyLock<Mutex<Connection>> = LazyLock::new(|| {
                 id INTEGER PRIMARY KEY,
                 data TEXT NOT NULL
             );
+            CREATE TABLE IF NOT EXISTS {DAILY_CLAIMS} (
+                id INTEGER PRIMARY KEY,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS {OPERATION_STATES} (
+                id INTEGER PRIMARY KEY,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS {SESSION_STATS} (
+                id INTEGER PRIMARY KEY,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS {GLOBAL_PRESETS} (
+                id INTEGER PRIMARY KEY,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS {INPUT_MACROS} (
+                id INTEGER PRIMARY KEY,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS {CONFIG_CHANGES} (
+                id INTEGER PRIMARY KEY,
+                data TEXT NOT NULL
+            );
             "#
         )
         .as_str(),
@@ -60,6 +118,47 @@ static CONNECTION: LazyLock<Mutex<Connection>> = LazyLock::new(|| {
     Mutex::new(conn)
 });
 static EVENT: LazyLock<Sender<DatabaseEvent>> = LazyLock::new(|| channel(5).0);
+/// Whether writes are rejected such as when spectating another instance.
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+/// Queued writes keyed by table and id with only the latest write of each entity kept.
+static PENDING_WRITES: LazyLock<Mutex<PendingWrites>> = LazyLock::new(Mutex::default);
+/// Wakes the background writer thread to flush [`PENDING_WRITES`].
+static WRITER: LazyLock<mpsc::Sender<()>> = LazyLock::new(|| {
+    let (tx, rx) = mpsc::channel::<()>();
+    thread::spawn(move || {
+        while rx.recv().is_ok() {
+            thread::sleep(WRITE_COALESCE_DELAY);
+            // Wake-ups during the delay are covered by this flush
+            rx.try_iter().for_each(drop);
+
+            flush_pending_writes(&mut CONNECTION.lock().unwrap());
+        }
+    });
+    tx
+});
+
+type PendingWrites = HashMap<(&'static str, i64), PendingWrite>;
+
+#[derive(Debug)]
+struct PendingWrite {
+    json: String,
+    revision: u64,
+    event: DatabaseEvent,
+    /// The number of times writing has failed.
+    attempts: u32,
+}
+
+/// The error returned when upserting an entity that was modified since it was read.
+#[derive(Debug)]
+pub struct RevisionConflict;
+
+impl fmt::Display for RevisionConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "entity was modified since it was read")
+    }
+}
+
+impl std::error::Error for RevisionConflict {}
 
 #[derive(Debug, Clone)]
 #[allow(clippy::large_enum_variant)]
@@ -68,16 +167,27 @@ pub enum DatabaseEvent {
     MapDeleted(i64),
     NavigationPathsUpdated,
     NavigationPathsDeleted,
+    GlobalPresetUpdated,
+    GlobalPresetDeleted,
+    InputMacroUpdated,
+    InputMacroDeleted,
     SettingsUpdated(Settings),
     LocalizationUpdated(Localization),
     CharacterUpdated(Character),
     CharacterDeleted(i64),
+    /// A queued write failed [`MAX_WRITE_ATTEMPTS`] times and was dropped.
+    WriteDropped,
 }
 
 pub fn database_event_receiver() -> Receiver<DatabaseEvent> {
     EVENT.subscribe()
 }
 
+/// Rejects all subsequent writes.
+pub fn set_read_only() {
+    READ_ONLY.store(true, Ordering::Relaxed);
+}
+
 pub fn query_and_upsert_seeds() -> Seeds {
     let mut seeds = query_from_table::<Seeds>(SEEDS)
         .unwrap()
@@ -124,13 +234,58 @@ pub fn upsert_settings(settings: &mut Settings) -> Result<()> {
     })
 }
 
+pub fn query_or_upsert_daily_claims() -> Result<DailyClaims> {
+    let mut claims = query_from_table::<DailyClaims>(DAILY_CLAIMS)?
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    if claims.id.is_none() {
+        upsert_daily_claims(&mut claims)?;
+    }
+    Ok(claims)
+}
+
+pub fn upsert_daily_claims(claims: &mut DailyClaims) -> Result<()> {
+    upsert_to_table(DAILY_CLAIMS, claims)
+}
+
+pub fn query_or_upsert_operation_state() -> Result<OperationState> {
+    let mut state = query_from_table::<OperationState>(OPERATION_STATES)?
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    if state.id.is_none() {
+        upsert_operation_state(&mut state)?;
+    }
+    Ok(state)
+}
+
+pub fn upsert_operation_state(state: &mut OperationState) -> Result<()> {
+    upsert_to_table(OPERATION_STATES, state)
+}
+
+/// Queries all recorded sessions with the most recent session first.
+pub fn query_session_stats() -> Result<Vec<SessionStats>> {
+    let mut stats = query_from_table::<SessionStats>(SESSION_STATS)?;
+    stats.sort_by_key(|stats| Reverse(stats.started_millis));
+    Ok(stats)
+}
+
+pub fn upsert_session_stats(stats: &mut SessionStats) -> Result<()> {
+    upsert_to_table(SESSION_STATS, stats)
+}
+
 pub fn query_characters() -> Result<Vec<Character>> {
     query_from_table(CHARACTERS)
 }
 
+pub fn query_character(id: i64) -> Result<Option<Character>> {
+    query_one_from_table(CHARACTERS, id)
+}
+
 pub fn upsert_character(character: &mut Character) -> Result<()> {
-    upsert_to_table(CHARACTERS, character).inspect(|_| {
-        let _ = EVENT.send(DatabaseEvent::CharacterUpdated(character.clone()));
+    upsert_to_table_coalesced(CHARACTERS, character, |character| {
+        DatabaseEvent::CharacterUpdated(character.clone())
     })
 }
 
@@ -146,10 +301,29 @@ pub fn query_maps() -> Result<Vec<Map>> {
     query_from_table(MAPS)
 }
 
+pub fn query_map(id: i64) -> Result<Option<Map>> {
+    query_one_from_table(MAPS, id)
+}
+
 pub fn upsert_map(map: &mut Map) -> Result<()> {
-    upsert_to_table(MAPS, map).inspect(|_| {
-        let _ = EVENT.send(DatabaseEvent::MapUpdated(map.clone()));
-    })
+    upsert_to_table_coalesced(MAPS, map, |map| DatabaseEvent::MapUpdated(map.clone()))
+}
+
+/// Applies `edit` to `map` and upserts it.
+///
+/// If `map` was modified since it was read, `map` is replaced by the stored copy and `edit` is
+/// re-applied to it instead of failing with [`RevisionConflict`].
+pub fn upsert_map_with(map: &mut Map, edit: impl Fn(&mut Map)) -> Result<()> {
+    edit(map);
+    match upsert_map(map) {
+        Err(err) if err.is::<RevisionConflict>() => {
+            let id = map.id.expect("has id if conflicted");
+            *map = query_map(id)?.ok_or(anyhow!("map was deleted"))?;
+            edit(map);
+            upsert_map(map)
+        }
+        result => result,
+    }
 }
 
 pub fn delete_map(map: &Map) -> Result<()> {
@@ -176,6 +350,67 @@ pub fn delete_navigation_paths(paths: &NavigationPaths) -> Result<()> {
     })
 }
 
+pub fn query_global_presets() -> Result<Vec<GlobalPreset>> {
+    query_from_table(GLOBAL_PRESETS)
+}
+
+pub fn upsert_global_preset(preset: &mut GlobalPreset) -> Result<()> {
+    upsert_to_table(GLOBAL_PRESETS, preset).inspect(|_| {
+        let _ = EVENT.send(DatabaseEvent::GlobalPresetUpdated);
+    })
+}
+
+pub fn delete_global_preset(preset: &GlobalPreset) -> Result<()> {
+    delete_from_table(GLOBAL_PRESETS, preset).inspect(|_| {
+        let _ = EVENT.send(DatabaseEvent::GlobalPresetDeleted);
+    })
+}
+
+pub fn query_input_macros() -> Result<Vec<InputMacro>> {
+    query_from_table(INPUT_MACROS)
+}
+
+pub fn upsert_input_macro(input_macro: &mut InputMacro) -> Result<()> {
+    upsert_to_table(INPUT_MACROS, input_macro).inspect(|_| {
+        let _ = EVENT.send(DatabaseEvent::InputMacroUpdated);
+    })
+}
+
+pub fn delete_input_macro(input_macro: &InputMacro) -> Result<()> {
+    delete_from_table(INPUT_MACROS, input_macro).inspect(|_| {
+        let _ = EVENT.send(DatabaseEvent::InputMacroDeleted);
+    })
+}
+
+/// Queries the recorded configuration changes with the most recent change first.
+pub fn query_config_changes() -> Result<Vec<ConfigChange>> {
+    let mut changes = query_from_table::<ConfigChange>(CONFIG_CHANGES)?;
+    changes.sort_by_key(|change| Reverse(change.id));
+    Ok(changes)
+}
+
+/// Reverts the fields changed by `change` on the current copy of its entity.
+///
+/// Fields changed afterward by other changes are kept. The revert is recorded as a new change.
+pub fn revert_config_change(change: &ConfigChange) -> Result<()> {
+    match change.kind {
+        ConfigChangeKind::Map => {
+            let map = query_map(change.entity_id)?.ok_or(anyhow!("map was deleted"))?;
+            upsert_map(&mut changelog::revert_config_change(&map, change)?)
+        }
+        ConfigChangeKind::Character => {
+            let character =
+                query_character(change.entity_id)?.ok_or(anyhow!("character was deleted"))?;
+            upsert_character(&mut changelog::revert_config_change(&character, change)?)
+        }
+        ConfigChangeKind::Settings => {
+            let settings = query_one_from_table::<Settings>(SETTINGS, change.entity_id)?
+                .ok_or(anyhow!("settings was deleted"))?;
+            upsert_settings(&mut changelog::revert_config_change(&settings, change)?)
+        }
+    }
+}
+
 fn map_data<T>(mut stmt: Statement<'_>, params: impl Params) -> Result<Vec<T>>
 where
     T: DeserializeOwned + Identifiable + Default,
@@ -196,16 +431,32 @@ fn query_from_table<T>(table: &str) -> Result<Vec<T>>
 where
     T: DeserializeOwned + Identifiable + Default,
 {
-    let conn = CONNECTION.lock().unwrap();
+    let mut conn = CONNECTION.lock().unwrap();
+    // Reads must observe writes that are still queued
+    flush_pending_writes(&mut conn);
     let stmt = format!("SELECT id, data FROM {table};");
     let stmt = conn.prepare(&stmt).unwrap();
     map_data(stmt, [])
 }
 
+fn query_one_from_table<T>(table: &str, id: i64) -> Result<Option<T>>
+where
+    T: DeserializeOwned + Identifiable + Default,
+{
+    let mut conn = CONNECTION.lock().unwrap();
+    flush_pending_writes(&mut conn);
+    let stmt = format!("SELECT id, data FROM {table} WHERE id = ?1;");
+    let stmt = conn.prepare(&stmt)?;
+    Ok(map_data(stmt, [id])?.into_iter().next())
+}
+
 fn upsert_to_table<T>(table: &str, data: &mut T) -> Result<()>
 where
     T: Serialize + Identifiable,
 {
+    if READ_ONLY.load(Ordering::Relaxed) {
+        bail!("database is read-only");
+    }
     let json = serde_json::to_string(&data).unwrap();
     let conn = CONNECTION.lock().unwrap();
     let stmt = format!(
@@ -213,7 +464,11 @@ where
     );
     match data.id() {
         Some(id) => {
-            if conn.execute(&stmt, (id, &json))? > 0 {
+            // Recorded in the same transaction so that a failed write leaves no change behind
+            let transaction = conn.unchecked_transaction()?;
+            record_config_change(&transaction, table, id, &json)?;
+            if transaction.execute(&stmt, (id, &json))? > 0 {
+                transaction.commit()?;
                 Ok(())
             } else {
                 bail!("no row was updated")
@@ -230,10 +485,188 @@ where
     }
 }
 
-fn delete_from_table<T: Identifiable>(table: &str, data: &T) -> Result<()> {
-    fn inner(table: &str, id: Option<i64>) -> Result<()> {
+/// Queues `data` to be written by the background writer if it already exists.
+///
+/// Successive upserts of the same entity within [`WRITE_COALESCE_DELAY`] are coalesced into
+/// a single write and a single `event`. New entity is inserted immediately to assign its id.
+///
+/// Fails with [`RevisionConflict`] if the revision of `data` is not the latest one. Otherwise,
+/// the revision of `data` is incremented.
+fn upsert_to_table_coalesced<T>(
+    table: &'static str,
+    data: &mut T,
+    event: impl FnOnce(&T) -> DatabaseEvent,
+) -> Result<()>
+where
+    T: Serialize + Identifiable + Revisioned,
+{
+    if READ_ONLY.load(Ordering::Relaxed) {
+        bail!("database is read-only");
+    }
+    let Some(id) = data.id() else {
+        return upsert_to_table(table, data).inspect(|_| {
+            let _ = EVENT.send(event(data));
+        });
+    };
+
+    // Both locks are held until queued so that checking and bumping revision is atomic
+    let conn = CONNECTION.lock().unwrap();
+    let mut pending = PENDING_WRITES.lock().unwrap();
+    let revision = match pending.get(&(table, id)) {
+        Some(write) => write.revision,
+        None => query_revision(&conn, table, id)?,
+    };
+    if data.revision() != revision {
+        return Err(RevisionConflict.into());
+    }
+    data.set_revision(revision + 1);
+
+    let write = PendingWrite {
+        json: serde_json::to_string(&data)?,
+        revision: data.revision(),
+        event: event(data),
+        attempts: 0,
+    };
+    pending.insert((table, id), write);
+    drop(pending);
+    drop(conn);
+
+    let _ = WRITER.send(());
+    Ok(())
+}
+
+fn query_revision(conn: &Connection, table: &str, id: i64) -> Result<u64> {
+    let stmt =
+        format!("SELECT COALESCE(json_extract(data, '$.revision'), 0) FROM {table} WHERE id = ?1;");
+    let revision = conn.query_row(&stmt, [id], |row| row.get::<_, i64>(0))?;
+
+    Ok(revision as u64)
+}
+
+/// Writes all queued writes in a single transaction.
+///
+/// This must be called while holding the [`CONNECTION`] lock so that readers cannot observe
+/// the database between taking and writing the queued writes.
+fn flush_pending_writes(conn: &mut Connection) {
+    flush_writes(conn, &PENDING_WRITES);
+}
+
+/// Writes all writes in `queue` in a single transaction.
+///
+/// If the transaction fails, each write is written separately so that a failing write does not
+/// hold back the others. Failed writes are queued again in `queue` to be retried by the next
+/// flush until they fail [`MAX_WRITE_ATTEMPTS`] times, after which they are dropped and
+/// [`DatabaseEvent::WriteDropped`] is sent.
+///
+/// Failures are not returned so that reads are still served from the written data.
+fn flush_writes(conn: &mut Connection, queue: &Mutex<PendingWrites>) {
+    let pending = mem::take(&mut *queue.lock().unwrap());
+    if pending.is_empty() {
+        return;
+    }
+
+    let (written, failed) = match write_pending_writes(conn, &pending) {
+        Ok(()) => (pending, PendingWrites::new()),
+        Err(_) => pending
+            .into_iter()
+            .partition::<PendingWrites, _>(|(key, write)| {
+                write_pending_writes(conn, iter::once((key, write)))
+                    .inspect_err(|err| {
+                        error!(target: "database", "failed to write {key:?} {err}");
+                    })
+                    .is_ok()
+            }),
+    };
+    for write in written.into_values() {
+        let _ = EVENT.send(write.event);
+    }
+
+    for key in requeue_writes(&mut queue.lock().unwrap(), failed) {
+        error!(
+            target: "database",
+            "dropped write {key:?} after failing {MAX_WRITE_ATTEMPTS} times"
+        );
+        let _ = EVENT.send(DatabaseEvent::WriteDropped);
+    }
+}
+
+fn write_pending_writes<'a>(
+    conn: &mut Connection,
+    pending: impl IntoIterator<Item = (&'a (&'static str, i64), &'a PendingWrite)>,
+) -> Result<()> {
+    let transaction = conn.transaction()?;
+    for ((table, id), write) in pending {
+        let stmt = format!(
+            "INSERT INTO {table} (id, data) VALUES (?1, ?2) ON CONFLICT (id) DO UPDATE SET data = ?2;",
+        );
+        record_config_change(&transaction, table, *id, &write.json)?;
+        transaction.execute(&stmt, (id, &write.json))?;
+    }
+    transaction.commit()?;
+
+    Ok(())
+}
+
+/// Queues failed `writes` again into `queue` without overwriting newer writes of the same
+/// entity.
+///
+/// Returns the keys of the writes dropped for failing [`MAX_WRITE_ATTEMPTS`] times.
+fn requeue_writes(queue: &mut PendingWrites, writes: PendingWrites) -> Vec<(&'static str, i64)> {
+    let mut dropped = vec![];
+    for (key, mut write) in writes {
+        write.attempts += 1;
+        if write.attempts >= MAX_WRITE_ATTEMPTS {
+            dropped.push(key);
+        } else {
+            queue.entry(key).or_insert(write);
+        }
+    }
+    dropped
+}
+
+/// Records the change from the stored data of `id` in `table` to `json` if `table` is a map,
+/// character or settings table.
+///
+/// This must be called before writing `json`.
+fn record_config_change(conn: &Connection, table: &str, id: i64, json: &str) -> Result<()> {
+    let kind = match table {
+        MAPS => ConfigChangeKind::Map,
+        CHARACTERS => ConfigChangeKind::Character,
+        SETTINGS => ConfigChangeKind::Settings,
+        _ => return Ok(()),
+    };
+    let stmt = format!("SELECT data FROM {table} WHERE id = ?1;");
+    let Some(before) = conn
+        .query_row(&stmt, [id], |row| row.get::<_, String>(0))
+        .optional()?
+    else {
+        return Ok(());
+    };
+    let timestamp_millis = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+    let Some(change) = config_change(kind, id, &before, json, timestamp_millis) else {
+        return Ok(());
+    };
+
+    conn.execute(
+        &format!("INSERT INTO {CONFIG_CHANGES} (data) VALUES (?1);"),
+        [serde_json::to_string(&change)?],
+    )?;
+    conn.execute(
+        &format!("DELETE FROM {CONFIG_CHANGES} WHERE id <= last_insert_rowid() - ?1;"),
+        [MAX_CONFIG_CHANGES],
+    )?;
+    Ok(())
+}
+
+fn delete_from_table<T: Identifiable>(table: &'static str, data: &T) -> Result<()> {
+    fn inner(table: &'static str, id: Option<i64>) -> Result<()> {
+        if READ_ONLY.load(Ordering::Relaxed) {
+            bail!("database is read-only");
+        }
         if let Some(id) = id {
             let conn = CONNECTION.lock().unwrap();
+            // Prevents a queued write from re-inserting the deleted entity
+            PENDING_WRITES.lock().unwrap().remove(&(table, id));
             let stmt = format!("DELETE FROM {table} WHERE id = ?1;");
             let deleted = conn.execute(&stmt, [id])?;
 
@@ -246,3 +679,77 @@ fn delete_from_table<T: Identifiable>(table: &str, data: &T) -> Result<()> {
 
     inner(table, data.id())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(revision: u64) -> PendingWrite {
+        PendingWrite {
+            json: "{}".to_string(),
+            revision,
+            event: DatabaseEvent::NavigationPathsUpdated,
+            attempts: 0,
+        }
+    }
+
+    #[test]
+    fn flush_writes_queues_only_failed_writes_again() {
+        // Only the maps table exists so writing to characters fails
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!(
+            "CREATE TABLE {MAPS} (id INTEGER PRIMARY KEY, data TEXT NOT NULL);"
+        ))
+        .unwrap();
+        let queue = Mutex::new(HashMap::from([
+            ((MAPS, 1), write(1)),
+            ((CHARACTERS, 2), write(3)),
+        ]));
+
+        flush_writes(&mut conn, &queue);
+
+        let queue = queue.into_inner().unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[&(CHARACTERS, 2)].revision, 3);
+        assert_eq!(queue[&(CHARACTERS, 2)].attempts, 1);
+        let written = conn
+            .query_row(
+                &format!("SELECT data FROM {MAPS} WHERE id = 1;"),
+                [],
+                |row| row.get::<_, String>(0),
+            )
+            .unwrap();
+        assert_eq!(written, "{}");
+    }
+
+    #[test]
+    fn flush_writes_drops_writes_after_max_attempts() {
+        // No table exists so every write fails
+        let mut conn = Connection::open_in_memory().unwrap();
+        let queue = Mutex::new(HashMap::from([((MAPS, 1), write(1))]));
+
+        for _ in 0..MAX_WRITE_ATTEMPTS - 1 {
+            flush_writes(&mut conn, &queue);
+            assert!(queue.lock().unwrap().contains_key(&(MAPS, 1)));
+        }
+        flush_writes(&mut conn, &queue);
+
+        assert!(queue.into_inner().unwrap().is_empty());
+    }
+
+    #[test]
+    fn requeue_writes_keeps_newer_writes() {
+        let mut queue = HashMap::from([((MAPS, 1), write(5))]);
+
+        let dropped = requeue_writes(
+            &mut queue,
+            HashMap::from([((MAPS, 1), write(4)), ((MAPS, 2), write(1))]),
+        );
+
+        assert!(dropped.is_empty());
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[&(MAPS, 1)].revision, 5);
+        assert_eq!(queue[&(MAPS, 2)].revision, 1);
+        assert_eq!(queue[&(MAPS, 2)].attempts, 1);
+    }
+}
@@ -0,0 +1,236 @@
+//! Opt-in capture of rune samples for improving the rune model.
+//!
+//! When [`Settings::enable_rune_dataset`] is enabled, the rune arrows of each solve are cropped
+//! out of the frame and kept until the solve is validated. The crop is then saved with its solver
+//! output as YOLO labels into the `solved` or `unsolved` folder of the local rune dataset depending
+//! on whether the rune buff appeared. Cropping to the arrows anonymizes the sample by leaving out
+//! the character, chat and other players.
+
+use std::{
+    cell::RefCell,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use anyhow::{Result, bail};
+use log::info;
+use opencv::core::{Mat, MatTraitConst, Rect};
+use rand::distr::{Alphanumeric, SampleString};
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    Settings,
+    bridge::KeyKind,
+    detect::{ArrowsComplete, Detector},
+    utils::{self, DatasetDir},
+};
+
+/// The padding around the rune arrows when cropping.
+const CROP_PAD: i32 = 32;
+
+/// A rune sample waiting for validation.
+#[derive(Debug)]
+struct RuneSample {
+    crop: Mat,
+    labels: String,
+    has_spin_arrow: bool,
+}
+
+/// A resource for capturing the rune dataset.
+#[derive(Debug)]
+pub struct RuneDataset {
+    settings: Rc<RefCell<Settings>>,
+    pending: RefCell<Option<RuneSample>>,
+}
+
+impl RuneDataset {
+    pub fn new(settings: Rc<RefCell<Settings>>) -> Self {
+        Self {
+            settings,
+            pending: RefCell::new(None),
+        }
+    }
+
+    /// Keeps the rune arrows crop of `detector` and the solver `result` until validated.
+    pub fn set_pending_sample(&self, detector: &dyn Detector, result: ArrowsComplete) {
+        if !self.settings.borrow().enable_rune_dataset {
+            return;
+        }
+
+        let mat = detector.mat();
+        let sample = rune_sample(&*mat, result);
+        *self.pending.borrow_mut() = sample;
+    }
+
+    /// Saves the pending sample as `solved` or not depending on whether the rune buff appeared.
+    pub fn save_pending_sample(&self, solved: bool) {
+        let Some(sample) = self.pending.borrow_mut().take() else {
+            return;
+        };
+        if !self.settings.borrow().enable_rune_dataset {
+            return;
+        }
+
+        let folder = if solved { "solved" } else { "unsolved" };
+        let mut name = Alphanumeric.sample_string(&mut rand::rng(), 8);
+        if sample.has_spin_arrow {
+            name = format!("{name}_spin");
+        }
+        utils::save_image_to(
+            &sample.crop,
+            DatasetDir::Rune,
+            Path::new(folder).join(format!("{name}.png")),
+        );
+        utils::save_file_to(
+            sample.labels,
+            DatasetDir::Rune,
+            Path::new(folder).join(format!("{name}.txt")),
+        );
+    }
+}
+
+/// Exports the local rune dataset as a zip archive in the dataset folder.
+///
+/// Returns the path to the archive.
+pub fn export_rune_dataset() -> Result<PathBuf> {
+    let folder = DatasetDir::Rune.to_folder();
+    let path = DatasetDir::Root
+        .to_folder()
+        .join(format!("rune_{}.zip", utils::epoch_millis_as_string()));
+    let mut files = Vec::new();
+    collect_files(&folder, &mut files)?;
+    if files.is_empty() {
+        bail!("rune dataset is empty");
+    }
+
+    let mut zip = ZipWriter::new(File::create(&path)?);
+    for file in files {
+        let name = file
+            .strip_prefix(&folder)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        zip.start_file(name, SimpleFileOptions::default())?;
+        zip.write_all(&fs::read(file)?)?;
+    }
+    zip.finish()?;
+    info!(target: "rune", "rune dataset exported to {}", path.display());
+
+    Ok(path)
+}
+
+fn collect_files(folder: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn rune_sample(mat: &impl MatTraitConst, result: ArrowsComplete) -> Option<RuneSample> {
+    let size = mat.size().ok()?;
+    let bound = result.bboxes.into_iter().reduce(|acc, bbox| acc | bbox)?;
+    let x = (bound.x - CROP_PAD).max(0);
+    let y = (bound.y - CROP_PAD).max(0);
+    let crop_bbox = Rect::new(
+        x,
+        y,
+        (bound.x + bound.width + CROP_PAD).min(size.width) - x,
+        (bound.y + bound.height + CROP_PAD).min(size.height) - y,
+    );
+    let crop = mat.roi(crop_bbox).ok()?.try_clone().ok()?;
+    let crop_size = crop_bbox.size();
+
+    // Spin arrows are labelled for the spin model with a single class while normal arrows are
+    // labelled with their directions
+    let has_spin_arrow = result.spins.iter().any(|spin| *spin);
+    let labels = result
+        .bboxes
+        .into_iter()
+        .zip(result.keys)
+        .zip(result.spins)
+        .filter(|(_, spin)| !has_spin_arrow || *spin)
+        .map(|((bbox, arrow), _)| {
+            let bbox = Rect::new(bbox.x - x, bbox.y - y, bbox.width, bbox.height);
+            let label = if has_spin_arrow {
+                0
+            } else {
+                match arrow {
+                    KeyKind::Up => 0,
+                    KeyKind::Down => 1,
+                    KeyKind::Left => 2,
+                    KeyKind::Right => 3,
+                    _ => unreachable!(),
+                }
+            };
+            utils::to_yolo_format(label, crop_size, bbox)
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    Some(RuneSample {
+        crop,
+        labels,
+        has_spin_arrow,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use opencv::core::{CV_8UC4, MatExprTraitConst, Size};
+
+    use super::*;
+
+    #[test]
+    fn rune_sample_crops_and_relabels_arrows() {
+        let mat = Mat::zeros(1000, 1000, CV_8UC4).unwrap().to_mat().unwrap();
+        let result = ArrowsComplete {
+            keys: [KeyKind::Up, KeyKind::Down, KeyKind::Left, KeyKind::Right],
+            bboxes: [
+                Rect::new(100, 100, 50, 50),
+                Rect::new(200, 100, 50, 50),
+                Rect::new(300, 100, 50, 50),
+                Rect::new(400, 100, 50, 50),
+            ],
+            spins: [false; 4],
+        };
+
+        let sample = rune_sample(&mat, result).unwrap();
+
+        assert_eq!(sample.crop.size().unwrap(), Size::new(414, 114));
+        assert!(!sample.has_spin_arrow);
+        let labels = sample.labels.lines().collect::<Vec<_>>();
+        assert_eq!(labels.len(), 4);
+        assert!(labels[0].starts_with("0 "));
+        assert!(labels[3].starts_with("3 "));
+    }
+
+    #[test]
+    fn rune_sample_labels_only_spin_arrows_when_present() {
+        let mat = Mat::zeros(1000, 1000, CV_8UC4).unwrap().to_mat().unwrap();
+        let result = ArrowsComplete {
+            keys: [KeyKind::Up, KeyKind::Down, KeyKind::Left, KeyKind::Right],
+            bboxes: [
+                Rect::new(10, 10, 50, 50),
+                Rect::new(100, 10, 50, 50),
+                Rect::new(200, 10, 50, 50),
+                Rect::new(300, 10, 50, 50),
+            ],
+            spins: [true, false, true, false],
+        };
+
+        let sample = rune_sample(&mat, result).unwrap();
+
+        assert!(sample.has_spin_arrow);
+        assert_eq!(sample.crop.size().unwrap(), Size::new(382, 92));
+        let labels = sample.labels.lines().collect::<Vec<_>>();
+        assert_eq!(labels.len(), 2);
+        assert!(labels.iter().all(|label| label.starts_with("0 ")));
+    }
+}
@@ -16,8 +16,6 @@ use opencv::imgproc::{FONT_HERSHEY_SIMPLEX, put_text_def};
 use opencv::imgproc::{LINE_8, circle_def};
 use rand::distr::{Alphanumeric, SampleString};
 
-use crate::bridge::KeyKind;
-use crate::detect::ArrowsComplete;
 use crate::tracker::STrack;
 use crate::utils::{self, DatasetDir};
 
@@ -303,52 +301,12 @@ pub fn debug_rune(mat: &Mat, preds: &Vec<&[f32]>, w_ratio: f32, h_ratio: f32) {
     );
 }
 
-pub fn save_rune_for_training<T: MatTraitConst + ToInputArray>(mat: &T, result: ArrowsComplete) {
-    let has_spin_arrow = result.spins.iter().any(|spin| *spin);
-    let mut name = Alphanumeric.sample_string(&mut rand::rng(), 8);
-    if has_spin_arrow {
-        name = format!("{name}_spin");
-    }
-    let size = mat.size().unwrap();
-
-    let labels = if has_spin_arrow {
-        result
-            .bboxes
-            .into_iter()
-            .enumerate()
-            .filter(|(index, _)| result.spins[*index])
-            .map(|(_, bbox)| to_yolo_format(0, size, bbox))
-            .collect::<Vec<String>>()
-            .join("\n")
-    } else {
-        result
-            .bboxes
-            .into_iter()
-            .zip(result.keys)
-            .map(|(bbox, arrow)| {
-                let label = match arrow {
-                    KeyKind::Up => 0,
-                    KeyKind::Down => 1,
-                    KeyKind::Left => 2,
-                    KeyKind::Right => 3,
-                    _ => unreachable!(),
-                };
-                to_yolo_format(label, size, bbox)
-            })
-            .collect::<Vec<String>>()
-            .join("\n")
-    };
-
-    utils::save_image_to(mat, DatasetDir::Rune, format!("{name}.png"));
-    utils::save_file_to(labels, DatasetDir::Rune, format!("{name}.txt"));
-}
-
 #[allow(unused)]
 pub fn save_mobs_for_training(mat: &Mat, mobs: &[Rect]) {
     let name = Alphanumeric.sample_string(&mut rand::rng(), 8);
     let mut labels = Vec::<String>::new();
     for mob in mobs.iter().copied() {
-        labels.push(to_yolo_format(0, mat.size().unwrap(), mob));
+        labels.push(utils::to_yolo_format(0, mat.size().unwrap(), mob));
     }
 
     let key = debug_mat(
@@ -367,6 +325,22 @@ pub fn save_mobs_for_training(mat: &Mat, mobs: &[Rect]) {
     }
 }
 
+/// Saves `mat` with the user reviewed `mobs` as YOLO labels to the mob dataset.
+///
+/// Returns the name of the saved sample.
+pub fn save_labeled_mobs(mat: &Mat, mobs: &[Rect]) -> String {
+    let name = Alphanumeric.sample_string(&mut rand::rng(), 8);
+    let size = mat.size().unwrap();
+    let labels = mobs
+        .iter()
+        .map(|mob| utils::to_yolo_format(0, size, *mob))
+        .collect::<Vec<_>>();
+
+    utils::save_image_to(mat, DatasetDir::Mob, format!("{name}.png"));
+    utils::save_file_to(labels.join("\n"), DatasetDir::Mob, format!("{name}.txt"));
+    name
+}
+
 pub fn save_minimap_for_training<T: MatTraitConst + ToInputArray>(mat: &T, minimap: Rect) {
     let name = Alphanumeric.sample_string(&mut rand::rng(), 8);
 
@@ -374,7 +348,7 @@ pub fn save_minimap_for_training<T: MatTraitConst + ToInputArray>(mat: &T, minim
     if key == 97 {
         utils::save_image_to(mat, DatasetDir::Minimap, format!("{name}.png"));
         utils::save_file_to(
-            to_yolo_format(0, mat.size().unwrap(), minimap),
+            utils::to_yolo_format(0, mat.size().unwrap(), minimap),
             DatasetDir::Minimap,
             format!("{name}.txt"),
         );
@@ -388,13 +362,3 @@ fn map_bbox_from_prediction(pred: &[f32], size: Size, w_ratio: f32, h_ratio: f32
     let br_y = (pred[3] / h_ratio).max(0.0).min(size.height as f32) as i32;
     Rect::from_points(Point::new(tl_x, tl_y), Point::new(br_x, br_y))
 }
-
-fn to_yolo_format(label: u32, size: Size, bbox: Rect) -> String {
-    let x_center = bbox.x + bbox.width / 2;
-    let y_center = bbox.y + bbox.height / 2;
-    let x_center = x_center as f32 / size.width as f32;
-    let y_center = y_center as f32 / size.height as f32;
-    let width = bbox.width as f32 / size.width as f32;
-    let height = bbox.height as f32 / size.height as f32;
-    format!("{label} {x_center} {y_center} {width} {height}")
-}
@@ -0,0 +1,207 @@
+//! Detections of the player buffs.
+
+use opencv::{
+    boxed_ref::BoxedRef,
+    core::{CV_32FC3, Mat, MatTraitConst, ModifyInplace, Point, Rect, ToInputArray, no_array},
+    imgcodecs::{IMREAD_COLOR, IMREAD_GRAYSCALE},
+};
+
+use super::{
+    BuffKind,
+    engine::{
+        Template, detect_template, detect_template_multiple, detect_template_single, template,
+    },
+};
+
+pub(super) fn detect_player_buff<T: MatTraitConst + ToInputArray>(
+    mat: &T,
+    kind: BuffKind,
+    scale: f64,
+) -> bool {
+    fn to_normalized_mask(mut mat: Mat) -> Mat {
+        unsafe {
+            mat.modify_inplace(|mat, mat_mut| {
+                mat.convert_to(mat_mut, CV_32FC3, 1.0 / 255.0, 0.0).unwrap();
+            });
+        }
+        mat
+    }
+
+    static RUNE_BUFF: Template = template!("RUNE_BUFF_TEMPLATE", IMREAD_GRAYSCALE);
+    static FAMILIAR_BUFF: Template = template!("FAMILIAR_BUFF_TEMPLATE", IMREAD_GRAYSCALE);
+    static SAYRAM_ELIXIR_BUFF: Template =
+        template!("SAYRAM_ELIXIR_BUFF_TEMPLATE", IMREAD_GRAYSCALE);
+    static AURELIA_ELIXIR_BUFF: Template =
+        template!("AURELIA_ELIXIR_BUFF_TEMPLATE", IMREAD_GRAYSCALE);
+    static EXP_COUPON_X2_BUFF: Template =
+        template!("EXP_COUPON_X2_BUFF_TEMPLATE", IMREAD_GRAYSCALE);
+    static EXP_COUPON_X3_BUFF: Template =
+        template!("EXP_COUPON_X3_BUFF_TEMPLATE", IMREAD_GRAYSCALE);
+    static EXP_COUPON_X4_BUFF: Template =
+        template!("EXP_COUPON_X4_BUFF_TEMPLATE", IMREAD_GRAYSCALE);
+    static BONUS_EXP_COUPON_BUFF: Template =
+        template!("BONUS_EXP_COUPON_BUFF_TEMPLATE", IMREAD_GRAYSCALE);
+    static LEGION_WEALTH_BUFF: Template = template!("LEGION_WEALTH_BUFF_TEMPLATE", IMREAD_COLOR);
+    static LEGION_WEALTH_BUFF_2: Template =
+        template!("LEGION_WEALTH_BUFF_2_TEMPLATE", IMREAD_COLOR);
+    static LEGION_LUCK_BUFF: Template = template!("LEGION_LUCK_BUFF_TEMPLATE", IMREAD_COLOR);
+    static LEGION_LUCK_BUFF_MASK: Template =
+        template!("LEGION_LUCK_BUFF_MASK_TEMPLATE", IMREAD_GRAYSCALE);
+    static WEALTH_EXP_POTION_MASK: Template = template!(
+        "WEALTH_EXP_POTION_MASK_TEMPLATE",
+        IMREAD_GRAYSCALE,
+        to_normalized_mask
+    );
+    static WEALTH_ACQUISITION_POTION_BUFF: Template =
+        template!("WEALTH_ACQUISITION_POTION_BUFF_TEMPLATE", IMREAD_COLOR);
+    static EXP_ACCUMULATION_POTION_BUFF: Template =
+        template!("EXP_ACCUMULATION_POTION_BUFF_TEMPLATE", IMREAD_COLOR);
+    static SMALL_WEALTH_EXP_POTION_MASK: Template = template!(
+        "SMALL_WEALTH_EXP_POTION_MASK_TEMPLATE",
+        IMREAD_GRAYSCALE,
+        to_normalized_mask
+    );
+    static SMALL_WEALTH_ACQUISITION_POTION_BUFF: Template = template!(
+        "SMALL_WEALTH_ACQUISITION_POTION_BUFF_TEMPLATE",
+        IMREAD_COLOR
+    );
+    static SMALL_EXP_ACCUMULATION_POTION_BUFF: Template =
+        template!("SMALL_EXP_ACCUMULATION_POTION_BUFF_TEMPLATE", IMREAD_COLOR);
+    static FOR_THE_GUILD_BUFF: Template =
+        template!("FOR_THE_GUILD_BUFF_TEMPLATE", IMREAD_GRAYSCALE);
+    static HARD_HITTER_BUFF: Template = template!("HARD_HITTER_BUFF_TEMPLATE", IMREAD_GRAYSCALE);
+    static EXTREME_RED_POTION_BUFF: Template =
+        template!("EXTREME_RED_POTION_BUFF_TEMPLATE", IMREAD_COLOR);
+    static EXTREME_BLUE_POTION_BUFF: Template =
+        template!("EXTREME_BLUE_POTION_BUFF_TEMPLATE", IMREAD_COLOR);
+    static EXTREME_GREEN_POTION_BUFF: Template =
+        template!("EXTREME_GREEN_POTION_BUFF_TEMPLATE", IMREAD_COLOR);
+    static EXTREME_GOLD_POTION_BUFF: Template =
+        template!("EXTREME_GOLD_POTION_BUFF_TEMPLATE", IMREAD_COLOR);
+
+    let threshold = match kind {
+        BuffKind::AureliaElixir => 0.8,
+        BuffKind::LegionWealth | BuffKind::LegionLuck => 0.73,
+        BuffKind::SmallWealthAcquisitionPotion
+        | BuffKind::SmallExpAccumulationPotion
+        | BuffKind::WealthAcquisitionPotion
+        | BuffKind::ExpAccumulationPotion => 0.65,
+        BuffKind::Rune
+        | BuffKind::Familiar
+        | BuffKind::SayramElixir
+        | BuffKind::ExpCouponX2
+        | BuffKind::ExpCouponX3
+        | BuffKind::ExpCouponX4
+        | BuffKind::BonusExpCoupon
+        | BuffKind::ForTheGuild
+        | BuffKind::HardHitter
+        | BuffKind::ExtremeRedPotion
+        | BuffKind::ExtremeBluePotion
+        | BuffKind::ExtremeGreenPotion
+        | BuffKind::ExtremeGoldPotion => 0.75,
+    };
+    let template = match kind {
+        BuffKind::Rune => &RUNE_BUFF,
+        BuffKind::Familiar => &FAMILIAR_BUFF,
+        BuffKind::SayramElixir => &SAYRAM_ELIXIR_BUFF,
+        BuffKind::AureliaElixir => &AURELIA_ELIXIR_BUFF,
+        BuffKind::ExpCouponX2 => &EXP_COUPON_X2_BUFF,
+        BuffKind::ExpCouponX3 => &EXP_COUPON_X3_BUFF,
+        BuffKind::ExpCouponX4 => &EXP_COUPON_X4_BUFF,
+        BuffKind::BonusExpCoupon => &BONUS_EXP_COUPON_BUFF,
+        BuffKind::LegionWealth => &LEGION_WEALTH_BUFF,
+        BuffKind::LegionLuck => &LEGION_LUCK_BUFF,
+        BuffKind::WealthAcquisitionPotion => &WEALTH_ACQUISITION_POTION_BUFF,
+        BuffKind::ExpAccumulationPotion => &EXP_ACCUMULATION_POTION_BUFF,
+        BuffKind::SmallWealthAcquisitionPotion => &SMALL_WEALTH_ACQUISITION_POTION_BUFF,
+        BuffKind::SmallExpAccumulationPotion => &SMALL_EXP_ACCUMULATION_POTION_BUFF,
+        BuffKind::ForTheGuild => &FOR_THE_GUILD_BUFF,
+        BuffKind::HardHitter => &HARD_HITTER_BUFF,
+        BuffKind::ExtremeRedPotion => &EXTREME_RED_POTION_BUFF,
+        BuffKind::ExtremeBluePotion => &EXTREME_BLUE_POTION_BUFF,
+        BuffKind::ExtremeGreenPotion => &EXTREME_GREEN_POTION_BUFF,
+        BuffKind::ExtremeGoldPotion => &EXTREME_GOLD_POTION_BUFF,
+    }
+    .get_scaled(scale);
+
+    match kind {
+        BuffKind::SmallWealthAcquisitionPotion
+        | BuffKind::SmallExpAccumulationPotion
+        | BuffKind::WealthAcquisitionPotion
+        | BuffKind::ExpAccumulationPotion => {
+            // Because the two potions are really similar, detecting one may mis-detect for the other.
+            // Can't really think of a better way to do this.... But this seems working just fine.
+            let mask = match kind {
+                BuffKind::SmallWealthAcquisitionPotion | BuffKind::SmallExpAccumulationPotion => {
+                    &SMALL_WEALTH_EXP_POTION_MASK
+                }
+                BuffKind::WealthAcquisitionPotion | BuffKind::ExpAccumulationPotion => {
+                    &WEALTH_EXP_POTION_MASK
+                }
+                _ => unreachable!(),
+            }
+            .get_scaled(scale);
+            let matches =
+                detect_template_multiple(mat, &*template, &*mask, Point::default(), 2, threshold)
+                    .into_iter()
+                    .filter_map(|result| result.ok())
+                    .collect::<Vec<_>>();
+            if matches.is_empty() {
+                return false;
+            }
+            // Likely both potions are active
+            if matches.len() == 2 {
+                return true;
+            }
+
+            let template_other = match kind {
+                BuffKind::SmallWealthAcquisitionPotion => &SMALL_EXP_ACCUMULATION_POTION_BUFF,
+                BuffKind::SmallExpAccumulationPotion => &SMALL_WEALTH_ACQUISITION_POTION_BUFF,
+                BuffKind::WealthAcquisitionPotion => &EXP_ACCUMULATION_POTION_BUFF,
+                BuffKind::ExpAccumulationPotion => &WEALTH_ACQUISITION_POTION_BUFF,
+                _ => unreachable!(),
+            }
+            .get_scaled(scale);
+            let match_current = matches.into_iter().next().unwrap();
+            let match_other =
+                detect_template_single(mat, &*template_other, &*mask, Point::default(), threshold);
+
+            match_other.is_err()
+                || match_other.as_ref().copied().unwrap().0 != match_current.0
+                || match_other.unwrap().1 < match_current.1
+        }
+        BuffKind::LegionLuck => detect_template_single(
+            mat,
+            &*template,
+            &*LEGION_LUCK_BUFF_MASK.get_scaled(scale),
+            Point::default(),
+            threshold,
+        )
+        .is_ok(),
+        BuffKind::LegionWealth => {
+            detect_template_single(mat, &*template, no_array(), Point::default(), threshold)
+                .or_else(|_| {
+                    detect_template_single(
+                        mat,
+                        &*LEGION_WEALTH_BUFF_2.get_scaled(scale),
+                        no_array(),
+                        Point::default(),
+                        threshold,
+                    )
+                })
+                .is_ok()
+        }
+        _ => detect_template(mat, &*template, Point::default(), threshold).is_ok(),
+    }
+}
+
+/// Crops `mat` to the buffs region.
+#[inline]
+pub(super) fn to_buffs_region(mat: &impl MatTraitConst) -> BoxedRef<'_, Mat> {
+    let size = mat.size().unwrap();
+    // Crop to top right of the image for buffs region
+    let crop_x = size.width / 3;
+    let crop_y = size.height / 4;
+    let crop_bbox = Rect::new(size.width - crop_x, 0, crop_x, crop_y);
+    mat.roi(crop_bbox).unwrap()
+}
@@ -0,0 +1,786 @@
+//! The template matching engine shared by the detection modules.
+//!
+//! Compiled-in templates can be overridden by user provided PNGs, the template scale matching the
+//! client is resolved from an anchor per bot instance and templates decoded from
+//! [`Localization`](crate::models::Localization) are kept in a bounded cache.
+
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Debug,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::PathBuf,
+    sync::{
+        Arc, LazyLock, Mutex, OnceLock,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, anyhow, bail};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use log::{error, info};
+use opencv::{
+    core::{
+        Mat, MatTrait, MatTraitConst, ModifyInplace, Point, Rect, Scalar, Size, ToInputArray, UMat,
+        UMatTraitConst, Vector, add_weighted_def, min_max_loc, no_array,
+    },
+    imgcodecs::{IMREAD_ANYCOLOR, IMREAD_COLOR, IMREAD_GRAYSCALE, imdecode, imencode_def},
+    imgproc::{
+        COLOR_BGR2HSV_FULL, COLOR_BGRA2BGR, COLOR_BGRA2GRAY, INTER_LINEAR, TM_CCOEFF_NORMED,
+        cvt_color_def, match_template, resize,
+    },
+};
+
+use crate::{
+    cache::LruCache,
+    mat::{OwnedMat, to_umat},
+};
+
+static TEMPLATES_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
+    let dir = env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("templates");
+    fs::create_dir_all(dir.clone()).unwrap();
+    dir
+});
+
+/// The user provided PNGs loaded from [`TEMPLATES_DIR`] keyed by [`Template`] name.
+static TEMPLATE_OVERRIDES: LazyLock<Mutex<HashMap<String, Arc<Vec<u8>>>>> =
+    LazyLock::new(Mutex::default);
+
+/// Incremented on every [`reload_templates`] so that each [`Template`] decodes its override again.
+static TEMPLATE_OVERRIDES_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// The maximum number of scales each [`Template`] keeps its compiled-in template resized at.
+const TEMPLATE_SCALED_CACHE_SIZE: usize = 4;
+
+/// Declares a compiled-in [`Template`] from the build environment variable `$name`.
+macro_rules! template {
+    ($name:literal, $flags:expr) => {
+        Template::new($name, include_bytes!(env!($name)), $flags, None)
+    };
+    ($name:literal, $flags:expr, $map:expr) => {
+        Template::new($name, include_bytes!(env!($name)), $flags, Some($map))
+    };
+}
+
+pub(super) use template;
+
+/// A compiled-in template that can be overridden by a user provided PNG.
+///
+/// The override is the PNG in the `templates` folder named after the template without the
+/// `_TEMPLATE` suffix in lowercase (e.g. `popup_yes.png` for `POPUP_YES_TEMPLATE`). It is used in
+/// preference to the compiled-in template until the next [`reload_templates`].
+///
+/// Only the compiled-in template is resized by the [`TemplateScale`] since the override is
+/// captured from the client at its own scale.
+pub struct Template {
+    name: &'static str,
+    bytes: &'static [u8],
+    flags: i32,
+    /// Post-processes the decoded template (e.g. to convert a mask).
+    map: Option<fn(Mat) -> Mat>,
+    default: OnceLock<Arc<Mat>>,
+    /// The compiled-in template resized at the most recently used scales other than `1.0`.
+    scaled: Mutex<Vec<(f64, Arc<Mat>)>>,
+    /// The decoded override if any and the [`TEMPLATE_OVERRIDES_GENERATION`] it is decoded at.
+    overridden: Mutex<Option<(usize, Option<Arc<Mat>>)>>,
+}
+
+impl Debug for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Template")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl Template {
+    pub(super) const fn new(
+        name: &'static str,
+        bytes: &'static [u8],
+        flags: i32,
+        map: Option<fn(Mat) -> Mat>,
+    ) -> Self {
+        Self {
+            name,
+            bytes,
+            flags,
+            map,
+            default: OnceLock::new(),
+            scaled: Mutex::new(Vec::new()),
+            overridden: Mutex::new(None),
+        }
+    }
+
+    /// Retrieves the user provided template if any or the compiled-in one otherwise.
+    pub fn get(&self) -> Arc<Mat> {
+        self.overridden().unwrap_or_else(|| self.compiled_in())
+    }
+
+    /// Same as [`Self::get`] but the compiled-in template is resized by `scale`.
+    pub(super) fn get_scaled(&self, scale: f64) -> Arc<Mat> {
+        self.overridden()
+            .unwrap_or_else(|| self.compiled_in_scaled(scale))
+    }
+
+    fn overridden(&self) -> Option<Arc<Mat>> {
+        let generation = TEMPLATE_OVERRIDES_GENERATION.load(Ordering::Acquire);
+        let mut overridden = self.overridden.lock().unwrap();
+        if overridden
+            .as_ref()
+            .is_none_or(|(decoded_generation, _)| *decoded_generation != generation)
+        {
+            let bytes = TEMPLATE_OVERRIDES.lock().unwrap().get(self.name).cloned();
+            let mat = bytes.and_then(|bytes| match self.decode(&bytes) {
+                Ok(mat) => Some(Arc::new(mat)),
+                Err(err) => {
+                    error!(target: "detect", "failed to decode user template {}: {err}", self.name);
+                    None
+                }
+            });
+            *overridden = Some((generation, mat));
+        }
+
+        overridden.as_ref().and_then(|(_, mat)| mat.clone())
+    }
+
+    fn compiled_in(&self) -> Arc<Mat> {
+        self.default
+            .get_or_init(|| Arc::new(self.decode(self.bytes).unwrap()))
+            .clone()
+    }
+
+    /// Retrieves the compiled-in template resized by `scale`.
+    ///
+    /// Falls back to the compiled-in template as is if it cannot be resized.
+    fn compiled_in_scaled(&self, scale: f64) -> Arc<Mat> {
+        if scale == 1.0 {
+            return self.compiled_in();
+        }
+
+        let mut scaled = self.scaled.lock().unwrap();
+        if let Some((_, mat)) = scaled.iter().find(|(cached, _)| *cached == scale) {
+            return mat.clone();
+        }
+
+        let default = self.compiled_in();
+        let mut mat = Mat::default();
+        if let Err(err) = resize(
+            &*default,
+            &mut mat,
+            Size::default(),
+            scale,
+            scale,
+            INTER_LINEAR,
+        ) {
+            error!(target: "detect", "failed to scale template {} by {scale}: {err}", self.name);
+            return default;
+        }
+
+        let mat = Arc::new(mat);
+        if scaled.len() >= TEMPLATE_SCALED_CACHE_SIZE {
+            scaled.remove(0);
+        }
+        scaled.push((scale, mat.clone()));
+        mat
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Mat> {
+        let bytes = Vector::<u8>::from_iter(bytes.iter().copied());
+        let mat = imdecode(&bytes, self.flags)?;
+        if mat.empty() {
+            bail!("template is empty");
+        }
+
+        Ok(match self.map {
+            Some(map) => map(mat),
+            None => mat,
+        })
+    }
+}
+
+/// Reloads the user provided templates from the `templates` folder.
+///
+/// Returns the file names of the loaded templates.
+pub fn reload_templates() -> Vec<String> {
+    let mut overrides = HashMap::new();
+    let mut names = fs::read_dir(&*TEMPLATES_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            let bytes = fs::read(&path).ok()?;
+            overrides.insert(
+                format!("{}_TEMPLATE", name.to_ascii_uppercase()),
+                Arc::new(bytes),
+            );
+            Some(format!("{name}.png"))
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+
+    *TEMPLATE_OVERRIDES.lock().unwrap() = overrides;
+    TEMPLATE_OVERRIDES_GENERATION.fetch_add(1, Ordering::Release);
+    info!(target: "detect", "loaded user templates {names:?}");
+    names
+}
+
+/// The client height in pixels the compiled-in templates are captured at.
+const TEMPLATE_BASE_HEIGHT: i32 = 720;
+
+/// The interval between attempts to resolve the [`TemplateScale`] from the anchor.
+const TEMPLATE_SCALE_RESOLVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The number of attempts in a row the anchor must match best at the same scale to resolve it.
+const TEMPLATE_SCALE_CONFIRMATIONS: u32 = 3;
+
+/// The scale of the templates and pixel regions relative to the frames being detected.
+///
+/// The client renders at the templates scale unless the in-game default ratio is enabled, in
+/// which case everything is scaled to the client height. When the client height differs from
+/// [`TEMPLATE_BASE_HEIGHT`], an anchor always visible in game is matched at both scales and
+/// the scale it matches best at is used once confirmed by [`TEMPLATE_SCALE_CONFIRMATIONS`]
+/// attempts in a row. The scale is kept until the frame size changes and is `1.0` until
+/// resolved.
+#[derive(Debug, Default)]
+struct TemplateScale {
+    frame_size: Size,
+    resolved: Option<f64>,
+    /// The scale the anchor last matched best at and the number of attempts in a row.
+    confirming: Option<(f64, u32)>,
+    last_attempt: Option<Instant>,
+    /// Whether an attempt is still matching the anchor.
+    attempting: bool,
+}
+
+impl TemplateScale {
+    /// Updates the size of the frames being detected and resets the scale if it changed.
+    ///
+    /// Returns the scales to match the anchor at if an attempt to resolve the scale is due.
+    fn update(&mut self, frame_size: Size, now: Instant) -> Option<[f64; 2]> {
+        if self.frame_size != frame_size {
+            *self = TemplateScale {
+                frame_size,
+                resolved: (frame_size.height == TEMPLATE_BASE_HEIGHT).then_some(1.0),
+                ..TemplateScale::default()
+            };
+        }
+        if self.resolved.is_some()
+            || self.attempting
+            || frame_size.height <= 0
+            || self.last_attempt.is_some_and(|instant| {
+                now.duration_since(instant) < TEMPLATE_SCALE_RESOLVE_INTERVAL
+            })
+        {
+            return None;
+        }
+
+        self.last_attempt = Some(now);
+        self.attempting = true;
+        Some([1.0, frame_size.height as f64 / TEMPLATE_BASE_HEIGHT as f64])
+    }
+
+    /// Records the `scale` the anchor matched best at in a frame of `frame_size` or `None` if it
+    /// did not match at all.
+    fn record_anchor_match(&mut self, frame_size: Size, scale: Option<f64>) {
+        // The frame size changed while matching
+        if self.frame_size != frame_size {
+            return;
+        }
+        self.attempting = false;
+        // The anchor may be hidden such as when in the cash shop
+        let Some(scale) = scale else {
+            return;
+        };
+        let count = match self.confirming {
+            Some((confirming, count)) if confirming == scale => count + 1,
+            _ => 1,
+        };
+
+        if count >= TEMPLATE_SCALE_CONFIRMATIONS {
+            info!(
+                target: "detect",
+                "template scale resolved to {scale} for frame size {:?}",
+                self.frame_size
+            );
+            self.resolved = Some(scale);
+            self.confirming = None;
+        } else {
+            self.confirming = Some((scale, count));
+        }
+    }
+
+    #[inline]
+    fn scale(&self) -> f64 {
+        self.resolved.unwrap_or(1.0)
+    }
+}
+
+/// Resolves the [`TemplateScale`] from the frames of a single bot instance.
+///
+/// Each instance captures its own client, which can be at a different size, so the scale is not
+/// shared across instances.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateScaleResolver {
+    inner: Arc<Mutex<TemplateScale>>,
+}
+
+impl TemplateScaleResolver {
+    /// Returns the resolved scale or `1.0` if not resolved.
+    pub(super) fn scale(&self) -> f64 {
+        self.inner.lock().unwrap().scale()
+    }
+
+    /// Updates the scale from the BGRA `mat` of the frame being detected.
+    ///
+    /// While the scale is unresolved, the compiled-in `anchor` is matched against `mat` with
+    /// `threshold` at most once per [`TEMPLATE_SCALE_RESOLVE_INTERVAL`]. Matching runs on a
+    /// separate thread so that it does not delay the detection of the frame.
+    pub(super) fn update(&self, mat: Arc<OwnedMat>, anchor: &'static Template, threshold: f64) {
+        let size = mat.as_mat().size().expect("has size");
+        let Some(scales) = self.inner.lock().unwrap().update(size, Instant::now()) else {
+            return;
+        };
+
+        let inner = self.inner.clone();
+        thread::spawn(move || {
+            let grayscale = to_grayscale(&mat.as_mat(), true);
+            let best = scales
+                .into_iter()
+                .filter_map(|scale| {
+                    let anchor = anchor.compiled_in_scaled(scale);
+                    if anchor.cols() > size.width || anchor.rows() > size.height {
+                        return None;
+                    }
+                    let matches = match_template_multiple(
+                        &grayscale,
+                        &*anchor,
+                        &no_array(),
+                        Point::default(),
+                        1,
+                        threshold,
+                    );
+                    let (_, score) = matches.into_iter().next()?.ok()?;
+                    Some((scale, score))
+                })
+                .max_by(|(_, first), (_, second)| first.total_cmp(second))
+                .map(|(scale, _)| scale);
+
+            inner.lock().unwrap().record_anchor_match(size, best);
+        });
+    }
+}
+
+/// Scales a pixel length `value` measured at [`TEMPLATE_BASE_HEIGHT`] by `scale`.
+#[inline]
+pub(super) fn scale_pixels(value: i32, scale: f64) -> i32 {
+    (value as f64 * scale).round() as i32
+}
+
+/// Detects a single match from `template` with the given BGR image `Mat`.
+#[inline]
+pub(super) fn detect_template<T: ToInputArray + MatTraitConst>(
+    mat: &impl ToInputArray,
+    template: &T,
+    offset: Point,
+    threshold: f64,
+) -> Result<Rect> {
+    detect_template_single(mat, template, no_array(), offset, threshold).map(|(bbox, _)| bbox)
+}
+
+/// Detects a single match with `mask` from `template` with the given BGR image `Mat`.
+#[inline]
+pub(super) fn detect_template_single<T: ToInputArray + MatTraitConst>(
+    mat: &impl ToInputArray,
+    template: &T,
+    mask: impl ToInputArray,
+    offset: Point,
+    threshold: f64,
+) -> Result<(Rect, f64)> {
+    detect_template_multiple(mat, template, mask, offset, 1, threshold)
+        .into_iter()
+        .next()
+        .ok_or(anyhow!("no match"))
+        .and_then(|x| x)
+}
+
+/// Detects whether any of the `(template, mask, threshold)` `candidates` matches `mat`.
+///
+/// The candidates are matched in parallel and the remaining candidates that have not started
+/// matching are skipped as soon as one matches.
+pub(super) fn detect_any_template(
+    mat: &(impl ToInputArray + Sync),
+    candidates: &[(&Mat, Option<&Mat>, f64)],
+) -> bool {
+    let found = AtomicBool::new(false);
+    let next = AtomicUsize::new(0);
+    let workers = thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+        .min(candidates.len());
+    let match_next = || {
+        while !found.load(Ordering::Relaxed) {
+            let Some((template, mask, threshold)) =
+                candidates.get(next.fetch_add(1, Ordering::Relaxed))
+            else {
+                break;
+            };
+            let matched = match mask {
+                Some(mask) => {
+                    detect_template_single(mat, *template, *mask, Point::default(), *threshold)
+                }
+                None => {
+                    detect_template_single(mat, *template, no_array(), Point::default(), *threshold)
+                }
+            }
+            .is_ok();
+            if matched {
+                found.store(true, Ordering::Relaxed);
+            }
+        }
+    };
+
+    thread::scope(|scope| {
+        for _ in 1..workers {
+            scope.spawn(match_next);
+        }
+        match_next();
+    });
+
+    found.into_inner()
+}
+
+/// Detects multiple matches from `template` from the given BGR image `Mat` and returns up to
+/// `max_matches` best results.
+#[inline]
+pub(super) fn detect_template_multiple<T: ToInputArray + MatTraitConst>(
+    mat: &impl ToInputArray,
+    template: &T,
+    mask: impl ToInputArray,
+    offset: Point,
+    max_matches: usize,
+    threshold: f64,
+) -> Vec<Result<(Rect, f64)>> {
+    match_template_multiple(mat, template, &mask, offset, max_matches, threshold)
+}
+
+/// Matches `template` against `mat` with [`TM_CCOEFF_NORMED`] into `result`.
+///
+/// Matching runs on GPU if OpenCL is enabled with only `result` downloaded back.
+#[inline]
+fn match_template_normed(
+    mat: &impl ToInputArray,
+    template: &impl ToInputArray,
+    mask: &impl ToInputArray,
+    result: &mut Mat,
+) -> Result<()> {
+    if let Some(mat) = to_umat(mat)
+        && let Some(template) = to_umat(template)
+        && let Some(mask) = to_umat(mask)
+    {
+        let mut umat_result = UMat::new_def();
+        match_template(&mat, &template, &mut umat_result, TM_CCOEFF_NORMED, &mask)?;
+        umat_result.copy_to(result)?;
+    } else {
+        match_template(mat, template, result, TM_CCOEFF_NORMED, mask)?;
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn match_template_multiple<T: ToInputArray + MatTraitConst>(
+    mat: &impl ToInputArray,
+    template: &T,
+    mask: &impl ToInputArray,
+    offset: Point,
+    max_matches: usize,
+    threshold: f64,
+) -> Vec<Result<(Rect, f64)>> {
+    #[inline]
+    fn clear_result(result: &mut Mat, loc: Point, template_size: Size) {
+        let cols = result.cols();
+        let rows = result.rows();
+
+        let x1 = loc.x.clamp(0, cols);
+        let y1 = loc.y.clamp(0, rows);
+        let x2 = (loc.x + template_size.width).clamp(0, cols);
+        let y2 = (loc.y + template_size.height).clamp(0, rows);
+
+        if x2 <= x1 || y2 <= y1 {
+            return;
+        }
+
+        result
+            .roi_mut(Rect::new(x1, y1, x2 - x1, y2 - y1))
+            .expect("valid ROI")
+            .set_scalar(Scalar::all(0.0))
+            .expect("failed to set scalar");
+    }
+
+    #[inline]
+    fn append_result(
+        matches: &mut Vec<Result<(Rect, f64)>>,
+        score: f64,
+        loc: Point,
+        offset: Point,
+        template_size: Size,
+    ) {
+        // Weird INFINITY values when match template with mask
+        // https://github.com/opencv/opencv/issues/23257
+        if score == f64::INFINITY {
+            return;
+        }
+
+        let tl = Point::new(loc.x + offset.x, loc.y + offset.y);
+        let br = tl + Point::new(template_size.width, template_size.height);
+        let rect = Rect::from_points(tl, br);
+        if matches
+            .iter()
+            .filter_map(|result| result.as_ref().ok())
+            .any(|(match_rect, _)| iou(*match_rect, rect) > 0.9)
+        {
+            return;
+        }
+
+        matches.push(Ok((rect, score)));
+    }
+
+    let mut result = Mat::default();
+    if let Err(err) = match_template_normed(mat, template, mask, &mut result) {
+        error!(target: "detect", "template detection error {err}");
+        return vec![];
+    }
+
+    let template_size = template.size().unwrap();
+    let max_matches = max_matches.max(1);
+    let mut matches = Vec::new();
+    while matches.len() < max_matches {
+        let mut score = 0f64;
+        let mut loc = Point::default();
+        min_max_loc(
+            &result,
+            None,
+            Some(&mut score),
+            None,
+            Some(&mut loc),
+            &no_array(),
+        )
+        .unwrap();
+        if score < threshold {
+            matches.push(Err(anyhow!("template not found").context(score)));
+            break;
+        }
+
+        clear_result(&mut result, loc, template_size);
+        append_result(&mut matches, score, loc, offset, template_size);
+    }
+
+    matches
+}
+
+/// Expands `bbox` in all the direction by `count` pixel(s) and clamps to `size` if provided.
+#[inline]
+pub(super) fn expand_bbox(size: Option<Size>, bbox: Rect, count: i32) -> Rect {
+    let mut x1 = bbox.x - count;
+    let mut y1 = bbox.y - count;
+    if size.is_some() {
+        x1 = x1.max(0);
+        y1 = y1.max(0);
+    }
+
+    let br = bbox.br();
+    let mut x2 = br.x + count;
+    let mut y2 = br.y + count;
+    if let Some(size) = size {
+        x2 = x2.min(size.width);
+        y2 = y2.min(size.height);
+    }
+
+    Rect::new(x1, y1, x2 - x1, y2 - y1)
+}
+
+/// Computes the intersection over union ratio.
+#[inline]
+pub(super) fn iou(first: Rect, second: Rect) -> f32 {
+    let intersection = (first & second).area() as f32;
+    let union = (first | second).area() as f32;
+    intersection / union
+}
+
+/// Converts a BGR `Mat` image to HSV.
+#[inline]
+pub(super) fn to_hsv(mat: &impl MatTraitConst) -> Mat {
+    let mut mat = mat.try_clone().unwrap();
+    unsafe {
+        // SAFETY: can be modified inplace
+        mat.modify_inplace(|mat, mat_mut| {
+            cvt_color_def(mat, mat_mut, COLOR_BGR2HSV_FULL).unwrap();
+        });
+    }
+    mat
+}
+
+/// Converts a BGRA `Mat` image to BGR.
+#[inline]
+pub(super) fn to_bgr(mat: &impl MatTraitConst) -> Mat {
+    let mut mat = mat.try_clone().unwrap();
+    unsafe {
+        // SAFETY: can be modified inplace
+        mat.modify_inplace(|mat, mat_mut| {
+            cvt_color_def(mat, mat_mut, COLOR_BGRA2BGR).unwrap();
+        });
+    }
+    mat
+}
+
+/// Converts a BGRA `Mat` image to grayscale.
+///
+/// `add_contrast` can be set to `true` in order to increase contrast by a fixed amount
+/// used for template matching.
+#[inline]
+pub(super) fn to_grayscale(mat: &impl MatTraitConst, add_contrast: bool) -> Mat {
+    let mut mat = mat.try_clone().unwrap();
+    unsafe {
+        // SAFETY: all of the functions below can be called in place.
+        mat.modify_inplace(|mat, mat_mut| {
+            cvt_color_def(mat, mat_mut, COLOR_BGRA2GRAY).unwrap();
+            if add_contrast {
+                // TODO: is this needed?
+                add_weighted_def(mat, 1.5, mat, 0.0, -80.0, mat_mut).unwrap();
+            }
+        });
+    }
+    mat
+}
+
+/// The memory budget in bytes for [`LOCALIZATION_TEMPLATES`].
+const LOCALIZATION_TEMPLATES_BUDGET_BYTES: usize = 64 * 1024 * 1024;
+
+/// Decoded templates from [`Localization`] keyed by the hash of the base64 and the flag it is
+/// decoded with.
+///
+/// This cache is cleared by [`invalidate_localization_templates`] whenever [`Localization`] is
+/// updated so that new templates take effect without restarting. The least recently used
+/// templates are evicted when exceeding [`LOCALIZATION_TEMPLATES_BUDGET_BYTES`].
+static LOCALIZATION_TEMPLATES: LazyLock<Mutex<LruCache<(u64, i32), Arc<Mat>>>> =
+    LazyLock::new(|| Mutex::new(LruCache::new(LOCALIZATION_TEMPLATES_BUDGET_BYTES)));
+
+/// Converts `base64` to a [`Mat`] cached in [`LOCALIZATION_TEMPLATES`].
+///
+/// If `grayscale` is `true`, `base64` will be read with [`IMREAD_GRAYSCALE`]. Otherwise, it is
+/// read with [`IMREAD_COLOR`].
+pub(super) fn to_cached_mat_from_base64(base64: &str, grayscale: bool) -> Result<Arc<Mat>> {
+    let flag = if grayscale {
+        IMREAD_GRAYSCALE
+    } else {
+        IMREAD_COLOR
+    };
+    to_cached_mat_from_base64_with_flag(base64, flag)
+}
+
+/// Same as [`to_mat_from_base64_any_color`] but caches the decoded `Mat` in
+/// [`LOCALIZATION_TEMPLATES`].
+pub fn to_cached_mat_from_base64_any_color(base64: &str) -> Result<Arc<Mat>> {
+    to_cached_mat_from_base64_with_flag(base64, IMREAD_ANYCOLOR)
+}
+
+fn to_cached_mat_from_base64_with_flag(base64: &str, flag: i32) -> Result<Arc<Mat>> {
+    let mut hasher = DefaultHasher::new();
+    base64.hash(&mut hasher);
+    let key = (hasher.finish(), flag);
+
+    if let Some(mat) = LOCALIZATION_TEMPLATES.lock().unwrap().get(&key) {
+        return Ok(mat);
+    }
+
+    let bytes = BASE64_STANDARD.decode(base64)?;
+    let bytes = Vector::<u8>::from_iter(bytes);
+    let mat = Arc::new(imdecode(&bytes, flag)?);
+    let bytes = mat.total() * mat.elem_size()?;
+    LOCALIZATION_TEMPLATES
+        .lock()
+        .unwrap()
+        .insert(key, mat.clone(), bytes);
+    Ok(mat)
+}
+
+/// Returns the memory used and budget in bytes of cached [`Localization`] templates.
+pub fn localization_templates_memory() -> (usize, usize) {
+    let templates = LOCALIZATION_TEMPLATES.lock().unwrap();
+    (templates.used_bytes(), templates.budget_bytes())
+}
+
+/// Clears all cached [`Localization`] templates.
+pub fn invalidate_localization_templates() {
+    LOCALIZATION_TEMPLATES.lock().unwrap().clear();
+}
+
+/// Converts base64 PNG `base64` to a `Mat` keeping grayscale image as single channel.
+pub fn to_mat_from_base64_any_color(base64: &str) -> Result<Mat> {
+    let bytes = BASE64_STANDARD.decode(base64)?;
+    let bytes = Vector::<u8>::from_iter(bytes);
+
+    Ok(imdecode(&bytes, IMREAD_ANYCOLOR)?)
+}
+
+/// Converts `mat` to a base64 PNG [`String`].
+pub fn to_base64_from_mat(mat: &Mat) -> Result<String> {
+    let mut bytes = Vector::new();
+    imencode_def(".png", mat, &mut bytes)?;
+    Ok(BASE64_STANDARD.encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_scale_resolves_after_confirmations_without_overlapping_attempts() {
+        let size = Size::new(1920, 1080);
+        let mut scale = TemplateScale::default();
+        let mut now = Instant::now();
+
+        for _ in 0..TEMPLATE_SCALE_CONFIRMATIONS {
+            assert_eq!(scale.update(size, now), Some([1.0, 1.5]));
+            assert_eq!(
+                scale.update(size, now + TEMPLATE_SCALE_RESOLVE_INTERVAL),
+                None
+            );
+            scale.record_anchor_match(size, Some(1.5));
+            now += TEMPLATE_SCALE_RESOLVE_INTERVAL;
+        }
+
+        assert_eq!(scale.scale(), 1.5);
+        assert_eq!(scale.update(size, now), None);
+    }
+
+    #[test]
+    fn template_scale_ignores_anchor_match_from_previous_frame_size() {
+        let previous = Size::new(1920, 1080);
+        let size = Size::new(1366, 768);
+        let mut scale = TemplateScale::default();
+        let now = Instant::now();
+
+        assert!(scale.update(previous, now).is_some());
+        assert!(scale.update(size, now).is_some());
+        for _ in 0..TEMPLATE_SCALE_CONFIRMATIONS {
+            scale.record_anchor_match(previous, Some(1.5));
+        }
+
+        assert_eq!(scale.scale(), 1.0);
+        assert!(scale.attempting);
+    }
+}
@@ -0,0 +1,241 @@
+//! Detections inside the familiar menu.
+
+use anyhow::Result;
+use opencv::{
+    core::{Mat, MatTraitConst, Point, Rect, ToInputArray, min_max_loc, no_array},
+    imgcodecs::{IMREAD_COLOR, IMREAD_GRAYSCALE},
+    imgproc::{TM_SQDIFF_NORMED, match_template},
+};
+
+use super::{
+    FamiliarLevel, FamiliarRank,
+    engine::{
+        Template, detect_template, detect_template_multiple, detect_template_single, iou, template,
+        to_cached_mat_from_base64,
+    },
+};
+use crate::models::Localization;
+
+pub static FAMILIAR_SAVE_BUTTON_TEMPLATE: Template =
+    template!("FAMILIAR_BUTTON_SAVE_TEMPLATE", IMREAD_COLOR);
+
+pub(super) fn detect_familiar_save_button(
+    bgr: &impl ToInputArray,
+    localization: &Localization,
+    scale: f64,
+) -> Result<Rect> {
+    let template = localization
+        .familiar_save_button_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, false).ok());
+
+    detect_template(
+        bgr,
+        template
+            .as_deref()
+            .unwrap_or(&*FAMILIAR_SAVE_BUTTON_TEMPLATE.get_scaled(scale)),
+        Point::default(),
+        0.75,
+    )
+}
+
+pub static FAMILIAR_LEVEL_BUTTON_TEMPLATE: Template =
+    template!("FAMILIAR_BUTTON_LEVEL_TEMPLATE", IMREAD_COLOR);
+
+pub(super) fn detect_familiar_level_button(
+    bgr: &impl ToInputArray,
+    localization: &Localization,
+    scale: f64,
+) -> Result<Rect> {
+    let template = localization
+        .familiar_level_button_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, false).ok());
+
+    detect_template(
+        bgr,
+        template
+            .as_deref()
+            .unwrap_or(&*FAMILIAR_LEVEL_BUTTON_TEMPLATE.get_scaled(scale)),
+        Point::default(),
+        0.75,
+    )
+}
+
+static FAMILIAR_SLOT_FREE: Template = template!("FAMILIAR_SLOT_FREE_TEMPLATE", IMREAD_COLOR);
+
+pub(super) fn detect_familiar_slots(bgr: &impl ToInputArray, scale: f64) -> Vec<(Rect, bool)> {
+    static FAMILIAR_SLOT_OCCUPIED: Template =
+        template!("FAMILIAR_SLOT_OCCUPIED_TEMPLATE", IMREAD_COLOR);
+    static FAMILIAR_SLOT_OCCUPIED_MASK: Template =
+        template!("FAMILIAR_SLOT_OCCUPIED_MASK_TEMPLATE", IMREAD_GRAYSCALE);
+
+    let first = detect_template_multiple(
+        bgr,
+        &*FAMILIAR_SLOT_FREE.get_scaled(scale),
+        no_array(),
+        Point::default(),
+        3,
+        0.75,
+    );
+    let first_slots = first
+        .into_iter()
+        .filter_map(|bbox| bbox.ok().map(|(bbox, _)| (bbox, true)))
+        .collect::<Vec<(Rect, bool)>>();
+
+    // The occupied slots detection acts more like a general familiar slot frame detection. This
+    // may include both free and occupied slots. As such, truely free slots need to be filtered
+    // out.
+    let second = detect_template_multiple(
+        bgr,
+        &*FAMILIAR_SLOT_OCCUPIED.get_scaled(scale),
+        &*FAMILIAR_SLOT_OCCUPIED_MASK.get_scaled(scale),
+        Point::default(),
+        3,
+        0.75,
+    );
+    let second_slots = second
+        .into_iter()
+        .filter_map(|bbox| bbox.ok().map(|(bbox, _)| (bbox, false)))
+        .filter(|(second_slot, _)| {
+            !first_slots
+                .iter()
+                .any(|(first_slot, _)| iou(*first_slot, *second_slot) >= 0.8)
+        })
+        .collect::<Vec<(Rect, bool)>>();
+
+    let mut vec = [first_slots, second_slots].concat();
+    vec.sort_by_key(|(bbox, _)| bbox.x);
+    vec
+}
+
+pub(super) fn detect_familiar_slot_is_free(bgr: &impl ToInputArray, scale: f64) -> bool {
+    detect_template(
+        bgr,
+        &*FAMILIAR_SLOT_FREE.get_scaled(scale),
+        Point::default(),
+        0.75,
+    )
+    .is_ok()
+}
+
+pub(super) fn detect_familiar_hover_level<T: ToInputArray + MatTraitConst>(
+    bgr: &T,
+    scale: f64,
+) -> Result<FamiliarLevel> {
+    static TEMPLATE: Template = template!("FAMILIAR_LEVEL_5_TEMPLATE", IMREAD_COLOR);
+    static TEMPLATE_MASK: Template = template!("FAMILIAR_LEVEL_5_MASK_TEMPLATE", IMREAD_GRAYSCALE);
+
+    let level_bbox = detect_template(bgr, &*TEMPLATE.get_scaled(scale), Point::default(), 0.65)?;
+    let level = bgr.roi(level_bbox)?;
+    Ok(detect_template_single(
+        &level,
+        &*TEMPLATE.get_scaled(scale),
+        &*TEMPLATE_MASK.get_scaled(scale),
+        Point::default(),
+        0.70,
+    )
+    .map_or(FamiliarLevel::LevelOther, |_| FamiliarLevel::Level5))
+}
+
+pub(super) fn detect_familiar_cards<T: MatTraitConst + ToInputArray>(
+    bgr: &T,
+    scale: f64,
+) -> Vec<(Rect, FamiliarRank)> {
+    static TEMPLATE_RARE: Template = template!("FAMILIAR_CARD_RARE_TEMPLATE", IMREAD_COLOR);
+    static TEMPLATE_EPIC: Template = template!("FAMILIAR_CARD_EPIC_TEMPLATE", IMREAD_COLOR);
+    static TEMPLATE_MASK: Template = template!("FAMILIAR_CARD_MASK_TEMPLATE", IMREAD_GRAYSCALE);
+
+    #[inline]
+    fn match_template_score(
+        mat: &impl ToInputArray,
+        template: &impl ToInputArray,
+        mask: &impl ToInputArray,
+    ) -> f64 {
+        let mut result = Mat::default();
+        let mut score = 0f64;
+        match_template(mat, template, &mut result, TM_SQDIFF_NORMED, mask).unwrap();
+        min_max_loc(&result, Some(&mut score), None, None, None, &no_array()).unwrap();
+        score
+    }
+
+    // The current method would match all card without distinguishing rarity
+    let cards = detect_template_multiple(
+        bgr,
+        &*TEMPLATE_RARE.get_scaled(scale),
+        &*TEMPLATE_MASK.get_scaled(scale),
+        Point::default(),
+        64,
+        0.75,
+    )
+    .into_iter()
+    .filter_map(|result| result.ok().map(|(bbox, _)| bbox))
+    .collect::<Vec<_>>();
+
+    let mut filtered = vec![];
+    if cards.is_empty() {
+        return filtered;
+    }
+
+    for card in cards {
+        let roi = bgr.roi(card).unwrap();
+        let score_rare = match_template_score(
+            &roi,
+            &*TEMPLATE_RARE.get_scaled(scale),
+            &*TEMPLATE_MASK.get_scaled(scale),
+        );
+        let score_epic = match_template_score(
+            &roi,
+            &*TEMPLATE_EPIC.get_scaled(scale),
+            &*TEMPLATE_MASK.get_scaled(scale),
+        );
+        // TODO: If matching all rarities, it will probably be easier since just need to
+        // pick lowest score
+        if score_rare < 0.14 || score_epic < 0.14 {
+            let rank = if score_rare < score_epic {
+                FamiliarRank::Rare
+            } else {
+                FamiliarRank::Epic
+            };
+            filtered.push((card, rank));
+        }
+    }
+    filtered.sort_by_key(|(bbox, _)| (bbox.y, bbox.x));
+
+    filtered
+}
+
+pub(super) fn detect_familiar_scrollbar(grayscale: &impl ToInputArray, scale: f64) -> Result<Rect> {
+    static TEMPLATE: Template = template!("FAMILIAR_SCROLLBAR_TEMPLATE", IMREAD_GRAYSCALE);
+
+    detect_template(
+        grayscale,
+        &*TEMPLATE.get_scaled(scale),
+        Point::default(),
+        0.6,
+    )
+}
+
+pub(super) fn detect_familiar_menu_opened(grayscale: &impl ToInputArray, scale: f64) -> bool {
+    static TEMPLATE: Template = template!("FAMILIAR_MENU_TEMPLATE", IMREAD_GRAYSCALE);
+
+    detect_template(
+        grayscale,
+        &*TEMPLATE.get_scaled(scale),
+        Point::default(),
+        0.75,
+    )
+    .is_ok()
+}
+
+pub(super) fn detect_familiar_essence_depleted(grayscale: &impl ToInputArray, scale: f64) -> bool {
+    static TEMPLATE: Template = template!("FAMILIAR_ESSENCE_DEPLETE_TEMPLATE", IMREAD_GRAYSCALE);
+
+    detect_template(
+        grayscale,
+        &*TEMPLATE.get_scaled(scale),
+        Point::default(),
+        0.8,
+    )
+    .is_ok()
+}
@@ -0,0 +1,176 @@
+//! Detections inside the HEXA matrix menus.
+
+use anyhow::{Result, bail};
+use opencv::{
+    core::{Point, Rect, ToInputArray},
+    imgcodecs::{IMREAD_COLOR, IMREAD_GRAYSCALE},
+};
+
+use super::{
+    SolErda,
+    engine::{
+        Template, detect_template, detect_template_single, template, to_cached_mat_from_base64,
+    },
+};
+use crate::models::Localization;
+
+pub(super) fn detect_hexa_menu(grayscale: &impl ToInputArray, scale: f64) -> bool {
+    static TEMPLATE: Template = template!("HEXA_MENU_TEMPLATE", IMREAD_GRAYSCALE);
+
+    detect_template(
+        grayscale,
+        &*TEMPLATE.get_scaled(scale),
+        Point::default(),
+        0.75,
+    )
+    .is_ok()
+}
+
+pub(super) fn detect_hexa_quick_menu(grayscale: &impl ToInputArray, scale: f64) -> Result<Rect> {
+    static TEMPLATE: Template = template!("HEXA_QUICK_MENU_TEMPLATE", IMREAD_GRAYSCALE);
+
+    detect_template(
+        grayscale,
+        &*TEMPLATE.get_scaled(scale),
+        Point::default(),
+        0.75,
+    )
+}
+
+pub static HEXA_ERDA_CONVERSION_BUTTON_TEMPLATE: Template =
+    template!("HEXA_BUTTON_ERDA_CONVERSION_TEMPLATE", IMREAD_COLOR);
+
+pub(super) fn detect_hexa_erda_conversion_button(
+    bgr: &impl ToInputArray,
+    localization: &Localization,
+    scale: f64,
+) -> Result<Rect> {
+    let template = localization
+        .hexa_erda_conversion_button_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, false).ok());
+
+    detect_template(
+        bgr,
+        template
+            .as_deref()
+            .unwrap_or(&*HEXA_ERDA_CONVERSION_BUTTON_TEMPLATE.get_scaled(scale)),
+        Point::default(),
+        0.75,
+    )
+}
+
+pub static HEXA_BOOSTER_BUTTON_TEMPLATE: Template =
+    template!("HEXA_BUTTON_HEXA_BOOSTER_TEMPLATE", IMREAD_COLOR);
+
+pub(super) fn detect_hexa_booster_button(
+    bgr: &impl ToInputArray,
+    localization: &Localization,
+    scale: f64,
+) -> Result<Rect> {
+    let template = localization
+        .hexa_booster_button_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, false).ok());
+
+    detect_template(
+        bgr,
+        template
+            .as_deref()
+            .unwrap_or(&*HEXA_BOOSTER_BUTTON_TEMPLATE.get_scaled(scale)),
+        Point::default(),
+        0.75,
+    )
+}
+
+pub static HEXA_MAX_BUTTON_TEMPLATE: Template = template!("HEXA_BUTTON_MAX_TEMPLATE", IMREAD_COLOR);
+
+pub(super) fn detect_hexa_max_button(
+    bgr: &impl ToInputArray,
+    localization: &Localization,
+    scale: f64,
+) -> Result<Rect> {
+    let template = localization
+        .hexa_max_button_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, false).ok());
+
+    detect_template(
+        bgr,
+        template
+            .as_deref()
+            .unwrap_or(&*HEXA_MAX_BUTTON_TEMPLATE.get_scaled(scale)),
+        Point::default(),
+        0.75,
+    )
+}
+
+pub static HEXA_CONVERT_BUTTON_TEMPLATE: Template =
+    template!("HEXA_BUTTON_CONVERT_TEMPLATE", IMREAD_COLOR);
+
+pub(super) fn detect_hexa_convert_button(
+    bgr: &impl ToInputArray,
+    localization: &Localization,
+    scale: f64,
+) -> Result<Rect> {
+    let template = localization
+        .hexa_convert_button_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, false).ok());
+
+    detect_template(
+        bgr,
+        template
+            .as_deref()
+            .unwrap_or(&*HEXA_CONVERT_BUTTON_TEMPLATE.get_scaled(scale)),
+        Point::default(),
+        0.75,
+    )
+}
+
+pub(super) fn detect_hexa_sol_erda(grayscale: &impl ToInputArray, scale: f64) -> Result<SolErda> {
+    static TEMPLATE: Template = template!("HEXA_SOL_ERDA_TEMPLATE", IMREAD_GRAYSCALE);
+    static FULL_TEMPLATE: Template = template!("HEXA_SOL_ERDA_FULL_TEMPLATE", IMREAD_GRAYSCALE);
+    static FULL_MASK_TEMPLATE: Template =
+        template!("HEXA_SOL_ERDA_FULL_MASK_TEMPLATE", IMREAD_GRAYSCALE);
+    static EMPTY_TEMPLATE: Template = template!("HEXA_SOL_ERDA_EMPTY_TEMPLATE", IMREAD_GRAYSCALE);
+    static EMPTY_MASK_TEMPLATE: Template =
+        template!("HEXA_SOL_ERDA_EMPTY_MASK_TEMPLATE", IMREAD_GRAYSCALE);
+
+    if detect_template_single(
+        grayscale,
+        &*FULL_TEMPLATE.get_scaled(scale),
+        &*FULL_MASK_TEMPLATE.get_scaled(scale),
+        Point::default(),
+        0.8,
+    )
+    .is_ok()
+    {
+        return Ok(SolErda::Full);
+    }
+
+    if detect_template_single(
+        grayscale,
+        &*EMPTY_TEMPLATE.get_scaled(scale),
+        &*EMPTY_MASK_TEMPLATE.get_scaled(scale),
+        Point::default(),
+        0.8,
+    )
+    .is_ok()
+    {
+        return Ok(SolErda::Empty);
+    }
+
+    if detect_template(
+        grayscale,
+        &*TEMPLATE.get_scaled(scale),
+        Point::default(),
+        0.75,
+    )
+    .is_ok()
+    {
+        return Ok(SolErda::AtLeastOne);
+    };
+
+    bail!("sol erda tracker menu not visible")
+}
@@ -0,0 +1,445 @@
+//! ONNX model sessions and the pre/post-processing for YOLO and text detection models.
+
+use std::sync::{
+    LazyLock, Mutex, MutexGuard, OnceLock,
+    atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::Result;
+use opencv::{
+    core::{
+        BORDER_CONSTANT, CMP_EQ, CMP_GT, CV_8U, CV_32FC3, CV_32S, Mat, MatExprTraitConst, MatTrait,
+        MatTraitConst, MatTraitConstManual, ModifyInplace, Point, Range, Rect, Scalar, Size,
+        Vector, add, bitwise_and_def, compare, copy_make_border, divide2_def, find_non_zero,
+        min_max_loc, no_array, subtract_def, transpose_nd,
+    },
+    dnn::{
+        ModelTrait, TextRecognitionModel, TextRecognitionModelTrait,
+        TextRecognitionModelTraitConst, read_net_from_onnx_buffer,
+    },
+    imgproc::{
+        CC_STAT_AREA, CC_STAT_HEIGHT, CC_STAT_LEFT, CC_STAT_TOP, CC_STAT_WIDTH, COLOR_BGR2RGB,
+        INTER_CUBIC, INTER_LINEAR, MORPH_RECT, THRESH_BINARY, connected_components_with_stats,
+        cvt_color_def, dilate_def, get_structuring_element_def, min_area_rect, resize, threshold,
+    },
+};
+use ort::{
+    execution_providers::CUDAExecutionProvider,
+    session::{Session, SessionInputValue, SessionOutputs},
+    value::TensorRef,
+};
+
+/// Extracts texts from the non-preprocessed `Mat` and detected text bounding boxes.
+pub(super) fn extract_texts(mat: &impl MatTraitConst, bboxes: &[Rect]) -> Vec<String> {
+    static TEXT_RECOGNITION_MODEL: LazyLock<Mutex<TextRecognitionModel>> = LazyLock::new(|| {
+        let model = read_net_from_onnx_buffer(&Vector::from_slice(include_bytes!(env!(
+            "TEXT_RECOGNITION_MODEL"
+        ))))
+        .unwrap();
+        Mutex::new(
+            TextRecognitionModel::new(&model)
+                .and_then(|mut m| {
+                    m.set_input_params(
+                        1.0 / 127.5,
+                        Size::new(100, 32),
+                        Scalar::new(127.5, 127.5, 127.5, 0.0),
+                        false,
+                        false,
+                    )?;
+                    m.set_decode_type("CTC-greedy")?.set_vocabulary(
+                        &include_str!(env!("TEXT_RECOGNITION_ALPHABET"))
+                            .lines()
+                            .collect::<Vector<String>>(),
+                    )
+                })
+                .expect("build text recognition model successfully"),
+        )
+    });
+
+    let recognizier = TEXT_RECOGNITION_MODEL.lock().unwrap();
+    bboxes
+        .iter()
+        .copied()
+        .filter_map(|word| {
+            let mut mat = mat.roi(word).unwrap().clone_pointee();
+            unsafe {
+                mat.modify_inplace(|mat, mat_mut| {
+                    cvt_color_def(mat, mat_mut, COLOR_BGR2RGB).unwrap();
+                });
+            }
+            recognizier.recognize(&mat).ok()
+        })
+        .collect()
+}
+
+/// Extracts text bounding boxes from the preprocessed [`Mat`].
+///
+/// This function is adapted from
+/// https://github.com/clovaai/CRAFT-pytorch/blob/master/craft_utils.py#L19 with minor changes
+pub(super) fn extract_text_bboxes(
+    mat_in: &impl MatTraitConst,
+    w_ratio: f32,
+    h_ratio: f32,
+    x_offset: i32,
+    y_offset: i32,
+) -> Vec<Rect> {
+    const TEXT_SCORE_THRESHOLD: f64 = 0.7;
+    const LINK_SCORE_THRESHOLD: f64 = 0.4;
+    static TEXT_DETECTION_MODEL: ModelSession = ModelSession::new(
+        include_bytes!(env!("TEXT_DETECTION_MODEL")),
+        "build text detection session normally",
+    );
+
+    let mut model = TEXT_DETECTION_MODEL.lock();
+    let result = model.run([to_input_value(mat_in)]).unwrap();
+    let mat = from_output_value(&result);
+    let text_score = mat
+        .ranges(&Vector::from_iter([
+            Range::all().unwrap(),
+            Range::all().unwrap(),
+            Range::new(0, 1).unwrap(),
+        ]))
+        .unwrap()
+        .clone_pointee();
+    // remove last channel (not sure what other way to do it without clone_pointee first)
+    let text_score = text_score
+        .reshape_nd(1, &text_score.mat_size()[..2])
+        .unwrap();
+
+    let mut text_low_score = Mat::default();
+    threshold(
+        &text_score,
+        &mut text_low_score,
+        LINK_SCORE_THRESHOLD,
+        1.0,
+        THRESH_BINARY,
+    )
+    .unwrap();
+
+    let mut link_score = mat
+        .ranges(&Vector::from_iter([
+            Range::all().unwrap(),
+            Range::all().unwrap(),
+            Range::new(1, 2).unwrap(),
+        ]))
+        .unwrap()
+        .clone_pointee();
+    // remove last channel (not sure what other way to do it without clone_pointee first)
+    let link_score_size = link_score.mat_size().to_vec();
+    let mut link_score = link_score.reshape_nd_mut(1, &link_score_size[..2]).unwrap();
+    // SAFETY: can be modified in place
+    unsafe {
+        link_score.modify_inplace(|mat, mat_mut| {
+            threshold(mat, mat_mut, LINK_SCORE_THRESHOLD, 1.0, THRESH_BINARY).unwrap();
+        });
+    }
+
+    let mut combined_score = Mat::default();
+    let mut gt_one_mask = Mat::default();
+    add(
+        &text_low_score,
+        &link_score,
+        &mut combined_score,
+        &no_array(),
+        CV_8U,
+    )
+    .unwrap();
+    compare(&combined_score, &Scalar::all(1.0), &mut gt_one_mask, CMP_GT).unwrap();
+    combined_score
+        .set_to(&Scalar::all(1.0), &gt_one_mask)
+        .unwrap();
+
+    let mut bboxes = Vec::<Rect>::new();
+    let mut labels = Mat::default();
+    let mut stats = Mat::default();
+    let labels_count = connected_components_with_stats(
+        &combined_score,
+        &mut labels,
+        &mut stats,
+        &mut Mat::default(),
+        4,
+        CV_32S,
+    )
+    .unwrap();
+    for i in 1..labels_count {
+        let area = *stats.at_2d::<i32>(i, CC_STAT_AREA).unwrap();
+        if area < 10 {
+            continue;
+        }
+        let mut mask = Mat::default();
+        let mut max_score = 0.0f64;
+        compare(&labels, &Scalar::all(i as f64), &mut mask, CMP_EQ).unwrap();
+        min_max_loc(&text_score, None, Some(&mut max_score), None, None, &mask).unwrap();
+        if max_score < TEXT_SCORE_THRESHOLD {
+            continue;
+        }
+
+        let shape = mask.size().unwrap();
+        // SAFETY: The position (row, col) is guaranteed by OpenCV
+        let x = unsafe { *stats.at_2d_unchecked::<i32>(i, CC_STAT_LEFT).unwrap() };
+        let y = unsafe { *stats.at_2d_unchecked::<i32>(i, CC_STAT_TOP).unwrap() };
+        let w = unsafe { *stats.at_2d_unchecked::<i32>(i, CC_STAT_WIDTH).unwrap() };
+        let h = unsafe { *stats.at_2d_unchecked::<i32>(i, CC_STAT_HEIGHT).unwrap() };
+        let size = area as f64 * w.min(h) as f64 / (w as f64 * h as f64);
+        let size = ((size).sqrt() * 2.0) as i32;
+        let sx = (x - size + 1).max(0);
+        let sy = (y - size + 1).max(0);
+        let ex = (x + w + size + 1).min(shape.width);
+        let ey = (y + h + size + 1).min(shape.height);
+        let kernel =
+            get_structuring_element_def(MORPH_RECT, Size::new(size + 1, size + 1)).unwrap();
+
+        let mut link_mask = Mat::default();
+        let mut text_mask = Mat::default();
+        let mut and_mask = Mat::default();
+        let mut seg_map = Mat::zeros(shape.height, shape.width, CV_8U)
+            .unwrap()
+            .to_mat()
+            .unwrap();
+        compare(&link_score, &Scalar::all(1.0), &mut link_mask, CMP_EQ).unwrap();
+        compare(&text_score, &Scalar::all(0.0), &mut text_mask, CMP_EQ).unwrap();
+        bitwise_and_def(&link_mask, &text_mask, &mut and_mask).unwrap();
+        seg_map.set_to(&Scalar::all(255.0), &mask).unwrap();
+        seg_map.set_to(&Scalar::all(0.0), &and_mask).unwrap();
+
+        let mut seg_contours = Vector::<Point>::new();
+        let mut seg_roi = seg_map
+            .roi_mut(Rect::from_points(Point::new(sx, sy), Point::new(ex, ey)))
+            .unwrap();
+        // SAFETY: all of the functions below can be called in place.
+        unsafe {
+            seg_roi.modify_inplace(|mat, mat_mut| {
+                dilate_def(mat, mat_mut, &kernel).unwrap();
+                mat.copy_to(mat_mut).unwrap();
+            });
+        }
+        find_non_zero(&seg_map, &mut seg_contours).unwrap();
+
+        let contour = min_area_rect(&seg_contours)
+            .unwrap()
+            .bounding_rect2f()
+            .unwrap();
+        let tl = contour.tl();
+        let tl = Point::new(
+            (tl.x * w_ratio * 2.0) as i32 + x_offset,
+            (tl.y * h_ratio * 2.0) as i32 + y_offset,
+        );
+        let br = contour.br();
+        let br = Point::new(
+            (br.x * w_ratio * 2.0) as i32 + x_offset,
+            (br.y * h_ratio * 2.0) as i32 + y_offset,
+        );
+        bboxes.push(Rect::from_points(tl, br));
+    }
+    bboxes
+}
+
+#[inline]
+pub(super) fn remap_from_yolo(
+    pred: &[f32],
+    size: Size,
+    w_ratio: f32,
+    h_ratio: f32,
+    left: i32,
+    top: i32,
+) -> Rect {
+    let tl_x = ((pred[0] - left as f32) / w_ratio)
+        .max(0.0)
+        .min(size.width as f32);
+    let tl_y = ((pred[1] - top as f32) / h_ratio)
+        .max(0.0)
+        .min(size.height as f32);
+    let br_x = ((pred[2] - left as f32) / w_ratio)
+        .max(0.0)
+        .min(size.width as f32);
+    let br_y = ((pred[3] - top as f32) / h_ratio)
+        .max(0.0)
+        .min(size.height as f32);
+    Rect::from_points(
+        Point::new(tl_x as i32, tl_y as i32),
+        Point::new(br_x as i32, br_y as i32),
+    )
+}
+
+/// Preprocesses a BGR `Mat` image to a normalized and resized RGB `Mat` image with type `f32`
+/// for YOLO detection.
+///
+/// Returns a triplet of `(Mat, width_ratio, height_ratio, left, top)`
+#[inline]
+pub(super) fn preprocess_for_yolo(mat: &impl MatTraitConst) -> (Mat, f32, f32, i32, i32) {
+    // https://github.com/ultralytics/ultralytics/blob/main/ultralytics/data/augment.py
+    let mut mat = mat.try_clone().unwrap();
+
+    let size = mat.size().unwrap();
+    let (w_ratio, h_ratio) = (640.0 / size.width as f32, 640.0 / size.height as f32);
+    let min_ratio = w_ratio.min(h_ratio);
+
+    let w = (size.width as f32 * min_ratio).round();
+    let h = (size.height as f32 * min_ratio).round();
+
+    let pad_w = (640.0 - w) / 2.0;
+    let pad_h = (640.0 - h) / 2.0;
+
+    let top = (pad_h - 0.1).round() as i32;
+    let bottom = (pad_h + 0.1).round() as i32;
+    let left = (pad_w - 0.1).round() as i32;
+    let right = (pad_w + 0.1).round() as i32;
+
+    // SAFETY: all of the functions below can be called in place.
+    unsafe {
+        mat.modify_inplace(|mat, mat_mut| {
+            cvt_color_def(mat, mat_mut, COLOR_BGR2RGB).unwrap();
+            resize(
+                mat,
+                mat_mut,
+                Size::new(w as i32, h as i32),
+                0.0,
+                0.0,
+                INTER_LINEAR,
+            )
+            .unwrap();
+            copy_make_border(
+                mat,
+                mat_mut,
+                top,
+                bottom,
+                left,
+                right,
+                BORDER_CONSTANT,
+                Scalar::all(114.0),
+            )
+            .unwrap();
+            mat.convert_to(mat_mut, CV_32FC3, 1.0 / 255.0, 0.0).unwrap();
+        });
+    }
+    (mat, min_ratio, min_ratio, left, top)
+}
+
+/// Preprocesses a BGR `Mat` image to a normalized and resized RGB `Mat` image with type `f32`
+/// for text bounding boxes detection.
+///
+/// The preprocess is adapted from: https://github.com/clovaai/CRAFT-pytorch/blob/master/imgproc.py
+///
+/// Returns a `(Mat, width_ratio, height_ratio)`.
+#[inline]
+pub(super) fn preprocess_for_text_bboxes(mat: &impl MatTraitConst) -> (Mat, f32, f32) {
+    let mut mat = mat.try_clone().unwrap();
+    let size = mat.size().unwrap();
+    let size_w = size.width as f32;
+    let size_h = size.height as f32;
+    let size_max = size_w.max(size_h);
+    let resize_size = 5.0 * size_max;
+    let resize_ratio = resize_size / size_max;
+
+    let resize_w = (resize_ratio * size_w) as i32;
+    let resize_w = (resize_w + 31) & !31; // rounds to multiple of 32
+    let resize_w_ratio = size_w / resize_w as f32;
+
+    let resize_h = (resize_ratio * size_h) as i32;
+    let resize_h = (resize_h + 31) & !31;
+    let resize_h_ratio = size_h / resize_h as f32;
+    // SAFETY: all of the below functions can be called in place
+    unsafe {
+        mat.modify_inplace(|mat, mat_mut| {
+            cvt_color_def(mat, mat_mut, COLOR_BGR2RGB).unwrap();
+            resize(
+                mat,
+                mat_mut,
+                Size::new(resize_w, resize_h),
+                0.0,
+                0.0,
+                INTER_CUBIC,
+            )
+            .unwrap();
+            mat.convert_to(mat_mut, CV_32FC3, 1.0, 0.0).unwrap();
+            // these values are pre-multiplied from the above link in normalizeMeanVariance
+            subtract_def(mat, &Scalar::new(123.675, 116.28, 103.53, 0.0), mat_mut).unwrap();
+            divide2_def(&mat, &Scalar::new(58.395, 57.12, 57.375, 1.0), mat_mut).unwrap();
+        });
+    }
+    (mat, resize_w_ratio, resize_h_ratio)
+}
+
+/// Extracts a borrowed `Mat` from `SessionOutputs`.
+///
+/// The returned `Mat` has shape `[..dims]` with batch size (1) removed.
+#[inline]
+pub(super) fn from_output_value(result: &SessionOutputs) -> Mat {
+    let (dims, outputs) = result["output0"].try_extract_tensor::<f32>().unwrap();
+    let dims = dims.iter().map(|&dim| dim as i32).collect::<Vec<i32>>();
+    let mat = Mat::new_nd_with_data(dims.as_slice(), outputs).unwrap();
+    let mat = mat.reshape_nd(1, &dims.as_slice()[1..]).unwrap();
+    mat.clone_pointee()
+}
+
+/// Converts a continuous, normalized `f32` RGB `Mat` image to `SessionInputValue`.
+///
+/// The input `Mat` is assumed to be continuous, normalized RGB `f32` data type and
+/// will panic if not. The `Mat` is reshaped to single channel, tranposed to `[1, 3, H, W]` and
+/// converted to `SessionInputValue`.
+#[inline]
+pub(super) fn to_input_value(mat: &impl MatTraitConst) -> SessionInputValue<'_> {
+    let mat = mat.reshape_nd(1, &[1, mat.rows(), mat.cols(), 3]).unwrap();
+    let mut mat_t = Mat::default();
+    transpose_nd(&mat, &Vector::from_slice(&[0, 3, 1, 2]), &mut mat_t).unwrap();
+    let shape = mat_t.mat_size();
+    let input = (shape.to_vec(), mat_t.data_typed::<f32>().unwrap());
+    let tensor = TensorRef::from_array_view(input).unwrap();
+    SessionInputValue::Owned(tensor.clone().into_dyn())
+}
+
+/// Whether GPU inference is allowed.
+///
+/// This only has effect when the `gpu` feature is enabled.
+static GPU_INFERENCE_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Sets whether GPU inference is allowed for subsequent model inferences.
+///
+/// Disabling falls back to a separate CPU session that is built on first use.
+pub fn set_gpu_inference_enabled(enabled: bool) {
+    GPU_INFERENCE_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// An ONNX model with lazily built GPU and CPU sessions.
+pub(super) struct ModelSession {
+    model: &'static [u8],
+    /// The expectation message when building a session fails.
+    expect: &'static str,
+    gpu: OnceLock<Mutex<Session>>,
+    cpu: OnceLock<Mutex<Session>>,
+}
+
+impl ModelSession {
+    pub(super) const fn new(model: &'static [u8], expect: &'static str) -> Self {
+        Self {
+            model,
+            expect,
+            gpu: OnceLock::new(),
+            cpu: OnceLock::new(),
+        }
+    }
+
+    /// Locks the GPU session if GPU inference is allowed or the CPU session otherwise.
+    pub(super) fn lock(&self) -> MutexGuard<'_, Session> {
+        let use_gpu = cfg!(feature = "gpu") && GPU_INFERENCE_ENABLED.load(Ordering::Relaxed);
+        let session = if use_gpu { &self.gpu } else { &self.cpu };
+
+        session
+            .get_or_init(|| Mutex::new(build_session(self.model, use_gpu).expect(self.expect)))
+            .lock()
+            .unwrap()
+    }
+}
+
+#[inline]
+fn build_session(model: &[u8], use_gpu: bool) -> Result<Session> {
+    // TODO: ort supports fallback to CPU if GPU is not found. Check if missing GPU-related
+    // TODO: onnxruntime dlls affect this.
+    if use_gpu {
+        Ok(Session::builder()?
+            .with_execution_providers([CUDAExecutionProvider::default().build()])?
+            .commit_from_memory(model)?)
+    } else {
+        Ok(Session::builder()?.commit_from_memory(model)?)
+    }
+}
@@ -0,0 +1,345 @@
+//! Detections of the minimap and the objects inside it.
+
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow, bail};
+use log::debug;
+use opencv::{
+    core::{
+        MatTraitConst, MatTraitConstManual, ModifyInplace, Point, Rect, Size, ToInputArray, Vec3b,
+        Vector, no_array,
+    },
+    imgcodecs::{IMREAD_COLOR, IMREAD_GRAYSCALE},
+    imgproc::{
+        CHAIN_APPROX_SIMPLE, MORPH_RECT, RETR_EXTERNAL, THRESH_BINARY, bounding_rect, dilate_def,
+        find_contours_def, get_structuring_element_def, threshold,
+    },
+};
+
+use super::{
+    OtherPlayerKind,
+    engine::{
+        Template, detect_template_multiple, detect_template_single, expand_bbox, iou, scale_pixels,
+        template, to_grayscale,
+    },
+    inference::{
+        ModelSession, from_output_value, preprocess_for_yolo, remap_from_yolo, to_input_value,
+    },
+};
+
+pub(super) fn detect_minimap(bgr: &impl MatTraitConst, border_threshold: u8) -> Result<Rect> {
+    static MINIMAP_MODEL: ModelSession = ModelSession::new(
+        include_bytes!(env!("MINIMAP_MODEL")),
+        "build minimap detection session successfully",
+    );
+
+    #[derive(Debug)]
+    enum Border {
+        Top,
+        Bottom,
+        Left,
+        Right,
+    }
+
+    fn scan_border(minimap: &impl MatTraitConst, border: Border, border_threshold: u8) -> i32 {
+        let mut counts = HashMap::<u32, u32>::new();
+        let is_pixel_above_threshold =
+            |pixel: &[u8; 3]| pixel.iter().all(|&v| v >= border_threshold);
+        let (primary_len, secondary_len, flip_primary) = match border {
+            Border::Top => (minimap.rows(), minimap.cols(), false),
+            Border::Bottom => (minimap.rows(), minimap.cols(), true),
+            Border::Left => (minimap.cols(), minimap.rows(), false),
+            Border::Right => (minimap.cols(), minimap.rows(), true),
+        };
+
+        let secondary_start = ((secondary_len - 1) as f32 * 0.1) as i32;
+        let secondary_end = secondary_len - secondary_start;
+
+        for secondary in secondary_start..secondary_end {
+            let mut count = 0;
+
+            for primary in 0..primary_len {
+                let flipped_primary = if flip_primary {
+                    primary_len - primary - 1
+                } else {
+                    primary
+                };
+                let (row, col) = match border {
+                    Border::Top | Border::Bottom => (flipped_primary, secondary),
+                    Border::Left | Border::Right => (secondary, flipped_primary),
+                };
+
+                let pixel = minimap.at_2d::<Vec3b>(row, col).unwrap();
+                if is_pixel_above_threshold(pixel) {
+                    count += 1;
+                } else {
+                    break;
+                }
+            }
+
+            *counts.entry(count).or_insert(0) += 1;
+        }
+
+        counts
+            .into_iter()
+            .max_by_key(|e| e.1)
+            .map(|e| e.0)
+            .unwrap_or_default() as i32
+    }
+
+    let size = bgr.size().unwrap();
+    let (mat_in, w_ratio, h_ratio, left, top) = preprocess_for_yolo(bgr);
+    let mut model = MINIMAP_MODEL.lock();
+    let result = model.run([to_input_value(&mat_in)]).unwrap();
+    let mat_out = from_output_value(&result);
+    let pred = (0..mat_out.rows())
+        // SAFETY: 0..result.rows() is within Mat bounds
+        .map(|i| unsafe { mat_out.at_row_unchecked::<f32>(i).unwrap() })
+        .max_by(|&a, &b| {
+            // a and b have shapes [bbox(4) + class(1)]
+            a[4].total_cmp(&b[4])
+        })
+        .ok_or(anyhow!("minimap detection failed"))?;
+
+    debug!(target: "minimap", "yolo detection: {pred:?}");
+
+    // Extract the thresholded minimap
+    let minimap_bbox = remap_from_yolo(pred, size, w_ratio, h_ratio, left, top);
+    if minimap_bbox.empty() {
+        bail!("minimap is empty");
+    }
+
+    let mut minimap_thresh = to_grayscale(&bgr.roi(minimap_bbox).unwrap(), true);
+    unsafe {
+        // SAFETY: threshold can be called in place.
+        minimap_thresh.modify_inplace(|mat, mat_mut| {
+            threshold(mat, mat_mut, border_threshold as f64, 255.0, THRESH_BINARY).unwrap()
+        });
+    }
+
+    // Find the contours with largest area
+    let mut contours = Vector::<Vector<Point>>::new();
+    find_contours_def(
+        &minimap_thresh,
+        &mut contours,
+        RETR_EXTERNAL,
+        CHAIN_APPROX_SIMPLE,
+    )
+    .unwrap();
+    let contour_bbox = contours
+        .into_iter()
+        .map(|contour| bounding_rect(&contour).unwrap())
+        .max_by_key(|bbox| bbox.area())
+        .ok_or(anyhow!("minimap contours is empty"))?
+        + minimap_bbox.tl();
+    if iou(contour_bbox, minimap_bbox) < 0.8 {
+        bail!("wrong minimap likely caused by detection during map switching")
+    }
+
+    // Scan the 4 borders and crop
+    let minimap = bgr.roi(contour_bbox).unwrap();
+    let top = scan_border(&minimap, Border::Top, border_threshold);
+    let bottom = scan_border(&minimap, Border::Bottom, border_threshold);
+    // Left side gets a discount because it is darker than the other three borders
+    let left = scan_border(&minimap, Border::Left, border_threshold.saturating_sub(10));
+    let right = scan_border(&minimap, Border::Right, border_threshold);
+
+    debug!(target: "minimap", "crop white border left {left}, top {top}, bottom {bottom}, right {right}");
+
+    let bbox = Rect::new(
+        left,
+        top,
+        minimap.cols() - right - left,
+        minimap.rows() - bottom - top,
+    );
+    debug!(target: "minimap", "bbox {bbox:?}");
+
+    Ok(bbox + contour_bbox.tl())
+}
+
+pub(super) fn detect_minimap_name(
+    grayscale: &impl MatTraitConst,
+    minimap: Rect,
+    scale: f64,
+) -> Result<Rect> {
+    /// Top offset backward from the `y` of `minimap`.
+    const TOP_OFFSET: i32 = 24;
+    /// Left offset from the `x` of `minimap`.
+    const LEFT_OFFSET: i32 = 36;
+    /// The height of the name region.
+    const NAME_HEIGHT: i32 = 20;
+
+    let x = minimap.x + scale_pixels(LEFT_OFFSET, scale);
+    let y = minimap.y - scale_pixels(TOP_OFFSET, scale);
+    let kernel = get_structuring_element_def(MORPH_RECT, Size::new(5, 5)).unwrap();
+    let name_bbox = Rect::new(
+        x,
+        y,
+        minimap.x + minimap.width - x,
+        scale_pixels(NAME_HEIGHT, scale),
+    );
+    let mut name = grayscale.roi(name_bbox)?.clone_pointee();
+    unsafe {
+        name.modify_inplace(|mat, mat_mut| {
+            threshold(mat, mat_mut, 200.0, 255.0, THRESH_BINARY).unwrap();
+            dilate_def(mat, mat_mut, &kernel).unwrap();
+        });
+    }
+
+    let mut contours = Vector::<Vector<Point>>::new();
+    find_contours_def(&name, &mut contours, RETR_EXTERNAL, CHAIN_APPROX_SIMPLE).unwrap();
+    if contours.is_empty() {
+        bail!("cannot find the minimap name contours")
+    }
+    let contour_bbox = contours
+        .into_iter()
+        .map(|contour| bounding_rect(&contour).unwrap())
+        .reduce(|first, second| first | second)
+        .ok_or(anyhow!("minimap name contours is empty"))?;
+    let name_bbox = contour_bbox + name_bbox.tl();
+
+    Ok(name_bbox)
+}
+
+/// The number of pixels the minimap and its name are expanded by before matching a snapshot.
+const MINIMAP_MATCH_EXPAND_SIZE: i32 = 4;
+
+pub(super) fn detect_minimap_match<T: ToInputArray + MatTraitConst>(
+    bgra: &impl MatTraitConst,
+    grayscale: &impl MatTraitConst,
+    minimap_snapshot: &T,
+    minimap_snapshot_grayscale: bool,
+    minimap_name_snapshot: &T,
+    minimap_bbox: Rect,
+    minimap_name_bbox: Rect,
+    scale: f64,
+) -> Result<f64> {
+    let name_score =
+        detect_minimap_name_match(grayscale, minimap_name_snapshot, minimap_name_bbox, scale)?;
+
+    let minimap_bbox = expand_bbox(
+        Some(bgra.size().expect("size available")),
+        minimap_bbox,
+        scale_pixels(MINIMAP_MATCH_EXPAND_SIZE, scale),
+    );
+    let minimap = if minimap_snapshot_grayscale {
+        to_grayscale(&bgra.roi(minimap_bbox)?, false)
+    } else {
+        bgra.roi(minimap_bbox)?.clone_pointee()
+    };
+
+    let minimap_score = detect_template_single(
+        &minimap,
+        minimap_snapshot,
+        no_array(),
+        Point::default(),
+        0.6,
+    )
+    .map(|(_, score)| score)?;
+
+    Ok((name_score + minimap_score) / 2.0)
+}
+
+pub(super) fn detect_minimap_name_match<T: ToInputArray + MatTraitConst>(
+    grayscale: &impl MatTraitConst,
+    minimap_name_snapshot: &T,
+    minimap_name_bbox: Rect,
+    scale: f64,
+) -> Result<f64> {
+    let minimap_name_bbox = expand_bbox(
+        Some(grayscale.size().expect("size available")),
+        minimap_name_bbox,
+        scale_pixels(MINIMAP_MATCH_EXPAND_SIZE, scale),
+    );
+    let minimap_name = grayscale.roi(minimap_name_bbox)?;
+    let snapshot_size = minimap_name_snapshot.size()?;
+    // A longer name cannot be the same map
+    if snapshot_size.width > minimap_name_bbox.width
+        || snapshot_size.height > minimap_name_bbox.height
+    {
+        bail!("minimap name is smaller than the snapshot");
+    }
+
+    detect_template_single(
+        &minimap_name,
+        minimap_name_snapshot,
+        no_array(),
+        Point::default(),
+        0.8,
+    )
+    .map(|(_, score)| score)
+}
+
+pub(super) fn detect_minimap_portals<T: MatTraitConst + ToInputArray>(
+    minimap_bgr: T,
+    scale: f64,
+) -> Vec<Rect> {
+    static TEMPLATE: Template = template!("PORTAL_TEMPLATE", IMREAD_COLOR);
+    const PORTAL_EXPAND_SIZE: i32 = 5;
+
+    detect_template_multiple(
+        &minimap_bgr,
+        &*TEMPLATE.get_scaled(scale),
+        no_array(),
+        Point::default(),
+        16,
+        0.7,
+    )
+    .into_iter()
+    .filter_map(|result| result.ok())
+    .map(|(bbox, _)| {
+        expand_bbox(
+            Some(minimap_bgr.size().expect("size available")),
+            bbox,
+            PORTAL_EXPAND_SIZE,
+        )
+    })
+    .collect::<Vec<_>>()
+}
+
+pub(super) fn detect_minimap_rune(minimap_bgr: &impl ToInputArray, scale: f64) -> Result<Rect> {
+    static TEMPLATE: Template = template!("RUNE_TEMPLATE", IMREAD_COLOR);
+    static TEMPLATE_MASK: Template = template!("RUNE_MASK_TEMPLATE", IMREAD_GRAYSCALE);
+
+    // Expands by 2 pixels to preserve previous position calculation. Previous template is 11x11
+    // while the current template is 9x9.
+    detect_template_single(
+        minimap_bgr,
+        &*TEMPLATE.get_scaled(scale),
+        &*TEMPLATE_MASK.get_scaled(scale),
+        Point::default(),
+        0.75,
+    )
+    .map(|(bbox, _)| expand_bbox(None, bbox, 1))
+}
+
+pub(super) fn detect_player_kind_count(
+    minimap_bgr: &impl ToInputArray,
+    kind: OtherPlayerKind,
+    scale: f64,
+) -> usize {
+    const MAX_PLAYERS_COUNT: usize = 16;
+
+    static STRANGER_TEMPLATE: Template = template!("PLAYER_STRANGER_TEMPLATE", IMREAD_COLOR);
+    static GUILDIE_TEMPLATE: Template = template!("PLAYER_GUILDIE_TEMPLATE", IMREAD_COLOR);
+    static FRIEND_TEMPLATE: Template = template!("PLAYER_FRIEND_TEMPLATE", IMREAD_COLOR);
+
+    let template = match kind {
+        OtherPlayerKind::Stranger => &STRANGER_TEMPLATE,
+        OtherPlayerKind::Guildie => &GUILDIE_TEMPLATE,
+        OtherPlayerKind::Friend => &FRIEND_TEMPLATE,
+    }
+    .get_scaled(scale);
+
+    detect_template_multiple(
+        minimap_bgr,
+        &*template,
+        no_array(),
+        Point::default(),
+        MAX_PLAYERS_COUNT,
+        0.85,
+    )
+    .into_iter()
+    .filter(|result| result.is_ok())
+    .count()
+}
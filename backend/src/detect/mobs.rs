@@ -0,0 +1,176 @@
+//! Detections of mobs and elite bosses.
+
+use anyhow::{Result, anyhow};
+use opencv::{
+    core::{MatTraitConst, MatTraitConstManual, Point, Rect, Size},
+    imgcodecs::IMREAD_GRAYSCALE,
+};
+
+use super::{
+    engine::{Template, detect_template, template},
+    inference::{
+        ModelSession, from_output_value, preprocess_for_yolo, remap_from_yolo, to_input_value,
+    },
+};
+
+/// Approximates the mob coordinate on screen to mob coordinate on minimap.
+///
+/// This function tries to approximate the delta (dx, dy) that the player needs to move
+/// in relative to the minimap coordinate in order to reach the mob. Returns the mob
+/// coordinate on the minimap by adding the delta to the player position.
+///
+/// Note: It is not that accurate but that is that and this is this. Hey it seems better than
+/// the previous alchemy.
+#[inline]
+fn to_minimap_coordinate(
+    mob_bbox: Rect,
+    minimap_bbox: Rect,
+    mobbing_bound: Rect,
+    player: Point,
+    mat_size: Size,
+) -> Option<Point> {
+    // These numbers are for scaling dx/dy on the screen to dx/dy on the minimap.
+    // They are approximated in 1280x720 resolution by going from one point to another point
+    // from the middle of the screen with both points visible on screen before traveling. Take
+    // the distance traveled on the minimap and divide it by half of the resolution
+    // (e.g. tralveled minimap x / 640). Whether it is correct or not, time will tell.
+    const X_SCALE: f32 = 0.059_375;
+    const Y_SCALE: f32 = 0.036_111;
+
+    // The main idea is to calculate the offset of the detected mob from the middle of screen
+    // and use that distance as dx/dy to move the player. This assumes the player will
+    // most of the time be near or very close to the middle of the screen. This is already
+    // not accurate in the sense that the camera will have a bit of lag before
+    // it is centered again on the player. And when the player is near edges of the map,
+    // this function is just plain wrong. For better accuracy, detecting where the player is
+    // on the screen and use that as the basis is required.
+    let x_screen_mid = mat_size.width / 2;
+    let x_mob_mid = mob_bbox.x + mob_bbox.width / 2;
+    let x_screen_delta = x_screen_mid - x_mob_mid;
+    let x_minimap_delta = (x_screen_delta as f32 * X_SCALE) as i32;
+
+    // For dy, if the whole mob bounding box is above the screen mid point, then the
+    // box top edge is used to increase the dy distance as to help the player move up. The same
+    // goes for moving down. If the bounding box overlaps with the screen mid point, the box
+    // mid point is used as to to help the player stay in place.
+    let y_screen_mid = mat_size.height / 2;
+    let y_mob = if mob_bbox.y + mob_bbox.height < y_screen_mid {
+        mob_bbox.y
+    } else if mob_bbox.y > y_screen_mid {
+        mob_bbox.y + mob_bbox.height
+    } else {
+        mob_bbox.y + mob_bbox.height / 2
+    };
+    let y_screen_delta = y_screen_mid - y_mob;
+    let y_minimap_delta = (y_screen_delta as f32 * Y_SCALE) as i32;
+
+    let point_x = if x_minimap_delta > 0 {
+        (player.x - x_minimap_delta).max(0)
+    } else {
+        (player.x - x_minimap_delta).min(minimap_bbox.width)
+    };
+    let point_y = (player.y + y_minimap_delta).max(0).min(minimap_bbox.height);
+    // Minus the y by minimap height to make it relative to the minimap top edge
+    let point = Point::new(point_x, minimap_bbox.height - point_y);
+    if point.x < mobbing_bound.x
+        || point.x > mobbing_bound.x + mobbing_bound.width
+        || point.y < mobbing_bound.y
+        || point.y > mobbing_bound.y + mobbing_bound.height
+    {
+        None
+    } else {
+        Some(point)
+    }
+}
+
+pub(super) fn detect_mobs(
+    bgr: &impl MatTraitConst,
+    minimap: Rect,
+    bound: Rect,
+    player: Point,
+) -> Result<Vec<(Point, u32, f32)>> {
+    let size = bgr.size().unwrap();
+    let points = detect_mob_predictions(bgr)
+        .into_iter()
+        .filter_map(|(bbox, class, confidence)| {
+            to_minimap_coordinate(bbox, minimap, bound, player, size)
+                .map(|point| (point, class, confidence))
+        })
+        .collect::<Vec<_>>();
+    Ok(points)
+}
+
+pub(super) fn detect_elite_boss(
+    bgr: &impl MatTraitConst,
+    minimap: Rect,
+    player: Point,
+) -> Result<Point> {
+    let size = bgr.size().unwrap();
+    let bound = Rect::new(0, 0, minimap.width, minimap.height);
+
+    detect_mob_bboxes(bgr)
+        .into_iter()
+        .max_by_key(|bbox| bbox.area())
+        .and_then(|bbox| to_minimap_coordinate(bbox, minimap, bound, player, size))
+        .ok_or(anyhow!("elite boss not found"))
+}
+
+pub(super) fn detect_mob_bboxes(bgr: &impl MatTraitConst) -> Vec<Rect> {
+    detect_mob_predictions(bgr)
+        .into_iter()
+        .map(|(bbox, _, _)| bbox)
+        .collect()
+}
+
+/// Detects the bounding boxes of mobs on screen with their class index and confidence.
+///
+/// Single-class models always have the class index `0`.
+fn detect_mob_predictions(bgr: &impl MatTraitConst) -> Vec<(Rect, u32, f32)> {
+    static MOB_MODEL: ModelSession = ModelSession::new(
+        include_bytes!(env!("MOB_MODEL")),
+        "build mob detection session successfully",
+    );
+
+    let size = bgr.size().unwrap();
+    let (mat_in, w_ratio, h_ratio, left, top) = preprocess_for_yolo(bgr);
+    let mut model = MOB_MODEL.lock();
+    let result = model.run([to_input_value(&mat_in)]).unwrap();
+    let result = from_output_value(&result);
+    // SAFETY: 0..result.rows() is within Mat bounds
+    (0..result.rows())
+        .map(|i| unsafe { result.at_row_unchecked::<f32>(i).unwrap() })
+        .filter(|pred| pred[4] >= 0.5)
+        .map(|pred| {
+            (
+                remap_from_yolo(pred, size, w_ratio, h_ratio, left, top),
+                pred[5] as u32,
+                pred[4],
+            )
+        })
+        .collect()
+}
+
+pub(super) fn detect_elite_boss_bar(grayscale: &impl MatTraitConst, scale: f64) -> bool {
+    static TEMPLATE_1: Template = template!("ELITE_BOSS_BAR_1_TEMPLATE", IMREAD_GRAYSCALE);
+    static TEMPLATE_2: Template = template!("ELITE_BOSS_BAR_2_TEMPLATE", IMREAD_GRAYSCALE);
+
+    let size = grayscale.size().unwrap();
+    // crop to top part of the image for boss bar
+    let crop_y = size.height / 5;
+    let crop_bbox = Rect::new(0, 0, size.width, crop_y);
+    let boss_bar = grayscale.roi(crop_bbox).unwrap();
+    detect_template(
+        &boss_bar,
+        &*TEMPLATE_1.get_scaled(scale),
+        Point::default(),
+        0.9,
+    )
+    .is_ok()
+        || detect_template(
+            &boss_bar,
+            &*TEMPLATE_2.get_scaled(scale),
+            Point::default(),
+            0.9,
+        )
+        .is_ok()
+}
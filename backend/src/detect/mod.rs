@@ -0,0 +1,931 @@
+//! Detections of objects from the game frames.
+//!
+//! The detections are grouped by domain into submodules sharing the template matching engine in
+//! [`engine`] and the model inference in [`inference`]. [`Detector`] is the only entry point used
+//! by the rest of the crate.
+
+use std::{
+    fmt::Debug,
+    sync::{Arc, LazyLock},
+    time::Instant,
+};
+
+use anyhow::{Result, anyhow};
+use buffs::{detect_player_buff, to_buffs_region};
+use engine::{detect_template_single, to_bgr, to_grayscale};
+use familiar::{
+    detect_familiar_cards, detect_familiar_essence_depleted, detect_familiar_hover_level,
+    detect_familiar_level_button, detect_familiar_menu_opened, detect_familiar_save_button,
+    detect_familiar_scrollbar, detect_familiar_slot_is_free, detect_familiar_slots,
+};
+use hexa::{
+    detect_hexa_booster_button, detect_hexa_convert_button, detect_hexa_erda_conversion_button,
+    detect_hexa_max_button, detect_hexa_quick_menu, detect_hexa_sol_erda,
+};
+use minimap::{
+    detect_minimap, detect_minimap_match, detect_minimap_name, detect_minimap_name_match,
+    detect_minimap_portals, detect_minimap_rune, detect_player_kind_count,
+};
+use mobs::{detect_elite_boss, detect_elite_boss_bar, detect_mob_bboxes, detect_mobs};
+#[cfg(test)]
+use mockall::automock;
+use opencv::{
+    boxed_ref::BoxedRef,
+    core::{Mat, MatTraitConst, Point, Rect, no_array},
+};
+use player::{
+    HP_BAR_ANCHOR, detect_exp_bar, detect_player, detect_player_current_max_health_bars,
+    detect_player_health, detect_player_health_bar, detect_player_in_cash_shop,
+    detect_player_is_dead, detect_player_mana, detect_player_name_texts, detect_user_template,
+    extract_player_dot_template,
+};
+use popups::{
+    detect_admin_visible, detect_change_channel_menu_opened, detect_chat_menu_opened,
+    detect_esc_settings, detect_lie_detector, detect_lie_detector_preparing, detect_party_invite,
+    detect_player_request, detect_popup_confirm_button, detect_popup_ok_new_button,
+    detect_timer_visible, detect_transparent_shapes,
+};
+use quick_slots::{
+    detect_erda_shower, detect_quick_slots_hexa_booster, detect_quick_slots_potion_count,
+    detect_shop_potion, to_quick_slots_region,
+};
+use rune::{detect_rune_arrows, detect_rune_arrows_with_scores_regions};
+
+use crate::{
+    array::Array,
+    bridge::KeyKind,
+    mat::OwnedMat,
+    models::{Localization, TunableDetection},
+};
+
+mod buffs;
+mod engine;
+mod familiar;
+mod hexa;
+mod inference;
+mod minimap;
+mod mobs;
+mod player;
+mod popups;
+mod quick_slots;
+mod rune;
+
+pub use engine::{
+    TemplateScaleResolver, invalidate_localization_templates, localization_templates_memory,
+    reload_templates, to_base64_from_mat, to_cached_mat_from_base64_any_color,
+    to_mat_from_base64_any_color,
+};
+pub use familiar::{FAMILIAR_LEVEL_BUTTON_TEMPLATE, FAMILIAR_SAVE_BUTTON_TEMPLATE};
+pub use hexa::{
+    HEXA_BOOSTER_BUTTON_TEMPLATE, HEXA_CONVERT_BUTTON_TEMPLATE,
+    HEXA_ERDA_CONVERSION_BUTTON_TEMPLATE, HEXA_MAX_BUTTON_TEMPLATE,
+};
+pub use inference::set_gpu_inference_enabled;
+pub use player::CASH_SHOP_TEMPLATE;
+pub use popups::{
+    CHANGE_CHANNEL_TEMPLATE, POPUP_CANCEL_NEW_TEMPLATE, POPUP_CANCEL_OLD_TEMPLATE,
+    POPUP_CONFIRM_TEMPLATE, POPUP_END_CHAT_TEMPLATE, POPUP_NEXT_TEMPLATE, POPUP_OK_NEW_TEMPLATE,
+    POPUP_OK_OLD_TEMPLATE, POPUP_YES_TEMPLATE, TIMER_TEMPLATE,
+};
+
+const MAX_ARROWS: usize = 4;
+const MAX_SPIN_ARROWS: usize = 2; // PRAY
+
+/// The minimum score for a detected rune arrow to be used when solving the rune.
+pub const RUNE_ARROW_SCORE_THRESHOLD: f32 = 0.8;
+
+/// Struct for storing information about the spinning arrows.
+#[derive(Debug, Copy, Clone)]
+struct SpinArrow {
+    /// The centroid of the spinning arrow relative to the whole image.
+    centroid: Point,
+    /// The region of the spinning arrow relative to the whole image.
+    region: Rect,
+    /// The last arrow head relative to the centroid.
+    last_arrow_head: Option<Point>,
+    /// Final result of spinning arrow.
+    final_arrow: Option<KeyKind>,
+    #[cfg(debug_assertions)]
+    is_spin_testing: bool,
+}
+
+/// The current arrows detection/calibration state.
+#[derive(Debug)]
+pub enum ArrowsState {
+    Calibrating(ArrowsCalibrating),
+    Complete(ArrowsComplete),
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ArrowsComplete {
+    pub keys: [KeyKind; MAX_ARROWS],
+    pub bboxes: [Rect; MAX_ARROWS],
+    pub spins: [bool; MAX_ARROWS],
+}
+
+/// Struct representing arrows calibration in-progress
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ArrowsCalibrating {
+    spin_arrows: Option<Array<SpinArrow, MAX_SPIN_ARROWS>>,
+    spin_arrows_calibrate_count: u32,
+    spin_arrows_calibrated: bool,
+    #[cfg(debug_assertions)]
+    is_spin_testing: bool,
+}
+
+impl ArrowsCalibrating {
+    #[cfg(debug_assertions)]
+    pub fn enable_spin_test(&mut self) {
+        self.is_spin_testing = true;
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum OtherPlayerKind {
+    Guildie,
+    Stranger,
+    Friend,
+}
+
+#[derive(Debug)]
+pub enum FamiliarLevel {
+    Level5,
+    LevelOther,
+}
+
+#[derive(Debug)]
+pub enum FamiliarRank {
+    Rare,
+    Epic,
+}
+
+#[derive(Debug)]
+pub enum BuffKind {
+    Rune,
+    Familiar,
+    SayramElixir,
+    AureliaElixir,
+    ExpCouponX2,
+    ExpCouponX3,
+    ExpCouponX4,
+    BonusExpCoupon,
+    LegionWealth,
+    LegionLuck,
+    WealthAcquisitionPotion,
+    ExpAccumulationPotion,
+    SmallWealthAcquisitionPotion,
+    SmallExpAccumulationPotion,
+    ForTheGuild,
+    HardHitter,
+    ExtremeRedPotion,
+    ExtremeBluePotion,
+    ExtremeGreenPotion,
+    ExtremeGoldPotion,
+}
+
+/// A party invite popup with the inviter name and its buttons.
+#[derive(Debug)]
+pub struct PartyInvite {
+    pub inviter: String,
+    pub accept: Rect,
+    pub decline: Rect,
+}
+
+/// The kind of a [`PlayerRequest`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PlayerRequestKind {
+    Trade,
+    Duel,
+}
+
+/// A trade or duel request popup from another player with its decline button.
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerRequest {
+    pub kind: PlayerRequestKind,
+    pub decline: Rect,
+}
+
+/// The EXP percentage in the bottom bar and the player level.
+#[derive(Clone, Copy, Debug)]
+pub struct ExpBar {
+    /// The player level if it is readable above the HP bar.
+    pub level: Option<u32>,
+    pub percent: f32,
+}
+
+#[derive(Debug)]
+pub enum QuickSlotsHexaBooster {
+    Available,
+    Unavailable,
+}
+
+#[derive(Debug)]
+pub enum SolErda {
+    Full,
+    AtLeastOne,
+    Empty,
+}
+
+/// A trait for detecting objects from provided frame.
+#[cfg_attr(test, automock)]
+pub trait Detector: Debug + Send + Sync {
+    /// Gets the original [`OwnedMat`] as a [`BoxedRef`].
+    fn mat(&self) -> BoxedRef<'_, Mat>;
+
+    /// Gets the grayscale version.
+    fn grayscale(&self) -> &Mat;
+
+    /// Gets the instant the frame is captured.
+    ///
+    /// Results of detections running asynchronously can be compared against this to discard ones
+    /// from frames that are too old.
+    fn captured_at(&self) -> Instant;
+
+    /// Detects a list of mobs.
+    ///
+    /// Returns a list of mobs coordinate relative to minimap coordinate together with the
+    /// detection class index and confidence.
+    fn detect_mobs(
+        &self,
+        minimap: Rect,
+        bound: Rect,
+        player: Point,
+    ) -> Result<Vec<(Point, u32, f32)>>;
+
+    /// Detects the bounding boxes of mobs on screen.
+    fn detect_mob_bboxes(&self) -> Vec<Rect>;
+
+    /// Detects the elite boss approximated as the largest mob on screen.
+    ///
+    /// Returns the elite boss coordinate relative to minimap coordinate.
+    fn detect_elite_boss(&self, minimap: Rect, player: Point) -> Result<Point>;
+
+    /// Detects whether to press ESC for unstucking.
+    fn detect_esc_settings(&self) -> bool;
+
+    /// Detects the popup `Confirm` button.
+    fn detect_popup_confirm_button(&self) -> Result<Rect>;
+
+    /// Detects the new popup `OK` button.
+    fn detect_popup_ok_new_button(&self) -> Result<Rect>;
+
+    /// Detects whether there is an elite boss bar.
+    fn detect_elite_boss_bar(&self) -> bool;
+
+    /// Detects the minimap.
+    ///
+    /// The `border_threshold` determines the "whiteness" (grayscale value from 0..255) of
+    /// the minimap's white border.
+    fn detect_minimap(&self, border_threshold: u8) -> Result<Rect>;
+
+    /// Detects the minimap name rectangle.
+    fn detect_minimap_name(&self, minimap: Rect) -> Result<Rect>;
+
+    /// Detects whether the given `minimap_snapshot` and `minimap_name_snapshot` matches the one
+    /// cropped by `minimap_name_bbox` and `minimap_bbox` rectangles.
+    fn detect_minimap_match(
+        &self,
+        minimap_snapshot: &Mat,
+        minimap_snapshot_grayscale: bool,
+        minimap_name_snapshot: &Mat,
+        minimap_bbox: Rect,
+        minimap_name_bbox: Rect,
+    ) -> Result<f64>;
+
+    /// Detects whether the given `minimap_name_snapshot` matches the one cropped by
+    /// `minimap_name_bbox` rectangle.
+    fn detect_minimap_name_match(
+        &self,
+        minimap_name_snapshot: &Mat,
+        minimap_name_bbox: Rect,
+    ) -> Result<f64>;
+
+    /// Detects the portals from the given `minimap` rectangle.
+    ///
+    /// Returns `Rect` relative to `minimap` coordinate.
+    fn detect_minimap_portals(&self, minimap: Rect) -> Vec<Rect>;
+
+    /// Detects the rune from the given `minimap` rectangle.
+    ///
+    /// Returns `Rect` relative to `minimap` coordinate.
+    fn detect_minimap_rune(&self, minimap: Rect) -> Result<Rect>;
+
+    /// Detects the player in the provided `minimap` rectangle.
+    ///
+    /// Returns `Rect` relative to `minimap` coordinate.
+    fn detect_player(&self, minimap: Rect) -> Result<Rect>;
+
+    /// Detects the number of distinct players of `kind` in the minimap.
+    fn detect_player_kind_count(&self, minimap: Rect, kind: OtherPlayerKind) -> usize;
+
+    /// Detects whether the player is dead.
+    fn detect_player_is_dead(&self) -> bool;
+
+    /// Detects whether the player is in cash shop.
+    fn detect_player_in_cash_shop(&self) -> bool;
+
+    /// Detects the cash shop tab containing the daily free items.
+    fn detect_cash_shop_free_tab(&self) -> Result<Rect>;
+
+    /// Detects an unclaimed daily free item inside the cash shop.
+    fn detect_cash_shop_free_item(&self) -> Result<Rect>;
+
+    /// Detects the cash shop buy button of the currently selected item.
+    fn detect_cash_shop_buy_button(&self) -> Result<Rect>;
+
+    /// Detects the player health bar.
+    ///
+    /// This is the biggest red health bar below the name.
+    fn detect_player_health_bar(&self) -> Result<Rect>;
+
+    /// Detects the player current and max health bars.
+    ///
+    /// These are the two smaller bars extracted from `health_bar`.
+    fn detect_player_current_max_health_bars(&self, health_bar: Rect) -> Result<(Rect, Rect)>;
+
+    /// Detects the player current health and max health.
+    fn detect_player_health(&self, current_bar: Rect, max_bar: Rect) -> Result<(u32, u32)>;
+
+    /// Detects the player current mana and max mana.
+    ///
+    /// The MP bar is located right below `health_bar` and is split the same way as the HP bar.
+    fn detect_player_mana(&self, health_bar: Rect) -> Result<(u32, u32)>;
+
+    /// Detects the texts of the player level and name above `health_bar`.
+    ///
+    /// The name is not separated from the level, so all extracted texts are returned.
+    fn detect_player_name_texts(&self, health_bar: Rect) -> Result<Vec<String>>;
+
+    /// Detects the EXP percentage in the bottom bar and the player level above the HP bar.
+    fn detect_exp_bar(&self) -> Result<ExpBar>;
+
+    /// Detects whether the player has a buff specified by `kind`.
+    fn detect_player_buff(&self, kind: BuffKind) -> bool;
+
+    /// Detects arrows from the given RGBA `Mat` image.
+    ///
+    /// `calibrating` represents the previous calibrating state returned by
+    /// [`ArrowsState::Calibrating`]
+    fn detect_rune_arrows(&self, calibrating: ArrowsCalibrating) -> Result<ArrowsState>;
+
+    /// Detects all rune arrow candidates with their scores without calibrating.
+    ///
+    /// Returns a triple of `(Rect, KeyKind, f32)` sorted from left to right. Candidates with
+    /// scores lower than [`RUNE_ARROW_SCORE_THRESHOLD`] are not used when solving the rune.
+    fn detect_rune_arrows_with_scores(&self) -> Vec<(Rect, KeyKind, f32)>;
+
+    /// Detects the Erda Shower skill from the given BGRA `Mat` image.
+    fn detect_erda_shower(&self) -> Result<Rect>;
+
+    /// Detects `detection` with `threshold` in place of the tuned or default one.
+    ///
+    /// The returned bounding box is relative to the whole frame. `minimap` is required for
+    /// [`TunableDetection::Player`].
+    fn detect_tunable(
+        &self,
+        detection: TunableDetection,
+        threshold: f64,
+        minimap: Option<Rect>,
+    ) -> Result<Rect>;
+
+    /// Detects familiar menu save button.
+    fn detect_familiar_save_button(&self) -> Result<Rect>;
+
+    /// Detects familiar menu level button.
+    fn detect_familiar_level_button(&self) -> Result<Rect>;
+
+    /// Detects the familiar slots assuming the familiar menu opened.
+    ///
+    /// Returns a pair of `(Rect, bool)` with `bool` of `true` indicating the slot is free.
+    fn detect_familiar_slots(&self) -> Vec<(Rect, bool)>;
+
+    /// Detects whether the familiar slot is free.
+    fn detect_familiar_slot_is_free(&self, slot: Rect) -> bool;
+
+    /// Detects the currently mouse hovering familiar level.
+    fn detect_familiar_hover_level(&self) -> Result<FamiliarLevel>;
+
+    /// Detects all the familiar cards assuming the familiar menu opened.
+    fn detect_familiar_cards(&self) -> Vec<(Rect, FamiliarRank)>;
+
+    /// Detects familiar menu setup's tab scrollbar assuming familiar menu opened.
+    fn detect_familiar_scrollbar(&self) -> Result<Rect>;
+
+    /// Detects whether the familiar menu is opened.
+    fn detect_familiar_menu_opened(&self) -> bool;
+
+    /// Detects whether the familiar essence depleted assuming already buffed.
+    fn detect_familiar_essence_depleted(&self) -> bool;
+
+    /// Detects whether the change channel menu is opened.
+    fn detect_change_channel_menu_opened(&self) -> bool;
+
+    /// Detects whether the chat menu is opened.
+    fn detect_chat_menu_opened(&self) -> bool;
+
+    /// Detects whether the admin image is visible inside the currently opened popup/dialog.
+    fn detect_admin_visible(&self) -> bool;
+
+    /// Detects whether there is a timer (e.g. from using booster).
+    fn detect_timer_visible(&self) -> bool;
+
+    /// Detects the lie detector popup.
+    fn detect_lie_detector(&self) -> Result<Rect>;
+
+    fn detect_lie_detector_preparing(&self) -> bool;
+
+    /// Detects the party invite popup and extracts the inviter name.
+    fn detect_party_invite(&self) -> Result<PartyInvite>;
+
+    /// Detects a trade or duel request popup from another player.
+    fn detect_player_request(&self) -> Result<PlayerRequest>;
+
+    /// Detects the state for HEXA Booster in the quick slots.
+    fn detect_quick_slots_hexa_booster(&self) -> Result<QuickSlotsHexaBooster>;
+
+    /// Detects the item count of the potion in the quick slots.
+    fn detect_quick_slots_potion_count(&self) -> Result<u32>;
+
+    /// Detects the potion item in the NPC shop.
+    fn detect_shop_potion(&self) -> Result<Rect>;
+
+    /// Detects the HEXA icon in quick menu.
+    fn detect_hexa_quick_menu(&self) -> Result<Rect>;
+
+    /// Detects the `Erda conversion` button in HEXA matrix.
+    fn detect_hexa_erda_conversion_button(&self) -> Result<Rect>;
+
+    /// Detects the `HEXA Booster` button in `Erda conversion` menu.
+    fn detect_hexa_booster_button(&self) -> Result<Rect>;
+
+    /// Detects the `MAX` button in `Erda conversion` menu.
+    fn detect_hexa_max_button(&self) -> Result<Rect>;
+
+    /// Detects the `Convert` button in `Erda conversion` menu.
+    fn detect_hexa_convert_button(&self) -> Result<Rect>;
+
+    /// Detects the Sol Erda state from the tracker menu.
+    fn detect_hexa_sol_erda(&self) -> Result<SolErda>;
+
+    /// Detects the best match of `template` against the whole image.
+    ///
+    /// A single channel `template` is matched against the grayscale image and the BGR image
+    /// otherwise. Returns the match bounding box and score.
+    fn detect_template_match(&self, template: &Mat) -> Result<(Rect, f64)>;
+
+    /// Detects a list of transparent shapes during lie detector event.
+    ///
+    /// The returned [`Rect`]s have coordinates relative to `region`.
+    fn detect_transparent_shapes(&self, region: Rect) -> Vec<Rect>;
+
+    /// Extracts the player dot nearest to `position` as a BGR template.
+    ///
+    /// `position` is relative to `minimap` coordinate.
+    fn extract_player_dot_template(&self, minimap: Rect, position: Point) -> Result<Mat>;
+}
+
+type MatFn = Box<dyn FnOnce() -> Mat + Send>;
+
+/// A detector that lazily transform `Mat`.
+#[derive(Debug)]
+pub struct DefaultDetector {
+    bgra: Arc<OwnedMat>,
+    bgr: LazyLock<Mat, MatFn>,
+    grayscale: LazyLock<Mat, MatFn>,
+    localization: Arc<Localization>,
+    /// A known minimap bounding box returned in place of detecting one.
+    minimap: Option<Rect>,
+    /// The scale the compiled-in templates and pixel regions are resized by.
+    scale: f64,
+}
+
+impl DefaultDetector {
+    /// Creates a default implementation of [`Detector`] from the given BGRA `mat`.
+    pub fn new(mat: OwnedMat, localization: Arc<Localization>) -> Self {
+        let bgra = Arc::new(mat);
+
+        let cloned = bgra.clone();
+        let bgr = LazyLock::<Mat, MatFn>::new(Box::new(move || to_bgr(&cloned.as_mat())));
+
+        let cloned = bgra.clone();
+        let grayscale =
+            LazyLock::<Mat, MatFn>::new(Box::new(move || to_grayscale(&cloned.as_mat(), true)));
+
+        Self {
+            bgra,
+            bgr,
+            grayscale,
+            localization,
+            minimap: None,
+            scale: 1.0,
+        }
+    }
+
+    /// Uses the template scale resolved by `resolver` instead of `1.0`.
+    ///
+    /// The frame is also used by `resolver` to resolve the scale if it is not resolved.
+    pub fn with_template_scale(mut self, resolver: &TemplateScaleResolver) -> Self {
+        resolver.update(
+            self.bgra.clone(),
+            &HP_BAR_ANCHOR,
+            self.localization
+                .detection_threshold(TunableDetection::HealthBar),
+        );
+        self.scale = resolver.scale();
+        self
+    }
+
+    /// Uses `minimap` as the minimap bounding box instead of detecting it.
+    ///
+    /// This is used by the sandbox whose synthetic minimap cannot be detected by the model.
+    pub fn with_minimap(mut self, minimap: Rect) -> Self {
+        self.minimap = Some(minimap);
+        self
+    }
+
+    fn bgra(&self) -> BoxedRef<'_, Mat> {
+        self.bgra.as_mat()
+    }
+
+    fn bgr(&self) -> &Mat {
+        &self.bgr
+    }
+}
+
+impl Detector for DefaultDetector {
+    fn mat(&self) -> BoxedRef<'_, Mat> {
+        self.bgra()
+    }
+
+    fn grayscale(&self) -> &Mat {
+        &self.grayscale
+    }
+
+    fn captured_at(&self) -> Instant {
+        self.bgra.captured_at()
+    }
+
+    fn detect_mobs(
+        &self,
+        minimap: Rect,
+        bound: Rect,
+        player: Point,
+    ) -> Result<Vec<(Point, u32, f32)>> {
+        detect_mobs(self.bgr(), minimap, bound, player)
+    }
+
+    fn detect_mob_bboxes(&self) -> Vec<Rect> {
+        detect_mob_bboxes(self.bgr())
+    }
+
+    fn detect_elite_boss(&self, minimap: Rect, player: Point) -> Result<Point> {
+        detect_elite_boss(self.bgr(), minimap, player)
+    }
+
+    fn detect_esc_settings(&self) -> bool {
+        detect_esc_settings(self.bgr(), self.grayscale(), &self.localization, self.scale)
+    }
+
+    fn detect_popup_confirm_button(&self) -> Result<Rect> {
+        detect_popup_confirm_button(self.grayscale(), &self.localization, self.scale)
+    }
+
+    fn detect_popup_ok_new_button(&self) -> Result<Rect> {
+        detect_popup_ok_new_button(self.grayscale(), &self.localization, self.scale)
+    }
+
+    fn detect_elite_boss_bar(&self) -> bool {
+        detect_elite_boss_bar(self.grayscale(), self.scale)
+    }
+
+    fn detect_minimap(&self, border_threshold: u8) -> Result<Rect> {
+        if let Some(minimap) = self.minimap {
+            return Ok(minimap);
+        }
+
+        detect_minimap(self.bgr(), border_threshold)
+    }
+
+    fn detect_minimap_name(&self, minimap: Rect) -> Result<Rect> {
+        detect_minimap_name(self.grayscale(), minimap, self.scale)
+    }
+
+    fn detect_minimap_match(
+        &self,
+        minimap_snapshot: &Mat,
+        minimap_snapshot_grayscale: bool,
+        minimap_name_snapshot: &Mat,
+        minimap_bbox: Rect,
+        minimap_name_bbox: Rect,
+    ) -> Result<f64> {
+        detect_minimap_match(
+            &self.bgra(),
+            self.grayscale(),
+            minimap_snapshot,
+            minimap_snapshot_grayscale,
+            minimap_name_snapshot,
+            minimap_bbox,
+            minimap_name_bbox,
+            self.scale,
+        )
+    }
+
+    fn detect_minimap_name_match(
+        &self,
+        minimap_name_snapshot: &Mat,
+        minimap_name_bbox: Rect,
+    ) -> Result<f64> {
+        detect_minimap_name_match(
+            self.grayscale(),
+            minimap_name_snapshot,
+            minimap_name_bbox,
+            self.scale,
+        )
+    }
+
+    fn detect_minimap_portals(&self, minimap: Rect) -> Vec<Rect> {
+        detect_minimap_portals(self.bgr().roi(minimap).unwrap(), self.scale)
+    }
+
+    fn detect_minimap_rune(&self, minimap: Rect) -> Result<Rect> {
+        detect_minimap_rune(&self.bgr().roi(minimap).unwrap(), self.scale)
+    }
+
+    fn detect_player(&self, minimap: Rect) -> Result<Rect> {
+        detect_player(
+            &self.bgr().roi(minimap).unwrap(),
+            &self.localization,
+            self.localization
+                .detection_threshold(TunableDetection::Player),
+            self.scale,
+        )
+    }
+
+    fn detect_player_kind_count(&self, minimap: Rect, kind: OtherPlayerKind) -> usize {
+        detect_player_kind_count(&self.bgr().roi(minimap).unwrap(), kind, self.scale)
+    }
+
+    fn detect_player_is_dead(&self) -> bool {
+        detect_player_is_dead(self.grayscale(), self.scale)
+    }
+
+    fn detect_player_in_cash_shop(&self) -> bool {
+        detect_player_in_cash_shop(self.grayscale(), &self.localization, self.scale)
+    }
+
+    fn detect_cash_shop_free_tab(&self) -> Result<Rect> {
+        detect_user_template(
+            self.bgr(),
+            self.localization.cash_shop_free_tab_base64.as_deref(),
+        )
+    }
+
+    fn detect_cash_shop_free_item(&self) -> Result<Rect> {
+        detect_user_template(
+            self.bgr(),
+            self.localization.cash_shop_free_item_base64.as_deref(),
+        )
+    }
+
+    fn detect_cash_shop_buy_button(&self) -> Result<Rect> {
+        detect_user_template(
+            self.bgr(),
+            self.localization.cash_shop_buy_button_base64.as_deref(),
+        )
+    }
+
+    fn detect_player_health_bar(&self) -> Result<Rect> {
+        detect_player_health_bar(
+            self.grayscale(),
+            self.localization
+                .detection_threshold(TunableDetection::HealthBar),
+            self.scale,
+        )
+    }
+
+    fn detect_player_current_max_health_bars(&self, health_bar: Rect) -> Result<(Rect, Rect)> {
+        detect_player_current_max_health_bars(self.bgr(), self.grayscale(), health_bar, self.scale)
+    }
+
+    fn detect_player_health(&self, current_bar: Rect, max_bar: Rect) -> Result<(u32, u32)> {
+        detect_player_health(self.bgr(), current_bar, max_bar)
+    }
+
+    fn detect_player_mana(&self, health_bar: Rect) -> Result<(u32, u32)> {
+        detect_player_mana(self.bgr(), self.grayscale(), health_bar, self.scale)
+    }
+
+    fn detect_player_name_texts(&self, health_bar: Rect) -> Result<Vec<String>> {
+        detect_player_name_texts(self.bgr(), health_bar)
+    }
+
+    fn detect_exp_bar(&self) -> Result<ExpBar> {
+        detect_exp_bar(
+            self.bgr(),
+            self.grayscale(),
+            self.localization
+                .detection_threshold(TunableDetection::HealthBar),
+            self.scale,
+        )
+    }
+
+    fn detect_player_buff(&self, kind: BuffKind) -> bool {
+        let mat = match kind {
+            BuffKind::Rune
+            | BuffKind::Familiar
+            | BuffKind::SayramElixir
+            | BuffKind::AureliaElixir
+            | BuffKind::ExpCouponX2
+            | BuffKind::ExpCouponX3
+            | BuffKind::ExpCouponX4
+            | BuffKind::BonusExpCoupon
+            | BuffKind::ForTheGuild
+            | BuffKind::HardHitter => &to_buffs_region(self.grayscale()),
+            BuffKind::LegionWealth
+            | BuffKind::LegionLuck
+            | BuffKind::WealthAcquisitionPotion
+            | BuffKind::ExpAccumulationPotion
+            | BuffKind::SmallWealthAcquisitionPotion
+            | BuffKind::SmallExpAccumulationPotion
+            | BuffKind::ExtremeRedPotion
+            | BuffKind::ExtremeBluePotion
+            | BuffKind::ExtremeGreenPotion
+            | BuffKind::ExtremeGoldPotion => &to_buffs_region(self.bgr()),
+        };
+        detect_player_buff(mat, kind, self.scale)
+    }
+
+    fn detect_rune_arrows(&self, calibrating: ArrowsCalibrating) -> Result<ArrowsState> {
+        detect_rune_arrows(self.bgr(), calibrating, self.scale)
+    }
+
+    fn detect_rune_arrows_with_scores(&self) -> Vec<(Rect, KeyKind, f32)> {
+        detect_rune_arrows_with_scores_regions(self.bgr())
+    }
+
+    fn detect_erda_shower(&self) -> Result<Rect> {
+        detect_erda_shower(
+            self.grayscale(),
+            self.localization
+                .detection_threshold(TunableDetection::ErdaShower),
+            self.scale,
+        )
+    }
+
+    fn detect_tunable(
+        &self,
+        detection: TunableDetection,
+        threshold: f64,
+        minimap: Option<Rect>,
+    ) -> Result<Rect> {
+        match detection {
+            TunableDetection::Player => {
+                let minimap = minimap.ok_or(anyhow!("minimap not detected"))?;
+                let bbox = detect_player(
+                    &self.bgr().roi(minimap).unwrap(),
+                    &self.localization,
+                    threshold,
+                    self.scale,
+                )?;
+                Ok(Rect::new(
+                    bbox.x + minimap.x,
+                    bbox.y + minimap.y,
+                    bbox.width,
+                    bbox.height,
+                ))
+            }
+            TunableDetection::HealthBar => {
+                detect_player_health_bar(self.grayscale(), threshold, self.scale)
+            }
+            TunableDetection::ErdaShower => {
+                detect_erda_shower(self.grayscale(), threshold, self.scale)
+            }
+        }
+    }
+
+    fn detect_familiar_save_button(&self) -> Result<Rect> {
+        detect_familiar_save_button(self.bgr(), &self.localization, self.scale)
+    }
+
+    fn detect_familiar_level_button(&self) -> Result<Rect> {
+        detect_familiar_level_button(self.bgr(), &self.localization, self.scale)
+    }
+
+    fn detect_familiar_slots(&self) -> Vec<(Rect, bool)> {
+        detect_familiar_slots(self.bgr(), self.scale)
+    }
+
+    fn detect_familiar_slot_is_free(&self, slot: Rect) -> bool {
+        detect_familiar_slot_is_free(&self.bgr().roi(slot).unwrap(), self.scale)
+    }
+
+    fn detect_familiar_hover_level(&self) -> Result<FamiliarLevel> {
+        detect_familiar_hover_level(self.bgr(), self.scale)
+    }
+
+    fn detect_familiar_cards(&self) -> Vec<(Rect, FamiliarRank)> {
+        detect_familiar_cards(self.bgr(), self.scale)
+    }
+
+    fn detect_familiar_scrollbar(&self) -> Result<Rect> {
+        detect_familiar_scrollbar(&to_grayscale(&self.bgra(), false), self.scale)
+    }
+
+    fn detect_familiar_menu_opened(&self) -> bool {
+        detect_familiar_menu_opened(self.grayscale(), self.scale)
+    }
+
+    fn detect_familiar_essence_depleted(&self) -> bool {
+        detect_familiar_essence_depleted(&to_buffs_region(self.grayscale()), self.scale)
+    }
+
+    fn detect_change_channel_menu_opened(&self) -> bool {
+        detect_change_channel_menu_opened(self.grayscale(), &self.localization, self.scale)
+    }
+
+    fn detect_chat_menu_opened(&self) -> bool {
+        detect_chat_menu_opened(self.grayscale(), self.scale)
+    }
+
+    fn detect_admin_visible(&self) -> bool {
+        detect_admin_visible(self.grayscale(), self.scale)
+    }
+
+    fn detect_timer_visible(&self) -> bool {
+        detect_timer_visible(self.grayscale(), &self.localization, self.scale)
+    }
+
+    fn detect_lie_detector(&self) -> Result<Rect> {
+        detect_lie_detector(self.bgr(), self.scale)
+    }
+
+    fn detect_lie_detector_preparing(&self) -> bool {
+        detect_lie_detector_preparing(self.bgr(), self.scale).is_ok()
+    }
+
+    fn detect_party_invite(&self) -> Result<PartyInvite> {
+        detect_party_invite(self.bgr(), &self.localization)
+    }
+
+    fn detect_player_request(&self) -> Result<PlayerRequest> {
+        detect_player_request(self.bgr(), &self.localization)
+    }
+
+    fn detect_quick_slots_hexa_booster(&self) -> Result<QuickSlotsHexaBooster> {
+        detect_quick_slots_hexa_booster(&to_quick_slots_region(self.grayscale()).0, self.scale)
+    }
+
+    fn detect_quick_slots_potion_count(&self) -> Result<u32> {
+        detect_quick_slots_potion_count(self.bgr(), &self.localization)
+    }
+
+    fn detect_shop_potion(&self) -> Result<Rect> {
+        detect_shop_potion(self.bgr(), &self.localization)
+    }
+
+    fn detect_hexa_quick_menu(&self) -> Result<Rect> {
+        detect_hexa_quick_menu(self.grayscale(), self.scale)
+    }
+
+    fn detect_hexa_erda_conversion_button(&self) -> Result<Rect> {
+        detect_hexa_erda_conversion_button(self.bgr(), &self.localization, self.scale)
+    }
+
+    fn detect_hexa_booster_button(&self) -> Result<Rect> {
+        detect_hexa_booster_button(self.bgr(), &self.localization, self.scale)
+    }
+
+    fn detect_hexa_max_button(&self) -> Result<Rect> {
+        detect_hexa_max_button(self.bgr(), &self.localization, self.scale)
+    }
+
+    fn detect_hexa_convert_button(&self) -> Result<Rect> {
+        detect_hexa_convert_button(self.bgr(), &self.localization, self.scale)
+    }
+
+    fn detect_hexa_sol_erda(&self) -> Result<SolErda> {
+        detect_hexa_sol_erda(self.grayscale(), self.scale)
+    }
+
+    fn detect_template_match(&self, template: &Mat) -> Result<(Rect, f64)> {
+        if template.channels() == 1 {
+            detect_template_single(
+                self.grayscale(),
+                template,
+                no_array(),
+                Point::default(),
+                0.0,
+            )
+        } else {
+            detect_template_single(self.bgr(), template, no_array(), Point::default(), 0.0)
+        }
+    }
+
+    fn detect_transparent_shapes(&self, region: Rect) -> Vec<Rect> {
+        detect_transparent_shapes(&self.bgr().roi(region).unwrap())
+    }
+
+    fn extract_player_dot_template(&self, minimap: Rect, position: Point) -> Result<Mat> {
+        extract_player_dot_template(&self.bgr().roi(minimap)?, position)
+    }
+}
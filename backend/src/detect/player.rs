@@ -0,0 +1,621 @@
+//! Detections of the player state and status bars.
+
+use anyhow::{Result, anyhow, bail};
+use opencv::{
+    core::{Mat, MatTraitConst, Point, Rect, ToInputArray, Vec3b, no_array},
+    imgcodecs::{IMREAD_COLOR, IMREAD_GRAYSCALE},
+};
+
+use super::{
+    ExpBar,
+    engine::{
+        Template, detect_template, detect_template_single, scale_pixels, template,
+        to_cached_mat_from_base64,
+    },
+    inference::{extract_text_bboxes, extract_texts, preprocess_for_text_bboxes},
+};
+use crate::models::Localization;
+
+/// The anchor next to the HP bar that is always visible in game.
+pub(super) static HP_BAR_ANCHOR: Template = template!("HP_BAR_ANCHOR_TEMPLATE", IMREAD_GRAYSCALE);
+
+/// The threshold the calibrated player dot template is matched at.
+const PLAYER_DOT_THRESHOLD: f64 = 0.8;
+
+/// The margin in pixels of the minimap surrounding the player dot kept in a calibrated template.
+///
+/// The edge of the dot against the minimap gives the template the contrast needed for
+/// [`TM_CCOEFF_NORMED`](opencv::imgproc::TM_CCOEFF_NORMED) to score reliably.
+const PLAYER_DOT_MARGIN: i32 = 1;
+
+/// The minimum standard deviation of any color channel of a calibrated player dot template.
+const PLAYER_DOT_MIN_STDDEV: f64 = 10.0;
+
+pub(super) fn detect_player(
+    minimap_bgr: &impl ToInputArray,
+    localization: &Localization,
+    threshold: f64,
+    scale: f64,
+) -> Result<Rect> {
+    /// Stores offsets information for various player templates.
+    #[derive(Debug)]
+    struct TemplateOffsets {
+        template: &'static Template,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    }
+
+    static TEMPLATE: Template = template!("PLAYER_TEMPLATE", IMREAD_COLOR);
+    static TEMPLATE_LEFT_HALF: Template = template!("PLAYER_LEFT_HALF_TEMPLATE", IMREAD_COLOR);
+    static TEMPLATE_RIGHT_HALF: Template = template!("PLAYER_RIGHT_HALF_TEMPLATE", IMREAD_COLOR);
+    static TEMPLATE_TOP_HALF: Template = template!("PLAYER_TOP_HALF_TEMPLATE", IMREAD_COLOR);
+    static TEMPLATE_BOTTOM_HALF: Template = template!("PLAYER_BOTTOM_HALF_TEMPLATE", IMREAD_COLOR);
+    static TEMPLATE_OFFSETS: [TemplateOffsets; 5] = [
+        TemplateOffsets {
+            template: &TEMPLATE,
+            x: -1,
+            y: -1,
+            width: 2,
+            height: 2,
+        },
+        TemplateOffsets {
+            template: &TEMPLATE_LEFT_HALF,
+            x: -1,
+            y: -1,
+            width: 6,
+            height: 2,
+        },
+        TemplateOffsets {
+            template: &TEMPLATE_RIGHT_HALF,
+            x: -5,
+            y: -1,
+            width: 6,
+            height: 2,
+        },
+        TemplateOffsets {
+            template: &TEMPLATE_TOP_HALF,
+            x: -1,
+            y: -1,
+            width: 2,
+            height: 6,
+        },
+        TemplateOffsets {
+            template: &TEMPLATE_BOTTOM_HALF,
+            x: -1,
+            y: -5,
+            width: 2,
+            height: 6,
+        },
+    ];
+
+    // The calibrated template is for player dot styles the default templates do not match and is
+    // re-centered to the size of the full template hit so that it can be averaged with the others
+    let calibrated = localization
+        .player_dot_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, false).ok())
+        .and_then(|template| {
+            detect_template_single(
+                minimap_bgr,
+                &*template,
+                no_array(),
+                Point::default(),
+                PLAYER_DOT_THRESHOLD,
+            )
+            .ok()
+        })
+        .map(|(rect, score)| {
+            let template = TEMPLATE.get_scaled(scale);
+            let offsets = &TEMPLATE_OFFSETS[0];
+            let width = template.cols() + offsets.width;
+            let height = template.rows() + offsets.height;
+            let center = rect.tl() + Point::new(rect.width / 2, rect.height / 2);
+
+            (
+                Rect::new(center.x - width / 2, center.y - height / 2, width, height),
+                score,
+            )
+        });
+
+    // Detect all and offset as needed to get a 10x10 for preserving previous behavior.
+    let matches = TEMPLATE_OFFSETS
+        .iter()
+        .filter_map(|offsets| {
+            let (rect, score) = detect_template_single(
+                minimap_bgr,
+                &*offsets.template.get_scaled(scale),
+                no_array(),
+                Point::default(),
+                threshold,
+            )
+            .ok()?;
+            let x = rect.x + offsets.x;
+            let y = rect.y + offsets.y;
+            let width = rect.width + offsets.width;
+            let height = rect.height + offsets.height;
+
+            Some((Rect::new(x, y, width, height), score))
+        })
+        .chain(calibrated)
+        .collect::<Vec<_>>();
+    // Prefers the calibrated hit as the best one when there is any
+    let (best, _) = calibrated
+        .or_else(|| {
+            matches
+                .iter()
+                .copied()
+                .max_by(|(_, first), (_, second)| first.total_cmp(second))
+        })
+        .ok_or(anyhow!("player not found"))?;
+
+    // Averages the hits overlapping with the best hit weighted by their scores. When the player
+    // is clipped by the minimap edge, the full template can match a nearby wrong location while
+    // the half templates still agree on the correct one.
+    let (x_sum, y_sum, score_sum) = matches
+        .into_iter()
+        .filter(|(rect, _)| (*rect & best).area() > 0)
+        .fold(
+            (0.0, 0.0, 0.0),
+            |(x_sum, y_sum, score_sum), (rect, score)| {
+                (
+                    x_sum + rect.x as f64 * score,
+                    y_sum + rect.y as f64 * score,
+                    score_sum + score,
+                )
+            },
+        );
+    let x = (x_sum / score_sum).round() as i32;
+    let y = (y_sum / score_sum).round() as i32;
+
+    Ok(Rect::new(x, y, best.width, best.height))
+}
+
+/// Extracts the player dot near `position` from `minimap_bgr` as a template.
+///
+/// The dot is flood filled from the most vivid pixel near `position` and kept with a margin of
+/// [`PLAYER_DOT_MARGIN`] pixels of the surrounding minimap. Templates too close to the minimap
+/// edge or too uniform to match reliably are rejected.
+pub(super) fn extract_player_dot_template(
+    minimap_bgr: &impl MatTraitConst,
+    position: Point,
+) -> Result<Mat> {
+    const SEARCH_SIZE: i32 = 16;
+    const COLOR_TOLERANCE: u32 = 48;
+    const MIN_PIXELS_COUNT: usize = 4;
+
+    let size = minimap_bgr.size()?;
+    let window = Rect::new(
+        position.x - SEARCH_SIZE / 2,
+        position.y - SEARCH_SIZE / 2,
+        SEARCH_SIZE,
+        SEARCH_SIZE,
+    ) & Rect::new(0, 0, size.width, size.height);
+    if window.empty() {
+        bail!("position is outside of minimap");
+    }
+
+    let window_bgr = minimap_bgr.roi(window)?;
+    let pixel = |point: Point| *window_bgr.at_pt::<Vec3b>(point).unwrap();
+    let vividness = |point: Point| {
+        let color = pixel(point);
+        let max = color[0].max(color[1]).max(color[2]) as u32;
+        let min = color[0].min(color[1]).min(color[2]) as u32;
+        (max - min) + max
+    };
+    // The player dot is assumed to be the most vivid pixel near the confirmed position
+    let seed = (0..window.height)
+        .flat_map(|y| (0..window.width).map(move |x| Point::new(x, y)))
+        .max_by_key(|point| vividness(*point))
+        .expect("non-empty window");
+    let seed_color = pixel(seed);
+
+    // Flood fills pixels of similar color to get the dot shape
+    let mut visited = vec![false; (window.width * window.height) as usize];
+    let mut stack = vec![seed];
+    let mut tl = seed;
+    let mut br = seed;
+    let mut count = 0;
+    while let Some(point) = stack.pop() {
+        if point.x < 0 || point.y < 0 || point.x >= window.width || point.y >= window.height {
+            continue;
+        }
+        let index = (point.y * window.width + point.x) as usize;
+        if visited[index] {
+            continue;
+        }
+        visited[index] = true;
+
+        let color = pixel(point);
+        let diff = (0..3)
+            .map(|i| color[i].abs_diff(seed_color[i]) as u32)
+            .sum::<u32>();
+        if diff > COLOR_TOLERANCE {
+            continue;
+        }
+
+        count += 1;
+        tl = Point::new(tl.x.min(point.x), tl.y.min(point.y));
+        br = Point::new(br.x.max(point.x), br.y.max(point.y));
+        stack.extend([
+            point + Point::new(1, 0),
+            point - Point::new(1, 0),
+            point + Point::new(0, 1),
+            point - Point::new(0, 1),
+        ]);
+    }
+    if count < MIN_PIXELS_COUNT {
+        bail!("player dot not found near {position:?}");
+    }
+
+    let margin = Point::new(PLAYER_DOT_MARGIN, PLAYER_DOT_MARGIN);
+    let dot = Rect::from_points(tl - margin, br + Point::new(1, 1) + margin);
+    if dot & Rect::new(0, 0, window.width, window.height) != dot {
+        bail!("player dot near {position:?} is too close to the minimap edge");
+    }
+
+    let points = (dot.y..dot.br().y)
+        .flat_map(|y| (dot.x..dot.br().x).map(move |x| Point::new(x, y)))
+        .collect::<Vec<_>>();
+    let count = points.len() as f64;
+    let stddev = (0..3)
+        .map(|i| {
+            let values = points
+                .iter()
+                .map(|point| pixel(*point)[i] as f64)
+                .collect::<Vec<_>>();
+            let mean = values.iter().sum::<f64>() / count;
+            let variance = values
+                .iter()
+                .map(|value| (value - mean).powi(2))
+                .sum::<f64>()
+                / count;
+            variance.sqrt()
+        })
+        .fold(0.0, f64::max);
+    if stddev < PLAYER_DOT_MIN_STDDEV {
+        bail!("player dot near {position:?} has too little contrast");
+    }
+
+    Ok(window_bgr.roi(dot)?.try_clone()?)
+}
+
+pub(super) fn detect_player_is_dead(grayscale: &impl ToInputArray, scale: f64) -> bool {
+    static TEMPLATE: Template = template!("TOMB_TEMPLATE", IMREAD_GRAYSCALE);
+
+    detect_template(
+        grayscale,
+        &*TEMPLATE.get_scaled(scale),
+        Point::default(),
+        0.8,
+    )
+    .is_ok()
+}
+
+pub static CASH_SHOP_TEMPLATE: Template = template!("CASH_SHOP_TEMPLATE", IMREAD_GRAYSCALE);
+
+pub(super) fn detect_player_in_cash_shop(
+    grayscale: &impl ToInputArray,
+    localization: &Localization,
+    scale: f64,
+) -> bool {
+    let template = localization
+        .cash_shop_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, true).ok());
+
+    detect_template(
+        grayscale,
+        template
+            .as_deref()
+            .unwrap_or(&*CASH_SHOP_TEMPLATE.get_scaled(scale)),
+        Point::default(),
+        0.7,
+    )
+    .is_ok()
+}
+
+/// Detects a color template that has no default and must be provided through [`Localization`].
+pub(super) fn detect_user_template(bgr: &impl ToInputArray, base64: Option<&str>) -> Result<Rect> {
+    let Some(base64) = base64 else {
+        bail!("template not provided");
+    };
+    let template = to_cached_mat_from_base64(base64, false)?;
+
+    detect_template(bgr, &*template, Point::default(), 0.75)
+}
+
+pub(super) fn detect_player_health_bar<T: MatTraitConst + ToInputArray>(
+    grayscale: &T,
+    threshold: f64,
+    scale: f64,
+) -> Result<Rect> {
+    const HP_BAR_X_OFFSET_FROM_ANCHOR_CENTER: i32 = 122;
+    const HP_BAR_Y_OFFSET_FROM_ANCHOR_CENTER: i32 = 19;
+    const HP_BAR_HALF_WIDTH: i32 = 100;
+    const HP_BAR_HALF_HEIGHT: i32 = 10;
+
+    let anchor = detect_template(
+        grayscale,
+        &*HP_BAR_ANCHOR.get_scaled(scale),
+        Point::default(),
+        threshold,
+    )?;
+    let size = grayscale.size().expect("has size");
+    let hp_bar_x_center =
+        anchor.x + anchor.width / 2 + scale_pixels(HP_BAR_X_OFFSET_FROM_ANCHOR_CENTER, scale);
+    let hp_bar_y_center =
+        anchor.y + anchor.height / 2 - scale_pixels(HP_BAR_Y_OFFSET_FROM_ANCHOR_CENTER, scale);
+    let hp_bar_half_width = scale_pixels(HP_BAR_HALF_WIDTH, scale);
+    let hp_bar_half_height = scale_pixels(HP_BAR_HALF_HEIGHT, scale);
+    if hp_bar_x_center > size.width || hp_bar_y_center < 0 {
+        bail!("failed to determine HP bar center");
+    }
+
+    let hp_bar_tl = Point::new(
+        hp_bar_x_center - hp_bar_half_width,
+        hp_bar_y_center - hp_bar_half_height,
+    );
+    let hp_bar_br = Point::new(
+        hp_bar_x_center + hp_bar_half_width,
+        hp_bar_y_center + hp_bar_half_height,
+    );
+    if hp_bar_tl.x < 0 || hp_bar_tl.y < 0 || hp_bar_br.x > size.width || hp_bar_br.y > size.height {
+        bail!("failed to determine HP bar");
+    }
+
+    Ok(Rect::from_points(hp_bar_tl, hp_bar_br))
+}
+
+pub(super) fn detect_player_current_max_health_bars(
+    bgr: &impl MatTraitConst,
+    grayscale: &impl MatTraitConst,
+    hp_bar: Rect,
+    scale: f64,
+) -> Result<(Rect, Rect)> {
+    static HP_SEPARATOR: Template = template!("HP_SEPARATOR_TEMPLATE", IMREAD_GRAYSCALE);
+    static HP_SHIELD: Template = template!("HP_SHIELD_TEMPLATE", IMREAD_GRAYSCALE);
+
+    let hp_separator = detect_template(
+        &grayscale.roi(hp_bar).unwrap(),
+        &*HP_SEPARATOR.get_scaled(scale),
+        hp_bar.tl(),
+        0.7,
+    )?;
+
+    let hp_shield = detect_template(
+        &grayscale.roi(hp_bar).unwrap(),
+        &*HP_SHIELD.get_scaled(scale),
+        hp_bar.tl(),
+        0.8,
+    )
+    .ok();
+
+    let left = bgr
+        .roi(Rect::new(
+            hp_bar.x,
+            hp_bar.y,
+            hp_separator.x - hp_bar.x,
+            hp_bar.height,
+        ))
+        .unwrap();
+    let (left_in, left_w_ratio, left_h_ratio) = preprocess_for_text_bboxes(&left);
+    let left_bbox = extract_text_bboxes(&left_in, left_w_ratio, left_h_ratio, hp_bar.x, hp_bar.y)
+        .into_iter()
+        .min_by_key(|bbox| ((bbox.x + bbox.width) - hp_separator.x).abs())
+        .ok_or(anyhow!("failed to detect current health bar"))?;
+    let left_bbox_x = hp_shield.map_or(left_bbox.x, |bbox| bbox.x + bbox.width); // When there is shield, skips past it
+    let left_bbox = Rect::new(
+        left_bbox_x,
+        left_bbox.y,
+        hp_separator.x - left_bbox_x,
+        left_bbox.height,
+    );
+
+    let right = bgr
+        .roi(Rect::new(
+            hp_separator.x + hp_separator.width,
+            hp_bar.y,
+            (hp_bar.x + hp_bar.width) - (hp_separator.x + hp_separator.width),
+            hp_bar.height,
+        ))
+        .unwrap();
+    let (right_in, right_w_ratio, right_h_ratio) = preprocess_for_text_bboxes(&right);
+    let right_bbox = extract_text_bboxes(
+        &right_in,
+        right_w_ratio,
+        right_h_ratio,
+        hp_separator.x + hp_separator.width,
+        hp_bar.y,
+    )
+    .into_iter()
+    .reduce(|acc, cur| acc | cur)
+    .ok_or(anyhow!("failed to detect max health bar"))?;
+
+    Ok((left_bbox, right_bbox))
+}
+
+pub(super) fn detect_player_health(
+    bgr: &impl MatTraitConst,
+    current_bar: Rect,
+    max_bar: Rect,
+) -> Result<(u32, u32)> {
+    let current_health = extract_texts(bgr, &[current_bar]);
+    let current_health = current_health
+        .first()
+        .and_then(|value| value.parse::<u32>().ok())
+        .ok_or(anyhow!("cannot detect current health"))?;
+    let max_health = extract_texts(bgr, &[max_bar]);
+    let max_health = max_health
+        .first()
+        .and_then(|value| value.parse::<u32>().ok())
+        .ok_or(anyhow!("cannot detect max health"))?;
+    Ok((current_health.min(max_health), max_health))
+}
+
+pub(super) fn detect_player_mana(
+    bgr: &impl MatTraitConst,
+    grayscale: &impl MatTraitConst,
+    hp_bar: Rect,
+    scale: f64,
+) -> Result<(u32, u32)> {
+    const MP_BAR_Y_OFFSET_FROM_HP_BAR: i32 = 18;
+
+    let size = grayscale.size().expect("has size");
+    let mp_bar = Rect::new(
+        hp_bar.x,
+        hp_bar.y + scale_pixels(MP_BAR_Y_OFFSET_FROM_HP_BAR, scale),
+        hp_bar.width,
+        hp_bar.height,
+    );
+    if mp_bar.y + mp_bar.height > size.height {
+        bail!("failed to determine MP bar");
+    }
+
+    // The MP bar shares the same separator and text layout as the HP bar
+    let (current_bar, max_bar) =
+        detect_player_current_max_health_bars(bgr, grayscale, mp_bar, scale)?;
+    detect_player_health(bgr, current_bar, max_bar)
+}
+
+pub(super) fn detect_player_name_texts(
+    bgr: &impl MatTraitConst,
+    hp_bar: Rect,
+) -> Result<Vec<String>> {
+    // The level and name are located right above the HP bar
+    let name_y = (hp_bar.y - hp_bar.height * 2).max(0);
+    let name_height = hp_bar.y - name_y;
+    if name_height <= 0 {
+        bail!("HP bar is at the top of the screen");
+    }
+    let name_region = Rect::new(hp_bar.x, name_y, hp_bar.width, name_height);
+    let name = bgr.roi(name_region)?;
+    let (name_in, w_ratio, h_ratio) = preprocess_for_text_bboxes(&name);
+    let name_bboxes = extract_text_bboxes(&name_in, w_ratio, h_ratio, name_region.x, name_region.y);
+    if name_bboxes.is_empty() {
+        bail!("failed to detect player name");
+    }
+
+    Ok(extract_texts(bgr, &name_bboxes))
+}
+
+pub(super) fn detect_exp_bar(
+    bgr: &impl MatTraitConst,
+    grayscale: &(impl MatTraitConst + ToInputArray),
+    health_bar_threshold: f64,
+    scale: f64,
+) -> Result<ExpBar> {
+    /// The height of the EXP text at the bottom of the screen.
+    const EXP_TEXT_HEIGHT: i32 = 16;
+
+    // The EXP text (e.g. EXP. 123456789[12.345%]) is centered in the bottom bar
+    let size = bgr.size()?;
+    let exp_text_height = scale_pixels(EXP_TEXT_HEIGHT, scale);
+    if size.height < exp_text_height {
+        bail!("frame is too small for the EXP bar");
+    }
+    let exp_region = Rect::new(
+        size.width / 4,
+        size.height - exp_text_height,
+        size.width / 2,
+        exp_text_height,
+    );
+    let exp = bgr.roi(exp_region)?;
+    let (exp_in, w_ratio, h_ratio) = preprocess_for_text_bboxes(&exp);
+    let exp_bboxes = extract_text_bboxes(&exp_in, w_ratio, h_ratio, exp_region.x, exp_region.y);
+    let percent = parse_exp_percent(&extract_texts(bgr, &exp_bboxes))
+        .ok_or(anyhow!("failed to detect EXP percentage"))?;
+    let level = detect_player_health_bar(grayscale, health_bar_threshold, scale)
+        .and_then(|hp_bar| detect_player_name_texts(bgr, hp_bar))
+        .ok()
+        .and_then(|texts| parse_level(&texts));
+
+    Ok(ExpBar { level, percent })
+}
+
+/// Parses the percentage in brackets from the recognized EXP `texts`.
+fn parse_exp_percent(texts: &[String]) -> Option<f32> {
+    let text = texts.concat();
+    let (before_percent, _) = text.split_once('%')?;
+    let percent = before_percent
+        .rsplit(['[', '(', ' '])
+        .next()?
+        .parse::<f32>()
+        .ok()?;
+
+    (0.0..=100.0).contains(&percent).then_some(percent)
+}
+
+/// Parses the level following `Lv.` from the recognized level and name `texts`.
+fn parse_level(texts: &[String]) -> Option<u32> {
+    let text = texts.concat();
+    let index = text.to_ascii_lowercase().find("lv")?;
+    let level = text[index + 2..]
+        .trim_start_matches(['.', ' '])
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect::<String>();
+
+    level.parse::<u32>().ok().filter(|level| *level > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use opencv::core::{CV_8UC3, MatTrait, Scalar};
+
+    use super::*;
+
+    fn minimap_with_dot(background: Scalar, dot: Scalar) -> Mat {
+        let mut mat = Mat::new_rows_cols_with_default(40, 40, CV_8UC3, background).unwrap();
+        mat.roi_mut(Rect::new(18, 18, 4, 4))
+            .unwrap()
+            .set_scalar(dot)
+            .unwrap();
+        mat
+    }
+
+    #[test]
+    fn extract_player_dot_template_keeps_margin() {
+        let background = Scalar::new(30.0, 30.0, 30.0, 0.0);
+        let dot = Scalar::new(0.0, 220.0, 255.0, 0.0);
+        let minimap = minimap_with_dot(background, dot);
+
+        let template = extract_player_dot_template(&minimap, Point::new(20, 20)).unwrap();
+
+        assert_eq!(template.cols(), 6);
+        assert_eq!(template.rows(), 6);
+        assert_eq!(
+            *template.at_2d::<Vec3b>(0, 0).unwrap(),
+            Vec3b::from([30, 30, 30])
+        );
+        assert_eq!(
+            *template.at_2d::<Vec3b>(3, 3).unwrap(),
+            Vec3b::from([0, 220, 255])
+        );
+    }
+
+    #[test]
+    fn extract_player_dot_template_rejects_uniform_patch() {
+        let color = Scalar::new(0.0, 220.0, 255.0, 0.0);
+        let minimap = minimap_with_dot(color, color);
+
+        assert!(extract_player_dot_template(&minimap, Point::new(20, 20)).is_err());
+    }
+
+    #[test]
+    fn extract_player_dot_template_rejects_low_contrast() {
+        let background = Scalar::new(100.0, 100.0, 100.0, 0.0);
+        let dot = Scalar::new(117.0, 117.0, 117.0, 0.0);
+        let minimap = minimap_with_dot(background, dot);
+
+        assert!(extract_player_dot_template(&minimap, Point::new(20, 20)).is_err());
+    }
+
+    #[test]
+    fn extract_player_dot_template_rejects_outside_minimap() {
+        let minimap = minimap_with_dot(Scalar::all(30.0), Scalar::new(0.0, 220.0, 255.0, 0.0));
+
+        assert!(extract_player_dot_template(&minimap, Point::new(100, 100)).is_err());
+    }
+}
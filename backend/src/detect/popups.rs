@@ -0,0 +1,338 @@
+//! Detections of popups, menus and dialogs.
+
+use anyhow::{Result, anyhow, bail};
+use opencv::{
+    core::{Mat, MatTraitConst, MatTraitConstManual, Point, Rect, ToInputArray},
+    imgcodecs::{IMREAD_COLOR, IMREAD_GRAYSCALE},
+};
+
+use super::{
+    PartyInvite, PlayerRequest, PlayerRequestKind,
+    engine::{Template, detect_any_template, detect_template, template, to_cached_mat_from_base64},
+    hexa::detect_hexa_menu,
+    inference::{
+        ModelSession, extract_text_bboxes, extract_texts, from_output_value,
+        preprocess_for_text_bboxes, preprocess_for_yolo, remap_from_yolo, to_input_value,
+    },
+    player::detect_user_template,
+};
+use crate::models::Localization;
+
+pub static POPUP_CONFIRM_TEMPLATE: Template = template!("POPUP_CONFIRM_TEMPLATE", IMREAD_GRAYSCALE);
+
+pub static POPUP_YES_TEMPLATE: Template = template!("POPUP_YES_TEMPLATE", IMREAD_GRAYSCALE);
+
+pub static POPUP_NEXT_TEMPLATE: Template = template!("POPUP_NEXT_TEMPLATE", IMREAD_GRAYSCALE);
+
+pub static POPUP_END_CHAT_TEMPLATE: Template =
+    template!("POPUP_END_CHAT_TEMPLATE", IMREAD_GRAYSCALE);
+
+pub static POPUP_OK_NEW_TEMPLATE: Template = template!("POPUP_OK_NEW_TEMPLATE", IMREAD_GRAYSCALE);
+
+pub static POPUP_OK_OLD_TEMPLATE: Template = template!("POPUP_OK_OLD_TEMPLATE", IMREAD_GRAYSCALE);
+
+pub static POPUP_CANCEL_NEW_TEMPLATE: Template =
+    template!("POPUP_CANCEL_NEW_TEMPLATE", IMREAD_GRAYSCALE);
+
+pub static POPUP_CANCEL_OLD_TEMPLATE: Template =
+    template!("POPUP_CANCEL_OLD_TEMPLATE", IMREAD_GRAYSCALE);
+
+/// Computes the center band region of `mat` where popup buttons can appear.
+///
+/// Constraining the search to this region avoids matching chat text resembling buttons. The
+/// region size is configured by [`Localization::popup_roi_ratios`].
+fn popup_roi(mat: &impl MatTraitConst, localization: &Localization) -> Rect {
+    let size = mat.size().unwrap();
+    let (width_ratio, height_ratio) = localization.popup_roi_ratios();
+    let width = (size.width as f32 * width_ratio) as i32;
+    let height = (size.height as f32 * height_ratio) as i32;
+
+    Rect::new(
+        (size.width - width) / 2,
+        (size.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+pub(super) fn detect_esc_settings(
+    bgr: &impl ToInputArray,
+    grayscale: &(impl MatTraitConst + ToInputArray + Sync),
+    localization: &Localization,
+    scale: f64,
+) -> bool {
+    static ESC_MENU_TEMPLATE: Template = template!("ESC_MENU_TEMPLATE", IMREAD_COLOR);
+
+    if detect_template(
+        bgr,
+        &*ESC_MENU_TEMPLATE.get_scaled(scale),
+        Point::default(),
+        0.75,
+    )
+    .is_ok()
+    {
+        return true;
+    }
+
+    let popup_templates = [
+        (
+            &localization.popup_confirm_base64,
+            POPUP_CONFIRM_TEMPLATE.get_scaled(scale),
+        ),
+        (
+            &localization.popup_yes_base64,
+            POPUP_YES_TEMPLATE.get_scaled(scale),
+        ),
+        (
+            &localization.popup_next_base64,
+            POPUP_NEXT_TEMPLATE.get_scaled(scale),
+        ),
+        (
+            &localization.popup_end_chat_base64,
+            POPUP_END_CHAT_TEMPLATE.get_scaled(scale),
+        ),
+        (
+            &localization.popup_ok_new_base64,
+            POPUP_OK_NEW_TEMPLATE.get_scaled(scale),
+        ),
+        (
+            &localization.popup_ok_old_base64,
+            POPUP_OK_OLD_TEMPLATE.get_scaled(scale),
+        ),
+        (
+            &localization.popup_cancel_new_base64,
+            POPUP_CANCEL_NEW_TEMPLATE.get_scaled(scale),
+        ),
+        (
+            &localization.popup_cancel_old_base64,
+            POPUP_CANCEL_OLD_TEMPLATE.get_scaled(scale),
+        ),
+    ]
+    .map(|(base64, default)| {
+        let template = base64
+            .as_ref()
+            .and_then(|base64| to_cached_mat_from_base64(base64, true).ok());
+        (template, default)
+    });
+    let candidates = popup_templates
+        .iter()
+        .map(|(template, default)| (template.as_deref().unwrap_or(default.as_ref()), None, 0.75))
+        .collect::<Vec<_>>();
+    if detect_any_template(
+        &grayscale.roi(popup_roi(grayscale, localization)).unwrap(),
+        &candidates,
+    ) {
+        return true;
+    }
+
+    detect_hexa_menu(grayscale, scale)
+}
+
+pub(super) fn detect_popup_confirm_button(
+    grayscale: &impl MatTraitConst,
+    localization: &Localization,
+    scale: f64,
+) -> Result<Rect> {
+    let template = localization
+        .popup_confirm_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, true).ok());
+
+    let roi = popup_roi(grayscale, localization);
+
+    detect_template(
+        &grayscale.roi(roi).unwrap(),
+        template
+            .as_deref()
+            .unwrap_or(&*POPUP_CONFIRM_TEMPLATE.get_scaled(scale)),
+        roi.tl(),
+        0.75,
+    )
+}
+
+pub(super) fn detect_popup_ok_new_button(
+    grayscale: &impl MatTraitConst,
+    localization: &Localization,
+    scale: f64,
+) -> Result<Rect> {
+    let template = localization
+        .popup_ok_new_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, true).ok());
+
+    let roi = popup_roi(grayscale, localization);
+
+    detect_template(
+        &grayscale.roi(roi).unwrap(),
+        template
+            .as_deref()
+            .unwrap_or(&*POPUP_OK_NEW_TEMPLATE.get_scaled(scale)),
+        roi.tl(),
+        0.75,
+    )
+}
+
+pub static CHANGE_CHANNEL_TEMPLATE: Template =
+    template!("CHANGE_CHANNEL_MENU_TEMPLATE", IMREAD_GRAYSCALE);
+
+pub(super) fn detect_change_channel_menu_opened(
+    grayscale: &impl ToInputArray,
+    localization: &Localization,
+    scale: f64,
+) -> bool {
+    let template = localization
+        .change_channel_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, true).ok());
+
+    detect_template(
+        grayscale,
+        template
+            .as_deref()
+            .unwrap_or(&*CHANGE_CHANNEL_TEMPLATE.get_scaled(scale)),
+        Point::default(),
+        0.75,
+    )
+    .is_ok()
+}
+
+pub(super) fn detect_chat_menu_opened(grayscale: &impl ToInputArray, scale: f64) -> bool {
+    static TEMPLATE: Template = template!("CHAT_MENU_TEMPLATE", IMREAD_GRAYSCALE);
+
+    detect_template(
+        grayscale,
+        &*TEMPLATE.get_scaled(scale),
+        Point::default(),
+        0.75,
+    )
+    .is_ok()
+}
+
+pub(super) fn detect_admin_visible(grayscale: &impl ToInputArray, scale: f64) -> bool {
+    static TEMPLATE: Template = template!("ADMIN_TEMPLATE", IMREAD_GRAYSCALE);
+
+    detect_template(
+        grayscale,
+        &*TEMPLATE.get_scaled(scale),
+        Point::default(),
+        0.75,
+    )
+    .is_ok()
+}
+
+pub static TIMER_TEMPLATE: Template = template!("TIMER_TEMPLATE", IMREAD_GRAYSCALE);
+
+pub(super) fn detect_timer_visible(
+    grayscale: &impl ToInputArray,
+    localization: &Localization,
+    scale: f64,
+) -> bool {
+    let template = localization
+        .timer_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, true).ok());
+
+    detect_template(
+        grayscale,
+        template
+            .as_deref()
+            .unwrap_or(&*TIMER_TEMPLATE.get_scaled(scale)),
+        Point::default(),
+        0.75,
+    )
+    .is_ok()
+}
+
+pub(super) fn detect_party_invite(bgr: &Mat, localization: &Localization) -> Result<PartyInvite> {
+    let popup = detect_user_template(bgr, localization.party_invite_base64.as_deref())?;
+    let accept = detect_user_template(
+        bgr,
+        localization.party_invite_accept_button_base64.as_deref(),
+    )?;
+    let decline = detect_user_template(
+        bgr,
+        localization.party_invite_decline_button_base64.as_deref(),
+    )?;
+
+    // The inviter name is located between the popup title and the buttons
+    let name_y = popup.y + popup.height;
+    let name_height = accept.y.min(decline.y) - name_y;
+    if name_height <= 0 {
+        bail!("party invite buttons are not below the popup title");
+    }
+    let name_region = Rect::new(popup.x, name_y, popup.width, name_height);
+    let name = bgr.roi(name_region)?;
+    let (name_in, w_ratio, h_ratio) = preprocess_for_text_bboxes(&name);
+    // Assumes the first word is the inviter name (e.g. "<name> has invited you to a party")
+    let name_bbox = extract_text_bboxes(&name_in, w_ratio, h_ratio, name_region.x, name_region.y)
+        .into_iter()
+        .min_by_key(|bbox| (bbox.y, bbox.x))
+        .ok_or(anyhow!("failed to detect party inviter name"))?;
+    let inviter = extract_texts(bgr, &[name_bbox])
+        .into_iter()
+        .next()
+        .ok_or(anyhow!("failed to extract party inviter name"))?;
+
+    Ok(PartyInvite {
+        inviter,
+        accept,
+        decline,
+    })
+}
+
+pub(super) fn detect_player_request(
+    bgr: &Mat,
+    localization: &Localization,
+) -> Result<PlayerRequest> {
+    let kind = [
+        (
+            PlayerRequestKind::Trade,
+            localization.trade_request_base64.as_deref(),
+        ),
+        (
+            PlayerRequestKind::Duel,
+            localization.duel_request_base64.as_deref(),
+        ),
+    ]
+    .into_iter()
+    .find_map(|(kind, base64)| detect_user_template(bgr, base64).ok().map(|_| kind))
+    .ok_or(anyhow!("player request popup not found"))?;
+    let decline = detect_user_template(
+        bgr,
+        localization.player_request_decline_button_base64.as_deref(),
+    )?;
+
+    Ok(PlayerRequest { kind, decline })
+}
+
+pub(super) fn detect_lie_detector(bgr: &impl ToInputArray, scale: f64) -> Result<Rect> {
+    static TEMPLATE: Template = template!("LIE_DETECTOR_TEMPLATE", IMREAD_COLOR);
+
+    detect_template(bgr, &*TEMPLATE.get_scaled(scale), Point::default(), 0.6)
+}
+
+pub(super) fn detect_lie_detector_preparing(bgr: &impl ToInputArray, scale: f64) -> Result<Rect> {
+    static TEMPLATE: Template = template!("LIE_DETECTOR_PREPARE_TEMPLATE", IMREAD_COLOR);
+
+    detect_template(bgr, &*TEMPLATE.get_scaled(scale), Point::default(), 0.6)
+}
+
+pub(super) fn detect_transparent_shapes(bgr: &impl MatTraitConst) -> Vec<Rect> {
+    static MODEL: ModelSession = ModelSession::new(
+        include_bytes!(env!("TRANSPARENT_SHAPE_MODEL")),
+        "build transparent shape detection session successfully",
+    );
+
+    let size = bgr.size().unwrap();
+    let (mat_in, w_ratio, h_ratio, left, top) = preprocess_for_yolo(bgr);
+    let mut model = MODEL.lock();
+    let result = model.run([to_input_value(&mat_in)]).unwrap();
+    let mat_out = from_output_value(&result);
+
+    (0..mat_out.rows())
+        // SAFETY: 0..result.rows() is within Mat bounds
+        .map(|i| unsafe { mat_out.at_row_unchecked::<f32>(i).unwrap() })
+        .filter(|pred| pred[4] >= 0.1)
+        .map(|pred| remap_from_yolo(pred, size, w_ratio, h_ratio, left, top))
+        .collect()
+}
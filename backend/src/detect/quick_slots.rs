@@ -0,0 +1,134 @@
+//! Detections of the quick slots skills and items.
+
+use anyhow::{Result, anyhow, bail};
+use opencv::{
+    boxed_ref::BoxedRef,
+    core::{Mat, MatTraitConst, Point, Rect, ToInputArray},
+    imgcodecs::IMREAD_GRAYSCALE,
+};
+
+use super::{
+    QuickSlotsHexaBooster,
+    engine::{
+        Template, detect_template, detect_template_single, template, to_cached_mat_from_base64,
+    },
+    inference::{extract_text_bboxes, extract_texts, preprocess_for_text_bboxes},
+};
+use crate::models::Localization;
+
+pub(super) fn detect_erda_shower(
+    grayscale: &impl MatTraitConst,
+    threshold: f64,
+    scale: f64,
+) -> Result<Rect> {
+    static ERDA_SHOWER: Template = template!("ERDA_SHOWER_TEMPLATE", IMREAD_GRAYSCALE);
+
+    let (quick_slots, crop_bbox) = to_quick_slots_region(grayscale);
+    detect_template(
+        &quick_slots,
+        &*ERDA_SHOWER.get_scaled(scale),
+        crop_bbox.tl(),
+        threshold,
+    )
+}
+
+pub(super) fn detect_quick_slots_hexa_booster<T: MatTraitConst + ToInputArray>(
+    grayscale: &T,
+    scale: f64,
+) -> Result<QuickSlotsHexaBooster> {
+    static TEMPLATE: Template = template!("HEXA_BOOSTER_TEMPLATE", IMREAD_GRAYSCALE);
+    static TEMPLATE_NUMBER: Template = template!("HEXA_BOOSTER_NUMBER_TEMPLATE", IMREAD_GRAYSCALE);
+    static TEMPLATE_NUMBER_MASK: Template =
+        template!("HEXA_BOOSTER_NUMBER_MASK_TEMPLATE", IMREAD_GRAYSCALE);
+
+    let pad_height = TEMPLATE_NUMBER.get_scaled(scale).size().unwrap().height;
+    let booster_bbox = detect_template(
+        grayscale,
+        &*TEMPLATE.get_scaled(scale),
+        Point::default(),
+        0.75,
+    )
+    .map(|bbox| {
+        let br = bbox.br();
+
+        let x1 = bbox.x - 1;
+        let x2 = br.x + 1;
+
+        let y1 = bbox.y;
+        let y2 = br.y + pad_height;
+
+        Rect::new(x1, y1, x2 - x1, y2 - y1)
+    })?;
+    let booster = grayscale.roi(booster_bbox).expect("can extract roi");
+    let has_booster = detect_template_single(
+        &booster,
+        &*TEMPLATE_NUMBER.get_scaled(scale),
+        &*TEMPLATE_NUMBER_MASK.get_scaled(scale),
+        Point::default(),
+        0.8,
+    )
+    .is_err();
+
+    if has_booster {
+        Ok(QuickSlotsHexaBooster::Available)
+    } else {
+        Ok(QuickSlotsHexaBooster::Unavailable)
+    }
+}
+
+pub(super) fn detect_shop_potion(
+    bgr: &impl ToInputArray,
+    localization: &Localization,
+) -> Result<Rect> {
+    let template = localization
+        .shop_potion_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, false).ok())
+        .ok_or(anyhow!("shop potion template is not set"))?;
+
+    detect_template(bgr, &*template, Point::default(), 0.75)
+}
+
+/// Detects the item count drawn right below the user-provided potion icon template.
+pub(super) fn detect_quick_slots_potion_count(
+    bgr: &impl MatTraitConst,
+    localization: &Localization,
+) -> Result<u32> {
+    let template = localization
+        .quick_slots_potion_base64
+        .as_ref()
+        .and_then(|base64| to_cached_mat_from_base64(base64, false).ok())
+        .ok_or(anyhow!("quick slots potion template is not set"))?;
+    let (quick_slots, crop_bbox) = to_quick_slots_region(bgr);
+    let icon = detect_template(&quick_slots, &*template, crop_bbox.tl(), 0.75)?;
+
+    let size = bgr.size()?;
+    let count_y = icon.br().y;
+    let count_height = icon.height.min(size.height - count_y);
+    if count_height <= 0 {
+        bail!("potion icon is at the bottom of the screen");
+    }
+    let count_bbox = Rect::new(icon.x, count_y, icon.width, count_height);
+    let count = bgr.roi(count_bbox)?;
+    let (count_in, w_ratio, h_ratio) = preprocess_for_text_bboxes(&count);
+    let count_bboxes = extract_text_bboxes(&count_in, w_ratio, h_ratio, count_bbox.x, count_bbox.y);
+    if count_bboxes.is_empty() {
+        bail!("failed to detect potion count");
+    }
+
+    extract_texts(bgr, &count_bboxes)
+        .concat()
+        .parse::<u32>()
+        .map_err(|_| anyhow!("cannot parse potion count"))
+}
+
+/// Crops `mat` to the bottom right of the image for quick slots region.
+#[inline]
+pub(super) fn to_quick_slots_region(mat: &impl MatTraitConst) -> (BoxedRef<'_, Mat>, Rect) {
+    let size = mat.size().unwrap();
+    let crop_x = size.width / 2;
+    let crop_y = size.height / 5;
+    let crop_bbox = Rect::new(size.width - crop_x, size.height - crop_y, crop_x, crop_y);
+    let crop_roi = mat.roi(crop_bbox).unwrap();
+    (crop_roi, crop_bbox)
+}
@@ -0,0 +1,328 @@
+//! Detections of the rune arrows.
+
+use anyhow::{Result, anyhow, bail};
+use log::{debug, info};
+use opencv::{
+    core::{
+        Mat, MatTrait, MatTraitConst, MatTraitConstManual, ModifyInplace, Point, Rect, Scalar,
+        Size, Vector, extract_channel,
+    },
+    imgproc::{
+        CHAIN_APPROX_SIMPLE, MORPH_RECT, RETR_EXTERNAL, THRESH_BINARY, contour_area, dilate_def,
+        find_contours_def, get_structuring_element_def, min_enclosing_triangle, threshold,
+    },
+};
+
+use super::{
+    ArrowsCalibrating, ArrowsComplete, ArrowsState, MAX_ARROWS, MAX_SPIN_ARROWS,
+    RUNE_ARROW_SCORE_THRESHOLD, SpinArrow,
+    engine::{scale_pixels, to_hsv},
+    inference::{
+        ModelSession, from_output_value, preprocess_for_yolo, remap_from_yolo, to_input_value,
+    },
+};
+#[cfg(debug_assertions)]
+use crate::debug::{debug_mat, debug_spinning_arrows};
+use crate::{array::Array, bridge::KeyKind};
+
+pub(super) fn detect_rune_arrows_with_scores_regions(
+    bgr: &impl MatTraitConst,
+) -> Vec<(Rect, KeyKind, f32)> {
+    static RUNE_MODEL: ModelSession = ModelSession::new(
+        include_bytes!(env!("RUNE_MODEL")),
+        "build rune detection session successfully",
+    );
+
+    fn map_arrow(pred: &[f32]) -> KeyKind {
+        match pred[5] as i32 {
+            0 => KeyKind::Up,
+            1 => KeyKind::Down,
+            2 => KeyKind::Left,
+            3 => KeyKind::Right,
+            _ => unreachable!(),
+        }
+    }
+
+    let size = bgr.size().unwrap();
+    let (mat_in, w_ratio, h_ratio, left, top) = preprocess_for_yolo(bgr);
+
+    let mut model = RUNE_MODEL.lock();
+    let result = model.run([to_input_value(&mat_in)]).unwrap();
+
+    let mat_out = from_output_value(&result);
+    let mut vec = (0..mat_out.rows())
+        // SAFETY: 0..outputs.rows() is within Mat bounds
+        .map(|i| unsafe { mat_out.at_row_unchecked::<f32>(i).unwrap() })
+        .filter(|pred| pred[4] >= 0.2)
+        .map(|pred| {
+            (
+                remap_from_yolo(pred, size, w_ratio, h_ratio, left, top),
+                map_arrow(pred),
+                pred[4],
+            )
+        })
+        .collect::<Vec<_>>();
+    vec.sort_by_key(|a| a.0.x);
+    vec
+}
+
+pub(super) fn detect_rune_arrows(
+    bgr: &impl MatTraitConst,
+    mut calibrating: ArrowsCalibrating,
+    scale: f64,
+) -> Result<ArrowsState> {
+    const MAX_CALIBRATE_COUNT: u32 = 3;
+
+    if !calibrating.spin_arrows_calibrated
+        && calibrating.spin_arrows_calibrate_count < MAX_CALIBRATE_COUNT
+    {
+        calibrating.spin_arrows_calibrate_count += 1;
+        calibrate_for_spin_arrows(bgr, &mut calibrating, scale);
+        return Ok(ArrowsState::Calibrating(calibrating));
+    }
+
+    // After calibration is complete and there are spin arrows, prioritize its detection
+    if let Some(ref mut spin_arrows) = calibrating.spin_arrows
+        && spin_arrows.iter().any(|arrow| arrow.final_arrow.is_none())
+    {
+        for spin_arrow in spin_arrows
+            .iter_mut()
+            .filter(|arrow| arrow.final_arrow.is_none())
+        {
+            detect_spin_arrow(bgr, spin_arrow)?;
+        }
+        return Ok(ArrowsState::Calibrating(calibrating));
+    }
+
+    // Normal detection path
+    let mut bgr = bgr.try_clone().unwrap();
+    if let Some(spin_arrows) = calibrating.spin_arrows.as_ref() {
+        //  Set all spin arrow regions to black pixels
+        for region in spin_arrows.iter().map(|arrow| arrow.region) {
+            bgr.roi_mut(region)?.set_scalar(Scalar::default())?;
+        }
+
+        #[cfg(debug_assertions)]
+        if calibrating.is_spin_testing {
+            debug_mat("Rune Region Spin Arrows Removed", &bgr, 0, &[]);
+        }
+    }
+
+    let result = detect_rune_arrows_with_scores_regions(&bgr)
+        .into_iter()
+        .filter_map(|(rect, arrow, score)| {
+            (score >= RUNE_ARROW_SCORE_THRESHOLD).then_some((rect, false, arrow))
+        })
+        .collect::<Vec<_>>();
+    if calibrating.spin_arrows.is_some() {
+        if result.len() != MAX_ARROWS / 2 {
+            info!(target: "rune", "spin arrows detection completed but normal arrows failed");
+            return Err(anyhow!("failed to detect normal rune arrows"));
+        }
+        let mut vec = calibrating
+            .spin_arrows
+            .take()
+            .unwrap()
+            .into_iter()
+            .map(|arrow| (arrow.region, true, arrow.final_arrow.unwrap()))
+            .chain(result)
+            .collect::<Vec<_>>();
+        vec.sort_by_key(|a| a.0.x);
+        return Ok(ArrowsState::Complete(to_arrows_complete(vec)));
+    }
+
+    if result.len() == MAX_ARROWS {
+        Ok(ArrowsState::Complete(to_arrows_complete(result)))
+    } else {
+        Err(anyhow!("failed to detect rune arrows"))
+    }
+}
+
+fn calibrate_for_spin_arrows(
+    bgr: &impl MatTraitConst,
+    calibrating: &mut ArrowsCalibrating,
+    scale: f64,
+) {
+    static RUNE_SPIN_MODEL: ModelSession = ModelSession::new(
+        include_bytes!(env!("RUNE_SPIN_MODEL")),
+        "build rune spin detection session successfully",
+    );
+
+    const SPIN_REGION_PAD: i32 = 16;
+
+    // Detect the rune region
+    let size = bgr.size().unwrap();
+    let spin_region_pad = scale_pixels(SPIN_REGION_PAD, scale);
+    let (mat_in, w_ratio, h_ratio, left, top) = preprocess_for_yolo(bgr);
+    let mut model = RUNE_SPIN_MODEL.lock();
+    let result = model.run([to_input_value(&mat_in)]).unwrap();
+    let mat_out = from_output_value(&result);
+    let spin_arrow_regions = (0..mat_out.rows())
+        // SAFETY: 0..result.rows() is within Mat bounds
+        .map(|i| unsafe { mat_out.at_row_unchecked::<f32>(i).unwrap() })
+        .filter(|pred| pred[4] >= 0.8)
+        .map(|pred| remap_from_yolo(pred, size, w_ratio, h_ratio, left, top))
+        .collect::<Vec<Rect>>();
+    if spin_arrow_regions.is_empty() {
+        calibrating.spin_arrows_calibrated = true;
+        info!(target: "rune", "no spin arrow is found, proceed with normal detection...");
+        return;
+    }
+    if spin_arrow_regions.len() < MAX_SPIN_ARROWS {
+        info!(target: "rune", "retry calibrating spin arrow because at least 1 spin arrow is found...");
+        return;
+    }
+    if spin_arrow_regions.len() > MAX_SPIN_ARROWS {
+        info!(target: "rune", "retry calibrating spin arrow because of false positives...");
+        return;
+    }
+    calibrating.spin_arrows_calibrated = true;
+
+    let mut spin_arrows = Array::new();
+
+    for region in spin_arrow_regions {
+        let x = region.x;
+        let y = region.y;
+        let w = region.width;
+        let h = region.height;
+
+        // Pad to ensure the region always contain the spin arrow even when it rotates
+        // horitzontally or vertically
+        let padded_x = (x - spin_region_pad).max(0);
+        let padded_y = (y - spin_region_pad).max(0);
+        let padded_w = (padded_x + w + spin_region_pad * 2).min(size.width) - padded_x;
+        let padded_h = (padded_y + h + spin_region_pad * 2).min(size.height) - padded_y;
+        let rect = Rect::new(padded_x, padded_y, padded_w, padded_h);
+
+        #[cfg(debug_assertions)]
+        if calibrating.is_spin_testing {
+            debug_mat("Spin Arrow", bgr, 0, &[(rect, "Region")]);
+        }
+
+        spin_arrows.push(SpinArrow {
+            centroid: Point::new(x + w / 2, y + h / 2),
+            region: rect,
+            last_arrow_head: None,
+            final_arrow: None,
+            #[cfg(debug_assertions)]
+            is_spin_testing: calibrating.is_spin_testing,
+        });
+    }
+
+    if spin_arrows.len() == MAX_SPIN_ARROWS {
+        info!(target: "rune", "{} spinning rune arrows detected, calibrating...", spin_arrows.len());
+        calibrating.spin_arrows = Some(spin_arrows);
+    }
+}
+
+fn detect_spin_arrow(bgr: &impl MatTraitConst, spin_arrow: &mut SpinArrow) -> Result<()> {
+    const SPIN_LAG_THRESHOLD: i32 = 30;
+
+    // Extract spin arrow region
+    let spin_arrow_mat = to_hsv(&bgr.roi(spin_arrow.region)?);
+    let kernel = get_structuring_element_def(MORPH_RECT, Size::new(3, 3)).unwrap();
+    let mut spin_arrow_thresh = Mat::default();
+    unsafe {
+        spin_arrow_thresh.modify_inplace(|mat, mat_mut| {
+            extract_channel(&spin_arrow_mat, mat_mut, 1).unwrap();
+            threshold(mat, mat_mut, 245.0, 255.0, THRESH_BINARY).unwrap();
+            dilate_def(mat, mat_mut, &kernel).unwrap();
+        })
+    }
+
+    let mut contours = Vector::<Vector<Point>>::new();
+    find_contours_def(
+        &spin_arrow_thresh,
+        &mut contours,
+        RETR_EXTERNAL,
+        CHAIN_APPROX_SIMPLE,
+    )
+    .unwrap();
+    if contours.is_empty() {
+        bail!("cannot find the spinning arrow contour");
+    }
+
+    let contour = contours
+        .iter()
+        .min_by_key(|contour| contour_area(contour, false).unwrap() as i32)
+        .expect("not empty");
+    let mut triangle = Vector::<Point>::new();
+    let triangle_area = min_enclosing_triangle(&contour, &mut triangle).unwrap() as i32;
+    if triangle_area == 0 {
+        bail!("failed to determine the spinning arrow triangle");
+    }
+
+    let shortest_edge = triangle
+        .iter()
+        .zip(triangle.iter().cycle().skip(1))
+        .min_by(|first_edge, second_edge| {
+            let first_norm = (first_edge.0 - first_edge.1).norm();
+            let second_norm = (second_edge.0 - second_edge.1).norm();
+            first_norm.total_cmp(&second_norm)
+        })
+        .expect("has value");
+    let arrow_head = (shortest_edge.0 + shortest_edge.1) / 2;
+
+    let centroid = spin_arrow.centroid - spin_arrow.region.tl();
+    let cur_arrow_head = arrow_head - centroid;
+
+    if spin_arrow.last_arrow_head.is_none() {
+        spin_arrow.last_arrow_head = Some(cur_arrow_head);
+        return Ok(());
+    }
+
+    let prev_arrow_head = spin_arrow.last_arrow_head.unwrap();
+    // https://stackoverflow.com/a/13221874
+    let dot = prev_arrow_head.x * -cur_arrow_head.y + prev_arrow_head.y * cur_arrow_head.x;
+    if dot >= SPIN_LAG_THRESHOLD {
+        debug!(target: "rune", "spinning arrow lag detected");
+        let directions = [
+            (KeyKind::Up, prev_arrow_head.dot(Point::new(0, -1))),
+            (KeyKind::Down, prev_arrow_head.dot(Point::new(0, 1))),
+            (KeyKind::Left, prev_arrow_head.dot(Point::new(-1, 0))),
+            (KeyKind::Right, prev_arrow_head.dot(Point::new(1, 0))),
+        ];
+        let (arrow, _) = directions
+            .into_iter()
+            .max_by_key(|(_, score)| *score)
+            .unwrap();
+        info!(target: "rune", "spinning arrow result {arrow:?} {directions:?}");
+        spin_arrow.final_arrow = Some(arrow);
+    }
+    spin_arrow.last_arrow_head = Some(cur_arrow_head);
+
+    #[cfg(debug_assertions)]
+    if spin_arrow.is_spin_testing {
+        debug_spinning_arrows(
+            bgr,
+            &triangle,
+            &contours,
+            spin_arrow.region,
+            prev_arrow_head,
+            cur_arrow_head,
+            spin_arrow.centroid,
+        );
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn to_arrows_complete(vec: Vec<(Rect, bool, KeyKind)>) -> ArrowsComplete {
+    debug_assert!(vec.len() == 4);
+    info!( target: "player", "solving rune result {vec:?}");
+
+    let first = vec[0];
+    let second = vec[1];
+    let third = vec[2];
+    let fourth = vec[3];
+    let keys = [first.2, second.2, third.2, fourth.2];
+    let bboxes = [first.0, second.0, third.0, fourth.0];
+    let spins = [first.1, second.1, third.1, fourth.1];
+
+    ArrowsComplete {
+        keys,
+        bboxes,
+        spins,
+    }
+}
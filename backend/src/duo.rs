@@ -0,0 +1,409 @@
+//! Cooperative duo mode coordinating two instances botting the same map.
+//!
+//! One instance hosts through its remote control server and the other joins at the duo address,
+//! which is the host's remote control address. Both sides exchange [`DuoMessage`]s over the
+//! `ExchangeDuo` stream of the `BotControl` service from [`crate::remote`]. The messages are used
+//! to:
+//! - Stagger casting the same buff so that both instances do not cast it at once.
+//! - Avoid auto mobbing in the quadrant the peer is currently auto mobbing.
+//! - Take turns solving the shared map's rune, starting with the host.
+//!
+//! When the peer is not connected, every coordination falls back to acting alone.
+
+use std::{
+    convert::identity,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+use tokio::{
+    sync::{
+        broadcast::{self, Receiver, Sender, error::RecvError},
+        mpsc,
+    },
+    time::sleep,
+};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Status, Streaming, transport::Channel};
+
+use crate::{
+    DuoMode,
+    buff::BuffKind,
+    player::Quadrant,
+    remote::{
+        proto::{self, bot_control_client::BotControlClient, duo_message::Message},
+        with_token,
+    },
+};
+
+/// The duration after the peer casts a buff during which the same buff is not cast locally.
+const BUFF_STAGGER: Duration = Duration::from_secs(5);
+
+/// The duration a rune can stay on the map on the peer's turn before solving it locally.
+///
+/// This avoids the rune being left unsolved when the peer is busy or on a different map.
+const RUNE_TURN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The delay before reconnecting after the connection to the host is lost.
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+/// The number of [`DuoMessage`]s buffered for the peer before waiting.
+const STREAM_BUFFER: usize = 16;
+
+static DUO: OnceLock<Duo> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DuoMessage {
+    /// The peer queued casting the [`BuffKind`] with the index.
+    BuffCast(usize),
+    /// The peer started auto mobbing in the quadrant.
+    AutoMobQuadrant(Quadrant),
+    /// The peer solved the rune, making it this instance's turn.
+    RuneSolved,
+}
+
+impl DuoMessage {
+    /// Converts a message received from the peer, returning [`None`] if it is malformed.
+    fn from_proto(message: proto::DuoMessage) -> Option<Self> {
+        Some(match message.message? {
+            Message::BuffCast(index) => DuoMessage::BuffCast(index as usize),
+            Message::AutoMobQuadrant(quadrant) => {
+                let quadrant = match proto::Quadrant::try_from(quadrant).ok()? {
+                    proto::Quadrant::TopLeft => Quadrant::TopLeft,
+                    proto::Quadrant::TopRight => Quadrant::TopRight,
+                    proto::Quadrant::BottomRight => Quadrant::BottomRight,
+                    proto::Quadrant::BottomLeft => Quadrant::BottomLeft,
+                };
+                DuoMessage::AutoMobQuadrant(quadrant)
+            }
+            Message::RuneSolved(_) => DuoMessage::RuneSolved,
+        })
+    }
+
+    fn into_proto(self) -> proto::DuoMessage {
+        let message = match self {
+            DuoMessage::BuffCast(index) => Message::BuffCast(index as u32),
+            DuoMessage::AutoMobQuadrant(quadrant) => {
+                let quadrant = match quadrant {
+                    Quadrant::TopLeft => proto::Quadrant::TopLeft,
+                    Quadrant::TopRight => proto::Quadrant::TopRight,
+                    Quadrant::BottomRight => proto::Quadrant::BottomRight,
+                    Quadrant::BottomLeft => proto::Quadrant::BottomLeft,
+                };
+                Message::AutoMobQuadrant(quadrant.into())
+            }
+            DuoMessage::RuneSolved => Message::RuneSolved(proto::RuneSolved {}),
+        };
+
+        proto::DuoMessage {
+            message: Some(message),
+        }
+    }
+}
+
+/// The coordination state received from the peer.
+#[derive(Debug)]
+struct DuoState {
+    connected: bool,
+    /// The last time the peer cast each [`BuffKind`].
+    peer_buff_casts: [Option<Instant>; BuffKind::COUNT],
+    peer_auto_mob_quadrant: Option<Quadrant>,
+    rune_turn: bool,
+}
+
+impl DuoState {
+    fn new(mode: DuoMode) -> Self {
+        Self {
+            connected: false,
+            peer_buff_casts: [None; BuffKind::COUNT],
+            peer_auto_mob_quadrant: None,
+            rune_turn: matches!(mode, DuoMode::Host),
+        }
+    }
+
+    fn set_connected(&mut self, connected: bool) {
+        self.connected = connected;
+        if !connected {
+            self.peer_auto_mob_quadrant = None;
+        }
+    }
+
+    fn receive(&mut self, message: DuoMessage) {
+        match message {
+            DuoMessage::BuffCast(index) => {
+                if let Some(instant) = self.peer_buff_casts.get_mut(index) {
+                    *instant = Some(Instant::now());
+                }
+            }
+            DuoMessage::AutoMobQuadrant(quadrant) => {
+                self.peer_auto_mob_quadrant = Some(quadrant);
+            }
+            DuoMessage::RuneSolved => self.rune_turn = true,
+        }
+    }
+
+    fn should_stagger_buff(&self, kind: BuffKind) -> bool {
+        self.connected
+            && self.peer_buff_casts[kind as usize]
+                .is_some_and(|instant| instant.elapsed() < BUFF_STAGGER)
+    }
+
+    fn avoided_auto_mob_quadrant(&self) -> Option<Quadrant> {
+        self.peer_auto_mob_quadrant.filter(|_| self.connected)
+    }
+
+    fn is_rune_turn(&self, rune_appeared: Instant) -> bool {
+        !self.connected || self.rune_turn || rune_appeared.elapsed() >= RUNE_TURN_TIMEOUT
+    }
+}
+
+#[derive(Debug)]
+struct Duo {
+    mode: DuoMode,
+    state: Mutex<DuoState>,
+    message_tx: Sender<DuoMessage>,
+}
+
+impl Duo {
+    #[inline]
+    fn send(&self, message: DuoMessage) {
+        if self.state.lock().unwrap().connected {
+            let _ = self.message_tx.send(message);
+        }
+    }
+}
+
+/// Starts coordinating with the peer instance as `mode`.
+///
+/// When hosting, the peer is accepted by [`accept`] through the remote control server and
+/// `address` and `token` are unused. When joining, `address` is the host's remote control address
+/// and `token` is its remote control token.
+///
+/// This must be called within a Tokio runtime.
+pub fn start(mode: DuoMode, address: String, token: String) {
+    if matches!(mode, DuoMode::None) {
+        return;
+    }
+    let duo = Duo {
+        mode,
+        state: Mutex::new(DuoState::new(mode)),
+        message_tx: broadcast::channel(16).0,
+    };
+    if DUO.set(duo).is_err() {
+        return;
+    }
+
+    if matches!(mode, DuoMode::Join) {
+        tokio::spawn(join_loop(address, token));
+    }
+}
+
+/// Accepts the joining peer's `incoming` messages from the `ExchangeDuo` stream.
+///
+/// Returns the stream of messages to the peer or an error [`Status`] if this instance is not
+/// hosting or a peer is already connected.
+pub(crate) fn accept(
+    incoming: Streaming<proto::DuoMessage>,
+) -> Result<ReceiverStream<Result<proto::DuoMessage, Status>>, Status> {
+    let duo = DUO
+        .get()
+        .filter(|duo| matches!(duo.mode, DuoMode::Host))
+        .ok_or_else(|| Status::failed_precondition("this instance is not hosting duo"))?;
+    let message_rx = {
+        let mut state = duo.state.lock().unwrap();
+        if state.connected {
+            return Err(Status::already_exists("a duo peer is already connected"));
+        }
+        state.set_connected(true);
+        duo.message_tx.subscribe()
+    };
+
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    tokio::spawn(async move {
+        info!(target: "duo", "peer connected");
+        exchange_messages(duo, incoming, message_rx, tx, Ok).await;
+        info!(target: "duo", "peer disconnected");
+    });
+
+    Ok(ReceiverStream::new(rx))
+}
+
+/// Whether casting buff `kind` should be skipped because the peer has just cast it.
+pub fn should_stagger_buff(kind: BuffKind) -> bool {
+    DUO.get()
+        .is_some_and(|duo| duo.state.lock().unwrap().should_stagger_buff(kind))
+}
+
+/// Notifies the peer that buff `kind` is being cast.
+pub fn notify_buff_cast(kind: BuffKind) {
+    if let Some(duo) = DUO.get() {
+        duo.send(DuoMessage::BuffCast(kind as usize));
+    }
+}
+
+/// Gets the quadrant the peer is currently auto mobbing in, if any.
+pub fn avoided_auto_mob_quadrant() -> Option<Quadrant> {
+    DUO.get()
+        .and_then(|duo| duo.state.lock().unwrap().avoided_auto_mob_quadrant())
+}
+
+/// Notifies the peer that auto mobbing is moving to `quadrant`.
+pub fn notify_auto_mob_quadrant(quadrant: Quadrant) {
+    if let Some(duo) = DUO.get() {
+        duo.send(DuoMessage::AutoMobQuadrant(quadrant));
+    }
+}
+
+/// Whether this instance should solve the rune that appeared at `rune_appeared`.
+pub fn is_rune_turn(rune_appeared: Instant) -> bool {
+    DUO.get()
+        .is_none_or(|duo| duo.state.lock().unwrap().is_rune_turn(rune_appeared))
+}
+
+/// Notifies the peer that this instance solved the rune, passing the next turn to the peer.
+pub fn notify_rune_solved() {
+    if let Some(duo) = DUO.get() {
+        duo.state.lock().unwrap().rune_turn = false;
+        duo.send(DuoMessage::RuneSolved);
+    }
+}
+
+async fn join_loop(address: String, token: String) {
+    let duo = DUO.get().expect("initialized");
+    let endpoint = match Channel::from_shared(format!("http://{address}")) {
+        Ok(endpoint) => endpoint,
+        Err(err) => {
+            warn!(target: "duo", "invalid duo address {address} {err}");
+            return;
+        }
+    };
+
+    loop {
+        match endpoint.connect().await {
+            Ok(channel) => {
+                let mut client = BotControlClient::with_interceptor(channel, with_token(&token));
+                let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+                let message_rx = duo.message_tx.subscribe();
+                match client.exchange_duo(ReceiverStream::new(rx)).await {
+                    Ok(response) => {
+                        info!(target: "duo", "joined duo at {address}");
+                        duo.state.lock().unwrap().set_connected(true);
+                        let incoming = response.into_inner();
+                        exchange_messages(duo, incoming, message_rx, tx, identity).await;
+                        info!(target: "duo", "disconnected from {address}");
+                    }
+                    Err(status) => {
+                        let message = status.message();
+                        warn!(target: "duo", "unable to join duo at {address} {message}");
+                    }
+                }
+            }
+            Err(err) => warn!(target: "duo", "unable to join duo at {address} {err}"),
+        }
+        sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Exchanges messages with the peer until either side disconnects.
+///
+/// `wrap` converts a message to the item type of the `outgoing` stream to the peer.
+async fn exchange_messages<T>(
+    duo: &Duo,
+    mut incoming: Streaming<proto::DuoMessage>,
+    mut message_rx: Receiver<DuoMessage>,
+    outgoing: mpsc::Sender<T>,
+    wrap: fn(proto::DuoMessage) -> T,
+) {
+    loop {
+        tokio::select! {
+            message = incoming.message() => {
+                let Ok(Some(message)) = message else {
+                    break;
+                };
+                match DuoMessage::from_proto(message) {
+                    Some(message) => duo.state.lock().unwrap().receive(message),
+                    None => warn!(target: "duo", "invalid message from peer"),
+                }
+            }
+            message = message_rx.recv() => {
+                let message = match message {
+                    Ok(message) => message,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                if outgoing.send(wrap(message.into_proto())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    duo.state.lock().unwrap().set_connected(false);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duo_state_staggers_buff_cast_by_peer() {
+        let mut state = DuoState::new(DuoMode::Host);
+        state.set_connected(true);
+
+        state.receive(DuoMessage::BuffCast(BuffKind::Rune as usize));
+
+        assert!(state.should_stagger_buff(BuffKind::Rune));
+        assert!(!state.should_stagger_buff(BuffKind::SayramElixir));
+    }
+
+    #[test]
+    fn duo_state_ignores_peer_when_disconnected() {
+        let mut state = DuoState::new(DuoMode::Join);
+        state.set_connected(true);
+        state.receive(DuoMessage::BuffCast(BuffKind::Rune as usize));
+        state.receive(DuoMessage::AutoMobQuadrant(Quadrant::TopLeft));
+        assert!(!state.is_rune_turn(Instant::now()));
+
+        state.set_connected(false);
+
+        assert!(!state.should_stagger_buff(BuffKind::Rune));
+        assert_eq!(state.avoided_auto_mob_quadrant(), None);
+        assert!(state.is_rune_turn(Instant::now()));
+    }
+
+    #[test]
+    fn duo_message_round_trips_through_proto() {
+        let messages = [
+            DuoMessage::BuffCast(BuffKind::Rune as usize),
+            DuoMessage::AutoMobQuadrant(Quadrant::BottomLeft),
+            DuoMessage::RuneSolved,
+        ];
+
+        for message in messages {
+            assert_eq!(DuoMessage::from_proto(message.into_proto()), Some(message));
+        }
+        assert_eq!(
+            DuoMessage::from_proto(proto::DuoMessage { message: None }),
+            None
+        );
+        assert_eq!(
+            DuoMessage::from_proto(proto::DuoMessage {
+                message: Some(Message::AutoMobQuadrant(10)),
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn duo_state_alternates_rune_turn() {
+        let mut state = DuoState::new(DuoMode::Join);
+        state.set_connected(true);
+        assert!(!state.is_rune_turn(Instant::now()));
+        assert!(state.is_rune_turn(Instant::now() - RUNE_TURN_TIMEOUT));
+
+        state.receive(DuoMessage::RuneSolved);
+
+        assert!(state.is_rune_turn(Instant::now()));
+    }
+}
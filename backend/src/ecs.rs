@@ -1,19 +1,25 @@
-#[cfg(debug_assertions)]
-use std::cell::RefCell;
-#[cfg(test)]
-use std::rc::Rc;
 use std::sync::Arc;
+#[cfg(test)]
+use std::{cell::RefCell, rc::Rc};
 
 use crate::services::Event;
-#[cfg(test)]
-use crate::{Settings, bridge::MockInput, detect::MockDetector};
 use crate::{
-    bridge::Input, buff::BuffEntities, detect::Detector, minimap::MinimapEntity,
-    notification::DiscordNotification, operation::Operation, player::PlayerEntity, rng::Rng,
+    FrameTimings,
+    bridge::Input,
+    buff::BuffEntities,
+    dataset::RuneDataset,
+    detect::{Detector, PlayerRequest},
+    errors::ErrorEvents,
+    minimap::MinimapEntity,
+    notification::DiscordNotification,
+    operation::{HaltReason, Operation},
+    player::PlayerEntity,
+    rng::Rng,
     skill::SkillEntities,
+    stats::Stats,
 };
-#[cfg(debug_assertions)]
-use crate::{debug::save_rune_for_training, detect::ArrowsComplete};
+#[cfg(test)]
+use crate::{Settings, bridge::MockInput, detect::MockDetector};
 
 macro_rules! transition {
     ($entity:expr, $state:expr) => {{
@@ -100,50 +106,11 @@ macro_rules! try_ok_transition {
 
 pub(super) use try_ok_transition;
 
-#[derive(Debug, Default)]
-#[cfg(debug_assertions)]
-pub struct Debug {
-    auto_save: RefCell<bool>,
-    last_rune_detector: RefCell<Option<Arc<dyn Detector>>>,
-    last_rune_result: RefCell<Option<ArrowsComplete>>,
-}
-
-#[cfg(debug_assertions)]
-impl Debug {
-    pub fn auto_save_rune(&self) -> bool {
-        *self.auto_save.borrow()
-    }
-
-    pub fn set_auto_save_rune(&self, auto_save: bool) {
-        *self.auto_save.borrow_mut() = auto_save;
-    }
-
-    pub fn save_last_rune_result(&self) {
-        if !*self.auto_save.borrow() {
-            return;
-        }
-        if let Some((detector, result)) = self
-            .last_rune_detector
-            .borrow()
-            .as_ref()
-            .zip(*self.last_rune_result.borrow())
-        {
-            save_rune_for_training(&detector.mat(), result);
-        }
-    }
-
-    pub fn set_last_rune_result(&self, detector: Arc<dyn Detector>, result: ArrowsComplete) {
-        *self.last_rune_detector.borrow_mut() = Some(detector);
-        *self.last_rune_result.borrow_mut() = Some(result);
-    }
-}
-
 /// A struct containing shared resources.
 #[derive(Debug)]
 pub struct Resources {
-    /// A resource to hold debugging information.
-    #[cfg(debug_assertions)]
-    pub debug: Debug,
+    /// A resource for capturing the opt-in rune dataset.
+    pub rune_dataset: RuneDataset,
     /// A resource to send inputs.
     pub input: Box<dyn Input>,
     /// A resource for generating random values.
@@ -156,6 +123,16 @@ pub struct Resources {
     pub detector: Option<Arc<dyn Detector>>,
     /// A resource indicating current operation state.
     pub operation: Operation,
+    /// A resource indicating why [`Self::operation`] is halted by a safety mode.
+    ///
+    /// Clears once the operation is no longer halting.
+    pub halt_reason: Option<HaltReason>,
+    /// A resource for recording statistics of the current session.
+    pub stats: Stats,
+    /// A resource for tracking failures surfaced to the UI.
+    pub errors: ErrorEvents,
+    /// A resource for tracking the frame rate and the time spent processing each frame.
+    pub frame_timings: FrameTimings,
     /// A resource indicating current tick.
     pub tick: u64,
 }
@@ -164,13 +141,16 @@ impl Resources {
     #[cfg(test)]
     pub fn new(input: Option<MockInput>, detector: Option<MockDetector>) -> Self {
         Self {
-            #[cfg(debug_assertions)]
-            debug: Debug::default(),
+            rune_dataset: RuneDataset::new(Rc::new(RefCell::new(Settings::default()))),
             input: Box::new(input.unwrap_or_default()),
             rng: Rng::new(rand::random(), rand::random()),
             notification: DiscordNotification::new(Rc::new(RefCell::new(Settings::default()))),
             detector: detector.map(|detector| Arc::new(detector) as Arc<dyn Detector>),
             operation: Operation::Running,
+            halt_reason: None,
+            stats: Stats::default(),
+            errors: ErrorEvents::default(),
+            frame_timings: FrameTimings::default(),
             tick: 0,
         }
     }
@@ -205,9 +185,22 @@ pub enum WorldEvent {
     CycledToRun,
     PlayerDied,
     MinimapChanged,
+    /// The map after [`Self::MinimapChanged`] is verified to not be the expected map.
+    MapMismatched,
     CaptureFailed,
     LieDetectorAppeared,
+    /// The lie detector has failed to be solved too many times.
+    LieDetectorUnsolved,
     EliteBossAppeared,
+    /// Cash shop daily free items are claimed on the provided day.
+    CashShopFreebiesClaimed(u64),
+    AdminAppeared,
+    /// The randomized delay after [`Self::AdminAppeared`] has passed and chat can be sent.
+    AdminChatReady,
+    /// The player has not made any progress for the idle watchdog duration.
+    PlayerIdleTimedOut,
+    /// A trade or duel request popup from another player has appeared.
+    PlayerRequestAppeared(PlayerRequest),
 }
 
 impl Event for WorldEvent {}
@@ -0,0 +1,78 @@
+//! Failures surfaced to the UI status bar.
+//!
+//! Systems and services report an [`ErrorEvent`] whenever a failure happens. An event stays
+//! active for [`ERROR_EVENT_ACTIVE_DURATION`] after its last report so that the UI can show a
+//! persistent indicator instead of the failure only being visible in the logs.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use log::debug;
+
+use crate::ErrorEvent;
+
+/// How long an [`ErrorEvent`] stays active after it was last reported.
+const ERROR_EVENT_ACTIVE_DURATION: Duration = Duration::from_secs(10);
+
+/// A resource for tracking recently reported [`ErrorEvent`]s.
+#[derive(Debug, Default)]
+pub struct ErrorEvents {
+    /// The [`Instant`] each [`ErrorEvent`] was last reported.
+    reported: RefCell<HashMap<ErrorEvent, Instant>>,
+}
+
+impl ErrorEvents {
+    /// Reports `event` as just happened.
+    pub fn report(&self, event: ErrorEvent) {
+        self.report_at(event, Instant::now());
+    }
+
+    /// Gets the currently active [`ErrorEvent`]s sorted by their declaration order.
+    pub fn active(&self) -> Vec<ErrorEvent> {
+        self.active_at(Instant::now())
+    }
+
+    fn report_at(&self, event: ErrorEvent, now: Instant) {
+        if self.reported.borrow_mut().insert(event, now).is_none() {
+            debug!(target: "errors", "error event {event} reported");
+        }
+    }
+
+    fn active_at(&self, now: Instant) -> Vec<ErrorEvent> {
+        let mut reported = self.reported.borrow_mut();
+        reported.retain(|_, instant| {
+            now.saturating_duration_since(*instant) < ERROR_EVENT_ACTIVE_DURATION
+        });
+
+        let mut events = reported.keys().copied().collect::<Vec<_>>();
+        events.sort();
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_expires_after_duration() {
+        let errors = ErrorEvents::default();
+        let now = Instant::now();
+
+        errors.report_at(ErrorEvent::InputRejected, now);
+        errors.report_at(ErrorEvent::CaptureLost, now + Duration::from_secs(5));
+
+        assert_eq!(
+            errors.active_at(now + Duration::from_secs(6)),
+            vec![ErrorEvent::CaptureLost, ErrorEvent::InputRejected]
+        );
+        assert_eq!(
+            errors.active_at(now + Duration::from_secs(12)),
+            vec![ErrorEvent::CaptureLost]
+        );
+        assert!(errors.active_at(now + Duration::from_secs(20)).is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+//! Typed events for crates embedding the backend.
+//!
+//! Unlike the internal event bus of the services, where handlers are registered at compile time,
+//! these events can be subscribed to at runtime with [`subscribe_event`]. Each event type has its
+//! own broadcast channel that is only created on the first subscription, so events nobody is
+//! subscribed to are dropped without being cloned.
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+    sync::{LazyLock, Mutex},
+};
+
+use tokio::sync::broadcast::{Receiver, Sender, channel};
+
+/// The capacity of each event type broadcast channel.
+const EVENT_CAPACITY: usize = 16;
+
+static SENDERS: LazyLock<Mutex<HashMap<TypeId, Box<dyn Any + Send>>>> =
+    LazyLock::new(Mutex::default);
+
+/// An event that can be subscribed to with [`subscribe_event`].
+pub trait SubscribableEvent: Clone + Debug + Send + Sync + 'static {}
+
+/// The player died.
+#[derive(Clone, Debug)]
+pub struct PlayerDied;
+
+impl SubscribableEvent for PlayerDied {}
+
+/// A rune appeared in the minimap at `x` and `y` in minimap coordinates.
+#[derive(Clone, Debug)]
+pub struct RuneDetected {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl SubscribableEvent for RuneDetected {}
+
+/// The in-game map changed and the minimap is being detected again.
+#[derive(Clone, Debug)]
+pub struct MinimapChanged;
+
+impl SubscribableEvent for MinimapChanged {}
+
+/// The in-use [`Map`](crate::Map) changed to the one with `map_id` or none.
+#[derive(Clone, Debug)]
+pub struct MapChanged {
+    pub map_id: Option<i64>,
+}
+
+impl SubscribableEvent for MapChanged {}
+
+/// The player completed executing an action named `name`.
+///
+/// Aborted actions are not included.
+#[derive(Clone, Debug)]
+pub struct ActionCompleted {
+    pub name: String,
+    pub is_priority: bool,
+}
+
+impl SubscribableEvent for ActionCompleted {}
+
+/// Subscribes to events of type `E` emitted from now on by all bot instances.
+pub fn subscribe_event<E: SubscribableEvent>() -> Receiver<E> {
+    let mut senders = SENDERS.lock().unwrap();
+    senders
+        .entry(TypeId::of::<E>())
+        .or_insert_with(|| Box::new(channel::<E>(EVENT_CAPACITY).0))
+        .downcast_ref::<Sender<E>>()
+        .expect("sender has the same event type")
+        .subscribe()
+}
+
+/// Emits `event` to the subscribers of `E` if there is any.
+pub fn emit_event<E: SubscribableEvent>(event: E) {
+    emit_event_with(|| event);
+}
+
+/// Emits the event created by `event` to the subscribers of `E` if there is any.
+///
+/// This avoids creating the event when nobody is subscribed.
+pub fn emit_event_with<E: SubscribableEvent>(event: impl FnOnce() -> E) {
+    let senders = SENDERS.lock().unwrap();
+    let Some(sender) = senders
+        .get(&TypeId::of::<E>())
+        .and_then(|sender| sender.downcast_ref::<Sender<E>>())
+    else {
+        return;
+    };
+
+    if sender.receiver_count() > 0 {
+        let _ = sender.send(event());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct TestEvent(u32);
+
+    impl SubscribableEvent for TestEvent {}
+
+    #[test]
+    fn subscribe_event_receives_emitted_events_of_same_type() {
+        emit_event(TestEvent(0));
+
+        let mut rx = subscribe_event::<TestEvent>();
+        emit_event(TestEvent(1));
+        emit_event(PlayerDied);
+
+        assert_eq!(rx.try_recv().ok(), Some(TestEvent(1)));
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,122 @@
+//! EXP rate estimation from the EXP bar.
+//!
+//! The EXP percentage is sampled periodically through OCR. The rate is estimated from the
+//! samples within a sliding window so that it follows recent changes such as buffs expiring.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::{ExpRate, detect::ExpBar};
+
+/// The sliding window samples are kept in for estimating the rate.
+const RATE_WINDOW: Duration = Duration::from_secs(600);
+
+/// The minimum duration between the oldest and newest samples before the rate is estimated.
+const MIN_RATE_DURATION: Duration = Duration::from_secs(60);
+
+/// Tracks EXP bar samples for estimating EXP per hour and time to level.
+#[derive(Debug, Default)]
+pub struct ExpTracker {
+    samples: VecDeque<(Instant, f32)>,
+    level: Option<u32>,
+}
+
+impl ExpTracker {
+    /// Adds the EXP `bar` sampled at `now`.
+    ///
+    /// The samples are restarted on level up or EXP loss since the percentage is no longer
+    /// comparable to older samples.
+    pub fn push(&mut self, now: Instant, bar: ExpBar) {
+        let level_changed = matches!(
+            (self.level, bar.level),
+            (Some(level), Some(new_level)) if level != new_level
+        );
+        let exp_lost = self
+            .samples
+            .back()
+            .is_some_and(|(_, percent)| bar.percent < *percent);
+        if level_changed || exp_lost {
+            self.samples.clear();
+        }
+
+        self.level = bar.level.or(self.level);
+        self.samples.push_back((now, bar.percent));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(instant, _)| now.saturating_duration_since(*instant) > RATE_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Estimates the [`ExpRate`] from the samples.
+    ///
+    /// Returns [`None`] if there is no sample.
+    pub fn rate(&self) -> Option<ExpRate> {
+        let (last_instant, percent) = *self.samples.back()?;
+        let (first_instant, first_percent) = *self.samples.front()?;
+        let duration = last_instant.saturating_duration_since(first_instant);
+        let percent_per_hour = (duration >= MIN_RATE_DURATION)
+            .then(|| (percent - first_percent) / duration.as_secs_f32() * 3600.0);
+        let time_to_level = percent_per_hour
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| Duration::from_secs_f32((100.0 - percent) / rate * 3600.0));
+
+        Some(ExpRate {
+            level: self.level,
+            percent,
+            percent_per_hour,
+            time_to_level,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(level: u32, percent: f32) -> ExpBar {
+        ExpBar {
+            level: Some(level),
+            percent,
+        }
+    }
+
+    #[test]
+    fn rate_estimates_per_hour_and_time_to_level() {
+        let mut tracker = ExpTracker::default();
+        let now = Instant::now();
+
+        tracker.push(now, bar(200, 10.0));
+        assert_eq!(tracker.rate().unwrap().percent_per_hour, None);
+
+        tracker.push(now + Duration::from_secs(360), bar(200, 11.0));
+        let rate = tracker.rate().unwrap();
+
+        assert_eq!(rate.level, Some(200));
+        assert_eq!(rate.percent, 11.0);
+        assert!((rate.percent_per_hour.unwrap() - 10.0).abs() < 0.01);
+        assert!(
+            rate.time_to_level
+                .unwrap()
+                .abs_diff(Duration::from_secs(32040))
+                < Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn push_restarts_on_level_up() {
+        let mut tracker = ExpTracker::default();
+        let now = Instant::now();
+
+        tracker.push(now, bar(200, 99.0));
+        tracker.push(now + Duration::from_secs(120), bar(201, 1.0));
+
+        let rate = tracker.rate().unwrap();
+        assert_eq!(rate.level, Some(201));
+        assert_eq!(rate.percent_per_hour, None);
+    }
+}
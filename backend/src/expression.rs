@@ -0,0 +1,509 @@
+use std::{
+    iter::Peekable,
+    str::{Chars, FromStr},
+};
+
+use anyhow::{Result, anyhow, bail};
+
+use crate::{
+    buff::{Buff, BuffKind},
+    ecs::World,
+    minimap::Minimap,
+};
+
+/// Variables an [`Expression`] can read from.
+pub trait Variables {
+    /// The player's health in percentage, if known.
+    fn health_percent(&self) -> Option<f64>;
+
+    fn has_buff(&self, kind: BuffKind) -> bool;
+
+    fn has_rune(&self) -> bool;
+
+    fn stranger_count(&self) -> u32;
+}
+
+impl Variables for World {
+    fn health_percent(&self) -> Option<f64> {
+        self.player
+            .context
+            .health()
+            .filter(|(_, max)| *max > 0)
+            .map(|(current, max)| current as f64 / max as f64 * 100.0)
+    }
+
+    fn has_buff(&self, kind: BuffKind) -> bool {
+        matches!(self.buffs[kind].state, Buff::Yes)
+    }
+
+    fn has_rune(&self) -> bool {
+        match self.minimap.state {
+            Minimap::Detecting => false,
+            Minimap::Idle(idle) => idle.rune().is_some(),
+        }
+    }
+
+    fn stranger_count(&self) -> u32 {
+        match self.minimap.state {
+            Minimap::Detecting => 0,
+            Minimap::Idle(idle) => idle.stranger_count() as u32,
+        }
+    }
+}
+
+/// A compiled boolean expression used as an action condition.
+///
+/// The grammar is:
+/// ```text
+/// or      := and ('||' and)*
+/// and     := not ('&&' not)*
+/// not     := '!' not | compare
+/// compare := primary (('<' | '<=' | '>' | '>=' | '==' | '!=') primary)?
+/// primary := number '%'? | 'true' | 'false' | variable | '(' or ')'
+/// variable := 'health' | 'rune' | 'strangers' | 'buff' '(' BuffKind ')'
+/// ```
+#[derive(Debug, Clone)]
+pub struct Expression {
+    root: Node,
+}
+
+impl Expression {
+    /// Evaluates the expression against `variables`.
+    ///
+    /// Returns `false` when a variable the expression depends on is currently unknown.
+    pub fn evaluate(&self, variables: &impl Variables) -> bool {
+        matches!(self.root.evaluate(variables), Some(Value::Bool(true)))
+    }
+}
+
+impl FromStr for Expression {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+            depth: 0,
+        };
+        let root = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            bail!("unexpected {token:?} in expression");
+        }
+        if root.kind()? != Kind::Bool {
+            bail!("expression must evaluate to true or false");
+        }
+
+        Ok(Self { root })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Number(f64),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Number,
+    Bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Variable {
+    Health,
+    Rune,
+    Strangers,
+    Buff(BuffKind),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Value(Value),
+    Variable(Variable),
+    Not(Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Compare(Box<Node>, Operator, Box<Node>),
+}
+
+impl Node {
+    fn kind(&self) -> Result<Kind> {
+        match self {
+            Node::Value(Value::Number(_)) => Ok(Kind::Number),
+            Node::Value(Value::Bool(_)) => Ok(Kind::Bool),
+            Node::Variable(Variable::Health | Variable::Strangers) => Ok(Kind::Number),
+            Node::Variable(Variable::Rune | Variable::Buff(_)) => Ok(Kind::Bool),
+            Node::Not(node) => {
+                if node.kind()? != Kind::Bool {
+                    bail!("'!' can only be applied to true or false");
+                }
+                Ok(Kind::Bool)
+            }
+            Node::And(lhs, rhs) | Node::Or(lhs, rhs) => {
+                if lhs.kind()? != Kind::Bool || rhs.kind()? != Kind::Bool {
+                    bail!("'&&' and '||' can only be applied to true or false");
+                }
+                Ok(Kind::Bool)
+            }
+            Node::Compare(lhs, operator, rhs) => {
+                let lhs = lhs.kind()?;
+                if lhs != rhs.kind()? {
+                    bail!("cannot compare a number with true or false");
+                }
+                if lhs == Kind::Bool && !matches!(operator, Operator::Equal | Operator::NotEqual) {
+                    bail!("true or false can only be compared with '==' or '!='");
+                }
+                Ok(Kind::Bool)
+            }
+        }
+    }
+
+    fn evaluate(&self, variables: &impl Variables) -> Option<Value> {
+        let value = match self {
+            Node::Value(value) => *value,
+            Node::Variable(Variable::Health) => Value::Number(variables.health_percent()?),
+            Node::Variable(Variable::Rune) => Value::Bool(variables.has_rune()),
+            Node::Variable(Variable::Strangers) => Value::Number(variables.stranger_count() as f64),
+            Node::Variable(Variable::Buff(kind)) => Value::Bool(variables.has_buff(*kind)),
+            Node::Not(node) => Value::Bool(!node.evaluate_bool(variables)?),
+            Node::And(lhs, rhs) => {
+                Value::Bool(lhs.evaluate_bool(variables)? && rhs.evaluate_bool(variables)?)
+            }
+            Node::Or(lhs, rhs) => {
+                Value::Bool(lhs.evaluate_bool(variables)? || rhs.evaluate_bool(variables)?)
+            }
+            Node::Compare(lhs, operator, rhs) => {
+                let result = match (lhs.evaluate(variables)?, rhs.evaluate(variables)?) {
+                    (Value::Number(lhs), Value::Number(rhs)) => match operator {
+                        Operator::Less => lhs < rhs,
+                        Operator::LessEqual => lhs <= rhs,
+                        Operator::Greater => lhs > rhs,
+                        Operator::GreaterEqual => lhs >= rhs,
+                        Operator::Equal => lhs == rhs,
+                        Operator::NotEqual => lhs != rhs,
+                    },
+                    (Value::Bool(lhs), Value::Bool(rhs)) => match operator {
+                        Operator::Equal => lhs == rhs,
+                        Operator::NotEqual => lhs != rhs,
+                        _ => return None,
+                    },
+                    _ => return None,
+                };
+                Value::Bool(result)
+            }
+        };
+
+        Some(value)
+    }
+
+    fn evaluate_bool(&self, variables: &impl Variables) -> Option<bool> {
+        match self.evaluate(variables)? {
+            Value::Bool(value) => Some(value),
+            Value::Number(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Percent,
+    OpenParen,
+    CloseParen,
+    Not,
+    And,
+    Or,
+    Operator(Operator),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut chars = text.chars().peekable();
+    let mut tokens = Vec::new();
+
+    while let Some(ch) = chars.next() {
+        let token = match ch {
+            ch if ch.is_whitespace() => continue,
+            '0'..='9' | '.' => {
+                let mut number = String::from(ch);
+                while let Some(ch) = chars.next_if(|ch| ch.is_ascii_digit() || *ch == '.') {
+                    number.push(ch);
+                }
+                Token::Number(
+                    number
+                        .parse()
+                        .map_err(|_| anyhow!("invalid number '{number}' in expression"))?,
+                )
+            }
+            ch if ch.is_ascii_alphabetic() || ch == '_' => {
+                let mut identifier = String::from(ch);
+                while let Some(ch) = chars.next_if(|ch| ch.is_ascii_alphanumeric() || *ch == '_') {
+                    identifier.push(ch);
+                }
+                Token::Identifier(identifier)
+            }
+            '%' => Token::Percent,
+            '(' => Token::OpenParen,
+            ')' => Token::CloseParen,
+            '&' => expect_char(&mut chars, '&', Token::And)?,
+            '|' => expect_char(&mut chars, '|', Token::Or)?,
+            '=' => expect_char(&mut chars, '=', Token::Operator(Operator::Equal))?,
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Operator(Operator::NotEqual),
+            '!' => Token::Not,
+            '<' if chars.next_if_eq(&'=').is_some() => Token::Operator(Operator::LessEqual),
+            '<' => Token::Operator(Operator::Less),
+            '>' if chars.next_if_eq(&'=').is_some() => Token::Operator(Operator::GreaterEqual),
+            '>' => Token::Operator(Operator::Greater),
+            ch => bail!("unexpected character '{ch}' in expression"),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+#[inline]
+fn expect_char(chars: &mut Peekable<Chars<'_>>, expected: char, token: Token) -> Result<Token> {
+    if chars.next_if_eq(&expected).is_none() {
+        bail!("expected '{expected}{expected}' in expression");
+    }
+    Ok(token)
+}
+
+/// The maximum number of nested `!` and parentheses in an expression.
+///
+/// Bounds the recursion of [`Parser`] so deeply nested input fails to parse instead of
+/// overflowing the stack.
+const MAX_NESTING_DEPTH: usize = 32;
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn next_if_eq(&mut self, token: &Token) -> bool {
+        let matched = self.peek() == Some(token);
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            bail!("expression is nested too deeply");
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_or(&mut self) -> Result<Node> {
+        let mut node = self.parse_and()?;
+        while self.next_if_eq(&Token::Or) {
+            node = Node::Or(Box::new(node), Box::new(self.parse_and()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_and(&mut self) -> Result<Node> {
+        let mut node = self.parse_not()?;
+        while self.next_if_eq(&Token::And) {
+            node = Node::And(Box::new(node), Box::new(self.parse_not()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_not(&mut self) -> Result<Node> {
+        if self.next_if_eq(&Token::Not) {
+            return Ok(Node::Not(Box::new(self.nested(Self::parse_not)?)));
+        }
+        self.parse_compare()
+    }
+
+    fn parse_compare(&mut self) -> Result<Node> {
+        let lhs = self.parse_primary()?;
+        let Some(Token::Operator(operator)) = self.peek().cloned() else {
+            return Ok(lhs);
+        };
+        self.position += 1;
+
+        Ok(Node::Compare(
+            Box::new(lhs),
+            operator,
+            Box::new(self.parse_primary()?),
+        ))
+    }
+
+    fn parse_primary(&mut self) -> Result<Node> {
+        match self.advance() {
+            Some(Token::Number(number)) => {
+                // Percentage is only a notation, `50%` is the same as `50`
+                self.next_if_eq(&Token::Percent);
+                Ok(Node::Value(Value::Number(number)))
+            }
+            Some(Token::Identifier(identifier)) => match identifier.as_str() {
+                "true" => Ok(Node::Value(Value::Bool(true))),
+                "false" => Ok(Node::Value(Value::Bool(false))),
+                "health" => Ok(Node::Variable(Variable::Health)),
+                "rune" => Ok(Node::Variable(Variable::Rune)),
+                "strangers" => Ok(Node::Variable(Variable::Strangers)),
+                "buff" => {
+                    if !self.next_if_eq(&Token::OpenParen) {
+                        bail!("expected '(' after 'buff' in expression");
+                    }
+                    let Some(Token::Identifier(name)) = self.advance() else {
+                        bail!("expected a buff name in expression");
+                    };
+                    let kind = BuffKind::from_str(&name)
+                        .map_err(|_| anyhow!("unknown buff '{name}' in expression"))?;
+                    if !self.next_if_eq(&Token::CloseParen) {
+                        bail!("expected ')' after buff name in expression");
+                    }
+                    Ok(Node::Variable(Variable::Buff(kind)))
+                }
+                _ => bail!("unknown variable '{identifier}' in expression"),
+            },
+            Some(Token::OpenParen) => {
+                let node = self.nested(Self::parse_or)?;
+                if !self.next_if_eq(&Token::CloseParen) {
+                    bail!("expected ')' in expression");
+                }
+                Ok(node)
+            }
+            Some(token) => bail!("unexpected {token:?} in expression"),
+            None => bail!("unexpected end of expression"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockVariables {
+        health_percent: Option<f64>,
+        buffs: Vec<BuffKind>,
+        rune: bool,
+        strangers: u32,
+    }
+
+    impl Variables for MockVariables {
+        fn health_percent(&self) -> Option<f64> {
+            self.health_percent
+        }
+
+        fn has_buff(&self, kind: BuffKind) -> bool {
+            self.buffs.contains(&kind)
+        }
+
+        fn has_rune(&self) -> bool {
+            self.rune
+        }
+
+        fn stranger_count(&self) -> u32 {
+            self.strangers
+        }
+    }
+
+    fn evaluate(text: &str, variables: &MockVariables) -> bool {
+        text.parse::<Expression>()
+            .expect("expression is valid")
+            .evaluate(variables)
+    }
+
+    #[test]
+    fn expression_evaluate_health_and_buff() {
+        let text = "health < 50% && buff(Rune) == false";
+        let mut variables = MockVariables {
+            health_percent: Some(40.0),
+            ..Default::default()
+        };
+
+        assert!(evaluate(text, &variables));
+
+        variables.buffs.push(BuffKind::Rune);
+        assert!(!evaluate(text, &variables));
+
+        variables.buffs.clear();
+        variables.health_percent = Some(60.0);
+        assert!(!evaluate(text, &variables));
+
+        variables.health_percent = None;
+        assert!(!evaluate(text, &variables));
+    }
+
+    #[test]
+    fn expression_evaluate_precedence() {
+        let variables = MockVariables {
+            rune: true,
+            strangers: 2,
+            ..Default::default()
+        };
+
+        assert!(evaluate("false && rune || strangers >= 2", &variables));
+        assert!(!evaluate("false && (rune || strangers >= 2)", &variables));
+        assert!(evaluate("!(strangers == 0) && !!rune", &variables));
+        assert!(evaluate("strangers != 1 && rune == true", &variables));
+    }
+
+    #[test]
+    fn expression_parse_errors() {
+        assert!("".parse::<Expression>().is_err());
+        assert!("health".parse::<Expression>().is_err());
+        assert!("health < ".parse::<Expression>().is_err());
+        assert!("health & rune".parse::<Expression>().is_err());
+        assert!("health < rune".parse::<Expression>().is_err());
+        assert!("rune < true".parse::<Expression>().is_err());
+        assert!("!health".parse::<Expression>().is_err());
+        assert!("buff(Unknown)".parse::<Expression>().is_err());
+        assert!("(rune".parse::<Expression>().is_err());
+        assert!("rune rune".parse::<Expression>().is_err());
+        assert!("mana < 10".parse::<Expression>().is_err());
+    }
+
+    #[test]
+    fn expression_parse_nesting_limit() {
+        let nested = |depth: usize| {
+            format!("{}true{}", "(".repeat(depth), ")".repeat(depth)).parse::<Expression>()
+        };
+        assert!(nested(MAX_NESTING_DEPTH).is_ok());
+        assert!(nested(MAX_NESTING_DEPTH + 1).is_err());
+        assert!(nested(100_000).is_err());
+        assert!(
+            format!("{}true", "!".repeat(MAX_NESTING_DEPTH))
+                .parse::<Expression>()
+                .is_ok()
+        );
+        assert!(
+            format!("{}true", "!".repeat(100_000))
+                .parse::<Expression>()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Safety interlock for starting the bot after significant configuration changes.
+//!
+//! A [`RunSnapshot`] of the map, preset and character is taken each time the bot starts running
+//! from the UI. Before the next start, the UI queries the changes made since then and asks for
+//! confirmation if there are any so that a half-edited configuration is not run by accident.
+
+use serde_json::Value;
+
+use crate::{Character, Map};
+
+/// The configuration the bot last started running with.
+#[derive(Clone, Debug)]
+pub struct RunSnapshot {
+    map: Map,
+    preset: Option<String>,
+    character: Character,
+}
+
+impl RunSnapshot {
+    pub fn new(map: &Map, preset: Option<String>, character: &Character) -> Self {
+        Self {
+            map: map.clone(),
+            preset,
+            character: character.clone(),
+        }
+    }
+
+    /// Describes the significant changes from this snapshot to `map`, `preset` and `character`.
+    ///
+    /// Returns an empty list if nothing significant changed.
+    pub fn changes(&self, map: &Map, preset: Option<&str>, character: &Character) -> Vec<String> {
+        let mut changes = vec![];
+
+        if self.map.id != map.id {
+            changes.push(format!(
+                "Map changed from {} to {}",
+                self.map.name, map.name
+            ));
+        } else {
+            let deleted = self
+                .map
+                .platforms
+                .iter()
+                .filter(|platform| !map.platforms.contains(platform))
+                .count();
+            if deleted > 0 {
+                changes.push(format!("{deleted} platform(s) deleted"));
+            }
+
+            if self.preset.as_deref() != preset {
+                changes.push(format!(
+                    "Preset changed from {} to {}",
+                    self.preset.as_deref().unwrap_or("none"),
+                    preset.unwrap_or("none")
+                ));
+            } else if let Some(preset) = preset
+                && self.map.actions.get(preset) != map.actions.get(preset)
+            {
+                changes.push(format!("Actions of preset {preset} changed"));
+            }
+        }
+
+        if self.character.id != character.id {
+            changes.push(format!(
+                "Character changed from {} to {}",
+                self.character.name, character.name
+            ));
+        } else {
+            changes.extend(
+                changed_key_bindings(&self.character, character)
+                    .into_iter()
+                    .map(|name| format!("Key binding {name} changed")),
+            );
+            if self.character.actions != character.actions {
+                changes.push("Character actions changed".to_string());
+            }
+        }
+
+        changes
+    }
+}
+
+/// Lists the human-readable names of key binding fields that differ between `old` and `new`.
+fn changed_key_bindings(old: &Character, new: &Character) -> Vec<String> {
+    let (Ok(Value::Object(old)), Ok(Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return vec![];
+    };
+
+    old.iter()
+        .filter(|(field, value)| field.ends_with("_key") && new.get(field.as_str()) != Some(value))
+        .map(|(field, _)| field.replace('_', " "))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KeyBinding, KeyBindingConfiguration, Platform};
+
+    fn snapshot() -> RunSnapshot {
+        let map = Map {
+            id: Some(1),
+            name: "Map".to_string(),
+            platforms: vec![
+                Platform {
+                    x_start: 0,
+                    x_end: 10,
+                    y: 5,
+                },
+                Platform {
+                    x_start: 20,
+                    x_end: 30,
+                    y: 5,
+                },
+            ],
+            ..Default::default()
+        };
+        let character = Character {
+            id: Some(1),
+            name: "Character".to_string(),
+            ..Default::default()
+        };
+
+        RunSnapshot::new(&map, Some("Preset".to_string()), &character)
+    }
+
+    #[test]
+    fn changes_empty_when_unchanged() {
+        let snapshot = snapshot();
+
+        let changes = snapshot.changes(&snapshot.map, Some("Preset"), &snapshot.character);
+
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn changes_lists_deleted_platforms_and_key_bindings() {
+        let snapshot = snapshot();
+        let mut map = snapshot.map.clone();
+        map.platforms.pop();
+        let character = Character {
+            jump_key: KeyBindingConfiguration {
+                key: KeyBinding::Z,
+                enabled: true,
+            },
+            ..snapshot.character.clone()
+        };
+
+        let changes = snapshot.changes(&map, Some("Preset"), &character);
+
+        assert_eq!(
+            changes,
+            vec![
+                "1 platform(s) deleted".to_string(),
+                "Key binding jump key changed".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn changes_lists_new_map_selected() {
+        let snapshot = snapshot();
+        let map = Map {
+            id: Some(2),
+            name: "Other".to_string(),
+            ..Default::default()
+        };
+
+        let changes = snapshot.changes(&map, None, &snapshot.character);
+
+        assert_eq!(changes, vec!["Map changed from Map to Other".to_string()]);
+    }
+}
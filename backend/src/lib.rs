@@ -10,10 +10,14 @@
 #![feature(assert_matches)]
 
 use std::{
-    sync::{LazyLock, Mutex},
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
     time::{Duration, Instant},
 };
 
+use serde::{Deserialize, Serialize};
 use strum::Display;
 use tokio::{
     sync::{
@@ -23,15 +27,27 @@ use tokio::{
     task::spawn_blocking,
 };
 
+use crate::database::RevisionConflict;
+
 mod array;
 mod bridge;
 mod buff;
+mod cache;
+mod changelog;
 mod control;
+mod dashboard;
 mod database;
+mod dataset;
 #[cfg(debug_assertions)]
 mod debug;
 mod detect;
+mod duo;
 mod ecs;
+mod errors;
+mod events;
+mod exp;
+mod expression;
+mod interlock;
 mod mat;
 mod minimap;
 mod models;
@@ -40,39 +56,73 @@ mod notification;
 mod operation;
 mod pathing;
 mod player;
+mod potion;
+mod quickslots;
+mod recording;
+mod remote;
 mod rng;
 mod rotator;
 mod rpc;
 mod run;
+mod sandbox;
+mod scripting;
+mod selfcheck;
 mod services;
+mod simulation;
 mod skill;
+mod spectate;
+mod stats;
+mod status;
 mod task;
+mod telegram;
 mod tracker;
 mod utils;
 
 pub use {
     database::{DatabaseEvent, database_event_receiver},
+    events::{
+        ActionCompleted, MapChanged, MinimapChanged, PlayerDied, RuneDetected, SubscribableEvent,
+        subscribe_event,
+    },
     models::*,
     pathing::MAX_PLATFORMS_COUNT,
-    run::init,
+    run::{MAX_INSTANCES, PRIMARY_INSTANCE, add_instance, init, init_sandbox, instance_count},
+    simulation::{
+        RotationSimulation, RotationSimulationArgs, SimulatedActionSummary, SimulatedCollision,
+        SimulatedFire, simulate_rotation,
+    },
+    spectate::spectate,
+    stats::rotation_suggestions,
+    status::status_title,
     strum::{EnumMessage, IntoEnumIterator, ParseError},
 };
 
 type PendingRequest = (Request, Sender<Response>);
 
-static REQUESTS: LazyLock<(
+type RequestChannel = (
     mpsc::UnboundedSender<PendingRequest>,
     Mutex<mpsc::UnboundedReceiver<PendingRequest>>,
-)> = LazyLock::new(|| {
-    let (tx, rx) = mpsc::unbounded_channel();
-    (tx, Mutex::new(rx))
+);
+
+/// The request channels indexed by bot instance.
+static REQUESTS: LazyLock<[RequestChannel; MAX_INSTANCES]> = LazyLock::new(|| {
+    std::array::from_fn(|_| {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (tx, Mutex::new(rx))
+    })
 });
 
+/// The index of the bot instance requests are sent to.
+static SELECTED_INSTANCE: AtomicUsize = AtomicUsize::new(0);
+
 macro_rules! send_request {
     ($variant:ident $(( $( $field:ident ),* ))?) => {{
         let request = Request::$variant$(( $( $field ),* ))?;
         let (tx, rx) = oneshot::channel();
-        REQUESTS.0.send((request, tx)).expect("channel open");
+        REQUESTS[SELECTED_INSTANCE.load(Ordering::Acquire)]
+            .0
+            .send((request, tx))
+            .expect("channel open");
 
         let response = rx.await.expect("successful response");
         match response {
@@ -84,7 +134,10 @@ macro_rules! send_request {
     ($variant:ident $(( $( $field:ident ),* ))? => ( $( $response:ident ),+ )) => {{
         let request = Request::$variant$(( $( $field ),* ))?;
         let (tx, rx) = oneshot::channel();
-        REQUESTS.0.send((request, tx)).expect("channel open");
+        REQUESTS[SELECTED_INSTANCE.load(Ordering::Acquire)]
+            .0
+            .send((request, tx))
+            .expect("channel open");
 
         let response = rx.await.expect("successful response");
         match response {
@@ -98,11 +151,17 @@ macro_rules! send_request {
 #[derive(Debug)]
 enum Request {
     UpdateOperation(BotOperationUpdate),
+    QueryRunChanges,
+    QueryResumableOperation,
+    ResumeOperation(bool),
+    QueryPresetRecovery,
+    RecoverPreset(bool),
     CreateMap(String),
     UpdateMap(Option<String>, Option<Map>),
     CreateNavigationPath,
     RecaptureNavigationPath(NavigationPath),
     NavigationSnapshotAsGrayscale(String),
+    NavigateToMap(i64),
     UpdateCharacter(Option<Character>),
     RedetectMinimap,
     GameStateReceiver,
@@ -111,13 +170,21 @@ enum Request {
     QueryCaptureHandles,
     SelectCaptureHandle(Option<usize>),
     QueryTemplate(GameTemplate),
+    ReloadTemplates,
+    TestTemplate(String),
     ConvertImageToBase64(Vec<u8>, bool),
     SaveCaptureImage(bool),
+    CalibratePlayerDot(i32, i32),
+    RunSelfCheck,
+    ScanQuickSlots,
+    RecordInputMacro(bool),
+    UpdateDisabledActionTags(Vec<String>),
+    UpdateMinimapVisible(bool),
+    QueryPlatformGraph,
+    QueryPositionHeatmap,
     #[cfg(debug_assertions)]
     DebugStateReceiver,
     #[cfg(debug_assertions)]
-    AutoSaveRune(bool),
-    #[cfg(debug_assertions)]
     InferRune,
     #[cfg(debug_assertions)]
     InferMinimap,
@@ -125,6 +192,16 @@ enum Request {
     RecordImages(bool),
     #[cfg(debug_assertions)]
     TestSpinRune,
+    #[cfg(debug_assertions)]
+    CaptureMobFrame,
+    #[cfg(debug_assertions)]
+    SaveMobLabels(Vec<Bound>),
+    #[cfg(debug_assertions)]
+    TuneDetectionThreshold(TunableDetection, f64),
+    #[cfg(debug_assertions)]
+    BenchmarkDetection,
+    #[cfg(debug_assertions)]
+    TestMouseAlignment(Option<(i32, i32)>),
 }
 
 /// Represents response to UI [`Request`].
@@ -134,11 +211,17 @@ enum Request {
 #[derive(Debug)]
 enum Response {
     UpdateOperation,
+    QueryRunChanges(Vec<String>),
+    QueryResumableOperation(Option<BotOperation>),
+    ResumeOperation,
+    QueryPresetRecovery(Option<PresetRecovery>),
+    RecoverPreset,
     CreateMap(Option<Map>),
     UpdateMap,
     CreateNavigationPath(Option<NavigationPath>),
     RecaptureNavigationPath(NavigationPath),
     NavigationSnapshotAsGrayscale(String),
+    NavigateToMap(bool),
     UpdateCharacter,
     RedetectMinimap,
     GameStateReceiver(broadcast::Receiver<GameState>),
@@ -147,13 +230,21 @@ enum Response {
     QueryCaptureHandles((Vec<String>, Option<usize>)),
     SelectCaptureHandle,
     QueryTemplate(String),
+    ReloadTemplates(Vec<String>),
+    TestTemplate(Option<TemplateMatch>),
     ConvertImageToBase64(Option<String>),
-    SaveCaptureImage,
+    SaveCaptureImage(Option<Vec<u8>>),
+    CalibratePlayerDot(Option<String>),
+    RunSelfCheck(Option<SelfCheck>),
+    ScanQuickSlots(Option<Vec<QuickSlotsSkill>>),
+    RecordInputMacro(Vec<InputMacroKey>),
+    UpdateDisabledActionTags,
+    UpdateMinimapVisible,
+    QueryPlatformGraph(Option<PlatformGraph>),
+    QueryPositionHeatmap(Option<PositionHeatmap>),
     #[cfg(debug_assertions)]
     DebugStateReceiver(broadcast::Receiver<DebugState>),
     #[cfg(debug_assertions)]
-    AutoSaveRune,
-    #[cfg(debug_assertions)]
     InferRune,
     #[cfg(debug_assertions)]
     InferMinimap,
@@ -161,6 +252,16 @@ enum Response {
     RecordImages,
     #[cfg(debug_assertions)]
     TestSpinRune,
+    #[cfg(debug_assertions)]
+    CaptureMobFrame(Option<MobFrame>),
+    #[cfg(debug_assertions)]
+    SaveMobLabels(Option<String>),
+    #[cfg(debug_assertions)]
+    TuneDetectionThreshold(Option<ThresholdPreview>),
+    #[cfg(debug_assertions)]
+    BenchmarkDetection(Option<DetectionBenchmark>),
+    #[cfg(debug_assertions)]
+    TestMouseAlignment(Option<MouseAlignmentPreview>),
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -184,8 +285,113 @@ pub enum GameTemplate {
     HexaConvertButton,
 }
 
+/// The result of matching a template against the currently captured frame.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TemplateMatch {
+    pub bbox: Bound,
+    pub score: f64,
+}
+
+/// A detection checked by [`run_self_check`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct SelfCheckItem {
+    pub name: String,
+    pub passed: bool,
+}
+
+/// The result of running the self-check suite against the currently captured frame.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SelfCheck {
+    pub items: Vec<SelfCheckItem>,
+}
+
+impl SelfCheck {
+    /// The health score as the percentage of passed detections.
+    pub fn score(&self) -> u32 {
+        if self.items.is_empty() {
+            return 0;
+        }
+
+        let passed = self.items.iter().filter(|item| item.passed).count();
+        (passed * 100 / self.items.len()) as u32
+    }
+}
+
+/// A known skill icon checked by [`scan_quick_slots`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct QuickSlotsSkill {
+    pub name: String,
+    /// Whether the skill icon is found in the quick slots.
+    pub found: bool,
+    /// Whether the character or its actions have a key bound to use the skill.
+    pub bound: bool,
+}
+
+impl QuickSlotsSkill {
+    /// Whether the skill is only either found in the quick slots or bound to a key.
+    pub fn is_mismatched(&self) -> bool {
+        self.found != self.bound
+    }
+}
+
+/// The movement of a [`PlatformGraphEdge`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display, Serialize)]
+pub enum PlatformGraphMovement {
+    Walk,
+    Fall,
+    Jump,
+    #[strum(to_string = "Up jump")]
+    UpJump,
+    Grapple,
+    #[strum(to_string = "Double jump")]
+    DoubleJump,
+}
+
+/// A directed edge from a platform to one of its reachable neighbors in a [`PlatformGraph`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+pub struct PlatformGraphEdge {
+    /// The index of the platform in [`PlatformGraph::platforms`] the edge starts from.
+    pub from: usize,
+    /// The index of the platform in [`PlatformGraph::platforms`] the edge ends at.
+    pub to: usize,
+    pub movement: PlatformGraphMovement,
+    /// The cost of the edge during path finding or [`None`] if the path finder refuses it.
+    pub cost: Option<u32>,
+}
+
+/// The platform connectivity graph used for path finding in the current map.
+///
+/// Platforms not connected by an edge are unreachable from one another.
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct PlatformGraph {
+    pub platforms: Vec<Platform>,
+    pub edges: Vec<PlatformGraphEdge>,
+}
+
+/// The number of recorded player positions in each square cell of the current minimap.
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct PositionHeatmap {
+    /// The width and height of a cell in minimap pixels.
+    pub cell_size: usize,
+    pub columns: usize,
+    pub rows: usize,
+    /// The row-major counts of [`Self::columns`] * [`Self::rows`] cells.
+    ///
+    /// The first row starts at y = 0, which is the bottom of the minimap.
+    pub counts: Vec<u32>,
+}
+
+/// An actions preset with edits not stored in the database before the last abnormal termination.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PresetRecovery {
+    pub map_id: i64,
+    pub map_name: String,
+    pub preset: String,
+    pub actions: Vec<Action>,
+}
+
 /// The four quads of a bound.
-#[derive(Clone, Copy, Debug, Display)]
+#[derive(Clone, Copy, Debug, Display, Serialize, Deserialize)]
 pub enum BoundQuadrant {
     TopLeft,
     TopRight,
@@ -198,24 +404,162 @@ pub enum BoundQuadrant {
 #[cfg(debug_assertions)]
 pub struct DebugState {
     pub is_recording: bool,
-    pub is_rune_auto_saving: bool,
+    /// The memory used and budget in bytes of decoded localization templates.
+    pub templates_memory: (usize, usize),
+    /// The memory used and budget in bytes of frames captured for pending notifications.
+    pub notification_frames_memory: (usize, usize),
+}
+
+/// A frame captured for labeling mobs.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg(debug_assertions)]
+pub struct MobFrame {
+    /// The frame as a base64-encoded PNG.
+    pub base64: String,
+    pub width: i32,
+    pub height: i32,
+    /// The bounding boxes of mobs detected by the mob model.
+    pub detections: Vec<Bound>,
+}
+
+/// A frame with the match of a [`TunableDetection`] re-run with a threshold.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg(debug_assertions)]
+pub struct ThresholdPreview {
+    /// The frame as a base64-encoded PNG.
+    pub base64: String,
+    pub width: i32,
+    pub height: i32,
+    /// The bounding box of the match or [`None`] if nothing matches with the threshold.
+    pub bbox: Option<Bound>,
+}
+
+/// The average per-frame latency of detections on the current frame with and without OpenCL.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg(debug_assertions)]
+pub struct DetectionBenchmark {
+    /// The number of frames each latency is averaged over.
+    pub frames: u32,
+    pub cpu_millis: f64,
+    /// The latency with OpenCL or [`None`] if no OpenCL device is available.
+    pub opencl_millis: Option<f64>,
+}
+
+/// A frame for verifying that the mouse moves to where it is sent on the frame.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg(debug_assertions)]
+pub struct MouseAlignmentPreview {
+    /// The frame as a base64-encoded PNG.
+    pub base64: String,
+    pub width: i32,
+    pub height: i32,
+    /// The factor the game window logical pixels are scaled by to physical pixels.
+    ///
+    /// This is [`None`] if the game window cannot be found.
+    pub scale: Option<f32>,
 }
 
 /// A struct for storing game information.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GameState {
     pub position: Option<(i32, i32)>,
     pub health: Option<(u32, u32)>,
+    pub mana: Option<(u32, u32)>,
     pub state: String,
     pub normal_action: Option<String>,
     pub priority_action: Option<String>,
+    /// The next fixed interval priority action and the remaining time until it is due.
+    pub next_priority_action: Option<(String, Duration)>,
     pub erda_shower_state: String,
     pub destinations: Vec<(i32, i32)>,
+    /// The positions of the executing and upcoming normal actions in rotation order.
+    pub waypoints: Vec<ActionWaypoint>,
     pub operation: BotOperation,
     pub frame: Option<(Vec<u8>, usize, usize)>,
     pub platforms_bound: Option<Bound>,
     pub portals: Vec<Bound>,
     pub auto_mob_quadrant: Option<BoundQuadrant>,
+    /// The number of strangers currently in the minimap.
+    pub stranger_count: usize,
+    /// The average number of strangers in the minimap over roughly the last minute.
+    pub crowding_score: f32,
+    /// The error from reloading the current preset's external file.
+    pub external_preset_error: Option<String>,
+    /// The id of the last character automatically selected from the detected in-game name.
+    pub detected_character_id: Option<i64>,
+    pub exp: Option<ExpRate>,
+    /// The number of runes solved in the current session.
+    pub runes_solved: u32,
+    /// The number of failed rune solving attempts in the current session.
+    pub runes_failed: u32,
+    /// The currently active failures.
+    pub errors: Vec<ErrorEvent>,
+    pub frame_timings: FrameTimings,
+    /// The name and the number of times each priority action was dropped for exceeding the
+    /// maximum queue depth.
+    pub priority_actions_dropped: Vec<(String, u32)>,
+}
+
+/// The position of a normal action in rotation order.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ActionWaypoint {
+    pub x: i32,
+    pub y: i32,
+    /// Whether this is the position of the currently executing normal action.
+    pub current: bool,
+}
+
+/// The rate frames are processed at and the time spent on each stage of processing a frame.
+#[derive(Clone, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub struct FrameTimings {
+    /// The number of frames processed over the last second.
+    pub fps: u32,
+    /// The number of frames skipped over the last second because processing ran late.
+    pub skipped_frames: u32,
+    /// The number of frames captured over the last second.
+    ///
+    /// This can be higher than [`Self::fps`] when capturing on every monitor refresh.
+    pub capture_fps: u32,
+    /// The time spent on each stage (e.g. capture, minimap) of the last processed frame.
+    pub stages: Vec<(String, Duration)>,
+    /// The time from capturing the last processed frame until the player finished detecting.
+    pub capture_to_detection: Duration,
+    /// The time from capturing the last processed frame until inputs are dispatched.
+    ///
+    /// This includes the age of a frame reused on skipped capture ticks.
+    pub capture_to_dispatch: Duration,
+}
+
+/// A failure surfaced to the UI status bar.
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Serialize, Deserialize,
+)]
+pub enum ErrorEvent {
+    /// The game window cannot be captured.
+    #[strum(to_string = "Capture lost")]
+    CaptureLost,
+    /// The input method failed to send inputs.
+    #[strum(to_string = "Input rejected")]
+    InputRejected,
+    /// The minimap has not been detected for a while.
+    #[strum(to_string = "Detection degraded")]
+    DetectionDegraded,
+    /// Writing to the database failed.
+    #[strum(to_string = "Database write failed")]
+    DatabaseWriteFailed,
+}
+
+/// The EXP rate estimated from the EXP bar.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ExpRate {
+    pub level: Option<u32>,
+    pub percent: f32,
+    /// The EXP percentage gained per hour.
+    ///
+    /// This is [`None`] until enough samples are collected.
+    pub percent_per_hour: Option<f32>,
+    /// The estimated duration until the next level.
+    pub time_to_level: Option<Duration>,
 }
 
 #[derive(PartialEq, Clone, Copy, Debug)]
@@ -232,6 +576,31 @@ pub enum BotOperationUpdate {
     Halt,
     TemporaryHalt,
     Run,
+    /// Replaces the wall-clock schedule without changing the current operation.
+    Schedule(OperationSchedule),
+}
+
+/// The result of upserting an entity guarded by its revision.
+#[derive(Debug)]
+pub enum Upsert<T> {
+    /// The entity was saved with its revision incremented.
+    Saved(T),
+    /// The entity was modified since it was read.
+    ///
+    /// Contains the stored copy to merge with. The entity can be overwritten by upserting it
+    /// again with the revision of the stored copy.
+    Conflict(T),
+    Failed,
+}
+
+impl<T> Upsert<T> {
+    /// Returns the saved entity or [`None`] if not saved.
+    pub fn saved(self) -> Option<T> {
+        match self {
+            Upsert::Saved(value) => Some(value),
+            Upsert::Conflict(_) | Upsert::Failed => None,
+        }
+    }
 }
 
 /// Updates the bot current's operation.
@@ -239,6 +608,35 @@ pub async fn update_operation(update: BotOperationUpdate) {
     send_request!(UpdateOperation(update))
 }
 
+/// Queries the significant map and character changes made since the bot last started running.
+///
+/// The UI should ask for confirmation before running again if the list is not empty.
+pub async fn query_run_changes() -> Vec<String> {
+    send_request!(QueryRunChanges => (changes))
+}
+
+/// Queries the operation saved before the last restart that is pending to be resumed.
+pub async fn query_resumable_operation() -> Option<BotOperation> {
+    send_request!(QueryResumableOperation => (operation))
+}
+
+/// Resumes the operation from [`query_resumable_operation`] if `resume` is true or discards it.
+pub async fn resume_operation(resume: bool) {
+    send_request!(ResumeOperation(resume))
+}
+
+/// Queries the actions preset auto-saved with edits that were not stored in the database before
+/// the last abnormal termination.
+pub async fn query_preset_recovery() -> Option<PresetRecovery> {
+    send_request!(QueryPresetRecovery => (recovery))
+}
+
+/// Restores the preset from [`query_preset_recovery`] to its map if `restore` is true or
+/// discards it.
+pub async fn recover_preset(restore: bool) {
+    send_request!(RecoverPreset(restore))
+}
+
 /// Queries localization from the database.
 pub async fn query_localization() -> Localization {
     spawn_blocking(database::query_or_upsert_localization)
@@ -275,6 +673,18 @@ pub async fn upsert_settings(mut settings: Settings) -> Settings {
     .unwrap()
 }
 
+/// Exports the local rune dataset captured with [`Settings::enable_rune_dataset`] as a zip
+/// archive.
+///
+/// Returns the path to the archive or [`None`] if the dataset is empty or fails to export.
+pub async fn export_rune_dataset() -> Option<String> {
+    spawn_blocking(dataset::export_rune_dataset)
+        .await
+        .unwrap()
+        .ok()
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
 /// Queries maps from the database.
 pub async fn query_maps() -> Option<Vec<Map>> {
     spawn_blocking(database::query_maps).await.unwrap().ok()
@@ -290,13 +700,41 @@ pub async fn create_map(name: String) -> Option<Map> {
 /// Upserts `map` to the database.
 ///
 /// If `map` does not previously exist, a new one will be created and its `id` will
-/// be updated.
+/// be updated. Otherwise, the write is queued and coalesced with other upserts of the same
+/// `map` in quick succession.
 ///
-/// Returns the updated [`Minimap`] on success.
-pub async fn upsert_map(mut map: Map) -> Option<Map> {
-    spawn_blocking(move || database::upsert_map(&mut map).is_ok().then_some(map))
-        .await
-        .unwrap()
+/// Returns [`Upsert::Conflict`] without saving if `map` was modified since it was read.
+pub async fn upsert_map(mut map: Map) -> Upsert<Map> {
+    spawn_blocking(move || match database::upsert_map(&mut map) {
+        Ok(()) => Upsert::Saved(map),
+        Err(err) if err.is::<RevisionConflict>() => database::query_map(map.id.unwrap())
+            .ok()
+            .flatten()
+            .map(Upsert::Conflict)
+            .unwrap_or(Upsert::Failed),
+        Err(_) => Upsert::Failed,
+    })
+    .await
+    .unwrap()
+}
+
+/// Applies `edit` to `map` and upserts it to the database.
+///
+/// If `map` was modified since it was read, `edit` is re-applied to the stored copy instead so
+/// that the modification is merged rather than overwritten.
+///
+/// Returns the updated [`Map`] on success.
+pub async fn upsert_map_with(
+    mut map: Map,
+    edit: impl Fn(&mut Map) + Send + 'static,
+) -> Option<Map> {
+    spawn_blocking(move || {
+        database::upsert_map_with(&mut map, edit)
+            .is_ok()
+            .then_some(map)
+    })
+    .await
+    .unwrap()
 }
 
 /// Updates the current map used by the main game loop.
@@ -349,6 +787,14 @@ pub async fn recapture_navigation_path(path: NavigationPath) -> NavigationPath {
     send_request!(RecaptureNavigationPath(path) => (path))
 }
 
+/// Navigates the player to the path attached to map `map_id` through the portals of navigation
+/// paths.
+///
+/// Returns `false` if the map does not exist or has no attached path.
+pub async fn navigate_to_map(map_id: i64) -> bool {
+    send_request!(NavigateToMap(map_id) => (navigating))
+}
+
 pub async fn navigation_snapshot_as_grayscale(base64: String) -> String {
     send_request!(NavigationSnapshotAsGrayscale(base64) => (base64))
 }
@@ -362,6 +808,96 @@ pub async fn delete_navigation_paths(paths: NavigationPaths) -> bool {
         .unwrap()
 }
 
+/// Queries global presets from the database.
+pub async fn query_global_presets() -> Option<Vec<GlobalPreset>> {
+    spawn_blocking(database::query_global_presets)
+        .await
+        .unwrap()
+        .ok()
+}
+
+/// Upserts `preset` to the database.
+///
+/// Returns the updated [`GlobalPreset`] on success.
+pub async fn upsert_global_preset(mut preset: GlobalPreset) -> Option<GlobalPreset> {
+    spawn_blocking(move || {
+        database::upsert_global_preset(&mut preset)
+            .is_ok()
+            .then_some(preset)
+    })
+    .await
+    .unwrap()
+}
+
+/// Deletes `preset` from the database.
+///
+/// Returns `true` if `preset` was deleted.
+pub async fn delete_global_preset(preset: GlobalPreset) -> bool {
+    spawn_blocking(move || database::delete_global_preset(&preset).is_ok())
+        .await
+        .unwrap()
+}
+
+/// Queries input macros from the database.
+pub async fn query_input_macros() -> Option<Vec<InputMacro>> {
+    spawn_blocking(database::query_input_macros)
+        .await
+        .unwrap()
+        .ok()
+}
+
+/// Upserts `input_macro` to the database.
+///
+/// Returns the updated [`InputMacro`] on success.
+pub async fn upsert_input_macro(mut input_macro: InputMacro) -> Option<InputMacro> {
+    spawn_blocking(move || {
+        database::upsert_input_macro(&mut input_macro)
+            .is_ok()
+            .then_some(input_macro)
+    })
+    .await
+    .unwrap()
+}
+
+/// Deletes `input_macro` from the database.
+///
+/// Returns `true` if `input_macro` was deleted.
+pub async fn delete_input_macro(input_macro: InputMacro) -> bool {
+    spawn_blocking(move || database::delete_input_macro(&input_macro).is_ok())
+        .await
+        .unwrap()
+}
+
+/// Queries the statistics of recorded sessions from the database.
+///
+/// Sessions are ordered with the most recent first. The current session is persisted
+/// periodically so it may lag behind by a few seconds.
+pub async fn query_session_stats() -> Option<Vec<SessionStats>> {
+    spawn_blocking(database::query_session_stats)
+        .await
+        .unwrap()
+        .ok()
+}
+
+/// Queries the recorded changes to maps, characters and settings from the database.
+///
+/// Changes are ordered with the most recent first.
+pub async fn query_config_changes() -> Option<Vec<ConfigChange>> {
+    spawn_blocking(database::query_config_changes)
+        .await
+        .unwrap()
+        .ok()
+}
+
+/// Reverts the fields changed by `change` while keeping changes made afterward.
+///
+/// Returns `true` if the change was reverted.
+pub async fn revert_config_change(change: ConfigChange) -> bool {
+    spawn_blocking(move || database::revert_config_change(&change).is_ok())
+        .await
+        .unwrap()
+}
+
 /// Queries characters from the database.
 pub async fn query_characters() -> Option<Vec<Character>> {
     spawn_blocking(database::query_characters)
@@ -373,14 +909,21 @@ pub async fn query_characters() -> Option<Vec<Character>> {
 /// Upserts `character` to the database.
 ///
 /// If `character` does not previously exist, a new one will be created and its `id` will
-/// be updated.
+/// be updated. Otherwise, the write is queued and coalesced with other upserts of the same
+/// `character` in quick succession.
 ///
-/// Returns the updated [`Character`] on success.
-pub async fn upsert_character(mut character: Character) -> Option<Character> {
-    spawn_blocking(move || {
-        database::upsert_character(&mut character)
-            .is_ok()
-            .then_some(character)
+/// Returns [`Upsert::Conflict`] without saving if `character` was modified since it was read.
+pub async fn upsert_character(mut character: Character) -> Upsert<Character> {
+    spawn_blocking(move || match database::upsert_character(&mut character) {
+        Ok(()) => Upsert::Saved(character),
+        Err(err) if err.is::<RevisionConflict>() => {
+            database::query_character(character.id.unwrap())
+                .ok()
+                .flatten()
+                .map(Upsert::Conflict)
+                .unwrap_or(Upsert::Failed)
+        }
+        Err(_) => Upsert::Failed,
     })
     .await
     .unwrap()
@@ -405,10 +948,16 @@ pub async fn redetect_minimap() {
 }
 
 pub async fn game_state_receiver() -> broadcast::Receiver<GameState> {
+    if let Some(spectator) = spectate::spectator() {
+        return spectator.subscribe_state();
+    }
     send_request!(GameStateReceiver => (receiver))
 }
 
 pub async fn key_receiver() -> broadcast::Receiver<KeyBinding> {
+    if let Some(spectator) = spectate::spectator() {
+        return spectator.subscribe_key();
+    }
     send_request!(KeyReceiver => (receiver))
 }
 
@@ -428,22 +977,95 @@ pub async fn query_template(template: GameTemplate) -> String {
     send_request!(QueryTemplate(template) => (base64))
 }
 
+/// Reloads the user provided PNGs in the `templates` folder next to the executable.
+///
+/// Each PNG is named after a compiled-in template and is used in preference to it. Returns the
+/// file names of the loaded templates.
+pub async fn reload_templates() -> Vec<String> {
+    send_request!(ReloadTemplates => (names))
+}
+
+/// Tests base64-encoded PNG `base64` template against the currently captured frame.
+///
+/// Returns [`None`] if there is no captured frame or the template is invalid.
+pub async fn test_template(base64: String) -> Option<TemplateMatch> {
+    send_request!(TestTemplate(base64) => (result))
+}
+
 pub async fn convert_image_to_base64(image: Vec<u8>, is_grayscale: bool) -> Option<String> {
     send_request!(ConvertImageToBase64(image, is_grayscale) => (base64))
 }
 
-pub async fn save_capture_image(is_grayscale: bool) {
-    send_request!(SaveCaptureImage(is_grayscale))
+/// Saves the currently captured image to the `datasets` folder.
+///
+/// Returns the saved image encoded as PNG or [`None`] if nothing is captured.
+pub async fn save_capture_image(is_grayscale: bool) -> Option<Vec<u8>> {
+    send_request!(SaveCaptureImage(is_grayscale) => (image))
 }
 
-#[cfg(debug_assertions)]
-pub async fn debug_state_receiver() -> broadcast::Receiver<DebugState> {
-    send_request!(DebugStateReceiver => (receiver))
+/// Extracts the player dot at the user confirmed minimap position `(x, y)` as a base64-encoded
+/// PNG template.
+///
+/// Returns [`None`] if the minimap is not detected or no dot is found near the position.
+pub async fn calibrate_player_dot(x: i32, y: i32) -> Option<String> {
+    send_request!(CalibratePlayerDot(x, y) => (base64))
+}
+
+/// Runs the self-check suite of essential detections against the live game client.
+///
+/// Returns [`None`] if no frame has been captured yet.
+pub async fn run_self_check() -> Option<SelfCheck> {
+    send_request!(RunSelfCheck => (check))
+}
+
+/// Scans the quick slots for known skill icons and checks them against the key bindings of the
+/// currently in use character and actions.
+///
+/// Returns [`None`] if no frame has been captured yet.
+pub async fn scan_quick_slots() -> Option<Vec<QuickSlotsSkill>> {
+    send_request!(ScanQuickSlots => (skills))
+}
+
+/// Starts recording the received keys if `recording` is `true` or stops recording otherwise.
+///
+/// Returns the keys recorded since the recording started when stopping. The key to toggle
+/// actions is not recorded.
+pub async fn record_input_macro(recording: bool) -> Vec<InputMacroKey> {
+    send_request!(RecordInputMacro(recording) => (keys))
+}
+
+/// Disables all actions having any of `tags` until updated again.
+///
+/// This is in addition to the tags the current map disables while strangers are present.
+pub async fn update_disabled_action_tags(tags: Vec<String>) {
+    send_request!(UpdateDisabledActionTags(tags))
+}
+
+/// Updates whether the UI minimap is currently visible.
+///
+/// While not visible, the minimap frame of [`GameState`] is not encoded and broadcasted unless
+/// another listener such as the dashboard needs it.
+pub async fn update_minimap_visible(visible: bool) {
+    send_request!(UpdateMinimapVisible(visible))
+}
+
+/// Queries the platform connectivity graph computed for the current map.
+///
+/// Returns [`None`] if the minimap is not detected.
+pub async fn query_platform_graph() -> Option<PlatformGraph> {
+    send_request!(QueryPlatformGraph => (graph))
+}
+
+/// Queries the heatmap of the player positions recorded in the current map.
+///
+/// Returns [`None`] if the minimap is not detected.
+pub async fn query_position_heatmap() -> Option<PositionHeatmap> {
+    send_request!(QueryPositionHeatmap => (heatmap))
 }
 
 #[cfg(debug_assertions)]
-pub async fn auto_save_rune(auto_save: bool) {
-    send_request!(AutoSaveRune(auto_save))
+pub async fn debug_state_receiver() -> broadcast::Receiver<DebugState> {
+    send_request!(DebugStateReceiver => (receiver))
 }
 
 #[cfg(debug_assertions)]
@@ -466,6 +1088,67 @@ pub async fn test_spin_rune() {
     send_request!(TestSpinRune)
 }
 
-fn poll_request() -> Option<PendingRequest> {
-    LazyLock::force(&REQUESTS).1.lock().unwrap().try_recv().ok()
+/// Captures the current frame with the detected mobs for labeling.
+///
+/// The frame is kept until [`save_mob_labels`] is called or another frame is captured.
+#[cfg(debug_assertions)]
+pub async fn capture_mob_frame() -> Option<MobFrame> {
+    send_request!(CaptureMobFrame => (frame))
+}
+
+/// Saves the last captured frame with the reviewed `mobs` as YOLO labels to the mob dataset.
+///
+/// Returns the name of the saved sample or [`None`] if there is no captured frame.
+#[cfg(debug_assertions)]
+pub async fn save_mob_labels(mobs: Vec<Bound>) -> Option<String> {
+    send_request!(SaveMobLabels(mobs) => (name))
+}
+
+/// Re-runs `detection` with `threshold` on the current frame for previewing the match.
+///
+/// Returns [`None`] if there is no captured frame.
+#[cfg(debug_assertions)]
+pub async fn tune_detection_threshold(
+    detection: TunableDetection,
+    threshold: f64,
+) -> Option<ThresholdPreview> {
+    send_request!(TuneDetectionThreshold(detection, threshold) => (preview))
+}
+
+/// Benchmarks the per-frame latency of detections on the current frame on CPU and through OpenCL.
+///
+/// Returns [`None`] if there is no captured frame.
+#[cfg(debug_assertions)]
+pub async fn benchmark_detection() -> Option<DetectionBenchmark> {
+    send_request!(BenchmarkDetection => (benchmark))
+}
+
+/// Moves the mouse to `target` on the frame if provided and previews the current frame.
+///
+/// The game cursor on the frame should be at `target` if mouse coordinates are aligned with the
+/// game window, including when the window is scaled by display scaling.
+///
+/// Returns [`None`] if there is no captured frame.
+#[cfg(debug_assertions)]
+pub async fn test_mouse_alignment(target: Option<(i32, i32)>) -> Option<MouseAlignmentPreview> {
+    send_request!(TestMouseAlignment(target) => (preview))
+}
+
+/// Selects the bot instance that subsequent requests are sent to.
+///
+/// Requests sent before the selection are still handled by the previously selected instance.
+/// Does nothing if `instance` has not been added.
+pub fn select_instance(instance: usize) {
+    if instance < instance_count() {
+        SELECTED_INSTANCE.store(instance, Ordering::Release);
+    }
+}
+
+/// Gets the index of the bot instance requests are sent to.
+pub fn selected_instance() -> usize {
+    SELECTED_INSTANCE.load(Ordering::Acquire)
+}
+
+fn poll_request(instance: usize) -> Option<PendingRequest> {
+    REQUESTS[instance].1.lock().unwrap().try_recv().ok()
 }
@@ -1,29 +1,101 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
 use anyhow::Result;
 use opencv::{
     boxed_ref::BoxedRef,
-    core::{Mat, Vec4b},
+    core::{
+        _InputArrayTraitConst, Mat, MatTraitConst, MatTraitConstManual, Size, ToInputArray, UMat,
+        Vec4b, have_opencl, set_use_opencl,
+    },
+    imgproc::{INTER_AREA, resize},
 };
 use platforms::capture::Frame;
 
+/// Whether detection runs on GPU through OpenCL [`UMat`]s.
+static OPENCL_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether subsequent detections run on GPU through OpenCL [`UMat`]s.
+///
+/// Returns whether OpenCL is enabled, which is `false` if no OpenCL device is available.
+pub fn set_opencl_enabled(enabled: bool) -> bool {
+    let enabled = enabled && have_opencl().unwrap_or(false);
+    OPENCL_ENABLED.store(enabled, Ordering::Relaxed);
+    enabled
+}
+
+/// Whether detection runs on GPU through OpenCL [`UMat`]s.
+#[inline]
+pub fn opencl_enabled() -> bool {
+    OPENCL_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Uploads `array` to a [`UMat`] if OpenCL is enabled.
+///
+/// Returns [`None`] if OpenCL is disabled or uploading fails so that the caller can fall back to
+/// `array` on CPU.
+pub fn to_umat(array: &impl ToInputArray) -> Option<UMat> {
+    if !opencl_enabled() {
+        return None;
+    }
+    // OpenCL usage is per thread in OpenCV and detections can run on any thread
+    set_use_opencl(true).ok()?;
+
+    let mut umat = UMat::new_def();
+    array.input_array().ok()?.copy_to(&mut umat).ok()?;
+    Some(umat)
+}
+
 /// A BGRA [`Mat`] that owns the external buffer.
 #[derive(Debug)]
 pub struct OwnedMat {
     rows: i32,
     cols: i32,
     bytes: Vec<u8>,
+    captured_at: Instant,
 }
 
 impl OwnedMat {
+    /// Creates a new `Mat` from `frame`.
+    ///
+    /// A frame scaled from the window logical pixels (see [`Frame::scale`]) is scaled back so that
+    /// the `Mat` is always in the game logical pixels.
     #[inline]
     pub fn new(frame: Frame) -> Result<Self> {
+        let scale = frame.scale;
         let owned = Self {
             rows: frame.height,
             cols: frame.width,
             bytes: frame.data,
+            captured_at: frame.captured_at,
         };
-        let _ = owned.as_mat_inner()?;
+        let mat = owned.as_mat_inner()?;
+        if scale == 1.0 || scale <= 0.0 {
+            drop(mat);
+            return Ok(owned);
+        }
+
+        let size = Size::new(
+            (owned.cols as f32 / scale).round() as i32,
+            (owned.rows as f32 / scale).round() as i32,
+        );
+        let mut resized = Mat::default();
+        resize(&mat, &mut resized, size, 0.0, 0.0, INTER_AREA)?;
 
-        Ok(owned)
+        Ok(Self {
+            rows: resized.rows(),
+            cols: resized.cols(),
+            bytes: resized.data_bytes()?.to_vec(),
+            captured_at: owned.captured_at,
+        })
+    }
+
+    /// Gets the instant the frame of this `Mat` is captured.
+    #[inline]
+    pub fn captured_at(&self) -> Instant {
+        self.captured_at
     }
 
     pub fn as_mat(&self) -> BoxedRef<'_, Mat> {
@@ -42,12 +114,11 @@ impl OwnedMat {
 #[cfg(debug_assertions)]
 impl From<Mat> for OwnedMat {
     fn from(value: Mat) -> Self {
-        use opencv::core::{MatTraitConst, MatTraitConstManual};
-
         Self {
             rows: value.rows(),
             cols: value.cols(),
             bytes: value.data_bytes().unwrap().to_vec(),
+            captured_at: Instant::now(),
         }
     }
 }
@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     fmt,
     hash::{Hash, Hasher},
     sync::Arc,
@@ -7,9 +7,10 @@ use std::{
 
 use anyhow::{Result, anyhow};
 use log::{debug, info};
-use opencv::core::{MatTraitConst, Point, Rect, Vec4b};
+use opencv::core::{Mat, MatTraitConst, Point, Rect, Vec4b, absdiff, mean_def};
 
 use crate::{
+    PositionHeatmap,
     array::Array,
     detect::{Detector, OtherPlayerKind},
     ecs::{Resources, transition, transition_if, try_some_transition},
@@ -18,11 +19,32 @@ use crate::{
         MAX_PLATFORMS_COUNT, Platform, PlatformWithNeighbors, find_neighbors, find_platforms_bound,
     },
     player::{DOUBLE_JUMP_THRESHOLD, GRAPPLING_MAX_THRESHOLD, JUMP_THRESHOLD, Player},
+    run::MS_PER_TICK_F32,
     task::{Task, Update, update_detection_task},
 };
 
-const MINIMAP_BORDER_WHITENESS_THRESHOLD: u8 = 160;
+pub const MINIMAP_BORDER_WHITENESS_THRESHOLD: u8 = 160;
 const MAX_PORTALS_COUNT: usize = 16;
+/// The mean absolute pixel difference above which the minimap is considered changed.
+const MINIMAP_CHANGE_THRESHOLD: f64 = 2.0;
+/// The approximate window in milliseconds the crowding score is averaged over.
+const CROWDING_SCORE_WINDOW_MILLIS: f32 = 60000.0;
+
+/// The number of frames the anchor pixels are median filtered over in robust detection.
+const ROBUST_FRAMES_COUNT: usize = 3;
+
+/// The number of consecutive frames both anchors must mismatch in robust detection before the
+/// minimap is re-detected.
+const ROBUST_MAX_ANCHORS_MISMATCH_COUNT: u32 = 3;
+
+/// The multiplier to the maximum detection fail counts in robust detection.
+const ROBUST_FAIL_COUNT_MULTIPLIER: u32 = 2;
+
+/// The maximum number of player positions kept for the heatmap, about 30 minutes of ticks.
+const MAX_POSITIONS_COUNT: usize = (30.0 * 60.0 * 1000.0 / MS_PER_TICK_F32) as usize;
+
+/// The width and height of a square cell in the position heatmap.
+const POSITION_HEATMAP_CELL_SIZE: usize = 2;
 
 /// A wrapper struct for [`Rect`] that implements [`Hash`].
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -63,17 +85,40 @@ pub struct MinimapContext {
     /// Task to detect elite boss.
     has_elite_boss_task: Option<Task<Result<()>>>,
     /// Task to detect guildie player(s) in the minimap.
-    has_guildie_player_task: Option<Task<Result<()>>>,
+    has_guildie_player_task: Option<Task<Result<usize>>>,
     /// Task to detect stranger player(s) in the minimap.
-    has_stranger_player_task: Option<Task<Result<()>>>,
+    has_stranger_player_task: Option<Task<Result<usize>>>,
     /// Task to detect firend player(s) in the minimap.
-    has_friend_player_task: Option<Task<Result<()>>>,
+    has_friend_player_task: Option<Task<Result<usize>>>,
+    /// The minimap image at the last time it was considered changed.
+    ///
+    /// Used for diffing against the current minimap to skip redundant detections.
+    snapshot: Option<Mat>,
 
     platforms: Vec<Platform>,
     /// Whether to update the [`MinimapIdle::platforms`].
     ///
     /// This is set to true each time [`Self::data`] is updated.
     platforms_dirty: bool,
+    /// Whether detection should tolerate screen shake and heavy weather effects.
+    ///
+    /// When enabled, the anchor pixels and the minimap bounding box are median filtered over the
+    /// last [`ROBUST_FRAMES_COUNT`] frames and detections respectively, single-frame anchor
+    /// mismatches do not re-detect the minimap and detections are allowed to fail more times
+    /// before being cleared.
+    robust: bool,
+    /// The anchor pixels of the last [`ROBUST_FRAMES_COUNT`] frames.
+    anchor_pixels: VecDeque<(Vec4b, Vec4b)>,
+    /// The detected minimap bounding boxes of the last [`ROBUST_FRAMES_COUNT`] detections.
+    bboxes: VecDeque<Rect>,
+    /// The number of consecutive frames both anchors mismatched.
+    anchors_mismatch_count: u32,
+    /// The last [`MAX_POSITIONS_COUNT`] recorded player positions.
+    positions: VecDeque<Point>,
+    /// The width and height of the minimap [`Self::positions`] are recorded in.
+    ///
+    /// The positions are cleared when a minimap of a different size is detected.
+    positions_size: Option<(i32, i32)>,
 }
 
 impl MinimapContext {
@@ -86,6 +131,49 @@ impl MinimapContext {
         self.platforms = platforms;
         self.platforms_dirty = true;
     }
+
+    /// Sets whether detection should tolerate screen shake and heavy weather effects.
+    ///
+    /// The raised detection fail counts only take effect after the minimap is re-detected.
+    pub fn set_robust(&mut self, robust: bool) {
+        self.robust = robust;
+        self.anchor_pixels.clear();
+        self.bboxes.clear();
+        self.anchors_mismatch_count = 0;
+    }
+
+    /// Records the player `position` for the position heatmap.
+    ///
+    /// The oldest position is discarded when there are already [`MAX_POSITIONS_COUNT`] positions.
+    pub fn record_position(&mut self, position: Point) {
+        if self.positions.len() >= MAX_POSITIONS_COUNT {
+            self.positions.pop_front();
+        }
+        self.positions.push_back(position);
+    }
+
+    /// Aggregates the recorded positions into a heatmap of the minimap with `width` and
+    /// `height`.
+    pub fn position_heatmap(&self, width: i32, height: i32) -> PositionHeatmap {
+        let columns = (width.max(0) as usize).div_ceil(POSITION_HEATMAP_CELL_SIZE);
+        let rows = (height.max(0) as usize).div_ceil(POSITION_HEATMAP_CELL_SIZE);
+        let mut counts = vec![0; columns * rows];
+        for position in self.positions.iter() {
+            if !(0..width).contains(&position.x) || !(0..height).contains(&position.y) {
+                continue;
+            }
+            let column = position.x as usize / POSITION_HEATMAP_CELL_SIZE;
+            let row = position.y as usize / POSITION_HEATMAP_CELL_SIZE;
+            counts[row * columns + column] += 1;
+        }
+
+        PositionHeatmap {
+            cell_size: POSITION_HEATMAP_CELL_SIZE,
+            columns,
+            rows,
+            counts,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -129,16 +217,25 @@ pub struct MinimapIdle {
     ///
     /// It is partially overlapped by other UIs if one of the anchor mismatches.
     pub partially_overlapping: bool,
+    /// Whether the minimap has changed noticeably since the last snapshot.
+    ///
+    /// Small changes such as the player moving a few pixels do not count as changed.
+    pub changed: bool,
     /// The rune position.
     ///
     /// The rune position is in player-relative coordinate, which is bottom-left.
     rune: Threshold<Point>,
-    /// Whether there is a guildie.
-    has_guildie_player: Threshold<()>,
-    /// Whether there is a stranger.
-    has_stranger_player: Threshold<()>,
-    /// Whether there is a friend.
-    has_friend_player: Threshold<()>,
+    /// The number of guildies if there is any.
+    has_guildie_player: Threshold<usize>,
+    /// The number of strangers if there is any.
+    has_stranger_player: Threshold<usize>,
+    /// The number of friends if there is any.
+    has_friend_player: Threshold<usize>,
+    /// The average number of strangers over roughly the last [`CROWDING_SCORE_WINDOW_MILLIS`].
+    ///
+    /// This smooths out strangers briefly passing through so that only a sustained crowd results
+    /// in a high score.
+    pub crowding_score: f32,
     /// The portal positions.
     ///
     /// The portals are in player-relative coordinate, which is bottom-left.
@@ -178,7 +275,12 @@ impl MinimapIdle {
 
     #[cfg(test)]
     pub fn set_has_any_other_player(&mut self, has_any_other_player: bool) {
-        self.has_stranger_player.value = has_any_other_player.then_some(());
+        self.has_stranger_player.value = has_any_other_player.then_some(1);
+    }
+
+    #[inline]
+    pub fn stranger_count(&self) -> usize {
+        self.has_stranger_player.value.unwrap_or_default()
     }
 
     #[inline]
@@ -213,23 +315,34 @@ pub fn run_system(resources: &Resources, minimap: &mut MinimapEntity, player_sta
 }
 
 fn update_detecting_state(resources: &Resources, minimap: &mut MinimapEntity) {
-    let Update::Ok((anchors, bbox)) = update_detection_task(
+    let bboxes = minimap
+        .context
+        .robust
+        .then(|| minimap.context.bboxes.clone());
+    let Update::Ok((anchors, bbox, detected_bbox)) = update_detection_task(
         resources,
         2000,
         &mut minimap.context.minimap_task,
         move |detector| {
-            let bbox = detector.detect_minimap(MINIMAP_BORDER_WHITENESS_THRESHOLD)?;
+            let detected_bbox = detector.detect_minimap(MINIMAP_BORDER_WHITENESS_THRESHOLD)?;
+            // Anchors are taken at the filtered bounding box so that they are verified there
+            let bbox = bboxes.map_or(detected_bbox, |mut bboxes| {
+                median_bbox(&mut bboxes, detected_bbox)
+            });
             let size = bbox.width.min(bbox.height) as usize;
             let tl = anchor_at(&detector.mat(), bbox.tl(), size, 1)?;
             let br = anchor_at(&detector.mat(), bbox.br(), size, -1)?;
             let anchors = Anchors { tl, br };
 
             debug!(target: "minimap", "anchor points: {anchors:?}");
-            Ok((anchors, bbox))
+            Ok((anchors, bbox, detected_bbox))
         },
     ) else {
         return;
     };
+    if minimap.context.robust {
+        median_bbox(&mut minimap.context.bboxes, detected_bbox);
+    }
 
     let (platforms, platforms_bound) = platforms_and_bound(bbox, &minimap.context.platforms);
     minimap.context.platforms_dirty = false;
@@ -240,14 +353,24 @@ fn update_detecting_state(resources: &Resources, minimap: &mut MinimapEntity) {
     minimap.context.has_guildie_player_task = None;
     minimap.context.has_stranger_player_task = None;
     minimap.context.has_friend_player_task = None;
+    minimap.context.snapshot = None;
+    minimap.context.anchor_pixels.clear();
+    minimap.context.anchors_mismatch_count = 0;
+    if minimap.context.positions_size != Some((bbox.width, bbox.height)) {
+        minimap.context.positions.clear();
+        minimap.context.positions_size = Some((bbox.width, bbox.height));
+    }
+    let robust = minimap.context.robust;
     minimap.state = Minimap::Idle(MinimapIdle {
         anchors,
         bbox,
         partially_overlapping: false,
-        rune: Threshold::new(3),
-        has_guildie_player: Threshold::new(2),
-        has_stranger_player: Threshold::new(2),
-        has_friend_player: Threshold::new(2),
+        changed: true,
+        rune: Threshold::new(max_fail_count(3, robust)),
+        has_guildie_player: Threshold::new(max_fail_count(2, robust)),
+        has_stranger_player: Threshold::new(max_fail_count(2, robust)),
+        has_friend_player: Threshold::new(max_fail_count(2, robust)),
+        crowding_score: 0.0,
         portals: Array::new(),
         platforms,
         platforms_bound,
@@ -269,6 +392,7 @@ fn update_idle_state(
         has_guildie_player,
         has_stranger_player,
         has_friend_player,
+        crowding_score,
         portals,
         mut platforms,
         mut platforms_bound,
@@ -286,6 +410,11 @@ fn update_idle_state(
         Minimap::Detecting,
         pixel_at(&detector.mat(), anchors.br.0)
     );
+    let (tl_pixel, br_pixel) = if minimap.context.robust {
+        median_anchor_pixels(&mut minimap.context.anchor_pixels, (tl_pixel, br_pixel))
+    } else {
+        (tl_pixel, br_pixel)
+    };
     let tl_match = anchor_match(anchors.tl.1, tl_pixel);
     let br_match = anchor_match(anchors.br.1, br_pixel);
     if !tl_match && !br_match {
@@ -295,10 +424,19 @@ fn update_idle_state(
             (tl_pixel, br_pixel),
             (anchors.tl.1, anchors.br.1)
         );
+        if minimap.context.robust {
+            minimap.context.anchors_mismatch_count += 1;
+            // Keeps the last state for frames distorted by screen shake
+            if minimap.context.anchors_mismatch_count < ROBUST_MAX_ANCHORS_MISMATCH_COUNT {
+                return;
+            }
+        }
         transition!(minimap, Minimap::Detecting);
     }
+    minimap.context.anchors_mismatch_count = 0;
 
     let partially_overlapping = (tl_match && !br_match) || (!tl_match && br_match);
+    let changed = update_snapshot(&detector.mat(), bbox, &mut minimap.context.snapshot);
     let rune = update_rune_task(
         resources,
         &mut minimap.context.rune_task,
@@ -327,12 +465,17 @@ fn update_idle_state(
         has_friend_player,
         OtherPlayerKind::Friend,
     );
+    let crowding_score = update_crowding_score(
+        crowding_score,
+        has_stranger_player.value.unwrap_or_default(),
+    );
     let portals = update_portals_task(
         resources,
         &mut minimap.context.portals_task,
         &mut minimap.context.portals_invalidate_map,
         portals,
         bbox,
+        changed,
     );
 
     if minimap.context.platforms_dirty {
@@ -345,10 +488,12 @@ fn update_idle_state(
 
     minimap.state = Minimap::Idle(MinimapIdle {
         partially_overlapping,
+        changed,
         rune,
         has_guildie_player,
         has_stranger_player,
         has_friend_player,
+        crowding_score,
         portals,
         platforms,
         platforms_bound,
@@ -356,6 +501,100 @@ fn update_idle_state(
     });
 }
 
+/// Diffs the minimap inside `bbox` of `mat` against `snapshot` and returns whether it changed.
+///
+/// The `snapshot` is only replaced when changed so that gradual changes accumulate until they
+/// exceed [`MINIMAP_CHANGE_THRESHOLD`].
+fn update_snapshot(mat: &impl MatTraitConst, bbox: Rect, snapshot: &mut Option<Mat>) -> bool {
+    let Ok(minimap) = mat.roi(bbox) else {
+        return true;
+    };
+    let changed = snapshot.as_ref().is_none_or(|snapshot| {
+        let mut diff = Mat::default();
+        if absdiff(&minimap, snapshot, &mut diff).is_err() {
+            return true;
+        }
+        mean_def(&diff)
+            .is_ok_and(|mean| (mean[0] + mean[1] + mean[2]) / 3.0 > MINIMAP_CHANGE_THRESHOLD)
+    });
+    if changed {
+        *snapshot = minimap.try_clone().ok();
+    }
+
+    changed
+}
+
+/// Pushes the anchor `pixels` of the current frame to `history` and returns the per-channel
+/// median of the anchor pixels over the last [`ROBUST_FRAMES_COUNT`] frames.
+fn median_anchor_pixels(
+    history: &mut VecDeque<(Vec4b, Vec4b)>,
+    pixels: (Vec4b, Vec4b),
+) -> (Vec4b, Vec4b) {
+    #[inline]
+    fn median(pixels: impl Iterator<Item = Vec4b>) -> Vec4b {
+        let pixels = pixels.collect::<Vec<_>>();
+        let mut median = Vec4b::default();
+        for channel in 0..4 {
+            let mut values = pixels
+                .iter()
+                .map(|pixel| pixel[channel])
+                .collect::<Vec<_>>();
+            values.sort_unstable();
+            median[channel] = values[values.len() / 2];
+        }
+        median
+    }
+
+    if history.len() >= ROBUST_FRAMES_COUNT {
+        history.pop_front();
+    }
+    history.push_back(pixels);
+
+    (
+        median(history.iter().map(|(tl, _)| *tl)),
+        median(history.iter().map(|(_, br)| *br)),
+    )
+}
+
+/// Pushes the detected `bbox` to `history` and returns the per-coordinate median of the bounding
+/// boxes over the last [`ROBUST_FRAMES_COUNT`] detections.
+///
+/// The `history` is cleared first if `bbox` has a different size from the last detection because
+/// screen shake only shifts the minimap while a different size means a different minimap.
+fn median_bbox(history: &mut VecDeque<Rect>, bbox: Rect) -> Rect {
+    if history
+        .back()
+        .is_some_and(|last| last.size() != bbox.size())
+    {
+        history.clear();
+    }
+    if history.len() >= ROBUST_FRAMES_COUNT {
+        history.pop_front();
+    }
+    history.push_back(bbox);
+
+    let median = |coordinate: fn(&Rect) -> i32| {
+        let mut values = history.iter().map(coordinate).collect::<Vec<_>>();
+        values.sort_unstable();
+        values[values.len() / 2]
+    };
+    Rect::new(
+        median(|bbox| bbox.x),
+        median(|bbox| bbox.y),
+        bbox.width,
+        bbox.height,
+    )
+}
+
+#[inline]
+fn max_fail_count(count: u32, robust: bool) -> u32 {
+    if robust {
+        count * ROBUST_FAIL_COUNT_MULTIPLIER
+    } else {
+        count
+    }
+}
+
 #[inline]
 fn anchor_match(anchor: Vec4b, pixel: Vec4b) -> bool {
     const ANCHOR_ACCEPTABLE_ERROR_RANGE: u32 = 45;
@@ -410,19 +649,23 @@ fn update_rune_task(
 #[inline]
 fn update_other_player_task(
     resources: &Resources,
-    task: &mut Option<Task<Result<()>>>,
+    task: &mut Option<Task<Result<usize>>>,
     minimap: Rect,
-    threshold: Threshold<()>,
+    threshold: Threshold<usize>,
     kind: OtherPlayerKind,
-) -> Threshold<()> {
+) -> Threshold<usize> {
     let has_player = threshold.value.is_some();
-    let threshold = update_threshold_detection(resources, 3000, threshold, task, move |detector| {
-        if detector.detect_player_kind(minimap, kind) {
-            Ok(())
-        } else {
-            Err(anyhow!("player not found"))
-        }
-    });
+    let threshold =
+        update_threshold_detection(
+            resources,
+            3000,
+            threshold,
+            task,
+            move |detector| match detector.detect_player_kind_count(minimap, kind) {
+                0 => Err(anyhow!("player not found")),
+                count => Ok(count),
+            },
+        );
     if !resources.operation.halting() && !has_player && threshold.value.is_some() {
         info!(target: "minimap", "sending {kind:?} notification...");
         let notification = match kind {
@@ -435,6 +678,18 @@ fn update_other_player_task(
     threshold
 }
 
+/// Moves `score` towards `stranger_count` as an exponential moving average ticked every frame.
+#[inline]
+fn update_crowding_score(score: f32, stranger_count: usize) -> f32 {
+    const SMOOTHING: f32 = MS_PER_TICK_F32 / CROWDING_SCORE_WINDOW_MILLIS;
+
+    score + (stranger_count as f32 - score) * SMOOTHING
+}
+
+/// Updates the portals detection `task`.
+///
+/// Portals do not move so a new detection is only started when the minimap has `changed`. A
+/// detection already in progress is always polled until it completes.
 #[inline]
 fn update_portals_task(
     resources: &Resources,
@@ -442,7 +697,12 @@ fn update_portals_task(
     invalidate_map: &mut HashMap<HashedRect, u32>,
     portals: Array<Rect, MAX_PORTALS_COUNT>,
     minimap: Rect,
+    changed: bool,
 ) -> Array<Rect, MAX_PORTALS_COUNT> {
+    if !changed && task.as_ref().is_none_or(|task| task.completed()) {
+        return portals;
+    }
+
     let update = update_detection_task(resources, 5000, task, move |detector| {
         Ok(detector.detect_minimap_portals(minimap))
     });
@@ -512,7 +772,10 @@ fn merge_portals_and_invalidate_if_needed(
 fn platforms_and_bound(
     bbox: Rect,
     platforms: &[Platform],
-) -> (Array<PlatformWithNeighbors, 24>, Option<Rect>) {
+) -> (
+    Array<PlatformWithNeighbors, MAX_PLATFORMS_COUNT>,
+    Option<Rect>,
+) {
     let platforms = Array::from_iter(find_neighbors(
         platforms,
         DOUBLE_JUMP_THRESHOLD,
@@ -607,7 +870,7 @@ mod tests {
     use mockall::predicate::eq;
     use opencv::{
         boxed_ref::BoxedRef,
-        core::{Mat, MatExprTraitConst, MatTrait, Point, Rect, Vec4b},
+        core::{Mat, MatExprTraitConst, MatTrait, Point, Rect, Scalar, Vec4b},
     };
     use tokio::time;
 
@@ -719,10 +982,12 @@ mod tests {
             anchors,
             bbox,
             partially_overlapping: false,
+            changed: true,
             rune: Threshold::new(3),
             has_guildie_player: Threshold::default(),
             has_stranger_player: Threshold::default(),
             has_friend_player: Threshold::default(),
+            crowding_score: 0.0,
             portals: Array::new(),
             platforms: Array::new(),
             platforms_bound: None,
@@ -817,6 +1082,166 @@ mod tests {
         assert_eq!(threshold.fail_count, 0);
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn update_portals_task_polls_pending_task_while_unchanged() {
+        let minimap = Rect::new(0, 0, 100, 100);
+        let portal = Rect::new(10, 20, 5, 5);
+        let mut detector = MockDetector::new();
+        detector
+            .expect_detect_minimap_portals()
+            .times(1)
+            .returning(move |_| vec![portal]);
+        let resources = Resources::new(None, Some(detector));
+        let mut task = None;
+        let mut invalidate_map = HashMap::new();
+
+        let mut portals = update_portals_task(
+            &resources,
+            &mut task,
+            &mut invalidate_map,
+            Array::new(),
+            minimap,
+            true,
+        );
+        while portals.is_empty() {
+            time::advance(Duration::from_millis(1000)).await;
+            portals = update_portals_task(
+                &resources,
+                &mut task,
+                &mut invalidate_map,
+                portals,
+                minimap,
+                false,
+            );
+        }
+        assert_eq!(
+            portals.into_iter().collect::<Vec<_>>(),
+            vec![Rect::new(10, 75, 5, 5)]
+        );
+
+        // Does not start a new detection while unchanged
+        time::advance(Duration::from_millis(10000)).await;
+        update_portals_task(
+            &resources,
+            &mut task,
+            &mut invalidate_map,
+            portals,
+            minimap,
+            false,
+        );
+        assert_matches!(task, Some(ref task) if task.completed());
+    }
+
+    #[test]
+    fn median_anchor_pixels_filters_single_frame_outlier() {
+        let normal = Vec4b::from([200, 200, 200, 255]);
+        let shaken = Vec4b::from([0, 0, 0, 255]);
+        let mut history = VecDeque::new();
+
+        median_anchor_pixels(&mut history, (normal, normal));
+        median_anchor_pixels(&mut history, (normal, normal));
+        let pixels = median_anchor_pixels(&mut history, (shaken, shaken));
+        assert_eq!(pixels, (normal, normal));
+
+        median_anchor_pixels(&mut history, (shaken, shaken));
+        assert_eq!(history.len(), ROBUST_FRAMES_COUNT);
+        let pixels = median_anchor_pixels(&mut history, (shaken, normal));
+        assert_eq!(pixels, (shaken, shaken));
+    }
+
+    #[test]
+    fn median_bbox_filters_single_detection_outlier() {
+        let normal = Rect::new(10, 20, 100, 80);
+        let shaken = Rect::new(14, 17, 100, 80);
+        let mut history = VecDeque::new();
+
+        median_bbox(&mut history, normal);
+        median_bbox(&mut history, normal);
+        assert_eq!(median_bbox(&mut history, shaken), normal);
+
+        median_bbox(&mut history, shaken);
+        assert_eq!(history.len(), ROBUST_FRAMES_COUNT);
+        assert_eq!(median_bbox(&mut history, normal), shaken);
+    }
+
+    #[test]
+    fn median_bbox_resets_on_different_size() {
+        let mut history = VecDeque::new();
+        median_bbox(&mut history, Rect::new(10, 20, 100, 80));
+        median_bbox(&mut history, Rect::new(10, 20, 100, 80));
+
+        let bbox = Rect::new(30, 40, 120, 90);
+
+        assert_eq!(median_bbox(&mut history, bbox), bbox);
+        assert_eq!(history.len(), 1);
+    }
+
+    #[test]
+    fn update_snapshot_changed_only_above_threshold() {
+        let (mut mat, _) = create_test_mat();
+        let bbox = Rect::new(0, 0, 100, 100);
+        let mut snapshot = None;
+
+        assert!(update_snapshot(&mat, bbox, &mut snapshot));
+        assert!(!update_snapshot(&mat, bbox, &mut snapshot));
+
+        // A few pixels changing such as the player moving
+        *mat.at_pt_mut::<Vec4b>(Point::new(50, 50)).unwrap() = Vec4b::all(255);
+        assert!(!update_snapshot(&mat, bbox, &mut snapshot));
+
+        mat.set_scalar(Scalar::all(255.0)).unwrap();
+        assert!(update_snapshot(&mat, bbox, &mut snapshot));
+        assert!(!update_snapshot(&mat, bbox, &mut snapshot));
+    }
+
+    #[test]
+    fn position_heatmap_counts_positions_per_cell() {
+        let mut context = MinimapContext::default();
+        context.record_position(Point::new(0, 0));
+        context.record_position(Point::new(1, 1));
+        context.record_position(Point::new(4, 2));
+        context.record_position(Point::new(5, 5));
+        context.record_position(Point::new(-1, 0));
+
+        let heatmap = context.position_heatmap(5, 4);
+
+        assert_eq!(heatmap.columns, 3);
+        assert_eq!(heatmap.rows, 2);
+        assert_eq!(heatmap.counts, vec![2, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn record_position_discards_oldest_when_full() {
+        let mut context = MinimapContext::default();
+        for _ in 0..MAX_POSITIONS_COUNT {
+            context.record_position(Point::new(0, 0));
+        }
+        context.record_position(Point::new(2, 0));
+
+        let heatmap = context.position_heatmap(4, 2);
+
+        assert_eq!(heatmap.counts, vec![MAX_POSITIONS_COUNT as u32 - 1, 1]);
+    }
+
+    #[test]
+    fn update_crowding_score_sustained_crowd() {
+        let ticks = (CROWDING_SCORE_WINDOW_MILLIS / MS_PER_TICK_F32) as usize;
+        let mut score = 0.0;
+
+        score = update_crowding_score(score, 3);
+        assert!(score < 0.1);
+
+        for _ in 0..ticks * 3 {
+            score = update_crowding_score(score, 3);
+        }
+        assert!(score > 2.5 && score <= 3.0);
+
+        for _ in 0..ticks * 3 {
+            score = update_crowding_score(score, 0);
+        }
+        assert!(score < 0.5);
+    }
+
     fn rect(x: i32, y: i32, w: i32, h: i32) -> Rect {
         Rect::new(x, y, w, h)
     }
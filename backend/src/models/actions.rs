@@ -1,4 +1,6 @@
-use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
 use strum::{Display, EnumIter, EnumString};
 
 use super::{KeyBinding, LinkKeyBinding, deserialize_with_ok_or_default};
@@ -10,6 +12,8 @@ pub enum Action {
     Move(ActionMove),
     /// An action that uses a specific key with or without a location.
     Key(ActionKey),
+    /// An action that replays a recorded [`super::InputMacro`].
+    Macro(ActionMacro),
 }
 
 impl Action {
@@ -17,6 +21,31 @@ impl Action {
         match self {
             Action::Move(action) => action.condition,
             Action::Key(action) => action.condition,
+            Action::Macro(action) => action.condition,
+        }
+    }
+
+    /// The maximum weight of a normal action in [`RotationMode::WeightedRandom`].
+    pub const MAX_WEIGHT: u32 = 10_000;
+
+    /// The weight of a normal action in [`RotationMode::WeightedRandom`].
+    ///
+    /// The weight is clamped between `1` and [`Self::MAX_WEIGHT`].
+    pub fn weight(&self) -> u32 {
+        let weight = match self {
+            Action::Move(action) => action.weight,
+            Action::Key(action) => action.weight,
+            Action::Macro(_) => None,
+        };
+
+        weight.unwrap_or(1).clamp(1, Self::MAX_WEIGHT)
+    }
+
+    pub fn tags(&self) -> ActionTags {
+        match self {
+            Action::Move(action) => action.tags,
+            Action::Key(action) => action.tags,
+            Action::Macro(action) => action.tags,
         }
     }
 
@@ -30,6 +59,10 @@ impl Action {
                 condition,
                 ..*action
             }),
+            Action::Macro(action) => Action::Macro(ActionMacro {
+                condition,
+                ..*action
+            }),
         }
     }
 }
@@ -40,6 +73,25 @@ pub struct ActionMove {
     pub position: Position,
     pub condition: ActionCondition,
     pub wait_after_move_millis: u64,
+    /// The relative chance of an [`ActionCondition::Any`] action being picked in
+    /// [`RotationMode::WeightedRandom`].
+    ///
+    /// Defaults to one when [`None`].
+    #[serde(default)]
+    pub weight: Option<u32>,
+    #[serde(default)]
+    pub tags: ActionTags,
+}
+
+/// A persistent model for the [`Action::Macro`] action.
+#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ActionMacro {
+    /// The id of the [`super::InputMacro`] to replay.
+    pub macro_id: Option<i64>,
+    pub condition: ActionCondition,
+    pub queue_to_front: Option<bool>,
+    #[serde(default)]
+    pub tags: ActionTags,
 }
 
 /// A persistent model for the [`Action::Key`] action.
@@ -65,6 +117,23 @@ pub struct ActionKey {
     #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
     pub wait_after_buffered: WaitAfterBuffered,
     pub queue_to_front: Option<bool>,
+    /// The maximum number of instances of an [`ActionCondition::EveryMillis`] action that can be
+    /// queued or executing at the same time.
+    ///
+    /// Defaults to one instance when [`None`].
+    #[serde(default)]
+    pub max_queued_instances: Option<u32>,
+    /// The relative chance of an [`ActionCondition::Any`] action being picked in
+    /// [`RotationMode::WeightedRandom`].
+    ///
+    /// Defaults to one when [`None`].
+    #[serde(default)]
+    pub weight: Option<u32>,
+    /// How to notify when this action starts executing.
+    #[serde(default)]
+    pub notify: ActionNotify,
+    #[serde(default)]
+    pub tags: ActionTags,
 }
 
 impl Default for ActionKey {
@@ -85,6 +154,10 @@ impl Default for ActionKey {
             wait_after_use_millis_random_range: 0,
             wait_after_buffered: WaitAfterBuffered::None,
             queue_to_front: None,
+            max_queued_instances: None,
+            weight: None,
+            notify: ActionNotify::default(),
+            tags: ActionTags::EMPTY,
         }
     }
 }
@@ -110,6 +183,178 @@ pub enum ActionCondition {
     EveryMillis(u64),
     ErdaShowerOffCooldown,
     Linked,
+    /// Queues the action whenever the user-provided expression evaluates to true.
+    Expression(ConditionExpression),
+}
+
+/// The source text of an [`ActionCondition::Expression`].
+///
+/// The text is stored inline with a fixed capacity so that [`ActionCondition`] stays [`Copy`].
+#[derive(Clone, Copy)]
+pub struct ConditionExpression {
+    bytes: [u8; ConditionExpression::MAX_LEN],
+    len: usize,
+}
+
+impl ConditionExpression {
+    pub const MAX_LEN: usize = 128;
+
+    /// Creates a new [`ConditionExpression`] from `text`.
+    ///
+    /// Returns [`None`] if `text` is longer than [`Self::MAX_LEN`] bytes.
+    pub fn new(text: &str) -> Option<Self> {
+        let len = text.len();
+        if len > Self::MAX_LEN {
+            return None;
+        }
+
+        let mut bytes = [0; Self::MAX_LEN];
+        bytes[..len].copy_from_slice(text.as_bytes());
+        Some(Self { bytes, len })
+    }
+
+    pub fn as_str(&self) -> &str {
+        // `bytes[..len]` is always copied from a valid `&str`
+        str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+}
+
+impl Default for ConditionExpression {
+    fn default() -> Self {
+        Self {
+            bytes: [0; Self::MAX_LEN],
+            len: 0,
+        }
+    }
+}
+
+impl PartialEq for ConditionExpression {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl fmt::Debug for ConditionExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for ConditionExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ConditionExpression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ConditionExpression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        ConditionExpression::new(&text).ok_or_else(|| {
+            de::Error::custom(format!(
+                "expression is longer than {} bytes",
+                ConditionExpression::MAX_LEN
+            ))
+        })
+    }
+}
+
+/// The comma-separated free-form tags of an action.
+///
+/// Tags are used to disable a group of actions at once. The text is stored inline with a fixed
+/// capacity so that [`Action`] stays [`Copy`].
+#[derive(Clone, Copy)]
+pub struct ActionTags {
+    bytes: [u8; ActionTags::MAX_LEN],
+    len: usize,
+}
+
+impl ActionTags {
+    pub const MAX_LEN: usize = 64;
+
+    pub const EMPTY: ActionTags = ActionTags {
+        bytes: [0; ActionTags::MAX_LEN],
+        len: 0,
+    };
+
+    /// Creates a new [`ActionTags`] from comma-separated `text`.
+    ///
+    /// Returns [`None`] if `text` is longer than [`Self::MAX_LEN`] bytes.
+    pub fn new(text: &str) -> Option<Self> {
+        let len = text.len();
+        if len > Self::MAX_LEN {
+            return None;
+        }
+
+        let mut bytes = [0; Self::MAX_LEN];
+        bytes[..len].copy_from_slice(text.as_bytes());
+        Some(Self { bytes, len })
+    }
+
+    pub fn as_str(&self) -> &str {
+        // `bytes[..len]` is always copied from a valid `&str`
+        str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+
+    /// Iterates over the trimmed non-empty tags.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.as_str()
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+    }
+
+    /// Whether any of the tags is in `tags`.
+    pub fn contains_any(&self, tags: &[String]) -> bool {
+        self.iter().any(|tag| tags.iter().any(|other| other == tag))
+    }
+}
+
+impl Default for ActionTags {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl PartialEq for ActionTags {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl fmt::Debug for ActionTags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for ActionTags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ActionTags {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ActionTags {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        ActionTags::new(&text).ok_or_else(|| {
+            de::Error::custom(format!(
+                "tags are longer than {} bytes",
+                ActionTags::MAX_LEN
+            ))
+        })
+    }
 }
 
 #[derive(
@@ -132,6 +377,21 @@ pub enum ActionKeyDirection {
     Right,
 }
 
+/// Notification emitted when an action starts executing.
+///
+/// This is useful for confirming that a rarely executed action actually triggers.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum ActionNotify {
+    #[default]
+    None,
+    /// Writes a log line.
+    Log,
+    /// Writes a log line and plays the system notification sound.
+    Sound,
+}
+
 #[derive(
     Clone, Copy, Display, EnumString, EnumIter, PartialEq, Debug, Serialize, Deserialize, Default,
 )]
@@ -203,6 +463,10 @@ impl From<ActionConfiguration> for Action {
             direction: ActionKeyDirection::Any,
             with: value.with,
             queue_to_front: Some(true),
+            max_queued_instances: None,
+            weight: None,
+            notify: ActionNotify::None,
+            tags: ActionTags::EMPTY,
             wait_before_use_millis: value.wait_before_millis,
             wait_before_use_millis_random_range: value.wait_before_millis_random_range,
             wait_after_use_millis: value.wait_after_millis,
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use strum::Display;
+
+use super::impl_identifiable;
+
+/// The kind of configuration mutated by a [`ConfigChange`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Display)]
+pub enum ConfigChangeKind {
+    #[default]
+    Map,
+    Character,
+    Settings,
+}
+
+/// A field of a configuration changed by a [`ConfigChange`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigFieldChange {
+    /// The keys from the top-level JSON object of the configuration to this field.
+    pub path: Vec<String>,
+    /// The value before the change or `None` if the field did not exist.
+    pub before: Option<Value>,
+    /// The value after the change or `None` if the field was removed.
+    pub after: Option<Value>,
+}
+
+/// A persistent model of one recorded mutation to a map, character or settings.
+///
+/// Timestamps are the number of milliseconds since the Unix epoch.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    #[serde(skip_serializing, default)]
+    pub id: Option<i64>,
+    #[serde(default)]
+    pub timestamp_millis: u64,
+    #[serde(default)]
+    pub kind: ConfigChangeKind,
+    /// The id of the mutated map, character or settings.
+    #[serde(default)]
+    pub entity_id: i64,
+    /// The name of the mutated map or character at the time of the change.
+    #[serde(default)]
+    pub entity_name: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<ConfigFieldChange>,
+    /// A short human-readable line for each of [`Self::fields`].
+    #[serde(default)]
+    pub summary: Vec<String>,
+}
+
+impl_identifiable!(ConfigChange);
@@ -5,13 +5,16 @@ use strum::{Display, EnumIter, EnumString};
 
 use super::{
     ActionConfiguration, KeyBinding, KeyBindingConfiguration, deserialize_with_ok_or_default,
-    impl_identifiable,
+    impl_identifiable, impl_revisioned,
 };
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Character {
     #[serde(skip_serializing, default)]
     pub id: Option<i64>,
+    /// Incremented on each save to detect saving over a newer modification.
+    #[serde(default)]
+    pub revision: u64,
     pub name: String,
     pub ropelift_key: Option<KeyBindingConfiguration>,
     pub teleport_key: Option<KeyBindingConfiguration>,
@@ -21,6 +24,8 @@ pub struct Character {
     #[serde(default = "key_default")]
     pub interact_key: KeyBindingConfiguration,
     pub cash_shop_key: Option<KeyBindingConfiguration>,
+    #[serde(default)]
+    pub cash_shop_claim_freebies: bool,
     pub familiar_menu_key: Option<KeyBindingConfiguration>,
     pub to_town_key: Option<KeyBindingConfiguration>,
     pub change_channel_key: Option<KeyBindingConfiguration>,
@@ -32,7 +37,22 @@ pub struct Character {
     pub potion_mode: PotionMode,
     pub health_update_millis: u64,
     #[serde(default)]
+    pub mana_potion_key: KeyBindingConfiguration,
+    #[serde(default = "mana_potion_percent_default")]
+    pub mana_potion_percent: f32,
+    #[serde(default = "potion_cooldown_millis_default")]
+    pub potion_cooldown_millis: u64,
+    #[serde(default)]
+    pub pet_auto_potion: bool,
+    #[serde(default)]
+    pub potion_stock: PotionStock,
+    #[serde(default)]
+    pub low_health_policy: LowHealthPolicy,
+    #[serde(default)]
     pub familiars: Familiars,
+    /// Named alternatives to [`Self::familiars`] that map presets can switch to.
+    #[serde(default)]
+    pub familiars_setups: Vec<FamiliarsSetup>,
     pub familiar_buff_key: KeyBindingConfiguration,
     #[serde(default = "key_default")]
     pub familiar_essence_key: KeyBindingConfiguration,
@@ -86,14 +106,20 @@ pub struct Character {
     pub elite_boss_behavior: EliteBossBehavior,
     #[serde(default)]
     pub elite_boss_behavior_key: KeyBinding,
+    #[serde(default)]
+    pub elite_boss_rotation: Vec<EliteBossRotationKey>,
+    #[serde(default)]
+    pub elite_boss_rotation_move_to_boss: bool,
 }
 
 impl_identifiable!(Character);
+impl_revisioned!(Character);
 
 impl Default for Character {
     fn default() -> Self {
         Self {
             id: None,
+            revision: 0,
             name: String::new(),
             ropelift_key: None,
             teleport_key: None,
@@ -101,6 +127,7 @@ impl Default for Character {
             up_jump_key: None,
             interact_key: key_default(),
             cash_shop_key: None,
+            cash_shop_claim_freebies: false,
             familiar_menu_key: None,
             to_town_key: None,
             change_channel_key: None,
@@ -110,7 +137,14 @@ impl Default for Character {
             potion_key: KeyBindingConfiguration::default(),
             potion_mode: PotionMode::EveryMillis(180000),
             health_update_millis: 1000,
+            mana_potion_key: KeyBindingConfiguration::default(),
+            mana_potion_percent: mana_potion_percent_default(),
+            potion_cooldown_millis: potion_cooldown_millis_default(),
+            pet_auto_potion: false,
+            potion_stock: PotionStock::default(),
+            low_health_policy: LowHealthPolicy::default(),
             familiars: Familiars::default(),
+            familiars_setups: vec![],
             familiar_buff_key: KeyBindingConfiguration::default(),
             familiar_essence_key: key_default(),
             sayram_elixir_key: KeyBindingConfiguration::default(),
@@ -145,6 +179,8 @@ impl Default for Character {
             actions: vec![],
             elite_boss_behavior_key: KeyBinding::default(),
             elite_boss_behavior: EliteBossBehavior::default(),
+            elite_boss_rotation: vec![],
+            elite_boss_rotation_move_to_boss: false,
         }
     }
 }
@@ -153,6 +189,14 @@ fn feed_pet_count_default() -> u32 {
     3
 }
 
+fn mana_potion_percent_default() -> f32 {
+    30.0
+}
+
+fn potion_cooldown_millis_default() -> u64 {
+    1000
+}
+
 fn hexa_booster_exchange_amount_default() -> u32 {
     1
 }
@@ -185,6 +229,105 @@ impl Default for PotionMode {
     }
 }
 
+/// Tracking of the remaining count of the potion used with [`Character::potion_key`].
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PotionStock {
+    pub enabled: bool,
+    /// The number of potions in stock the remaining count is estimated from.
+    ///
+    /// Changing this restarts counting the potion uses.
+    pub starting_count: u32,
+    /// The remaining count at or below which the stock is considered low.
+    pub low_count: u32,
+    /// Whether to halt instead of only notifying when the stock is low.
+    pub halt_on_low: bool,
+    /// Whether to correct the estimated count by reading the potion item count in the quick
+    /// slots.
+    pub verify_with_quick_slots: bool,
+    /// Buys potions from a shop NPC when low.
+    pub restock: PotionRestock,
+}
+
+impl Default for PotionStock {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            starting_count: 0,
+            low_count: 50,
+            halt_on_low: false,
+            verify_with_quick_slots: false,
+            restock: PotionRestock::default(),
+        }
+    }
+}
+
+/// Buying potions from a shop NPC when the [`PotionStock`] is low.
+///
+/// The player travels to the shop map, buys the potions and travels back to the map it started
+/// from. The stock is only considered low when this fails.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PotionRestock {
+    pub enabled: bool,
+    /// The id of the map the shop NPC is in.
+    ///
+    /// This map and the map the restock starts from must have a navigation path attached.
+    pub map_id: Option<i64>,
+    /// The x position of the shop NPC in the map.
+    pub npc_x: i32,
+    /// The y position of the shop NPC in the map.
+    pub npc_y: i32,
+    /// The number of potions to buy.
+    pub quantity: u32,
+}
+
+impl Default for PotionRestock {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            map_id: None,
+            npc_x: 0,
+            npc_y: 0,
+            quantity: 100,
+        }
+    }
+}
+
+/// Emergency policy for when health stays critical despite using potions.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LowHealthPolicy {
+    pub enabled: bool,
+    /// The health percentage below which health is considered critical.
+    pub critical_percent: f32,
+    /// How long health must stay critical before executing [`Self::action`].
+    pub duration_millis: u64,
+    #[serde(deserialize_with = "deserialize_with_ok_or_default")]
+    pub action: LowHealthAction,
+}
+
+impl Default for LowHealthPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            critical_percent: 20.0,
+            duration_millis: 10000,
+            action: LowHealthAction::default(),
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum LowHealthAction {
+    #[default]
+    GoToTown,
+    ChangeChannel,
+    ExitToCharacterSelect,
+}
+
 #[derive(
     Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
 )]
@@ -193,6 +336,14 @@ pub enum EliteBossBehavior {
     None,
     CycleChannel,
     UseKey,
+    Rotation,
+}
+
+/// A key used in [`EliteBossBehavior::Rotation`] while the elite boss is present.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct EliteBossRotationKey {
+    pub key: KeyBinding,
+    pub cooldown_millis: u64,
 }
 
 #[derive(
@@ -225,6 +376,13 @@ impl Default for Familiars {
     }
 }
 
+/// A named [`Familiars`] configuration referenced by [`Map::familiars_setups`](super::Map).
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct FamiliarsSetup {
+    pub name: String,
+    pub familiars: Familiars,
+}
+
 fn familiars_swap_check_millis() -> u64 {
     300000
 }
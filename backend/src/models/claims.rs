@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+use super::impl_identifiable;
+
+/// A persistent model tracking per-day claimable rewards.
+///
+/// Days are counted as the number of days since the Unix epoch in UTC, which matches the
+/// game's daily reset.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DailyClaims {
+    #[serde(skip_serializing, default)]
+    pub id: Option<i64>,
+    /// The last day cash shop free items were claimed.
+    #[serde(default)]
+    pub cash_shop_freebies_day: Option<u64>,
+}
+
+impl_identifiable!(DailyClaims);
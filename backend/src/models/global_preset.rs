@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Action, impl_identifiable};
+
+/// A persistent model representing an actions preset shared across maps.
+///
+/// A map preset links global presets by name through [`super::Map::actions_global_presets`].
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GlobalPreset {
+    #[serde(skip_serializing, default)]
+    pub id: Option<i64>,
+    pub name: String,
+    pub actions: Vec<Action>,
+}
+
+impl_identifiable!(GlobalPreset);
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+use super::{KeyBinding, impl_identifiable};
+
+/// A persistent model representing a recorded sequence of keys.
+///
+/// Actions reference an input macro by id through [`super::ActionMacro::macro_id`].
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InputMacro {
+    #[serde(skip_serializing, default)]
+    pub id: Option<i64>,
+    pub name: String,
+    pub keys: Vec<InputMacroKey>,
+}
+
+impl_identifiable!(InputMacro);
+
+/// A key of an [`InputMacro`].
+#[derive(PartialEq, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct InputMacroKey {
+    pub key: KeyBinding,
+    /// The delay since the previous key or zero for the first key.
+    pub delay_millis: u64,
+}
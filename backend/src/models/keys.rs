@@ -9,6 +9,13 @@ pub struct KeyBindingConfiguration {
     pub enabled: bool,
 }
 
+/// Translates the key `from` used by stored actions to the key `to` currently bound in game.
+#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+pub struct KeyRemap {
+    pub from: KeyBinding,
+    pub to: KeyBinding,
+}
+
 #[derive(
     Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
 )]
@@ -62,6 +69,18 @@ pub enum KeyBinding {
     F10,
     F11,
     F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
     Up,
     Down,
     Left,
@@ -85,6 +104,23 @@ pub enum KeyBinding {
     Ctrl,
     Alt,
     Backspace,
+    NumpadZero,
+    NumpadOne,
+    NumpadTwo,
+    NumpadThree,
+    NumpadFour,
+    NumpadFive,
+    NumpadSix,
+    NumpadSeven,
+    NumpadEight,
+    NumpadNine,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+    Mouse4,
+    Mouse5,
 }
 
 #[derive(
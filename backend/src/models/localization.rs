@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
 
 use super::impl_identifiable;
 
@@ -7,6 +10,12 @@ pub struct Localization {
     #[serde(skip_serializing, default)]
     pub id: Option<i64>,
     pub cash_shop_base64: Option<String>,
+    #[serde(default)]
+    pub cash_shop_free_tab_base64: Option<String>,
+    #[serde(default)]
+    pub cash_shop_free_item_base64: Option<String>,
+    #[serde(default)]
+    pub cash_shop_buy_button_base64: Option<String>,
     pub change_channel_base64: Option<String>,
     pub timer_base64: Option<String>,
     pub popup_confirm_base64: Option<String>,
@@ -23,6 +32,104 @@ pub struct Localization {
     pub hexa_erda_conversion_button_base64: Option<String>,
     pub hexa_booster_button_base64: Option<String>,
     pub hexa_max_button_base64: Option<String>,
+    #[serde(default)]
+    pub party_invite_base64: Option<String>,
+    #[serde(default)]
+    pub party_invite_accept_button_base64: Option<String>,
+    #[serde(default)]
+    pub party_invite_decline_button_base64: Option<String>,
+    /// The trade request popup title.
+    #[serde(default)]
+    pub trade_request_base64: Option<String>,
+    /// The duel request popup title.
+    #[serde(default)]
+    pub duel_request_base64: Option<String>,
+    /// The decline button of trade and duel request popups.
+    #[serde(default)]
+    pub player_request_decline_button_base64: Option<String>,
+    /// A calibrated player dot template used in place of the default player templates.
+    #[serde(default)]
+    pub player_dot_base64: Option<String>,
+    /// The top part of the potion icon in the quick slots above its item count.
+    #[serde(default)]
+    pub quick_slots_potion_base64: Option<String>,
+    /// The potion item in the NPC shop list.
+    #[serde(default)]
+    pub shop_potion_base64: Option<String>,
+    /// Matching thresholds tuned by the user in place of the default ones.
+    #[serde(default)]
+    pub detection_thresholds: HashMap<TunableDetection, f64>,
+    /// The width in percentage of the frame width of the center band popup buttons are
+    /// searched in.
+    ///
+    /// Defaults to [`DEFAULT_POPUP_ROI_WIDTH_PERCENT`] when [`None`].
+    #[serde(default)]
+    pub popup_roi_width_percent: Option<f32>,
+    /// The height in percentage of the frame height of the center band popup buttons are
+    /// searched in.
+    ///
+    /// Defaults to [`DEFAULT_POPUP_ROI_HEIGHT_PERCENT`] when [`None`].
+    #[serde(default)]
+    pub popup_roi_height_percent: Option<f32>,
 }
 
+/// The default width in percentage of the popup search region.
+pub const DEFAULT_POPUP_ROI_WIDTH_PERCENT: f32 = 60.0;
+
+/// The default height in percentage of the popup search region.
+pub const DEFAULT_POPUP_ROI_HEIGHT_PERCENT: f32 = 80.0;
+
+/// The smallest size in percentage of the popup search region.
+const MIN_POPUP_ROI_PERCENT: f32 = 10.0;
+
 impl_identifiable!(Localization);
+
+impl Localization {
+    /// Gets the matching threshold of `detection` with the tuned one taking precedence.
+    pub fn detection_threshold(&self, detection: TunableDetection) -> f64 {
+        self.detection_thresholds
+            .get(&detection)
+            .copied()
+            .unwrap_or(detection.default_threshold())
+    }
+
+    /// Gets the width and height ratios relative to the frame of the popup search region.
+    pub fn popup_roi_ratios(&self) -> (f32, f32) {
+        let ratio = |percent: Option<f32>, default: f32| {
+            percent
+                .unwrap_or(default)
+                .clamp(MIN_POPUP_ROI_PERCENT, 100.0)
+                / 100.0
+        };
+
+        (
+            ratio(
+                self.popup_roi_width_percent,
+                DEFAULT_POPUP_ROI_WIDTH_PERCENT,
+            ),
+            ratio(
+                self.popup_roi_height_percent,
+                DEFAULT_POPUP_ROI_HEIGHT_PERCENT,
+            ),
+        )
+    }
+}
+
+/// A template-based detection whose matching threshold can be tuned.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display, EnumIter, Serialize, Deserialize)]
+pub enum TunableDetection {
+    Player,
+    #[strum(to_string = "HP bar")]
+    HealthBar,
+    #[strum(to_string = "Erda Shower")]
+    ErdaShower,
+}
+
+impl TunableDetection {
+    pub fn default_threshold(&self) -> f64 {
+        match self {
+            TunableDetection::Player | TunableDetection::HealthBar => 0.75,
+            TunableDetection::ErdaShower => 0.8,
+        }
+    }
+}
@@ -4,7 +4,10 @@ use opencv::core::Rect;
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumIter, EnumString};
 
-use super::{Action, MobbingKey, deserialize_with_ok_or_default, impl_identifiable};
+use super::{
+    Action, ActionKeyDirection, MobbingKey, deserialize_with_ok_or_default, impl_identifiable,
+    impl_revisioned,
+};
 use crate::pathing;
 
 /// A persistent model representing a map-related data.
@@ -12,11 +15,24 @@ use crate::pathing;
 pub struct Map {
     #[serde(skip_serializing)]
     pub id: Option<i64>,
+    /// Incremented on each save to detect saving over a newer modification.
+    #[serde(default)]
+    pub revision: u64,
     pub name: String,
     pub width: i32,
     pub height: i32,
+    /// The grayscale minimap name as a base64 PNG captured when the map is created.
+    ///
+    /// Used together with [`Self::width`] and [`Self::height`] to verify the current map. Only
+    /// the minimap size is verified when this is not captured.
+    #[serde(default)]
+    pub name_snapshot_base64: Option<String>,
     #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
     pub rotation_mode: RotationMode,
+    /// Whether [`RotationMode::WeightedRandom`] avoids picking the same normal action twice in a
+    /// row when there are other actions.
+    #[serde(default)]
+    pub rotation_weighted_random_no_repeat: bool,
     #[serde(default)]
     pub rotation_ping_pong_bound: Bound,
     #[serde(default)]
@@ -24,23 +40,112 @@ pub struct Map {
     #[serde(default)]
     pub rotation_mobbing_key: MobbingKey,
     pub platforms: Vec<Platform>,
+    /// Alternative [`Self::platforms`] switched to in order when their triggers are met.
+    ///
+    /// Useful for maps that change their platforms after an event phase.
+    #[serde(default)]
+    pub platform_sets: Vec<PlatformSet>,
     pub rune_platforms_pathing: bool,
     pub rune_platforms_pathing_up_jump_only: bool,
+    /// How solving the rune is retried when it fails.
+    #[serde(default)]
+    pub rune_retry_policy: RuneRetryPolicy,
     pub auto_mob_platforms_pathing: bool,
     pub auto_mob_platforms_pathing_up_jump_only: bool,
     pub auto_mob_platforms_bound: bool,
+    /// How auto mobbing balances picking mobs between the quadrants of its bound.
+    #[serde(default)]
+    pub auto_mob_quadrants: AutoMobQuadrants,
+    /// The mob detection class indices auto mobbing prioritizes, from highest to lowest.
+    ///
+    /// Mobs of the highest priority class detected are picked before any other mob. Mobs with
+    /// classes not in this list are only picked when no prioritized class is detected.
+    #[serde(default)]
+    pub auto_mob_priority_classes: Vec<u32>,
+    /// Whether auto mobbing tracks mobs across frames.
+    ///
+    /// Tracking keeps detecting mobs while moving to chase the predicted position of a moving mob
+    /// and to avoid picking a mob that has just died.
+    #[serde(default)]
+    pub auto_mob_track_mobs: bool,
     #[serde(default)]
     pub auto_mob_use_key_when_pathing: bool,
     #[serde(default)]
     pub auto_mob_use_key_when_pathing_update_millis: u64,
     pub actions_any_reset_on_erda_condition: bool,
+    /// The maximum number of priority actions in queue before the oldest are dropped.
+    ///
+    /// A value of `0` means unlimited.
+    #[serde(default)]
+    pub actions_priority_queue_max_depth: u32,
     pub actions: HashMap<String, Vec<Action>>,
+    /// Maps a preset to the names of [`super::GlobalPreset`]s linked to it.
+    ///
+    /// The actions of the linked global presets are run together with the preset's own actions.
+    #[serde(default)]
+    pub actions_global_presets: HashMap<String, Vec<String>>,
+    /// Maps a preset to the direction its [`ActionKeyDirection::Any`] actions face.
+    ///
+    /// Useful for directional burst maps where attacks should consistently face one side instead
+    /// of the last known direction.
+    #[serde(default)]
+    pub actions_preferred_directions: HashMap<String, ActionKeyDirection>,
+    /// Maps a preset to the name of a [`super::FamiliarsSetup`] of the character.
+    ///
+    /// The character's own familiars configuration is used if the preset has none or the setup
+    /// does not exist.
+    #[serde(default)]
+    pub familiars_setups: HashMap<String, String>,
+    /// Maps a preset to an external JSON file that its actions are reloaded from when changed.
+    #[serde(default)]
+    pub actions_external_files: HashMap<String, String>,
+    /// Action tags to disable while strangers are in the minimap.
+    #[serde(default)]
+    pub actions_disabled_tags_with_strangers: Vec<String>,
+    /// Presets of [`Self::actions`] to run one after another for a number of minutes each.
+    ///
+    /// The slices repeat from the first one after the last one ends. When not empty, these are
+    /// used instead of the selected preset.
+    #[serde(default)]
+    pub actions_time_slices: Vec<ActionsTimeSlice>,
+    /// Burst windows aligning tagged priority actions to fire back-to-back.
+    #[serde(default)]
+    pub actions_burst_windows: Vec<ActionsBurstWindow>,
     // Not FK, loose coupling to another navigation paths and its index
     #[serde(default)]
     pub paths_id_index: Option<(i64, usize)>,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub notifications: MapNotifications,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub stranger_policy: StrangerPolicy,
+    /// Whether minimap detection should tolerate screen shake and heavy weather effects.
+    #[serde(default)]
+    pub robust_detection: bool,
 }
 
 impl_identifiable!(Map);
+impl_revisioned!(Map);
+
+/// A time slice running the actions of a preset for a number of minutes.
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ActionsTimeSlice {
+    pub preset: String,
+    pub minutes: u32,
+}
+
+/// A burst window of fixed interval actions fired back-to-back once all of them are due.
+///
+/// The actions are fired in the order they appear in the preset, so buff actions should come
+/// before the attack action.
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ActionsBurstWindow {
+    /// The tag of the actions in the window.
+    pub tag: String,
+    /// How long before being due an action can still be fired together with the window.
+    pub tolerance_millis: u64,
+    /// Whether to queue the actions in front of other priority actions when firing.
+    pub boost_priority: bool,
+}
 
 #[derive(Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize)]
 pub struct Bound {
@@ -68,6 +173,65 @@ impl From<Rect> for Bound {
     }
 }
 
+/// Per-map policy for changing channel when strangers stay in the minimap.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StrangerPolicy {
+    pub enabled: bool,
+    /// How long strangers must stay in the minimap before changing channel.
+    pub linger_millis: u64,
+    /// The maximum number of channel changes within an hour before halting.
+    ///
+    /// A value of `0` means unlimited.
+    pub max_changes_per_hour: u32,
+    /// Channel numbers to skip past when changing channel.
+    ///
+    /// Only used when [`Self::start_channel`] is known.
+    pub avoid_channels: Vec<u32>,
+    /// The channel number the bot starts in or `0` if unknown.
+    ///
+    /// The current channel is estimated from this by counting channel changes.
+    pub start_channel: u32,
+    /// The number of channels in the world.
+    pub channel_count: u32,
+}
+
+impl Default for StrangerPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            linger_millis: 10000,
+            max_changes_per_hour: 6,
+            avoid_channels: vec![],
+            start_channel: 0,
+            channel_count: 40,
+        }
+    }
+}
+
+/// Per-map overrides of the global [`Notifications`](super::Notifications).
+#[derive(Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub struct MapNotifications {
+    pub rune_appear: NotificationOverride,
+    pub elite_boss_appear: NotificationOverride,
+    pub player_guildie_appear: NotificationOverride,
+    pub player_stranger_appear: NotificationOverride,
+    pub player_friend_appear: NotificationOverride,
+    /// Sends notifications without waiting to capture game snapshots.
+    pub notify_immediately: bool,
+}
+
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum NotificationOverride {
+    /// Uses the global notification setting.
+    #[default]
+    Global,
+    Enabled,
+    Disabled,
+}
+
 #[derive(
     Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
 )]
@@ -77,6 +241,76 @@ pub enum RotationMode {
     StartToEndThenReverse,
     AutoMobbing,
     PingPong,
+    /// Randomly picks the next normal action based on its weight.
+    WeightedRandom,
+}
+
+/// How auto mobbing picks the quadrant of its bound to mob in.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum AutoMobQuadrantMode {
+    /// Picks any detected mob regardless of its quadrant.
+    #[default]
+    Any,
+    /// Randomly picks a quadrant with detected mobs based on its weight.
+    Weighted,
+    /// Picks the quadrant with detected mobs that has been picked the least.
+    Even,
+}
+
+/// The retry policy of solving the rune.
+///
+/// A failed attempt is either failing to detect the rune arrows or not receiving the rune buff
+/// after sending the detected keys.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuneRetryPolicy {
+    /// The number of consecutive failed attempts before giving up.
+    pub max_attempts: u32,
+    /// Whether to move to the rune again before retrying instead of retrying in place.
+    pub reapproach: bool,
+    /// Milliseconds to wait after a failed attempt before retrying.
+    pub cooldown_millis: u64,
+    /// Whether to change channel instead of entering the cash shop when giving up.
+    pub change_channel_on_give_up: bool,
+}
+
+impl Default for RuneRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            reapproach: true,
+            cooldown_millis: 0,
+            change_channel_on_give_up: false,
+        }
+    }
+}
+
+/// The quadrant balancing of [`RotationMode::AutoMobbing`].
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoMobQuadrants {
+    pub mode: AutoMobQuadrantMode,
+    /// The weights of each quadrant used in [`AutoMobQuadrantMode::Weighted`].
+    ///
+    /// A quadrant with `0` weight is only picked when no other quadrant has mobs.
+    pub top_left_weight: u32,
+    pub top_right_weight: u32,
+    pub bottom_right_weight: u32,
+    pub bottom_left_weight: u32,
+}
+
+impl Default for AutoMobQuadrants {
+    fn default() -> Self {
+        Self {
+            mode: AutoMobQuadrantMode::default(),
+            top_left_weight: 1,
+            top_right_weight: 1,
+            bottom_right_weight: 1,
+            bottom_left_weight: 1,
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
@@ -86,6 +320,27 @@ pub struct Platform {
     pub y: i32,
 }
 
+/// A named set of platforms replacing [`Map::platforms`] once its trigger is met.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct PlatformSet {
+    pub name: String,
+    pub platforms: Vec<Platform>,
+    #[serde(default)]
+    pub trigger: PlatformSetTrigger,
+}
+
+/// The rule for switching to a [`PlatformSet`] from the previous one.
+#[derive(Clone, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display)]
+pub enum PlatformSetTrigger {
+    /// Never switches.
+    #[default]
+    None,
+    /// Switches after the previous set has been active for the milliseconds.
+    AfterMillis(u64),
+    /// Switches when the base64-encoded PNG template is detected in the game.
+    Template(String),
+}
+
 // TODO: Should be part of pathing logics, not here
 impl From<Platform> for pathing::Platform {
     fn from(value: Platform) -> Self {
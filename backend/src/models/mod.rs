@@ -2,22 +2,34 @@ use serde::{Deserialize, Deserializer};
 use serde_json::Value;
 
 mod actions;
+mod changelog;
 mod character;
+mod claims;
+mod global_preset;
+mod input_macro;
 mod keys;
 mod localization;
 mod map;
 mod navigation;
+mod operation;
 mod seeds;
 mod settings;
+mod stats;
 
 pub use actions::*;
+pub use changelog::*;
 pub use character::*;
+pub use claims::*;
+pub use global_preset::*;
+pub use input_macro::*;
 pub use keys::*;
 pub use localization::*;
 pub use map::*;
 pub use navigation::*;
+pub use operation::*;
 pub use seeds::*;
 pub use settings::*;
+pub use stats::*;
 
 pub trait Identifiable {
     fn id(&self) -> Option<i64>;
@@ -41,6 +53,29 @@ macro_rules! impl_identifiable {
 
 use impl_identifiable;
 
+/// A model guarded by a revision counter against concurrent modifications.
+pub trait Revisioned {
+    fn revision(&self) -> u64;
+
+    fn set_revision(&mut self, revision: u64);
+}
+
+macro_rules! impl_revisioned {
+    ($type:ty) => {
+        impl $crate::models::Revisioned for $type {
+            fn revision(&self) -> u64 {
+                self.revision
+            }
+
+            fn set_revision(&mut self, revision: u64) {
+                self.revision = revision;
+            }
+        }
+    };
+}
+
+use impl_revisioned;
+
 fn deserialize_with_ok_or_default<'a, T, D>(deserializer: D) -> Result<T, D::Error>
 where
     T: Deserialize<'a> + Default,
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+use super::impl_identifiable;
+
+/// A persistent model of the last bot operation so that it can be resumed after a restart.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OperationState {
+    #[serde(skip_serializing, default)]
+    pub id: Option<i64>,
+    #[serde(default)]
+    pub operation: SavedOperation,
+}
+
+impl_identifiable!(OperationState);
+
+/// A wall-clock representation of the bot operation.
+///
+/// Timestamps are the number of milliseconds since the Unix epoch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum SavedOperation {
+    HaltUntil {
+        timestamp_millis: u64,
+        run_duration_millis: u64,
+        stop_duration_millis: u64,
+    },
+    TemporaryHalting {
+        resume_millis: u64,
+        run_duration_millis: u64,
+        stop_duration_millis: u64,
+        once: bool,
+    },
+    #[default]
+    Halting,
+    Running,
+    RunUntil {
+        timestamp_millis: u64,
+        run_duration_millis: u64,
+        stop_duration_millis: u64,
+        once: bool,
+    },
+}
@@ -2,13 +2,22 @@ use serde::{Deserialize, Serialize};
 use strum::{Display, EnumIter, EnumString};
 
 use super::{deserialize_with_ok_or_default, impl_identifiable};
-use crate::{KeyBinding, KeyBindingConfiguration};
+use crate::{KeyBinding, KeyBindingConfiguration, KeyRemap};
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Settings {
     #[serde(skip_serializing, default)]
     pub id: Option<i64>,
     pub capture_mode: CaptureMode,
+    /// The maximum number of frames captured per second.
+    ///
+    /// Only applies to capture modes receiving frames on every monitor refresh so that high
+    /// refresh rate monitors do not capture more frames than processed.
+    #[serde(default = "capture_target_fps_default")]
+    pub capture_target_fps: u32,
+    /// Whether to capture on every monitor refresh instead of [`Self::capture_target_fps`].
+    #[serde(default)]
+    pub capture_sync_monitor_refresh: bool,
     #[serde(default = "enable_solving_default")]
     pub enable_rune_solving: bool,
     #[serde(default = "enable_solving_default")]
@@ -23,8 +32,87 @@ pub struct Settings {
     pub cycle_run_duration_millis: u64,
     #[serde(default = "cycle_stop_duration_millis_default")]
     pub cycle_stop_duration_millis: u64,
+    /// Wall-clock schedule that runs or halts the bot on top of the run/stop cycle.
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub operation_schedule: OperationSchedule,
+    #[serde(default)]
+    pub enable_party_invite_auto_accept: bool,
+    #[serde(default)]
+    pub party_invite_whitelist: Vec<String>,
+    /// What to do when a trade or duel request popup from another player appears.
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub player_request_policy: PlayerRequestPolicy,
+    #[serde(default)]
+    pub enable_character_auto_detect: bool,
+    #[serde(default)]
+    pub enable_character_verification: bool,
+    #[serde(default)]
+    pub enable_idle_watchdog: bool,
+    #[serde(default = "idle_watchdog_millis_default")]
+    pub idle_watchdog_millis: u64,
+    #[serde(default)]
+    pub enable_power_saving: bool,
+    #[serde(default = "power_saving_cpu_temperature_threshold_default")]
+    pub power_saving_cpu_temperature_threshold: u32,
+    #[serde(default)]
+    pub enable_spectate_server: bool,
+    #[serde(default = "spectate_server_address_default")]
+    pub spectate_server_address: String,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub duo_mode: DuoMode,
+    #[serde(default = "duo_address_default")]
+    pub duo_address: String,
+    /// The remote control token of the duo host when joining.
+    #[serde(default)]
+    pub duo_token: String,
+    #[serde(default)]
+    pub enable_dashboard: bool,
+    #[serde(default = "dashboard_address_default")]
+    pub dashboard_address: String,
+    #[serde(default)]
+    pub enable_remote_control: bool,
+    #[serde(default = "remote_control_address_default")]
+    pub remote_control_address: String,
+    /// The shared token remote control clients must send as `authorization: Bearer <token>`.
+    ///
+    /// Remote control is only served outside loopback when this token is set.
+    #[serde(default)]
+    pub remote_control_token: String,
+    /// The path to the PEM certificate for serving remote control over TLS.
+    ///
+    /// TLS is only used when both this and [`Self::remote_control_tls_key_path`] are set.
+    #[serde(default)]
+    pub remote_control_tls_cert_path: String,
+    /// The path to the PEM private key for serving remote control over TLS.
+    #[serde(default)]
+    pub remote_control_tls_key_path: String,
+    /// Whether to write the bot status to [`Self::status_file_path`] for streaming tools.
+    #[serde(default)]
+    pub enable_status_file: bool,
+    #[serde(default = "status_file_path_default")]
+    pub status_file_path: String,
+    /// Whether the UI shows the bot status as the window title instead of a random one.
+    #[serde(default)]
+    pub enable_status_window_title: bool,
+    /// Whether template matching runs on GPU through OpenCL when an OpenCL device is available.
+    #[serde(default)]
+    pub enable_opencl: bool,
+    /// Whether to save solved rune crops to the local rune dataset for improving the rune model.
+    #[serde(default)]
+    pub enable_rune_dataset: bool,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub admin_reaction: AdminReaction,
     pub input_method: InputMethod,
     pub input_method_rpc_server_url: String,
+    /// How keys are translated to the keys pressed for [`InputMethod::Default`].
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub keyboard_layout: KeyboardLayout,
+    /// Whether held down keys are repeatedly sent at [`Self::key_repeat_interval_millis`] for
+    /// clients that ignore the held state.
+    #[serde(default)]
+    pub enable_key_repeat: bool,
+    #[serde(default = "key_repeat_interval_millis_default")]
+    pub key_repeat_interval_millis: u64,
     #[serde(default)]
     pub discord_bot_access_token: String,
     pub notifications: Notifications,
@@ -36,6 +124,10 @@ pub struct Settings {
     pub platform_end_key: KeyBindingConfiguration,
     #[serde(default = "platform_add_key_default")]
     pub platform_add_key: KeyBindingConfiguration,
+    /// Remaps keys sent to the game so that stored actions keep working after the in-game
+    /// keymap changes.
+    #[serde(default)]
+    pub key_remaps: Vec<KeyRemap>,
 }
 
 impl Default for Settings {
@@ -43,28 +135,67 @@ impl Default for Settings {
         Self {
             id: None,
             capture_mode: CaptureMode::default(),
+            capture_target_fps: capture_target_fps_default(),
+            capture_sync_monitor_refresh: false,
             enable_rune_solving: enable_solving_default(),
             enable_transparent_shape_solving: enable_solving_default(),
             enable_panic_mode: false,
             input_method: InputMethod::default(),
             input_method_rpc_server_url: String::default(),
+            keyboard_layout: KeyboardLayout::default(),
+            enable_key_repeat: false,
+            key_repeat_interval_millis: key_repeat_interval_millis_default(),
             stop_on_fail_or_change_map: false,
             stop_on_player_die: stop_on_player_die_default(),
             cycle_run_stop: CycleRunStopMode::default(),
             cycle_run_duration_millis: cycle_run_duration_millis_default(),
             cycle_stop_duration_millis: cycle_stop_duration_millis_default(),
+            operation_schedule: OperationSchedule::default(),
+            enable_party_invite_auto_accept: false,
+            party_invite_whitelist: vec![],
+            player_request_policy: PlayerRequestPolicy::default(),
+            enable_character_auto_detect: false,
+            enable_character_verification: false,
+            enable_idle_watchdog: false,
+            idle_watchdog_millis: idle_watchdog_millis_default(),
+            enable_power_saving: false,
+            power_saving_cpu_temperature_threshold: power_saving_cpu_temperature_threshold_default(
+            ),
+            enable_spectate_server: false,
+            spectate_server_address: spectate_server_address_default(),
+            duo_mode: DuoMode::default(),
+            duo_address: duo_address_default(),
+            duo_token: String::new(),
+            enable_dashboard: false,
+            dashboard_address: dashboard_address_default(),
+            enable_remote_control: false,
+            remote_control_address: remote_control_address_default(),
+            remote_control_token: String::new(),
+            remote_control_tls_cert_path: String::new(),
+            remote_control_tls_key_path: String::new(),
+            enable_status_file: false,
+            status_file_path: status_file_path_default(),
+            enable_status_window_title: false,
+            enable_opencl: false,
+            enable_rune_dataset: false,
+            admin_reaction: AdminReaction::default(),
             discord_bot_access_token: String::default(),
             notifications: Notifications::default(),
             toggle_actions_key: toggle_actions_key_default(),
             platform_start_key: platform_start_key_default(),
             platform_end_key: platform_end_key_default(),
             platform_add_key: platform_add_key_default(),
+            key_remaps: vec![],
         }
     }
 }
 
 impl_identifiable!(Settings);
 
+fn capture_target_fps_default() -> u32 {
+    30
+}
+
 fn stop_on_player_die_default() -> bool {
     true
 }
@@ -77,6 +208,38 @@ fn cycle_stop_duration_millis_default() -> u64 {
     3600000 // 1 hour
 }
 
+fn idle_watchdog_millis_default() -> u64 {
+    60000
+}
+
+fn power_saving_cpu_temperature_threshold_default() -> u32 {
+    85
+}
+
+fn spectate_server_address_default() -> String {
+    "127.0.0.1:5002".to_string()
+}
+
+fn duo_address_default() -> String {
+    "127.0.0.1:5004".to_string()
+}
+
+fn dashboard_address_default() -> String {
+    "127.0.0.1:5005".to_string()
+}
+
+fn remote_control_address_default() -> String {
+    "127.0.0.1:5004".to_string()
+}
+
+fn status_file_path_default() -> String {
+    "status.txt".to_string()
+}
+
+fn key_repeat_interval_millis_default() -> u64 {
+    33
+}
+
 fn enable_solving_default() -> bool {
     true
 }
@@ -118,6 +281,31 @@ pub enum InputMethod {
     Rpc,
 }
 
+/// How configured keys are translated to the keys pressed in the game.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum KeyboardLayout {
+    /// Presses the key producing the configured character in the active keyboard layout.
+    #[default]
+    Active,
+    /// Presses the physical key at the configured key position on a US QWERTY keyboard.
+    Qwerty,
+}
+
+/// How this instance connects to a peer instance for cooperative duo mode.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum DuoMode {
+    #[default]
+    None,
+    /// Accepts the peer connection through the remote control server.
+    Host,
+    /// Connects to the remote control server of the peer hosting at the duo address.
+    Join,
+}
+
 #[derive(
     Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
 )]
@@ -128,6 +316,35 @@ pub enum CycleRunStopMode {
     Repeat,
 }
 
+/// A daily wall-clock schedule for running or halting the bot.
+///
+/// Times are minutes since midnight in the time zone offset by [`Self::utc_offset_minutes`] from
+/// UTC. The schedule only acts when a boundary is crossed so that running or halting manually
+/// in-between is respected until the next boundary.
+#[derive(Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub struct OperationSchedule {
+    pub mode: OperationScheduleMode,
+    /// The minute the bot starts running for [`OperationScheduleMode::Window`].
+    pub start_minute: u32,
+    /// The minute the bot halts for [`OperationScheduleMode::Window`] and
+    /// [`OperationScheduleMode::StopAt`].
+    pub end_minute: u32,
+    pub utc_offset_minutes: i32,
+}
+
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum OperationScheduleMode {
+    #[default]
+    None,
+    /// Runs between the start and end minutes every day.
+    Window,
+    /// Halts at the end minute every day.
+    #[strum(to_string = "Stop at")]
+    StopAt,
+}
+
 #[derive(
     Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
 )]
@@ -139,10 +356,16 @@ pub enum CaptureMode {
     BitBltArea,
 }
 
-#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Notifications {
     pub discord_webhook_url: String,
     pub discord_user_id: String,
+    /// The Telegram bot token for sending notifications and receiving commands.
+    #[serde(default)]
+    pub telegram_bot_token: String,
+    /// The Telegram chat notified and accepted commands from.
+    #[serde(default)]
+    pub telegram_chat_id: String,
     pub notify_on_fail_or_change_map: bool,
     pub notify_on_rune_appear: bool,
     pub notify_on_elite_boss_appear: bool,
@@ -154,4 +377,94 @@ pub struct Notifications {
     pub notify_on_lie_detector_appear: bool,
     #[serde(default)]
     pub notify_on_cycle_run_stop: bool,
+    #[serde(default)]
+    pub notify_on_admin_appear: bool,
+    #[serde(default)]
+    pub notify_on_player_idle: bool,
+    #[serde(default)]
+    pub notify_on_rune_solve_fail: bool,
+    #[serde(default)]
+    pub notify_on_player_health_low: bool,
+    /// The health percentage below which [`Self::notify_on_player_health_low`] notifies.
+    #[serde(default = "player_health_low_percent_default")]
+    pub player_health_low_percent: u32,
+    #[serde(default)]
+    pub notify_on_bot_halt: bool,
+    #[serde(default)]
+    pub notify_on_potion_stock_low: bool,
+    #[serde(default)]
+    pub notify_on_player_request: bool,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            discord_webhook_url: String::default(),
+            discord_user_id: String::default(),
+            telegram_bot_token: String::default(),
+            telegram_chat_id: String::default(),
+            notify_on_fail_or_change_map: false,
+            notify_on_rune_appear: false,
+            notify_on_elite_boss_appear: false,
+            notify_on_player_die: false,
+            notify_on_player_guildie_appear: false,
+            notify_on_player_stranger_appear: false,
+            notify_on_player_friend_appear: false,
+            notify_on_lie_detector_appear: false,
+            notify_on_cycle_run_stop: false,
+            notify_on_admin_appear: false,
+            notify_on_player_idle: false,
+            notify_on_rune_solve_fail: false,
+            notify_on_player_health_low: false,
+            player_health_low_percent: player_health_low_percent_default(),
+            notify_on_bot_halt: false,
+            notify_on_potion_stock_low: false,
+            notify_on_player_request: false,
+        }
+    }
+}
+
+fn player_health_low_percent_default() -> u32 {
+    30
+}
+
+/// Policy for trade and duel request popups from other players.
+///
+/// These popups are a strong sign that a human is interacting with the character.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum PlayerRequestPolicy {
+    /// Leaves the popup as is.
+    Ignore,
+    /// Clicks the decline button of the popup.
+    #[default]
+    Decline,
+    /// Halts the bot and notifies with the popup left open.
+    HaltAndNotify,
+}
+
+/// Reaction when a GM/admin is detected.
+///
+/// When enabled, the bot halts immediately and optionally sends a chat message after
+/// a randomized delay.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AdminReaction {
+    pub enabled: bool,
+    pub chat_enabled: bool,
+    pub chat_content: String,
+    pub chat_delay_millis: u64,
+    pub chat_delay_millis_random_range: u64,
+}
+
+impl Default for AdminReaction {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            chat_enabled: false,
+            chat_content: String::new(),
+            chat_delay_millis: 5000,
+            chat_delay_millis_random_range: 5000,
+        }
+    }
 }
@@ -0,0 +1,110 @@
+use std::{collections::HashMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use super::impl_identifiable;
+
+/// A persistent model of the metrics recorded during one bot session.
+///
+/// A session starts when the bot is launched. Timestamps are the number of milliseconds since
+/// the Unix epoch.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionStats {
+    #[serde(skip_serializing, default)]
+    pub id: Option<i64>,
+    #[serde(default)]
+    pub started_millis: u64,
+    /// The total duration the bot was running in this session.
+    #[serde(default)]
+    pub runtime_millis: u64,
+    #[serde(default)]
+    pub runes_solved: u32,
+    #[serde(default)]
+    pub runes_failed: u32,
+    #[serde(default)]
+    pub deaths: u32,
+    #[serde(default)]
+    pub channel_changes: u32,
+    /// The number of actions executed keyed by preset name.
+    #[serde(default)]
+    pub actions_per_preset: HashMap<String, u32>,
+    /// The total number of mobs detected while auto mobbing.
+    #[serde(default)]
+    pub mob_detections: u32,
+    /// The number of mobs detected while auto mobbing keyed by the quadrant of the bound.
+    #[serde(default)]
+    pub mob_detections_per_quadrant: HashMap<String, u32>,
+    /// The delays of fixed interval actions keyed by action name.
+    #[serde(default)]
+    pub action_delays: HashMap<String, ActionDelayStats>,
+    #[serde(default)]
+    pub exp_samples: Vec<ExpSample>,
+}
+
+impl_identifiable!(SessionStats);
+
+/// A sample of the player EXP and level read through OCR.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExpSample {
+    pub timestamp_millis: u64,
+    pub level: Option<u32>,
+    pub exp_percent: Option<f32>,
+}
+
+/// The delays of a fixed interval action in starting again after its interval.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionDelayStats {
+    /// The configured interval of the action.
+    pub interval_millis: u64,
+    /// The number of times the action started again after its interval.
+    pub samples: u32,
+    /// The number of samples that noticeably started late.
+    pub delayed_samples: u32,
+    /// The total delay of the delayed samples.
+    pub delayed_millis: u64,
+}
+
+/// A suggestion for tuning the rotation generated from a [`SessionStats`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RotationSuggestion {
+    /// A quadrant of the auto mob bound yields fewer mob detections than the others.
+    ShrinkBound {
+        quadrant: String,
+        /// How many percent fewer detections than the average of the other quadrants.
+        fewer_percent: u32,
+    },
+    /// A fixed interval action often starts late.
+    ShortenInterval {
+        action: String,
+        interval_millis: u64,
+        /// The average delay of the delayed samples.
+        delay_millis: u64,
+        suggested_interval_millis: u64,
+    },
+}
+
+impl fmt::Display for RotationSuggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RotationSuggestion::ShrinkBound {
+                quadrant,
+                fewer_percent,
+            } => write!(
+                f,
+                "Quadrant {quadrant} yields {fewer_percent}% fewer mob detections, consider \
+                shrinking the bound"
+            ),
+            RotationSuggestion::ShortenInterval {
+                action,
+                interval_millis,
+                delay_millis,
+                suggested_interval_millis,
+            } => write!(
+                f,
+                "{action} every {interval_millis} ms is often delayed by {}s, consider \
+                {suggested_interval_millis} ms",
+                delay_millis / 1000
+            ),
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     cell::RefCell,
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     fmt::{Debug, Formatter},
     hash::Hash,
     rc::Rc,
@@ -9,7 +9,7 @@ use std::{
 
 use anyhow::{Result, anyhow};
 use base64::{Engine, prelude::BASE64_STANDARD};
-use log::{debug, info};
+use log::{debug, info, warn};
 #[cfg(test)]
 use mockall::automock;
 use opencv::{
@@ -19,9 +19,9 @@ use opencv::{
 use tokio::sync::broadcast::Receiver;
 
 use crate::{
-    ActionKeyDirection, ActionKeyWith, NavigationPaths, Position, WaitAfterBuffered,
+    ActionKeyDirection, ActionKeyWith, Map, NavigationPaths, Position, WaitAfterBuffered,
     bridge::{KeyKind, LinkKeyKind},
-    database::query_navigation_paths,
+    database::{query_map, query_navigation_paths},
     detect::Detector,
     ecs::{Resources, WorldEvent},
     minimap::Minimap,
@@ -33,6 +33,8 @@ use crate::{
 #[cfg_attr(test, automock)]
 trait NavigatorDataSource: 'static + Debug {
     fn query_paths(&self) -> Result<Vec<NavigationPaths>>;
+
+    fn query_map(&self, id: i64) -> Result<Option<Map>>;
 }
 
 #[derive(Debug, Default)]
@@ -42,6 +44,10 @@ impl NavigatorDataSource for DefaultNavigatorDataSource {
     fn query_paths(&self) -> Result<Vec<NavigationPaths>> {
         query_navigation_paths()
     }
+
+    fn query_map(&self, id: i64) -> Result<Option<Map>> {
+        query_map(id)
+    }
 }
 
 /// Internal representation of [`NavigationPath`].
@@ -96,6 +102,20 @@ enum UpdateState {
     NoMatch,
 }
 
+/// The maximum number of consecutive recoveries from a mismatched map before giving up.
+pub const MAX_MAP_MISMATCH_RECOVERIES: u32 = 2;
+
+/// Verification state of the current map after the minimap has changed.
+///
+/// The minimap changes after a channel change, a portal use or a navigation step. The rotation
+/// is held until the newly detected minimap is verified to be the expected map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MapVerification {
+    Pending,
+    Matched,
+    Mismatched,
+}
+
 /// Manages navigation paths to reach a certain minimap.
 #[cfg_attr(test, automock)]
 pub trait Navigator: Debug + 'static {
@@ -120,6 +140,35 @@ pub trait Navigator: Debug + 'static {
     /// Same as [`Self::mark_dirty`] with `invalidate_cache` as `false` but also sets
     /// the navigation destination.
     fn mark_dirty_with_destination(&mut self, paths_id_index: Option<(i64, usize)>);
+
+    /// Sets the navigation destination to the path attached to map `map_id`.
+    ///
+    /// The player is then walked through the portals on the route with the fewest portal
+    /// traversals from the current map. Returns `false` if the map does not exist or has no
+    /// attached path.
+    fn navigate_to(&mut self, map_id: i64) -> bool;
+
+    /// Sets the expected minimap `(width, height)` and the expected base64 minimap name snapshot
+    /// used to verify the current map after the minimap has changed.
+    fn set_expected_minimap(
+        &mut self,
+        size: Option<(i32, i32)>,
+        name_snapshot_base64: Option<String>,
+    );
+
+    /// Whether the current map was verified to not be the expected map.
+    fn is_map_mismatched(&self) -> bool;
+
+    /// Marks a mismatched map to be verified again after the minimap is re-detected.
+    ///
+    /// Returns `false` without marking if the map stayed mismatched after
+    /// [`MAX_MAP_MISMATCH_RECOVERIES`] consecutive recoveries.
+    fn recover_map_mismatch(&mut self) -> bool;
+
+    /// Whether the player has reached the navigation destination in the current map.
+    ///
+    /// This is `false` until the paths are recomputed after the destination is set.
+    fn has_reached_destination(&self) -> bool;
 }
 
 #[derive(Debug)]
@@ -143,6 +192,14 @@ pub struct DefaultNavigator {
     /// Cached next point navigation computation.
     last_point_state: Option<PointState>,
     destination_path_id: Option<String>,
+    /// The expected minimap `(width, height)` of the current map.
+    expected_minimap_size: Option<(i32, i32)>,
+    /// The expected grayscale minimap name snapshot of the current map.
+    expected_minimap_name: Option<Mat>,
+    /// Whether the current map has been verified after the minimap changed.
+    map_verification: MapVerification,
+    /// The number of recoveries from a mismatched map since the map was last matched.
+    map_mismatch_recoveries: u32,
     event_receiver: Receiver<WorldEvent>,
 }
 
@@ -164,6 +221,10 @@ impl DefaultNavigator {
             path_last_update: Instant::now(),
             last_point_state: None,
             destination_path_id: None,
+            expected_minimap_size: None,
+            expected_minimap_name: None,
+            map_verification: MapVerification::Pending,
+            map_mismatch_recoveries: 0,
             event_receiver,
         }
     }
@@ -172,11 +233,6 @@ impl DefaultNavigator {
     fn update(&mut self, resources: &Resources, minimap_state: Minimap, did_minimap_changed: bool) {
         const UPDATE_RETRY_MAX_COUNT: u32 = 3;
 
-        if did_minimap_changed {
-            // Do not reset `base_path`, `current_path` and `last_point_state` here so that
-            // `update_current_path_from_current_location` will try to reuse that when looking up.
-            self.mark_dirty(false);
-        }
         if self.path_dirty {
             match self.update_current_path_from_current_location(resources, minimap_state) {
                 UpdateState::Pending => (),
@@ -193,55 +249,6 @@ impl DefaultNavigator {
     }
 
     fn compute_next_point(&self) -> PointState {
-        fn search_point(from: Rc<RefCell<Path>>, to_id: String) -> Option<Point> {
-            type CameFrom = (Option<Rc<RefCell<Path>>>, Option<Point>);
-
-            let from_id = from.borrow().id.clone();
-            let mut point = None;
-            let mut came_from: HashMap<String, CameFrom> = HashMap::new();
-
-            dfs(
-                (from, None, None),
-                |(path, _, _)| path.borrow().id.clone(),
-                |(path, _, _)| {
-                    path.borrow()
-                        .points
-                        .iter()
-                        .filter_map(|point| {
-                            Some((
-                                point.next_path.clone()?,
-                                Some(path.clone()),
-                                Some(point.clone()),
-                            ))
-                        })
-                        .collect()
-                },
-                |(path, from_path, from_point)| {
-                    let path_id = path.borrow().id.clone();
-
-                    came_from
-                        .try_insert(path_id.clone(), (from_path.clone(), from_point.clone()))
-                        .expect("not visited");
-                    if path_id == to_id {
-                        let mut current = path_id.clone();
-                        while let Some((Some(from_path), Some(from_point))) =
-                            came_from.get(&current)
-                        {
-                            if from_path.borrow().id == from_id {
-                                point = Some(from_point.clone());
-                                return false;
-                            }
-                            current = from_path.borrow().id.clone();
-                        }
-                    }
-
-                    true
-                },
-            );
-
-            point
-        }
-
         if self.path_dirty {
             return PointState::Dirty;
         }
@@ -265,7 +272,7 @@ impl DefaultNavigator {
         // Search from current
         self.current_path
             .clone()
-            .and_then(|path| search_point(path, path_id))
+            .and_then(|path| find_next_point(path, &path_id))
             .map_or(PointState::Unreachable, |point| {
                 PointState::Next(point.x, point.y, point.transition, point.next_path.clone())
             })
@@ -367,6 +374,88 @@ impl DefaultNavigator {
         UpdateState::NoMatch
     }
 
+    /// Verifies the current map if [`Self::map_verification`] is pending.
+    ///
+    /// The map is mismatched when the destination is unreachable from the current map or when the
+    /// minimap size differs from [`Self::expected_minimap_size`] or the minimap name detected by
+    /// `detector` differs from [`Self::expected_minimap_name`] while not on the way to the
+    /// destination. `point_state` is `None` when there is no destination.
+    fn update_map_verification(
+        &mut self,
+        detector: Option<&dyn Detector>,
+        minimap_state: Minimap,
+        point_state: Option<&PointState>,
+    ) -> MapVerification {
+        const MINIMAP_SIZE_TOLERANCE: i32 = 2;
+
+        if self.map_verification != MapVerification::Pending {
+            return self.map_verification;
+        }
+        let Minimap::Idle(idle) = minimap_state else {
+            return MapVerification::Pending;
+        };
+
+        // Maps on the way to the destination are expected to have a different size
+        let en_route = matches!(point_state, Some(PointState::Next(_, _, _, _)));
+        let size_mismatched = !en_route
+            && self.expected_minimap_size.is_some_and(|(width, height)| {
+                (idle.bbox.width - width).abs() > MINIMAP_SIZE_TOLERANCE
+                    || (idle.bbox.height - height).abs() > MINIMAP_SIZE_TOLERANCE
+            });
+        let name_mismatched =
+            !en_route && !size_mismatched && self.is_minimap_name_mismatched(detector, idle.bbox);
+        let verification = if size_mismatched || name_mismatched {
+            MapVerification::Mismatched
+        } else {
+            match point_state {
+                Some(PointState::Dirty) => MapVerification::Pending,
+                Some(PointState::Unreachable) => MapVerification::Mismatched,
+                Some(PointState::Completed | PointState::Next(_, _, _, _)) | None => {
+                    MapVerification::Matched
+                }
+            }
+        };
+        match verification {
+            MapVerification::Pending => (),
+            MapVerification::Matched => {
+                info!(target: "navigator", "current map verified after minimap changed");
+                self.map_mismatch_recoveries = 0;
+            }
+            MapVerification::Mismatched if name_mismatched => {
+                warn!(target: "navigator", "current map mismatched with the expected minimap name");
+            }
+            MapVerification::Mismatched => {
+                warn!(
+                    target: "navigator",
+                    "current map mismatched with minimap size {:?}, expected {:?}",
+                    (idle.bbox.width, idle.bbox.height),
+                    self.expected_minimap_size
+                );
+            }
+        }
+
+        self.map_verification = verification;
+        verification
+    }
+
+    /// Whether the name of `minimap` detected by `detector` does not match
+    /// [`Self::expected_minimap_name`].
+    ///
+    /// Returns `false` when there is no expected name or the name cannot be detected.
+    fn is_minimap_name_mismatched(&self, detector: Option<&dyn Detector>, minimap: Rect) -> bool {
+        let (Some(detector), Some(expected_name)) = (detector, self.expected_minimap_name.as_ref())
+        else {
+            return false;
+        };
+        let Ok(name_bbox) = detector.detect_minimap_name(minimap) else {
+            return false;
+        };
+
+        detector
+            .detect_minimap_name_match(expected_name, name_bbox)
+            .is_err()
+    }
+
     #[inline]
     fn did_minimap_changed(&mut self) -> bool {
         matches!(
@@ -383,19 +472,55 @@ impl Navigator for DefaultNavigator {
         player_context: &mut PlayerContext,
         minimap_state: Minimap,
     ) -> bool {
-        if self.destination_path_id.is_none() || resources.operation.halting() {
+        if resources.operation.halting() {
             return true;
         }
 
         let did_minimap_changed = self.did_minimap_changed();
-        self.update(resources, minimap_state, did_minimap_changed);
+        if did_minimap_changed {
+            // Do not reset `base_path`, `current_path` and `last_point_state` here so that
+            // `update_current_path_from_current_location` will try to reuse that when looking up.
+            self.mark_dirty(false);
+        }
+
+        let next_point_state = if self.destination_path_id.is_some() {
+            self.update(resources, minimap_state, did_minimap_changed);
+
+            let next_point_state = self.compute_next_point();
+            if !matches!(next_point_state, PointState::Dirty) {
+                // Only update `last_point_state` if non-dirty
+                self.last_point_state = Some(next_point_state.clone());
+            }
+            Some(next_point_state)
+        } else {
+            None
+        };
 
-        let next_point_state = self.compute_next_point();
-        if !matches!(next_point_state, PointState::Dirty) {
-            // Only update `last_point_state` if non-dirty
-            self.last_point_state = Some(next_point_state.clone());
+        let was_map_mismatched = self.is_map_mismatched();
+        match self.update_map_verification(
+            resources.detector.as_deref(),
+            minimap_state,
+            next_point_state.as_ref(),
+        ) {
+            MapVerification::Pending => {
+                if did_minimap_changed {
+                    player_context.take_priority_action();
+                }
+                return false;
+            }
+            MapVerification::Mismatched => {
+                // Do not execute any on-going action on the wrong map
+                if !was_map_mismatched {
+                    player_context.clear_actions_aborted(true);
+                }
+                return false;
+            }
+            MapVerification::Matched => (),
         }
 
+        let Some(next_point_state) = next_point_state else {
+            return true;
+        };
         match next_point_state {
             PointState::Dirty => {
                 if did_minimap_changed {
@@ -441,14 +566,16 @@ impl Navigator for DefaultNavigator {
 
     #[inline]
     fn was_last_point_available_or_completed(&self) -> bool {
-        matches!(
-            self.last_point_state,
-            Some(PointState::Next(_, _, _, _) | PointState::Completed)
-        )
+        !self.is_map_mismatched()
+            && matches!(
+                self.last_point_state,
+                Some(PointState::Next(_, _, _, _) | PointState::Completed)
+            )
     }
 
     #[inline]
     fn mark_dirty(&mut self, invalidate_cache: bool) {
+        self.map_verification = MapVerification::Pending;
         self.path_dirty = true;
         self.path_dirty_retry_count = 0;
         if invalidate_cache {
@@ -464,6 +591,55 @@ impl Navigator for DefaultNavigator {
             paths_id_index.map(|(id, index)| path_id_from_paths_id_index(id, index));
         self.mark_dirty(false);
     }
+
+    fn navigate_to(&mut self, map_id: i64) -> bool {
+        let Some(map) = self.source.query_map(map_id).ok().flatten() else {
+            return false;
+        };
+        let Some(paths_id_index) = map.paths_id_index else {
+            return false;
+        };
+        info!(target: "navigator", "navigating to map {}", map.name);
+
+        self.mark_dirty_with_destination(Some(paths_id_index));
+        self.set_expected_minimap(Some((map.width, map.height)), map.name_snapshot_base64);
+        true
+    }
+
+    fn set_expected_minimap(
+        &mut self,
+        size: Option<(i32, i32)>,
+        name_snapshot_base64: Option<String>,
+    ) {
+        self.expected_minimap_size = size;
+        self.expected_minimap_name =
+            name_snapshot_base64.and_then(|base64| decode_base64_to_mat(&base64, true).ok());
+        self.map_verification = MapVerification::Pending;
+        self.map_mismatch_recoveries = 0;
+    }
+
+    #[inline]
+    fn is_map_mismatched(&self) -> bool {
+        self.map_verification == MapVerification::Mismatched
+    }
+
+    fn recover_map_mismatch(&mut self) -> bool {
+        if self.map_mismatch_recoveries >= MAX_MAP_MISMATCH_RECOVERIES {
+            return false;
+        }
+
+        self.map_mismatch_recoveries += 1;
+        self.mark_dirty(true);
+        true
+    }
+
+    #[inline]
+    fn has_reached_destination(&self) -> bool {
+        !self.path_dirty
+            && self.destination_path_id.is_some()
+            && self.map_verification == MapVerification::Matched
+            && matches!(self.last_point_state, Some(PointState::Completed))
+    }
 }
 
 fn build_base_path_from(
@@ -539,6 +715,41 @@ fn build_base_path_from(
     ))
 }
 
+/// Finds the first point to go through from `from` on the route with the fewest portal
+/// traversals to the path `to_id`.
+///
+/// Paths are the nodes and points with a next path are the edges of the searched graph.
+fn find_next_point(from: Rc<RefCell<Path>>, to_id: &str) -> Option<Point> {
+    let from_id = from.borrow().id.clone();
+    // The first point taken from `from` to reach each visited path
+    let mut first_points = HashMap::<String, Option<Point>>::from([(from_id, None)]);
+    let mut queue = VecDeque::from([from]);
+
+    while let Some(path) = queue.pop_front() {
+        let path = path.borrow();
+        let first_point = first_points.get(&path.id).cloned().flatten();
+
+        for point in path.points.iter() {
+            let Some(next_path) = point.next_path.clone() else {
+                continue;
+            };
+            let next_id = next_path.borrow().id.clone();
+            if first_points.contains_key(&next_id) {
+                continue;
+            }
+
+            let first_point = first_point.clone().unwrap_or_else(|| point.clone());
+            if next_id == to_id {
+                return Some(first_point);
+            }
+            first_points.insert(next_id, Some(first_point));
+            queue.push_back(next_path);
+        }
+    }
+
+    None
+}
+
 fn find_current_from_base_path(
     base_path: Rc<RefCell<Path>>,
     detector: &dyn Detector,
@@ -912,4 +1123,200 @@ mod tests {
         assert!(navigator.current_path.is_some());
         assert!(navigator.base_path.is_some());
     }
+
+    #[test]
+    fn navigate_player_holds_when_minimap_detecting() {
+        let resources = Resources::new(None, None);
+        let mut player_context = PlayerContext::default();
+        let mut navigator = DefaultNavigator::default();
+
+        let result = navigator.navigate_player(&resources, &mut player_context, Minimap::Detecting);
+
+        assert!(!result);
+        assert_eq!(navigator.map_verification, MapVerification::Pending);
+    }
+
+    #[test]
+    fn navigate_player_holds_when_minimap_size_mismatched() {
+        let resources = Resources::new(None, None);
+        let mut player_context = PlayerContext::default();
+        let mut minimap = MinimapIdle::default();
+        minimap.bbox = Rect::new(0, 0, 50, 50);
+        let mut navigator = DefaultNavigator::default();
+        navigator.set_expected_minimap(Some((100, 100)), None);
+
+        let result =
+            navigator.navigate_player(&resources, &mut player_context, Minimap::Idle(minimap));
+
+        assert!(!result);
+        assert!(navigator.is_map_mismatched());
+        assert!(!navigator.was_last_point_available_or_completed());
+    }
+
+    #[test]
+    fn navigate_player_continues_when_minimap_size_matched() {
+        let resources = Resources::new(None, None);
+        let mut player_context = PlayerContext::default();
+        let mut minimap = MinimapIdle::default();
+        minimap.bbox = Rect::new(0, 0, 101, 100);
+        let mut navigator = DefaultNavigator::default();
+        navigator.set_expected_minimap(Some((100, 100)), None);
+
+        let result =
+            navigator.navigate_player(&resources, &mut player_context, Minimap::Idle(minimap));
+
+        assert!(result);
+        assert_eq!(navigator.map_verification, MapVerification::Matched);
+    }
+
+    #[test]
+    fn has_reached_destination_only_after_recomputing_paths() {
+        let mut navigator = DefaultNavigator::default();
+        navigator.destination_path_id = Some(42.to_string());
+        navigator.path_dirty = false;
+        navigator.map_verification = MapVerification::Matched;
+        navigator.last_point_state = Some(PointState::Completed);
+        assert!(navigator.has_reached_destination());
+
+        navigator.mark_dirty_with_destination(Some((43, 0)));
+
+        assert!(!navigator.has_reached_destination());
+    }
+
+    #[test]
+    fn recover_map_mismatch_gives_up_after_max_recoveries() {
+        let mut navigator = DefaultNavigator::default();
+
+        for _ in 0..MAX_MAP_MISMATCH_RECOVERIES {
+            navigator.map_verification = MapVerification::Mismatched;
+            assert!(navigator.recover_map_mismatch());
+            assert_eq!(navigator.map_verification, MapVerification::Pending);
+        }
+        navigator.map_verification = MapVerification::Mismatched;
+
+        assert!(!navigator.recover_map_mismatch());
+        assert!(navigator.is_map_mismatched());
+    }
+
+    #[test]
+    fn recover_map_mismatch_resets_after_map_matched() {
+        let mut minimap = MinimapIdle::default();
+        minimap.bbox = Rect::new(0, 0, 100, 100);
+        let mut navigator = DefaultNavigator::default();
+        navigator.map_mismatch_recoveries = MAX_MAP_MISMATCH_RECOVERIES;
+
+        navigator.update_map_verification(None, Minimap::Idle(minimap), None);
+
+        assert_eq!(navigator.map_mismatch_recoveries, 0);
+    }
+
+    #[test]
+    fn update_map_verification_mismatched_when_destination_unreachable() {
+        let mut minimap = MinimapIdle::default();
+        minimap.bbox = Rect::new(0, 0, 100, 100);
+        let mut navigator = DefaultNavigator::default();
+
+        let result = navigator.update_map_verification(
+            None,
+            Minimap::Idle(minimap),
+            Some(&PointState::Unreachable),
+        );
+
+        assert_eq!(result, MapVerification::Mismatched);
+    }
+
+    #[test]
+    fn update_map_verification_matched_when_en_route_with_different_size() {
+        let mut minimap = MinimapIdle::default();
+        minimap.bbox = Rect::new(0, 0, 50, 50);
+        let mut navigator = DefaultNavigator::default();
+        navigator.set_expected_minimap(Some((100, 100)), None);
+        let point_state = PointState::Next(0, 0, NavigationTransition::Portal, None);
+
+        let result =
+            navigator.update_map_verification(None, Minimap::Idle(minimap), Some(&point_state));
+
+        assert_eq!(result, MapVerification::Matched);
+    }
+
+    #[test]
+    fn update_map_verification_mismatched_when_minimap_name_differs() {
+        let mut minimap = MinimapIdle::default();
+        minimap.bbox = Rect::new(0, 0, 100, 100);
+        let mut detector = MockDetector::new();
+        detector
+            .expect_detect_minimap_name()
+            .returning(|_| Ok(Rect::new(0, 0, 10, 5)));
+        detector
+            .expect_detect_minimap_name_match()
+            .returning(|_, _| Err(anyhow!("minimap name not matched")));
+        let mut navigator = DefaultNavigator::default();
+        navigator.set_expected_minimap(Some((100, 100)), None);
+        navigator.expected_minimap_name = Some(Mat::default());
+
+        let result =
+            navigator.update_map_verification(Some(&detector), Minimap::Idle(minimap), None);
+
+        assert_eq!(result, MapVerification::Mismatched);
+    }
+
+    #[test]
+    fn find_next_point_takes_fewest_portal_traversals() {
+        fn path(id: i32, points: Vec<Point>) -> Rc<RefCell<Path>> {
+            Rc::new(RefCell::new(Path {
+                id: id.to_string(),
+                minimap_snapshot_base64: "".into(),
+                name_snapshot_base64: "".into(),
+                minimap_snapshot_grayscale: false,
+                points,
+            }))
+        }
+
+        fn point(x: i32, next_path: Rc<RefCell<Path>>) -> Point {
+            Point {
+                next_path: Some(next_path),
+                x,
+                y: 0,
+                transition: NavigationTransition::Portal,
+            }
+        }
+
+        // A → B → D and A → C → E → D
+        let path_d = path(4, vec![]);
+        let path_e = path(5, vec![point(50, path_d.clone())]);
+        let path_c = path(3, vec![point(30, path_e)]);
+        let path_b = path(2, vec![point(20, path_d)]);
+        let path_a = path(1, vec![point(10, path_b), point(11, path_c)]);
+
+        let point = find_next_point(path_a.clone(), "4").expect("reachable");
+        assert_eq!(point.x, 10);
+        assert!(find_next_point(path_a, "6").is_none());
+    }
+
+    #[test]
+    fn navigate_to_sets_destination_from_map() {
+        let mut mock_source = MockNavigatorDataSource::new();
+        mock_source.expect_query_map().returning(|id| {
+            Ok(Some(Map {
+                id: Some(id),
+                width: 120,
+                height: 80,
+                paths_id_index: (id == 1).then_some((7, 2)),
+                ..Map::default()
+            }))
+        });
+        let (_tx, rx) = channel::<WorldEvent>(1);
+        let mut navigator = DefaultNavigator::new_with_source(rx, mock_source);
+
+        assert!(!navigator.navigate_to(2));
+        assert!(navigator.destination_path_id.is_none());
+
+        assert!(navigator.navigate_to(1));
+        assert_eq!(
+            navigator.destination_path_id,
+            Some(path_id_from_paths_id_index(7, 2))
+        );
+        assert_eq!(navigator.expected_minimap_size, Some((120, 80)));
+        assert!(navigator.path_dirty);
+    }
 }
@@ -21,7 +21,10 @@ use tokio::{
     time::{Instant, sleep},
 };
 
-use crate::Settings;
+use crate::{MapNotifications, NotificationOverride, Settings, telegram::TelegramChat};
+
+/// The memory budget in bytes for frames captured by pending notifications.
+const FRAMES_BUDGET_BYTES: usize = 32 * 1024 * 1024;
 
 static TRUE: bool = true;
 static FALSE: bool = false;
@@ -37,8 +40,17 @@ pub enum NotificationKind {
     PlayerFriendAppear,
     PlayerIsDead,
     LieDetectorAppear,
+    LieDetectorUnsolved,
     CycledToHalt,
     CycledToRun,
+    AdminAppear,
+    PlayerIdle,
+    RuneSolveFail,
+    PlayerHealthLow,
+    PlayerHealthCritical,
+    PotionStockLow,
+    PlayerRequest,
+    BotHalt,
 }
 
 impl NotificationKind {
@@ -59,61 +71,94 @@ impl NotificationKind {
             NotificationKind::PlayerFriendAppear => {
                 settings.notifications.notify_on_player_friend_appear
             }
-            NotificationKind::LieDetectorAppear => {
+            NotificationKind::LieDetectorAppear | NotificationKind::LieDetectorUnsolved => {
                 settings.notifications.notify_on_lie_detector_appear
             }
             NotificationKind::CycledToHalt | NotificationKind::CycledToRun => {
                 settings.notifications.notify_on_cycle_run_stop
             }
+            NotificationKind::AdminAppear => settings.notifications.notify_on_admin_appear,
+            NotificationKind::PlayerIdle => settings.notifications.notify_on_player_idle,
+            NotificationKind::RuneSolveFail => settings.notifications.notify_on_rune_solve_fail,
+            NotificationKind::PlayerHealthLow | NotificationKind::PlayerHealthCritical => {
+                settings.notifications.notify_on_player_health_low
+            }
+            NotificationKind::PotionStockLow => settings.notifications.notify_on_potion_stock_low,
+            NotificationKind::PlayerRequest => settings.notifications.notify_on_player_request,
+            NotificationKind::BotHalt => settings.notifications.notify_on_bot_halt,
         }
     }
 
-    fn content(&self, settings: &Settings) -> String {
-        let user_id = settings
-            .notifications
-            .discord_user_id
-            .is_empty()
-            .not()
-            .then_some(format!("<@{}> ", settings.notifications.discord_user_id))
-            .unwrap_or_default();
+    fn map_override(&self, notifications: &MapNotifications) -> NotificationOverride {
+        match self {
+            NotificationKind::RuneAppear => notifications.rune_appear,
+            NotificationKind::EliteBossAppear => notifications.elite_boss_appear,
+            NotificationKind::PlayerGuildieAppear => notifications.player_guildie_appear,
+            NotificationKind::PlayerStrangerAppear => notifications.player_stranger_appear,
+            NotificationKind::PlayerFriendAppear => notifications.player_friend_appear,
+            NotificationKind::FailOrMapChange
+            | NotificationKind::PlayerIsDead
+            | NotificationKind::LieDetectorAppear
+            | NotificationKind::LieDetectorUnsolved
+            | NotificationKind::CycledToHalt
+            | NotificationKind::CycledToRun
+            | NotificationKind::AdminAppear
+            | NotificationKind::PlayerIdle
+            | NotificationKind::RuneSolveFail
+            | NotificationKind::PlayerHealthLow
+            | NotificationKind::PlayerHealthCritical
+            | NotificationKind::PotionStockLow
+            | NotificationKind::PlayerRequest
+            | NotificationKind::BotHalt => NotificationOverride::Global,
+        }
+    }
 
+    fn content(&self, settings: &Settings) -> String {
         match self {
             NotificationKind::FailOrMapChange => {
                 if settings.stop_on_fail_or_change_map {
-                    format!(
-                        "{user_id}Bot stopped because it has failed to detect or the map has changed"
-                    )
+                    "Bot stopped because it has failed to detect or the map has changed".to_string()
                 } else {
-                    format!("{user_id}Bot has failed to detect or the map has changed")
+                    "Bot has failed to detect or the map has changed".to_string()
                 }
             }
-            NotificationKind::RuneAppear => {
-                format!("{user_id}Bot has detected a rune on map")
-            }
-            NotificationKind::EliteBossAppear => {
-                format!("{user_id}Elite boss spawned")
-            }
-            NotificationKind::PlayerIsDead => {
-                format!("{user_id}The player is dead")
-            }
+            NotificationKind::RuneAppear => "Bot has detected a rune on map".to_string(),
+            NotificationKind::EliteBossAppear => "Elite boss spawned".to_string(),
+            NotificationKind::PlayerIsDead => "The player is dead".to_string(),
             NotificationKind::PlayerGuildieAppear => {
-                format!("{user_id}Bot has detected guildie player(s)")
+                "Bot has detected guildie player(s)".to_string()
             }
             NotificationKind::PlayerStrangerAppear => {
-                format!("{user_id}Bot has detected stranger player(s)")
+                "Bot has detected stranger player(s)".to_string()
             }
-            NotificationKind::PlayerFriendAppear => {
-                format!("{user_id}Bot has detected friend player(s)")
+            NotificationKind::PlayerFriendAppear => "Bot has detected friend player(s)".to_string(),
+            NotificationKind::LieDetectorAppear => "Bot has detected the lie detector".to_string(),
+            NotificationKind::LieDetectorUnsolved => {
+                "**URGENT** Bot has failed to solve the lie detector and stopped".to_string()
+            }
+            NotificationKind::CycledToRun => "Bot has cycled to run.".to_string(),
+            NotificationKind::CycledToHalt => "Bot has cycled to stop.".to_string(),
+            NotificationKind::AdminAppear => {
+                "**URGENT** Bot has detected an admin and stopped".to_string()
             }
-            NotificationKind::LieDetectorAppear => {
-                format!("{user_id}Bot has detected the lie detector")
+            NotificationKind::PlayerIdle => {
+                "Bot has not made any progress for a while and is unstucking".to_string()
             }
-            NotificationKind::CycledToRun => {
-                format!("{user_id}Bot has cycled to run.")
+            NotificationKind::RuneSolveFail => "Bot has failed to solve the rune".to_string(),
+            NotificationKind::PlayerHealthLow => {
+                format!(
+                    "The player health is below {}%",
+                    settings.notifications.player_health_low_percent
+                )
             }
-            NotificationKind::CycledToHalt => {
-                format!("{user_id}Bot has cycled to stop.")
+            NotificationKind::PlayerHealthCritical => {
+                "Bot has stopped because the player health stayed critical".to_string()
             }
+            NotificationKind::PotionStockLow => "The potion stock is running low".to_string(),
+            NotificationKind::PlayerRequest => {
+                "Bot has stopped because of a trade or duel request".to_string()
+            }
+            NotificationKind::BotHalt => "Bot has stopped running".to_string(),
         }
     }
 
@@ -129,10 +174,18 @@ impl NotificationKind {
             | NotificationKind::PlayerIsDead
             | NotificationKind::PlayerGuildieAppear
             | NotificationKind::PlayerStrangerAppear
-            | NotificationKind::PlayerFriendAppear => vec![ScheduledFrame::new_deadline(2)],
-            NotificationKind::RuneAppear | NotificationKind::LieDetectorAppear => {
-                vec![ScheduledFrame::new_deadline(1)]
-            }
+            | NotificationKind::PlayerFriendAppear
+            | NotificationKind::PlayerIdle
+            | NotificationKind::RuneSolveFail
+            | NotificationKind::PotionStockLow
+            | NotificationKind::BotHalt => vec![ScheduledFrame::new_deadline(2)],
+            NotificationKind::RuneAppear
+            | NotificationKind::PlayerHealthLow
+            | NotificationKind::PlayerHealthCritical
+            | NotificationKind::LieDetectorAppear
+            | NotificationKind::LieDetectorUnsolved
+            | NotificationKind::PlayerRequest
+            | NotificationKind::AdminAppear => vec![ScheduledFrame::new_deadline(1)],
         }
     }
 
@@ -146,8 +199,17 @@ impl NotificationKind {
             | NotificationKind::PlayerGuildieAppear
             | NotificationKind::PlayerStrangerAppear
             | NotificationKind::PlayerFriendAppear
-            | NotificationKind::RuneAppear => 3,
-            NotificationKind::LieDetectorAppear => 2,
+            | NotificationKind::PlayerIdle
+            | NotificationKind::RuneAppear
+            | NotificationKind::RuneSolveFail
+            | NotificationKind::PotionStockLow
+            | NotificationKind::BotHalt => 3,
+            NotificationKind::LieDetectorAppear
+            | NotificationKind::PlayerHealthLow
+            | NotificationKind::PlayerHealthCritical => 2,
+            NotificationKind::AdminAppear
+            | NotificationKind::LieDetectorUnsolved
+            | NotificationKind::PlayerRequest => 1,
         };
 
         Duration::from_secs(secs)
@@ -179,8 +241,12 @@ struct ScheduledNotification {
     instant: Instant,
     /// The kind of notification.
     kind: NotificationKind,
-    /// The webhook url.
-    url: String,
+    /// The webhook url if Discord notification is set up.
+    url: Option<String>,
+    /// The chat to also notify if Telegram notification is set up.
+    telegram: Option<TelegramChat>,
+    /// The Discord user mention prefixed to [`Self::content`] when sending to the webhook.
+    mention: String,
     /// The content of the message.
     content: String,
     /// The username of the message's owner.
@@ -198,6 +264,10 @@ struct ScheduledNotification {
 struct ScheduledFrame {
     inner: Option<Vec<u8>>,
     deadline_secs: u32,
+    /// Whether the captured frame was dropped due to exceeding [`FRAMES_BUDGET_BYTES`].
+    ///
+    /// An evicted frame is not captured again.
+    evicted: bool,
 }
 
 impl ScheduledFrame {
@@ -205,6 +275,7 @@ impl ScheduledFrame {
         Self {
             inner: None,
             deadline_secs,
+            evicted: false,
         }
     }
 }
@@ -219,6 +290,8 @@ pub struct DiscordNotification {
     ///
     /// There can only be one unique [`NotificationKind`] scheduled at a time.
     pending: Arc<Mutex<BitVec>>,
+    /// Overrides from the currently in use map merged with [`Self::settings`].
+    map_notifications: MapNotifications,
 }
 
 impl DiscordNotification {
@@ -230,16 +303,66 @@ impl DiscordNotification {
                 mem::variant_count::<NotificationKind>(),
                 false,
             ))),
+            map_notifications: MapNotifications::default(),
         }
     }
 
+    /// Sets the currently in use map's notification overrides.
+    pub fn set_map_notifications(&mut self, notifications: MapNotifications) {
+        self.map_notifications = notifications;
+    }
+
+    /// Gets the health ratio below which [`NotificationKind::PlayerHealthLow`] is notified.
+    ///
+    /// Returns [`None`] if the notification is not enabled.
+    pub fn player_health_low_ratio(&self) -> Option<f32> {
+        let notifications = &self.settings.borrow().notifications;
+        notifications
+            .notify_on_player_health_low
+            .then_some(notifications.player_health_low_percent as f32 / 100.0)
+    }
+
     pub fn schedule_notification(&self, kind: NotificationKind) -> Result<(), Error> {
+        self.schedule(kind, None)
+    }
+
+    /// Schedules `kind` with `details` appended to its content.
+    pub fn schedule_notification_with_details(
+        &self,
+        kind: NotificationKind,
+        details: String,
+    ) -> Result<(), Error> {
+        self.schedule(kind, Some(details))
+    }
+
+    fn schedule(&self, kind: NotificationKind, details: Option<String>) -> Result<(), Error> {
         let settings = self.settings.borrow();
-        if !kind.enabled(&settings) {
+        let enabled = match kind.map_override(&self.map_notifications) {
+            NotificationOverride::Global => kind.enabled(&settings),
+            NotificationOverride::Enabled => true,
+            NotificationOverride::Disabled => false,
+        };
+        if !enabled {
             bail!("notification not enabled");
         }
-        if settings.notifications.discord_webhook_url.is_empty() {
-            bail!("webhook url not provided");
+        let url = settings
+            .notifications
+            .discord_webhook_url
+            .is_empty()
+            .not()
+            .then(|| settings.notifications.discord_webhook_url.clone());
+        let telegram = TelegramChat::new(
+            settings.notifications.telegram_bot_token.clone(),
+            settings.notifications.telegram_chat_id.clone(),
+        );
+        if url.is_none() && telegram.is_none() {
+            bail!("webhook url or telegram chat not provided");
+        }
+        if url
+            .as_ref()
+            .is_some_and(|url| Url::try_from(url.as_str()).is_err())
+        {
+            bail!("failed to parse webhook url");
         }
 
         {
@@ -248,18 +371,25 @@ impl DiscordNotification {
                 bail!("notification is already sending");
             }
 
-            let url = settings.notifications.discord_webhook_url.clone();
-            if Url::try_from(url.as_str()).is_err() {
-                bail!("failed to parse webhook url");
-            }
-
-            let content = kind.content(&settings);
+            let mention = settings
+                .notifications
+                .discord_user_id
+                .is_empty()
+                .not()
+                .then_some(format!("<@{}> ", settings.notifications.discord_user_id))
+                .unwrap_or_default();
+            let content = match details {
+                Some(details) => format!("{} ({details})", kind.content(&settings)),
+                None => kind.content(&settings),
+            };
             let frames = kind.scheduled_frames();
             let mut scheduled = self.scheduled.lock().unwrap();
             scheduled.push(ScheduledNotification {
                 instant: Instant::now(),
                 kind,
                 url,
+                telegram,
+                mention,
                 content,
                 username: "maple-bot",
                 frames,
@@ -267,7 +397,11 @@ impl DiscordNotification {
             pending.set(kind.into(), true);
         }
 
-        let delay = kind.schedule_delay_duration();
+        let delay = if self.map_notifications.notify_immediately {
+            Duration::ZERO
+        } else {
+            kind.schedule_delay_duration()
+        };
         let pending = self.pending.clone();
         let scheduled = self.scheduled.clone();
         spawn(async move {
@@ -314,31 +448,78 @@ impl DiscordNotification {
             let elapsed_secs = item.instant.elapsed().as_secs() as u32;
             for scheduled_frame in item.frames.iter_mut() {
                 if elapsed_secs <= scheduled_frame.deadline_secs {
-                    if scheduled_frame.inner.is_none() {
+                    if scheduled_frame.inner.is_none() && !scheduled_frame.evicted {
                         scheduled_frame.inner = to_png(frame.as_ref());
                     }
                     break;
                 }
             }
         }
+        evict_frames_over_budget(&mut scheduled, FRAMES_BUDGET_BYTES);
+    }
+
+    /// Returns the memory used and budget in bytes of frames captured by pending notifications.
+    pub fn frames_memory(&self) -> (usize, usize) {
+        let scheduled = self.scheduled.lock().unwrap();
+        (frames_bytes(&scheduled), FRAMES_BUDGET_BYTES)
+    }
+}
+
+fn frames_bytes(scheduled: &[ScheduledNotification]) -> usize {
+    scheduled
+        .iter()
+        .flat_map(|item| item.frames.iter())
+        .filter_map(|frame| frame.inner.as_ref())
+        .map(Vec::len)
+        .sum()
+}
+
+/// Drops the captured frames of the oldest scheduled notifications until within `budget_bytes`.
+fn evict_frames_over_budget(scheduled: &mut [ScheduledNotification], budget_bytes: usize) {
+    let mut used_bytes = frames_bytes(scheduled);
+    let frames = scheduled.iter_mut().flat_map(|item| item.frames.iter_mut());
+
+    for frame in frames {
+        if used_bytes <= budget_bytes {
+            break;
+        }
+        if let Some(inner) = frame.inner.take() {
+            used_bytes -= inner.len();
+            frame.evicted = true;
+        }
     }
 }
 
 async fn post_notification(notification: ScheduledNotification) -> Result<(), Error> {
-    let http = Http::new("");
-    let webhook = Webhook::from_url(&http, &notification.url).await?;
-    let files = notification
+    let frames = notification
         .frames
         .into_iter()
         .filter_map(|frame| frame.inner)
-        .enumerate()
-        .map(|(index, frame)| {
-            CreateAttachment::bytes(frame, format!("image_{index}.png"))
-                .description(format!("Game snapshot #{index}"))
-        });
+        .collect::<Vec<_>>();
+
+    if let Some(chat) = notification.telegram.as_ref() {
+        let _ = post_telegram_notification(chat, &notification.content, &frames)
+            .await
+            .inspect(|_| {
+                debug!(target: "notification", "calling Telegram API {:?} succeeded", notification.kind);
+            })
+            .inspect_err(|err| {
+                error!(target: "notification", "calling Telegram API failed {err}");
+            });
+    }
+
+    let Some(url) = notification.url else {
+        return Ok(());
+    };
+    let http = Http::new("");
+    let webhook = Webhook::from_url(&http, &url).await?;
+    let files = frames.into_iter().enumerate().map(|(index, frame)| {
+        CreateAttachment::bytes(frame, format!("image_{index}.png"))
+            .description(format!("Game snapshot #{index}"))
+    });
 
     let builder = ExecuteWebhook::new()
-        .content(notification.content)
+        .content(format!("{}{}", notification.mention, notification.content))
         .username(notification.username)
         .files(files);
     let _ = webhook
@@ -354,6 +535,20 @@ async fn post_notification(notification: ScheduledNotification) -> Result<(), Er
     Ok(())
 }
 
+async fn post_telegram_notification(
+    chat: &TelegramChat,
+    content: &str,
+    frames: &[Vec<u8>],
+) -> Result<(), Error> {
+    chat.send_message(content.to_string()).await?;
+    for (index, frame) in frames.iter().enumerate() {
+        chat.send_photo(frame.clone(), format!("Game snapshot #{index}"))
+            .await?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use std::{cell::RefCell, rc::Rc, time::Duration};
@@ -361,8 +556,14 @@ mod test {
     use opencv::core::{CV_8UC4, Mat, MatExprTraitConst};
     use tokio::time::{Instant, advance};
 
-    use super::{DiscordNotification, NotificationKind, ScheduledNotification};
-    use crate::{Notifications, Settings, mat::OwnedMat, notification::ScheduledFrame};
+    use super::{
+        DiscordNotification, NotificationKind, ScheduledNotification, evict_frames_over_budget,
+        frames_bytes,
+    };
+    use crate::{
+        MapNotifications, NotificationOverride, Notifications, Settings, mat::OwnedMat,
+        notification::ScheduledFrame,
+    };
 
     #[tokio::test(start_paused = true)]
     async fn schedule_kind_unique() {
@@ -398,6 +599,77 @@ mod test {
         );
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn schedule_with_details_appends_content() {
+        let noti = DiscordNotification::new(Rc::new(RefCell::new(Settings {
+            notifications: Notifications {
+                discord_webhook_url: "https://discord.com/api/webhooks/foo/bar".to_string(),
+                notify_on_rune_solve_fail: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        })));
+
+        assert!(
+            noti.schedule_notification_with_details(
+                NotificationKind::RuneSolveFail,
+                "1 of 8 attempt(s)".to_string()
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            noti.scheduled.lock().unwrap()[0].content,
+            "Bot has failed to solve the rune (1 of 8 attempt(s))"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn schedule_with_map_override() {
+        let mut noti = DiscordNotification::new(Rc::new(RefCell::new(Settings {
+            notifications: Notifications {
+                discord_webhook_url: "https://discord.com/api/webhooks/foo/bar".to_string(),
+                notify_on_player_stranger_appear: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        })));
+        noti.set_map_notifications(MapNotifications {
+            player_stranger_appear: NotificationOverride::Disabled,
+            rune_appear: NotificationOverride::Enabled,
+            ..Default::default()
+        });
+
+        assert!(
+            noti.schedule_notification(NotificationKind::PlayerStrangerAppear)
+                .is_err()
+        );
+        assert!(
+            noti.schedule_notification(NotificationKind::RuneAppear)
+                .is_ok()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn schedule_with_telegram_only() {
+        let noti = DiscordNotification::new(Rc::new(RefCell::new(Settings {
+            notifications: Notifications {
+                telegram_bot_token: "123:abc".to_string(),
+                telegram_chat_id: "456".to_string(),
+                notify_on_rune_appear: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        })));
+
+        assert!(
+            noti.schedule_notification(NotificationKind::RuneAppear)
+                .is_ok()
+        );
+        let scheduled = noti.scheduled.lock().unwrap();
+        assert!(scheduled[0].url.is_none());
+        assert!(scheduled[0].telegram.is_some());
+    }
+
     #[tokio::test(start_paused = true)]
     async fn schedule_invalid_url() {
         let noti = DiscordNotification::new(Rc::new(RefCell::new(Settings {
@@ -421,7 +693,9 @@ mod test {
         noti.scheduled.lock().unwrap().push(ScheduledNotification {
             instant: Instant::now(),
             kind: NotificationKind::FailOrMapChange,
-            url: "https://example.com".into(),
+            url: Some("https://example.com".into()),
+            telegram: None,
+            mention: String::new(),
             content: "content".into(),
             username: "username",
             frames: vec![
@@ -454,4 +728,34 @@ mod test {
         assert!(scheduled.frames[1].inner.is_some());
         assert!(scheduled.frames[2].inner.is_some());
     }
+
+    #[test]
+    fn evict_frames_over_budget_drops_oldest_frames() {
+        let notification = |frames: Vec<ScheduledFrame>| ScheduledNotification {
+            instant: Instant::now(),
+            kind: NotificationKind::FailOrMapChange,
+            url: Some("https://example.com".into()),
+            telegram: None,
+            mention: String::new(),
+            content: "content".into(),
+            username: "username",
+            frames,
+        };
+        let frame = |bytes: usize| ScheduledFrame {
+            inner: Some(vec![0; bytes]),
+            ..ScheduledFrame::new_deadline(3)
+        };
+        let mut scheduled = vec![
+            notification(vec![frame(4), frame(4)]),
+            notification(vec![frame(4)]),
+        ];
+
+        evict_frames_over_budget(&mut scheduled, 8);
+
+        assert!(scheduled[0].frames[0].evicted);
+        assert!(scheduled[0].frames[0].inner.is_none());
+        assert!(scheduled[0].frames[1].inner.is_some());
+        assert!(scheduled[1].frames[0].inner.is_some());
+        assert_eq!(frames_bytes(&scheduled), 8);
+    }
 }
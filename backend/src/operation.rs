@@ -3,12 +3,18 @@ use std::fmt::Display;
 use std::fmt::Formatter;
 use std::time::Duration;
 use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
+use crate::BotOperation;
 use crate::BotOperationUpdate;
 use crate::CycleRunStopMode;
+use crate::OperationSchedule;
+use crate::OperationScheduleMode;
+use crate::SavedOperation;
 
 /// Current operating state of the bot.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Operation {
     HaltUntil {
         instant: Instant,
@@ -91,6 +97,7 @@ impl Operation {
                 }
             }
             (BotOperationUpdate::Run, CycleRunStopMode::None) => Operation::Running,
+            (BotOperationUpdate::Schedule(_), _) => self,
         }
     }
 
@@ -196,6 +203,191 @@ impl Display for Operation {
     }
 }
 
+/// The reason the bot is halted by a safety mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HaltReason {
+    /// The lie detector is still opened after failing to solve it multiple times.
+    LieDetectorUnsolved,
+    /// The current map stayed mismatched with the expected map after re-detecting the minimap.
+    MapMismatched,
+}
+
+impl Display for HaltReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HaltReason::LieDetectorUnsolved => write!(f, "Lie detector unsolved"),
+            HaltReason::MapMismatched => write!(f, "Map mismatched"),
+        }
+    }
+}
+
+impl From<Operation> for SavedOperation {
+    fn from(value: Operation) -> Self {
+        match value {
+            Operation::HaltUntil {
+                instant,
+                run_duration_millis,
+                stop_duration_millis,
+            } => SavedOperation::HaltUntil {
+                timestamp_millis: timestamp_millis_from_instant(instant),
+                run_duration_millis,
+                stop_duration_millis,
+            },
+            Operation::TemporaryHalting {
+                resume,
+                run_duration_millis,
+                stop_duration_millis,
+                once,
+            } => SavedOperation::TemporaryHalting {
+                resume_millis: resume.as_millis() as u64,
+                run_duration_millis,
+                stop_duration_millis,
+                once,
+            },
+            Operation::Halting => SavedOperation::Halting,
+            Operation::Running => SavedOperation::Running,
+            Operation::RunUntil {
+                instant,
+                run_duration_millis,
+                stop_duration_millis,
+                once,
+            } => SavedOperation::RunUntil {
+                timestamp_millis: timestamp_millis_from_instant(instant),
+                run_duration_millis,
+                stop_duration_millis,
+                once,
+            },
+        }
+    }
+}
+
+/// Converts back from [`SavedOperation`].
+///
+/// Timestamps that have already passed are converted to [`Instant::now`] so that
+/// [`Operation::update_tick`] can continue the schedule from there.
+impl From<SavedOperation> for Operation {
+    fn from(value: SavedOperation) -> Self {
+        match value {
+            SavedOperation::HaltUntil {
+                timestamp_millis,
+                run_duration_millis,
+                stop_duration_millis,
+            } => Operation::HaltUntil {
+                instant: instant_from_timestamp_millis(timestamp_millis),
+                run_duration_millis,
+                stop_duration_millis,
+            },
+            SavedOperation::TemporaryHalting {
+                resume_millis,
+                run_duration_millis,
+                stop_duration_millis,
+                once,
+            } => Operation::TemporaryHalting {
+                resume: Duration::from_millis(resume_millis),
+                run_duration_millis,
+                stop_duration_millis,
+                once,
+            },
+            SavedOperation::Halting => Operation::Halting,
+            SavedOperation::Running => Operation::Running,
+            SavedOperation::RunUntil {
+                timestamp_millis,
+                run_duration_millis,
+                stop_duration_millis,
+                once,
+            } => Operation::RunUntil {
+                instant: instant_from_timestamp_millis(timestamp_millis),
+                run_duration_millis,
+                stop_duration_millis,
+                once,
+            },
+        }
+    }
+}
+
+impl From<Operation> for BotOperation {
+    fn from(value: Operation) -> Self {
+        match value {
+            Operation::HaltUntil { instant, .. } => BotOperation::HaltUntil(instant),
+            Operation::TemporaryHalting { resume, .. } => BotOperation::TemporaryHalting(resume),
+            Operation::Halting => BotOperation::Halting,
+            Operation::Running => BotOperation::Running,
+            Operation::RunUntil { instant, .. } => BotOperation::RunUntil(instant),
+        }
+    }
+}
+
+/// A boundary of [`OperationSchedule`] at which the bot should start running or halt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScheduleBoundary {
+    Start,
+    End,
+}
+
+/// Returns the minute of the day at `time` in the time zone of `schedule`.
+pub fn schedule_minute_of_day(schedule: OperationSchedule, time: SystemTime) -> u32 {
+    const MINUTES_PER_DAY: i64 = 24 * 60;
+
+    let minutes = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+        / 60;
+
+    (minutes + schedule.utc_offset_minutes as i64).rem_euclid(MINUTES_PER_DAY) as u32
+}
+
+/// Returns the [`ScheduleBoundary`] of `schedule` crossed when the minute of the day changes
+/// from `from_minute` to `to_minute`.
+///
+/// The end boundary takes precedence if both boundaries are crossed at once.
+pub fn schedule_boundary_crossed(
+    schedule: OperationSchedule,
+    from_minute: u32,
+    to_minute: u32,
+) -> Option<ScheduleBoundary> {
+    let crossed = |minute: u32| {
+        if from_minute <= to_minute {
+            from_minute < minute && minute <= to_minute
+        } else {
+            // Wrapped past midnight
+            minute > from_minute || minute <= to_minute
+        }
+    };
+
+    match schedule.mode {
+        OperationScheduleMode::None => None,
+        OperationScheduleMode::Window => {
+            if crossed(schedule.end_minute) {
+                Some(ScheduleBoundary::End)
+            } else if crossed(schedule.start_minute) {
+                Some(ScheduleBoundary::Start)
+            } else {
+                None
+            }
+        }
+        OperationScheduleMode::StopAt => {
+            crossed(schedule.end_minute).then_some(ScheduleBoundary::End)
+        }
+    }
+}
+
+#[inline]
+fn timestamp_millis_from_instant(instant: Instant) -> u64 {
+    let time = SystemTime::now() + instant.saturating_duration_since(Instant::now());
+
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[inline]
+fn instant_from_timestamp_millis(timestamp_millis: u64) -> Instant {
+    let time = UNIX_EPOCH + Duration::from_millis(timestamp_millis);
+
+    Instant::now() + time.duration_since(SystemTime::now()).unwrap_or_default()
+}
+
 #[inline]
 fn halt_until(run_duration_millis: u64, stop_duration_millis: u64) -> Operation {
     Operation::HaltUntil {
@@ -1,14 +1,28 @@
 use core::range::Range;
 use std::{
     cmp::{Reverse, max, min},
-    collections::{BinaryHeap, HashMap},
+    collections::BinaryHeap,
 };
 
 use opencv::core::{Point, Rect};
 
 use crate::array::Array;
 
-pub const MAX_PLATFORMS_COUNT: usize = 24;
+/// The maximum number of platforms used for path finding.
+///
+/// This is bounded by the number of bits in [`PlatformWithNeighbors::neighbors`].
+pub const MAX_PLATFORMS_COUNT: usize = 128;
+
+/// The maximum horizontal gap between two non-overlapping platforms to walk and jump across.
+const WALK_AND_JUMP_THRESHOLD: i32 = 13;
+
+/// The base costs of each [`PlatformMovement`] added on top of the distance travelled.
+const WALK_COST: u32 = 1;
+const FALL_COST: u32 = 2;
+const JUMP_COST: u32 = 3;
+const DOUBLE_JUMP_COST: u32 = 5;
+const UP_JUMP_COST: u32 = 6;
+const GRAPPLE_COST: u32 = 10;
 
 /// The kind of movement the player should perform.
 #[derive(Debug, Clone, Copy)]
@@ -20,6 +34,48 @@ pub enum MovementHint {
     WalkAndJump,
 }
 
+/// The kind of movement used to reach a neighbor platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlatformMovement {
+    /// Walks and jumps across a small gap to a non-overlapping platform.
+    Walk,
+    /// Falls down to a lower overlapping platform.
+    Fall,
+    /// Jumps up to a slightly higher overlapping platform.
+    Jump,
+    /// Up jumps to a higher overlapping platform.
+    UpJump,
+    /// Grapples to a much higher overlapping platform.
+    Grapple,
+    /// Double jumps across to a non-overlapping platform.
+    DoubleJump,
+}
+
+/// The thresholds used to determine the movements between platforms during path finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathingThresholds {
+    /// The minimum x distance required for a double jump.
+    pub double_jump: i32,
+    /// The minimum y distance required for a regular jump.
+    pub jump: i32,
+    /// The minimum y distance required to grapple instead of up jumping.
+    pub grappling: i32,
+    /// The maximum y distance between two connected platforms to move vertically.
+    pub vertical: i32,
+}
+
+/// A directed edge from a platform to one of its reachable neighbors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlatformEdge {
+    /// The index of the platform the edge starts from.
+    pub from: usize,
+    /// The index of the neighbor platform the edge ends at.
+    pub to: usize,
+    pub movement: PlatformMovement,
+    /// The cost of this edge during path finding or [`None`] if path finding refuses it.
+    pub cost: Option<u32>,
+}
+
 /// A platform where player can stand on.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct Platform {
@@ -37,7 +93,11 @@ impl Platform {
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct PlatformWithNeighbors {
     inner: Platform,
-    neighbors: Array<Platform, MAX_PLATFORMS_COUNT>,
+    /// The set of indices of the reachable neighbor platforms.
+    ///
+    /// A bit set is used instead of an array of platforms so that copying the platforms stays
+    /// cheap with [`MAX_PLATFORMS_COUNT`] platforms.
+    neighbors: u128,
 }
 
 impl PlatformWithNeighbors {
@@ -50,13 +110,22 @@ impl PlatformWithNeighbors {
     pub fn y(&self) -> i32 {
         self.inner.y
     }
+
+    /// Iterates over the indices of the reachable neighbor platforms.
+    #[inline]
+    fn neighbors(&self) -> impl Iterator<Item = usize> {
+        let neighbors = self.neighbors;
+        (0..MAX_PLATFORMS_COUNT).filter(move |index| neighbors & (1 << index) != 0)
+    }
 }
 
 /// The platform being visited during path finding.
 #[derive(Debug, PartialEq, Eq)]
 struct VisitingPlatform {
+    /// The estimated total cost of the path going through this platform.
     score: u32,
-    platform: Platform,
+    /// The index of the platform.
+    index: usize,
 }
 
 impl PartialOrd for VisitingPlatform {
@@ -98,6 +167,8 @@ pub fn find_platforms_bound(
 /// Builds a list of `PlatformWithNeighbors` from  `&[Platforms]` by determining which platforms
 /// are reachable from each other.
 ///
+/// Empty platforms are skipped and only the first [`MAX_PLATFORMS_COUNT`] platforms are used.
+///
 /// The following thresholds are used to determine reachability:
 /// - `double_jump_threshold`: minimum x distance required for a double jump
 /// - `jump_threshold`: minimum y distance required for a regular jump
@@ -108,28 +179,26 @@ pub fn find_neighbors(
     jump_threshold: i32,
     grappling_threshold: i32,
 ) -> Vec<PlatformWithNeighbors> {
+    let platforms = platforms
+        .iter()
+        .copied()
+        .filter(|platform| !platform.xs.is_empty())
+        .take(MAX_PLATFORMS_COUNT)
+        .collect::<Vec<_>>();
     let mut vec = Vec::with_capacity(platforms.len());
-    for i in 0..platforms.len() {
-        let current = platforms[i];
-        if current.xs.is_empty() {
-            continue;
-        }
-
-        let mut neighbors = Array::new();
-        for j in (0..i).chain(i + 1..platforms.len()) {
-            let neighbor = platforms[j];
-            if neighbor.xs.is_empty() {
-                continue;
-            }
-
-            if platforms_reachable(
-                current,
-                neighbor,
-                double_jump_threshold,
-                jump_threshold,
-                grappling_threshold,
-            ) {
-                neighbors.push(neighbor);
+    for (i, current) in platforms.iter().copied().enumerate() {
+        let mut neighbors = 0;
+        for (j, neighbor) in platforms.iter().copied().enumerate() {
+            if i != j
+                && platforms_reachable(
+                    current,
+                    neighbor,
+                    double_jump_threshold,
+                    jump_threshold,
+                    grappling_threshold,
+                )
+            {
+                neighbors |= 1 << j;
             }
         }
         vec.push(PlatformWithNeighbors {
@@ -140,89 +209,114 @@ pub fn find_neighbors(
     vec
 }
 
+/// Finds the edges between each of `platforms` and its neighbors as seen by [`find_points_with`].
+///
+/// The edge indices refer to the position of the platforms in `platforms`.
+pub fn find_edges(
+    platforms: &Array<PlatformWithNeighbors, MAX_PLATFORMS_COUNT>,
+    thresholds: PathingThresholds,
+) -> Vec<PlatformEdge> {
+    let mut edges = vec![];
+    for (from, platform) in platforms.iter().enumerate() {
+        for to in platform.neighbors() {
+            let neighbor = platforms[to].inner;
+            let movement = platform_movement(platform.inner, neighbor, thresholds);
+
+            edges.push(PlatformEdge {
+                from,
+                to,
+                movement,
+                cost: edge_cost(platform.inner, neighbor, movement, thresholds.vertical),
+            });
+        }
+    }
+    edges
+}
+
 /// Finds a sequence of points representing a path from `from` to `to`, using the given
 /// platform map.
 ///
-/// `vertical_threshold` represents maximum y distance between two connected platforms to perform
-/// a grappling. This is used as weight score to help prioritize vertical movement over
-/// horizontal movement. If `enable_hint` is true, provides movement hints like `WalkAndJump`.
+/// The path is planned with A* where each edge costs the base cost of its [`PlatformMovement`]
+/// plus the distance travelled, and the y distance to the destination platform is used as the
+/// heuristic. Edges with y distance beyond [`PathingThresholds::vertical`] are refused. If
+/// `enable_hint` is true, provides movement hints like `WalkAndJump`.
 pub fn find_points_with(
     platforms: &Array<PlatformWithNeighbors, MAX_PLATFORMS_COUNT>,
     from: Point,
     to: Point,
     enable_hint: bool,
-    double_jump_threshold: i32,
-    jump_threshold: i32,
-    vertical_threshold: i32,
+    thresholds: PathingThresholds,
 ) -> Option<Vec<(Point, MovementHint)>> {
-    let platforms = platforms
-        .iter()
-        .map(|platform| (platform.inner, *platform))
-        .collect::<HashMap<_, _>>();
-    let from_platform = find_platform(&platforms, from, None)?; // Clamp `from` to nearest platform
-    let to_platform = find_platform(&platforms, to, Some(jump_threshold))?;
-    let mut came_from = HashMap::<Platform, Platform>::new();
+    let from_index = find_platform(platforms.as_slice(), from, None)?; // Clamp `from` to nearest platform
+    let to_index = find_platform(platforms.as_slice(), to, Some(thresholds.jump))?;
+    let to_y = platforms[to_index].inner.y;
+    let heuristic = |index: usize| (platforms[index].inner.y - to_y).unsigned_abs();
+    let mut came_from = [None::<usize>; MAX_PLATFORMS_COUNT];
+    let mut score = [u32::MAX; MAX_PLATFORMS_COUNT];
     let mut visiting = BinaryHeap::new();
-    let mut score = HashMap::<Platform, u32>::new();
 
     visiting.push(Reverse(VisitingPlatform {
-        score: 0,
-        platform: from_platform,
+        score: heuristic(from_index),
+        index: from_index,
     }));
-    score.insert(from_platform, 0);
+    score[from_index] = 0;
+
+    while let Some(Reverse(current)) = visiting.pop() {
+        if current.index == to_index {
+            let mut path = vec![platforms[to_index].inner];
+            let mut index = to_index;
+            while let Some(previous) = came_from[index] {
+                path.push(platforms[previous].inner);
+                index = previous;
+            }
+            path.reverse();
 
-    while !visiting.is_empty() {
-        let current = visiting.pop().unwrap().0;
-        let current_score = score.get(&current.platform).copied().unwrap_or(u32::MAX);
-        if current.platform == to_platform {
             return points_from(
-                &came_from,
+                &path,
                 from,
-                from_platform,
-                to_platform,
                 to,
                 enable_hint,
-                double_jump_threshold,
-                jump_threshold,
+                thresholds.double_jump,
+                thresholds.jump,
             );
         }
+        // Skips the stale entry of a platform already visited with a lower score
+        let current_score = score[current.index];
+        if current.score > current_score.saturating_add(heuristic(current.index)) {
+            continue;
+        }
 
-        let neighbors = platforms[&current.platform].neighbors;
-        for neighbor in neighbors {
-            let tentative_score = current_score.saturating_add(weight_score(
-                current.platform,
-                neighbor,
-                vertical_threshold,
-            ));
-            let neighbor_score = score.get(&neighbor).copied().unwrap_or(u32::MAX);
-            if tentative_score < neighbor_score {
-                came_from.insert(neighbor, current.platform);
-                score.insert(neighbor, tentative_score);
-                if !visiting
-                    .iter()
-                    .any(|platform| platform.0.platform == neighbor)
-                {
-                    visiting.push(Reverse(VisitingPlatform {
-                        score: tentative_score,
-                        platform: neighbor,
-                    }));
-                }
+        let platform = platforms[current.index].inner;
+        for neighbor in platforms[current.index].neighbors() {
+            let neighbor_platform = platforms[neighbor].inner;
+            let movement = platform_movement(platform, neighbor_platform, thresholds);
+            let Some(cost) = edge_cost(platform, neighbor_platform, movement, thresholds.vertical)
+            else {
+                continue;
+            };
+            let tentative_score = current_score.saturating_add(cost);
+            if tentative_score < score[neighbor] {
+                came_from[neighbor] = Some(current.index);
+                score[neighbor] = tentative_score;
+                visiting.push(Reverse(VisitingPlatform {
+                    score: tentative_score.saturating_add(heuristic(neighbor)),
+                    index: neighbor,
+                }));
             }
         }
     }
     None
 }
 
-/// Converts a path from the `came_from` graph into a list of `(Point, MovementHint)` pairs
-/// indicating how to move from `from` to `to`.
+/// Converts a `path` of platforms into a list of `(Point, MovementHint)` pairs indicating how to
+/// move from `from` to `to`.
+///
+/// The first and last platforms of `path` are the platforms of `from` and `to` respectively.
 ///
 /// Adds offsets to handle jump and landing safety margins.
-#[allow(clippy::too_many_arguments)]
 fn points_from(
-    came_from: &HashMap<Platform, Platform>,
+    path: &[Platform],
     from: Point,
-    from_platform: Platform,
-    to_platform: Platform,
     to: Point,
     enable_hint: bool,
     double_jump_threshold: i32,
@@ -235,23 +329,13 @@ fn points_from(
     /// A margin of error to ensure jump is launched just before the platform edge
     const JUMP_OFFSET: i32 = 2;
 
-    const WALK_AND_JUMP_THRESHOLD: i32 = 13;
-
-    let mut current = to_platform;
-    let mut went_to = HashMap::new();
-    while came_from.contains_key(&current) {
-        let next = came_from[&current];
-        went_to.insert(next, current);
-        current = next;
-    }
-    current = from_platform;
+    let mut current = *path.first()?;
 
     // TODO: Likely messed up some numbers
     let mut points = vec![];
     let mut last_point = Point::new(from.x, current.y);
     let double_jump_offset = double_jump_threshold / 2 + DOUBLE_JUMP_EXTRA_OFFSET;
-    while went_to.contains_key(&current) {
-        let next = went_to[&current];
+    for next in path.iter().copied().skip(1) {
         let start_max = max(next.xs.start, current.xs.start);
         let end_min = min(next.xs.end, current.xs.end);
 
@@ -311,7 +395,7 @@ fn points_from(
         current = next;
     }
 
-    points.push((Point::new(to.x, to_platform.y), MovementHint::Infer));
+    points.push((Point::new(to.x, current.y), MovementHint::Infer));
 
     Some(points)
 }
@@ -321,27 +405,74 @@ fn points_from(
 /// If `jump_threshold` is provided, it limits how far vertically the point can be from a platform.
 #[inline]
 fn find_platform(
-    platforms: &HashMap<Platform, PlatformWithNeighbors>,
+    platforms: &[PlatformWithNeighbors],
     point: Point,
     jump_threshold: Option<i32>,
-) -> Option<Platform> {
+) -> Option<usize> {
     platforms
-        .keys()
-        .filter(|platform| platform.xs.contains(&point.x))
-        .min_by_key(|platform| (platform.y - point.y).abs())
-        .filter(|platform| {
-            jump_threshold.is_none() || (platform.y - point.y).abs() < jump_threshold.unwrap()
+        .iter()
+        .enumerate()
+        .filter(|(_, platform)| platform.inner.xs.contains(&point.x))
+        .min_by_key(|(_, platform)| (platform.inner.y - point.y).abs())
+        .filter(|(_, platform)| {
+            jump_threshold.is_none() || (platform.inner.y - point.y).abs() < jump_threshold.unwrap()
         })
-        .copied()
+        .map(|(index, _)| index)
 }
 
+/// Computes the cost of moving from platform `current` to `neighbor` with `movement`.
+///
+/// The cost is the base cost of `movement` plus the y distance and the horizontal gap between
+/// the two platforms. Since the cost is never less than the y distance, the y distance to the
+/// destination stays an admissible heuristic. Returns [`None`] if the y distance is not less
+/// than `vertical_threshold`.
 #[inline]
-fn weight_score(current: Platform, neighbor: Platform, vertical_threshold: i32) -> u32 {
+fn edge_cost(
+    current: Platform,
+    neighbor: Platform,
+    movement: PlatformMovement,
+    vertical_threshold: i32,
+) -> Option<u32> {
     let y_distance = (current.y - neighbor.y).abs();
-    if y_distance < vertical_threshold {
-        y_distance as u32
+    if y_distance >= vertical_threshold {
+        return None;
+    }
+    let gap = max(current.xs.start, neighbor.xs.start) - min(current.xs.end, neighbor.xs.end);
+    let base = match movement {
+        PlatformMovement::Walk => WALK_COST,
+        PlatformMovement::Fall => FALL_COST,
+        PlatformMovement::Jump => JUMP_COST,
+        PlatformMovement::UpJump => UP_JUMP_COST,
+        PlatformMovement::Grapple => GRAPPLE_COST,
+        PlatformMovement::DoubleJump => DOUBLE_JUMP_COST,
+    };
+
+    Some(base + y_distance as u32 + max(gap, 0) as u32)
+}
+
+/// Determines the kind of movement to reach neighbor platform `to` from `from`.
+#[inline]
+fn platform_movement(
+    from: Platform,
+    to: Platform,
+    thresholds: PathingThresholds,
+) -> PlatformMovement {
+    let diff = to.y - from.y;
+    if !ranges_overlap(from.xs, to.xs) {
+        let gap = max(from.xs.start, to.xs.start) - min(from.xs.end, to.xs.end);
+        if gap < WALK_AND_JUMP_THRESHOLD && diff.abs() < thresholds.jump {
+            PlatformMovement::Walk
+        } else {
+            PlatformMovement::DoubleJump
+        }
+    } else if diff <= 0 {
+        PlatformMovement::Fall
+    } else if diff < thresholds.jump {
+        PlatformMovement::Jump
+    } else if diff < thresholds.grappling {
+        PlatformMovement::UpJump
     } else {
-        u32::MAX
+        PlatformMovement::Grapple
     }
 }
 
@@ -385,14 +516,25 @@ fn ranges_overlap<R: Into<Range<i32>>>(first: R, second: R) -> bool {
 
 #[cfg(test)]
 mod tests {
+    use std::time::{Duration, Instant};
+
     use opencv::core::Point;
 
     use super::{
-        MAX_PLATFORMS_COUNT, MovementHint, Platform, PlatformWithNeighbors, find_neighbors,
+        MAX_PLATFORMS_COUNT, MovementHint, PathingThresholds, Platform, PlatformEdge,
+        PlatformMovement, PlatformWithNeighbors, find_edges, find_neighbors,
     };
     use crate::{
         array::Array,
         pathing::{find_points_with, ranges_overlap},
+        run::FPS,
+    };
+
+    const THRESHOLDS: PathingThresholds = PathingThresholds {
+        double_jump: 25,
+        jump: 7,
+        grappling: 24,
+        vertical: 41,
     };
 
     fn make_platforms_with_neighbors(
@@ -428,7 +570,7 @@ mod tests {
         let from = Point::new(10, 50);
         let to = Point::new(20, 60);
 
-        let points = find_points_with(&platforms, from, to, true, 25, 7, 41).unwrap();
+        let points = find_points_with(&platforms, from, to, true, THRESHOLDS).unwrap();
 
         let expected = vec![
             (Point::new(10, 60), MovementHint::Infer),
@@ -438,6 +580,99 @@ mod tests {
         assert_eq!(points, expected);
     }
 
+    #[test]
+    fn find_edges_movements_and_refused_costs() {
+        let platforms = [
+            Platform::new(0..50, 50),
+            Platform::new(0..50, 55),   // Jump from the first
+            Platform::new(0..50, 90),   // Grapple from the first
+            Platform::new(70..120, 50), // Double jump from the first
+        ];
+        let platforms = make_platforms_with_neighbors(&platforms);
+
+        let edges = find_edges(
+            &platforms,
+            PathingThresholds {
+                vertical: 24,
+                ..THRESHOLDS
+            },
+        );
+        let edge = |from, to| {
+            edges
+                .iter()
+                .copied()
+                .find(|edge| edge.from == from && edge.to == to)
+        };
+
+        assert_eq!(
+            edge(0, 1),
+            Some(PlatformEdge {
+                from: 0,
+                to: 1,
+                movement: PlatformMovement::Jump,
+                cost: Some(8),
+            })
+        );
+        assert_eq!(
+            edge(0, 2).map(|edge| (edge.movement, edge.cost)),
+            Some((PlatformMovement::Grapple, None))
+        );
+        assert_eq!(
+            edge(2, 0).map(|edge| (edge.movement, edge.cost)),
+            Some((PlatformMovement::Fall, None))
+        );
+        assert_eq!(
+            edge(0, 3).map(|edge| (edge.movement, edge.cost)),
+            Some((PlatformMovement::DoubleJump, Some(25)))
+        );
+        assert_eq!(edge(3, 2), None);
+    }
+
+    #[test]
+    fn find_edges_walk_up_jump_and_grapple_costs() {
+        let platforms = [
+            Platform::new(0..50, 50),
+            Platform::new(55..100, 52), // Walk from the first
+            Platform::new(0..50, 65),   // Up jump from the first
+            Platform::new(0..50, 80),   // Grapple from the first
+        ];
+        let platforms = make_platforms_with_neighbors(&platforms);
+
+        let edges = find_edges(&platforms, THRESHOLDS);
+        let edge = |from, to| {
+            edges
+                .iter()
+                .copied()
+                .find(|edge| edge.from == from && edge.to == to)
+                .map(|edge| (edge.movement, edge.cost))
+        };
+
+        assert_eq!(edge(0, 1), Some((PlatformMovement::Walk, Some(8))));
+        assert_eq!(edge(0, 2), Some((PlatformMovement::UpJump, Some(21))));
+        assert_eq!(edge(0, 3), Some((PlatformMovement::Grapple, Some(40))));
+        assert_eq!(edge(2, 0), Some((PlatformMovement::Fall, Some(17))));
+    }
+
+    #[test]
+    fn find_points_with_prefers_cheaper_movements() {
+        let platforms = [
+            Platform::new(0..50, 50),
+            Platform::new(0..50, 80),   // Grapple from the first
+            Platform::new(0..50, 65),   // Up jump from the first and to the second
+            Platform::new(70..120, 50), // Unrelated double jump from the first
+        ];
+        let platforms = make_platforms_with_neighbors(&platforms);
+
+        let from = Point::new(10, 50);
+        let to = Point::new(10, 80);
+
+        let points = find_points_with(&platforms, from, to, true, THRESHOLDS).unwrap();
+        let ys = points.iter().map(|(point, _)| point.y).collect::<Vec<_>>();
+
+        // A grapple costs 10 + 30 while two up jumps cost 2 * (6 + 15)
+        assert_eq!(ys, vec![80, 80]);
+    }
+
     #[test]
     fn find_points_with_non_overlapping_jump() {
         let platforms = [
@@ -449,7 +684,7 @@ mod tests {
         let from = Point::new(25, 50);
         let to = Point::new(65, 55);
 
-        let points = find_points_with(&platforms, from, to, true, 25, 7, 41).unwrap();
+        let points = find_points_with(&platforms, from, to, true, THRESHOLDS).unwrap();
 
         assert_eq!(points.first().unwrap().0.y, 50);
         assert_eq!(points.last().unwrap().0.y, 55);
@@ -468,7 +703,7 @@ mod tests {
         let from = Point::new(10, 50);
         let to = Point::new(20, 131);
 
-        let points = find_points_with(&platforms, from, to, true, 25, 7, 41).unwrap();
+        let points = find_points_with(&platforms, from, to, true, THRESHOLDS).unwrap();
 
         // Check that y-values ascend (multi-hop upward movement)
         let ys: Vec<_> = points.iter().map(|(p, _)| p.y).collect();
@@ -492,7 +727,7 @@ mod tests {
         let from = Point::new(25, 50);
         let to = Point::new(125, 55);
 
-        let points = find_points_with(&platforms, from, to, true, 25, 7, 41);
+        let points = find_points_with(&platforms, from, to, true, THRESHOLDS);
         assert!(points.is_none());
     }
 
@@ -507,7 +742,7 @@ mod tests {
         let from = Point::new(45, 50); // Near right edge of first platform
         let to = Point::new(60, 52); // Near left edge of second platform
 
-        let points = find_points_with(&platforms, from, to, true, 25, 7, 41).unwrap();
+        let points = find_points_with(&platforms, from, to, true, THRESHOLDS).unwrap();
 
         let has_walk_and_jump = points
             .iter()
@@ -520,4 +755,41 @@ mod tests {
         assert_eq!(points.first().unwrap().0.y, 50);
         assert_eq!(points.last().unwrap().0.y, 52);
     }
+
+    #[test]
+    fn find_points_with_many_platforms_within_tick() {
+        // A 12 x 10 grid of platforms where each row is reachable by a jump and each column by
+        // a double jump
+        let platforms = (0..10)
+            .flat_map(|row| {
+                (0..12).map(move |column| Platform::new(column * 30..column * 30 + 20, row * 5))
+            })
+            .collect::<Vec<_>>();
+        let platforms = make_platforms_with_neighbors(&platforms);
+        assert_eq!(platforms.len(), 120);
+
+        let from = Point::new(5, 0);
+        let to = Point::new(345, 45);
+        let instant = Instant::now();
+        let points = find_points_with(&platforms, from, to, true, THRESHOLDS);
+        let elapsed = instant.elapsed();
+
+        assert_eq!(points.unwrap().last().unwrap().0, to);
+        assert!(
+            elapsed < Duration::from_millis(1000 / FPS as u64),
+            "Path finding took {elapsed:?}",
+        );
+    }
+
+    #[test]
+    fn find_neighbors_caps_platforms_count() {
+        let platforms = (0..MAX_PLATFORMS_COUNT as i32 + 10)
+            .map(|i| Platform::new(i * 100..i * 100 + 50, 0))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            find_neighbors(&platforms, 25, 7, 41).len(),
+            MAX_PLATFORMS_COUNT
+        );
+    }
 }
@@ -10,8 +10,8 @@ use crate::{
     ecs::{Resources, transition, transition_if},
     minimap::Minimap,
     models::{
-        Action, ActionKey, ActionKeyDirection, ActionKeyWith, ActionMove, FamiliarRarity, Position,
-        SwappableFamiliars, WaitAfterBuffered,
+        Action, ActionKey, ActionKeyDirection, ActionKeyWith, ActionMove, FamiliarRarity,
+        InputMacro, Position, SwappableFamiliars, WaitAfterBuffered,
     },
     player::PlayerEntity,
     run::MS_PER_TICK,
@@ -23,6 +23,9 @@ pub const AUTO_MOB_USE_KEY_X_THRESHOLD: i32 = 16;
 /// The minimum y distance required to transition to [`Player::UseKey`] in auto mob action.
 pub const AUTO_MOB_USE_KEY_Y_THRESHOLD: i32 = 8;
 
+/// The maximum number of keys replayed by a [`Macro`].
+pub const MAX_MACRO_KEYS: usize = 64;
+
 /// Represents the fixed key action.
 ///
 /// Converted from [`ActionKey`] without fields used by [`Rotator`]
@@ -113,6 +116,29 @@ impl From<ActionMove> for Move {
     }
 }
 
+/// Represents the recorded input macro action.
+///
+/// Converted from [`InputMacro`] referenced by [`ActionMacro`](crate::ActionMacro) with keys
+/// exceeding [`MAX_MACRO_KEYS`] dropped.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Macro {
+    /// The keys to send and the number of ticks to wait before sending each key.
+    pub keys: Array<(KeyKind, u32), MAX_MACRO_KEYS>,
+}
+
+impl Macro {
+    pub fn from_input_macro(input_macro: &InputMacro) -> Self {
+        let keys = input_macro
+            .keys
+            .iter()
+            .take(MAX_MACRO_KEYS)
+            .map(|key| (key.key.into(), (key.delay_millis / MS_PER_TICK) as u32))
+            .collect();
+
+        Self { keys }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(test, derive(Default))]
 pub struct AutoMob {
@@ -178,12 +204,16 @@ pub struct FamiliarsSwap {
 #[derive(Clone, Copy, Debug)]
 pub struct Panic {
     pub to: PanicTo,
+    /// The number of channels to skip past when [`Self::to`] is [`PanicTo::Channel`].
+    pub skip_channels: u32,
 }
 
 #[derive(Clone, Copy, Debug)]
 pub enum PanicTo {
     Town,
     Channel,
+    /// Exits to character select through the game menu.
+    CharacterSelect,
 }
 
 #[derive(Clone, Debug)]
@@ -208,6 +238,11 @@ pub struct ExchangeBooster {
     pub all: bool,
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct BuyPotion {
+    pub quantity: u32,
+}
+
 /// Represents an action the [`Rotator`] can use.
 #[derive(Clone, Debug, Display)]
 pub enum PlayerAction {
@@ -215,6 +250,8 @@ pub enum PlayerAction {
     Key(Key),
     /// Fixed move action provided by the user.
     Move(Move),
+    /// Recorded input macro action provided by the user.
+    Macro(Macro),
     /// Solves rune action.
     SolveRune,
     /// Solves the lie detector's transparent shape.
@@ -234,8 +271,24 @@ pub enum PlayerAction {
     UseBooster(UseBooster),
     /// Exchange HEXA booster action.
     ExchangeBooster(ExchangeBooster),
+    /// Buys potions from an opened NPC shop action.
+    BuyPotion(BuyPotion),
     /// Unstucking by pressing ESC.
     Unstuck,
+    /// Enters the state of a registered [`super::PlayerExtension`] action.
+    #[cfg(feature = "player_extensions")]
+    Extension(super::PlayerExtensionId),
+}
+
+impl PlayerAction {
+    /// Gets the position the action moves to before executing if any.
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            PlayerAction::Key(Key { position, .. }) => *position,
+            PlayerAction::Move(Move { position, .. }) => Some(*position),
+            _ => None,
+        }
+    }
 }
 
 impl From<Action> for PlayerAction {
@@ -243,6 +296,8 @@ impl From<Action> for PlayerAction {
         match action {
             Action::Move(action) => PlayerAction::Move(action.into()),
             Action::Key(action) => PlayerAction::Key(action.into()),
+            // Keys are resolved from the referenced `InputMacro` by the `Rotator`
+            Action::Macro(_) => PlayerAction::Macro(Macro::default()),
         }
     }
 }
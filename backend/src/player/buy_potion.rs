@@ -0,0 +1,275 @@
+use opencv::core::Rect;
+
+use super::{Player, timeout::Timeout};
+use crate::{
+    array::Array,
+    bridge::{KeyKind, MouseKind},
+    ecs::{Resources, transition, try_ok_transition},
+    player::{
+        PlayerEntity, next_action,
+        timeout::{Lifecycle, next_timeout_lifecycle},
+        transition_from_action,
+    },
+};
+
+/// The maximum quantity that can be typed into the shop quantity prompt.
+const MAX_QUANTITY: u32 = 9999;
+
+/// The number of ticks between the two clicks of a double click.
+const DOUBLE_CLICK_TICKS: u32 = 5;
+
+/// The number of ticks between typing each key of the quantity.
+const TYPE_INTERVAL: u32 = 10;
+
+/// States of buying potions from an opened NPC shop.
+#[derive(Debug, Clone, Copy)]
+enum State {
+    /// Finding the potion item in the shop list.
+    FindingPotion,
+    /// Double clicking the potion item to open the quantity prompt.
+    OpenQuantityPrompt(Timeout, Rect),
+    /// Typing the quantity into the prompt.
+    TypingQuantity(Timeout, usize),
+    /// Confirming the quantity prompt with `Enter`.
+    Confirming(Timeout),
+    /// Terminal state.
+    Completing(Timeout, bool),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BuyingPotion {
+    state: State,
+    keys: QuantityKeys,
+    success: bool,
+}
+
+impl BuyingPotion {
+    pub fn new(quantity: u32) -> Self {
+        let quantity = quantity.clamp(1, MAX_QUANTITY);
+
+        // Clears the default quantity before typing
+        let mut keys = QuantityKeys::from_iter([KeyKind::Backspace; 4]);
+        let keys_from_chars = quantity.to_string().chars().map(|char| match char {
+            '0' => KeyKind::Zero,
+            '1' => KeyKind::One,
+            '2' => KeyKind::Two,
+            '3' => KeyKind::Three,
+            '4' => KeyKind::Four,
+            '5' => KeyKind::Five,
+            '6' => KeyKind::Six,
+            '7' => KeyKind::Seven,
+            '8' => KeyKind::Eight,
+            '9' => KeyKind::Nine,
+            _ => unreachable!(),
+        });
+        for key in keys_from_chars {
+            keys.push(key);
+        }
+
+        Self {
+            state: State::FindingPotion,
+            keys,
+            success: false,
+        }
+    }
+}
+
+type QuantityKeys = Array<KeyKind, 8>;
+
+/// Updates [`Player::BuyingPotion`] contextual state.
+pub fn update_buying_potion_state(resources: &Resources, player: &mut PlayerEntity) {
+    let Player::BuyingPotion(mut buying) = player.state else {
+        panic!("state is not buying potion")
+    };
+
+    match buying.state {
+        State::FindingPotion => update_finding_potion(resources, &mut buying),
+        State::OpenQuantityPrompt(_, _) => update_open_quantity_prompt(resources, &mut buying),
+        State::TypingQuantity(_, _) => update_typing_quantity(resources, &mut buying),
+        State::Confirming(_) => update_confirming(resources, &mut buying),
+        State::Completing(_, _) => update_completing(resources, &mut buying),
+    };
+
+    let player_next_state = if matches!(buying.state, State::Completing(_, true)) {
+        Player::Idle
+    } else {
+        Player::BuyingPotion(buying)
+    };
+    let is_terminal = matches!(player_next_state, Player::Idle);
+
+    match next_action(&player.context) {
+        Some(_) => {
+            if is_terminal && buying.success {
+                player.context.potion_restocks += 1;
+            }
+            transition_from_action!(player, player_next_state, is_terminal)
+        }
+        None => transition!(
+            player,
+            Player::Idle // Force cancel if it is not initiated from an action
+        ),
+    }
+}
+
+fn update_finding_potion(resources: &Resources, buying: &mut BuyingPotion) {
+    let bbox = try_ok_transition!(
+        buying,
+        State::Completing(Timeout::default(), false),
+        resources.detector().detect_shop_potion()
+    );
+
+    transition!(buying, State::OpenQuantityPrompt(Timeout::default(), bbox))
+}
+
+fn update_open_quantity_prompt(resources: &Resources, buying: &mut BuyingPotion) {
+    let State::OpenQuantityPrompt(timeout, bbox) = buying.state else {
+        panic!("buying potion state is not opening quantity prompt")
+    };
+    let (x, y) = bbox_click_point(bbox);
+
+    match next_timeout_lifecycle(timeout, 20) {
+        Lifecycle::Started(timeout) => {
+            transition!(buying, State::OpenQuantityPrompt(timeout, bbox), {
+                resources.input.send_mouse(x, y, MouseKind::Click);
+            })
+        }
+        Lifecycle::Ended => {
+            transition!(buying, State::TypingQuantity(Timeout::default(), 0))
+        }
+        Lifecycle::Updated(timeout) => {
+            if timeout.current == DOUBLE_CLICK_TICKS {
+                resources.input.send_mouse(x, y, MouseKind::Click);
+            }
+
+            transition!(buying, State::OpenQuantityPrompt(timeout, bbox))
+        }
+    }
+}
+
+fn update_typing_quantity(resources: &Resources, buying: &mut BuyingPotion) {
+    let State::TypingQuantity(timeout, index) = buying.state else {
+        panic!("buying potion state is not typing quantity")
+    };
+    let max_timeout = (buying.keys.len() as u32 + 1) * TYPE_INTERVAL;
+
+    match next_timeout_lifecycle(timeout, max_timeout) {
+        Lifecycle::Started(timeout) => {
+            transition!(buying, State::TypingQuantity(timeout, index))
+        }
+        Lifecycle::Ended => transition!(buying, State::Confirming(Timeout::default())),
+        Lifecycle::Updated(timeout) => {
+            if timeout.current.is_multiple_of(TYPE_INTERVAL) && index < buying.keys.len() {
+                transition!(buying, State::TypingQuantity(timeout, index + 1), {
+                    resources.input.send_key(buying.keys[index]);
+                });
+            }
+
+            transition!(buying, State::TypingQuantity(timeout, index))
+        }
+    }
+}
+
+fn update_confirming(resources: &Resources, buying: &mut BuyingPotion) {
+    let State::Confirming(timeout) = buying.state else {
+        panic!("buying potion state is not confirming")
+    };
+
+    match next_timeout_lifecycle(timeout, 20) {
+        Lifecycle::Started(timeout) => {
+            transition!(buying, State::Confirming(timeout), {
+                resources.input.send_key(KeyKind::Enter);
+                buying.success = true;
+            })
+        }
+        Lifecycle::Ended => transition!(buying, State::Completing(Timeout::default(), false)),
+        Lifecycle::Updated(timeout) => transition!(buying, State::Confirming(timeout)),
+    }
+}
+
+fn update_completing(resources: &Resources, buying: &mut BuyingPotion) {
+    let State::Completing(timeout, completed) = buying.state else {
+        panic!("buying potion state is not completing")
+    };
+
+    match next_timeout_lifecycle(timeout, 20) {
+        Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
+            transition!(buying, State::Completing(timeout, completed))
+        }
+        // Closes the shop
+        Lifecycle::Ended => transition!(buying, State::Completing(timeout, true), {
+            resources.input.send_key(KeyKind::Esc);
+        }),
+    }
+}
+
+#[inline]
+fn bbox_click_point(bbox: Rect) -> (i32, i32) {
+    let x = bbox.x + bbox.width / 2;
+    let y = bbox.y + bbox.height / 2;
+    (x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use anyhow::anyhow;
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::{bridge::MockInput, detect::MockDetector};
+
+    #[test]
+    fn new_clears_then_types_quantity() {
+        let buying = BuyingPotion::new(250);
+
+        assert_eq!(
+            buying.keys.into_iter().copied().collect::<Vec<_>>(),
+            vec![
+                KeyKind::Backspace,
+                KeyKind::Backspace,
+                KeyKind::Backspace,
+                KeyKind::Backspace,
+                KeyKind::Two,
+                KeyKind::Five,
+                KeyKind::Zero,
+            ]
+        );
+    }
+
+    #[test]
+    fn update_finding_potion_double_clicks_potion() {
+        let mut detector = MockDetector::default();
+        detector
+            .expect_detect_shop_potion()
+            .returning(|| Ok(Rect::new(10, 10, 10, 10)));
+        let mut input = MockInput::default();
+        input
+            .expect_send_mouse()
+            .with(eq(15), eq(15), eq(MouseKind::Click))
+            .times(2);
+        let resources = Resources::new(Some(input), Some(detector));
+        let mut buying = BuyingPotion::new(1);
+
+        update_finding_potion(&resources, &mut buying);
+        assert_matches!(buying.state, State::OpenQuantityPrompt(_, _));
+        for _ in 0..=DOUBLE_CLICK_TICKS {
+            update_open_quantity_prompt(&resources, &mut buying);
+        }
+    }
+
+    #[test]
+    fn update_finding_potion_completes_when_not_found() {
+        let mut detector = MockDetector::default();
+        detector
+            .expect_detect_shop_potion()
+            .returning(|| Err(anyhow!("not found")));
+        let resources = Resources::new(None, Some(detector));
+        let mut buying = BuyingPotion::new(1);
+
+        update_finding_potion(&resources, &mut buying);
+
+        assert_matches!(buying.state, State::Completing(_, false));
+        assert!(!buying.success);
+    }
+}
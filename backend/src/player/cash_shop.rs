@@ -1,19 +1,34 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use log::info;
+use opencv::core::Rect;
 
 use super::{
-    Player,
+    Player, PlayerContext,
     timeout::{Lifecycle, Timeout, next_timeout_lifecycle},
 };
 use crate::{
-    bridge::KeyKind,
-    ecs::{Resources, transition, transition_if, try_some_transition},
+    bridge::{KeyKind, MouseKind},
+    ecs::{Resources, transition, transition_if, try_ok_transition, try_some_transition},
     player::PlayerEntity,
 };
 
+const SECS_PER_DAY: u64 = 86400;
+
 #[derive(Clone, Copy, Debug)]
 enum State {
     Entering,
     Entered(Timeout),
+    /// Opening the configured tab containing the daily free items.
+    OpeningFreeTab(Timeout),
+    /// Selecting an unclaimed free item.
+    SelectingFreeItem(Timeout, Rect),
+    /// Clicking the buy button of the selected free item.
+    BuyingFreeItem(Timeout, Rect),
+    /// Clicking the confirm button of the purchase popup.
+    ConfirmingFreeItem(Timeout, Rect),
+    /// Verifying the free item is no longer claimable.
+    VerifyingFreeItem(Timeout),
     Exitting,
     Exitted,
     Stalling(Timeout),
@@ -47,7 +62,22 @@ pub fn update_cash_shop_state(
 
     match cash_shop.state {
         State::Entering => update_entering(resources, &mut cash_shop, cash_shop_key),
-        State::Entered(timeout) => update_entered(&mut cash_shop, timeout),
+        State::Entered(timeout) => update_entered(&player.context, &mut cash_shop, timeout),
+        State::OpeningFreeTab(timeout) => {
+            update_opening_free_tab(resources, &mut cash_shop, timeout)
+        }
+        State::SelectingFreeItem(timeout, bbox) => {
+            update_selecting_free_item(resources, &mut cash_shop, timeout, bbox)
+        }
+        State::BuyingFreeItem(timeout, bbox) => {
+            update_buying_free_item(resources, &mut cash_shop, timeout, bbox)
+        }
+        State::ConfirmingFreeItem(timeout, bbox) => {
+            update_confirming_free_item(resources, &mut cash_shop, timeout, bbox)
+        }
+        State::VerifyingFreeItem(timeout) => {
+            update_verifying_free_item(resources, &mut player.context, &mut cash_shop, timeout)
+        }
         State::Exitting => update_exitting(resources, &mut cash_shop),
         State::Exitted => update_exitted(&mut cash_shop, failed_to_detect_player),
         State::Stalling(timeout) => update_stalling(&mut cash_shop, timeout),
@@ -81,16 +111,161 @@ fn update_entering(resources: &Resources, cash_shop: &mut CashShop, key: KeyKind
     );
 }
 
-fn update_entered(cash_shop: &mut CashShop, timeout: Timeout) {
+fn update_entered(context: &PlayerContext, cash_shop: &mut CashShop, timeout: Timeout) {
     // Exit after 10 secs
     match next_timeout_lifecycle(timeout, 305) {
-        Lifecycle::Ended => transition!(cash_shop, State::Exitting),
+        Lifecycle::Ended => transition_if!(
+            cash_shop,
+            State::OpeningFreeTab(Timeout::default()),
+            State::Exitting,
+            should_claim_freebies(context)
+        ),
         Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
             transition!(cash_shop, State::Entered(timeout))
         }
     }
 }
 
+fn update_opening_free_tab(resources: &Resources, cash_shop: &mut CashShop, timeout: Timeout) {
+    match next_timeout_lifecycle(timeout, 20) {
+        Lifecycle::Started(timeout) => {
+            let bbox = try_ok_transition!(
+                cash_shop,
+                State::Exitting,
+                resources.detector().detect_cash_shop_free_tab()
+            );
+
+            transition!(cash_shop, State::OpeningFreeTab(timeout), {
+                let (x, y) = bbox_click_point(bbox);
+                resources.input.send_mouse(x, y, MouseKind::Click);
+            })
+        }
+        Lifecycle::Ended => {
+            let bbox = try_ok_transition!(
+                cash_shop,
+                State::Exitting,
+                resources.detector().detect_cash_shop_free_item()
+            );
+
+            transition!(
+                cash_shop,
+                State::SelectingFreeItem(Timeout::default(), bbox)
+            )
+        }
+        Lifecycle::Updated(timeout) => transition!(cash_shop, State::OpeningFreeTab(timeout)),
+    }
+}
+
+fn update_selecting_free_item(
+    resources: &Resources,
+    cash_shop: &mut CashShop,
+    timeout: Timeout,
+    bbox: Rect,
+) {
+    match next_timeout_lifecycle(timeout, 20) {
+        Lifecycle::Started(timeout) => {
+            transition!(cash_shop, State::SelectingFreeItem(timeout, bbox), {
+                let (x, y) = bbox_click_point(bbox);
+                resources.input.send_mouse(x, y, MouseKind::Click);
+            })
+        }
+        Lifecycle::Ended => {
+            let bbox = try_ok_transition!(
+                cash_shop,
+                State::Exitting,
+                resources.detector().detect_cash_shop_buy_button()
+            );
+
+            transition!(cash_shop, State::BuyingFreeItem(Timeout::default(), bbox))
+        }
+        Lifecycle::Updated(timeout) => {
+            transition!(cash_shop, State::SelectingFreeItem(timeout, bbox))
+        }
+    }
+}
+
+fn update_buying_free_item(
+    resources: &Resources,
+    cash_shop: &mut CashShop,
+    timeout: Timeout,
+    bbox: Rect,
+) {
+    match next_timeout_lifecycle(timeout, 20) {
+        Lifecycle::Started(timeout) => {
+            transition!(cash_shop, State::BuyingFreeItem(timeout, bbox), {
+                let (x, y) = bbox_click_point(bbox);
+                resources.input.send_mouse(x, y, MouseKind::Click);
+            })
+        }
+        Lifecycle::Ended => {
+            // Some free items are claimed directly without a confirmation popup
+            let bbox = try_ok_transition!(
+                cash_shop,
+                State::VerifyingFreeItem(Timeout::default()),
+                resources.detector().detect_popup_confirm_button()
+            );
+
+            transition!(
+                cash_shop,
+                State::ConfirmingFreeItem(Timeout::default(), bbox)
+            )
+        }
+        Lifecycle::Updated(timeout) => {
+            transition!(cash_shop, State::BuyingFreeItem(timeout, bbox))
+        }
+    }
+}
+
+fn update_confirming_free_item(
+    resources: &Resources,
+    cash_shop: &mut CashShop,
+    timeout: Timeout,
+    bbox: Rect,
+) {
+    match next_timeout_lifecycle(timeout, 20) {
+        Lifecycle::Started(timeout) => {
+            transition!(cash_shop, State::ConfirmingFreeItem(timeout, bbox), {
+                let (x, y) = bbox_click_point(bbox);
+                resources.input.send_mouse(x, y, MouseKind::Click);
+            })
+        }
+        Lifecycle::Ended => transition!(cash_shop, State::VerifyingFreeItem(Timeout::default())),
+        Lifecycle::Updated(timeout) => {
+            transition!(cash_shop, State::ConfirmingFreeItem(timeout, bbox))
+        }
+    }
+}
+
+fn update_verifying_free_item(
+    resources: &Resources,
+    context: &mut PlayerContext,
+    cash_shop: &mut CashShop,
+    timeout: Timeout,
+) {
+    match next_timeout_lifecycle(timeout, 30) {
+        Lifecycle::Started(timeout) => {
+            transition!(cash_shop, State::VerifyingFreeItem(timeout), {
+                // Closes the purchase result popup if any
+                if let Ok(bbox) = resources.detector().detect_popup_ok_new_button() {
+                    let (x, y) = bbox_click_point(bbox);
+                    resources.input.send_mouse(x, y, MouseKind::Click);
+                }
+            })
+        }
+        Lifecycle::Ended => transition!(cash_shop, State::Exitting, {
+            if resources.detector().detect_cash_shop_free_item().is_err() {
+                info!(target: "player", "claimed cash shop free items for today");
+                context.cash_shop_freebies_claimed_day = Some(current_day());
+            } else {
+                info!(target: "player", "failed to verify cash shop free items claimed");
+            }
+        }),
+        Lifecycle::Updated(timeout) => {
+            transition!(cash_shop, State::VerifyingFreeItem(timeout))
+        }
+    }
+}
+
 fn update_exitting(resources: &Resources, cash_shop: &mut CashShop) {
     resources.input.send_key(KeyKind::Esc);
     resources.input.send_key(KeyKind::Enter);
@@ -111,3 +286,170 @@ fn update_stalling(cash_shop: &mut CashShop, timeout: Timeout) {
         }
     }
 }
+
+#[inline]
+fn should_claim_freebies(context: &PlayerContext) -> bool {
+    context.config.cash_shop_claim_freebies
+        && !context.cash_shop_freebies_claimed_day_unknown
+        && context.cash_shop_freebies_claimed_day != Some(current_day())
+}
+
+/// Number of days since Unix epoch in UTC.
+#[inline]
+fn current_day() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() / SECS_PER_DAY)
+        .unwrap_or_default()
+}
+
+#[inline]
+fn bbox_click_point(bbox: Rect) -> (i32, i32) {
+    let x = bbox.x + bbox.width / 2;
+    let y = bbox.y + bbox.height / 2;
+    (x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use anyhow::anyhow;
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::{bridge::MockInput, detect::MockDetector};
+
+    fn rect(x: i32, y: i32) -> Rect {
+        Rect {
+            x,
+            y,
+            width: 10,
+            height: 10,
+        }
+    }
+
+    fn ended_timeout(current: u32) -> Timeout {
+        Timeout {
+            current,
+            started: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn update_entered_exits_when_claim_disabled() {
+        let context = PlayerContext::default();
+        let mut cash_shop = CashShop::new();
+
+        update_entered(&context, &mut cash_shop, ended_timeout(305));
+        assert_matches!(cash_shop.state, State::Exitting);
+    }
+
+    #[test]
+    fn update_entered_exits_when_already_claimed_today() {
+        let mut context = PlayerContext::default();
+        context.config.cash_shop_claim_freebies = true;
+        context.cash_shop_freebies_claimed_day = Some(current_day());
+        let mut cash_shop = CashShop::new();
+
+        update_entered(&context, &mut cash_shop, ended_timeout(305));
+        assert_matches!(cash_shop.state, State::Exitting);
+    }
+
+    #[test]
+    fn update_entered_exits_when_claimed_day_unknown() {
+        let mut context = PlayerContext::default();
+        context.config.cash_shop_claim_freebies = true;
+        context.cash_shop_freebies_claimed_day_unknown = true;
+        let mut cash_shop = CashShop::new();
+
+        update_entered(&context, &mut cash_shop, ended_timeout(305));
+        assert_matches!(cash_shop.state, State::Exitting);
+    }
+
+    #[test]
+    fn update_entered_opens_free_tab_when_not_claimed_today() {
+        let mut context = PlayerContext::default();
+        context.config.cash_shop_claim_freebies = true;
+        context.cash_shop_freebies_claimed_day = Some(current_day() - 1);
+        let mut cash_shop = CashShop::new();
+
+        update_entered(&context, &mut cash_shop, ended_timeout(305));
+        assert_matches!(cash_shop.state, State::OpeningFreeTab(_));
+    }
+
+    #[test]
+    fn update_opening_free_tab_clicks_tab() {
+        let mut detector = MockDetector::default();
+        detector
+            .expect_detect_cash_shop_free_tab()
+            .returning(|| Ok(rect(10, 10)));
+        let mut input = MockInput::default();
+        input
+            .expect_send_mouse()
+            .with(eq(15), eq(15), eq(MouseKind::Click))
+            .once();
+        let resources = Resources::new(Some(input), Some(detector));
+        let mut cash_shop = CashShop::new();
+
+        update_opening_free_tab(&resources, &mut cash_shop, Timeout::default());
+        assert_matches!(cash_shop.state, State::OpeningFreeTab(_));
+    }
+
+    #[test]
+    fn update_opening_free_tab_exits_when_tab_not_found() {
+        let mut detector = MockDetector::default();
+        detector
+            .expect_detect_cash_shop_free_tab()
+            .returning(|| Err(anyhow!("not found")));
+        let resources = Resources::new(None, Some(detector));
+        let mut cash_shop = CashShop::new();
+
+        update_opening_free_tab(&resources, &mut cash_shop, Timeout::default());
+        assert_matches!(cash_shop.state, State::Exitting);
+    }
+
+    #[test]
+    fn update_buying_free_item_verifies_without_confirm_popup() {
+        let mut detector = MockDetector::default();
+        detector
+            .expect_detect_popup_confirm_button()
+            .returning(|| Err(anyhow!("not found")));
+        let resources = Resources::new(None, Some(detector));
+        let mut cash_shop = CashShop::new();
+
+        update_buying_free_item(&resources, &mut cash_shop, ended_timeout(20), rect(0, 0));
+        assert_matches!(cash_shop.state, State::VerifyingFreeItem(_));
+    }
+
+    #[test]
+    fn update_verifying_free_item_marks_claimed_day() {
+        let mut detector = MockDetector::default();
+        detector
+            .expect_detect_cash_shop_free_item()
+            .returning(|| Err(anyhow!("not found")));
+        let resources = Resources::new(None, Some(detector));
+        let mut context = PlayerContext::default();
+        let mut cash_shop = CashShop::new();
+
+        update_verifying_free_item(&resources, &mut context, &mut cash_shop, ended_timeout(30));
+        assert_matches!(cash_shop.state, State::Exitting);
+        assert_eq!(context.cash_shop_freebies_claimed_day, Some(current_day()));
+    }
+
+    #[test]
+    fn update_verifying_free_item_does_not_mark_when_item_remains() {
+        let mut detector = MockDetector::default();
+        detector
+            .expect_detect_cash_shop_free_item()
+            .returning(|| Ok(rect(0, 0)));
+        let resources = Resources::new(None, Some(detector));
+        let mut context = PlayerContext::default();
+        let mut cash_shop = CashShop::new();
+
+        update_verifying_free_item(&resources, &mut context, &mut cash_shop, ended_timeout(30));
+        assert_matches!(cash_shop.state, State::Exitting);
+        assert_eq!(context.cash_shop_freebies_claimed_day, None);
+    }
+}
@@ -0,0 +1,209 @@
+use opencv::core::Point;
+
+use super::state::LastMovement;
+use crate::run::FPS;
+
+/// The minimum number of samples before a movement speed is used for estimating.
+const MIN_SAMPLES: u32 = 3;
+
+/// The weight of a new sample in the exponential moving average of a movement speed.
+const SAMPLE_WEIGHT: f32 = 0.3;
+
+/// The minimum distance a movement must cover to be sampled.
+///
+/// Shorter movements are dominated by their startup and would underestimate the speed.
+const MIN_SAMPLE_DISTANCE: i32 = 5;
+
+/// The maximum number of ticks a movement can take to be sampled.
+///
+/// Longer movements were most likely interrupted (e.g. stuck or knocked back).
+const MAX_SAMPLE_TICKS: u64 = 5 * FPS as u64;
+
+#[derive(Clone, Copy, Debug, Default)]
+struct MovementSpeed {
+    /// The exponential moving average of distance covered per tick.
+    pixels_per_tick: f32,
+    samples: u32,
+}
+
+impl MovementSpeed {
+    fn record(&mut self, pixels_per_tick: f32) {
+        self.pixels_per_tick = if self.samples == 0 {
+            pixels_per_tick
+        } else {
+            self.pixels_per_tick + SAMPLE_WEIGHT * (pixels_per_tick - self.pixels_per_tick)
+        };
+        self.samples = self.samples.saturating_add(1);
+    }
+}
+
+/// Movement speeds calibrated from the character's own movements.
+///
+/// Speeds are measured along the axis a movement is used for: horizontal for
+/// [`LastMovement::Adjusting`] and [`LastMovement::DoubleJumping`], and vertical for
+/// [`LastMovement::Grappling`] and [`LastMovement::UpJumping`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MovementSpeeds {
+    adjusting: MovementSpeed,
+    double_jumping: MovementSpeed,
+    grappling: MovementSpeed,
+    up_jumping: MovementSpeed,
+    /// The movement being sampled with its starting position and tick.
+    sampling: Option<(LastMovement, Point, u64)>,
+}
+
+impl MovementSpeeds {
+    /// Starts sampling `movement` from `pos` at `tick`.
+    pub fn start_sample(&mut self, movement: LastMovement, pos: Point, tick: u64) {
+        self.sampling = Some((movement, pos, tick));
+    }
+
+    /// Ends sampling the started movement at `pos` and `tick`.
+    ///
+    /// The sample is only recorded if `last_movement` is still the sampled movement, meaning
+    /// no other movement happened in between.
+    pub fn end_sample(&mut self, last_movement: Option<LastMovement>, pos: Point, tick: u64) {
+        let Some((movement, start_pos, start_tick)) = self.sampling.take() else {
+            return;
+        };
+        if last_movement != Some(movement) {
+            return;
+        }
+
+        let distance = if is_horizontal(movement) {
+            (pos.x - start_pos.x).abs()
+        } else {
+            pos.y - start_pos.y
+        };
+        let ticks = tick.saturating_sub(start_tick);
+        if distance < MIN_SAMPLE_DISTANCE || ticks == 0 || ticks > MAX_SAMPLE_TICKS {
+            return;
+        }
+
+        if let Some(speed) = self.speed_mut(movement) {
+            speed.record(distance as f32 / ticks as f32);
+        }
+    }
+
+    /// Estimates the number of ticks for `movement` to cover `distance`.
+    ///
+    /// Returns [`None`] if `movement` is not calibrated yet.
+    pub fn estimate_ticks(&self, movement: LastMovement, distance: i32) -> Option<f32> {
+        self.speed(movement)
+            .filter(|speed| speed.samples >= MIN_SAMPLES && speed.pixels_per_tick > 0.0)
+            .map(|speed| distance as f32 / speed.pixels_per_tick)
+    }
+
+    /// Picks the fastest of `candidates` to cover `distance`.
+    ///
+    /// Returns [`None`] if any of the candidates is not calibrated yet, in which case the fixed
+    /// thresholds should be used instead.
+    pub fn fastest(&self, candidates: &[LastMovement], distance: i32) -> Option<LastMovement> {
+        let mut fastest = None::<(LastMovement, f32)>;
+        for movement in candidates.iter().copied() {
+            let ticks = self.estimate_ticks(movement, distance)?;
+            if fastest.is_none_or(|(_, fastest_ticks)| ticks < fastest_ticks) {
+                fastest = Some((movement, ticks));
+            }
+        }
+
+        fastest.map(|(movement, _)| movement)
+    }
+
+    fn speed(&self, movement: LastMovement) -> Option<&MovementSpeed> {
+        match movement {
+            LastMovement::Adjusting => Some(&self.adjusting),
+            LastMovement::DoubleJumping => Some(&self.double_jumping),
+            LastMovement::Grappling => Some(&self.grappling),
+            LastMovement::UpJumping => Some(&self.up_jumping),
+            LastMovement::Falling | LastMovement::Jumping => None,
+        }
+    }
+
+    fn speed_mut(&mut self, movement: LastMovement) -> Option<&mut MovementSpeed> {
+        match movement {
+            LastMovement::Adjusting => Some(&mut self.adjusting),
+            LastMovement::DoubleJumping => Some(&mut self.double_jumping),
+            LastMovement::Grappling => Some(&mut self.grappling),
+            LastMovement::UpJumping => Some(&mut self.up_jumping),
+            LastMovement::Falling | LastMovement::Jumping => None,
+        }
+    }
+}
+
+#[inline]
+fn is_horizontal(movement: LastMovement) -> bool {
+    matches!(
+        movement,
+        LastMovement::Adjusting | LastMovement::DoubleJumping
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calibrated(movement: LastMovement, distance: i32, ticks: u64) -> MovementSpeeds {
+        let mut speeds = MovementSpeeds::default();
+        calibrate(&mut speeds, movement, distance, ticks);
+        speeds
+    }
+
+    fn calibrate(speeds: &mut MovementSpeeds, movement: LastMovement, distance: i32, ticks: u64) {
+        for _ in 0..MIN_SAMPLES {
+            let end = if is_horizontal(movement) {
+                Point::new(distance, 0)
+            } else {
+                Point::new(0, distance)
+            };
+            speeds.start_sample(movement, Point::default(), 0);
+            speeds.end_sample(Some(movement), end, ticks);
+        }
+    }
+
+    #[test]
+    fn estimate_ticks_requires_min_samples() {
+        let mut speeds = MovementSpeeds::default();
+        speeds.start_sample(LastMovement::Adjusting, Point::default(), 0);
+        speeds.end_sample(Some(LastMovement::Adjusting), Point::new(20, 0), 10);
+
+        assert_eq!(speeds.estimate_ticks(LastMovement::Adjusting, 20), None);
+
+        calibrate(&mut speeds, LastMovement::Adjusting, 20, 10);
+        assert_eq!(
+            speeds.estimate_ticks(LastMovement::Adjusting, 20),
+            Some(10.0)
+        );
+    }
+
+    #[test]
+    fn end_sample_ignores_interrupted_movements() {
+        let mut speeds = MovementSpeeds::default();
+        for _ in 0..MIN_SAMPLES {
+            speeds.start_sample(LastMovement::Grappling, Point::default(), 0);
+            speeds.end_sample(Some(LastMovement::DoubleJumping), Point::new(0, 30), 10);
+            speeds.start_sample(LastMovement::Grappling, Point::default(), 0);
+            speeds.end_sample(
+                Some(LastMovement::Grappling),
+                Point::new(0, 30),
+                MAX_SAMPLE_TICKS + 1,
+            );
+        }
+
+        assert_eq!(speeds.estimate_ticks(LastMovement::Grappling, 30), None);
+    }
+
+    #[test]
+    fn fastest_picks_lowest_estimate_only_when_all_calibrated() {
+        let mut speeds = calibrated(LastMovement::Adjusting, 30, 10);
+        let candidates = [LastMovement::DoubleJumping, LastMovement::Adjusting];
+
+        assert_eq!(speeds.fastest(&candidates, 30), None);
+
+        calibrate(&mut speeds, LastMovement::DoubleJumping, 30, 20);
+        assert_eq!(
+            speeds.fastest(&candidates, 30),
+            Some(LastMovement::Adjusting)
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Player states registered outside of the core [`Player`] state machine.
+//!
+//! Larger routines (e.g. specific boss mechanics) can live in their own feature-gated modules by
+//! implementing [`PlayerExtension`] and registering it with [`register_player_extension`] under a
+//! [`PlayerExtensionId`]. The player enters the extension state from
+//! [`PlayerAction::Extension`](super::PlayerAction::Extension) and the registered extension is
+//! then updated every tick until it transitions the player out of [`Player::Extension`].
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt::{self, Display},
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use log::warn;
+
+use super::{Player, PlayerEntity, actions::next_action};
+use crate::{ecs::Resources, minimap::Minimap};
+
+static EXTENSIONS: LazyLock<Mutex<HashMap<PlayerExtensionId, Arc<dyn PlayerExtension>>>> =
+    LazyLock::new(Mutex::default);
+
+/// An opaque id of a registered [`PlayerExtension`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PlayerExtensionId(&'static str);
+
+impl PlayerExtensionId {
+    pub const fn new(name: &'static str) -> Self {
+        Self(name)
+    }
+}
+
+impl Display for PlayerExtensionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The state of [`Player::Extension`].
+///
+/// Besides the id, an extension can carry its own data across updates with [`Self::with_data`].
+#[derive(Clone, Debug)]
+pub struct PlayerExtensionState {
+    id: PlayerExtensionId,
+    data: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl PlayerExtensionState {
+    pub fn new(id: PlayerExtensionId) -> Self {
+        Self { id, data: None }
+    }
+
+    #[inline]
+    pub fn id(&self) -> PlayerExtensionId {
+        self.id
+    }
+
+    /// Gets the data previously set with [`Self::with_data`] if it is of type `T`.
+    #[inline]
+    pub fn data<T: Any>(&self) -> Option<&T> {
+        self.data.as_deref()?.downcast_ref()
+    }
+
+    /// Replaces the data of the state with `data`.
+    pub fn with_data<T: Any + Send + Sync>(self, data: T) -> Self {
+        Self {
+            data: Some(Arc::new(data)),
+            ..self
+        }
+    }
+}
+
+impl Display for PlayerExtensionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+/// A [`Player`] state implemented outside of the core state machine.
+pub trait PlayerExtension: Send + Sync + 'static {
+    /// Whether the state requires the player current position.
+    ///
+    /// A positional state is not updated while the player cannot be detected.
+    fn positional(&self) -> bool {
+        false
+    }
+
+    /// Updates the player while in [`Player::Extension`] of this extension.
+    ///
+    /// The extension is responsible for transitioning out of the state, including completing the
+    /// [`PlayerAction::Extension`](super::PlayerAction::Extension) it was entered from.
+    fn update(&self, resources: &Resources, player: &mut PlayerEntity, minimap_state: Minimap);
+}
+
+/// Registers `extension` to update [`Player::Extension`] states with `id`.
+///
+/// Replaces the previously registered extension with the same `id` if any.
+pub fn register_player_extension(id: PlayerExtensionId, extension: impl PlayerExtension) {
+    EXTENSIONS.lock().unwrap().insert(id, Arc::new(extension));
+}
+
+/// Updates [`Player::Extension`] contextual state with the registered extension.
+///
+/// `positional` is whether this is called when the player current position is available. Returns
+/// `false` without updating if it does not match [`PlayerExtension::positional`].
+pub fn update_extension_state(
+    resources: &Resources,
+    player: &mut PlayerEntity,
+    minimap_state: Minimap,
+    positional: bool,
+) -> bool {
+    let Player::Extension(state) = &player.state else {
+        panic!("state is not extension")
+    };
+    let id = state.id();
+    // Clones out so the lock is not held while updating
+    let extension = EXTENSIONS.lock().unwrap().get(&id).cloned();
+    let Some(extension) = extension else {
+        warn!(target: "player", "player extension {id} is not registered");
+        if next_action(&player.context).is_some() {
+            player.context.clear_action_completed();
+        }
+        player.state = Player::Idle;
+        return true;
+    };
+    if extension.positional() != positional {
+        return false;
+    }
+
+    extension.update(resources, player, minimap_state);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+    use crate::player::{PlayerAction, PlayerContext};
+
+    struct Counting;
+
+    #[derive(Debug, PartialEq)]
+    struct Count(u32);
+
+    impl PlayerExtension for Counting {
+        fn positional(&self) -> bool {
+            true
+        }
+
+        fn update(&self, _: &Resources, player: &mut PlayerEntity, _: Minimap) {
+            let Player::Extension(state) = player.state.clone() else {
+                unreachable!()
+            };
+            let count = state.data::<Count>().map_or(0, |count| count.0) + 1;
+
+            player.state = if count < 2 {
+                Player::Extension(state.with_data(Count(count)))
+            } else {
+                player.context.clear_action_completed();
+                Player::Idle
+            };
+        }
+    }
+
+    #[test]
+    fn update_extension_state_updates_registered_extension() {
+        const ID: PlayerExtensionId = PlayerExtensionId::new("counting");
+
+        register_player_extension(ID, Counting);
+        let resources = Resources::new(None, None);
+        let mut player = PlayerEntity {
+            state: Player::Extension(PlayerExtensionState::new(ID)),
+            context: PlayerContext::default(),
+        };
+        player
+            .context
+            .set_priority_action(None, PlayerAction::Extension(ID));
+
+        // Not updated when not positional
+        assert!(!update_extension_state(
+            &resources,
+            &mut player,
+            Minimap::Detecting,
+            false
+        ));
+
+        assert!(update_extension_state(
+            &resources,
+            &mut player,
+            Minimap::Detecting,
+            true
+        ));
+        assert_matches!(
+            &player.state,
+            Player::Extension(state) if state.data::<Count>() == Some(&Count(1))
+        );
+
+        update_extension_state(&resources, &mut player, Minimap::Detecting, true);
+        assert_matches!(player.state, Player::Idle);
+        assert!(!player.context.has_priority_action());
+    }
+
+    #[test]
+    fn update_extension_state_unregistered_completes_action() {
+        const ID: PlayerExtensionId = PlayerExtensionId::new("unregistered");
+
+        let resources = Resources::new(None, None);
+        let mut player = PlayerEntity {
+            state: Player::Extension(PlayerExtensionState::new(ID)),
+            context: PlayerContext::default(),
+        };
+        player
+            .context
+            .set_priority_action(None, PlayerAction::Extension(ID));
+
+        assert!(update_extension_state(
+            &resources,
+            &mut player,
+            Minimap::Detecting,
+            false
+        ));
+        assert_matches!(player.state, Player::Idle);
+        assert!(!player.context.has_priority_action());
+    }
+}
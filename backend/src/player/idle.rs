@@ -16,9 +16,9 @@ use crate::{
     ecs::{Resources, transition, transition_if},
     minimap::Minimap,
     player::{
-        ChattingContent, PlayerEntity, SolvingShape, chat::Chatting,
-        exchange_booster::ExchangingBooster, transition_from_action, unstuck::Unstucking,
-        use_booster::UsingBooster,
+        ChattingContent, PlayerEntity, SolvingShape, buy_potion::BuyingPotion, chat::Chatting,
+        exchange_booster::ExchangingBooster, replay_macro::ReplayingMacro, transition_from_action,
+        unstuck::Unstucking, use_booster::UsingBooster,
     },
     rng::Rng,
 };
@@ -195,7 +195,14 @@ fn update_from_action(resources: &Resources, player: &mut PlayerEntity, minimap_
         ),
 
         Some(PlayerAction::Panic(panic)) => {
-            transition!(player, Player::Panicking(Panicking::new(panic.to)))
+            transition!(
+                player,
+                Player::Panicking(Panicking::new(panic.to).with_skip_channels(panic.skip_channels))
+            )
+        }
+
+        Some(PlayerAction::Macro(action)) => {
+            transition!(player, Player::ReplayingMacro(ReplayingMacro::new(action)))
         }
 
         Some(PlayerAction::Chat(chat)) => transition!(
@@ -217,6 +224,13 @@ fn update_from_action(resources: &Resources, player: &mut PlayerEntity, minimap_
             )
         }
 
+        Some(PlayerAction::BuyPotion(buying)) => {
+            transition!(
+                player,
+                Player::BuyingPotion(BuyingPotion::new(buying.quantity))
+            )
+        }
+
         Some(PlayerAction::Unstuck) => {
             transition!(player, Player::Unstucking(Unstucking::new_esc()))
         }
@@ -225,6 +239,14 @@ fn update_from_action(resources: &Resources, player: &mut PlayerEntity, minimap_
             transition!(player, Player::SolvingShape(SolvingShape::default()))
         }
 
+        #[cfg(feature = "player_extensions")]
+        Some(PlayerAction::Extension(id)) => {
+            transition!(
+                player,
+                Player::Extension(super::PlayerExtensionState::new(id))
+            )
+        }
+
         None => (),
     }
 }
@@ -26,10 +26,12 @@ use crate::{
     minimap::{Minimap, MinimapEntity},
     models::ActionKeyDirection,
     player::{
+        buy_potion::{BuyingPotion, update_buying_potion_state},
         chat::{Chatting, update_chatting_state},
         exchange_booster::{ExchangingBooster, update_exchanging_booster_state},
         fall::Falling,
         grapple::Grappling,
+        replay_macro::{ReplayingMacro, update_replaying_macro_state},
         solve_shape::{SolvingShape, update_solving_shape_state},
         unstuck::Unstucking,
         use_booster::{UsingBooster, update_using_booster_state},
@@ -38,10 +40,14 @@ use crate::{
 
 mod actions;
 mod adjust;
+mod buy_potion;
 mod cash_shop;
 mod chat;
 mod double_jump;
+mod eta;
 mod exchange_booster;
+#[cfg(feature = "player_extensions")]
+mod extension;
 mod fall;
 mod familiars_swap;
 mod grapple;
@@ -49,6 +55,7 @@ mod idle;
 mod jump;
 mod moving;
 mod panic;
+mod replay_macro;
 mod solve_rune;
 mod solve_shape;
 mod stall;
@@ -60,9 +67,14 @@ mod use_booster;
 mod use_key;
 
 pub use actions::*;
+#[cfg(feature = "player_extensions")]
+pub use extension::{
+    PlayerExtension, PlayerExtensionId, PlayerExtensionState, register_player_extension,
+};
 pub use {
     chat::ChattingContent, double_jump::DOUBLE_JUMP_THRESHOLD, grapple::GRAPPLING_MAX_THRESHOLD,
-    grapple::GRAPPLING_THRESHOLD, panic::Panicking, state::PlayerContext, state::Quadrant,
+    grapple::GRAPPLING_THRESHOLD, panic::MAX_SKIP_CHANNELS, panic::Panicking, state::PlayerContext,
+    state::Quadrant,
 };
 
 /// Minimum y distance from the destination required to perform a jump.
@@ -115,6 +127,14 @@ pub enum Player {
     Chatting(Chatting),
     UsingBooster(UsingBooster),
     ExchangingBooster(ExchangingBooster),
+    /// Buys potions from an opened NPC shop.
+    BuyingPotion(BuyingPotion),
+    /// Replays the keys of a recorded input macro.
+    ReplayingMacro(ReplayingMacro),
+    /// Updated by the [`PlayerExtension`] registered with the state id.
+    #[cfg(feature = "player_extensions")]
+    #[strum(to_string = "Extension({0})")]
+    Extension(PlayerExtensionState),
 }
 
 impl Player {
@@ -155,8 +175,12 @@ impl Player {
             | Player::Panicking(_)
             | Player::UsingBooster(_)
             | Player::ExchangingBooster(_)
+            | Player::BuyingPotion(_)
             | Player::SolvingShape(_)
+            | Player::ReplayingMacro(_)
             | Player::Stalling(_, _) => false,
+            #[cfg(feature = "player_extensions")]
+            Player::Extension(_) => false,
         }
     }
 }
@@ -274,6 +298,12 @@ fn update_non_positional_state(
         Player::Chatting(chatting) => update_chatting_state(resources, player, chatting),
         Player::UsingBooster(_) => update_using_booster_state(resources, player),
         Player::ExchangingBooster(_) => update_exchanging_booster_state(resources, player),
+        Player::BuyingPotion(_) => update_buying_potion_state(resources, player),
+        Player::ReplayingMacro(_) => update_replaying_macro_state(resources, player),
+        #[cfg(feature = "player_extensions")]
+        Player::Extension(_) => {
+            return extension::update_extension_state(resources, player, minimap_state, false);
+        }
         Player::Detecting
         | Player::Idle
         | Player::Moving(_, _, _)
@@ -305,6 +335,10 @@ fn update_positional_state(
         Player::UpJumping(_) => update_up_jumping_state(resources, player, minimap_state),
         Player::Jumping(moving) => update_jumping_state(resources, player, moving),
         Player::Falling(Falling { .. }) => update_falling_state(resources, player, minimap_state),
+        #[cfg(feature = "player_extensions")]
+        Player::Extension(_) => {
+            extension::update_extension_state(resources, player, minimap_state, true);
+        }
         Player::UseKey(_)
         | Player::Unstucking(_)
         | Player::Stalling(_, _)
@@ -314,7 +348,9 @@ fn update_positional_state(
         | Player::Chatting(_)
         | Player::UsingBooster(_)
         | Player::ExchangingBooster(_)
+        | Player::BuyingPotion(_)
         | Player::SolvingShape(_)
+        | Player::ReplayingMacro(_)
         | Player::CashShopThenExit(_) => unreachable!(),
     }
 }
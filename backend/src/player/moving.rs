@@ -17,7 +17,7 @@ use crate::{
     bridge::KeyKind,
     ecs::{Resources, transition, transition_if},
     minimap::Minimap,
-    pathing::{MovementHint, PlatformWithNeighbors, find_points_with},
+    pathing::{MovementHint, PathingThresholds, PlatformWithNeighbors, find_points_with},
     player::{
         Falling, PlayerEntity,
         adjust::{ADJUSTING_MEDIUM_THRESHOLD, ADJUSTING_SHORT_THRESHOLD, Adjusting},
@@ -254,6 +254,10 @@ impl Moving {
 /// matching `x` of `dest`. Then, [`Player::Grappling`], [`Player::UpJumping`], [`Player::Jumping`]
 /// or [`Player::Falling`] for matching `y` of `dest`. (e.g. horizontal then vertical)
 ///
+/// Once the movement speeds of the character are calibrated from its previous movements, the
+/// fastest estimated of double jumping or adjusting and grappling or up jumping is picked instead
+/// of always preferring the former when the distance is over its threshold.
+///
 /// In auto mob or intermediate destination, most of the movement thresholds are relaxed for
 /// more fluid movement.
 pub fn update_moving_state(
@@ -277,6 +281,10 @@ pub fn update_moving_state(
     );
 
     let cur_pos = context.last_known_pos.unwrap();
+    context
+        .movement_speeds
+        .end_sample(context.last_movement, cur_pos, resources.tick);
+
     let moving = Moving::new(cur_pos, dest, exact, intermediates);
     let is_intermediate = moving.is_destination_intermediate();
     let skip_destination = moving.auto_mob_can_skip_current_destination(context);
@@ -286,10 +294,17 @@ pub fn update_moving_state(
 
     let disable_double_jumping = context.config.disable_double_jumping;
     let disable_adjusting = context.config.disable_adjusting;
+    // Falls back to the fixed thresholds ordering until the movement speeds are calibrated
+    let prefer_adjusting = !disable_adjusting
+        && context.movement_speeds.fastest(
+            &[LastMovement::DoubleJumping, LastMovement::Adjusting],
+            x_distance,
+        ) == Some(LastMovement::Adjusting);
 
     // Check to double jump
     if !skip_destination
         && !disable_double_jumping
+        && !prefer_adjusting
         && x_distance >= context.double_jump_threshold(is_intermediate)
     {
         let require_stationary = context.has_ping_pong_action_only()
@@ -297,6 +312,9 @@ pub fn update_moving_state(
                 context.last_movement,
                 Some(LastMovement::Grappling | LastMovement::UpJumping)
             );
+        context
+            .movement_speeds
+            .start_sample(LastMovement::DoubleJumping, cur_pos, resources.tick);
         return abort_action_on_state_repeat(
             player,
             Player::DoubleJumping(DoubleJumping::new(moving, false, require_stationary)),
@@ -309,6 +327,9 @@ pub fn update_moving_state(
         && ((!disable_adjusting && x_distance >= ADJUSTING_MEDIUM_THRESHOLD)
             || (exact && x_distance >= ADJUSTING_SHORT_THRESHOLD))
     {
+        context
+            .movement_speeds
+            .start_sample(LastMovement::Adjusting, cur_pos, resources.tick);
         return abort_action_on_state_repeat(
             player,
             Player::Adjusting(Adjusting::new(moving)),
@@ -318,12 +339,20 @@ pub fn update_moving_state(
 
     // Check to grapple
     let has_teleport_key = context.config.teleport_key.is_some();
+    let prefer_up_jumping = context.movement_speeds.fastest(
+        &[LastMovement::Grappling, LastMovement::UpJumping],
+        y_distance,
+    ) == Some(LastMovement::UpJumping);
     if !skip_destination
         && y_direction > 0
+        && !prefer_up_jumping
         && ((!has_teleport_key && y_distance >= GRAPPLING_THRESHOLD)
             || (has_teleport_key && y_distance >= GRAPPLING_MAX_THRESHOLD))
         && !context.should_disable_grappling()
     {
+        context
+            .movement_speeds
+            .start_sample(LastMovement::Grappling, cur_pos, resources.tick);
         return abort_action_on_state_repeat(
             player,
             Player::Grappling(Grappling::new(moving)),
@@ -349,6 +378,9 @@ pub fn update_moving_state(
             }
         );
 
+        context
+            .movement_speeds
+            .start_sample(LastMovement::UpJumping, cur_pos, resources.tick);
         let next_state = Player::UpJumping(UpJumping::new(moving, resources, context));
         return abort_action_on_state_repeat(player, next_state, minimap_state);
     }
@@ -475,16 +507,21 @@ fn update_from_action(player: &mut PlayerEntity, moving: Moving) {
 
         Some(
             PlayerAction::Chat(_)
+            | PlayerAction::Macro(_)
             | PlayerAction::SolveShape
             | PlayerAction::Unstuck
             | PlayerAction::Panic(_)
             | PlayerAction::FamiliarsSwap(_)
             | PlayerAction::UseBooster(_)
-            | PlayerAction::ExchangeBooster(_),
+            | PlayerAction::ExchangeBooster(_)
+            | PlayerAction::BuyPotion(_),
         ) => {
             panic!("unhandled action {action:?}")
         }
 
+        #[cfg(feature = "player_extensions")]
+        Some(PlayerAction::Extension(_)) => panic!("unhandled action {action:?}"),
+
         None => (),
     }
 }
@@ -503,15 +540,13 @@ pub fn find_intermediate_points(
     } else {
         GRAPPLING_MAX_THRESHOLD
     };
-    let vec = find_points_with(
-        platforms,
-        cur_pos,
-        dest,
-        enable_hint,
-        DOUBLE_JUMP_THRESHOLD,
-        JUMP_THRESHOLD,
-        vertical_threshold,
-    )?;
+    let thresholds = PathingThresholds {
+        double_jump: DOUBLE_JUMP_THRESHOLD,
+        jump: JUMP_THRESHOLD,
+        grappling: GRAPPLING_THRESHOLD,
+        vertical: vertical_threshold,
+    };
+    let vec = find_points_with(platforms, cur_pos, dest, enable_hint, thresholds)?;
     let len = vec.len();
     let array = Array::from_iter(
         vec.into_iter()
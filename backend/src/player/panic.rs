@@ -14,6 +14,11 @@ use crate::{
 
 const MAX_RETRY: u32 = 3;
 
+/// The maximum number of channels that can be skipped in one channel change.
+///
+/// Limited by the number of ticks between pressing right and enter in the change channel menu.
+pub const MAX_SKIP_CHANNELS: u32 = 7;
+
 /// States of panicking mode.
 #[derive(Debug, Clone, Copy)]
 enum State {
@@ -21,6 +26,8 @@ enum State {
     ChangingChannel(Timeout, u32),
     /// Going to town.
     GoingToTown(Timeout, u32),
+    /// Exiting to character select through the game menu.
+    ExitingToCharacterSelect(Timeout, u32),
     Completing(Timeout, bool),
 }
 
@@ -28,6 +35,7 @@ enum State {
 pub struct Panicking {
     state: State,
     pub to: PanicTo,
+    skip_channels: u32,
 }
 
 impl Panicking {
@@ -36,10 +44,18 @@ impl Panicking {
             state: match to {
                 PanicTo::Channel => State::ChangingChannel(Timeout::default(), 0),
                 PanicTo::Town => State::GoingToTown(Timeout::default(), 0),
+                PanicTo::CharacterSelect => State::ExitingToCharacterSelect(Timeout::default(), 0),
             },
             to,
+            skip_channels: 0,
         }
     }
+
+    /// Skips past `skip_channels` channels when changing channel.
+    pub fn with_skip_channels(mut self, skip_channels: u32) -> Self {
+        self.skip_channels = skip_channels.min(MAX_SKIP_CHANNELS);
+        self
+    }
 }
 
 /// Updates [`Player::Panicking`] contextual state.
@@ -49,28 +65,39 @@ pub fn update_panicking_state(
     minimap_state: Minimap,
     mut panicking: Panicking,
 ) {
-    let change_channel_key = try_some_transition!(
-        player,
-        Player::Idle,
-        player.context.config.change_channel_key,
-        {
-            info!(target: "player", "aborted panicking because change channel key is not set");
-            player.context.clear_action_completed();
-        }
-    );
-    let to_town_key =
-        try_some_transition!(player, Player::Idle, player.context.config.to_town_key, {
-            info!(target: "player", "aborted panicking because to town key is not set");
-            player.context.clear_action_completed();
-        });
-
+    let was_changing_channel = matches!(panicking.state, State::ChangingChannel(_, _));
     match panicking.state {
         State::ChangingChannel(_, _) => {
-            update_changing_channel(resources, &mut panicking, minimap_state, change_channel_key)
+            let key = try_some_transition!(
+                player,
+                Player::Idle,
+                player.context.config.change_channel_key,
+                {
+                    info!(
+                        target: "player",
+                        "aborted panicking because change channel key is not set"
+                    );
+                    player.context.clear_action_completed();
+                }
+            );
+            update_changing_channel(resources, &mut panicking, minimap_state, key)
+        }
+        State::GoingToTown(_, _) => {
+            let key =
+                try_some_transition!(player, Player::Idle, player.context.config.to_town_key, {
+                    info!(target: "player", "aborted panicking because to town key is not set");
+                    player.context.clear_action_completed();
+                });
+            update_going_to_town(resources, &mut panicking, key)
+        }
+        State::ExitingToCharacterSelect(_, _) => {
+            update_exiting_to_character_select(resources, &mut panicking)
         }
-        State::GoingToTown(_, _) => update_going_to_town(resources, &mut panicking, to_town_key),
         State::Completing(_, _) => update_completing(&mut panicking, minimap_state),
     };
+    if was_changing_channel && matches!(panicking.state, State::Completing(_, false)) {
+        player.context.channel_changed_count += 1;
+    }
 
     let player_next_state = if matches!(panicking.state, State::Completing(_, true)) {
         Player::Idle
@@ -86,11 +113,11 @@ pub fn update_panicking_state(
         ),
         None => transition_if!(
             player,
-            // Allow continuing for town even if the bot has already halted
+            // Allow continuing for town and character select even if the bot has already halted
             player_next_state,
             // Force cancel if it is not initiated from an action for other panic kind
             Player::Idle,
-            matches!(panicking.to, PanicTo::Town)
+            matches!(panicking.to, PanicTo::Town | PanicTo::CharacterSelect)
         ),
     }
 }
@@ -112,6 +139,8 @@ fn update_changing_channel(
     let State::ChangingChannel(timeout, retry_count) = panicking.state else {
         panic!("panicking state is not changing channel")
     };
+    // Presses right one more time every other tick for each skipped channel
+    let right_presses = panicking.skip_channels + 1;
     let max_timeout = if retry_count == 0 {
         TIMEOUT_INITIAL
     } else {
@@ -129,7 +158,10 @@ fn update_changing_channel(
             transition_if!(
                 panicking,
                 State::Completing(Timeout::default(), false),
-                !matches!(minimap_state, Minimap::Idle(_))
+                !matches!(minimap_state, Minimap::Idle(_)),
+                {
+                    resources.stats.record_channel_change();
+                }
             );
             transition_if!(
                 panicking,
@@ -146,7 +178,10 @@ fn update_changing_channel(
                     (PRESS_RIGHT_AT_AFTER, PRESS_ENTER_AT_AFTER)
                 };
                 match timeout.current {
-                    tick if tick == press_right_at => {
+                    tick if tick >= press_right_at
+                        && (tick - press_right_at) % 2 == 0
+                        && (tick - press_right_at) / 2 < right_presses =>
+                    {
                         if resources.detector().detect_change_channel_menu_opened() {
                             resources.input.send_key(KeyKind::Right);
                         }
@@ -194,6 +229,67 @@ fn update_going_to_town(resources: &Resources, panicking: &mut Panicking, key: K
     }
 }
 
+/// Opens the game menu, selects the last entry and confirms the exit popup.
+///
+/// The exit entry is expected to be the last entry of the game menu so pressing up once from the
+/// initially selected entry wraps to it.
+fn update_exiting_to_character_select(resources: &Resources, panicking: &mut Panicking) {
+    const PRESS_UP_AT: u32 = 30;
+    const PRESS_ENTER_AT: u32 = 45;
+
+    let State::ExitingToCharacterSelect(timeout, retry_count) = panicking.state else {
+        panic!("panicking state is not exiting to character select")
+    };
+
+    match next_timeout_lifecycle(timeout, 90) {
+        Lifecycle::Started(timeout) => {
+            transition!(
+                panicking,
+                State::ExitingToCharacterSelect(timeout, retry_count),
+                {
+                    if !resources.detector().detect_esc_settings() {
+                        resources.input.send_key(KeyKind::Esc);
+                    }
+                }
+            )
+        }
+        Lifecycle::Ended => {
+            let has_confirm_button = resources.detector().detect_popup_confirm_button().is_ok();
+            if has_confirm_button {
+                resources.input.send_key(KeyKind::Enter);
+            }
+
+            transition_if!(
+                panicking,
+                State::ExitingToCharacterSelect(Timeout::default(), retry_count + 1),
+                State::Completing(Timeout::default(), true),
+                !has_confirm_button && retry_count < MAX_RETRY
+            );
+        }
+        Lifecycle::Updated(timeout) => {
+            transition!(
+                panicking,
+                State::ExitingToCharacterSelect(timeout, retry_count),
+                {
+                    match timeout.current {
+                        PRESS_UP_AT => {
+                            if resources.detector().detect_esc_settings() {
+                                resources.input.send_key(KeyKind::Up);
+                            }
+                        }
+                        PRESS_ENTER_AT => {
+                            if resources.detector().detect_esc_settings() {
+                                resources.input.send_key(KeyKind::Enter);
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+            )
+        }
+    }
+}
+
 fn update_completing(panicking: &mut Panicking, minimap_state: Minimap) {
     let State::Completing(timeout, completed) = panicking.state else {
         panic!("panicking state is not completing")
@@ -202,7 +298,7 @@ fn update_completing(panicking: &mut Panicking, minimap_state: Minimap) {
     transition_if!(
         panicking,
         State::Completing(timeout, true),
-        matches!(panicking.to, PanicTo::Town)
+        matches!(panicking.to, PanicTo::Town | PanicTo::CharacterSelect)
     );
 
     match next_timeout_lifecycle(timeout, 245) {
@@ -272,6 +368,31 @@ mod tests {
         assert_matches!(panicking.state, State::ChangingChannel(_, _));
     }
 
+    #[test]
+    fn update_changing_channel_skip_channels_send_more_right_keys() {
+        let mut keys = MockInput::default();
+        let mut detector = MockDetector::default();
+        detector
+            .expect_detect_change_channel_menu_opened()
+            .return_const(true);
+        keys.expect_send_key().with(eq(KeyKind::Right)).times(3);
+        let resources = Resources::new(Some(keys), Some(detector));
+        let mut panicking = Panicking::new(PanicTo::Channel).with_skip_channels(2);
+        panicking.state = State::ChangingChannel(
+            Timeout {
+                current: 169,
+                started: true,
+                ..Default::default()
+            },
+            0,
+        );
+
+        for _ in 0..10 {
+            update_changing_channel(&resources, &mut panicking, Minimap::Detecting, KeyKind::F1);
+        }
+        assert_matches!(panicking.state, State::ChangingChannel(_, _));
+    }
+
     #[test]
     fn update_changing_channel_and_send_keys_retry() {
         let mut keys = MockInput::default();
@@ -411,6 +532,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn update_exiting_to_character_select_started_send_esc_if_menu_not_opened() {
+        let mut keys = MockInput::default();
+        keys.expect_send_key().once().with(eq(KeyKind::Esc));
+        let mut detector = MockDetector::default();
+        detector.expect_detect_esc_settings().return_const(false);
+        let resources = Resources::new(Some(keys), Some(detector));
+        let mut panicking = Panicking::new(PanicTo::CharacterSelect);
+
+        update_exiting_to_character_select(&resources, &mut panicking);
+
+        assert_matches!(panicking.state, State::ExitingToCharacterSelect(_, 0));
+    }
+
+    #[test]
+    fn update_exiting_to_character_select_ended_send_enter_and_complete_if_confirm_opened() {
+        let mut keys = MockInput::default();
+        keys.expect_send_key().once().with(eq(KeyKind::Enter));
+        let mut detector = MockDetector::default();
+        detector
+            .expect_detect_popup_confirm_button()
+            .returning(|| Ok(Rect::default()));
+        let resources = Resources::new(Some(keys), Some(detector));
+        let mut panicking = Panicking::new(PanicTo::CharacterSelect);
+        panicking.state = State::ExitingToCharacterSelect(
+            Timeout {
+                started: true,
+                current: 90,
+                ..Default::default()
+            },
+            0,
+        );
+
+        update_exiting_to_character_select(&resources, &mut panicking);
+
+        assert_matches!(panicking.state, State::Completing(_, true));
+    }
+
     #[test]
     fn update_completing_for_town_immediately_complete() {
         let mut panicking = Panicking::new(PanicTo::Town);
@@ -0,0 +1,120 @@
+use super::{Player, timeout::Timeout};
+use crate::{
+    array::Array,
+    bridge::KeyKind,
+    ecs::{Resources, transition},
+    player::{
+        MAX_MACRO_KEYS, Macro, PlayerEntity, next_action,
+        timeout::{Lifecycle, next_timeout_lifecycle},
+        transition_from_action,
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayingMacro {
+    keys: Array<(KeyKind, u32), MAX_MACRO_KEYS>,
+    /// The index of the next key to send.
+    index: usize,
+    /// The timeout for waiting before sending the next key.
+    timeout: Timeout,
+}
+
+impl ReplayingMacro {
+    pub fn new(action: Macro) -> Self {
+        Self {
+            keys: action.keys,
+            index: 0,
+            timeout: Timeout::default(),
+        }
+    }
+}
+
+/// Updates [`Player::ReplayingMacro`] contextual state.
+///
+/// Sends the recorded keys in order each after waiting for its delay and then transitions to
+/// [`Player::Idle`].
+pub fn update_replaying_macro_state(resources: &Resources, player: &mut PlayerEntity) {
+    let Player::ReplayingMacro(mut replaying) = player.state else {
+        panic!("state is not replaying macro")
+    };
+
+    update_replaying(resources, &mut replaying);
+
+    let player_next_state = if replaying.index < replaying.keys.len() {
+        Player::ReplayingMacro(replaying)
+    } else {
+        Player::Idle
+    };
+    let is_terminal = matches!(player_next_state, Player::Idle);
+
+    match next_action(&player.context) {
+        Some(_) => transition_from_action!(player, player_next_state, is_terminal),
+        None => transition!(player, Player::Idle), // Force cancel if not from action
+    }
+}
+
+fn update_replaying(resources: &Resources, replaying: &mut ReplayingMacro) {
+    let Some((key, delay_ticks)) = replaying.keys.as_slice().get(replaying.index).copied() else {
+        return;
+    };
+
+    if delay_ticks > 0 {
+        match next_timeout_lifecycle(replaying.timeout, delay_ticks) {
+            Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
+                replaying.timeout = timeout;
+                return;
+            }
+            Lifecycle::Ended => (),
+        }
+    }
+
+    resources.input.send_key(key);
+    replaying.index += 1;
+    replaying.timeout = Timeout::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::{bridge::MockInput, player::PlayerContext};
+
+    #[test]
+    fn update_replaying_sends_keys_after_delays() {
+        let mut keys = MockInput::default();
+        keys.expect_send_key().once().with(eq(KeyKind::A));
+        keys.expect_send_key().once().with(eq(KeyKind::B));
+        let resources = Resources::new(Some(keys), None);
+        let mut replaying = ReplayingMacro::new(Macro {
+            keys: Array::from_iter([(KeyKind::A, 0), (KeyKind::B, 2)]),
+        });
+
+        update_replaying(&resources, &mut replaying);
+        assert_eq!(replaying.index, 1);
+
+        // Started and two updates before ending
+        for _ in 0..3 {
+            update_replaying(&resources, &mut replaying);
+            assert_eq!(replaying.index, 1);
+        }
+
+        update_replaying(&resources, &mut replaying);
+        assert_eq!(replaying.index, 2);
+    }
+
+    #[test]
+    fn update_replaying_macro_state_transitions_to_idle_without_keys() {
+        let resources = Resources::new(None, None);
+        let mut player = PlayerEntity {
+            state: Player::ReplayingMacro(ReplayingMacro::new(Macro::default())),
+            context: PlayerContext::default(),
+        };
+
+        update_replaying_macro_state(&resources, &mut player);
+
+        assert_matches!(player.state, Player::Idle);
+    }
+}
@@ -1,6 +1,7 @@
 use super::{
     Player,
-    actions::PlayerAction,
+    actions::{PanicTo, PlayerAction},
+    panic::Panicking,
     timeout::{Lifecycle, next_timeout_lifecycle},
 };
 use crate::{
@@ -8,6 +9,7 @@ use crate::{
     detect::{ArrowsCalibrating, ArrowsState},
     ecs::{Resources, transition, transition_if, try_ok_transition},
     player::{PlayerContext, PlayerEntity, next_action, timeout::Timeout, transition_from_action},
+    run::MS_PER_TICK,
 };
 
 /// Representing the current state of rune solving.
@@ -21,6 +23,13 @@ pub enum State {
     Solving(ArrowsCalibrating, Timeout),
     /// Presses the keys.
     PressKeys(Timeout, [KeyKind; 4], usize),
+    /// Failed to detect the rune arrows.
+    Failed,
+    /// Waits for [`RuneRetryPolicy::cooldown_millis`](crate::RuneRetryPolicy::cooldown_millis)
+    /// before retrying.
+    Cooldown(Timeout),
+    /// Terminal stage to move to the rune again before retrying.
+    Reapproaching,
     /// Terminal stage.
     Completed,
 }
@@ -40,12 +49,29 @@ impl Default for SolvingRune {
 
 /// Updates the [`Player::SolvingRune`] contextual state.
 ///
+/// A failed attempt is retried according to [`RuneRetryPolicy`](crate::RuneRetryPolicy) until it
+/// gives up.
+///
 /// Note: This state does not use any [`Task`], so all detections are blocking. But this should be
 /// acceptable for this state.
 pub fn update_solving_rune_state(resources: &Resources, player: &mut PlayerEntity) {
     let Player::SolvingRune(mut solving_rune) = player.state else {
         panic!("state is not solving rune");
     };
+    if player.context.rune_change_channel {
+        player.context.rune_change_channel = false;
+        // The action is kept and completed by `Player::Panicking` after changing channel
+        transition!(player, Player::Panicking(Panicking::new(PanicTo::Channel)));
+    }
+
+    if matches!(solving_rune.state, State::Precondition(_)) {
+        player.context.rune_recording.clear();
+    } else if let Some(detector) = resources.detector.as_deref() {
+        player
+            .context
+            .rune_recording
+            .record(detector, resources.tick);
+    }
 
     match solving_rune.state {
         State::Precondition(_) => {
@@ -58,10 +84,17 @@ pub fn update_solving_rune_state(resources: &Resources, player: &mut PlayerEntit
         ),
         State::Solving(_, _) => update_solving(resources, &mut solving_rune),
         State::PressKeys(_, _, _) => update_press_keys(resources, &mut solving_rune),
-        State::Completed => unreachable!(),
+        State::Cooldown(_) => update_cooldown(&player.context, &mut solving_rune),
+        State::Failed | State::Reapproaching | State::Completed => unreachable!(),
+    }
+    if matches!(solving_rune.state, State::Failed) {
+        player.context.track_rune_fail_count(resources);
+        player.context.rune_recording.export();
+        solving_rune.state = State::Cooldown(Timeout::default());
     }
 
-    let player_next_state = if matches!(solving_rune.state, State::Completed) {
+    let player_next_state = if matches!(solving_rune.state, State::Reapproaching | State::Completed)
+    {
         Player::Idle
     } else {
         Player::SolvingRune(solving_rune)
@@ -70,7 +103,7 @@ pub fn update_solving_rune_state(resources: &Resources, player: &mut PlayerEntit
     match next_action(&player.context) {
         Some(PlayerAction::SolveRune) => {
             let is_terminal = matches!(player_next_state, Player::Idle);
-            if is_terminal {
+            if matches!(solving_rune.state, State::Completed) {
                 player.context.start_validating_rune();
             }
             transition_from_action!(player, player_next_state, is_terminal)
@@ -123,7 +156,7 @@ fn update_calibrating(
             })
         }
 
-        Lifecycle::Ended => transition!(solving_rune, State::Completed),
+        Lifecycle::Ended => transition!(solving_rune, State::Failed),
         Lifecycle::Updated(timeout) => {
             if timeout.current.is_multiple_of(SOLVE_INTERVAL) {
                 let arrows_state = try_ok_transition!(
@@ -154,11 +187,11 @@ fn update_solving(resources: &Resources, solving_rune: &mut SolvingRune) {
         Lifecycle::Started(timeout) => {
             transition!(solving_rune, State::Solving(calibrating, timeout))
         }
-        Lifecycle::Ended => transition!(solving_rune, State::Completed),
+        Lifecycle::Ended => transition!(solving_rune, State::Failed),
         Lifecycle::Updated(timeout) => {
             let arrows_state = try_ok_transition!(
                 solving_rune,
-                State::Completed,
+                State::Failed,
                 resources.detector().detect_rune_arrows(calibrating)
             );
             match arrows_state {
@@ -169,10 +202,9 @@ fn update_solving(resources: &Resources, solving_rune: &mut SolvingRune) {
                     solving_rune,
                     State::PressKeys(Timeout::default(), complete.keys, 0),
                     {
-                        #[cfg(debug_assertions)]
                         resources
-                            .debug
-                            .set_last_rune_result(resources.detector_cloned(), complete);
+                            .rune_dataset
+                            .set_pending_sample(resources.detector(), complete);
                     }
                 ),
             }
@@ -205,6 +237,27 @@ fn update_press_keys(resources: &Resources, solving_rune: &mut SolvingRune) {
     }
 }
 
+fn update_cooldown(player_context: &PlayerContext, solving_rune: &mut SolvingRune) {
+    let State::Cooldown(timeout) = solving_rune.state else {
+        panic!("solving rune state is not cooldown")
+    };
+    let policy = player_context.config.rune_retry_policy;
+    let max_timeout = (policy.cooldown_millis / MS_PER_TICK).max(1) as u32;
+
+    match next_timeout_lifecycle(timeout, max_timeout) {
+        // Completing the action lets the player move to the rune again when re-queued
+        Lifecycle::Ended => transition_if!(
+            solving_rune,
+            State::Reapproaching,
+            State::Precondition(Timeout::default()),
+            policy.reapproach
+        ),
+        Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
+            transition!(solving_rune, State::Cooldown(timeout))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::assert_matches::assert_matches;
@@ -214,6 +267,7 @@ mod tests {
 
     use super::*;
     use crate::{
+        RuneRetryPolicy,
         bridge::{KeyKind, MockInput},
         detect::{ArrowsCalibrating, ArrowsComplete, ArrowsState, MockDetector},
         ecs::Resources,
@@ -284,7 +338,7 @@ mod tests {
     }
 
     #[test]
-    fn update_calibrating_to_completed_on_timeout() {
+    fn update_calibrating_to_failed_on_timeout() {
         let mut detector = MockDetector::default();
         detector
             .expect_detect_rune_arrows()
@@ -303,11 +357,11 @@ mod tests {
 
         update_calibrating(&resources, &mut solving_rune, KeyKind::A);
 
-        assert_matches!(solving_rune.state, State::Completed);
+        assert_matches!(solving_rune.state, State::Failed);
     }
 
     #[test]
-    fn update_solving_to_completed_on_error() {
+    fn update_solving_to_failed_on_error() {
         let mut detector = MockDetector::default();
         detector
             .expect_detect_rune_arrows()
@@ -325,7 +379,7 @@ mod tests {
 
         update_solving(&resources, &mut solving_rune);
 
-        assert_matches!(solving_rune.state, State::Completed);
+        assert_matches!(solving_rune.state, State::Failed);
     }
 
     #[test]
@@ -391,6 +445,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn update_solving_rune_failed_to_cooldown_and_gives_up() {
+        let mut detector = MockDetector::default();
+        detector
+            .expect_detect_rune_arrows()
+            .returning(|_| Err(anyhow!("fail")));
+        let mut resources = Resources::new(None, Some(detector));
+        resources.tick = 1; // Avoid recording frames
+        let mut player = PlayerEntity {
+            state: Player::Idle,
+            context: PlayerContext::default(),
+        };
+        player.context.priority_action = Some(PlayerAction::SolveRune);
+        player.context.config.rune_retry_policy = RuneRetryPolicy {
+            max_attempts: 2,
+            change_channel_on_give_up: true,
+            ..RuneRetryPolicy::default()
+        };
+        let solving = SolvingRune {
+            state: State::Solving(
+                ArrowsCalibrating::default(),
+                Timeout {
+                    started: true,
+                    ..Default::default()
+                },
+            ),
+        };
+
+        player.state = Player::SolvingRune(solving);
+        update_solving_rune_state(&resources, &mut player);
+        assert_matches!(
+            player.state,
+            Player::SolvingRune(SolvingRune {
+                state: State::Cooldown(_)
+            })
+        );
+        assert!(!player.context.rune_change_channel);
+
+        player.state = Player::SolvingRune(solving);
+        update_solving_rune_state(&resources, &mut player);
+        assert!(player.context.rune_change_channel);
+
+        update_solving_rune_state(&resources, &mut player);
+        assert_matches!(player.state, Player::Panicking(_));
+        assert!(!player.context.rune_change_channel);
+        assert_matches!(
+            player.context.priority_action,
+            Some(PlayerAction::SolveRune)
+        );
+    }
+
+    #[test]
+    fn update_cooldown_retries_in_place_or_reapproaches() {
+        let mut player_context = PlayerContext::default();
+        let ended = State::Cooldown(Timeout {
+            started: true,
+            current: 1,
+            ..Default::default()
+        });
+
+        let mut solving_rune = SolvingRune { state: ended };
+        update_cooldown(&player_context, &mut solving_rune);
+        assert_matches!(solving_rune.state, State::Reapproaching);
+
+        player_context.config.rune_retry_policy.reapproach = false;
+        let mut solving_rune = SolvingRune { state: ended };
+        update_cooldown(&player_context, &mut solving_rune);
+        assert_matches!(solving_rune.state, State::Precondition(_));
+    }
+
     #[test]
     fn update_press_keys_to_completed_after_all_keys_sent() {
         let expected_keys = [KeyKind::A, KeyKind::S, KeyKind::D, KeyKind::F];
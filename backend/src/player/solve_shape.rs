@@ -21,6 +21,8 @@ pub enum State {
     Waiting,
     Solving(Timeout),
     Completed,
+    /// The lie detector is still opened after solving timed out.
+    Failed,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -51,13 +53,19 @@ pub fn update_solving_shape_state(resources: &Resources, player: &mut PlayerEnti
             player.context.shape_tracker(),
             &mut solving_shape,
         ),
-        State::Completed => unreachable!(),
+        State::Completed | State::Failed => unreachable!(),
     }
 
-    let player_next_state = if matches!(solving_shape.state, State::Completed) {
-        Player::Idle
-    } else {
-        Player::SolvingShape(solving_shape)
+    let player_next_state = match solving_shape.state {
+        State::Completed => {
+            player.context.clear_shape_fail_count();
+            Player::Idle
+        }
+        State::Failed => {
+            player.context.track_shape_fail_count();
+            Player::Idle
+        }
+        State::Waiting | State::Solving(_) => Player::SolvingShape(solving_shape),
     };
 
     match next_action(&player.context) {
@@ -125,7 +133,7 @@ fn update_solving(
     }
 
     match next_timeout_lifecycle(timeout, 545) {
-        Lifecycle::Ended => transition!(solving_shape, State::Completed),
+        Lifecycle::Ended => transition!(solving_shape, State::Failed),
         Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
             transition!(solving_shape, State::Solving(timeout), {
                 perform_solving(resources, tracker, solving_shape);
@@ -1,34 +1,48 @@
-use std::{collections::HashMap, range::Range};
+use std::{
+    collections::HashMap,
+    mem,
+    range::Range,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
 use log::{debug, info};
 use opencv::core::{Point, Rect};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
 
 use super::{
     DOUBLE_JUMP_THRESHOLD, JUMP_THRESHOLD, MOVE_TIMEOUT, Player, PlayerAction,
     double_jump::DOUBLE_JUMP_AUTO_MOB_THRESHOLD,
+    eta::MovementSpeeds,
     fall::FALLING_THRESHOLD,
     timeout::{Lifecycle, Timeout, next_timeout_lifecycle},
 };
 use crate::{
-    ActionKeyDirection,
+    ActionKeyDirection, ExpRate, RuneRetryPolicy,
     array::Array,
     bridge::{KeyKind, MouseKind},
     buff::{Buff, BuffEntities, BuffKind},
+    detect::ExpBar,
     ecs::Resources,
+    events::{ActionCompleted, emit_event_with},
+    exp::ExpTracker,
     minimap::Minimap,
     notification::NotificationKind,
     player::{AUTO_MOB_USE_KEY_X_THRESHOLD, AUTO_MOB_USE_KEY_Y_THRESHOLD, AutoMob, Booster},
+    potion::{Potion, PotionPolicy},
+    recording::RuneRecording,
     run::FPS,
-    task::{Task, Update, update_detection_task},
+    task::{
+        Task, TaskPriority, Update, update_detection_task, update_detection_task_with_priority,
+    },
     tracker::ByteTracker,
 };
 
 const STATIONARY_TIMEOUT: u32 = MOVE_TIMEOUT + 1;
 
-/// The maximum number of times rune solving can fail before transition to
-/// [`Player::CashShopThenExit`].
-const MAX_RUNE_FAILED_COUNT: u32 = 8;
+/// How often the EXP bar is read for estimating the EXP rate.
+const EXP_UPDATE_MILLIS: u64 = 10000;
 
 /// The maximum number of times using Generic Booster can fail before it is determined that it is not
 /// usable anymore (e.g. 10 times limit reached).
@@ -38,6 +52,10 @@ const MAX_BOOSTER_FAILED_COUNT: u32 = 5;
 /// there are no more cards to swap (e.g. All cards are at level 5).
 const MAX_FAMILIARS_SWAP_FAIL_COUNT: u32 = 3;
 
+/// The maximum number of consecutive times [`Player::SolvingShape`] can fail before halting
+/// safely instead of continuing with the lie detector still opened.
+const MAX_SHAPE_FAILED_COUNT: u32 = 3;
+
 /// The maximum number of times horizontal movement can be repeated in non-auto-mobbing action.
 const HORIZONTAL_MOVEMENT_REPEAT_COUNT: u32 = 20;
 
@@ -80,10 +98,21 @@ const UNSTUCK_COUNT_THRESHOLD: u32 = 6;
 /// The number of times [`Player::Unstucking`] can be transitioned to before entering GAMBA MODE.
 const UNSTUCK_GAMBA_MODE_COUNT: u32 = 3;
 
+/// The size of the window around the last known position to search for the player first while
+/// the minimap is unchanged.
+const PLAYER_PRIOR_SEARCH_SIZE: i32 = 32;
+
 /// The number of samples to store for approximating velocity.
 const VELOCITY_SAMPLES: usize = MOVE_TIMEOUT as usize;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+/// The maximum age of the frame an asynchronous detection is run on before its result is
+/// discarded.
+///
+/// The player may have moved far enough since the frame was captured that the result no longer
+/// reflects the current position.
+const MAX_DETECTION_FRAME_AGE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, PartialEq, Clone, Copy, Display, EnumIter, Serialize, Deserialize)]
 pub enum Quadrant {
     TopLeft,
     TopRight,
@@ -92,6 +121,20 @@ pub enum Quadrant {
 }
 
 impl Quadrant {
+    /// Gets the [`Quadrant`] of `bound` that `point` is in.
+    ///
+    /// Both `bound` and `point` are relative to the minimap top-left coordinate.
+    pub fn from_point(bound: Rect, point: Point) -> Quadrant {
+        let bound_x_mid = bound.x + bound.width / 2;
+        let bound_y_mid = bound.y + bound.height / 2;
+        match (point.x < bound_x_mid, point.y < bound_y_mid) {
+            (true, true) => Quadrant::TopLeft,
+            (false, true) => Quadrant::TopRight,
+            (false, false) => Quadrant::BottomRight,
+            (true, false) => Quadrant::BottomLeft,
+        }
+    }
+
     fn next_clockwise(self) -> Quadrant {
         match self {
             Quadrant::TopLeft => Quadrant::TopRight,
@@ -181,6 +224,8 @@ pub struct PlayerConfiguration {
     pub rune_platforms_pathing: bool,
     /// Uses only up jump(s) in rune platform pathing.
     pub rune_platforms_pathing_up_jump_only: bool,
+    /// How [`Player::SolvingRune`] is retried when it fails.
+    pub rune_retry_policy: RuneRetryPolicy,
 
     /// Enables platform pathing for auto mob.
     pub auto_mob_platforms_pathing: bool,
@@ -207,6 +252,8 @@ pub struct PlayerConfiguration {
     pub up_jump_key: Option<KeyKind>,
     /// The cash shop key.
     pub cash_shop_key: Option<KeyKind>,
+    /// Whether to claim daily free items when in cash shop.
+    pub cash_shop_claim_freebies: bool,
     /// The familiar key.
     pub familiar_key: Option<KeyKind>,
     /// The going to town key.
@@ -219,6 +266,16 @@ pub struct PlayerConfiguration {
     pub use_potion_below_percent: Option<f32>,
     /// Milliseconds interval to update current health.
     pub update_health_millis: Option<u64>,
+    /// Whether health is detected for the low health emergency policy.
+    pub detect_critical_health: bool,
+    /// The mana potion key.
+    pub mana_potion_key: KeyKind,
+    /// Uses mana potion when mana is below a percentage.
+    pub use_mana_potion_below_percent: Option<f32>,
+    /// Milliseconds cooldown between two uses of the same potion.
+    pub potion_cooldown_millis: u64,
+    /// Whether the pet auto potion is relied on before using potions.
+    pub pet_auto_potion: bool,
     /// Generic Booster key.
     pub generic_booster_key: KeyKind,
     /// HEXA Booster key.
@@ -236,6 +293,7 @@ impl Default for PlayerConfiguration {
             up_jump_specific_key_should_jump: false,
             rune_platforms_pathing: false,
             rune_platforms_pathing_up_jump_only: false,
+            rune_retry_policy: RuneRetryPolicy::default(),
             auto_mob_platforms_pathing: false,
             auto_mob_platforms_pathing_up_jump_only: false,
             auto_mob_platforms_bound: false,
@@ -247,12 +305,18 @@ impl Default for PlayerConfiguration {
             jump_key: KeyKind::A,
             up_jump_key: None,
             cash_shop_key: None,
+            cash_shop_claim_freebies: false,
             familiar_key: None,
             to_town_key: None,
             change_channel_key: None,
             potion_key: KeyKind::A,
             use_potion_below_percent: None,
+            detect_critical_health: false,
             update_health_millis: None,
+            mana_potion_key: KeyKind::A,
+            use_mana_potion_below_percent: None,
+            potion_cooldown_millis: 0,
+            pet_auto_potion: false,
             generic_booster_key: KeyKind::A,
             hexa_booster_key: KeyKind::A,
         }
@@ -287,6 +351,30 @@ pub struct PlayerContext {
     health_bar: Option<Rect>,
     /// The task for the health bar.
     health_bar_task: Option<Task<Result<Rect>>>,
+    /// Whether the player health is below the low health notification threshold.
+    is_health_low: bool,
+
+    /// The player current mana and max mana.
+    mana: Option<(u32, u32)>,
+    /// The task to update mana.
+    mana_task: Option<Task<Result<(u32, u32)>>>,
+    /// Tracks the usage of the health potion.
+    health_potion: Potion,
+    /// Tracks the usage of the mana potion.
+    mana_potion: Potion,
+    /// The number of times the health potion key has been used.
+    ///
+    /// This is kept across [`PlayerContext::reset`] for estimating the remaining potion stock.
+    pub(super) potion_uses: u32,
+    /// The number of times potions were bought from an NPC shop.
+    pub(super) potion_restocks: u32,
+
+    /// The task to update the EXP bar.
+    exp_task: Option<Task<Result<ExpBar>>>,
+    /// Tracks the EXP bar for estimating the EXP rate.
+    ///
+    /// This is kept across [`PlayerContext::reset`] so that changing map does not restart it.
+    exp_tracker: ExpTracker,
 
     /// Track if the player moved within a specified ticks to determine if the player is
     /// stationary.
@@ -325,6 +413,12 @@ pub struct PlayerContext {
     /// to [`None`] when the destination (possibly intermediate) is reached or
     /// in [`Player::Idle`].
     pub(super) last_movement: Option<LastMovement>,
+    /// Movement speeds calibrated for estimating the fastest movement to a destination.
+    ///
+    /// Kept across [`Self::reset`] until the character changes.
+    pub(super) movement_speeds: MovementSpeeds,
+    /// The id of the character [`Self::movement_speeds`] are calibrated for.
+    movement_speeds_character_id: Option<i64>,
     /// Tracks [`Self::last_movement`] to abort normal action when its position is not accurate.
     ///
     /// Clears when a normal action is completed or aborted.
@@ -349,7 +443,7 @@ pub struct PlayerContext {
     /// The next auto-mobbing bound's quadrant relative to bottom-left player coordinate.
     auto_mob_next_quadrant_bound: Option<Rect>,
     /// Task for detecting near and same direction mobs during pathing.
-    auto_mob_pathing_task: Option<Task<Result<Vec<Point>>>>,
+    auto_mob_pathing_task: Option<Task<Result<(Instant, Vec<(Point, u32, f32)>)>>>,
 
     /// Tracks whether movement-related actions do not change the player position after a while.
     ///
@@ -360,16 +454,37 @@ pub struct PlayerContext {
     /// Resets when threshold reached or position changed.
     unstuck_transitioned_count: u32,
 
-    /// The number of times [`Player::SolvingRune`] failed.
+    /// The number of consecutive times [`Player::SolvingRune`] failed.
     rune_failed_count: u32,
     /// Indicates the state will be transitioned to [`Player::CashShopThenExit`] in the next tick.
     pub(super) rune_cash_shop: bool,
+    /// Indicates [`Player::SolvingRune`] gives up by changing channel in its next update.
+    pub(super) rune_change_channel: bool,
+    /// The last day daily free items were claimed in [`Player::CashShopThenExit`].
+    ///
+    /// This is persisted and preserved across resets.
+    pub cash_shop_freebies_claimed_day: Option<u64>,
+    /// Whether [`Self::cash_shop_freebies_claimed_day`] failed to be read from the database.
+    ///
+    /// Claiming is skipped when set since the items may have already been claimed today. This
+    /// is preserved across resets.
+    pub cash_shop_freebies_claimed_day_unknown: bool,
+    /// The number of successful channel changes in [`Player::Panicking`].
+    ///
+    /// This is preserved across resets.
+    pub channel_changed_count: u32,
     /// [`Timeout`] for validating whether the rune is solved.
     ///
     /// This is [`Some`] when [`Player::SolvingRune`] successfully detects the rune
     /// and sends all the keys.
     rune_validate_timeout: Option<Timeout>,
+    /// Annotated frames recorded during [`Player::SolvingRune`] for troubleshooting.
+    pub(super) rune_recording: RuneRecording,
     shape_tracker: Option<ByteTracker>,
+    /// The number of consecutive times [`Player::SolvingShape`] failed.
+    shape_failed_count: u32,
+    /// Indicates [`Player::SolvingShape`] has failed too many times and the bot should halt.
+    shape_safe_halt: bool,
 
     /// A state to return to after stalling.
     ///
@@ -394,6 +509,11 @@ pub struct PlayerContext {
 
     /// The number of times [`Player::FamiliarsSwapping`] failed.
     familiars_swap_failed_count: u32,
+
+    /// The number of ticks the player has not made any progress while not halting.
+    ///
+    /// Progress is either a change in position or an action completion.
+    idle_ticks: u32,
 }
 
 impl PlayerContext {
@@ -404,21 +524,63 @@ impl PlayerContext {
     pub fn reset(&mut self) {
         *self = PlayerContext {
             config: self.config,
+            cash_shop_freebies_claimed_day: self.cash_shop_freebies_claimed_day,
+            cash_shop_freebies_claimed_day_unknown: self.cash_shop_freebies_claimed_day_unknown,
+            channel_changed_count: self.channel_changed_count,
+            exp_tracker: mem::take(&mut self.exp_tracker),
+            potion_uses: self.potion_uses,
+            potion_restocks: self.potion_restocks,
+            movement_speeds: self.movement_speeds,
+            movement_speeds_character_id: self.movement_speeds_character_id,
             reset_to_idle_next_update: true,
             ..PlayerContext::default()
         };
     }
 
+    /// Discards the calibrated movement speeds if they are not of the character `character_id`.
+    pub fn use_movement_speeds_of(&mut self, character_id: Option<i64>) {
+        if self.movement_speeds_character_id != character_id {
+            self.movement_speeds = MovementSpeeds::default();
+            self.movement_speeds_character_id = character_id;
+        }
+    }
+
     #[inline]
     pub fn health(&self) -> Option<(u32, u32)> {
         self.health
     }
 
+    #[inline]
+    pub fn mana(&self) -> Option<(u32, u32)> {
+        self.mana
+    }
+
+    #[inline]
+    pub fn potion_uses(&self) -> u32 {
+        self.potion_uses
+    }
+
+    #[inline]
+    pub fn potion_restocks(&self) -> u32 {
+        self.potion_restocks
+    }
+
+    #[inline]
+    pub fn exp_rate(&self) -> Option<ExpRate> {
+        self.exp_tracker.rate()
+    }
+
     #[inline]
     pub fn is_dead(&self) -> bool {
         self.is_dead
     }
 
+    /// The number of ticks the player has not made any progress while not halting.
+    #[inline]
+    pub fn idle_ticks(&self) -> u32 {
+        self.idle_ticks
+    }
+
     #[cfg(test)]
     pub fn normal_action(&self) -> Option<PlayerAction> {
         self.normal_action.clone()
@@ -596,11 +758,19 @@ impl PlayerContext {
     /// Clears either normal or priority due to completion.
     #[inline]
     pub(super) fn clear_action_completed(&mut self) {
+        self.idle_ticks = 0;
         self.clear_last_movement();
-        if self.has_priority_action() {
-            self.priority_action = None;
+        let is_priority = self.has_priority_action();
+        let action = if is_priority {
+            self.priority_action.take()
         } else {
-            self.normal_action = None;
+            self.normal_action.take()
+        };
+        if let Some(action) = action {
+            emit_event_with(|| ActionCompleted {
+                name: action.to_string(),
+                is_priority,
+            });
         }
     }
 
@@ -680,17 +850,67 @@ impl PlayerContext {
         self.familiars_swap_failed_count = 0;
     }
 
-    /// Increments the rune validation fail count and sets [`PlayerState::rune_cash_shop`]
-    /// if needed.
-    #[inline]
-    fn track_rune_fail_count(&mut self) {
+    /// Increments the rune fail count and gives up when
+    /// [`RuneRetryPolicy::max_attempts`] is reached.
+    ///
+    /// Giving up sets either [`PlayerContext::rune_change_channel`] or
+    /// [`PlayerContext::rune_cash_shop`]. Also records the failure and notifies with the
+    /// number of consecutive failures.
+    pub(super) fn track_rune_fail_count(&mut self, resources: &Resources) {
+        let policy = self.config.rune_retry_policy;
+
         self.rune_failed_count += 1;
-        if self.rune_failed_count >= MAX_RUNE_FAILED_COUNT {
+        resources.stats.record_rune_failed();
+        let _ = resources.notification.schedule_notification_with_details(
+            NotificationKind::RuneSolveFail,
+            format!(
+                "{} of {} attempt(s)",
+                self.rune_failed_count,
+                policy.max_attempts.max(1)
+            ),
+        );
+        info!(target: "rune", "failed to solve {} time(s)", self.rune_failed_count);
+        if self.rune_failed_count >= policy.max_attempts.max(1) {
+            info!(target: "rune", "giving up solving after {} time(s)", self.rune_failed_count);
             self.rune_failed_count = 0;
-            self.rune_cash_shop = true;
+            if policy.change_channel_on_give_up {
+                self.rune_change_channel = true;
+            } else {
+                self.rune_cash_shop = true;
+            }
         }
     }
 
+    /// Increments the lie detector fail count and requests a safe halt when
+    /// [`MAX_SHAPE_FAILED_COUNT`] is reached.
+    pub(super) fn track_shape_fail_count(&mut self) {
+        self.shape_failed_count += 1;
+        info!(target: "player", "failed to solve lie detector {} time(s)", self.shape_failed_count);
+        if self.shape_failed_count >= MAX_SHAPE_FAILED_COUNT {
+            self.shape_failed_count = 0;
+            self.shape_safe_halt = true;
+        }
+    }
+
+    /// Resets the lie detector fail count.
+    #[inline]
+    pub(super) fn clear_shape_fail_count(&mut self) {
+        self.shape_failed_count = 0;
+    }
+
+    /// Takes the safe halt request set after [`Player::SolvingShape`] failed too many times.
+    #[inline]
+    pub fn take_shape_safe_halt(&mut self) -> bool {
+        mem::take(&mut self.shape_safe_halt)
+    }
+
+    /// Resets the rune fail count and records the success.
+    #[inline]
+    fn track_rune_solved(&mut self, resources: &Resources) {
+        self.rune_failed_count = 0;
+        resources.stats.record_rune_solved();
+    }
+
     /// Increments the unstucking transitioned counter.
     ///
     /// Returns `true` when [`Player::Unstucking`] should enter GAMBA MODE.
@@ -842,20 +1062,26 @@ impl PlayerContext {
             Minimap::Detecting => return false,
         };
         let pos = self.last_known_pos.expect("in positional state");
-        let Update::Ok(points) = update_detection_task(
+        let Update::Ok((captured_at, mobs)) = update_detection_task_with_priority(
             resources,
+            TaskPriority::Low,
             self.config.auto_mob_use_key_when_pathing_update_millis,
             &mut self.auto_mob_pathing_task,
             move |detector| {
-                detector.detect_mobs(
+                let mobs = detector.detect_mobs(
                     minimap_bbox,
                     Rect::new(0, 0, minimap_bbox.width, minimap_bbox.height),
                     pos,
-                )
+                )?;
+                Ok((detector.captured_at(), mobs))
             },
         ) else {
             return false;
         };
+        if is_detection_stale(captured_at, Instant::now()) {
+            debug!(target: "player", "discarded stale auto mob pathing detection");
+            return false;
+        }
         let pathing_point = match self.normal_action {
             Some(PlayerAction::AutoMob(AutoMob { position, .. })) => {
                 Point::new(position.x, position.y)
@@ -863,9 +1089,9 @@ impl PlayerContext {
             _ => unreachable!(),
         };
 
-        let use_key = points
+        let use_key = mobs
             .into_iter()
-            .filter_map(|point| {
+            .filter_map(|(point, _, _)| {
                 let y = minimap_bbox.height - point.y;
                 let point = Point::new(point.x, y);
                 self.auto_mob_pick_reachable_y_position_inner(
@@ -935,18 +1161,8 @@ impl PlayerContext {
         } else {
             // Determine the player current quadrant inside the auto-mobbing bound
             // Convert current position to top-left coordinate first
-            let bound_width_half = bound.width / 2;
-            let bound_height_half = bound.height / 2;
-            let bound_x_mid = bound.x + bound_width_half;
-            let bound_y_mid = bound.y + bound_height_half;
             let pos = self.last_known_pos.expect("inside positional context");
-            let pos = Point::new(pos.x, bbox.height - pos.y);
-            match (pos.x < bound_x_mid, pos.y < bound_y_mid) {
-                (true, true) => Quadrant::TopLeft,
-                (false, true) => Quadrant::TopRight,
-                (false, false) => Quadrant::BottomRight,
-                (true, false) => Quadrant::BottomLeft,
-            }
+            Quadrant::from_point(bound, Point::new(pos.x, bbox.height - pos.y))
         };
 
         // Retrieve the next quadrant in clockwise order relative to current
@@ -1271,13 +1487,17 @@ impl PlayerContext {
         minimap_state: Minimap,
         buffs: &BuffEntities,
     ) -> bool {
+        self.idle_ticks = if resources.operation.halting() {
+            0
+        } else {
+            self.idle_ticks.saturating_add(1)
+        };
         if self.update_position_state(resources, minimap_state) {
             self.update_health_state(resources, player_state);
-            self.update_rune_validating_state(
-                #[cfg(debug_assertions)]
-                resources,
-                buffs,
-            );
+            self.update_mana_state(resources, player_state);
+            self.update_potion_state(resources, player_state);
+            self.update_exp_state(resources, player_state);
+            self.update_rune_validating_state(resources, buffs);
             self.update_is_dead_state(resources);
             self.update_stalling_buffer_state(resources);
             true
@@ -1293,11 +1513,17 @@ impl PlayerContext {
     /// with the y position. This is more intuitive both for the UI and development experience.
     #[inline]
     fn update_position_state(&mut self, resources: &Resources, minimap_state: Minimap) -> bool {
-        let minimap_bbox = match &minimap_state {
+        let (minimap_bbox, minimap_changed) = match &minimap_state {
             Minimap::Detecting => return false,
-            Minimap::Idle(idle) => idle.bbox,
+            Minimap::Idle(idle) => (idle.bbox, idle.changed),
         };
-        let Ok(player_bbox) = resources.detector().detect_player(minimap_bbox) else {
+        let prior_player_bbox = self
+            .last_known_pos
+            .filter(|_| !minimap_changed)
+            .and_then(|pos| detect_player_near(resources, minimap_bbox, pos));
+        let Some(player_bbox) =
+            prior_player_bbox.or_else(|| resources.detector().detect_player(minimap_bbox).ok())
+        else {
             return false;
         };
         let tl = player_bbox.tl();
@@ -1312,6 +1538,7 @@ impl PlayerContext {
         let pos = Point::new(x, y);
         let last_known_pos = self.last_known_pos.unwrap_or(pos);
         if last_known_pos != pos {
+            self.idle_ticks = 0;
             self.unstuck_count = 0;
             self.unstuck_transitioned_count = 0;
             self.is_stationary_timeout = Timeout::default();
@@ -1379,25 +1606,21 @@ impl PlayerContext {
     /// successfully detects and sends all the keys. After about 12 seconds, it
     /// will check if the player has the rune buff.
     #[inline]
-    fn update_rune_validating_state(
-        &mut self,
-        #[cfg(debug_assertions)] resources: &Resources,
-        buffs: &BuffEntities,
-    ) {
+    fn update_rune_validating_state(&mut self, resources: &Resources, buffs: &BuffEntities) {
         const VALIDATE_TIMEOUT: u32 = 375;
 
-        debug_assert!(self.rune_failed_count < MAX_RUNE_FAILED_COUNT);
         debug_assert!(!self.rune_cash_shop);
         self.rune_validate_timeout = self.rune_validate_timeout.and_then(|timeout| {
             match next_timeout_lifecycle(timeout, VALIDATE_TIMEOUT) {
                 Lifecycle::Ended => {
                     if matches!(buffs[BuffKind::Rune].state, Buff::No) {
-                        self.track_rune_fail_count();
-                        info!(target: "rune", "failed to solve {} time(s)", self.rune_failed_count);
+                        self.track_rune_fail_count(resources);
+                        self.rune_recording.export();
+                        resources.rune_dataset.save_pending_sample(false);
                     } else {
-                        self.rune_failed_count = 0;
-                        #[cfg(debug_assertions)]
-                        resources.debug.save_last_rune_result();
+                        self.track_rune_solved(resources);
+                        self.rune_recording.clear();
+                        resources.rune_dataset.save_pending_sample(true);
                     }
                     None
                 }
@@ -1411,17 +1634,27 @@ impl PlayerContext {
     /// The detection first detects the HP bar and caches the result. The HP bar is then used
     /// to crop into the game image and detects the current health bar and max health bar. These
     /// bars are then cached and used to extract the current health and max health.
+    ///
+    /// Health is only detected when using potion, notifying on low health or when the low health
+    /// emergency policy is enabled. Upon falling below the low health threshold, a notification
+    /// will be scheduled to notify the user. The HP bar is also detected when using mana potion
+    /// since the MP bar is located from it.
     // TODO: This should be a PlayerAction?
     #[inline]
     fn update_health_state(&mut self, resources: &Resources, player_state: Player) {
         if matches!(player_state, Player::SolvingRune(_)) {
             return;
         }
-        if self.config.use_potion_below_percent.is_none() {
+        let low_ratio = resources.notification.player_health_low_ratio();
+        let update_health = self.config.use_potion_below_percent.is_some()
+            || self.config.detect_critical_health
+            || low_ratio.is_some();
+        if !update_health && self.config.use_mana_potion_below_percent.is_none() {
             self.health = None;
             self.health_task = None;
             self.health_bar = None;
             self.health_bar_task = None;
+            self.is_health_low = false;
             return;
         }
 
@@ -1437,6 +1670,12 @@ impl PlayerContext {
             }
             return;
         };
+        if !update_health {
+            self.health = None;
+            self.health_task = None;
+            self.is_health_low = false;
+            return;
+        }
 
         let Update::Ok(health) = update_detection_task(
             resources,
@@ -1452,14 +1691,92 @@ impl PlayerContext {
             return;
         };
 
-        let percentage = self.config.use_potion_below_percent.unwrap();
         let (current, max) = health;
         let ratio = current as f32 / max as f32;
 
         self.health = Some(health);
-        if ratio <= percentage {
+
+        let is_health_low = low_ratio.is_some_and(|low_ratio| ratio <= low_ratio);
+        if is_health_low && !self.is_health_low {
+            let _ = resources
+                .notification
+                .schedule_notification(NotificationKind::PlayerHealthLow);
+        }
+        self.is_health_low = is_health_low;
+    }
+
+    /// Updates the player current mana from the MP bar below the detected HP bar.
+    ///
+    /// Mana is only detected when using mana potion.
+    #[inline]
+    fn update_mana_state(&mut self, resources: &Resources, player_state: Player) {
+        if matches!(player_state, Player::SolvingRune(_)) {
+            return;
+        }
+        let (Some(health_bar), Some(_)) =
+            (self.health_bar, self.config.use_mana_potion_below_percent)
+        else {
+            self.mana = None;
+            self.mana_task = None;
+            return;
+        };
+
+        let Update::Ok(mana) = update_detection_task(
+            resources,
+            self.config.update_health_millis.unwrap_or(1000),
+            &mut self.mana_task,
+            move |detector| detector.detect_player_mana(health_bar),
+        ) else {
+            return;
+        };
+        self.mana = Some(mana);
+    }
+
+    /// Uses the health and mana potions when below their thresholds.
+    ///
+    /// This runs every tick regardless of the current action so that potions are used even while
+    /// moving. The last detected health and mana are used with [`Potion`] preventing the same
+    /// potion from being used again within the cooldown.
+    #[inline]
+    fn update_potion_state(&mut self, resources: &Resources, player_state: Player) {
+        if matches!(player_state, Player::SolvingRune(_)) {
+            return;
+        }
+
+        let now = Instant::now();
+        let policy = PotionPolicy {
+            cooldown: Duration::from_millis(self.config.potion_cooldown_millis),
+            pet_auto_potion: self.config.pet_auto_potion,
+        };
+        let health_below = is_below_percent(self.health, self.config.use_potion_below_percent);
+        if self.health_potion.should_use(health_below, policy, now) {
             resources.input.send_key(self.config.potion_key);
+            self.potion_uses += 1;
+        }
+
+        let mana_below = is_below_percent(self.mana, self.config.use_mana_potion_below_percent);
+        if self.mana_potion.should_use(mana_below, policy, now) {
+            resources.input.send_key(self.config.mana_potion_key);
+        }
+    }
+
+    /// Updates the EXP bar every [`EXP_UPDATE_MILLIS`] for estimating the EXP rate.
+    #[inline]
+    fn update_exp_state(&mut self, resources: &Resources, player_state: Player) {
+        if matches!(player_state, Player::SolvingRune(_)) {
+            return;
         }
+
+        let Update::Ok(bar) = update_detection_task(
+            resources,
+            EXP_UPDATE_MILLIS,
+            &mut self.exp_task,
+            |detector| detector.detect_exp_bar(),
+        ) else {
+            return;
+        };
+        self.exp_tracker.push(Instant::now(), bar);
+        resources.stats.record_exp_sample(bar.level, bar.percent);
     }
 
     /// Updates whether the player is dead.
@@ -1475,6 +1792,7 @@ impl PlayerContext {
             return;
         };
         if is_dead && !self.is_dead {
+            resources.stats.record_death();
             let _ = resources
                 .notification
                 .schedule_notification(NotificationKind::PlayerIsDead);
@@ -1547,19 +1865,70 @@ fn auto_mob_ignore_xs_range_value(x: i32) -> (Range<i32>, u32) {
     (range.into(), 0)
 }
 
+/// Whether a detection on the frame captured at `captured_at` is too old to be used at `now`.
+#[inline]
+fn is_detection_stale(captured_at: Instant, now: Instant) -> bool {
+    now.saturating_duration_since(captured_at) > MAX_DETECTION_FRAME_AGE
+}
+
+/// Whether the `current` and max `value` is at or below `percent`.
+#[inline]
+fn is_below_percent(value: Option<(u32, u32)>, percent: Option<f32>) -> bool {
+    match (value, percent) {
+        (Some((current, max)), Some(percent)) if max > 0 => current as f32 / max as f32 <= percent,
+        _ => false,
+    }
+}
+
+/// Detects the player in a small window around the last known position `pos`.
+///
+/// Returns the player bounding box relative to `minimap_bbox`.
+#[inline]
+fn detect_player_near(resources: &Resources, minimap_bbox: Rect, pos: Point) -> Option<Rect> {
+    // Flips back to top-left coordinate relative to the whole frame
+    let center = Point::new(
+        minimap_bbox.x + pos.x,
+        minimap_bbox.y + minimap_bbox.height - pos.y,
+    );
+    let window = Rect::new(
+        center.x - PLAYER_PRIOR_SEARCH_SIZE / 2,
+        center.y - PLAYER_PRIOR_SEARCH_SIZE / 2,
+        PLAYER_PRIOR_SEARCH_SIZE,
+        PLAYER_PRIOR_SEARCH_SIZE,
+    ) & minimap_bbox;
+    if window.empty() {
+        return None;
+    }
+
+    let bbox = resources.detector().detect_player(window).ok()?;
+    Some(Rect::new(
+        bbox.x + window.x - minimap_bbox.x,
+        bbox.y + window.y - minimap_bbox.y,
+        bbox.width,
+        bbox.height,
+    ))
+}
+
 #[cfg(test)]
 mod tests {
-    use std::{assert_matches::assert_matches, collections::HashMap};
+    use std::{
+        assert_matches::assert_matches,
+        collections::HashMap,
+        time::{Duration, Instant},
+    };
 
+    use mockall::predicate::eq;
     use opencv::core::{Point, Rect};
 
+    use super::{MAX_DETECTION_FRAME_AGE, MAX_SHAPE_FAILED_COUNT, is_detection_stale};
     use crate::{
         Position,
         array::Array,
+        bridge::{KeyKind, MockInput},
         ecs::Resources,
         minimap::{Minimap, MinimapIdle},
         pathing::{Platform, find_neighbors},
-        player::{AutoMob, PlayerAction, PlayerContext, Quadrant},
+        player::{AutoMob, Player, PlayerAction, PlayerContext, Quadrant},
         rng::Rng,
     };
 
@@ -1775,4 +2144,56 @@ mod tests {
         assert_eq!(point.y, 20); // 100 - 80
         assert_matches!(state.auto_mob_last_quadrant, Some(Quadrant::BottomLeft));
     }
+
+    #[test]
+    fn update_potion_state_uses_potions_below_threshold_once_within_cooldown() {
+        let mut keys = MockInput::default();
+        keys.expect_send_key().with(eq(KeyKind::P)).once();
+        keys.expect_send_key().with(eq(KeyKind::O)).never();
+        let resources = Resources::new(Some(keys), None);
+        let mut state = PlayerContext {
+            health: Some((30, 100)),
+            mana: Some((80, 100)),
+            ..Default::default()
+        };
+        state.config.potion_key = KeyKind::P;
+        state.config.use_potion_below_percent = Some(0.5);
+        state.config.mana_potion_key = KeyKind::O;
+        state.config.use_mana_potion_below_percent = Some(0.5);
+        state.config.potion_cooldown_millis = 60000;
+
+        state.update_potion_state(&resources, Player::Idle);
+        state.update_potion_state(&resources, Player::Idle);
+    }
+
+    #[test]
+    fn track_shape_fail_count_requests_safe_halt_once_limit_reached() {
+        let mut state = PlayerContext::default();
+
+        state.track_shape_fail_count();
+        state.clear_shape_fail_count();
+        for _ in 0..MAX_SHAPE_FAILED_COUNT - 1 {
+            state.track_shape_fail_count();
+        }
+        assert!(!state.take_shape_safe_halt());
+
+        state.track_shape_fail_count();
+        assert!(state.take_shape_safe_halt());
+        assert!(!state.take_shape_safe_halt());
+    }
+
+    #[test]
+    fn is_detection_stale_discards_old_frames() {
+        let captured_at = Instant::now();
+
+        assert!(!is_detection_stale(captured_at, captured_at));
+        assert!(!is_detection_stale(
+            captured_at,
+            captured_at + MAX_DETECTION_FRAME_AGE
+        ));
+        assert!(is_detection_stale(
+            captured_at,
+            captured_at + MAX_DETECTION_FRAME_AGE + Duration::from_millis(1)
+        ));
+    }
 }
@@ -260,6 +260,9 @@ pub fn update_use_key_state(
             update_using(resources, &player.context, &mut use_key);
 
             let has_transition = matches!(use_key.pending_transition, PendingTransition::WaitAfter);
+            if has_transition && use_key.key == player.context.config.potion_key {
+                player.context.potion_uses += 1;
+            }
             let should_buffer =
                 has_transition && use_key.has_wait_after_buffer() && use_key.is_last_key_use();
 
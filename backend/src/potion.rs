@@ -0,0 +1,92 @@
+//! Automatic potion usage based on the detected HP and MP.
+//!
+//! A potion is used when the ratio falls to or below its threshold and the cooldown since the
+//! last use has passed. When the pet auto potion is relied on, the bot only uses a potion as a
+//! fallback after the ratio stays low for a grace duration the pet did not handle.
+
+use std::time::{Duration, Instant};
+
+/// The duration the ratio must stay low before using a potion when relying on pet auto potion.
+const PET_AUTO_POTION_GRACE: Duration = Duration::from_millis(1500);
+
+/// The policy shared by all potions.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PotionPolicy {
+    /// The minimum duration between two uses of the same potion.
+    pub cooldown: Duration,
+    /// Whether the pet auto potion is relied on before using a potion.
+    pub pet_auto_potion: bool,
+}
+
+/// Tracks the usage of a single potion.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Potion {
+    last_used: Option<Instant>,
+    /// The [`Instant`] the ratio first fell below the threshold.
+    below_since: Option<Instant>,
+}
+
+impl Potion {
+    /// Determines whether the potion should be used at `now` given whether the ratio is `below`
+    /// the threshold.
+    ///
+    /// Returns `true` if the potion key should be sent, in which case it is recorded as used.
+    pub fn should_use(&mut self, below: bool, policy: PotionPolicy, now: Instant) -> bool {
+        if !below {
+            self.below_since = None;
+            return false;
+        }
+
+        let below_since = *self.below_since.get_or_insert(now);
+        if policy.pet_auto_potion
+            && now.saturating_duration_since(below_since) < PET_AUTO_POTION_GRACE
+        {
+            return false;
+        }
+        if self
+            .last_used
+            .is_some_and(|instant| now.saturating_duration_since(instant) < policy.cooldown)
+        {
+            return false;
+        }
+
+        self.last_used = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_use_respects_cooldown() {
+        let mut potion = Potion::default();
+        let policy = PotionPolicy {
+            cooldown: Duration::from_secs(2),
+            pet_auto_potion: false,
+        };
+        let now = Instant::now();
+
+        assert!(!potion.should_use(false, policy, now));
+        assert!(potion.should_use(true, policy, now));
+        assert!(!potion.should_use(true, policy, now + Duration::from_secs(1)));
+        assert!(potion.should_use(true, policy, now + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn should_use_waits_for_pet_auto_potion() {
+        let mut potion = Potion::default();
+        let policy = PotionPolicy {
+            cooldown: Duration::ZERO,
+            pet_auto_potion: true,
+        };
+        let now = Instant::now();
+
+        assert!(!potion.should_use(true, policy, now));
+        // The pet used a potion in time
+        assert!(!potion.should_use(false, policy, now + Duration::from_secs(1)));
+        assert!(!potion.should_use(true, policy, now + Duration::from_secs(2)));
+        assert!(potion.should_use(true, policy, now + Duration::from_secs(4)));
+    }
+}
@@ -0,0 +1,87 @@
+//! Scanning of the quick slots for known skill icons.
+//!
+//! Key bindings are set manually for each new setup and easily get out of sync with the quick
+//! slots. [`scan_quick_slots`] template-matches the skill icons known to the bot across the quick
+//! slots region and compares them against what the character and its actions expect.
+
+use log::info;
+
+use crate::{Action, ActionCondition, Character, QuickSlotsSkill, detect::Detector};
+
+/// Scans the quick slots captured by `detector` for known skill icons.
+///
+/// `actions` are the in-use actions, used to know whether a key is bound to a skill that is only
+/// used through actions.
+pub fn scan_quick_slots(
+    detector: &dyn Detector,
+    character: Option<&Character>,
+    actions: &[Action],
+) -> Vec<QuickSlotsSkill> {
+    let erda_shower_bound = actions
+        .iter()
+        .any(|action| matches!(action.condition(), ActionCondition::ErdaShowerOffCooldown));
+    let hexa_booster_bound = character.is_some_and(|character| character.hexa_booster_key.enabled);
+
+    let skills = vec![
+        skill(
+            "Erda Shower",
+            detector.detect_erda_shower().is_ok(),
+            erda_shower_bound,
+        ),
+        skill(
+            "HEXA Booster",
+            detector.detect_quick_slots_hexa_booster().is_ok(),
+            hexa_booster_bound,
+        ),
+    ];
+    info!(target: "quickslots", "scanned quick slots {skills:?}");
+
+    skills
+}
+
+#[inline]
+fn skill(name: &str, found: bool, bound: bool) -> QuickSlotsSkill {
+    QuickSlotsSkill {
+        name: name.to_string(),
+        found,
+        bound,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use opencv::core::Rect;
+
+    use super::*;
+    use crate::{KeyBindingConfiguration, detect::MockDetector};
+
+    #[test]
+    fn scan_quick_slots_reports_found_icons_without_bound_keys() {
+        let mut detector = MockDetector::default();
+        detector
+            .expect_detect_erda_shower()
+            .returning(|| Ok(Rect::default()));
+        detector
+            .expect_detect_quick_slots_hexa_booster()
+            .returning(|| Err(anyhow!("not found")));
+        let character = Character {
+            hexa_booster_key: KeyBindingConfiguration {
+                enabled: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let skills = scan_quick_slots(&detector, Some(&character), &[]);
+
+        assert_eq!(
+            skills,
+            vec![
+                skill("Erda Shower", true, false),
+                skill("HEXA Booster", false, true),
+            ]
+        );
+        assert!(skills.iter().all(QuickSlotsSkill::is_mismatched));
+    }
+}
@@ -0,0 +1,213 @@
+//! Recording of annotated frames for troubleshooting failed rune solves.
+//!
+//! While the rune is being solved, frames are sampled and annotated with the detected rune arrows
+//! and their scores. When the rune is later validated as failed, the frames are exported as an
+//! animated GIF so that users can share exactly what the detector saw.
+
+use std::{
+    collections::VecDeque,
+    env,
+    fs::{self, File},
+    mem,
+    path::PathBuf,
+    sync::LazyLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Result, bail};
+use gif::{Encoder, Frame, Repeat};
+use log::{info, warn};
+use opencv::{
+    core::{Mat, MatTraitConst, MatTraitConstManual, Point, Rect, Scalar, Size},
+    imgproc::{
+        COLOR_BGRA2RGB, FONT_HERSHEY_SIMPLEX, INTER_AREA, LINE_8, cvt_color_def, put_text,
+        rectangle, resize,
+    },
+};
+use tokio::task::spawn_blocking;
+
+use crate::{
+    detect::{Detector, RUNE_ARROW_SCORE_THRESHOLD},
+    run::FPS,
+};
+
+/// The number of ticks between two recorded frames.
+const RECORD_INTERVAL: u64 = 4;
+
+/// The maximum number of most recent frames kept in a recording.
+const MAX_FRAMES: usize = 60;
+
+/// The width of a recorded frame after downscaling.
+const FRAME_WIDTH: i32 = 800;
+
+/// The delay between two GIF frames in centiseconds matching [`RECORD_INTERVAL`].
+const FRAME_DELAY: u16 = (RECORD_INTERVAL * 100 / FPS as u64) as u16;
+
+static TROUBLESHOOT_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
+    let dir = env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("troubleshoot");
+    fs::create_dir_all(dir.clone()).unwrap();
+    dir
+});
+
+/// A bounded recording of annotated frames while solving the rune.
+#[derive(Debug, Default)]
+pub struct RuneRecording {
+    frames: VecDeque<Mat>,
+}
+
+impl RuneRecording {
+    /// Records the frame of `detector` annotated with the detected rune arrows if `tick` is due.
+    pub fn record(&mut self, detector: &dyn Detector, tick: u64) {
+        if !tick.is_multiple_of(RECORD_INTERVAL) {
+            return;
+        }
+        let Ok(frame) = annotate_frame(detector) else {
+            return;
+        };
+        if self.frames.len() >= MAX_FRAMES {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    /// Clears all the recorded frames.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Exports the recorded frames as an animated GIF in the background and clears the recording.
+    ///
+    /// The saved path is logged once the export completes.
+    pub fn export(&mut self) {
+        let frames = mem::take(&mut self.frames);
+        if frames.is_empty() {
+            return;
+        }
+
+        spawn_blocking(move || match save_gif(frames) {
+            Ok(path) => info!(
+                target: "rune",
+                "troubleshooting GIF of failed solve saved to {}",
+                path.display()
+            ),
+            Err(err) => warn!(target: "rune", "unable to save troubleshooting GIF {err}"),
+        });
+    }
+}
+
+fn annotate_frame(detector: &dyn Detector) -> Result<Mat> {
+    let arrows = detector.detect_rune_arrows_with_scores();
+    let mat = detector.mat();
+    let scale = FRAME_WIDTH as f64 / mat.cols() as f64;
+    let size = Size::new(FRAME_WIDTH, (mat.rows() as f64 * scale) as i32);
+
+    let mut resized = Mat::default();
+    resize(&*mat, &mut resized, size, 0.0, 0.0, INTER_AREA)?;
+    let mut frame = Mat::default();
+    cvt_color_def(&resized, &mut frame, COLOR_BGRA2RGB)?;
+
+    for (bbox, arrow, score) in arrows {
+        let bbox = Rect::new(
+            (bbox.x as f64 * scale) as i32,
+            (bbox.y as f64 * scale) as i32,
+            (bbox.width as f64 * scale).max(1.0) as i32,
+            (bbox.height as f64 * scale).max(1.0) as i32,
+        );
+        // Green for arrows used when solving and red for discarded ones
+        let color = if score >= RUNE_ARROW_SCORE_THRESHOLD {
+            Scalar::new(0.0, 255.0, 0.0, 0.0)
+        } else {
+            Scalar::new(255.0, 0.0, 0.0, 0.0)
+        };
+        rectangle(&mut frame, bbox, color, 1, LINE_8, 0)?;
+        put_text(
+            &mut frame,
+            &format!("{arrow:?} {score:.2}"),
+            bbox.tl() - Point::new(0, 4),
+            FONT_HERSHEY_SIMPLEX,
+            0.4,
+            color,
+            1,
+            LINE_8,
+            false,
+        )?;
+    }
+
+    Ok(frame)
+}
+
+fn save_gif(frames: VecDeque<Mat>) -> Result<PathBuf> {
+    let size = frames[0].size()?;
+    let (Ok(width), Ok(height)) = (u16::try_from(size.width), u16::try_from(size.height)) else {
+        bail!("frame size {size:?} is too large for GIF");
+    };
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let path = TROUBLESHOOT_DIR.join(format!("rune_{millis}.gif"));
+
+    let mut encoder = Encoder::new(File::create(&path)?, width, height, &[])?;
+    encoder.set_repeat(Repeat::Infinite)?;
+    for frame in frames {
+        let mut frame = Frame::from_rgb_speed(width, height, frame.data_bytes()?, 10);
+        frame.delay = FRAME_DELAY;
+        encoder.write_frame(&frame)?;
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use opencv::{
+        boxed_ref::BoxedRef,
+        core::{CV_8UC3, CV_8UC4, MatExprTraitConst},
+    };
+
+    use super::*;
+    use crate::{bridge::KeyKind, detect::MockDetector};
+
+    fn mock_detector() -> MockDetector {
+        let mat = Mat::zeros(400, 1600, CV_8UC4).unwrap().to_mat().unwrap();
+        let mut detector = MockDetector::new();
+        detector
+            .expect_mat()
+            .returning(move || BoxedRef::from(mat.clone()));
+        detector
+            .expect_detect_rune_arrows_with_scores()
+            .returning(|| vec![(Rect::new(100, 100, 50, 50), KeyKind::Up, 0.9)]);
+        detector
+    }
+
+    #[test]
+    fn rune_recording_record_annotated_frame_only_when_tick_due() {
+        let detector = mock_detector();
+        let mut recording = RuneRecording::default();
+
+        recording.record(&detector, RECORD_INTERVAL + 1);
+        assert!(recording.frames.is_empty());
+
+        recording.record(&detector, RECORD_INTERVAL);
+        assert_eq!(recording.frames.len(), 1);
+        let frame = &recording.frames[0];
+        assert_eq!(frame.typ(), CV_8UC3);
+        assert_eq!(frame.size().unwrap(), Size::new(FRAME_WIDTH, 200));
+    }
+
+    #[test]
+    fn rune_recording_record_keeps_most_recent_frames() {
+        let detector = mock_detector();
+        let mut recording = RuneRecording::default();
+
+        for i in 0..=MAX_FRAMES as u64 {
+            recording.record(&detector, i * RECORD_INTERVAL);
+        }
+        assert_eq!(recording.frames.len(), MAX_FRAMES);
+
+        recording.clear();
+        assert!(recording.frames.is_empty());
+    }
+}
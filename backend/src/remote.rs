@@ -0,0 +1,353 @@
+//! Remote control of the bot over gRPC.
+//!
+//! The `BotControl` service from `proto/control.proto` exposes the same requests the UI sends
+//! through the request channel so that another process (e.g. a phone or another PC) can start
+//! and stop the bot, select the capture window and stream [`GameState`]s. Like the UI, requests
+//! go to the currently selected instance.
+//!
+//! The service also carries the `ExchangeDuo` stream used by [`crate::duo`] when this instance
+//! hosts duo mode.
+//!
+//! Requests must carry the shared [`Settings::remote_control_token`] as a bearer token when one
+//! is set, and the service is only served outside loopback with a token. TLS is used when a
+//! certificate and private key are configured.
+
+use std::{fs, io, net::SocketAddr, time::Instant};
+
+use log::{info, warn};
+use proto::{
+    Health, Operation, OperationUpdate, Point, QueryCaptureHandlesRequest,
+    QueryCaptureHandlesResponse, SelectCaptureHandleRequest, SelectCaptureHandleResponse,
+    StreamGameStateRequest, UpdateOperationRequest, UpdateOperationResponse,
+    bot_control_server::{BotControl, BotControlServer},
+};
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{
+    Request, Response, Status, Streaming,
+    metadata::{Ascii, MetadataValue},
+    transport::{Identity, Server, ServerTlsConfig},
+};
+
+use crate::{
+    BotOperation, BotOperationUpdate, GameState, Settings, duo, game_state_receiver,
+    query_capture_handles, refresh_capture_handles, select_capture_handle, update_operation,
+};
+
+pub(crate) mod proto {
+    tonic::include_proto!("control");
+}
+
+/// The number of [`GameState`]s buffered for a slow client before skipping newer ones.
+const STREAM_BUFFER: usize = 4;
+
+/// The metadata key of the `Bearer <token>` sent by clients.
+const AUTHORIZATION: &str = "authorization";
+
+#[derive(Debug)]
+struct RemoteControl;
+
+#[tonic::async_trait]
+impl BotControl for RemoteControl {
+    type StreamGameStateStream = ReceiverStream<Result<proto::GameState, Status>>;
+    type ExchangeDuoStream = ReceiverStream<Result<proto::DuoMessage, Status>>;
+
+    async fn update_operation(
+        &self,
+        request: Request<UpdateOperationRequest>,
+    ) -> Result<Response<UpdateOperationResponse>, Status> {
+        let update = match request.into_inner().update() {
+            OperationUpdate::Halt => BotOperationUpdate::Halt,
+            OperationUpdate::TemporaryHalt => BotOperationUpdate::TemporaryHalt,
+            OperationUpdate::Run => BotOperationUpdate::Run,
+        };
+        update_operation(update).await;
+
+        Ok(Response::new(UpdateOperationResponse {}))
+    }
+
+    async fn query_capture_handles(
+        &self,
+        request: Request<QueryCaptureHandlesRequest>,
+    ) -> Result<Response<QueryCaptureHandlesResponse>, Status> {
+        if request.into_inner().refresh {
+            refresh_capture_handles().await;
+        }
+        let (names, selected) = query_capture_handles().await;
+
+        Ok(Response::new(QueryCaptureHandlesResponse {
+            names,
+            selected_index: selected.map(|index| index as u32),
+        }))
+    }
+
+    async fn select_capture_handle(
+        &self,
+        request: Request<SelectCaptureHandleRequest>,
+    ) -> Result<Response<SelectCaptureHandleResponse>, Status> {
+        let index = request.into_inner().index.map(|index| index as usize);
+        let (names, _) = query_capture_handles().await;
+        if index.is_some_and(|index| index >= names.len()) {
+            return Err(Status::invalid_argument(
+                "capture handle index out of range",
+            ));
+        }
+        select_capture_handle(index).await;
+
+        Ok(Response::new(SelectCaptureHandleResponse {}))
+    }
+
+    async fn stream_game_state(
+        &self,
+        _: Request<StreamGameStateRequest>,
+    ) -> Result<Response<Self::StreamGameStateStream>, Status> {
+        let mut game_state_rx = game_state_receiver().await;
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(async move {
+            loop {
+                let state = match game_state_rx.recv().await {
+                    Ok(state) => state,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                if tx.send(Ok(game_state_message(state))).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn exchange_duo(
+        &self,
+        request: Request<Streaming<proto::DuoMessage>>,
+    ) -> Result<Response<Self::ExchangeDuoStream>, Status> {
+        Ok(Response::new(duo::accept(request.into_inner())?))
+    }
+}
+
+/// Serves the `BotControl` service at [`Settings::remote_control_address`] in the background.
+///
+/// Refuses to serve outside loopback without [`Settings::remote_control_token`].
+pub fn serve(settings: &Settings) {
+    let address = &settings.remote_control_address;
+    let addr = match address.parse::<SocketAddr>() {
+        Ok(addr) => addr,
+        Err(err) => {
+            warn!(target: "remote", "invalid remote control address {address} {err}");
+            return;
+        }
+    };
+    let token = settings.remote_control_token.clone();
+    if token.is_empty() && !addr.ip().is_loopback() {
+        warn!(target: "remote", "refusing to serve remote control at {addr} without a token");
+        return;
+    }
+    let tls = match tls_config(
+        &settings.remote_control_tls_cert_path,
+        &settings.remote_control_tls_key_path,
+    ) {
+        Ok(tls) => tls,
+        Err(err) => {
+            warn!(target: "remote", "unable to read remote control TLS identity {err}");
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        let mut builder = Server::builder();
+        if let Some(tls) = tls {
+            builder = match builder.tls_config(tls) {
+                Ok(builder) => builder,
+                Err(err) => {
+                    warn!(target: "remote", "invalid remote control TLS identity {err}");
+                    return;
+                }
+            };
+        }
+        let service =
+            BotControlServer::with_interceptor(RemoteControl, move |request: Request<()>| {
+                check_token(&token, request)
+            });
+
+        info!(target: "remote", "serving remote control at {addr}");
+        if let Err(err) = builder.add_service(service).serve(addr).await {
+            warn!(target: "remote", "unable to serve remote control at {addr} {err}");
+        }
+    });
+}
+
+/// Creates an interceptor for a `BotControl` client that sends `token` if it is not empty.
+pub(crate) fn with_token(
+    token: &str,
+) -> impl FnMut(Request<()>) -> Result<Request<()>, Status> + Clone {
+    let value = format!("Bearer {token}")
+        .parse::<MetadataValue<Ascii>>()
+        .ok()
+        .filter(|_| !token.is_empty());
+
+    move |mut request: Request<()>| {
+        if let Some(value) = value.clone() {
+            request.metadata_mut().insert(AUTHORIZATION, value);
+        }
+        Ok(request)
+    }
+}
+
+/// Reads the PEM `cert_path` and `key_path` into a TLS config if both are set.
+fn tls_config(cert_path: &str, key_path: &str) -> io::Result<Option<ServerTlsConfig>> {
+    if cert_path.is_empty() || key_path.is_empty() {
+        return Ok(None);
+    }
+    let cert = fs::read(cert_path)?;
+    let key = fs::read(key_path)?;
+
+    Ok(Some(
+        ServerTlsConfig::new().identity(Identity::from_pem(cert, key)),
+    ))
+}
+
+/// Rejects `request` unless `token` is empty or sent as the bearer token.
+fn check_token(token: &str, request: Request<()>) -> Result<Request<()>, Status> {
+    if token.is_empty() {
+        return Ok(request);
+    }
+    let authorized = request
+        .metadata()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|value| constant_time_eq(value.as_bytes(), token.as_bytes()));
+
+    if authorized {
+        Ok(request)
+    } else {
+        Err(Status::unauthenticated("invalid remote control token"))
+    }
+}
+
+/// Compares `a` and `b` without returning early at the first differing byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn game_state_message(state: GameState) -> proto::GameState {
+    let now = Instant::now();
+    let (operation, remaining) = match state.operation {
+        BotOperation::Halting => (Operation::Halting, None),
+        BotOperation::TemporaryHalting(duration) => (Operation::TemporaryHalting, Some(duration)),
+        BotOperation::HaltUntil(instant) => (
+            Operation::HaltUntil,
+            Some(instant.saturating_duration_since(now)),
+        ),
+        BotOperation::Running => (Operation::Running, None),
+        BotOperation::RunUntil(instant) => (
+            Operation::RunUntil,
+            Some(instant.saturating_duration_since(now)),
+        ),
+    };
+
+    proto::GameState {
+        position: state.position.map(|(x, y)| Point { x, y }),
+        health: state.health.map(|(current, max)| Health { current, max }),
+        state: state.state,
+        normal_action: state.normal_action,
+        priority_action: state.priority_action,
+        erda_shower_state: state.erda_shower_state,
+        destinations: state
+            .destinations
+            .into_iter()
+            .map(|(x, y)| Point { x, y })
+            .collect(),
+        operation: operation.into(),
+        operation_remaining_millis: remaining.map_or(0, |duration| duration.as_millis() as u64),
+        stranger_count: state.stranger_count as u32,
+        crowding_score: state.crowding_score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::FrameTimings;
+
+    fn request_with_authorization(value: &str) -> Request<()> {
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert(AUTHORIZATION, value.parse().unwrap());
+        request
+    }
+
+    #[test]
+    fn check_token_requires_matching_bearer_token() {
+        assert!(check_token("secret", request_with_authorization("Bearer secret")).is_ok());
+        assert!(check_token("secret", request_with_authorization("Bearer secrex")).is_err());
+        assert!(check_token("secret", request_with_authorization("secret")).is_err());
+        assert!(check_token("secret", Request::new(())).is_err());
+        assert!(check_token("", Request::new(())).is_ok());
+    }
+
+    #[test]
+    fn with_token_sends_bearer_token() {
+        let request = with_token("secret")(Request::new(())).unwrap();
+
+        assert!(check_token("secret", request).is_ok());
+        assert!(
+            with_token("")(Request::new(()))
+                .unwrap()
+                .metadata()
+                .get(AUTHORIZATION)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn game_state_message_maps_timed_operation() {
+        let state = GameState {
+            position: Some((10, 20)),
+            health: Some((50, 100)),
+            mana: None,
+            state: "Idle".to_string(),
+            normal_action: None,
+            priority_action: Some("Buff".to_string()),
+            next_priority_action: None,
+            erda_shower_state: "Detecting".to_string(),
+            destinations: vec![(1, 2)],
+            waypoints: vec![],
+            operation: BotOperation::TemporaryHalting(Duration::from_secs(5)),
+            frame: Some((vec![0; 4], 1, 1)),
+            platforms_bound: None,
+            portals: vec![],
+            auto_mob_quadrant: None,
+            stranger_count: 2,
+            crowding_score: 1.5,
+            external_preset_error: None,
+            detected_character_id: None,
+            exp: None,
+            runes_solved: 0,
+            runes_failed: 0,
+            errors: vec![],
+            frame_timings: FrameTimings::default(),
+            priority_actions_dropped: vec![],
+        };
+
+        let message = game_state_message(state);
+
+        assert_eq!(message.position, Some(Point { x: 10, y: 20 }));
+        assert_eq!(
+            message.health,
+            Some(Health {
+                current: 50,
+                max: 100
+            })
+        );
+        assert_eq!(message.operation(), Operation::TemporaryHalting);
+        assert_eq!(message.operation_remaining_millis, 5000);
+        assert_eq!(message.destinations, vec![Point { x: 1, y: 2 }]);
+        assert_eq!(message.stranger_count, 2);
+    }
+}
@@ -1,20 +1,22 @@
 use std::{
     assert_matches::debug_assert_matches,
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     fmt::Debug,
     sync::{
         Arc,
         atomic::{AtomicU32, Ordering},
     },
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use anyhow::Result;
-use log::{debug, info};
+use log::{debug, info, warn};
 #[cfg(test)]
 use mockall::{automock, concretize};
 use opencv::core::{Point, Rect};
 use ordered_hash_map::OrderedHashMap;
+use platforms::sound::play_notification_sound;
+use strum::IntoEnumIterator;
 
 use crate::{
     Bound,
@@ -22,25 +24,45 @@ use crate::{
     bridge::{KeyKind, LinkKeyKind},
     buff::{Buff, BuffKind},
     detect::{Detector, QuickSlotsHexaBooster, SolErda},
+    duo,
     ecs::{Resources, World},
+    expression::Expression,
     minimap::Minimap,
     models::{
-        Action, ActionCondition, ActionKey, ActionKeyDirection, ActionKeyWith, ActionMove,
-        EliteBossBehavior, ExchangeHexaBoosterCondition, Familiars, MobbingKey, Position,
-        WaitAfterBuffered,
+        Action, ActionCondition, ActionKey, ActionKeyDirection, ActionKeyWith, ActionMacro,
+        ActionMove, ActionNotify, ActionTags, ActionsBurstWindow, AutoMobQuadrantMode,
+        AutoMobQuadrants, EliteBossBehavior, EliteBossRotationKey, ExchangeHexaBoosterCondition,
+        Familiars, InputMacro, MobbingKey, Position, WaitAfterBuffered,
     },
     player::{
-        AutoMob, Booster, ExchangeBooster, FamiliarsSwap, GRAPPLING_THRESHOLD, Key, Panic, PanicTo,
-        PingPong, PingPongDirection, PlayerAction, PlayerContext, PlayerEntity, Quadrant,
+        AutoMob, Booster, ExchangeBooster, FamiliarsSwap, GRAPPLING_THRESHOLD, Key, Macro, Panic,
+        PanicTo, PingPong, PingPongDirection, PlayerAction, PlayerContext, PlayerEntity, Quadrant,
         UseBooster,
     },
-    run::MS_PER_TICK,
+    run::{FPS, MS_PER_TICK},
     skill::{Skill, SkillKind},
-    task::{Task, Update, update_detection_task},
+    task::{
+        Task, TaskPriority, Update, update_detection_task, update_detection_task_with_priority,
+    },
+    tracker::{ByteTracker, Detection},
 };
 
 const AUTO_MOB_SAME_QUAD_THRESHOLD: u32 = 5;
 
+/// The size of the box around a mob minimap coordinate used for tracking.
+///
+/// A mob on screen is only a few pixels on the minimap, so the box is enlarged for a moving mob
+/// to still overlap its previous box.
+const AUTO_MOB_TRACK_SIZE: i32 = 8;
+
+/// The number of detections ahead a tracked mob position is predicted.
+const AUTO_MOB_TRACK_LEAD_UPDATES: f32 = 5.0;
+
+/// The maximum number of recently died tracked mobs to avoid picking.
+const AUTO_MOB_DEAD_TRACKS_MAX_LEN: usize = 16;
+
+const EXPRESSION_ACTION_INTERVAL_MILLIS: u128 = 1000;
+
 /// [`Condition`] evaluation result.
 #[derive(Debug)]
 enum ConditionResult {
@@ -101,6 +123,29 @@ struct PriorityActionQueueInfo {
     ignoring: bool,
     /// The last [`Instant`] when this action was queued
     last_queued_time: Option<Instant>,
+    /// The last [`Instant`] when this action started executing.
+    last_started_time: Option<Instant>,
+    /// The maximum number of instances of this action that can be in queue or executing at
+    /// the same time.
+    ///
+    /// Defaults to one instance when [`None`].
+    max_instances: Option<u32>,
+    /// Number of times this action has been dropped from the queue due to
+    /// [`DefaultRotator::priority_actions_queue_max_depth`].
+    dropped_count: u32,
+}
+
+/// A set of fixed interval priority actions fired back-to-back once all of them are due.
+///
+/// Actions in the window are held back until every other action is due within
+/// [`Self::tolerance`] so that they do not drift apart over time.
+#[derive(Debug)]
+struct BurstWindow {
+    /// The ids of the priority actions in the order they are fired.
+    ids: Vec<u32>,
+    tolerance: Duration,
+    /// Whether the actions are queued to the front of [`DefaultRotator::priority_actions_queue`].
+    boost_priority: bool,
 }
 
 /// Action metadata to help identifying action type.
@@ -122,6 +167,27 @@ enum RotatorAction {
     Linked(LinkedAction),
 }
 
+impl RotatorAction {
+    /// Gets the name of the first action.
+    fn name(&self) -> String {
+        match self {
+            RotatorAction::Single(inner) => inner.to_string(),
+            RotatorAction::Linked(linked) => linked.inner.to_string(),
+        }
+    }
+
+    /// Gets the positions of all actions in order.
+    fn positions(&self) -> Vec<(i32, i32)> {
+        match self {
+            RotatorAction::Single(inner) => inner.position().into_iter().collect::<Vec<_>>(),
+            RotatorAction::Linked(linked) => linked.positions(),
+        }
+        .into_iter()
+        .map(|position| (position.x, position.y))
+        .collect()
+    }
+}
+
 /// A linked list of actions.
 #[derive(Clone, Debug)]
 struct LinkedAction {
@@ -129,34 +195,71 @@ struct LinkedAction {
     next: Option<Box<LinkedAction>>,
 }
 
+impl LinkedAction {
+    /// Gets the positions of this and the following actions in order.
+    fn positions(&self) -> Vec<Position> {
+        let mut positions = vec![];
+        let mut current = Some(self);
+        while let Some(action) = current {
+            positions.extend(action.inner.position());
+            current = action.next.as_deref();
+        }
+
+        positions
+    }
+}
+
 /// The rotator's rotation mode.
 #[derive(Default, Debug)]
 pub enum RotatorMode {
     StartToEnd,
     #[default]
     StartToEndThenReverse,
-    AutoMobbing(MobbingKey, Bound),
+    AutoMobbing(MobbingKey, Bound, AutoMobQuadrants),
     PingPong(MobbingKey, Bound),
+    WeightedRandom,
 }
 
 #[derive(Debug)]
 pub struct RotatorBuildArgs<'a> {
     pub mode: RotatorMode,
     pub actions: &'a [Action],
+    /// The [`InputMacro`]s replayed by [`Action::Macro`] actions.
+    pub input_macros: &'a [InputMacro],
+    /// Tags of actions to disable while there are strangers in the minimap.
+    pub disabled_tags_with_strangers: &'a [String],
+    /// The mob detection class indices [`RotatorMode::AutoMobbing`] prioritizes in order.
+    pub auto_mob_priority_classes: &'a [u32],
+    /// The burst windows of the fixed interval actions.
+    pub burst_windows: &'a [ActionsBurstWindow],
     pub buffs: &'a [(BuffKind, KeyKind)],
     pub familiars: Familiars,
     pub familiar_essence_key: KeyKind,
+    pub feed_pet_key: KeyKind,
+    pub feed_pet_millis: u64,
+    pub feed_pet_count: u32,
     pub elite_boss_behavior: EliteBossBehavior,
     pub elite_boss_behavior_key: KeyKind,
+    /// The keys used by [`EliteBossBehavior::Rotation`] while the elite boss is present.
+    pub elite_boss_rotation: &'a [EliteBossRotationKey],
+    /// Whether [`EliteBossBehavior::Rotation`] moves toward the elite boss before using keys.
+    pub elite_boss_rotation_move_to_boss: bool,
     pub hexa_booster_exchange_condition: ExchangeHexaBoosterCondition,
     pub hexa_booster_exchange_amount: u32,
     pub hexa_booster_exchange_all: bool,
+    pub enable_feeding_pet: bool,
     pub enable_panic_mode: bool,
     pub enable_rune_solving: bool,
     pub enable_transparent_shape_solving: bool,
     pub enable_reset_normal_actions_on_erda: bool,
     pub enable_using_generic_booster: bool,
     pub enable_using_hexa_booster: bool,
+    /// Whether [`RotatorMode::AutoMobbing`] tracks mobs across frames.
+    pub enable_auto_mob_tracking: bool,
+    /// Whether [`RotatorMode::WeightedRandom`] avoids picking the same action twice in a row.
+    pub enable_weighted_random_no_repeat: bool,
+    /// The maximum number of priority actions in queue with `0` being unlimited.
+    pub priority_actions_queue_max_depth: u32,
 }
 
 /// Handles rotating provided [`PlayerAction`]s.
@@ -182,6 +285,37 @@ pub trait Rotator: Debug + 'static {
     /// If [`Operation`] is currently halting, it does not rotate the built actions but only the
     /// side-loaded actions added by [`Self::inject_action`].
     fn rotate_action(&mut self, resources: &Resources, world: &mut World);
+
+    /// Gets the name and the remaining time until the next fixed interval priority action is due.
+    ///
+    /// Actions that are already queued or executing are skipped.
+    fn next_priority_action(&self) -> Option<(String, Duration)>;
+
+    /// Gets the positions of the upcoming normal actions in rotation order.
+    ///
+    /// This includes the remaining actions of the executing linked action followed by one full
+    /// cycle of the built normal actions. Only [`RotatorMode::StartToEnd`] and
+    /// [`RotatorMode::StartToEndThenReverse`] rotate in a predictable order so the cycle is empty
+    /// for other modes.
+    fn pending_normal_positions(&self) -> Vec<(i32, i32)>;
+
+    /// Gets the name and the number of times each priority action was dropped for exceeding
+    /// the maximum queue depth.
+    ///
+    /// Actions that have never been dropped are skipped.
+    fn priority_actions_dropped(&self) -> Vec<(String, u32)>;
+
+    /// Disables user actions having any of `tags` when rotating.
+    ///
+    /// Unlike [`Self::build_actions`], this does not reset the queues and persists across
+    /// rebuilds. Actions already in queue or executing are not affected.
+    fn set_disabled_tags(&mut self, tags: Vec<String>);
+
+    /// Sets whether to only rotate the side-loaded actions added by [`Self::inject_action`] as
+    /// if [`Operation`] is halting.
+    ///
+    /// Used for routines that run their own actions outside of the current map.
+    fn set_suspended(&mut self, suspended: bool);
 }
 
 #[derive(Default, Debug)]
@@ -193,14 +327,45 @@ pub struct DefaultRotator {
     normal_actions_backward: bool,
     normal_actions_reset_on_erda: bool,
     normal_rotate_mode: RotatorMode,
+    /// The weights of [`Self::normal_actions`] keyed by action id used in
+    /// [`RotatorMode::WeightedRandom`].
+    normal_actions_weights: HashMap<u32, u32>,
+    /// The last picked action id in [`RotatorMode::WeightedRandom`].
+    normal_last_weighted_id: Option<u32>,
+    /// Whether [`Self::normal_last_weighted_id`] is excluded from the next pick.
+    normal_weighted_no_repeat: bool,
 
     /// The [`Task`] used when [`Self::normal_rotate_mode`] is [`RotatorMode::AutoMobbing`]
-    auto_mob_task: Option<Task<Result<Vec<Point>>>>,
+    auto_mob_task: Option<Task<Result<Vec<(Point, u32, f32)>>>>,
+    /// The mob detection class indices to pick first in order in [`RotatorMode::AutoMobbing`].
+    auto_mob_priority_classes: Vec<u32>,
     /// Tracks number of times a mob detection has been completed inside the same quad.
     ///
     /// This limits the number of detections can be done inside the same quad as to help player
     /// advances to the next quad.
     auto_mob_quadrant_consecutive_count: Option<(Quadrant, u32)>,
+    /// Number of mobs picked in each [`Quadrant`] indexed by its discriminant.
+    ///
+    /// Used by [`AutoMobQuadrantMode::Even`] to pick the least picked quadrant.
+    auto_mob_quadrant_picks: [u32; 4],
+    /// Tracks detected mobs across frames in [`RotatorMode::AutoMobbing`] if enabled.
+    auto_mob_tracker: Option<ByteTracker>,
+    /// The track id of the mob currently moving to.
+    auto_mob_target_track: Option<u64>,
+    /// The track ids of previously targeted mobs no longer detected.
+    ///
+    /// These mobs are assumed to have died and are avoided as their dying animation can still be
+    /// detected for a moment.
+    auto_mob_dead_tracks: VecDeque<u64>,
+
+    /// The keys, cooldowns and last used instants of [`EliteBossBehavior::Rotation`].
+    elite_boss_rotation: Vec<(KeyKind, u64, Option<Instant>)>,
+    /// Whether [`EliteBossBehavior::Rotation`] moves toward the elite boss.
+    elite_boss_rotation_move_to_boss: bool,
+    /// The [`Task`] detecting whether the elite boss is present and its minimap coordinate.
+    elite_boss_task: Option<Task<Result<(bool, Option<Point>)>>>,
+    /// Whether the elite boss is present and normal actions are paused for the rotation.
+    elite_boss_present: bool,
 
     priority_actions: OrderedHashMap<u32, PriorityAction>,
     /// The currently executing [`RotatorAction::Linked`] action
@@ -214,13 +379,89 @@ pub struct DefaultRotator {
     /// These are actions injected externally and to be executed as appropriate with the current
     /// [`Self::priority_actions_queue`]. These actions are run only once and do not have an ID.
     priority_actions_side_queue: VecDeque<RotatorAction>,
+    /// The maximum length of [`Self::priority_actions_queue`] before the oldest actions are
+    /// dropped.
+    priority_actions_queue_max_depth: Option<usize>,
+    /// Total number of priority actions dropped from [`Self::priority_actions_queue`].
+    priority_actions_dropped_count: u32,
+    /// The burst windows of [`Self::priority_actions`].
+    burst_windows: Vec<BurstWindow>,
+
+    /// The action name and [`ActionNotify`] of user actions to notify when started executing.
+    action_notifies: HashMap<u32, (String, ActionNotify)>,
+    /// The [`ActionTags`] of user actions keyed by action id.
+    action_tags: HashMap<u32, ActionTags>,
+    /// Tags of user actions disabled by [`Rotator::set_disabled_tags`].
+    disabled_tags: Vec<String>,
+    /// Tags of user actions disabled while there are strangers in the minimap.
+    disabled_tags_with_strangers: Vec<String>,
+    /// Whether [`Self::disabled_tags_with_strangers`] are currently disabled.
+    has_stranger: bool,
+    /// Whether only the side-loaded actions are rotated as set by [`Rotator::set_suspended`].
+    suspended: bool,
 }
 
 impl DefaultRotator {
+    #[inline]
+    fn notify_action_started(&self, id: u32) {
+        let Some((name, notify)) = self.action_notifies.get(&id) else {
+            return;
+        };
+
+        info!(target: "rotator", "action {name} started executing");
+        if matches!(notify, ActionNotify::Sound) {
+            let _ = play_notification_sound();
+        }
+    }
+
+    /// Whether the user action `id` has any tag that is currently disabled.
+    #[inline]
+    fn is_action_disabled(&self, id: u32) -> bool {
+        let Some(tags) = self.action_tags.get(&id) else {
+            return false;
+        };
+
+        tags.contains_any(&self.disabled_tags)
+            || (self.has_stranger && tags.contains_any(&self.disabled_tags_with_strangers))
+    }
+
+    /// Whether all the enabled actions of `window` are due within its tolerance.
+    ///
+    /// The window is not ready while any of its actions is still in queue or executing.
+    fn is_burst_window_ready(
+        &self,
+        window: &BurstWindow,
+        player_context: &PlayerContext,
+        now: Instant,
+    ) -> bool {
+        window.ids.iter().copied().all(|id| {
+            if self.is_action_disabled(id) {
+                return true;
+            }
+            if player_context.priority_action_id() == Some(id)
+                || self.priority_actions_queue.contains(&id)
+            {
+                return false;
+            }
+            let Some(action) = self.priority_actions.get(&id) else {
+                return true;
+            };
+            let millis = action
+                .condition_kind
+                .and_then(fixed_action_interval_millis)
+                .unwrap_or_default();
+
+            action.queue_info.last_queued_time.is_none_or(|instant| {
+                instant + Duration::from_millis(millis) <= now + window.tolerance
+            })
+        })
+    }
+
     #[inline]
     fn reset_normal_actions_queue(&mut self) {
         self.normal_index = 0;
         self.normal_queuing_linked_action = None;
+        self.normal_last_weighted_id = None;
     }
 
     /// Rotates the actions inside the [`Self::priority_actions`]
@@ -342,8 +583,18 @@ impl DefaultRotator {
         let has_erda_action = has_erda_action_queuing_or_executing(self, &world.player.context);
         let ids = self.priority_actions.keys().copied().collect::<Vec<_>>();
         let mut did_queue_erda_action = false;
+        let now = Instant::now();
+        let burst_windows_ready = self
+            .burst_windows
+            .iter()
+            .map(|window| self.is_burst_window_ready(window, &world.player.context, now))
+            .collect::<Vec<_>>();
 
         for id in ids {
+            if self.is_action_disabled(id) {
+                continue;
+            }
+
             // Ignores for as long as the action is a linked action that is queuing
             // or executing
             let has_linked_action =
@@ -354,17 +605,24 @@ impl DefaultRotator {
                 Some(ActionCondition::ErdaShowerOffCooldown) => {
                     has_erda_action || has_linked_action
                 }
-                Some(ActionCondition::Linked) | Some(ActionCondition::EveryMillis(_)) | None => {
-                    world
+                Some(ActionCondition::Linked)
+                | Some(ActionCondition::EveryMillis(_))
+                | Some(ActionCondition::Expression(_))
+                | None => {
+                    let executing_count = world
                         .player
                         .context // The player currently executing action
                         .priority_action_id()
                         .is_some_and(|action_id| action_id == id)
-                        || self // The action is in queue
-                            .priority_actions_queue
-                            .iter()
-                            .any(|action_id| *action_id == id)
-                        || has_linked_action
+                        as u32;
+                    let queued_count = self // The action is in queue
+                        .priority_actions_queue
+                        .iter()
+                        .filter(|action_id| **action_id == id)
+                        .count() as u32;
+                    let max_instances = action.queue_info.max_instances.unwrap_or(1).max(1);
+
+                    executing_count + queued_count >= max_instances || has_linked_action
                 }
                 Some(ActionCondition::Any) => unreachable!(),
             };
@@ -373,6 +631,18 @@ impl DefaultRotator {
                 continue;
             }
 
+            // Actions in a burst window are only queued together when the window is ready
+            if let Some(index) = self
+                .burst_windows
+                .iter()
+                .position(|window| window.ids.contains(&id))
+            {
+                if burst_windows_ready[index] {
+                    action.queue_info.last_queued_time = Some(now);
+                }
+                continue;
+            }
+
             let condition_fn = &mut action.condition.0;
             let result = condition_fn(resources, world, &mut action.queue_info);
             match result {
@@ -425,12 +695,73 @@ impl DefaultRotator {
             }
         }
 
+        for (index, window) in self.burst_windows.iter().enumerate() {
+            if !burst_windows_ready[index] {
+                continue;
+            }
+
+            let ids = window
+                .ids
+                .iter()
+                .copied()
+                .filter(|id| !self.is_action_disabled(*id))
+                .collect::<Vec<_>>();
+            debug!(target: "rotator", "burst window firing actions {ids:?}");
+            if window.boost_priority {
+                for id in ids.into_iter().rev() {
+                    self.priority_actions_queue.push_front(id);
+                }
+            } else {
+                self.priority_actions_queue.extend(ids);
+            }
+        }
+
+        self.drop_priority_actions_exceeding_max_depth();
         if did_queue_erda_action && self.normal_actions_reset_on_erda {
             self.reset_normal_actions_queue();
             world.player.context.reset_normal_action();
         }
     }
 
+    /// Drops the oldest actions from [`Self::priority_actions_queue`] until it is within
+    /// [`Self::priority_actions_queue_max_depth`].
+    ///
+    /// Non-queue to front actions are dropped first starting from the front of the queue. Queue
+    /// to front actions are only dropped when there is no other action left and starting from the
+    /// back of the queue since they are pushed to the front.
+    fn drop_priority_actions_exceeding_max_depth(&mut self) {
+        let Some(max_depth) = self.priority_actions_queue_max_depth else {
+            return;
+        };
+
+        while self.priority_actions_queue.len() > max_depth {
+            let index = self
+                .priority_actions_queue
+                .iter()
+                .position(|id| {
+                    self.priority_actions
+                        .get(id)
+                        .is_none_or(|action| !action.queue_to_front)
+                })
+                .unwrap_or(self.priority_actions_queue.len() - 1);
+            let id = self
+                .priority_actions_queue
+                .remove(index)
+                .expect("index in bound");
+
+            self.priority_actions_dropped_count += 1;
+            if let Some(action) = self.priority_actions.get_mut(&id) {
+                action.queue_info.dropped_count += 1;
+                info!(
+                    target: "rotator",
+                    "dropped priority action {id} due to queue depth cap, dropped {} times ({} total)",
+                    action.queue_info.dropped_count,
+                    self.priority_actions_dropped_count
+                );
+            }
+        }
+    }
+
     /// Rotates the actions inside the [`Self::priority_actions_queue`].
     ///
     /// If there is any on-going linked action:
@@ -438,7 +769,7 @@ impl DefaultRotator {
     /// - For priority action, it will rotate and wait until all the actions are executed.
     ///
     /// After that, it will rotate actions inside [`Self::priority_actions_queue`].
-    fn rotate_priority_actions_queue(&mut self, player: &mut PlayerEntity) {
+    fn rotate_priority_actions_queue(&mut self, resources: &Resources, player: &mut PlayerEntity) {
         /// Checks if the player is queuing or executing a normal [`RotatorAction::Linked`] action.
         ///
         /// This prevents [`Self::rotate_priority_actions_queue`] from overriding the normal
@@ -542,6 +873,36 @@ impl DefaultRotator {
                 self.rotate_queuing_linked_action(&mut player.context, true);
             }
         }
+        self.record_priority_action_started(resources, id);
+        self.notify_action_started(id);
+    }
+
+    /// Records the delay of the priority action `id` that just started executing.
+    ///
+    /// Only [`ActionCondition::EveryMillis`] actions are recorded. The delay is how much longer
+    /// than its interval the action took to start again. Delays not less than the interval are
+    /// treated as interruptions such as halting and are not recorded.
+    fn record_priority_action_started(&mut self, resources: &Resources, id: u32) {
+        let Some(action) = self.priority_actions.get_mut(&id) else {
+            return;
+        };
+        let now = Instant::now();
+        let last_started_time = action.queue_info.last_started_time.replace(now);
+        let (Some(ActionCondition::EveryMillis(millis)), Some(last_started_time)) =
+            (action.condition_kind, last_started_time)
+        else {
+            return;
+        };
+
+        let interval = Duration::from_millis(millis);
+        let delay = now
+            .saturating_duration_since(last_started_time)
+            .saturating_sub(interval);
+        if delay < interval {
+            resources
+                .stats
+                .record_action_delay(action.inner.name(), interval, delay);
+        }
     }
 
     fn rotate_auto_mobbing(
@@ -551,8 +912,10 @@ impl DefaultRotator {
         minimap_state: Minimap,
         key: MobbingKey,
         bound: Bound,
+        quadrants: AutoMobQuadrants,
     ) {
-        if player_context.has_normal_action() {
+        // Keeps detecting while moving so that the tracker sees consecutive frames
+        if player_context.has_normal_action() && self.auto_mob_tracker.is_none() {
             return;
         }
 
@@ -568,17 +931,29 @@ impl DefaultRotator {
             bound.into()
         };
 
-        let Update::Ok(points) =
-            update_detection_task(resources, 0, &mut self.auto_mob_task, move |detector| {
-                detector.detect_mobs(idle.bbox, bound, pos)
-            })
-        else {
+        let Update::Ok(points) = update_detection_task_with_priority(
+            resources,
+            TaskPriority::Low,
+            0,
+            &mut self.auto_mob_task,
+            move |detector| detector.detect_mobs(idle.bbox, bound, pos),
+        ) else {
             return;
         };
+        let mobs = self.track_auto_mobs(&points, bound);
+        if player_context.has_normal_action() {
+            return;
+        }
+        resources.stats.record_mob_detections(points.len());
+        resources.stats.record_mob_quadrants(
+            points
+                .iter()
+                .map(|(point, _, _)| Quadrant::from_point(bound, *point)),
+        );
         // FIXME: Collect to a Vec first because `context.rng` needs to be borrowed again.
-        let points = points
-            .iter()
-            .filter_map(|point| {
+        let targets = mobs
+            .into_iter()
+            .filter_map(|(point, track, class)| {
                 let y = idle.bbox.height - point.y;
                 let point = if y <= pos.y || (y - pos.y).abs() <= GRAPPLING_THRESHOLD {
                     Some(Point::new(point.x, y))
@@ -586,15 +961,30 @@ impl DefaultRotator {
                     None
                 };
                 debug!(target: "rotator", "auto mob raw position {point:?}");
-                point.and_then(|point| {
-                    player_context.auto_mob_pick_reachable_y_position(
-                        resources,
-                        minimap_state,
-                        point,
-                    )
-                })
+                point
+                    .and_then(|point| {
+                        player_context.auto_mob_pick_reachable_y_position(
+                            resources,
+                            minimap_state,
+                            point,
+                        )
+                    })
+                    .map(|point| ((point, track), class))
             })
             .collect::<Vec<_>>();
+        let targets = auto_mob_priority_points(targets, &self.auto_mob_priority_classes);
+        let points = targets.iter().map(|(point, _)| *point).collect::<Vec<_>>();
+        let to_quadrant = |point: Point| {
+            Quadrant::from_point(bound, Point::new(point.x, idle.bbox.height - point.y))
+        };
+        // Leaves the quadrant to the peer in duo mode unless there is no other mob
+        let points = match duo::avoided_auto_mob_quadrant() {
+            Some(quadrant) if points.iter().any(|point| to_quadrant(*point) != quadrant) => points
+                .into_iter()
+                .filter(|point| to_quadrant(*point) != quadrant)
+                .collect(),
+            _ => points,
+        };
         let mut use_pathing_point = false;
 
         if let Some(last_quad) = player_context.auto_mob_last_quadrant()
@@ -622,14 +1012,21 @@ impl DefaultRotator {
         let point = if use_pathing_point {
             player_context.auto_mob_pathing_point(resources, minimap_state, bound)
         } else {
-            resources
-                .rng
-                .random_choose(points.into_iter())
+            self.pick_auto_mob_point(resources, points, quadrants, to_quadrant)
                 .unwrap_or_else(|| {
                     is_pathing = true;
                     player_context.auto_mob_pathing_point(resources, minimap_state, bound)
                 })
         };
+        self.auto_mob_target_track = if is_pathing {
+            None
+        } else {
+            targets
+                .iter()
+                .find(|(target, _)| *target == point)
+                .and_then(|(_, track)| *track)
+        };
+        duo::notify_auto_mob_quadrant(to_quadrant(point));
         let key_hold_ticks = (key.key_hold_millis / MS_PER_TICK) as u32;
         let wait_before_ticks = (key.wait_before_millis / MS_PER_TICK) as u32;
         let wait_before_ticks_random_range =
@@ -662,6 +1059,105 @@ impl DefaultRotator {
         );
     }
 
+    /// Picks a random point from `points` in a quadrant balanced according to `quadrants`.
+    fn pick_auto_mob_point(
+        &mut self,
+        resources: &Resources,
+        points: Vec<Point>,
+        quadrants: AutoMobQuadrants,
+        to_quadrant: impl Fn(Point) -> Quadrant,
+    ) -> Option<Point> {
+        let has_points =
+            |quadrant: Quadrant| points.iter().any(|point| to_quadrant(*point) == quadrant);
+        let quadrant = match quadrants.mode {
+            AutoMobQuadrantMode::Any => None,
+            AutoMobQuadrantMode::Weighted => {
+                let weights = Quadrant::iter()
+                    .map(|quadrant| {
+                        if has_points(quadrant) {
+                            auto_mob_quadrant_weight(quadrants, quadrant) as u64
+                        } else {
+                            0
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let total = weights.iter().sum::<u64>();
+                if total == 0 {
+                    None
+                } else {
+                    weighted_index(&weights, resources.rng.random_range(0..total))
+                        .and_then(|index| Quadrant::iter().nth(index))
+                }
+            }
+            AutoMobQuadrantMode::Even => Quadrant::iter()
+                .filter(|quadrant| has_points(*quadrant))
+                .min_by_key(|quadrant| self.auto_mob_quadrant_picks[*quadrant as usize]),
+        };
+        let points = points
+            .iter()
+            .copied()
+            .filter(|point| quadrant.is_none_or(|quadrant| to_quadrant(*point) == quadrant))
+            .collect::<Vec<_>>();
+        let point = resources.rng.random_choose(points.into_iter())?;
+        self.auto_mob_quadrant_picks[to_quadrant(point) as usize] += 1;
+
+        Some(point)
+    }
+
+    /// Updates [`Self::auto_mob_tracker`] with the detected `mobs` if tracking is enabled.
+    ///
+    /// Returns the mob points with their track id and class index. The points are the predicted
+    /// positions of the tracked mobs clamped to `bound` excluding mobs assumed to have died.
+    fn track_auto_mobs(
+        &mut self,
+        mobs: &[(Point, u32, f32)],
+        bound: Rect,
+    ) -> Vec<(Point, Option<u64>, u32)> {
+        let Some(tracker) = self.auto_mob_tracker.as_mut() else {
+            return mobs
+                .iter()
+                .map(|(point, class, _)| (*point, None, *class))
+                .collect();
+        };
+        let tracks = tracker.update(
+            mobs.iter()
+                .map(|(point, _, _)| Detection::new(auto_mob_track_rect(*point)))
+                .collect(),
+        );
+
+        if let Some(track) = self.auto_mob_target_track
+            && tracks.iter().all(|tracked| tracked.track_id() != track)
+        {
+            debug!(target: "rotator", "auto mob target track {track} is no longer detected");
+            self.auto_mob_target_track = None;
+            if self.auto_mob_dead_tracks.len() >= AUTO_MOB_DEAD_TRACKS_MAX_LEN {
+                self.auto_mob_dead_tracks.pop_front();
+            }
+            self.auto_mob_dead_tracks.push_back(track);
+        }
+
+        tracks
+            .into_iter()
+            .filter(|track| !self.auto_mob_dead_tracks.contains(&track.track_id()))
+            .filter_map(|track| {
+                // Tracks returned are updated with a detection in this frame
+                let (_, class, _) = mobs
+                    .iter()
+                    .find(|(point, _, _)| auto_mob_track_rect(*point) == track.rect())?;
+                let rect = track.kalman_rect();
+                let (vx, vy) = track.kalman_velocity();
+                let x = rect.x + rect.width / 2 + (vx * AUTO_MOB_TRACK_LEAD_UPDATES) as i32;
+                let y = rect.y + rect.height / 2 + (vy * AUTO_MOB_TRACK_LEAD_UPDATES) as i32;
+                let point = Point::new(
+                    x.clamp(bound.x, bound.x + bound.width),
+                    y.clamp(bound.y, bound.y + bound.height),
+                );
+
+                Some((point, Some(track.track_id()), *class))
+            })
+            .collect()
+    }
+
     fn rotate_ping_pong(
         &mut self,
         player_context: &mut PlayerContext,
@@ -726,6 +1222,9 @@ impl DefaultRotator {
         debug_assert!(self.normal_index < self.normal_actions.len());
         let (id, action) = self.normal_actions[self.normal_index].clone();
         self.normal_index = (self.normal_index + 1) % self.normal_actions.len();
+        if self.is_action_disabled(id) {
+            return;
+        }
         match action {
             RotatorAction::Single(action) => {
                 player_context.set_normal_action(Some(id), action);
@@ -735,6 +1234,7 @@ impl DefaultRotator {
                 self.rotate_queuing_linked_action(player_context, false);
             }
         }
+        self.notify_action_started(id);
     }
 
     fn rotate_start_to_end_then_reverse(&mut self, player_context: &mut PlayerContext) {
@@ -761,6 +1261,67 @@ impl DefaultRotator {
         let (id, action) = self.normal_actions[i].clone();
 
         self.normal_index = (self.normal_index + 1) % len;
+        if self.is_action_disabled(id) {
+            return;
+        }
+        match action {
+            RotatorAction::Single(action) => {
+                player_context.set_normal_action(Some(id), action);
+            }
+            RotatorAction::Linked(action) => {
+                self.normal_queuing_linked_action = Some((id, Box::new(action)));
+                self.rotate_queuing_linked_action(player_context, false);
+            }
+        }
+        self.notify_action_started(id);
+    }
+
+    /// Picks the next normal action randomly weighted by [`Self::normal_actions_weights`].
+    ///
+    /// If [`Self::normal_weighted_no_repeat`] is enabled, the last picked action is excluded when
+    /// there are other actions so that the same action is not executed twice in a row.
+    fn rotate_weighted_random(
+        &mut self,
+        resources: &Resources,
+        player_context: &mut PlayerContext,
+    ) {
+        if player_context.has_normal_action() || self.normal_actions.is_empty() {
+            return;
+        }
+        if self.rotate_queuing_linked_action(player_context, false) {
+            return;
+        }
+
+        let enabled_count = self
+            .normal_actions
+            .iter()
+            .filter(|(id, _)| !self.is_action_disabled(*id))
+            .count();
+        let weights = self
+            .normal_actions
+            .iter()
+            .map(|(id, _)| {
+                let excluded = self.is_action_disabled(*id)
+                    || (self.normal_weighted_no_repeat
+                        && enabled_count > 1
+                        && Some(*id) == self.normal_last_weighted_id);
+                if excluded {
+                    0
+                } else {
+                    self.normal_actions_weights.get(id).copied().unwrap_or(1) as u64
+                }
+            })
+            .collect::<Vec<_>>();
+        let total = weights.iter().sum::<u64>();
+        if total == 0 {
+            return;
+        }
+        let Some(index) = weighted_index(&weights, resources.rng.random_range(0..total)) else {
+            return;
+        };
+
+        let (id, action) = self.normal_actions[index].clone();
+        self.normal_last_weighted_id = Some(id);
         match action {
             RotatorAction::Single(action) => {
                 player_context.set_normal_action(Some(id), action);
@@ -770,6 +1331,7 @@ impl DefaultRotator {
                 self.rotate_queuing_linked_action(player_context, false);
             }
         }
+        self.notify_action_started(id);
     }
 
     #[inline]
@@ -796,6 +1358,92 @@ impl DefaultRotator {
         true
     }
 
+    /// Rotates the keys of [`EliteBossBehavior::Rotation`] while the elite boss is present.
+    ///
+    /// The keys off cooldown are pushed to [`Self::priority_actions_side_queue`] as a chain once
+    /// the previous chain has completed. When moving toward the elite boss is enabled, the first
+    /// key of the chain is used at the approximated elite boss position.
+    fn rotate_elite_boss(&mut self, resources: &Resources, world: &World) {
+        if self.elite_boss_rotation.is_empty() || resources.detector.is_none() {
+            return;
+        }
+
+        let minimap_bbox = match world.minimap.state {
+            Minimap::Detecting => None,
+            Minimap::Idle(idle) => Some(idle.bbox),
+        };
+        let player_pos = world.player.context.last_known_pos;
+        let move_to_boss = self.elite_boss_rotation_move_to_boss;
+        let boss = match update_detection_task(
+            resources,
+            1000,
+            &mut self.elite_boss_task,
+            move |detector| {
+                if !detector.detect_elite_boss_bar() {
+                    return Ok((false, None));
+                }
+                let boss = minimap_bbox
+                    .zip(player_pos)
+                    .filter(|_| move_to_boss)
+                    .and_then(|(bbox, pos)| detector.detect_elite_boss(bbox, pos).ok());
+
+                Ok((true, boss))
+            },
+        ) {
+            Update::Ok((present, boss)) => {
+                if present != self.elite_boss_present {
+                    info!(target: "rotator", "elite boss rotation active: {present}");
+                }
+                self.elite_boss_present = present;
+                boss
+            }
+            Update::Err(_) => {
+                self.elite_boss_present = false;
+                return;
+            }
+            Update::Pending => return,
+        };
+        if !self.elite_boss_present
+            || !self.priority_actions_side_queue.is_empty()
+            || has_side_loaded_action_executing(&world.player.context)
+        {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut position = boss.zip(minimap_bbox).map(|(point, bbox)| Position {
+            x: point.x,
+            x_random_range: 0,
+            y: bbox.height - point.y,
+            allow_adjusting: false,
+        });
+        for (key, cooldown_millis, last_used) in self.elite_boss_rotation.iter_mut() {
+            if last_used
+                .is_some_and(|instant| now < instant + Duration::from_millis(*cooldown_millis))
+            {
+                continue;
+            }
+
+            *last_used = Some(now);
+            self.priority_actions_side_queue
+                .push_back(RotatorAction::Single(PlayerAction::Key(Key {
+                    key: *key,
+                    key_hold_ticks: 0,
+                    key_hold_buffered_to_wait_after: false,
+                    link_key: LinkKeyKind::None,
+                    count: 1,
+                    position: position.take(),
+                    direction: ActionKeyDirection::Any,
+                    with: ActionKeyWith::Stationary,
+                    wait_before_use_ticks: 10,
+                    wait_before_use_ticks_random_range: 0,
+                    wait_after_use_ticks: 10,
+                    wait_after_use_ticks_random_range: 0,
+                    wait_after_buffered: WaitAfterBuffered::None,
+                })));
+        }
+    }
+
     #[inline]
     fn rotate_side_priority_action(&mut self, player_context: &mut PlayerContext) -> bool {
         if let Some(action) = self.priority_actions_side_queue.pop_front() {
@@ -820,28 +1468,60 @@ impl Rotator for DefaultRotator {
         let RotatorBuildArgs {
             mode,
             actions,
+            input_macros,
+            disabled_tags_with_strangers,
+            auto_mob_priority_classes,
+            burst_windows,
             buffs,
             familiars,
             familiar_essence_key,
+            feed_pet_key,
+            feed_pet_millis,
+            feed_pet_count,
             elite_boss_behavior,
             elite_boss_behavior_key,
+            elite_boss_rotation,
+            elite_boss_rotation_move_to_boss,
             hexa_booster_exchange_condition,
             hexa_booster_exchange_amount,
             hexa_booster_exchange_all,
+            enable_feeding_pet,
             enable_panic_mode,
             enable_rune_solving,
             enable_transparent_shape_solving,
             enable_reset_normal_actions_on_erda,
             enable_using_generic_booster,
             enable_using_hexa_booster,
+            enable_auto_mob_tracking,
+            enable_weighted_random_no_repeat,
+            priority_actions_queue_max_depth,
         } = args;
+        self.auto_mob_tracker = enable_auto_mob_tracking.then(|| ByteTracker::new(FPS));
         self.reset_queue();
+        self.priority_actions_queue_max_depth = (priority_actions_queue_max_depth > 0)
+            .then_some(priority_actions_queue_max_depth as usize);
         self.normal_actions.clear();
+        self.normal_actions_weights.clear();
         self.normal_rotate_mode = mode;
+        self.normal_weighted_no_repeat = enable_weighted_random_no_repeat;
         self.normal_actions_reset_on_erda = enable_reset_normal_actions_on_erda;
         self.priority_actions.clear();
+        self.action_notifies.clear();
+        self.action_tags.clear();
+        self.burst_windows.clear();
+        self.disabled_tags_with_strangers = disabled_tags_with_strangers.to_vec();
+        self.auto_mob_priority_classes = auto_mob_priority_classes.to_vec();
+        self.elite_boss_rotation.clear();
+        self.elite_boss_rotation_move_to_boss = elite_boss_rotation_move_to_boss;
 
         // Low priority
+        if enable_feeding_pet {
+            self.priority_actions.insert(
+                next_action_id(),
+                feed_pet_priority_action(feed_pet_key, feed_pet_millis, feed_pet_count),
+            );
+        }
+
         if enable_using_generic_booster {
             self.priority_actions.insert(
                 next_action_id(),
@@ -886,32 +1566,115 @@ impl Rotator for DefaultRotator {
         while i < actions.len() {
             let action = actions[i];
             let condition = action.condition();
-            let queue_to_front = match action {
-                Action::Move(_) => false,
-                Action::Key(ActionKey { queue_to_front, .. }) => queue_to_front.unwrap_or_default(),
+            let weight = action.weight();
+            let tags = action.tags();
+            let (queue_to_front, max_queued_instances, notify) = match action {
+                Action::Move(_) => (false, None, None),
+                Action::Macro(ActionMacro { queue_to_front, .. }) => {
+                    (queue_to_front.unwrap_or_default(), None, None)
+                }
+                Action::Key(ActionKey {
+                    key,
+                    queue_to_front,
+                    max_queued_instances,
+                    notify,
+                    ..
+                }) => (
+                    queue_to_front.unwrap_or_default(),
+                    max_queued_instances,
+                    (notify != ActionNotify::None).then(|| (format!("#{} {key}", i + 1), notify)),
+                ),
             };
-            let (action, offset) = rotator_action(action, i, actions);
+            let (action, offset) = rotator_action(action, i, actions, input_macros);
             debug_assert!(i != 0 || !matches!(condition, ActionCondition::Linked));
             // Should not move i below the match because it could cause
             // infinite loop due to auto mobbing ignoring Any condition
             i += offset;
             match condition {
                 ActionCondition::EveryMillis(_) | ActionCondition::ErdaShowerOffCooldown => {
+                    let id = next_action_id();
+                    self.priority_actions.insert(
+                        id,
+                        priority_action(action, condition, queue_to_front, max_queued_instances),
+                    );
+                    if let Some(notify) = notify {
+                        self.action_notifies.insert(id, notify);
+                    }
+                    self.action_tags.insert(id, tags);
+                }
+                ActionCondition::Expression(text) => {
+                    let expression = match text.as_str().parse::<Expression>() {
+                        Ok(expression) => expression,
+                        Err(err) => {
+                            warn!(
+                                target: "rotator",
+                                "skipping action with invalid expression {text:?}: {err}"
+                            );
+                            continue;
+                        }
+                    };
+                    let id = next_action_id();
                     self.priority_actions.insert(
-                        next_action_id(),
-                        priority_action(action, condition, queue_to_front),
+                        id,
+                        expression_priority_action(
+                            action,
+                            condition,
+                            expression,
+                            queue_to_front,
+                            max_queued_instances,
+                        ),
                     );
+                    if let Some(notify) = notify {
+                        self.action_notifies.insert(id, notify);
+                    }
+                    self.action_tags.insert(id, tags);
                 }
                 ActionCondition::Any => {
-                    if matches!(self.normal_rotate_mode, RotatorMode::AutoMobbing(_, _)) {
+                    if matches!(self.normal_rotate_mode, RotatorMode::AutoMobbing(_, _, _)) {
                         continue;
                     }
-                    self.normal_actions.push((next_action_id(), action))
+                    let id = next_action_id();
+                    self.normal_actions.push((id, action));
+                    self.normal_actions_weights.insert(id, weight);
+                    if let Some(notify) = notify {
+                        self.action_notifies.insert(id, notify);
+                    }
+                    self.action_tags.insert(id, tags);
                 }
                 ActionCondition::Linked => unreachable!(),
             }
         }
 
+        for window in burst_windows {
+            let tag = [window.tag.clone()];
+            // Ids are increasing in the order of the actions
+            let mut ids = self
+                .action_tags
+                .iter()
+                .filter(|(id, tags)| {
+                    tags.contains_any(&tag)
+                        && self.priority_actions.get(id).is_some_and(|action| {
+                            matches!(action.condition_kind, Some(ActionCondition::EveryMillis(_)))
+                        })
+                        && !self
+                            .burst_windows
+                            .iter()
+                            .any(|window| window.ids.contains(id))
+                })
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+            if ids.is_empty() {
+                continue;
+            }
+            ids.sort_unstable();
+
+            self.burst_windows.push(BurstWindow {
+                ids,
+                tolerance: Duration::from_millis(window.tolerance_millis),
+                boost_priority: window.boost_priority,
+            });
+        }
+
         // High priority
         if enable_rune_solving {
             self.priority_actions
@@ -936,6 +1699,12 @@ impl Rotator for DefaultRotator {
                     elite_boss_use_key_priority_action(elite_boss_behavior_key),
                 );
             }
+            EliteBossBehavior::Rotation => {
+                self.elite_boss_rotation = elite_boss_rotation
+                    .iter()
+                    .map(|key| (key.key.into(), key.cooldown_millis, None))
+                    .collect();
+            }
         }
 
         if enable_panic_mode {
@@ -969,6 +1738,14 @@ impl Rotator for DefaultRotator {
         self.priority_queuing_linked_action = None;
         self.auto_mob_task = None;
         self.auto_mob_quadrant_consecutive_count = None;
+        self.auto_mob_quadrant_picks = [0; 4];
+        if self.auto_mob_tracker.is_some() {
+            self.auto_mob_tracker = Some(ByteTracker::new(FPS));
+        }
+        self.auto_mob_target_track = None;
+        self.auto_mob_dead_tracks.clear();
+        self.elite_boss_task = None;
+        self.elite_boss_present = false;
     }
 
     #[inline]
@@ -977,40 +1754,198 @@ impl Rotator for DefaultRotator {
             .push_back(RotatorAction::Single(action));
     }
 
-    #[inline]
-    fn rotate_action(&mut self, resources: &Resources, world: &mut World) {
-        if resources.operation.halting() {
-            if !has_side_loaded_action_executing(&world.player.context) {
-                self.rotate_side_priority_action(&mut world.player.context);
-            }
-            return;
-        }
+    fn next_priority_action(&self) -> Option<(String, Duration)> {
+        let now = Instant::now();
 
-        self.rotate_priority_actions(resources, world);
-        self.rotate_priority_actions_queue(&mut world.player);
-
-        match self.normal_rotate_mode {
-            RotatorMode::StartToEnd => self.rotate_start_to_end(&mut world.player.context),
-            RotatorMode::StartToEndThenReverse => {
-                self.rotate_start_to_end_then_reverse(&mut world.player.context)
-            }
-            RotatorMode::AutoMobbing(key, bound) => self.rotate_auto_mobbing(
-                resources,
-                &mut world.player.context,
-                world.minimap.state,
-                key,
-                bound,
-            ),
-            RotatorMode::PingPong(key, bound) => {
-                self.rotate_ping_pong(&mut world.player.context, world.minimap.state, key, bound)
-            }
-        }
+        self.priority_actions
+            .values()
+            .filter(|action| !action.queue_info.ignoring)
+            .filter_map(|action| {
+                let millis = fixed_action_interval_millis(action.condition_kind?)?;
+                let due = action
+                    .queue_info
+                    .last_queued_time
+                    .map_or(now, |instant| instant + Duration::from_millis(millis));
+                Some((action.inner.name(), due.saturating_duration_since(now)))
+            })
+            .min_by_key(|(_, remaining)| *remaining)
     }
-}
 
-#[inline]
-fn has_side_loaded_action_executing(player_context: &PlayerContext) -> bool {
-    player_context.has_priority_action() && player_context.priority_action_id().is_none()
+    fn pending_normal_positions(&self) -> Vec<(i32, i32)> {
+        let mut positions = self
+            .normal_queuing_linked_action
+            .as_ref()
+            .map(|(_, action)| action.positions())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|position| (position.x, position.y))
+            .collect::<Vec<_>>();
+        let len = self.normal_actions.len();
+        if len == 0 {
+            return positions;
+        }
+
+        let mut index = self.normal_index;
+        let mut backward = self.normal_actions_backward;
+        for _ in 0..len {
+            let i = match self.normal_rotate_mode {
+                RotatorMode::StartToEnd => index,
+                RotatorMode::StartToEndThenReverse => {
+                    if index + 1 == len {
+                        backward = !backward;
+                        index = 0;
+                    }
+                    if backward {
+                        (len - index).saturating_sub(1)
+                    } else {
+                        index
+                    }
+                }
+                RotatorMode::AutoMobbing(_, _, _)
+                | RotatorMode::PingPong(_, _)
+                | RotatorMode::WeightedRandom => break,
+            };
+            index = (index + 1) % len;
+
+            let (id, action) = &self.normal_actions[i];
+            if !self.is_action_disabled(*id) {
+                positions.extend(action.positions());
+            }
+        }
+
+        positions
+    }
+
+    fn priority_actions_dropped(&self) -> Vec<(String, u32)> {
+        self.priority_actions
+            .values()
+            .filter(|action| action.queue_info.dropped_count > 0)
+            .map(|action| (action.inner.name(), action.queue_info.dropped_count))
+            .collect()
+    }
+
+    fn set_disabled_tags(&mut self, tags: Vec<String>) {
+        info!(target: "rotator", "disabling actions with tags {tags:?}");
+        self.disabled_tags = tags;
+    }
+
+    fn set_suspended(&mut self, suspended: bool) {
+        info!(target: "rotator", "rotating only side-loaded actions: {suspended}");
+        self.suspended = suspended;
+    }
+
+    #[inline]
+    fn rotate_action(&mut self, resources: &Resources, world: &mut World) {
+        if resources.operation.halting() || self.suspended {
+            if !has_side_loaded_action_executing(&world.player.context) {
+                self.rotate_side_priority_action(&mut world.player.context);
+            }
+            return;
+        }
+
+        self.has_stranger = match world.minimap.state {
+            Minimap::Detecting => false,
+            Minimap::Idle(idle) => idle.stranger_count() > 0,
+        };
+
+        let had_normal_action = world.player.context.has_normal_action();
+        let had_priority_action = world.player.context.has_priority_action();
+        self.rotate_elite_boss(resources, world);
+        self.rotate_priority_actions(resources, world);
+        self.rotate_priority_actions_queue(resources, &mut world.player);
+
+        // Normal actions resume after the elite boss is gone
+        if !self.elite_boss_present {
+            match self.normal_rotate_mode {
+                RotatorMode::StartToEnd => self.rotate_start_to_end(&mut world.player.context),
+                RotatorMode::StartToEndThenReverse => {
+                    self.rotate_start_to_end_then_reverse(&mut world.player.context)
+                }
+                RotatorMode::AutoMobbing(key, bound, quadrants) => self.rotate_auto_mobbing(
+                    resources,
+                    &mut world.player.context,
+                    world.minimap.state,
+                    key,
+                    bound,
+                    quadrants,
+                ),
+                RotatorMode::PingPong(key, bound) => self.rotate_ping_pong(
+                    &mut world.player.context,
+                    world.minimap.state,
+                    key,
+                    bound,
+                ),
+                RotatorMode::WeightedRandom => {
+                    self.rotate_weighted_random(resources, &mut world.player.context)
+                }
+            }
+        }
+
+        let started_actions = [
+            !had_normal_action && world.player.context.has_normal_action(),
+            !had_priority_action && world.player.context.has_priority_action(),
+        ];
+        resources.stats.record_actions(
+            started_actions
+                .into_iter()
+                .filter(|started| *started)
+                .count() as u32,
+        );
+    }
+}
+
+/// Keeps only the points of the first class in `priority_classes` having any point.
+///
+/// All points are kept when none of `priority_classes` has any point.
+fn auto_mob_priority_points<T>(points: Vec<(T, u32)>, priority_classes: &[u32]) -> Vec<T> {
+    let priority_class = priority_classes
+        .iter()
+        .find(|class| points.iter().any(|(_, point_class)| point_class == *class));
+
+    points
+        .into_iter()
+        .filter(|(_, class)| priority_class.is_none_or(|priority_class| class == priority_class))
+        .map(|(point, _)| point)
+        .collect()
+}
+
+/// Gets the box around a mob `point` on the minimap used for tracking.
+#[inline]
+fn auto_mob_track_rect(point: Point) -> Rect {
+    Rect::new(
+        point.x - AUTO_MOB_TRACK_SIZE / 2,
+        point.y - AUTO_MOB_TRACK_SIZE / 2,
+        AUTO_MOB_TRACK_SIZE,
+        AUTO_MOB_TRACK_SIZE,
+    )
+}
+
+#[inline]
+fn auto_mob_quadrant_weight(quadrants: AutoMobQuadrants, quadrant: Quadrant) -> u32 {
+    match quadrant {
+        Quadrant::TopLeft => quadrants.top_left_weight,
+        Quadrant::TopRight => quadrants.top_right_weight,
+        Quadrant::BottomRight => quadrants.bottom_right_weight,
+        Quadrant::BottomLeft => quadrants.bottom_left_weight,
+    }
+}
+
+/// Finds the index whose cumulative weight range of `weights` contains `pick`.
+#[inline]
+fn weighted_index(weights: &[u64], mut pick: u64) -> Option<usize> {
+    weights.iter().position(|weight| {
+        if pick < *weight {
+            true
+        } else {
+            pick -= weight;
+            false
+        }
+    })
+}
+
+#[inline]
+fn has_side_loaded_action_executing(player_context: &PlayerContext) -> bool {
+    player_context.has_priority_action() && player_context.priority_action_id().is_none()
 }
 
 /// Creates a [`RotatorAction`] with `start_action` as the initial action
@@ -1023,10 +1958,14 @@ fn rotator_action(
     start_action: Action,
     start_index: usize,
     actions: &[Action],
+    input_macros: &[InputMacro],
 ) -> (RotatorAction, usize) {
     if start_index == actions.len() - 1 {
         // Last action cannot be a linked action
-        return (RotatorAction::Single(start_action.into()), 1);
+        return (
+            RotatorAction::Single(player_action(start_action, input_macros)),
+            1,
+        );
     }
     if start_index + 1 < actions.len() {
         match actions[start_index + 1] {
@@ -1037,12 +1976,21 @@ fn rotator_action(
             | Action::Key(ActionKey {
                 condition: ActionCondition::Linked,
                 ..
+            })
+            | Action::Macro(ActionMacro {
+                condition: ActionCondition::Linked,
+                ..
             }) => (),
-            _ => return (RotatorAction::Single(start_action.into()), 1),
+            _ => {
+                return (
+                    RotatorAction::Single(player_action(start_action, input_macros)),
+                    1,
+                );
+            }
         }
     }
     let mut head = LinkedAction {
-        inner: start_action.into(),
+        inner: player_action(start_action, input_macros),
         next: None,
     };
     let mut current = &mut head;
@@ -1056,9 +2004,13 @@ fn rotator_action(
             | Action::Key(ActionKey {
                 condition: ActionCondition::Linked,
                 ..
+            })
+            | Action::Macro(ActionMacro {
+                condition: ActionCondition::Linked,
+                ..
             }) => {
                 let action = LinkedAction {
-                    inner: (*action).into(),
+                    inner: player_action(*action, input_macros),
                     next: None,
                 };
                 current.next = Some(Box::new(action));
@@ -1071,11 +2023,35 @@ fn rotator_action(
     (RotatorAction::Linked(head), offset)
 }
 
+/// Converts `action` to [`PlayerAction`] with the keys of [`Action::Macro`] resolved from
+/// `input_macros`.
+///
+/// An [`Action::Macro`] without a matching [`InputMacro`] replays no key.
+#[inline]
+fn player_action(action: Action, input_macros: &[InputMacro]) -> PlayerAction {
+    match action {
+        Action::Macro(ActionMacro { macro_id, .. }) => {
+            let input_macro = macro_id.and_then(|id| {
+                input_macros
+                    .iter()
+                    .find(|input_macro| input_macro.id == Some(id))
+            });
+            if input_macro.is_none() {
+                warn!(target: "rotator", "input macro {macro_id:?} not found");
+            }
+
+            PlayerAction::Macro(input_macro.map(Macro::from_input_macro).unwrap_or_default())
+        }
+        action => action.into(),
+    }
+}
+
 #[inline]
 fn priority_action(
     action: RotatorAction,
     condition: ActionCondition,
     queue_to_front: bool,
+    max_queued_instances: Option<u32>,
 ) -> PriorityAction {
     debug_assert_matches!(
         condition,
@@ -1093,7 +2069,45 @@ fn priority_action(
         condition_kind: Some(condition),
         metadata: None,
         queue_to_front,
-        queue_info: PriorityActionQueueInfo::default(),
+        queue_info: PriorityActionQueueInfo {
+            max_instances: max_queued_instances,
+            ..PriorityActionQueueInfo::default()
+        },
+    }
+}
+
+/// Creates a priority action that queues whenever `expression` evaluates to true.
+///
+/// The expression is re-evaluated at most once every [`EXPRESSION_ACTION_INTERVAL_MILLIS`] so
+/// that an expression staying true does not flood the queue.
+fn expression_priority_action(
+    action: RotatorAction,
+    condition: ActionCondition,
+    expression: Expression,
+    queue_to_front: bool,
+    max_queued_instances: Option<u32>,
+) -> PriorityAction {
+    debug_assert_matches!(condition, ActionCondition::Expression(_));
+    PriorityAction {
+        inner: action,
+        condition: Condition(Box::new(move |_, world, info| {
+            if at_least_millis_passed_since(
+                info.last_queued_time,
+                EXPRESSION_ACTION_INTERVAL_MILLIS,
+            ) && expression.evaluate(world)
+            {
+                ConditionResult::Queue
+            } else {
+                ConditionResult::Skip
+            }
+        })),
+        condition_kind: Some(condition),
+        metadata: None,
+        queue_to_front,
+        queue_info: PriorityActionQueueInfo {
+            max_instances: max_queued_instances,
+            ..PriorityActionQueueInfo::default()
+        },
     }
 }
 
@@ -1152,6 +2166,44 @@ fn familiar_essence_replenish_priority_action(key: KeyKind) -> PriorityAction {
     }
 }
 
+/// Creates a [`PlayerAction::Key`] priority action that feeds pets every `millis`.
+///
+/// Unlike other built-in actions, this action is queued to the back so that it does not interrupt
+/// the currently queued actions since feeding pets is not time-sensitive.
+#[inline]
+fn feed_pet_priority_action(key: KeyKind, millis: u64, count: u32) -> PriorityAction {
+    let condition = ActionCondition::EveryMillis(millis);
+
+    PriorityAction {
+        condition: Condition(Box::new(move |_, world, info| {
+            if should_queue_fixed_action(world, info.last_queued_time, condition) {
+                ConditionResult::Queue
+            } else {
+                ConditionResult::Skip
+            }
+        })),
+        condition_kind: Some(condition),
+        metadata: None,
+        inner: RotatorAction::Single(PlayerAction::Key(Key {
+            key,
+            key_hold_ticks: 0,
+            key_hold_buffered_to_wait_after: false,
+            link_key: LinkKeyKind::None,
+            count: count.max(1),
+            position: None,
+            direction: ActionKeyDirection::Any,
+            with: ActionKeyWith::Any,
+            wait_before_use_ticks: 10,
+            wait_before_use_ticks_random_range: 0,
+            wait_after_use_ticks: 10,
+            wait_after_use_ticks_random_range: 0,
+            wait_after_buffered: WaitAfterBuffered::None,
+        })),
+        queue_to_front: false,
+        queue_info: PriorityActionQueueInfo::default(),
+    }
+}
+
 #[inline]
 fn familiars_swap_priority_action(swap: FamiliarsSwap, swap_check_millis: u64) -> PriorityAction {
     PriorityAction {
@@ -1186,25 +2238,42 @@ fn familiars_swap_priority_action(swap: FamiliarsSwap, swap_check_millis: u64) -
 /// - The minimap is in the [`Minimap::Idle`] state.
 /// - A rune is present on the minimap.
 /// - The player currently has no rune buff.
+/// - It is this instance's turn to solve the rune in duo mode.
 #[inline]
 fn solve_rune_priority_action() -> PriorityAction {
+    let mut rune_appeared = None::<Instant>;
+    let mut solving = false;
+
     PriorityAction {
-        condition: Condition(Box::new(|_, world, info| {
+        condition: Condition(Box::new(move |_, world, info| {
             if world.player.context.is_validating_rune() {
                 return ConditionResult::Ignore;
             }
 
+            let has_rune_buff = !matches!(world.buffs[BuffKind::Rune].state, Buff::No);
+            if solving && has_rune_buff {
+                solving = false;
+                duo::notify_rune_solved();
+            }
+
             if !at_least_millis_passed_since(info.last_queued_time, 10000) {
                 return ConditionResult::Skip;
             }
 
             if let Minimap::Idle(idle) = world.minimap.state
                 && idle.rune().is_some()
-                && matches!(world.buffs[BuffKind::Rune].state, Buff::No)
+                && !has_rune_buff
             {
+                let appeared = *rune_appeared.get_or_insert_with(Instant::now);
+                if !duo::is_rune_turn(appeared) {
+                    return ConditionResult::Skip;
+                }
+
+                solving = true;
                 return ConditionResult::Queue;
             }
 
+            rune_appeared = None;
             ConditionResult::Skip
         })),
         condition_kind: None,
@@ -1248,6 +2317,7 @@ fn solve_transparent_shape_priority_action() -> PriorityAction {
 /// - Enough time has passed since the last queue attempt.
 /// - The minimap is in the [`Minimap::Idle`] state.
 /// - The specified buff is currently missing.
+/// - The peer has not just cast the same buff in duo mode.
 #[inline]
 fn buff_priority_action(buff: BuffKind, key: KeyKind) -> PriorityAction {
     macro_rules! skip_if_has_buff {
@@ -1297,11 +2367,12 @@ fn buff_priority_action(buff: BuffKind, key: KeyKind) -> PriorityAction {
                 _ => (),
             }
 
-            if matches!(world.buffs[buff].state, Buff::No) {
-                ConditionResult::Queue
-            } else {
-                ConditionResult::Skip
+            if !matches!(world.buffs[buff].state, Buff::No) || duo::should_stagger_buff(buff) {
+                return ConditionResult::Skip;
             }
+
+            duo::notify_buff_cast(buff);
+            ConditionResult::Queue
         })),
         condition_kind: None,
         inner: RotatorAction::Single(PlayerAction::Key(Key {
@@ -1345,6 +2416,7 @@ fn panic_priority_action() -> PriorityAction {
         condition_kind: None,
         inner: RotatorAction::Single(PlayerAction::Panic(Panic {
             to: PanicTo::Channel,
+            skip_channels: 0,
         })),
         metadata: None,
         queue_to_front: true,
@@ -1367,6 +2439,7 @@ fn elite_boss_change_channel_priority_action() -> PriorityAction {
         condition_kind: None,
         inner: RotatorAction::Single(PlayerAction::Panic(Panic {
             to: PanicTo::Channel,
+            skip_channels: 0,
         })),
         metadata: None,
         queue_to_front: true,
@@ -1573,11 +2646,7 @@ fn should_queue_fixed_action(
     last_queued_time: Option<Instant>,
     condition: ActionCondition,
 ) -> bool {
-    let millis_should_passed = match condition {
-        ActionCondition::EveryMillis(millis) => millis as u128,
-        ActionCondition::ErdaShowerOffCooldown => 20000,
-        ActionCondition::Linked | ActionCondition::Any => unreachable!(),
-    };
+    let millis_should_passed = fixed_action_interval_millis(condition).unwrap() as u128;
     if !at_least_millis_passed_since(last_queued_time, millis_should_passed) {
         return false;
     }
@@ -1589,6 +2658,16 @@ fn should_queue_fixed_action(
     true
 }
 
+/// Gets the interval of a fixed interval `condition`.
+#[inline]
+fn fixed_action_interval_millis(condition: ActionCondition) -> Option<u64> {
+    match condition {
+        ActionCondition::EveryMillis(millis) => Some(millis),
+        ActionCondition::ErdaShowerOffCooldown => Some(20000),
+        ActionCondition::Linked | ActionCondition::Any | ActionCondition::Expression(_) => None,
+    }
+}
+
 fn next_action_id() -> u32 {
     static NEXT_ID: AtomicU32 = AtomicU32::new(0);
 
@@ -1608,7 +2687,7 @@ mod tests {
 
     use super::*;
     use crate::{
-        Position,
+        ConditionExpression, KeyBinding, Position,
         buff::{BuffContext, BuffEntity, BuffKind},
         detect::MockDetector,
         minimap::{MinimapContext, MinimapEntity, MinimapIdle},
@@ -1626,6 +2705,8 @@ mod tests {
         },
         condition: ActionCondition::Any,
         wait_after_move_millis: 0,
+        weight: None,
+        tags: ActionTags::EMPTY,
     });
     const PRIORITY_ACTION: Action = Action::Move(ActionMove {
         position: Position {
@@ -1636,6 +2717,8 @@ mod tests {
         },
         condition: ActionCondition::ErdaShowerOffCooldown,
         wait_after_move_millis: 0,
+        weight: None,
+        tags: ActionTags::EMPTY,
     });
 
     fn mock_world() -> World {
@@ -1731,20 +2814,33 @@ mod tests {
         let args = RotatorBuildArgs {
             mode: RotatorMode::default(),
             actions: &actions,
+            input_macros: &[],
+            disabled_tags_with_strangers: &[],
+            auto_mob_priority_classes: &[],
+            burst_windows: &[],
             buffs: &buffs,
             familiars: Familiars::default(),
             familiar_essence_key: KeyKind::A,
+            feed_pet_key: KeyKind::A,
+            feed_pet_millis: 320000,
+            feed_pet_count: 1,
             elite_boss_behavior: EliteBossBehavior::CycleChannel,
             elite_boss_behavior_key: KeyKind::A,
+            elite_boss_rotation: &[],
+            elite_boss_rotation_move_to_boss: false,
             hexa_booster_exchange_condition: ExchangeHexaBoosterCondition::None,
             hexa_booster_exchange_amount: 1,
             hexa_booster_exchange_all: false,
+            enable_feeding_pet: false,
             enable_panic_mode: true,
             enable_rune_solving: true,
             enable_transparent_shape_solving: true,
             enable_reset_normal_actions_on_erda: false,
             enable_using_generic_booster: false,
             enable_using_hexa_booster: false,
+            enable_auto_mob_tracking: false,
+            enable_weighted_random_no_repeat: false,
+            priority_actions_queue_max_depth: 0,
         };
 
         rotator.build_actions(args);
@@ -1752,6 +2848,177 @@ mod tests {
         assert_eq!(rotator.normal_actions.len(), 2);
     }
 
+    #[test]
+    fn rotator_build_actions_feed_pet_queues_to_back() {
+        let mut rotator = DefaultRotator::default();
+        let args = RotatorBuildArgs {
+            mode: RotatorMode::default(),
+            actions: &[],
+            input_macros: &[],
+            disabled_tags_with_strangers: &[],
+            auto_mob_priority_classes: &[],
+            burst_windows: &[],
+            buffs: &[],
+            familiars: Familiars::default(),
+            familiar_essence_key: KeyKind::A,
+            feed_pet_key: KeyKind::B,
+            feed_pet_millis: 320000,
+            feed_pet_count: 3,
+            elite_boss_behavior: EliteBossBehavior::None,
+            elite_boss_behavior_key: KeyKind::A,
+            elite_boss_rotation: &[],
+            elite_boss_rotation_move_to_boss: false,
+            hexa_booster_exchange_condition: ExchangeHexaBoosterCondition::None,
+            hexa_booster_exchange_amount: 1,
+            hexa_booster_exchange_all: false,
+            enable_feeding_pet: true,
+            enable_panic_mode: false,
+            enable_rune_solving: false,
+            enable_transparent_shape_solving: false,
+            enable_reset_normal_actions_on_erda: false,
+            enable_using_generic_booster: false,
+            enable_using_hexa_booster: false,
+            enable_auto_mob_tracking: false,
+            enable_weighted_random_no_repeat: false,
+            priority_actions_queue_max_depth: 0,
+        };
+
+        rotator.build_actions(args);
+        let action = rotator
+            .priority_actions
+            .values()
+            .find(|action| action.condition_kind.is_some())
+            .unwrap();
+        assert!(!action.queue_to_front);
+        assert_eq!(
+            action.condition_kind,
+            Some(ActionCondition::EveryMillis(320000))
+        );
+        assert_matches!(
+            action.inner,
+            RotatorAction::Single(PlayerAction::Key(Key {
+                key: KeyKind::B,
+                count: 3,
+                ..
+            }))
+        );
+    }
+
+    #[test]
+    fn rotator_build_actions_notifies() {
+        let mut rotator = DefaultRotator::default();
+        let actions = vec![
+            NORMAL_ACTION,
+            Action::Key(ActionKey {
+                key: KeyBinding::B,
+                condition: ActionCondition::EveryMillis(1000),
+                notify: ActionNotify::Sound,
+                ..ActionKey::default()
+            }),
+        ];
+        let args = RotatorBuildArgs {
+            mode: RotatorMode::default(),
+            actions: &actions,
+            input_macros: &[],
+            disabled_tags_with_strangers: &[],
+            auto_mob_priority_classes: &[],
+            burst_windows: &[],
+            buffs: &[],
+            familiars: Familiars::default(),
+            familiar_essence_key: KeyKind::A,
+            feed_pet_key: KeyKind::A,
+            feed_pet_millis: 320000,
+            feed_pet_count: 1,
+            elite_boss_behavior: EliteBossBehavior::None,
+            elite_boss_behavior_key: KeyKind::A,
+            elite_boss_rotation: &[],
+            elite_boss_rotation_move_to_boss: false,
+            hexa_booster_exchange_condition: ExchangeHexaBoosterCondition::None,
+            hexa_booster_exchange_amount: 1,
+            hexa_booster_exchange_all: false,
+            enable_feeding_pet: false,
+            enable_panic_mode: false,
+            enable_rune_solving: false,
+            enable_transparent_shape_solving: false,
+            enable_reset_normal_actions_on_erda: false,
+            enable_using_generic_booster: false,
+            enable_using_hexa_booster: false,
+            enable_auto_mob_tracking: false,
+            enable_weighted_random_no_repeat: false,
+            priority_actions_queue_max_depth: 0,
+        };
+
+        rotator.build_actions(args);
+        let (id, notify) = rotator.action_notifies.iter().next().unwrap();
+        assert_eq!(rotator.action_notifies.len(), 1);
+        assert!(rotator.priority_actions.contains_key(id));
+        assert_eq!(notify, &("#2 B".to_string(), ActionNotify::Sound));
+    }
+
+    #[test]
+    fn rotator_build_actions_expression_skips_invalid() {
+        let mut rotator = DefaultRotator::default();
+        let actions = vec![
+            Action::Key(ActionKey {
+                key: KeyBinding::B,
+                condition: ActionCondition::Expression(
+                    ConditionExpression::new("health < 50% && buff(Rune) == false").unwrap(),
+                ),
+                ..ActionKey::default()
+            }),
+            Action::Key(ActionKey {
+                key: KeyBinding::C,
+                condition: ActionCondition::Expression(
+                    ConditionExpression::new("health <").unwrap(),
+                ),
+                ..ActionKey::default()
+            }),
+        ];
+        let args = RotatorBuildArgs {
+            mode: RotatorMode::default(),
+            actions: &actions,
+            input_macros: &[],
+            disabled_tags_with_strangers: &[],
+            auto_mob_priority_classes: &[],
+            burst_windows: &[],
+            buffs: &[],
+            familiars: Familiars::default(),
+            familiar_essence_key: KeyKind::A,
+            feed_pet_key: KeyKind::A,
+            feed_pet_millis: 320000,
+            feed_pet_count: 1,
+            elite_boss_behavior: EliteBossBehavior::None,
+            elite_boss_behavior_key: KeyKind::A,
+            elite_boss_rotation: &[],
+            elite_boss_rotation_move_to_boss: false,
+            hexa_booster_exchange_condition: ExchangeHexaBoosterCondition::None,
+            hexa_booster_exchange_amount: 1,
+            hexa_booster_exchange_all: false,
+            enable_feeding_pet: false,
+            enable_panic_mode: false,
+            enable_rune_solving: false,
+            enable_transparent_shape_solving: false,
+            enable_reset_normal_actions_on_erda: false,
+            enable_using_generic_booster: false,
+            enable_using_hexa_booster: false,
+            enable_auto_mob_tracking: false,
+            enable_weighted_random_no_repeat: false,
+            priority_actions_queue_max_depth: 0,
+        };
+
+        rotator.build_actions(args);
+        assert_eq!(rotator.priority_actions.len(), 1);
+        assert_matches!(
+            rotator
+                .priority_actions
+                .values()
+                .next()
+                .unwrap()
+                .condition_kind,
+            Some(ActionCondition::Expression(_))
+        );
+    }
+
     #[test]
     fn rotator_rotate_action_start_to_end_then_reverse() {
         let mut rotator = DefaultRotator::default();
@@ -1819,6 +3086,122 @@ mod tests {
         assert_eq!(rotator.normal_index, 0);
     }
 
+    #[test]
+    fn rotator_rotate_action_skips_disabled_tags() {
+        let mut world = mock_world();
+        let mut rotator = DefaultRotator::default();
+        let resources = Resources::new(None, None);
+        rotator.normal_rotate_mode = RotatorMode::StartToEnd;
+        for i in 0..2 {
+            rotator
+                .normal_actions
+                .push((i, RotatorAction::Single(NORMAL_ACTION.into())));
+        }
+        rotator
+            .action_tags
+            .insert(0, ActionTags::new("buff, burst").unwrap());
+        rotator.set_disabled_tags(vec!["burst".to_string()]);
+
+        rotator.rotate_action(&resources, &mut world);
+        assert!(!world.player.context.has_normal_action());
+        assert_eq!(rotator.normal_index, 1);
+
+        rotator.rotate_action(&resources, &mut world);
+        assert_eq!(world.player.context.normal_action_id(), Some(1));
+
+        world.player.context.clear_actions_aborted(true);
+        rotator.set_disabled_tags(vec![]);
+        rotator.rotate_action(&resources, &mut world);
+        assert_eq!(world.player.context.normal_action_id(), Some(0));
+    }
+
+    #[test]
+    fn rotator_rotate_action_suspended_rotates_only_side_loaded_actions() {
+        let mut world = mock_world();
+        let mut rotator = DefaultRotator::default();
+        let resources = Resources::new(None, None);
+        rotator.normal_rotate_mode = RotatorMode::StartToEnd;
+        rotator
+            .normal_actions
+            .push((0, RotatorAction::Single(NORMAL_ACTION.into())));
+        rotator.set_suspended(true);
+
+        rotator.rotate_action(&resources, &mut world);
+        assert!(!world.player.context.has_normal_action());
+
+        rotator.inject_action(PlayerAction::Unstuck);
+        rotator.rotate_action(&resources, &mut world);
+        assert!(world.player.context.has_priority_action());
+        assert!(!world.player.context.has_normal_action());
+
+        world.player.context.clear_actions_aborted(true);
+        rotator.set_suspended(false);
+        rotator.rotate_action(&resources, &mut world);
+        assert!(world.player.context.has_normal_action());
+    }
+
+    #[test]
+    fn rotator_rotate_action_weighted_random_no_repeat() {
+        let mut world = mock_world();
+        let mut rotator = DefaultRotator::default();
+        let resources = Resources::new(None, None);
+        rotator.normal_rotate_mode = RotatorMode::WeightedRandom;
+        rotator.normal_weighted_no_repeat = true;
+        for i in 0..2 {
+            rotator
+                .normal_actions
+                .push((i, RotatorAction::Single(NORMAL_ACTION.into())));
+        }
+        rotator.normal_actions_weights.insert(0, 100);
+
+        let mut last_id = None;
+        for _ in 0..10 {
+            rotator.rotate_action(&resources, &mut world);
+            let id = world.player.context.normal_action_id();
+            assert!(id.is_some());
+            assert_ne!(id, last_id);
+
+            last_id = id;
+            world.player.context.clear_actions_aborted(true);
+        }
+    }
+
+    #[test]
+    fn rotator_rotate_action_weighted_random_respects_weights() {
+        let mut world = mock_world();
+        let mut rotator = DefaultRotator::default();
+        let resources = Resources::new(None, None);
+        rotator.normal_rotate_mode = RotatorMode::WeightedRandom;
+        for i in 0..2 {
+            rotator
+                .normal_actions
+                .push((i, RotatorAction::Single(NORMAL_ACTION.into())));
+        }
+        rotator.normal_actions_weights.insert(0, 100);
+
+        let mut picks = [0; 2];
+        for _ in 0..100 {
+            rotator.rotate_action(&resources, &mut world);
+            let id = world.player.context.normal_action_id().unwrap();
+            picks[id as usize] += 1;
+
+            world.player.context.clear_actions_aborted(true);
+        }
+
+        // Strictly alternating would pick each action half of the time
+        assert!(picks[0] > 90);
+    }
+
+    #[test]
+    fn rotator_weighted_index() {
+        let weights = [1, 0, 3];
+
+        assert_eq!(weighted_index(&weights, 0), Some(0));
+        assert_eq!(weighted_index(&weights, 1), Some(2));
+        assert_eq!(weighted_index(&weights, 3), Some(2));
+        assert_eq!(weighted_index(&weights, 4), None);
+    }
+
     #[test]
     fn rotator_priority_actions_queue() {
         let mut rotator = DefaultRotator::default();
@@ -1928,6 +3311,197 @@ mod tests {
         assert_eq!(world.player.context.priority_action_id(), Some(4));
     }
 
+    #[test]
+    fn rotator_priority_actions_max_instances() {
+        let mut rotator = DefaultRotator::default();
+        let mut world = mock_world();
+        let resources = Resources::new(None, None);
+        rotator.priority_actions.insert(
+            2,
+            PriorityAction {
+                condition: Condition(Box::new(|_, _, _| ConditionResult::Queue)),
+                condition_kind: None,
+                inner: RotatorAction::Single(NORMAL_ACTION.into()),
+                metadata: None,
+                queue_to_front: false,
+                queue_info: PriorityActionQueueInfo {
+                    max_instances: Some(2),
+                    ..PriorityActionQueueInfo::default()
+                },
+            },
+        );
+
+        rotator.rotate_action(&resources, &mut world);
+        assert!(rotator.priority_actions_queue.is_empty());
+        assert_eq!(world.player.context.priority_action_id(), Some(2));
+
+        // queued again while executing
+        rotator.rotate_action(&resources, &mut world);
+        assert_eq!(rotator.priority_actions_queue, VecDeque::from_iter([2]));
+
+        // ignored when reaching max instances
+        rotator.rotate_action(&resources, &mut world);
+        assert_eq!(rotator.priority_actions_queue, VecDeque::from_iter([2]));
+        assert!(rotator.priority_actions[&2].queue_info.ignoring);
+    }
+
+    #[test]
+    fn rotator_next_priority_action_soonest_due() {
+        let mut rotator = DefaultRotator::default();
+        let now = Instant::now();
+        for (id, millis, last_queued_ago, ignoring) in [
+            (2, 10000, 2000, false),
+            (3, 5000, 1000, false),
+            (4, 5000, 4000, true),
+        ] {
+            let mut action = priority_action(
+                RotatorAction::Single(NORMAL_ACTION.into()),
+                ActionCondition::EveryMillis(millis),
+                false,
+                None,
+            );
+            action.queue_info.last_queued_time = Some(now - Duration::from_millis(last_queued_ago));
+            action.queue_info.ignoring = ignoring;
+            rotator.priority_actions.insert(id, action);
+        }
+
+        let (_, remaining) = rotator.next_priority_action().unwrap();
+
+        assert!(remaining <= Duration::from_millis(4000));
+        assert!(remaining > Duration::from_millis(3000));
+    }
+
+    #[test]
+    fn rotator_pending_normal_positions_follows_rotation_order() {
+        let mut rotator = DefaultRotator {
+            normal_rotate_mode: RotatorMode::StartToEndThenReverse,
+            ..DefaultRotator::default()
+        };
+        for x in 0..3 {
+            let action = Action::Move(ActionMove {
+                position: Position {
+                    x,
+                    ..Position::default()
+                },
+                ..ActionMove::default()
+            });
+            rotator
+                .normal_actions
+                .push((x as u32, RotatorAction::Single(action.into())));
+        }
+        rotator.normal_index = 1;
+
+        assert_eq!(
+            rotator.pending_normal_positions(),
+            vec![(1, 0), (2, 0), (1, 0)]
+        );
+
+        rotator.normal_rotate_mode = RotatorMode::StartToEnd;
+        assert_eq!(
+            rotator.pending_normal_positions(),
+            vec![(1, 0), (2, 0), (0, 0)]
+        );
+
+        rotator.normal_rotate_mode = RotatorMode::WeightedRandom;
+        assert!(rotator.pending_normal_positions().is_empty());
+    }
+
+    #[test]
+    fn rotator_priority_actions_queue_max_depth_drops_oldest() {
+        let mut rotator = DefaultRotator {
+            priority_actions_queue_max_depth: Some(2),
+            ..DefaultRotator::default()
+        };
+        let mut world = mock_world();
+        let resources = Resources::new(None, None);
+        for (id, queue_to_front) in [(2, false), (3, true), (4, false), (5, false)] {
+            rotator.priority_actions.insert(
+                id,
+                PriorityAction {
+                    condition: Condition(Box::new(|_, _, _| ConditionResult::Queue)),
+                    condition_kind: None,
+                    inner: RotatorAction::Single(NORMAL_ACTION.into()),
+                    metadata: None,
+                    queue_to_front,
+                    queue_info: PriorityActionQueueInfo::default(),
+                },
+            );
+        }
+
+        rotator.rotate_action(&resources, &mut world);
+
+        // [3, 2, 4, 5] drops non-front 2 and 4 then 3 executes
+        assert_eq!(world.player.context.priority_action_id(), Some(3));
+        assert_eq!(rotator.priority_actions_queue, VecDeque::from_iter([5]));
+        assert_eq!(rotator.priority_actions_dropped_count, 2);
+        assert_eq!(rotator.priority_actions[&2].queue_info.dropped_count, 1);
+        assert_eq!(rotator.priority_actions[&4].queue_info.dropped_count, 1);
+        let name = RotatorAction::Single(NORMAL_ACTION.into()).name();
+        assert_eq!(
+            rotator.priority_actions_dropped(),
+            vec![(name.clone(), 1), (name, 1)]
+        );
+    }
+
+    #[test]
+    fn rotator_burst_window_holds_until_all_due_then_queues_to_front() {
+        let mut rotator = DefaultRotator::default();
+        let mut world = mock_world();
+        let resources = Resources::new(None, None);
+        let now = Instant::now();
+        for (id, millis, last_queued_ago) in [(2, 5000, 6000), (3, 5000, 2000), (4, 5000, 2000)] {
+            let mut action = priority_action(
+                RotatorAction::Single(NORMAL_ACTION.into()),
+                ActionCondition::EveryMillis(millis),
+                false,
+                None,
+            );
+            action.queue_info.last_queued_time = Some(now - Duration::from_millis(last_queued_ago));
+            rotator.priority_actions.insert(id, action);
+        }
+        rotator.priority_actions.insert(
+            5,
+            PriorityAction {
+                condition: Condition(Box::new(|_, _, _| ConditionResult::Queue)),
+                condition_kind: None,
+                inner: RotatorAction::Single(NORMAL_ACTION.into()),
+                metadata: None,
+                queue_to_front: false,
+                queue_info: PriorityActionQueueInfo {
+                    max_instances: Some(2),
+                    ..PriorityActionQueueInfo::default()
+                },
+            },
+        );
+        rotator.burst_windows.push(BurstWindow {
+            ids: vec![2, 3, 4],
+            tolerance: Duration::from_millis(1000),
+            boost_priority: true,
+        });
+
+        // 2 is due but held back because 3 and 4 are not due within tolerance
+        rotator.rotate_action(&resources, &mut world);
+        assert_eq!(world.player.context.priority_action_id(), Some(5));
+        assert!(rotator.priority_actions_queue.is_empty());
+
+        for id in [3, 4] {
+            rotator
+                .priority_actions
+                .get_mut(&id)
+                .unwrap()
+                .queue_info
+                .last_queued_time = Some(now - Duration::from_millis(4500));
+        }
+
+        // 3 and 4 are due within tolerance so the window fires in front of 5
+        rotator.rotate_action(&resources, &mut world);
+        assert_eq!(world.player.context.priority_action_id(), Some(5));
+        assert_eq!(
+            rotator.priority_actions_queue,
+            VecDeque::from_iter([2, 3, 4, 5])
+        );
+    }
+
     #[test]
     fn rotator_priority_linked_action() {
         let mut rotator = DefaultRotator::default();
@@ -1985,6 +3559,111 @@ mod tests {
         assert_eq!(world.player.context.priority_action_id(), Some(2));
     }
 
+    #[test]
+    fn pick_auto_mob_point_weighted_skips_zero_weight_quadrant() {
+        let mut rotator = DefaultRotator::default();
+        let resources = Resources::new(None, None);
+        let bound = Rect::new(0, 0, 100, 100);
+        let quadrants = AutoMobQuadrants {
+            mode: AutoMobQuadrantMode::Weighted,
+            top_left_weight: 0,
+            ..AutoMobQuadrants::default()
+        };
+        let points = vec![Point::new(10, 10), Point::new(90, 10)];
+
+        for _ in 0..10 {
+            let point =
+                rotator.pick_auto_mob_point(&resources, points.clone(), quadrants, |point| {
+                    Quadrant::from_point(bound, point)
+                });
+            assert_eq!(point, Some(Point::new(90, 10)));
+        }
+
+        // Falls back to any quadrant when all quadrants with mobs have zero weight
+        let point =
+            rotator.pick_auto_mob_point(&resources, vec![Point::new(10, 10)], quadrants, |point| {
+                Quadrant::from_point(bound, point)
+            });
+        assert_eq!(point, Some(Point::new(10, 10)));
+    }
+
+    #[test]
+    fn auto_mob_priority_points_keeps_highest_priority_class() {
+        let points = vec![
+            (Point::new(1, 1), 0),
+            (Point::new(2, 2), 2),
+            (Point::new(3, 3), 1),
+            (Point::new(4, 4), 2),
+        ];
+
+        assert_eq!(
+            auto_mob_priority_points(points.clone(), &[2, 1]),
+            vec![Point::new(2, 2), Point::new(4, 4)]
+        );
+        assert_eq!(
+            auto_mob_priority_points(points.clone(), &[3, 1]),
+            vec![Point::new(3, 3)]
+        );
+        // Keeps all points when no prioritized class is detected
+        assert_eq!(auto_mob_priority_points(points, &[3]).len(), 4);
+    }
+
+    #[test]
+    fn track_auto_mobs_avoids_dead_target() {
+        let mut rotator = DefaultRotator {
+            auto_mob_tracker: Some(ByteTracker::new(FPS)),
+            ..DefaultRotator::default()
+        };
+        let bound = Rect::new(0, 0, 100, 100);
+        let mobs = [(Point::new(50, 50), 1, 0.9)];
+
+        let tracked = rotator.track_auto_mobs(&mobs, bound);
+        assert_eq!(tracked.len(), 1);
+        let (point, track, class) = tracked[0];
+        assert_eq!(point, Point::new(50, 50));
+        assert_eq!(class, 1);
+        rotator.auto_mob_target_track = track;
+
+        // Target no longer detected
+        assert!(rotator.track_auto_mobs(&[], bound).is_empty());
+        assert_eq!(rotator.auto_mob_target_track, None);
+        assert_eq!(
+            rotator.auto_mob_dead_tracks,
+            VecDeque::from_iter(track.into_iter())
+        );
+
+        // Dying animation detected again in the same place
+        assert!(rotator.track_auto_mobs(&mobs, bound).is_empty());
+    }
+
+    #[test]
+    fn pick_auto_mob_point_even_picks_least_picked_quadrant() {
+        let mut rotator = DefaultRotator::default();
+        let resources = Resources::new(None, None);
+        let bound = Rect::new(0, 0, 100, 100);
+        let quadrants = AutoMobQuadrants {
+            mode: AutoMobQuadrantMode::Even,
+            ..AutoMobQuadrants::default()
+        };
+        let points = vec![
+            Point::new(10, 10),
+            Point::new(90, 10),
+            Point::new(90, 90),
+            Point::new(10, 90),
+        ];
+
+        let picked_count = (0..8)
+            .filter_map(|_| {
+                rotator.pick_auto_mob_point(&resources, points.clone(), quadrants, |point| {
+                    Quadrant::from_point(bound, point)
+                })
+            })
+            .count();
+
+        assert_eq!(picked_count, 8);
+        assert_eq!(rotator.auto_mob_quadrant_picks, [2; 4]);
+    }
+
     #[test]
     fn rotate_ping_pong_direction() {
         let mut player = PlayerContext::default();
@@ -2205,6 +3884,45 @@ mod tests {
         queue_or_timeout(|| (action.condition.0)(&resources, &world, &info)).await;
     }
 
+    #[tokio::test]
+    async fn rotate_elite_boss_queues_keys_off_cooldown_and_pauses_normal_actions() {
+        let detector = mock_detector(|detector| {
+            detector.expect_detect_elite_boss_bar().return_const(true);
+        });
+        let resources = Resources::new(None, Some(detector));
+        let mut world = mock_world();
+        let mut rotator = DefaultRotator::default();
+        rotator.normal_rotate_mode = RotatorMode::StartToEnd;
+        rotator
+            .normal_actions
+            .push((0, RotatorAction::Single(NORMAL_ACTION.into())));
+        rotator.elite_boss_rotation = vec![
+            (KeyKind::A, 10000, None),
+            (KeyKind::B, 10000, Some(Instant::now())),
+        ];
+
+        timeout(Duration::from_secs(1), async {
+            while !rotator.elite_boss_present {
+                rotator.rotate_elite_boss(&resources, &world);
+                yield_now().await;
+            }
+        })
+        .await
+        .expect("elite boss present");
+
+        assert_matches!(
+            rotator.priority_actions_side_queue.as_slices().0,
+            [RotatorAction::Single(PlayerAction::Key(Key {
+                key: KeyKind::A,
+                position: None,
+                ..
+            }))]
+        );
+        rotator.rotate_action(&resources, &mut world);
+        assert!(world.player.context.has_priority_action());
+        assert!(!world.player.context.has_normal_action());
+    }
+
     #[tokio::test]
     async fn panic_priority_action_triggers_when_has_other_players() {
         let resources = Resources::new(None, None);
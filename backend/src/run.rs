@@ -4,36 +4,58 @@ use std::{
     rc::Rc,
     sync::{
         Arc,
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
     },
     thread,
     time::{Duration, Instant},
 };
 
 use anyhow::Result;
-use platforms::{Error, input::InputKind};
+use log::{error, info, warn};
+use opencv::core::Point;
+use platforms::{
+    Error,
+    input::InputKind,
+    power::{PowerStatus, query_power_status},
+};
 use strum::IntoEnumIterator;
 use tokio::sync::broadcast::{Sender, channel};
 
-#[cfg(debug_assertions)]
-use crate::ecs::Debug;
 use crate::{
-    bridge::{Capture, DefaultCapture, DefaultInput, InputMethod},
+    DuoMode, ErrorEvent, FrameTimings, PlayerRequestPolicy, SavedOperation, Settings,
+    bridge::{
+        Capture, DefaultCapture, DefaultInput, FramePacer, Input, InputMethod, MouseKind,
+        set_keyboard_layout,
+    },
     buff::{self, Buff, BuffContext, BuffEntity, BuffKind},
-    database::{query_and_upsert_seeds, query_or_upsert_localization, query_settings},
-    detect::{DefaultDetector, Detector},
+    database::{
+        query_and_upsert_seeds, query_or_upsert_daily_claims, query_or_upsert_localization,
+        query_or_upsert_operation_state, query_settings,
+    },
+    dataset::RuneDataset,
+    detect::{self, DefaultDetector, Detector, PartyInvite, PlayerRequest, TemplateScaleResolver},
+    duo,
     ecs::{Resources, World, WorldEvent},
-    mat::OwnedMat,
+    errors::ErrorEvents,
+    events::{MinimapChanged, PlayerDied, RuneDetected, emit_event},
+    mat::{self, OwnedMat},
     minimap::{self, Minimap, MinimapContext, MinimapEntity},
     navigator::{DefaultNavigator, Navigator},
     notification::DiscordNotification,
     operation::Operation,
     player::{self, Player, PlayerContext, PlayerEntity},
+    remote,
     rng::Rng,
     rotator::{DefaultRotator, Rotator},
+    sandbox::{Sandbox, SandboxCapture, SandboxInput},
     services::Services,
     skill::{self, Skill, SkillContext, SkillEntity, SkillKind},
-    task::{Task, Update, update_detection_task},
+    stats::Stats,
+    task::{
+        Task, TaskPriority, Update, update_detection_task, update_detection_task_with_priority,
+        update_task,
+    },
+    telegram::{self, TelegramChat},
 };
 
 /// The FPS the bot runs at.
@@ -47,7 +69,39 @@ pub const MS_PER_TICK: u64 = MS_PER_TICK_F32 as u64;
 /// Milliseconds per tick as an [`f32`].
 pub const MS_PER_TICK_F32: f32 = 1000.0 / FPS as f32;
 
+/// The maximum number of bot instances, each attached to its own game window.
+pub const MAX_INSTANCES: usize = 4;
+
+/// The only bot instance that serves spectators, the dashboard and remote control, joins duo mode,
+/// runs the Discord and Telegram bots and resumes its operation on restart.
+///
+/// These share process-wide addresses, bot tokens and the saved operation, so other instances
+/// only bot their own game window.
+pub const PRIMARY_INSTANCE: usize = 0;
+
+/// The number of started bot instances.
+static INSTANCES: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether the started bot instances run against a simulated [`Sandbox`].
+static SANDBOX: AtomicBool = AtomicBool::new(false);
+
+/// Captures and runs the systems only once every this number of ticks when power saving is
+/// throttling.
+const POWER_SAVING_CAPTURE_INTERVAL_TICKS: u64 = 2;
+
+/// The duration the minimap is not detected before reporting [`ErrorEvent::DetectionDegraded`].
+const DETECTION_DEGRADED_MILLIS: u64 = 10000;
+
 pub fn init() {
+    start(false);
+}
+
+/// Same as [`init`] but runs the bot against a simulated [`Sandbox`] instead of the game.
+pub fn init_sandbox() {
+    start(true);
+}
+
+fn start(sandbox: bool) {
     static LOOPING: AtomicBool = AtomicBool::new(false);
 
     if LOOPING
@@ -62,46 +116,139 @@ pub fn init() {
 
         ort::init_from(dll.to_str().unwrap()).commit().unwrap();
         platforms::init();
-        thread::spawn(|| {
-            let tokio_rt = tokio::runtime::Builder::new_multi_thread()
-                .enable_all()
-                .build()
-                .unwrap();
-            let _tokio_guard = tokio_rt.enter();
-            tokio_rt.block_on(async {
-                systems_loop();
-            });
-        });
+        detect::reload_templates();
+        SANDBOX.store(sandbox, Ordering::Release);
+        INSTANCES.store(1, Ordering::Release);
+        spawn_instance(0);
     }
 }
 
-fn systems_loop() {
+/// Starts another bot instance to attach a different game window to.
+///
+/// The new instance initially captures the default window and must be pointed to another
+/// window through the capture handle selection. Unlike [`PRIMARY_INSTANCE`], the new instance
+/// does not serve or resume anything.
+///
+/// Returns the index of the new instance or [`None`] if the bot has not been started or
+/// [`MAX_INSTANCES`] is reached.
+pub fn add_instance() -> Option<usize> {
+    let instance = INSTANCES
+        .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+            (count > 0 && count < MAX_INSTANCES).then_some(count + 1)
+        })
+        .ok()?;
+    spawn_instance(instance);
+
+    Some(instance)
+}
+
+/// Gets the number of started bot instances.
+pub fn instance_count() -> usize {
+    INSTANCES.load(Ordering::Acquire)
+}
+
+fn spawn_instance(instance: usize) {
+    let sandbox = SANDBOX.load(Ordering::Acquire);
+    thread::spawn(move || {
+        let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let _tokio_guard = tokio_rt.enter();
+        tokio_rt.block_on(async {
+            systems_loop(sandbox, instance);
+        });
+    });
+}
+
+fn systems_loop(sandbox: bool, instance: usize) {
     let settings = Rc::new(RefCell::new(query_settings()));
+    mat::set_opencl_enabled(settings.borrow().enable_opencl);
+    set_keyboard_layout(settings.borrow().keyboard_layout);
     let localization = Rc::new(RefCell::new(Arc::new(query_or_upsert_localization())));
     let seeds = query_and_upsert_seeds();
     let rng = Rng::new(seeds.rng_seed, seeds.perlin_seed);
     let (event_tx, event_rx) = channel::<WorldEvent>(5);
 
-    let mut service = Services::new(settings.clone(), localization.clone(), event_tx.subscribe());
-    let window = service.selected_window();
-    let mut input = DefaultInput::new(
-        InputMethod::Default(window, InputKind::Focused),
-        rng.clone(),
+    let mut service = Services::new(
+        settings.clone(),
+        localization.clone(),
+        event_tx.subscribe(),
+        if instance == PRIMARY_INSTANCE {
+            query_or_upsert_operation_state()
+                .inspect_err(|err| {
+                    error!(target: "database", "failed to query saved operation {err}");
+                })
+                .map_or(SavedOperation::Halting, |state| state.operation)
+        } else {
+            SavedOperation::Halting
+        },
+        instance,
     );
-    let mut capture = DefaultCapture::new(window);
-    service.update_window(&mut input, &mut capture);
+    let window = service.selected_window();
+    let sandbox = sandbox.then(|| Rc::new(RefCell::new(Sandbox::default())));
+    let (mut input, mut capture): (Box<dyn Input>, Box<dyn Capture>) =
+        if let Some(sandbox) = sandbox.as_ref() {
+            (
+                Box::new(SandboxInput::new(sandbox.clone())),
+                Box::new(SandboxCapture::new(sandbox.clone(), window)),
+            )
+        } else {
+            (
+                Box::new(DefaultInput::new(
+                    InputMethod::Default(window, InputKind::Focused),
+                    rng.clone(),
+                )),
+                Box::new(DefaultCapture::new(window)),
+            )
+        };
+    service.update_window(input.as_mut(), capture.as_mut());
+    if instance == PRIMARY_INSTANCE {
+        if settings.borrow().enable_spectate_server {
+            service.serve_spectators(settings.borrow().spectate_server_address.clone());
+        }
+        if settings.borrow().enable_dashboard {
+            service.serve_dashboard(settings.borrow().dashboard_address.clone());
+        }
+        if settings.borrow().enable_status_file {
+            service.write_status_file(settings.borrow().status_file_path.clone());
+        }
+        if matches!(settings.borrow().duo_mode, DuoMode::Host)
+            && !settings.borrow().enable_remote_control
+        {
+            warn!(target: "duo", "hosting duo requires serving remote control");
+        }
+        duo::start(
+            settings.borrow().duo_mode,
+            settings.borrow().duo_address.clone(),
+            settings.borrow().duo_token.clone(),
+        );
+        if settings.borrow().enable_remote_control {
+            remote::serve(&settings.borrow());
+            service.keep_game_state_frame();
+        }
+        if let Some(chat) = TelegramChat::new(
+            settings.borrow().notifications.telegram_bot_token.clone(),
+            settings.borrow().notifications.telegram_chat_id.clone(),
+        ) {
+            telegram::serve(chat);
+        }
+    }
 
     let mut rotator = DefaultRotator::default();
     let mut navigator = DefaultNavigator::new(event_rx);
     let notification = DiscordNotification::new(settings.clone());
     let mut resources = Resources {
-        #[cfg(debug_assertions)]
-        debug: Debug::default(),
-        input: Box::new(input),
+        rune_dataset: RuneDataset::new(settings.clone()),
+        input,
         rng,
         notification,
         detector: None,
         operation: Operation::Halting,
+        halt_reason: None,
+        stats: Stats::default(),
+        errors: ErrorEvents::default(),
+        frame_timings: FrameTimings::default(),
         tick: 0,
     };
 
@@ -109,10 +256,20 @@ fn systems_loop() {
         state: Minimap::Detecting,
         context: MinimapContext::default(),
     };
-    let player = PlayerEntity {
+    let mut player = PlayerEntity {
         state: Player::Idle,
         context: PlayerContext::default(),
     };
+    match query_or_upsert_daily_claims() {
+        Ok(claims) => player.context.cash_shop_freebies_claimed_day = claims.cash_shop_freebies_day,
+        Err(err) => {
+            error!(
+                target: "database",
+                "failed to query daily claims, skipping cash shop free items {err}"
+            );
+            player.context.cash_shop_freebies_claimed_day_unknown = true;
+        }
+    }
     let skills = SkillKind::iter()
         .map(SkillContext::new)
         .map(|context| SkillEntity {
@@ -139,6 +296,8 @@ fn systems_loop() {
         buffs,
     };
     let mut is_capturing_normally = false;
+    let mut minimap_detecting_ticks = 0;
+    let template_scale = TemplateScaleResolver::default();
 
     let mut lie_detector_event_task = event_task(
         WorldEvent::LieDetectorAppeared,
@@ -150,44 +309,98 @@ fn systems_loop() {
         event_tx.clone(),
         |detector| detector.detect_elite_boss_bar(),
     );
-
-    loop_with_fps(FPS, || {
-        let detector = capture
-            .grab()
-            .and_then(|frame| OwnedMat::new(frame).map_err(|_| Error::WindowInvalidSize))
-            .map(|mat| DefaultDetector::new(mat, localization.borrow().clone()));
+    let mut admin_event_task =
+        event_task(WorldEvent::AdminAppeared, event_tx.clone(), |detector| {
+            detector.detect_admin_visible()
+        });
+    let mut party_invite_task = party_invite_task(settings.clone());
+    let mut player_request_task = player_request_task(settings.clone(), event_tx.clone());
+    let mut power_saving_task = power_saving_task(settings.clone());
+
+    loop_with_fps(FPS, |pacer| {
+        let mut timer = StageTimer::new();
+        let mut capture_to_detection = Duration::ZERO;
+        let throttling = power_saving_task();
+        // Reuses the previous frame on skipped ticks to reduce capture and detection work
+        let skip_capture = throttling
+            && !resources
+                .tick
+                .is_multiple_of(POWER_SAVING_CAPTURE_INTERVAL_TICKS);
+        let detector = (!skip_capture).then(|| {
+            capture
+                .grab()
+                .and_then(|frame| OwnedMat::new(frame).map_err(|_| Error::WindowInvalidSize))
+                .map(|mat| {
+                    let detector = DefaultDetector::new(mat, localization.borrow().clone())
+                        .with_template_scale(&template_scale);
+                    match sandbox.as_ref() {
+                        Some(sandbox) => detector.with_minimap(sandbox.borrow().minimap_bbox()),
+                        None => detector,
+                    }
+                })
+        });
+        timer.lap("Capture");
         let was_capturing_normally = is_capturing_normally;
         let player_in_cash_shop = matches!(world.player.state, Player::CashShopThenExit(_));
 
-        is_capturing_normally = detector.is_ok()
-            || (!player_in_cash_shop
-                && !matches!(
-                    detector,
-                    Err(Error::WindowNotFound | Error::WindowInvalidSize)
-                ));
+        if let Some(detector) = detector.as_ref() {
+            is_capturing_normally = detector.is_ok()
+                || (!player_in_cash_shop
+                    && !matches!(
+                        detector,
+                        Err(Error::WindowNotFound | Error::WindowInvalidSize)
+                    ));
+        }
         resources.tick += 1;
-        if let Ok(detector) = detector {
+        if let Some(Ok(detector)) = detector {
             let was_running_cycle = matches!(resources.operation, Operation::RunUntil { .. });
             let was_stopping_cycle = matches!(resources.operation, Operation::HaltUntil { .. });
             let was_player_alive = !world.player.context.is_dead();
             let was_minimap_idle = matches!(world.minimap.state, Minimap::Idle(_));
+            let had_rune = rune_from(world.minimap.state).is_some();
+            let was_map_mismatched = navigator.is_map_mismatched();
+            let was_cash_shop_freebies_claimed_day =
+                world.player.context.cash_shop_freebies_claimed_day;
 
+            let captured_at = detector.captured_at();
             resources.detector = Some(Arc::new(detector));
             resources.operation = resources.operation.update_tick();
+            if !resources.operation.halting() {
+                resources.halt_reason = None;
+            }
+            if resources
+                .stats
+                .update(!resources.operation.halting())
+                .is_err()
+            {
+                resources.errors.report(ErrorEvent::DatabaseWriteFailed);
+            }
 
             minimap::run_system(&resources, &mut world.minimap, world.player.state.clone());
+            timer.lap("Minimap");
             player::run_system(&resources, &mut world.player, &world.minimap, &world.buffs);
+            if !resources.operation.halting()
+                && matches!(world.minimap.state, Minimap::Idle(_))
+                && let Some(pos) = world.player.context.last_known_pos
+            {
+                world.minimap.context.record_position(pos);
+            }
+            timer.lap("Player");
+            capture_to_detection = captured_at.elapsed();
             for skill in world.skills.iter_mut() {
                 skill::run_system(&resources, skill, world.player.state.clone());
             }
+            timer.lap("Skills");
             for buff in world.buffs.iter_mut() {
                 buff::run_system(&resources, buff, world.player.state.clone());
             }
+            timer.lap("Buffs");
 
             if navigator.navigate_player(&resources, &mut world.player.context, world.minimap.state)
             {
                 rotator.rotate_action(&resources, &mut world);
             }
+            timer.lap("Rotation");
 
             let did_cycled_to_stop = resources.operation.halting();
             let did_cycled_to_run = matches!(resources.operation, Operation::RunUntil { .. });
@@ -199,25 +412,87 @@ fn systems_loop() {
                 let _ = event_tx.send(WorldEvent::CycledToRun);
             }
 
+            if world.player.context.take_shape_safe_halt() {
+                let _ = event_tx.send(WorldEvent::LieDetectorUnsolved);
+            }
+
             let player_died = was_player_alive && world.player.context.is_dead();
             if player_died {
                 let _ = event_tx.send(WorldEvent::PlayerDied);
+                emit_event(PlayerDied);
+            }
+            if !had_rune && let Some(rune) = rune_from(world.minimap.state) {
+                emit_event(RuneDetected {
+                    x: rune.x,
+                    y: rune.y,
+                });
+            }
+
+            let idle_ticks = world.player.context.idle_ticks();
+            let idle_watchdog_ticks = {
+                let settings = settings.borrow();
+                settings
+                    .enable_idle_watchdog
+                    .then_some((settings.idle_watchdog_millis / MS_PER_TICK).max(1) as u32)
+            };
+            // Repeats every watchdog duration for as long as the player is still idle
+            if let Some(watchdog_ticks) = idle_watchdog_ticks
+                && idle_ticks > 0
+                && idle_ticks.is_multiple_of(watchdog_ticks)
+            {
+                let _ = event_tx.send(WorldEvent::PlayerIdleTimedOut);
             }
 
             let minimap_detecting = matches!(world.minimap.state, Minimap::Detecting);
             if was_minimap_idle && minimap_detecting {
                 let _ = event_tx.send(WorldEvent::MinimapChanged);
+                emit_event(MinimapChanged);
+            }
+            minimap_detecting_ticks = if minimap_detecting {
+                minimap_detecting_ticks + 1
+            } else {
+                0
+            };
+            if minimap_detecting_ticks > DETECTION_DEGRADED_MILLIS / MS_PER_TICK {
+                resources.errors.report(ErrorEvent::DetectionDegraded);
+            }
+
+            if !was_map_mismatched && navigator.is_map_mismatched() {
+                let _ = event_tx.send(WorldEvent::MapMismatched);
+            }
+
+            let cash_shop_freebies_claimed_day =
+                world.player.context.cash_shop_freebies_claimed_day;
+            if let Some(day) = cash_shop_freebies_claimed_day
+                && was_cash_shop_freebies_claimed_day != cash_shop_freebies_claimed_day
+            {
+                let _ = event_tx.send(WorldEvent::CashShopFreebiesClaimed(day));
             }
 
             lie_detector_event_task(&resources);
             elite_boss_event_task(&resources);
+            admin_event_task(&resources);
+            party_invite_task(&resources);
+            player_request_task(&resources);
+            timer.lap("Events");
         }
 
         if was_capturing_normally && !is_capturing_normally {
             let _ = event_tx.send(WorldEvent::CaptureFailed);
         }
+        if !is_capturing_normally {
+            resources.errors.report(ErrorEvent::CaptureLost);
+        }
+        if resources.input.take_rejected() {
+            resources.errors.report(ErrorEvent::InputRejected);
+        }
 
         resources.input.update(resources.tick);
+        let capture_to_dispatch = resources
+            .detector
+            .as_ref()
+            .map(|detector| detector.captured_at().elapsed())
+            .unwrap_or_default();
         resources
             .notification
             .update(resources.detector.as_ref().map(|detector| detector.mat()));
@@ -227,11 +502,181 @@ fn systems_loop() {
             &mut world,
             &mut rotator,
             &mut navigator,
-            &mut capture,
+            capture.as_mut(),
         );
+        timer.lap("Services");
+        // Broadcasted on the next tick as the game state is broadcasted while polling services
+        resources.frame_timings = FrameTimings {
+            fps: pacer.fps(),
+            skipped_frames: pacer.skipped_frames(),
+            capture_fps: capture.fps(),
+            stages: timer.stages,
+            capture_to_detection,
+            capture_to_dispatch,
+        };
     });
 }
 
+/// Records the time spent on each stage of processing a frame.
+#[derive(Debug)]
+struct StageTimer {
+    last_lap: Instant,
+    stages: Vec<(String, Duration)>,
+}
+
+impl StageTimer {
+    fn new() -> Self {
+        Self {
+            last_lap: Instant::now(),
+            stages: vec![],
+        }
+    }
+
+    /// Records the time since the last lap as the time spent on `stage`.
+    fn lap(&mut self, stage: &str) {
+        let now = Instant::now();
+        self.stages
+            .push((stage.to_string(), now.duration_since(self.last_lap)));
+        self.last_lap = now;
+    }
+}
+
+#[inline]
+fn rune_from(minimap: Minimap) -> Option<Point> {
+    match minimap {
+        Minimap::Detecting => None,
+        Minimap::Idle(idle) => idle.rune(),
+    }
+}
+
+/// Detects party invite popups and accepts them only from the inviters in
+/// [`Settings::party_invite_whitelist`].
+///
+/// Popups are left untouched while halting so that the user can handle them when playing
+/// manually.
+fn party_invite_task(settings: Rc<RefCell<Settings>>) -> impl FnMut(&Resources) {
+    let mut task: Option<Task<Result<PartyInvite>>> = None;
+
+    move |resources| {
+        if resources.operation.halting() {
+            // Drops any in-flight detection so that a stale popup is not clicked after resuming
+            task = None;
+            return;
+        }
+        if resources.detector.is_none() || !settings.borrow().enable_party_invite_auto_accept {
+            return;
+        }
+
+        match update_detection_task_with_priority(
+            resources,
+            TaskPriority::Low,
+            2000,
+            &mut task,
+            |detector| detector.detect_party_invite(),
+        ) {
+            Update::Ok(invite) => {
+                let accepted = is_party_inviter_whitelisted(
+                    &settings.borrow().party_invite_whitelist,
+                    &invite.inviter,
+                );
+                let button = if accepted {
+                    invite.accept
+                } else {
+                    invite.decline
+                };
+                let x = button.x + button.width / 2;
+                let y = button.y + button.height / 2;
+
+                info!(target: "party", "party invite from {} accepted {accepted}", invite.inviter);
+                resources.input.send_mouse(x, y, MouseKind::Click);
+            }
+            Update::Err(_) | Update::Pending => (),
+        }
+    }
+}
+
+/// Detects trade and duel request popups from other players.
+///
+/// Sends [`WorldEvent::PlayerRequestAppeared`] once when a popup appears so that it is handled
+/// according to [`Settings::player_request_policy`].
+fn player_request_task(
+    settings: Rc<RefCell<Settings>>,
+    event_tx: Sender<WorldEvent>,
+) -> impl FnMut(&Resources) {
+    let mut previous = false;
+    let mut task: Option<Task<Result<PlayerRequest>>> = None;
+
+    move |resources| {
+        if resources.detector.is_none()
+            || settings.borrow().player_request_policy == PlayerRequestPolicy::Ignore
+        {
+            return;
+        }
+
+        match update_detection_task_with_priority(
+            resources,
+            TaskPriority::Low,
+            2000,
+            &mut task,
+            |detector| detector.detect_player_request(),
+        ) {
+            Update::Ok(request) => {
+                if !previous {
+                    let _ = event_tx.send(WorldEvent::PlayerRequestAppeared(request));
+                }
+                previous = true;
+            }
+            Update::Err(_) => previous = false,
+            Update::Pending => (),
+        }
+    }
+}
+
+/// Polls the platform power status and returns whether the bot should be throttled.
+///
+/// Throttling happens when power saving is enabled and the machine is either on battery or the
+/// CPU temperature exceeds the configured threshold. GPU inference is disabled while throttling.
+fn power_saving_task(settings: Rc<RefCell<Settings>>) -> impl FnMut() -> bool {
+    let mut task: Option<Task<Result<PowerStatus>>> = None;
+    let mut status = PowerStatus::default();
+    let mut was_throttling = false;
+
+    move || {
+        let settings = settings.borrow();
+        if settings.enable_power_saving {
+            match update_task(10000, &mut task, || (), |_| Ok(query_power_status()?)) {
+                Update::Ok(current) => status = current,
+                Update::Err(_) | Update::Pending => (),
+            }
+        }
+
+        let throttling = settings.enable_power_saving
+            && (status.on_battery
+                || status.cpu_temperature.is_some_and(|temperature| {
+                    temperature >= settings.power_saving_cpu_temperature_threshold as f32
+                }));
+        if throttling != was_throttling {
+            if throttling {
+                warn!(target: "power", "throttling due to {status:?}");
+            } else {
+                info!(target: "power", "stopped throttling");
+            }
+            detect::set_gpu_inference_enabled(!throttling);
+            was_throttling = throttling;
+        }
+
+        throttling
+    }
+}
+
+/// Whether `inviter` matches any name in `whitelist` ignoring case and surrounding spaces.
+fn is_party_inviter_whitelisted(whitelist: &[String], inviter: &str) -> bool {
+    let inviter = inviter.trim();
+    whitelist
+        .iter()
+        .any(|name| name.trim().eq_ignore_ascii_case(inviter))
+}
+
 fn event_task(
     event: WorldEvent,
     event_tx: Sender<WorldEvent>,
@@ -259,32 +704,68 @@ fn event_task(
 }
 
 #[inline]
-fn loop_with_fps(fps: u32, mut on_tick: impl FnMut()) {
+fn loop_with_fps(fps: u32, mut on_tick: impl FnMut(&FramePacer)) {
     #[cfg(debug_assertions)]
     const LOG_INTERVAL_SECS: u64 = 5;
 
-    let nanos_per_frame = (1_000_000_000 / fps) as u128;
+    let mut pacer = FramePacer::new(fps);
     #[cfg(debug_assertions)]
     let mut last_logged_instant = Instant::now();
 
     loop {
-        let start = Instant::now();
+        on_tick(&pacer);
 
-        on_tick();
-
-        let now = Instant::now();
-        let elapsed_duration = now.duration_since(start);
-        let elapsed_nanos = elapsed_duration.as_nanos();
-        if elapsed_nanos <= nanos_per_frame {
-            thread::sleep(Duration::new(0, (nanos_per_frame - elapsed_nanos) as u32));
-        } else {
-            #[cfg(debug_assertions)]
-            if now.duration_since(last_logged_instant).as_secs() >= LOG_INTERVAL_SECS {
-                use log::debug;
+        #[cfg(not(debug_assertions))]
+        pacer.wait();
+        #[cfg(debug_assertions)]
+        {
+            use log::debug;
 
-                last_logged_instant = now;
-                debug!(target: "context", "ticking running late at {}ms", elapsed_duration.as_millis());
+            let elapsed_duration = pacer.wait();
+            if pacer.skipped_frames() > 0
+                && last_logged_instant.elapsed().as_secs() >= LOG_INTERVAL_SECS
+            {
+                last_logged_instant = Instant::now();
+                debug!(
+                    target: "context",
+                    "ticking running late with {} frames skipped, last tick at {}ms",
+                    pacer.skipped_frames(),
+                    elapsed_duration.as_millis()
+                );
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn whitelist() -> Vec<String> {
+        vec!["Friend".to_string(), " Guildie ".to_string()]
+    }
+
+    #[test]
+    fn is_party_inviter_whitelisted_matches_listed_name() {
+        assert!(is_party_inviter_whitelisted(&whitelist(), "Friend"));
+    }
+
+    #[test]
+    fn is_party_inviter_whitelisted_rejects_unlisted_name() {
+        assert!(!is_party_inviter_whitelisted(&whitelist(), "Stranger"));
+        assert!(!is_party_inviter_whitelisted(&whitelist(), "Friendly"));
+    }
+
+    #[test]
+    fn is_party_inviter_whitelisted_rejects_all_when_whitelist_empty() {
+        assert!(!is_party_inviter_whitelisted(&[], "Friend"));
+        assert!(!is_party_inviter_whitelisted(&[], ""));
+    }
+
+    #[test]
+    fn is_party_inviter_whitelisted_ignores_case_and_surrounding_spaces() {
+        assert!(is_party_inviter_whitelisted(&whitelist(), "  fRIEND "));
+        assert!(is_party_inviter_whitelisted(&whitelist(), "guildie"));
+        assert!(!is_party_inviter_whitelisted(&whitelist(), "Gui ldie"));
+    }
+}
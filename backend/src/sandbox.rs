@@ -0,0 +1,483 @@
+//! A simulated game for learning to configure the bot without a game client.
+//!
+//! The sandbox renders synthetic frames containing a minimap with platforms and a player dot.
+//! Keys sent through [`SandboxInput`] move the player with simple physics so that the whole
+//! detection and action pipeline can run against it. Only the minimap detection is bypassed as
+//! the real detection requires an in-game minimap.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use opencv::{
+    core::{CV_8UC4, Mat, MatTrait, MatTraitConst, MatTraitConstManual, Point, Rect, Scalar},
+    imgcodecs::{IMREAD_COLOR, imdecode},
+    imgproc::{COLOR_BGR2BGRA, LINE_8, cvt_color_def, line, rectangle},
+};
+use platforms::{Error, Window, capture::Frame};
+
+use crate::{
+    CaptureMode,
+    bridge::{
+        Capture, CaptureFpsCounter, Input, InputKeyDownOptions, InputMethod, KeyKind, MouseKind,
+    },
+};
+
+const FRAME_WIDTH: i32 = 1366;
+const FRAME_HEIGHT: i32 = 768;
+
+/// The minimap bounding box inside the frame including the border.
+const MINIMAP_BBOX: Rect = Rect {
+    x: 8,
+    y: 32,
+    width: 200,
+    height: 72,
+};
+
+/// Horizontal distance walked per tick.
+const WALK_SPEED: f32 = 0.5;
+
+/// Horizontal velocity of a jump key pressed again while airborne.
+const FLASH_JUMP_SPEED: f32 = 1.2;
+
+const JUMP_VELOCITY: f32 = 1.3;
+
+/// Vertical velocity of a jump key pressed while holding up.
+const UP_JUMP_VELOCITY: f32 = 2.4;
+
+const GRAVITY: f32 = 0.12;
+
+/// The key used for jumping inside the sandbox.
+///
+/// This matches the default jump key of a character.
+const JUMP_KEY: KeyKind = KeyKind::Space;
+
+/// A horizontal platform the player can stand on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SandboxPlatform {
+    pub x_start: i32,
+    pub x_end: i32,
+    pub y: i32,
+}
+
+/// The simulated map and player.
+///
+/// Coordinates are relative to the minimap with `(0, 0)` at the bottom-left, the same as player
+/// positions used by actions and platforms.
+#[derive(Debug)]
+pub struct Sandbox {
+    platforms: Vec<SandboxPlatform>,
+    x: f32,
+    y: f32,
+    velocity_x: f32,
+    velocity_y: f32,
+    grounded: bool,
+    flash_jumped: bool,
+    /// The platform being dropped through by down jump.
+    dropping_from: Option<SandboxPlatform>,
+    held_keys: HashSet<KeyKind>,
+    /// Keys pressed since the last step.
+    pressed_keys: Vec<KeyKind>,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self::new(vec![
+            SandboxPlatform {
+                x_start: 4,
+                x_end: 195,
+                y: 8,
+            },
+            SandboxPlatform {
+                x_start: 25,
+                x_end: 85,
+                y: 24,
+            },
+            SandboxPlatform {
+                x_start: 115,
+                x_end: 175,
+                y: 24,
+            },
+            SandboxPlatform {
+                x_start: 60,
+                x_end: 140,
+                y: 40,
+            },
+        ])
+    }
+}
+
+impl Sandbox {
+    /// Creates a sandbox with the player standing on the first of `platforms`.
+    pub fn new(platforms: Vec<SandboxPlatform>) -> Self {
+        let (x, y) = platforms
+            .first()
+            .map(|platform| ((platform.x_start + platform.x_end) / 2, platform.y))
+            .unwrap_or((MINIMAP_BBOX.width / 2, 0));
+
+        Self {
+            platforms,
+            x: x as f32,
+            y: y as f32,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            grounded: true,
+            flash_jumped: false,
+            dropping_from: None,
+            held_keys: HashSet::new(),
+            pressed_keys: Vec::new(),
+        }
+    }
+
+    /// The minimap bounding box inside the rendered frame.
+    #[inline]
+    pub fn minimap_bbox(&self) -> Rect {
+        MINIMAP_BBOX
+    }
+
+    /// The current player position.
+    #[inline]
+    pub fn player(&self) -> Point {
+        Point::new(self.x.round() as i32, self.y.round() as i32)
+    }
+
+    #[inline]
+    fn is_down(&self, key: KeyKind) -> bool {
+        self.held_keys.contains(&key) || self.pressed_keys.contains(&key)
+    }
+
+    /// Advances the simulation by one tick.
+    pub fn step(&mut self) {
+        let direction = match (self.is_down(KeyKind::Left), self.is_down(KeyKind::Right)) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => 0.0,
+        };
+        let jump_pressed = self.pressed_keys.contains(&JUMP_KEY)
+            || (self.grounded && self.held_keys.contains(&JUMP_KEY));
+
+        if self.grounded {
+            self.velocity_x = direction * WALK_SPEED;
+            if jump_pressed {
+                self.grounded = false;
+                if self.is_down(KeyKind::Down) {
+                    self.dropping_from = self.platform_below(self.y);
+                    self.velocity_y = 0.0;
+                } else if self.is_down(KeyKind::Up) {
+                    self.velocity_y = UP_JUMP_VELOCITY;
+                } else {
+                    self.velocity_y = JUMP_VELOCITY;
+                }
+            }
+        } else if jump_pressed && !self.flash_jumped && direction != 0.0 {
+            self.flash_jumped = true;
+            self.velocity_x = direction * FLASH_JUMP_SPEED;
+            self.velocity_y = self.velocity_y.max(0.0);
+        }
+        self.pressed_keys.clear();
+
+        let max_x = (MINIMAP_BBOX.width - 1) as f32;
+        self.x = (self.x + self.velocity_x).clamp(0.0, max_x);
+        if self.grounded {
+            // Walks off the platform edge
+            if self.y > 0.0 && self.platform_below(self.y).is_none() {
+                self.grounded = false;
+                self.velocity_y = 0.0;
+            }
+            return;
+        }
+
+        let previous_y = self.y;
+        self.velocity_y -= GRAVITY;
+        self.y += self.velocity_y;
+        if self.velocity_y > 0.0 {
+            return;
+        }
+
+        let landing = self
+            .platforms
+            .iter()
+            .copied()
+            .filter(|platform| Some(*platform) != self.dropping_from)
+            .filter(|platform| self.is_above(*platform))
+            .filter(|platform| (self.y..=previous_y).contains(&(platform.y as f32)))
+            .max_by_key(|platform| platform.y);
+        if let Some(platform) = landing {
+            self.y = platform.y as f32;
+            self.velocity_x = 0.0;
+            self.velocity_y = 0.0;
+            self.grounded = true;
+            self.flash_jumped = false;
+            self.dropping_from = None;
+        } else if self.y <= 0.0 {
+            self.y = 0.0;
+            self.velocity_y = 0.0;
+            self.grounded = true;
+            self.flash_jumped = false;
+            self.dropping_from = None;
+        }
+    }
+
+    fn platform_below(&self, y: f32) -> Option<SandboxPlatform> {
+        self.platforms
+            .iter()
+            .copied()
+            .find(|platform| platform.y as f32 == y && self.is_above(*platform))
+    }
+
+    #[inline]
+    fn is_above(&self, platform: SandboxPlatform) -> bool {
+        (platform.x_start as f32..=platform.x_end as f32).contains(&self.x)
+    }
+
+    /// Renders the current state as a BGRA frame.
+    pub fn render(&self) -> Result<Frame> {
+        static PLAYER: LazyLock<Mat> = LazyLock::new(|| {
+            let bgr = imdecode(include_bytes!(env!("PLAYER_TEMPLATE")), IMREAD_COLOR).unwrap();
+            let mut bgra = Mat::default();
+            cvt_color_def(&bgr, &mut bgra, COLOR_BGR2BGRA).unwrap();
+            bgra
+        });
+
+        let mut mat = Mat::new_rows_cols_with_default(
+            FRAME_HEIGHT,
+            FRAME_WIDTH,
+            CV_8UC4,
+            Scalar::new(60.0, 50.0, 45.0, 255.0),
+        )?;
+        mat.roi_mut(MINIMAP_BBOX)?
+            .set_scalar(Scalar::new(25.0, 25.0, 25.0, 255.0))?;
+        rectangle(&mut mat, MINIMAP_BBOX, Scalar::all(255.0), 1, LINE_8, 0)?;
+
+        let to_frame = |x: i32, y: i32| {
+            Point::new(MINIMAP_BBOX.x + x, MINIMAP_BBOX.y + MINIMAP_BBOX.height - y)
+        };
+        for platform in &self.platforms {
+            line(
+                &mut mat,
+                to_frame(platform.x_start, platform.y),
+                to_frame(platform.x_end, platform.y),
+                Scalar::new(110.0, 110.0, 110.0, 255.0),
+                1,
+                LINE_8,
+                0,
+            )?;
+        }
+
+        // Offsets the dot such that the detected player position is the simulated position
+        let player = self.player();
+        let dot = Rect::from_point_size(to_frame(player.x - 4, player.y + 8), PLAYER.size()?);
+        if (dot & MINIMAP_BBOX) == dot {
+            PLAYER.copy_to(&mut mat.roi_mut(dot)?)?;
+        }
+
+        Ok(Frame {
+            width: FRAME_WIDTH,
+            height: FRAME_HEIGHT,
+            data: mat.data_bytes()?.to_vec(),
+            captured_at: Instant::now(),
+            scale: 1.0,
+        })
+    }
+}
+
+/// [`Input`] that sends keys to a [`Sandbox`].
+#[derive(Debug)]
+pub struct SandboxInput {
+    sandbox: Rc<RefCell<Sandbox>>,
+}
+
+impl SandboxInput {
+    pub fn new(sandbox: Rc<RefCell<Sandbox>>) -> Self {
+        Self { sandbox }
+    }
+}
+
+impl Input for SandboxInput {
+    fn update(&mut self, _tick: u64) {}
+
+    fn set_method(&mut self, _method: InputMethod) {}
+
+    fn set_key_remaps(&mut self, _remaps: HashMap<KeyKind, KeyKind>) {}
+
+    fn set_key_repeat(&mut self, _interval: Option<Duration>) {}
+
+    fn send_mouse(&self, _x: i32, _y: i32, _kind: MouseKind) {}
+
+    fn send_key(&self, kind: KeyKind) {
+        self.sandbox.borrow_mut().pressed_keys.push(kind);
+    }
+
+    fn send_key_up(&self, kind: KeyKind) {
+        self.sandbox.borrow_mut().held_keys.remove(&kind);
+    }
+
+    fn send_key_down_with_options(&self, kind: KeyKind, _options: InputKeyDownOptions) {
+        let mut sandbox = self.sandbox.borrow_mut();
+        if sandbox.held_keys.insert(kind) {
+            sandbox.pressed_keys.push(kind);
+        }
+    }
+
+    fn is_key_cleared(&self, kind: KeyKind) -> bool {
+        !self.sandbox.borrow().held_keys.contains(&kind)
+    }
+
+    fn all_keys_cleared(&self) -> bool {
+        self.sandbox.borrow().held_keys.is_empty()
+    }
+
+    fn take_rejected(&self) -> bool {
+        false
+    }
+}
+
+/// [`Capture`] that steps and renders a [`Sandbox`] on each grab.
+#[derive(Debug)]
+pub struct SandboxCapture {
+    sandbox: Rc<RefCell<Sandbox>>,
+    window: Window,
+    mode: CaptureMode,
+    frame_rate: Option<u32>,
+    frames: u64,
+    fps_counter: CaptureFpsCounter,
+}
+
+impl SandboxCapture {
+    pub fn new(sandbox: Rc<RefCell<Sandbox>>, window: Window) -> Self {
+        Self {
+            sandbox,
+            window,
+            mode: CaptureMode::BitBlt,
+            frame_rate: None,
+            frames: 0,
+            fps_counter: CaptureFpsCounter::default(),
+        }
+    }
+}
+
+impl Capture for SandboxCapture {
+    fn grab(&mut self) -> Result<Frame, Error> {
+        let mut sandbox = self.sandbox.borrow_mut();
+        sandbox.step();
+        self.frames += 1;
+        self.fps_counter.update(Instant::now(), self.frames);
+        sandbox.render().map_err(|_| Error::WindowInvalidSize)
+    }
+
+    #[inline]
+    fn window(&self) -> Window {
+        self.window
+    }
+
+    #[inline]
+    fn set_window(&mut self, window: Window) {
+        self.window = window;
+    }
+
+    #[inline]
+    fn mode(&self) -> CaptureMode {
+        self.mode
+    }
+
+    #[inline]
+    fn set_mode(&mut self, mode: CaptureMode) {
+        self.mode = mode;
+    }
+
+    #[inline]
+    fn frame_rate(&self) -> Option<u32> {
+        self.frame_rate
+    }
+
+    #[inline]
+    fn set_frame_rate(&mut self, frame_rate: Option<u32>) {
+        self.frame_rate = frame_rate;
+    }
+
+    #[inline]
+    fn fps(&self) -> u32 {
+        self.fps_counter.fps()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        detect::{DefaultDetector, Detector},
+        mat::OwnedMat,
+    };
+
+    fn step_until_grounded(sandbox: &mut Sandbox) {
+        for _ in 0..200 {
+            sandbox.step();
+            if sandbox.grounded {
+                return;
+            }
+        }
+        panic!("player never landed");
+    }
+
+    #[test]
+    fn step_walks_while_key_held() {
+        let mut sandbox = Sandbox::default();
+        let start = sandbox.player();
+        sandbox.held_keys.insert(KeyKind::Right);
+
+        for _ in 0..10 {
+            sandbox.step();
+        }
+
+        assert_eq!(sandbox.player(), Point::new(start.x + 5, start.y));
+    }
+
+    #[test]
+    fn step_up_jump_lands_on_upper_platform() {
+        let mut sandbox = Sandbox::default();
+        sandbox.x = 50.0;
+        sandbox.held_keys.insert(KeyKind::Up);
+        sandbox.pressed_keys.push(JUMP_KEY);
+
+        sandbox.step();
+        sandbox.held_keys.clear();
+        step_until_grounded(&mut sandbox);
+
+        assert_eq!(sandbox.player(), Point::new(50, 24));
+    }
+
+    #[test]
+    fn step_down_jump_drops_to_lower_platform() {
+        let mut sandbox = Sandbox::default();
+        sandbox.x = 50.0;
+        sandbox.y = 24.0;
+        sandbox.held_keys.insert(KeyKind::Down);
+        sandbox.pressed_keys.push(JUMP_KEY);
+
+        sandbox.step();
+        sandbox.held_keys.clear();
+        step_until_grounded(&mut sandbox);
+
+        assert_eq!(sandbox.player(), Point::new(50, 8));
+    }
+
+    #[test]
+    fn render_player_detected_at_position() {
+        let sandbox = Sandbox::default();
+        let mat = OwnedMat::new(sandbox.render().unwrap()).unwrap();
+        let detector = DefaultDetector::new(mat, Arc::default());
+
+        let bbox = detector.detect_player(sandbox.minimap_bbox()).unwrap();
+        let x = (bbox.tl().x + bbox.br().x) / 2;
+        let y = MINIMAP_BBOX.height - bbox.br().y + 1;
+
+        assert_eq!(Point::new(x, y), sandbox.player());
+    }
+}
@@ -0,0 +1,372 @@
+//! User scripts for custom behaviors.
+//!
+//! Scripts are [Rhai](https://rhai.rs) files loaded from the `scripts` folder next to the
+//! executable. A script subscribes to a [`ScriptEvent`] by defining a function of the same name
+//! (e.g. `fn on_rune(state) { ... }`) that receives a read-only snapshot of the game state. Inside
+//! these functions, `key(name)`, `key(name, count)` and `move_to(x, y)` enqueue actions that are
+//! injected into the rotator after the scripts finish.
+
+use std::{
+    cell::RefCell,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    str::FromStr,
+    sync::LazyLock,
+};
+
+use log::{info, warn};
+use rhai::{AST, Dynamic, Engine, EvalAltResult, Scope};
+
+use crate::{
+    ActionKey, ActionMove, KeyBinding, Position,
+    ecs::{Resources, World},
+    minimap::Minimap,
+    player::{Player, PlayerAction},
+};
+
+static SCRIPTS_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
+    let dir = env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("scripts");
+    fs::create_dir_all(dir.clone()).unwrap();
+    dir
+});
+
+/// The maximum number of operations a script can run for a single event.
+///
+/// Scripts run inside the bot loop so a runaway script must not stall it.
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// An event that scripts can subscribe to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScriptEvent {
+    /// A rune appeared in the minimap.
+    Rune,
+    /// The player died.
+    Death,
+    /// The player became idle with no action to execute.
+    Idle,
+}
+
+impl ScriptEvent {
+    /// The name of the script function subscribing to this event.
+    fn function_name(self) -> &'static str {
+        match self {
+            ScriptEvent::Rune => "on_rune",
+            ScriptEvent::Death => "on_death",
+            ScriptEvent::Idle => "on_idle",
+        }
+    }
+}
+
+/// A read-only snapshot of the game state passed to scripts.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct ScriptState {
+    pub position: Option<(i32, i32)>,
+    pub health: Option<(u32, u32)>,
+    pub state: String,
+    pub rune: Option<(i32, i32)>,
+    pub stranger_count: usize,
+    pub is_dead: bool,
+    /// Whether the player is idle with no action to execute.
+    pub is_idle: bool,
+    pub halting: bool,
+}
+
+impl ScriptState {
+    pub fn new(resources: &Resources, world: &World) -> Self {
+        let idle = match world.minimap.state {
+            Minimap::Detecting => None,
+            Minimap::Idle(idle) => Some(idle),
+        };
+
+        Self {
+            position: world
+                .player
+                .context
+                .last_known_pos
+                .map(|pos| (pos.x, pos.y)),
+            health: world.player.context.health(),
+            state: world.player.state.to_string(),
+            rune: idle
+                .and_then(|idle| idle.rune())
+                .map(|rune| (rune.x, rune.y)),
+            stranger_count: idle.map(|idle| idle.stranger_count()).unwrap_or_default(),
+            is_dead: world.player.context.is_dead(),
+            is_idle: matches!(world.player.state, Player::Idle)
+                && !world.player.context.has_normal_action()
+                && !world.player.context.has_priority_action(),
+            halting: resources.operation.halting(),
+        }
+    }
+
+    fn to_dynamic(&self) -> Dynamic {
+        fn point(point: Option<(i32, i32)>, x: &str, y: &str) -> Dynamic {
+            point.map_or(Dynamic::UNIT, |(first, second)| {
+                let mut map = rhai::Map::new();
+                map.insert(x.into(), (first as i64).into());
+                map.insert(y.into(), (second as i64).into());
+                map.into()
+            })
+        }
+
+        let mut map = rhai::Map::new();
+        map.insert("position".into(), point(self.position, "x", "y"));
+        map.insert(
+            "health".into(),
+            point(
+                self.health
+                    .map(|(current, max)| (current as i32, max as i32)),
+                "current",
+                "max",
+            ),
+        );
+        map.insert("state".into(), self.state.clone().into());
+        map.insert("rune".into(), point(self.rune, "x", "y"));
+        map.insert("stranger_count".into(), (self.stranger_count as i64).into());
+        map.insert("is_dead".into(), self.is_dead.into());
+        map.insert("is_idle".into(), self.is_idle.into());
+        map.insert("halting".into(), self.halting.into());
+        map.into()
+    }
+}
+
+/// The compiled user scripts.
+pub struct Scripts {
+    engine: Engine,
+    scripts: Vec<(String, AST)>,
+    /// Actions enqueued by the currently running script functions.
+    actions: Rc<RefCell<Vec<PlayerAction>>>,
+}
+
+impl fmt::Debug for Scripts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scripts")
+            .field(
+                "scripts",
+                &self
+                    .scripts
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        Self::from_sources([])
+    }
+}
+
+impl Scripts {
+    /// Loads and compiles all `.rhai` files in the `scripts` folder.
+    ///
+    /// Scripts that fail to load or compile are skipped.
+    pub fn load() -> Self {
+        Self::load_from(&SCRIPTS_DIR)
+    }
+
+    fn load_from(dir: &Path) -> Self {
+        let mut paths = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        paths.sort();
+
+        Self::from_sources(paths.into_iter().filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().into_owned();
+            match fs::read_to_string(&path) {
+                Ok(source) => Some((name, source)),
+                Err(err) => {
+                    warn!(target: "scripting", "failed to read script {name}: {err}");
+                    None
+                }
+            }
+        }))
+    }
+
+    /// Compiles the scripts from `sources` of script name and source pairs.
+    pub fn from_sources(sources: impl IntoIterator<Item = (String, String)>) -> Self {
+        let actions = Rc::new(RefCell::new(Vec::new()));
+        let engine = engine(actions.clone());
+        let scripts = sources
+            .into_iter()
+            .filter_map(|(name, source)| match engine.compile(&source) {
+                Ok(ast) => {
+                    info!(target: "scripting", "loaded script {name}");
+                    Some((name, ast))
+                }
+                Err(err) => {
+                    warn!(target: "scripting", "failed to compile script {name}: {err}");
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            engine,
+            scripts,
+            actions,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty()
+    }
+
+    /// Runs the functions subscribing to `event` of all scripts with `state`.
+    ///
+    /// Returns the actions enqueued by the scripts in order.
+    pub fn run(&self, event: ScriptEvent, state: &ScriptState) -> Vec<PlayerAction> {
+        let function = event.function_name();
+        let state = state.to_dynamic();
+
+        for (name, ast) in self.scripts.iter() {
+            if !ast
+                .iter_functions()
+                .any(|metadata| metadata.name == function)
+            {
+                continue;
+            }
+
+            let result =
+                self.engine
+                    .call_fn::<Dynamic>(&mut Scope::new(), ast, function, (state.clone(),));
+            if let Err(err) = result {
+                warn!(target: "scripting", "script {name} failed in {function}: {err}");
+            }
+        }
+
+        self.actions.take()
+    }
+}
+
+fn engine(actions: Rc<RefCell<Vec<PlayerAction>>>) -> Engine {
+    fn key_action(name: &str, count: i64) -> Result<PlayerAction, Box<EvalAltResult>> {
+        let key = KeyBinding::from_str(name).map_err(|_| format!("unknown key {name}"))?;
+        let action = ActionKey {
+            key,
+            count: count.max(1) as u32,
+            ..ActionKey::default()
+        };
+
+        Ok(PlayerAction::Key(action.into()))
+    }
+
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.on_print(|text| info!(target: "scripting", "{text}"));
+    engine.on_debug(|text, _, _| info!(target: "scripting", "{text}"));
+
+    let key_actions = actions.clone();
+    engine.register_fn("key", move |name: &str| -> Result<(), Box<EvalAltResult>> {
+        key_actions.borrow_mut().push(key_action(name, 1)?);
+        Ok(())
+    });
+    let key_count_actions = actions.clone();
+    engine.register_fn(
+        "key",
+        move |name: &str, count: i64| -> Result<(), Box<EvalAltResult>> {
+            key_count_actions
+                .borrow_mut()
+                .push(key_action(name, count)?);
+            Ok(())
+        },
+    );
+    engine.register_fn("move_to", move |x: i64, y: i64| {
+        let action = ActionMove {
+            position: Position {
+                x: x as i32,
+                y: y as i32,
+                ..Position::default()
+            },
+            ..ActionMove::default()
+        };
+        actions.borrow_mut().push(PlayerAction::Move(action.into()));
+    });
+
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+    use crate::{bridge::KeyKind, player::Key};
+
+    fn scripts(source: &str) -> Scripts {
+        Scripts::from_sources([("test.rhai".to_string(), source.to_string())])
+    }
+
+    #[test]
+    fn run_enqueues_actions_of_subscribed_event() {
+        let scripts = scripts(
+            r#"
+            fn on_rune(state) {
+                key("A", 2);
+                move_to(state.rune.x, state.rune.y);
+            }
+            "#,
+        );
+        let state = ScriptState {
+            rune: Some((10, 20)),
+            ..ScriptState::default()
+        };
+
+        let actions = scripts.run(ScriptEvent::Rune, &state);
+        assert_matches!(
+            actions.as_slice(),
+            [
+                PlayerAction::Key(Key {
+                    key: KeyKind::A,
+                    count: 2,
+                    ..
+                }),
+                PlayerAction::Move(_)
+            ]
+        );
+        assert!(scripts.run(ScriptEvent::Idle, &state).is_empty());
+    }
+
+    #[test]
+    fn run_skips_failed_and_invalid_scripts() {
+        let scripts = Scripts::from_sources([
+            ("invalid.rhai".to_string(), "fn on_idle(".to_string()),
+            (
+                "unknown.rhai".to_string(),
+                r#"fn on_idle(state) { key("Unknown"); }"#.to_string(),
+            ),
+            (
+                "loop.rhai".to_string(),
+                "fn on_idle(state) { loop {} }".to_string(),
+            ),
+            (
+                "valid.rhai".to_string(),
+                r#"fn on_idle(state) { if state.stranger_count > 0 { key("B"); } }"#.to_string(),
+            ),
+        ]);
+        let state = ScriptState {
+            stranger_count: 1,
+            ..ScriptState::default()
+        };
+
+        let actions = scripts.run(ScriptEvent::Idle, &state);
+        assert_matches!(
+            actions.as_slice(),
+            [PlayerAction::Key(Key {
+                key: KeyKind::B,
+                ..
+            })]
+        );
+    }
+}
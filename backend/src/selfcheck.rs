@@ -0,0 +1,101 @@
+//! Self-check of the essential detections against the live game client.
+//!
+//! Game patches can change the UI and silently break template-based detections. Running
+//! [`run_self_check`] on startup reports which detections still work on the current frame so that
+//! broken ones are known before starting a run instead of being discovered midway.
+
+use log::info;
+
+use crate::{
+    SelfCheck, SelfCheckItem, detect::Detector, minimap::MINIMAP_BORDER_WHITENESS_THRESHOLD,
+};
+
+/// Runs the self-check suite on the frame captured by `detector`.
+///
+/// The player is checked only if the minimap is detected since it is detected within the minimap.
+pub fn run_self_check(detector: &dyn Detector) -> SelfCheck {
+    let minimap = detector
+        .detect_minimap(MINIMAP_BORDER_WHITENESS_THRESHOLD)
+        .ok();
+    let player = minimap.is_some_and(|minimap| detector.detect_player(minimap).is_ok());
+    let health_bar = detector.detect_player_health_bar().is_ok();
+    let quick_slots = detector.detect_erda_shower().is_ok();
+
+    let check = SelfCheck {
+        items: vec![
+            item("Minimap", minimap.is_some()),
+            item("Player", player),
+            item("HP bar", health_bar),
+            item("Quick slots (Erda Shower)", quick_slots),
+        ],
+    };
+    info!(target: "selfcheck", "self-check scored {}% {:?}", check.score(), check.items);
+
+    check
+}
+
+#[inline]
+fn item(name: &str, passed: bool) -> SelfCheckItem {
+    SelfCheckItem {
+        name: name.to_string(),
+        passed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use opencv::core::Rect;
+
+    use super::*;
+    use crate::detect::MockDetector;
+
+    #[test]
+    fn run_self_check_skips_player_without_minimap() {
+        let mut detector = MockDetector::default();
+        detector
+            .expect_detect_minimap()
+            .returning(|_| Err(anyhow!("minimap not found")));
+        detector.expect_detect_player().never();
+        detector
+            .expect_detect_player_health_bar()
+            .returning(|| Ok(Rect::default()));
+        detector
+            .expect_detect_erda_shower()
+            .returning(|| Err(anyhow!("erda shower not found")));
+
+        let check = run_self_check(&detector);
+
+        assert_eq!(
+            check
+                .items
+                .iter()
+                .map(|item| item.passed)
+                .collect::<Vec<_>>(),
+            vec![false, false, true, false]
+        );
+        assert_eq!(check.score(), 25);
+    }
+
+    #[test]
+    fn run_self_check_passes_all() {
+        let mut detector = MockDetector::default();
+        detector
+            .expect_detect_minimap()
+            .returning(|_| Ok(Rect::new(0, 0, 100, 100)));
+        detector
+            .expect_detect_player()
+            .returning(|_| Ok(Rect::new(10, 10, 5, 5)));
+        detector
+            .expect_detect_player_health_bar()
+            .returning(|| Ok(Rect::default()));
+        detector
+            .expect_detect_erda_shower()
+            .returning(|| Ok(Rect::default()));
+
+        let check = run_self_check(&detector);
+
+        assert!(check.items.iter().all(|item| item.passed));
+        assert_eq!(check.score(), 100);
+    }
+}
@@ -1,9 +1,17 @@
 use std::fmt::Debug;
 
+use anyhow::Result;
+use log::{info, warn};
 #[cfg(test)]
 use mockall::automock;
 
-use crate::{Character, PotionMode, player::PlayerContext};
+use crate::{
+    Action, ActionCondition, Character, PotionMode,
+    database::query_characters,
+    ecs::Resources,
+    player::PlayerContext,
+    task::{Task, TaskPriority, Update, update_detection_task_with_priority},
+};
 
 /// A service to handle character-related incoming requests.
 #[cfg_attr(test, automock)]
@@ -17,11 +25,64 @@ pub trait CharacterService: Debug {
 
     /// Updates `player_context` with information from the currently in use `[Character]`.
     fn apply_character(&self, player_context: &mut PlayerContext);
+
+    /// Queues detecting the [`Character`] from the in-game name on screen.
+    fn queue_detect_character(&mut self);
+
+    /// Polls for the [`Character`] detected from the in-game name on screen.
+    ///
+    /// Returns [`Some`] only once detected and exactly one [`Character`] has a matching name that
+    /// is not the currently in use one.
+    fn poll_detected_character(&mut self, resources: &Resources) -> Option<Character>;
+
+    /// Gets the id of the last [`Character`] returned from [`Self::poll_detected_character`] or
+    /// [`Self::poll_verify_character`].
+    fn detected_character_id(&self) -> Option<i64>;
+
+    /// Queues verifying the currently in use [`Character`] against the game client.
+    fn queue_verify_character(&mut self);
+
+    /// Polls for the result of verifying the currently in use [`Character`].
+    ///
+    /// The in-game name and the Erda Shower quick slot expected by `actions` are checked. Returns
+    /// [`Some`] only once verified and the logged in character does not match.
+    fn poll_verify_character(
+        &mut self,
+        resources: &Resources,
+        actions: &[Action],
+    ) -> Option<CharacterMismatch>;
 }
 
-#[derive(Debug, Default)]
+/// The logged in character does not match the currently in use [`Character`].
+#[derive(Debug)]
+pub enum CharacterMismatch {
+    /// Exactly one other [`Character`] matches the in-game name.
+    Matched(Character),
+    /// No other [`Character`] matches the in-game name.
+    Unmatched,
+}
+
+#[derive(Debug)]
 pub struct DefaultCharacterService {
     character: Option<Character>,
+    detect_pending: bool,
+    detect_task: Option<Task<Result<(Vec<String>, Vec<Character>)>>>,
+    detected_character_id: Option<i64>,
+    verify_pending: bool,
+    verify_task: Option<Task<Result<(bool, Vec<String>, Vec<Character>)>>>,
+}
+
+impl Default for DefaultCharacterService {
+    fn default() -> Self {
+        Self {
+            character: None,
+            detect_pending: true, // Detects on startup
+            detect_task: None,
+            detected_character_id: None,
+            verify_pending: false,
+            verify_task: None,
+        }
+    }
 }
 
 impl CharacterService for DefaultCharacterService {
@@ -35,6 +96,8 @@ impl CharacterService for DefaultCharacterService {
 
     fn apply_character(&self, player_context: &mut PlayerContext) {
         player_context.reset();
+        player_context
+            .use_movement_speeds_of(self.character.as_ref().and_then(|character| character.id));
         if let Some(character) = self.character.as_ref() {
             player_context.config.link_key_timing_millis = character.link_key_timing_millis;
             player_context.config.disable_double_jumping = character.disable_double_jumping;
@@ -49,6 +112,7 @@ impl CharacterService for DefaultCharacterService {
             player_context.config.jump_key = character.jump_key.key.into();
             player_context.config.up_jump_key = character.up_jump_key.map(|key| key.key.into());
             player_context.config.cash_shop_key = character.cash_shop_key.map(|key| key.key.into());
+            player_context.config.cash_shop_claim_freebies = character.cash_shop_claim_freebies;
             player_context.config.familiar_key =
                 character.familiar_menu_key.map(|key| key.key.into());
             player_context.config.to_town_key = character.to_town_key.map(|key| key.key.into());
@@ -61,16 +125,168 @@ impl CharacterService for DefaultCharacterService {
                     (_, PotionMode::Percentage(percent)) => Some(percent / 100.0),
                 };
             player_context.config.update_health_millis = Some(character.health_update_millis);
+            player_context.config.mana_potion_key = character.mana_potion_key.key.into();
+            player_context.config.use_mana_potion_below_percent = character
+                .mana_potion_key
+                .enabled
+                .then_some(character.mana_potion_percent / 100.0);
+            player_context.config.potion_cooldown_millis = character.potion_cooldown_millis;
+            player_context.config.pet_auto_potion = character.pet_auto_potion;
+            player_context.config.detect_critical_health = character.low_health_policy.enabled;
             player_context.config.generic_booster_key = character.generic_booster_key.key.into();
             player_context.config.hexa_booster_key = character.hexa_booster_key.key.into();
         }
     }
+
+    fn queue_detect_character(&mut self) {
+        self.detect_pending = true;
+        self.detect_task = None;
+    }
+
+    fn poll_detected_character(&mut self, resources: &Resources) -> Option<Character> {
+        if !self.detect_pending || resources.detector.is_none() {
+            return None;
+        }
+
+        let update = update_detection_task_with_priority(
+            resources,
+            TaskPriority::Low,
+            1000,
+            &mut self.detect_task,
+            |detector| {
+                let health_bar = detector.detect_player_health_bar()?;
+                let texts = detector.detect_player_name_texts(health_bar)?;
+                // Queries in the task to keep the database off the systems loop
+                Ok((texts, query_characters()?))
+            },
+        );
+        let Update::Ok((texts, characters)) = update else {
+            return None;
+        };
+        self.detect_pending = false;
+        self.detect_task = None;
+
+        let Some(character) = find_character_by_name_texts(characters, &texts) else {
+            info!(target: "character", "no unique character matches detected texts {texts:?}");
+            return None;
+        };
+        if self.character.as_ref().and_then(|current| current.id) == character.id {
+            return None;
+        }
+
+        info!(target: "character", "detected character {} from in-game name", character.name);
+        self.detected_character_id = character.id;
+        Some(character)
+    }
+
+    fn detected_character_id(&self) -> Option<i64> {
+        self.detected_character_id
+    }
+
+    fn queue_verify_character(&mut self) {
+        self.verify_pending = true;
+        self.verify_task = None;
+    }
+
+    fn poll_verify_character(
+        &mut self,
+        resources: &Resources,
+        actions: &[Action],
+    ) -> Option<CharacterMismatch> {
+        if !self.verify_pending || resources.detector.is_none() {
+            return None;
+        }
+        let Some(current) = self.character.as_ref() else {
+            self.verify_pending = false;
+            return None;
+        };
+
+        let expects_erda_shower = actions
+            .iter()
+            .any(|action| matches!(action.condition(), ActionCondition::ErdaShowerOffCooldown));
+        let update = update_detection_task_with_priority(
+            resources,
+            TaskPriority::Low,
+            1000,
+            &mut self.verify_task,
+            move |detector| {
+                let erda_shower_found =
+                    !expects_erda_shower || detector.detect_erda_shower().is_ok();
+                let texts = detector
+                    .detect_player_health_bar()
+                    .and_then(|health_bar| detector.detect_player_name_texts(health_bar))
+                    .unwrap_or_default();
+                Ok((erda_shower_found, texts, query_characters()?))
+            },
+        );
+        let Update::Ok((erda_shower_found, texts, characters)) = update else {
+            return None;
+        };
+        self.verify_pending = false;
+        self.verify_task = None;
+
+        let mismatch = verify_character(current, characters, &texts, erda_shower_found)?;
+        match &mismatch {
+            CharacterMismatch::Matched(character) => {
+                warn!(target: "character", "logged in character matches {} instead", character.name);
+                self.detected_character_id = character.id;
+            }
+            CharacterMismatch::Unmatched => {
+                warn!(target: "character", "logged in character does not match {}", current.name);
+            }
+        }
+
+        Some(mismatch)
+    }
+}
+
+/// Verifies `current` against the detected in-game name `texts` and whether the expected Erda
+/// Shower quick slot is found.
+///
+/// A unique other [`Character`] matching the in-game name takes precedence over the quick slot
+/// since the name is the stronger signal of a different character being logged in.
+fn verify_character(
+    current: &Character,
+    characters: Vec<Character>,
+    texts: &[String],
+    erda_shower_found: bool,
+) -> Option<CharacterMismatch> {
+    let others = characters
+        .into_iter()
+        .filter(|character| character.id != current.id)
+        .collect::<Vec<_>>();
+    let current_matched = find_character_by_name_texts(vec![current.clone()], texts).is_some();
+    if !current_matched && let Some(character) = find_character_by_name_texts(others, texts) {
+        return Some(CharacterMismatch::Matched(character));
+    }
+
+    (!erda_shower_found).then_some(CharacterMismatch::Unmatched)
+}
+
+/// Finds the only [`Character`] in `characters` whose name matches one of the words in `texts`.
+fn find_character_by_name_texts(characters: Vec<Character>, texts: &[String]) -> Option<Character> {
+    let mut matches = characters.into_iter().filter(|character| {
+        let name = character.name.trim();
+        !name.is_empty()
+            && texts
+                .iter()
+                .flat_map(|text| text.split_whitespace())
+                .any(|word| word.eq_ignore_ascii_case(name))
+    });
+    let character = matches.next()?;
+
+    matches.next().is_none().then_some(character)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::assert_matches::assert_matches;
+
     use super::*;
-    use crate::{KeyBinding, KeyBindingConfiguration, bridge::KeyKind, player::PlayerContext};
+    use crate::{
+        KeyBinding, KeyBindingConfiguration, LowHealthPolicy, bridge::KeyKind,
+        player::PlayerContext,
+    };
 
     fn mock_character() -> Character {
         Character {
@@ -104,6 +320,7 @@ mod tests {
                 key: KeyBinding::B,
                 ..Default::default()
             }),
+            cash_shop_claim_freebies: true,
             familiar_menu_key: Some(KeyBindingConfiguration {
                 key: KeyBinding::N,
                 ..Default::default()
@@ -122,6 +339,17 @@ mod tests {
             },
             potion_mode: PotionMode::Percentage(50.0),
             health_update_millis: 3000,
+            mana_potion_key: KeyBindingConfiguration {
+                key: KeyBinding::O,
+                enabled: true,
+            },
+            mana_potion_percent: 20.0,
+            potion_cooldown_millis: 2000,
+            pet_auto_potion: true,
+            low_health_policy: LowHealthPolicy {
+                enabled: true,
+                ..Default::default()
+            },
             ..Default::default()
         }
     }
@@ -138,6 +366,48 @@ mod tests {
         assert_eq!(current, &mock_character());
     }
 
+    #[test]
+    fn find_character_by_name_texts_unique() {
+        let characters = vec![
+            Character {
+                id: Some(1),
+                name: "Komari".to_string(),
+                ..Default::default()
+            },
+            Character {
+                id: Some(2),
+                name: "Other".to_string(),
+                ..Default::default()
+            },
+        ];
+        let texts = vec!["Lv.285".to_string(), "komari".to_string()];
+
+        let character = find_character_by_name_texts(characters, &texts).unwrap();
+
+        assert_eq!(character.id, Some(1));
+    }
+
+    #[test]
+    fn find_character_by_name_texts_ambiguous_or_none() {
+        let characters = vec![
+            Character {
+                id: Some(1),
+                name: "Komari".to_string(),
+                ..Default::default()
+            },
+            Character {
+                id: Some(2),
+                name: "komari".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        assert!(
+            find_character_by_name_texts(characters.clone(), &["Komari".to_string()]).is_none()
+        );
+        assert!(find_character_by_name_texts(characters, &["Other".to_string()]).is_none());
+    }
+
     #[test]
     fn update_from_character_none() {
         let service = DefaultCharacterService::default();
@@ -181,11 +451,55 @@ mod tests {
         assert_eq!(state.config.jump_key, KeyKind::C);
         assert_eq!(state.config.up_jump_key, Some(KeyKind::A));
         assert_eq!(state.config.cash_shop_key, Some(KeyKind::B));
+        assert!(state.config.cash_shop_claim_freebies);
         assert_eq!(state.config.familiar_key, Some(KeyKind::N));
         assert_eq!(state.config.to_town_key, Some(KeyKind::M));
         assert_eq!(state.config.change_channel_key, Some(KeyKind::L));
         assert_eq!(state.config.potion_key, KeyKind::P);
         assert_eq!(state.config.use_potion_below_percent, Some(0.5));
         assert_eq!(state.config.update_health_millis, Some(3000));
+        assert_eq!(state.config.mana_potion_key, KeyKind::O);
+        assert_eq!(state.config.use_mana_potion_below_percent, Some(0.2));
+        assert_eq!(state.config.potion_cooldown_millis, 2000);
+        assert!(state.config.pet_auto_potion);
+        assert!(state.config.detect_critical_health);
+    }
+
+    fn named_character(id: i64, name: &str) -> Character {
+        Character {
+            id: Some(id),
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn verify_character_matches_other_character_by_name() {
+        let current = named_character(1, "Alpha");
+        let characters = vec![current.clone(), named_character(2, "Beta")];
+        let texts = vec!["Beta".to_string()];
+
+        let mismatch = verify_character(&current, characters, &texts, true);
+
+        assert_matches!(
+            mismatch,
+            Some(CharacterMismatch::Matched(Character { id: Some(2), .. }))
+        );
+    }
+
+    #[test]
+    fn verify_character_unmatched_without_quick_slot() {
+        let current = named_character(1, "Alpha");
+        let characters = vec![current.clone(), named_character(2, "Beta")];
+        let texts = vec!["Alpha".to_string()];
+
+        assert_matches!(
+            verify_character(&current, characters.clone(), &texts, true),
+            None
+        );
+        assert_matches!(
+            verify_character(&current, characters, &texts, false),
+            Some(CharacterMismatch::Unmatched)
+        );
     }
 }
@@ -31,6 +31,19 @@ pub trait ControlService: Debug {
 pub struct DefaultControlService {
     bot: DiscordBot,
     bot_command_rx: Receiver<ControlEvent>,
+    bot_enabled: bool,
+}
+
+impl DefaultControlService {
+    /// Creates a service that never starts the Discord bot.
+    ///
+    /// Used by additional bot instances since only one bot can connect with the same token.
+    pub fn without_bot() -> Self {
+        Self {
+            bot_enabled: false,
+            ..Self::default()
+        }
+    }
 }
 
 impl Default for DefaultControlService {
@@ -39,6 +52,7 @@ impl Default for DefaultControlService {
         Self {
             bot,
             bot_command_rx: bot_command_receiver,
+            bot_enabled: true,
         }
     }
 }
@@ -49,7 +63,7 @@ impl ControlService for DefaultControlService {
     }
 
     fn update(&mut self, settings: &Settings) {
-        if !settings.discord_bot_access_token.is_empty() {
+        if self.bot_enabled && !settings.discord_bot_access_token.is_empty() {
             let _ = self.bot.start(settings.discord_bot_access_token.clone());
         }
     }
@@ -5,23 +5,29 @@ use std::{
 };
 
 use include_dir::{Dir, include_dir};
-use log::debug;
+use log::{debug, info};
 use opencv::{
-    core::{Mat, ModifyInplace, Vector},
+    core::{Mat, MatTraitConst, ModifyInplace, Rect, Vector},
     imgcodecs::{IMREAD_COLOR, imdecode},
     imgproc::{COLOR_BGR2BGRA, cvt_color_def},
 };
 use rand::distr::SampleString;
 use rand_distr::Alphanumeric;
+use strum::IntoEnumIterator;
 use tokio::sync::broadcast::{self, Receiver, Sender};
 
 use crate::{
-    DebugState,
-    debug::save_minimap_for_training,
-    detect::{ArrowsCalibrating, ArrowsState, DefaultDetector, Detector},
+    Bound, DebugState, DetectionBenchmark, MobFrame, MouseAlignmentPreview, ThresholdPreview,
+    bridge::{Capture, MouseKind},
+    debug::{save_labeled_mobs, save_minimap_for_training},
+    detect::{
+        ArrowsCalibrating, ArrowsState, DefaultDetector, Detector, localization_templates_memory,
+        to_base64_from_mat,
+    },
     ecs::Resources,
-    mat::OwnedMat,
-    models::Localization,
+    mat::{OwnedMat, opencl_enabled, set_opencl_enabled},
+    minimap::Minimap,
+    models::{Localization, TunableDetection},
     utils::{self, DatasetDir},
 };
 
@@ -32,6 +38,7 @@ pub struct DebugService {
     state: Sender<DebugState>,
     recording_id: Option<String>,
     infering_rune: Option<(ArrowsCalibrating, Instant)>,
+    labeling_mob_frame: Option<Mat>,
 }
 
 impl Default for DebugService {
@@ -40,6 +47,7 @@ impl Default for DebugService {
             state: broadcast::channel(1).0,
             recording_id: None,
             infering_rune: None,
+            labeling_mob_frame: None,
         }
     }
 }
@@ -80,7 +88,8 @@ impl DebugService {
         if self.state.is_empty() {
             let _ = self.state.send(DebugState {
                 is_recording: self.recording_id.is_some(),
-                is_rune_auto_saving: resources.debug.auto_save_rune(),
+                templates_memory: localization_templates_memory(),
+                notification_frames_memory: resources.notification.frames_memory(),
             });
         }
     }
@@ -89,10 +98,6 @@ impl DebugService {
         self.state.subscribe()
     }
 
-    pub fn set_auto_save_rune(&self, resources: &Resources, auto_save: bool) {
-        resources.debug.set_auto_save_rune(auto_save);
-    }
-
     pub fn record_images(&mut self, start: bool) {
         self.recording_id = if start {
             Some(Alphanumeric.sample_string(&mut rand::rng(), 8))
@@ -113,6 +118,127 @@ impl DebugService {
         }
     }
 
+    pub fn capture_mob_frame(&mut self, resources: &Resources) -> Option<MobFrame> {
+        let detector = resources.detector.as_ref()?;
+        let mat = detector.mat().try_clone().ok()?;
+        let detections = detector
+            .detect_mob_bboxes()
+            .into_iter()
+            .map(Bound::from)
+            .collect();
+        let frame = MobFrame {
+            base64: to_base64_from_mat(&mat).ok()?,
+            width: mat.cols(),
+            height: mat.rows(),
+            detections,
+        };
+        self.labeling_mob_frame = Some(mat);
+
+        Some(frame)
+    }
+
+    pub fn save_mob_labels(&mut self, mobs: Vec<Bound>) -> Option<String> {
+        let mat = self.labeling_mob_frame.take()?;
+        let mobs = mobs.into_iter().map(Rect::from).collect::<Vec<_>>();
+        let name = save_labeled_mobs(&mat, &mobs);
+        info!(target: "debug", "saved mob labels {name} with {} mobs", mobs.len());
+
+        Some(name)
+    }
+
+    pub fn tune_detection_threshold(
+        &self,
+        resources: &Resources,
+        minimap_state: Minimap,
+        detection: TunableDetection,
+        threshold: f64,
+    ) -> Option<ThresholdPreview> {
+        let detector = resources.detector.as_ref()?;
+        let minimap = match minimap_state {
+            Minimap::Idle(idle) => Some(idle.bbox),
+            Minimap::Detecting => None,
+        };
+        let bbox = detector
+            .detect_tunable(detection, threshold, minimap)
+            .ok()
+            .map(Bound::from);
+        let mat = detector.mat();
+
+        Some(ThresholdPreview {
+            base64: to_base64_from_mat(&mat).ok()?,
+            width: mat.cols(),
+            height: mat.rows(),
+            bbox,
+        })
+    }
+
+    pub fn benchmark_detection(
+        &self,
+        resources: &Resources,
+        minimap_state: Minimap,
+    ) -> Option<DetectionBenchmark> {
+        const FRAMES: u32 = 30;
+
+        let mat = resources.detector.as_ref()?.mat().try_clone().ok()?;
+        let minimap = match minimap_state {
+            Minimap::Idle(idle) => Some(idle.bbox),
+            Minimap::Detecting => None,
+        };
+        let localization = Arc::new(Localization::default());
+        let run_frames = |frames: u32| {
+            let instant = Instant::now();
+            for _ in 0..frames {
+                // A new detector for each frame so that nothing is reused from cache
+                let detector =
+                    DefaultDetector::new(OwnedMat::from(mat.clone()), localization.clone());
+                let _ = detector.detect_minimap(160);
+                for detection in TunableDetection::iter() {
+                    let _ =
+                        detector.detect_tunable(detection, detection.default_threshold(), minimap);
+                }
+            }
+            instant.elapsed().as_secs_f64() * 1000.0 / frames as f64
+        };
+
+        let was_opencl_enabled = opencl_enabled();
+        set_opencl_enabled(false);
+        let cpu_millis = run_frames(FRAMES);
+        let opencl_millis = set_opencl_enabled(true).then(|| {
+            // Warms up so that compiling OpenCL kernels is not measured
+            run_frames(1);
+            run_frames(FRAMES)
+        });
+        set_opencl_enabled(was_opencl_enabled);
+        info!(target: "debug", "detection benchmark CPU {cpu_millis}ms OpenCL {opencl_millis:?}ms");
+
+        Some(DetectionBenchmark {
+            frames: FRAMES,
+            cpu_millis,
+            opencl_millis,
+        })
+    }
+
+    pub fn test_mouse_alignment(
+        &self,
+        resources: &Resources,
+        capture: &dyn Capture,
+        target: Option<(i32, i32)>,
+    ) -> Option<MouseAlignmentPreview> {
+        let mat = resources.detector.as_ref()?.mat();
+        let scale = capture.window().scale_factor().ok();
+        if let Some((x, y)) = target {
+            debug!(target: "debug", "mouse alignment test moving to {x},{y} with scale {scale:?}");
+            resources.input.send_mouse(x, y, MouseKind::Move);
+        }
+
+        Some(MouseAlignmentPreview {
+            base64: to_base64_from_mat(&mat).ok()?,
+            width: mat.cols(),
+            height: mat.rows(),
+            scale,
+        })
+    }
+
     pub fn test_spin_rune(&self) {
         static SPIN_TEST_DIR: Dir<'static> = include_dir!("$SPIN_TEST_DIR");
         static SPIN_TEST_IMAGES: LazyLock<Vec<Mat>> = LazyLock::new(|| {
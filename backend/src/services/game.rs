@@ -1,4 +1,7 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    time::{Duration, Instant},
+};
 
 use log::debug;
 #[cfg(test)]
@@ -11,13 +14,14 @@ use tokio::{
 
 use super::EventContext;
 use crate::{
-    BotOperation, BotOperationUpdate, BoundQuadrant, Character, DatabaseEvent, GameState,
-    KeyBinding, KeyBindingConfiguration, Localization, Map, Settings,
+    ActionWaypoint, BotOperation, BotOperationUpdate, BoundQuadrant, Character, DatabaseEvent,
+    ErrorEvent, GameState, InputMacroKey, KeyBinding, KeyBindingConfiguration, Localization, Map,
+    Settings,
     bridge::InputReceiver,
+    database::{query_global_presets, query_input_macros},
     database_event_receiver,
     ecs::{Resources, World},
     minimap::Minimap,
-    operation::Operation,
     player::Quadrant,
     services::{Event, EventHandler},
     skill::SkillKind,
@@ -32,6 +36,10 @@ pub enum GameEvent {
     SettingsUpdated(Settings),
     LocalizationUpdated(Localization),
     NavigationPathsUpdated,
+    GlobalPresetsUpdated,
+    InputMacrosUpdated,
+    /// A queued database write was dropped after failing repeatedly.
+    DatabaseWriteDropped,
 }
 
 impl Event for GameEvent {}
@@ -50,13 +58,39 @@ pub trait GameService: Debug {
     /// Gets a mutable reference to [`InputReceiver`].
     fn input_receiver_mut(&mut self) -> &mut dyn InputReceiver;
 
+    /// Starts recording received keys as [`InputMacroKey`]s.
+    fn start_recording_input_macro(&mut self);
+
+    /// Stops recording received keys and returns the recorded [`InputMacroKey`]s.
+    fn stop_recording_input_macro(&mut self) -> Vec<InputMacroKey>;
+
     /// Broadcasts game state to listeners.
     #[cfg_attr(test, concretize)]
-    fn broadcast_state(&self, resources: &Resources, world: &World, map: Option<&Map>);
+    fn broadcast_state(
+        &self,
+        resources: &Resources,
+        world: &World,
+        map: Option<&Map>,
+        next_priority_action: Option<(String, Duration)>,
+        pending_normal_positions: Vec<(i32, i32)>,
+        priority_actions_dropped: Vec<(String, u32)>,
+        external_preset_error: Option<String>,
+        detected_character_id: Option<i64>,
+    );
 
     /// Subscribes to game state.
     fn subscribe_state(&self) -> Receiver<GameState>;
 
+    /// Sets whether to skip encoding the minimap frame of the broadcasted [`GameState`].
+    ///
+    /// Has no effect once [`Self::keep_frame`] is called.
+    fn set_frame_paused(&mut self, paused: bool);
+
+    /// Keeps encoding the minimap frame regardless of [`Self::set_frame_paused`].
+    ///
+    /// Used when there are listeners other than the UI needing the frame.
+    fn keep_frame(&mut self);
+
     /// Subscribes to key event.
     fn subscribe_key(&self) -> Receiver<KeyBinding>;
 }
@@ -67,6 +101,12 @@ pub struct DefaultGameService {
     key_tx: Sender<KeyBinding>,
     database_event_rx: Receiver<DatabaseEvent>,
     game_state_tx: Sender<GameState>,
+    /// Whether the minimap frame is skipped as set by [`GameService::set_frame_paused`].
+    frame_paused: bool,
+    /// Whether the minimap frame is always encoded as set by [`GameService::keep_frame`].
+    frame_kept: bool,
+    /// The time of the last recorded key and the recorded keys if recording an input macro.
+    input_macro_recording: Option<(Instant, Vec<InputMacroKey>)>,
 }
 
 impl DefaultGameService {
@@ -76,6 +116,9 @@ impl DefaultGameService {
             key_tx: broadcast::channel(1).0,
             database_event_rx: database_event_receiver(),
             game_state_tx: broadcast::channel(1).0,
+            frame_paused: false,
+            frame_kept: false,
+            input_macro_recording: None,
         }
     }
 }
@@ -101,16 +144,45 @@ impl GameService for DefaultGameService {
         self.input_rx.as_mut()
     }
 
+    fn start_recording_input_macro(&mut self) {
+        self.input_macro_recording = Some((Instant::now(), Vec::new()));
+    }
+
+    fn stop_recording_input_macro(&mut self) -> Vec<InputMacroKey> {
+        self.input_macro_recording
+            .take()
+            .map(|(_, keys)| keys)
+            .unwrap_or_default()
+    }
+
     #[cfg_attr(test, concretize)]
-    fn broadcast_state(&self, resources: &Resources, world: &World, map_data: Option<&Map>) {
+    fn broadcast_state(
+        &self,
+        resources: &Resources,
+        world: &World,
+        map_data: Option<&Map>,
+        next_priority_action: Option<(String, Duration)>,
+        pending_normal_positions: Vec<(i32, i32)>,
+        priority_actions_dropped: Vec<(String, u32)>,
+        external_preset_error: Option<String>,
+        detected_character_id: Option<i64>,
+    ) {
         if self.game_state_tx.is_empty() {
             let position = world
                 .player
                 .context
                 .last_known_pos
                 .map(|pos| (pos.x, pos.y));
-            let state = world.player.state.to_string();
+            let state = match resources.halt_reason {
+                Some(reason) if resources.operation.halting() => format!("Halted ({reason})"),
+                _ => world.player.state.to_string(),
+            };
             let health = world.player.context.health();
+            let mana = world.player.context.mana();
+            let exp = world.player.context.exp_rate();
+            let (runes_solved, runes_failed) = resources.stats.runes();
+            let errors = resources.errors.active();
+            let frame_timings = resources.frame_timings.clone();
             let normal_action = world.player.context.normal_action_name();
             let priority_action = world.player.context.priority_action_name();
             let erda_shower_state = world.skills[SkillKind::ErdaShower].state.to_string();
@@ -126,20 +198,35 @@ impl GameService for DefaultGameService {
                         .collect::<Vec<_>>()
                 })
                 .unwrap_or_default();
-            let operation = match resources.operation {
-                Operation::HaltUntil { instant, .. } => BotOperation::HaltUntil(instant),
-                Operation::TemporaryHalting { resume, .. } => {
-                    BotOperation::TemporaryHalting(resume)
-                }
-                Operation::Halting => BotOperation::Halting,
-                Operation::Running => BotOperation::Running,
-                Operation::RunUntil { instant, .. } => BotOperation::RunUntil(instant),
-            };
+            let waypoints = world
+                .player
+                .context
+                .normal_action()
+                .and_then(|action| action.position())
+                .map(|position| ActionWaypoint {
+                    x: position.x,
+                    y: position.y,
+                    current: true,
+                })
+                .into_iter()
+                .chain(
+                    pending_normal_positions
+                        .into_iter()
+                        .map(|(x, y)| ActionWaypoint {
+                            x,
+                            y,
+                            current: false,
+                        }),
+                )
+                .collect::<Vec<_>>();
+            let operation = BotOperation::from(resources.operation);
             let idle = if let Minimap::Idle(idle) = world.minimap.state {
                 Some(idle)
             } else {
                 None
             };
+            let stranger_count = idle.map(|idle| idle.stranger_count()).unwrap_or_default();
+            let crowding_score = idle.map(|idle| idle.crowding_score).unwrap_or_default();
             let platforms_bound = if map_data.is_some_and(|data| data.auto_mob_platforms_bound)
                 && let Some(idle) = idle
             {
@@ -165,11 +252,12 @@ impl GameService for DefaultGameService {
                     Quadrant::BottomRight => BoundQuadrant::BottomRight,
                     Quadrant::BottomLeft => BoundQuadrant::BottomLeft,
                 });
-            let detector = if resources.detector.is_some() {
-                Some(resources.detector_cloned())
-            } else {
-                None
-            };
+            let detector =
+                if resources.detector.is_some() && (!self.frame_paused || self.frame_kept) {
+                    Some(resources.detector_cloned())
+                } else {
+                    None
+                };
             let sender = self.game_state_tx.clone();
 
             spawn(async move {
@@ -181,16 +269,29 @@ impl GameService for DefaultGameService {
                 let game_state = GameState {
                     position,
                     health,
+                    mana,
                     state,
                     normal_action,
                     priority_action,
+                    next_priority_action,
                     erda_shower_state,
                     destinations,
+                    waypoints,
                     operation,
                     frame,
                     platforms_bound,
                     portals,
                     auto_mob_quadrant,
+                    stranger_count,
+                    crowding_score,
+                    external_preset_error,
+                    detected_character_id,
+                    exp,
+                    runes_solved,
+                    runes_failed,
+                    errors,
+                    frame_timings,
+                    priority_actions_dropped,
                 };
                 let _ = sender.send(game_state);
             });
@@ -201,6 +302,17 @@ impl GameService for DefaultGameService {
         self.game_state_tx.subscribe()
     }
 
+    fn set_frame_paused(&mut self, paused: bool) {
+        if self.frame_paused != paused {
+            debug!(target: "game", "minimap frame broadcast paused {paused}");
+        }
+        self.frame_paused = paused;
+    }
+
+    fn keep_frame(&mut self) {
+        self.frame_kept = true;
+    }
+
     fn subscribe_key(&self) -> Receiver<KeyBinding> {
         self.key_tx.subscribe()
     }
@@ -242,6 +354,13 @@ impl EventHandler<GameEvent> for GameEventHandler {
                 );
 
                 context.control_service.update(&settings_service.settings());
+                context.operation_service.apply(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    &settings_service.settings(),
+                    BotOperationUpdate::Schedule(settings_service.settings().operation_schedule),
+                );
                 context.rotator_service.apply(
                     context.rotator,
                     context.map_service.map(),
@@ -253,6 +372,34 @@ impl EventHandler<GameEvent> for GameEventHandler {
                 .localization_service
                 .update_localization(localization),
             GameEvent::NavigationPathsUpdated => context.navigator.mark_dirty(true),
+            GameEvent::GlobalPresetsUpdated => {
+                let map_service = &context.map_service;
+                let map = map_service.map();
+                let character = context.character_service.character();
+                let rotator_service = &mut context.rotator_service;
+                rotator_service.update_global_presets(query_global_presets().unwrap_or_default());
+                rotator_service.update_actions(map, map_service.preset(), character);
+                rotator_service.apply(
+                    context.rotator,
+                    map,
+                    character,
+                    &context.settings_service.settings(),
+                );
+            }
+            GameEvent::InputMacrosUpdated => {
+                let rotator_service = &mut context.rotator_service;
+                rotator_service.update_input_macros(query_input_macros().unwrap_or_default());
+                rotator_service.apply(
+                    context.rotator,
+                    context.map_service.map(),
+                    context.character_service.character(),
+                    &context.settings_service.settings(),
+                );
+            }
+            GameEvent::DatabaseWriteDropped => context
+                .resources
+                .errors
+                .report(ErrorEvent::DatabaseWriteFailed),
         }
     }
 }
@@ -282,6 +429,20 @@ fn poll_key(service: &mut DefaultGameService, settings: &Settings) -> Option<Gam
         return Some(GameEvent::ToggleOperation);
     }
 
+    if let Some((last_key_instant, keys)) = service.input_macro_recording.as_mut() {
+        let now = Instant::now();
+        let delay_millis = if keys.is_empty() {
+            0
+        } else {
+            now.duration_since(*last_key_instant).as_millis() as u64
+        };
+        keys.push(InputMacroKey {
+            key: received_key.into(),
+            delay_millis,
+        });
+        *last_key_instant = now;
+    }
+
     let _ = service.key_tx.send(received_key.into());
     None
 }
@@ -310,6 +471,12 @@ fn poll_database(
         DatabaseEvent::NavigationPathsUpdated | DatabaseEvent::NavigationPathsDeleted => {
             return Some(GameEvent::NavigationPathsUpdated);
         }
+        DatabaseEvent::GlobalPresetUpdated | DatabaseEvent::GlobalPresetDeleted => {
+            return Some(GameEvent::GlobalPresetsUpdated);
+        }
+        DatabaseEvent::InputMacroUpdated | DatabaseEvent::InputMacroDeleted => {
+            return Some(GameEvent::InputMacrosUpdated);
+        }
         DatabaseEvent::SettingsUpdated(settings) => {
             return Some(GameEvent::SettingsUpdated(settings));
         }
@@ -329,6 +496,7 @@ fn poll_database(
                 return Some(GameEvent::CharacterUpdated(None));
             }
         }
+        DatabaseEvent::WriteDropped => return Some(GameEvent::DatabaseWriteDropped),
     }
 
     None
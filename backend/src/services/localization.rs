@@ -1,29 +1,61 @@
 use std::{cell::RefCell, fmt::Debug, rc::Rc, sync::Arc};
 
+use log::info;
+use opencv::{
+    core::{Point, Vector},
+    imgcodecs::imencode_def,
+};
+
 use crate::{
-    GameTemplate, Localization,
+    GameTemplate, Localization, TemplateMatch,
     detect::{
         CASH_SHOP_TEMPLATE, CHANGE_CHANNEL_TEMPLATE, FAMILIAR_LEVEL_BUTTON_TEMPLATE,
         FAMILIAR_SAVE_BUTTON_TEMPLATE, HEXA_BOOSTER_BUTTON_TEMPLATE, HEXA_CONVERT_BUTTON_TEMPLATE,
         HEXA_ERDA_CONVERSION_BUTTON_TEMPLATE, HEXA_MAX_BUTTON_TEMPLATE, POPUP_CANCEL_NEW_TEMPLATE,
         POPUP_CANCEL_OLD_TEMPLATE, POPUP_CONFIRM_TEMPLATE, POPUP_END_CHAT_TEMPLATE,
         POPUP_NEXT_TEMPLATE, POPUP_OK_NEW_TEMPLATE, POPUP_OK_OLD_TEMPLATE, POPUP_YES_TEMPLATE,
-        TIMER_TEMPLATE, to_base64_from_mat,
+        TIMER_TEMPLATE, invalidate_localization_templates, reload_templates, to_base64_from_mat,
+        to_mat_from_base64_any_color,
     },
     ecs::Resources,
+    minimap::Minimap,
     utils::{self, DatasetDir},
 };
 
 /// A service for handling localization-related incoming requests.
 pub trait LocalizationService: Debug {
     /// Retrieves the default base64-encoded PNG for template `template`.
+    ///
+    /// The default is the user provided template in the `templates` folder if any.
     fn template(&self, template: GameTemplate) -> String;
 
+    /// Reloads the user provided templates from the `templates` folder.
+    ///
+    /// Returns the file names of the loaded templates.
+    fn reload_templates(&self) -> Vec<String>;
+
     /// Updates the currently in use [`Localization`] with new `localization`.
+    ///
+    /// This also invalidates all cached templates decoded from the previous [`Localization`].
     fn update_localization(&mut self, localization: Localization);
 
+    /// Tests base64-encoded PNG `base64` template against the currently captured frame.
+    fn test_template(&self, resources: &Resources, base64: String) -> Option<TemplateMatch>;
+
     /// Saves the currently captured image to the `datasets` folder.
-    fn save_capture_image(&self, resources: &Resources, is_grayscale: bool);
+    ///
+    /// Returns the saved image encoded as PNG or [`None`] if nothing is captured.
+    fn save_capture_image(&self, resources: &Resources, is_grayscale: bool) -> Option<Vec<u8>>;
+
+    /// Extracts the player dot at the user confirmed `position` as a base64-encoded PNG template.
+    ///
+    /// `position` is in player-relative coordinate, which is bottom-left.
+    fn calibrate_player_dot(
+        &self,
+        resources: &Resources,
+        minimap_state: Minimap,
+        position: (i32, i32),
+    ) -> Option<String>;
 }
 
 #[derive(Debug)]
@@ -59,20 +91,61 @@ impl LocalizationService for DefaultLocalizationService {
             GameTemplate::HexaConvertButton => &HEXA_CONVERT_BUTTON_TEMPLATE,
         };
 
-        to_base64_from_mat(template).expect("convert successfully")
+        to_base64_from_mat(&template.get()).expect("convert successfully")
+    }
+
+    fn reload_templates(&self) -> Vec<String> {
+        reload_templates()
     }
 
     fn update_localization(&mut self, localization: Localization) {
         *self.localization.borrow_mut() = Arc::new(localization);
+        invalidate_localization_templates();
     }
 
-    fn save_capture_image(&self, resources: &Resources, is_grayscale: bool) {
-        if let Some(detector) = resources.detector.as_ref() {
-            if is_grayscale {
-                utils::save_image_to_default(detector.grayscale(), DatasetDir::Root);
-            } else {
-                utils::save_image_to_default(&detector.mat(), DatasetDir::Root);
-            }
+    fn test_template(&self, resources: &Resources, base64: String) -> Option<TemplateMatch> {
+        let detector = resources.detector.as_ref()?;
+        let template = to_mat_from_base64_any_color(&base64).ok()?;
+        let (bbox, score) = detector.detect_template_match(&template).ok()?;
+
+        Some(TemplateMatch {
+            bbox: bbox.into(),
+            score,
+        })
+    }
+
+    fn save_capture_image(&self, resources: &Resources, is_grayscale: bool) -> Option<Vec<u8>> {
+        let detector = resources.detector.as_ref()?;
+        let mut bytes = Vector::new();
+        if is_grayscale {
+            utils::save_image_to_default(detector.grayscale(), DatasetDir::Root);
+            imencode_def(".png", detector.grayscale(), &mut bytes).ok()?;
+        } else {
+            let mat = detector.mat();
+            utils::save_image_to_default(&mat, DatasetDir::Root);
+            imencode_def(".png", &mat, &mut bytes).ok()?;
         }
+
+        Some(bytes.to_vec())
+    }
+
+    fn calibrate_player_dot(
+        &self,
+        resources: &Resources,
+        minimap_state: Minimap,
+        (x, y): (i32, i32),
+    ) -> Option<String> {
+        let detector = resources.detector.as_ref()?;
+        let Minimap::Idle(idle) = minimap_state else {
+            return None;
+        };
+        // Flip back to top-left coordinate
+        let position = Point::new(x, idle.bbox.height - y);
+        let template = detector
+            .extract_player_dot_template(idle.bbox, position)
+            .inspect_err(|err| info!(target: "localization", "player dot calibration failed {err}"))
+            .ok()?;
+
+        to_base64_from_mat(&template).ok()
     }
 }
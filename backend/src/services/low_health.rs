@@ -0,0 +1,176 @@
+use std::{
+    fmt::Debug,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+
+use super::EventContext;
+use crate::{
+    LowHealthAction, LowHealthPolicy,
+    ecs::{Resources, World},
+    notification::NotificationKind,
+    player::{Panic, PanicTo, Player, PlayerAction},
+    services::{Event, EventHandler},
+};
+
+/// Executes the provided [`LowHealthAction`] and halts.
+#[derive(Debug)]
+pub struct LowHealthEvent(LowHealthAction);
+
+impl Event for LowHealthEvent {}
+
+/// A service to apply the character's [`LowHealthPolicy`].
+pub trait LowHealthService: Debug {
+    /// Polls for any pending [`LowHealthEvent`] from the current player health.
+    fn poll(
+        &mut self,
+        resources: &Resources,
+        world: &World,
+        policy: Option<&LowHealthPolicy>,
+    ) -> Option<LowHealthEvent>;
+}
+
+#[derive(Debug, Default)]
+pub struct DefaultLowHealthService {
+    /// The time health started staying below [`LowHealthPolicy::critical_percent`].
+    critical_since: Option<Instant>,
+}
+
+impl DefaultLowHealthService {
+    fn update(
+        &mut self,
+        now: Instant,
+        policy: &LowHealthPolicy,
+        health_ratio: Option<f32>,
+    ) -> Option<LowHealthEvent> {
+        let is_critical = health_ratio.is_some_and(|ratio| ratio < policy.critical_percent / 100.0);
+        if !is_critical {
+            self.critical_since = None;
+            return None;
+        }
+
+        let since = *self.critical_since.get_or_insert(now);
+        if now.duration_since(since) < Duration::from_millis(policy.duration_millis) {
+            return None;
+        }
+        self.critical_since = None;
+
+        Some(LowHealthEvent(policy.action))
+    }
+}
+
+impl LowHealthService for DefaultLowHealthService {
+    fn poll(
+        &mut self,
+        resources: &Resources,
+        world: &World,
+        policy: Option<&LowHealthPolicy>,
+    ) -> Option<LowHealthEvent> {
+        let policy = policy.filter(|policy| policy.enabled)?;
+        if resources.operation.halting() || matches!(world.player.state, Player::Panicking(_)) {
+            self.critical_since = None;
+            return None;
+        }
+
+        let health_ratio = world
+            .player
+            .context
+            .health()
+            .filter(|(_, max)| *max > 0)
+            .map(|(current, max)| current as f32 / max as f32);
+        self.update(Instant::now(), policy, health_ratio)
+    }
+}
+
+pub struct LowHealthEventHandler;
+
+impl EventHandler<LowHealthEvent> for LowHealthEventHandler {
+    fn handle(&mut self, context: &mut EventContext<'_>, event: LowHealthEvent) {
+        let LowHealthEvent(action) = event;
+        warn!(target: "low_health", "health stayed critical, executing {action} and halting");
+        let _ = context
+            .resources
+            .notification
+            .schedule_notification(NotificationKind::PlayerHealthCritical);
+
+        let to = match action {
+            LowHealthAction::GoToTown => {
+                context.operation_service.halt(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    true,
+                );
+                return;
+            }
+            LowHealthAction::ChangeChannel => PanicTo::Channel,
+            LowHealthAction::ExitToCharacterSelect => PanicTo::CharacterSelect,
+        };
+        context
+            .operation_service
+            .halt(context.resources, context.world, context.rotator, false);
+        context.rotator.inject_action(PlayerAction::Panic(Panic {
+            to,
+            skip_channels: 0,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+
+    fn policy() -> LowHealthPolicy {
+        LowHealthPolicy {
+            enabled: true,
+            critical_percent: 20.0,
+            duration_millis: 1000,
+            action: LowHealthAction::ChangeChannel,
+        }
+    }
+
+    #[test]
+    fn update_executes_action_after_staying_critical() {
+        let policy = policy();
+        let mut service = DefaultLowHealthService::default();
+        let now = Instant::now();
+
+        assert_matches!(service.update(now, &policy, Some(0.1)), None);
+        assert_matches!(
+            service.update(now + Duration::from_millis(500), &policy, Some(0.15)),
+            None
+        );
+        assert_matches!(
+            service.update(now + Duration::from_millis(1000), &policy, Some(0.1)),
+            Some(LowHealthEvent(LowHealthAction::ChangeChannel))
+        );
+    }
+
+    #[test]
+    fn update_resets_when_health_recovers_or_unknown() {
+        let policy = policy();
+        let mut service = DefaultLowHealthService::default();
+        let now = Instant::now();
+
+        assert_matches!(service.update(now, &policy, Some(0.1)), None);
+        assert_matches!(
+            service.update(now + Duration::from_millis(500), &policy, Some(0.5)),
+            None
+        );
+        assert_matches!(
+            service.update(now + Duration::from_millis(1000), &policy, Some(0.1)),
+            None
+        );
+        assert_matches!(
+            service.update(now + Duration::from_millis(1500), &policy, None),
+            None
+        );
+        assert_matches!(
+            service.update(now + Duration::from_millis(2000), &policy, Some(0.1)),
+            None
+        );
+    }
+}
@@ -1,20 +1,38 @@
-use std::fmt::Debug;
+use std::{
+    env,
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::{Duration, Instant, SystemTime},
+};
 
+use anyhow::{Result, anyhow, bail};
+use log::{info, warn};
 #[cfg(test)]
 use mockall::automock;
+use opencv::core::MatTraitConst;
 
 use crate::{
+    PresetRecovery,
+    database::{query_map, upsert_map, upsert_map_with},
+    detect::{to_base64_from_mat, to_cached_mat_from_base64_any_color},
+    ecs::Resources,
+    events::{MapChanged, emit_event},
     minimap::{Minimap, MinimapContext, MinimapEntity},
-    models::Map,
+    models::{Action, ActionCondition, Map, PlatformSetTrigger},
     pathing::Platform,
     player::PlayerContext,
+    task::{Task, Update, update_detection_task},
 };
 
 /// A service to handle map-related incoming requests.
 #[cfg_attr(test, automock)]
 pub trait MapService: Debug {
     /// Creates a new [`Map`] from currently detected minimap with `name`.
-    fn create(&self, minimap_state: Minimap, name: String) -> Option<Map>;
+    ///
+    /// The minimap name is also captured if it can be detected.
+    fn create(&self, resources: &Resources, minimap_state: Minimap, name: String) -> Option<Map>;
 
     /// Gets the currently in use [`Map`].
     #[allow(clippy::needless_lifetimes)]
@@ -32,21 +50,136 @@ pub trait MapService: Debug {
 
     /// Re-detects current minimap.
     fn redetect(&self, minimap: &mut MinimapEntity);
+
+    /// Polls the external file of the currently in use preset for changes.
+    ///
+    /// Returns the currently in use [`Map`] with the preset's actions replaced by the file
+    /// content when the file has changed and is valid.
+    fn poll_external_preset(&mut self) -> Option<Map>;
+
+    /// Gets the error from the last reload of the currently in use preset's external file.
+    fn external_preset_error(&self) -> Option<String>;
+
+    /// Polls the trigger of the next [`Map::platform_sets`] and switches the platforms in
+    /// `minimap_context` to that set when the trigger is met.
+    fn poll_platform_set(&mut self, resources: &Resources, minimap_context: &mut MinimapContext);
+
+    /// Polls auto-saving the currently in use preset if it has edits applied through
+    /// [`Self::update_map_preset`] that are not yet stored in the database.
+    ///
+    /// The preset is also written to a recovery file until it is stored so that the edits can be
+    /// recovered after an abnormal termination.
+    fn poll_auto_save(&mut self);
+
+    /// Gets the preset with unsaved edits recovered from the last abnormal termination.
+    fn recovery(&self) -> Option<PresetRecovery>;
+
+    /// Restores the preset from [`Self::recovery`] to its map if `restore` is true or discards
+    /// it.
+    fn recover(&mut self, restore: bool);
+}
+
+/// How often the external file of a preset is checked for changes.
+const EXTERNAL_PRESET_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the template of [`PlatformSetTrigger::Template`] is detected.
+const PLATFORM_SET_DETECTION_INTERVAL_MILLIS: u64 = 1000;
+
+/// The minimum score for the template of [`PlatformSetTrigger::Template`] to be considered
+/// detected.
+const PLATFORM_SET_TEMPLATE_THRESHOLD: f64 = 0.8;
+
+/// How often the currently in use preset with unsaved edits is auto-saved.
+const AUTO_SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+static AUTO_SAVE_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
+    let dir = env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("autosave");
+    fs::create_dir_all(dir.clone()).unwrap();
+    dir
+});
+
+#[derive(Debug)]
+struct ExternalPreset {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_checked: Option<Instant>,
+    error: Option<String>,
 }
 
 #[derive(Debug, Default)]
 pub struct DefaultMapService {
     map: Option<Map>,
     preset: Option<String>,
+    external_preset: Option<ExternalPreset>,
+    /// The index of the currently active [`Map::platform_sets`].
+    ///
+    /// [`None`] means [`Map::platforms`] is active.
+    platform_set_index: Option<usize>,
+    /// The instant the currently active platforms started being used.
+    platform_set_started: Option<Instant>,
+    platform_set_task: Option<Task<Result<bool>>>,
+    /// The recovery file the currently in use preset is auto-saved to.
+    ///
+    /// [`None`] means auto-saving is disabled.
+    auto_save_path: Option<PathBuf>,
+    /// Whether the currently in use preset has edits not yet stored in the database.
+    dirty: bool,
+    last_auto_saved: Option<Instant>,
+    recovery: Option<PresetRecovery>,
+}
+
+impl DefaultMapService {
+    /// Creates the service for bot `instance` with auto-saving enabled.
+    ///
+    /// The preset auto-saved by the same `instance` is recovered if its edits were not stored
+    /// in the database before the last termination.
+    pub fn new(instance: usize) -> Self {
+        let path = AUTO_SAVE_DIR.join(format!("instance_{instance}.json"));
+        let recovery = load_recovery(&path);
+        if recovery.is_some() {
+            info!(target: "map", "recovered preset with unsaved edits from {}", path.display());
+        } else {
+            let _ = fs::remove_file(&path);
+        }
+
+        Self {
+            auto_save_path: Some(path),
+            recovery,
+            ..Self::default()
+        }
+    }
+
+    fn preset_to_recover(&self) -> Option<PresetRecovery> {
+        let map = self.map.as_ref()?;
+        let preset = self.preset.clone()?;
+
+        Some(PresetRecovery {
+            map_id: map.id?,
+            map_name: map.name.clone(),
+            actions: map.actions.get(&preset)?.clone(),
+            preset,
+        })
+    }
 }
 
 impl MapService for DefaultMapService {
-    fn create(&self, minimap_state: Minimap, name: String) -> Option<Map> {
+    fn create(&self, resources: &Resources, minimap_state: Minimap, name: String) -> Option<Map> {
         if let Minimap::Idle(idle) = minimap_state {
+            let name_snapshot_base64 = resources.detector.as_ref().and_then(|detector| {
+                let name_bbox = detector.detect_minimap_name(idle.bbox).ok()?;
+                let name = detector.grayscale().roi(name_bbox).ok()?.try_clone().ok()?;
+                to_base64_from_mat(&name).ok()
+            });
+
             Some(Map {
                 name,
                 width: idle.bbox.width,
                 height: idle.bbox.height,
+                name_snapshot_base64,
                 ..Map::default()
             })
         } else {
@@ -63,6 +196,44 @@ impl MapService for DefaultMapService {
     }
 
     fn update_map_preset(&mut self, map: Option<Map>, preset: Option<String>) {
+        let path = map
+            .as_ref()
+            .zip(preset.as_ref())
+            .and_then(|(map, preset)| map.actions_external_files.get(preset))
+            .map(PathBuf::from);
+        // Keeps the last modified time so that applying a reloaded map does not reload again
+        if self.external_preset.as_ref().map(|external| &external.path) != path.as_ref() {
+            self.external_preset = path.map(|path| ExternalPreset {
+                path,
+                modified: None,
+                last_checked: None,
+                error: None,
+            });
+        }
+
+        // Only edits to the same preset are unsaved as switching is stored by the UI
+        let preset_actions = |map: Option<&Map>| {
+            map.zip(preset.as_ref())
+                .and_then(|(map, preset)| map.actions.get(preset))
+        };
+        let same_preset = self.preset == preset
+            && self.map.as_ref().map(|map| map.id) == map.as_ref().map(|map| map.id);
+        if !same_preset {
+            self.dirty = false;
+        } else if preset_actions(self.map.as_ref()) != preset_actions(map.as_ref()) {
+            self.dirty = true;
+        }
+
+        // Restarts from the default platforms only when switching to a different map
+        if self.map.as_ref().map(|map| map.id) != map.as_ref().map(|map| map.id) {
+            self.platform_set_index = None;
+            self.platform_set_started = None;
+            self.platform_set_task = None;
+            emit_event(MapChanged {
+                map_id: map.as_ref().and_then(|map| map.id),
+            });
+        }
+
         self.map = map;
         self.preset = preset;
     }
@@ -71,7 +242,9 @@ impl MapService for DefaultMapService {
         let platforms = self
             .map()
             .map(|data| {
-                data.platforms
+                self.platform_set_index
+                    .and_then(|index| data.platform_sets.get(index))
+                    .map_or(&data.platforms, |set| &set.platforms)
                     .iter()
                     .copied()
                     .map(Platform::from)
@@ -79,12 +252,14 @@ impl MapService for DefaultMapService {
             })
             .unwrap_or_default();
         minimap_context.set_platforms(platforms);
+        minimap_context.set_robust(self.map().is_some_and(|map| map.robust_detection));
 
         player_context.reset();
         if let Some(minimap) = self.map() {
             player_context.config.rune_platforms_pathing = minimap.rune_platforms_pathing;
             player_context.config.rune_platforms_pathing_up_jump_only =
                 minimap.rune_platforms_pathing_up_jump_only;
+            player_context.config.rune_retry_policy = minimap.rune_retry_policy;
             player_context.config.auto_mob_platforms_pathing = minimap.auto_mob_platforms_pathing;
             player_context
                 .config
@@ -103,17 +278,225 @@ impl MapService for DefaultMapService {
     fn redetect(&self, minimap: &mut MinimapEntity) {
         minimap.state = Minimap::Detecting;
     }
+
+    fn poll_external_preset(&mut self) -> Option<Map> {
+        let external = self.external_preset.as_mut()?;
+        if external
+            .last_checked
+            .is_some_and(|instant| instant.elapsed() < EXTERNAL_PRESET_CHECK_INTERVAL)
+        {
+            return None;
+        }
+        external.last_checked = Some(Instant::now());
+
+        let modified = match fs::metadata(&external.path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => modified,
+            Err(err) => {
+                if external.error.is_none() {
+                    warn!(target: "map", "unable to access external preset file {err}");
+                }
+                // Reloads when the file is accessible again
+                external.modified = None;
+                external.error = Some(format!("Unable to access file: {err}"));
+                return None;
+            }
+        };
+        if external.modified == Some(modified) {
+            return None;
+        }
+        external.modified = Some(modified);
+
+        let actions = match fs::read(&external.path)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| parse_external_actions(&bytes))
+        {
+            Ok(actions) => actions,
+            Err(err) => {
+                warn!(target: "map", "invalid external preset file {err}");
+                external.error = Some(format!("Invalid file: {err}"));
+                return None;
+            }
+        };
+        external.error = None;
+
+        let preset = self.preset.clone()?;
+        let mut map = self.map.clone()?;
+        if map.actions.get(&preset) == Some(&actions) {
+            return None;
+        }
+        info!(target: "map", "reloaded preset {preset} from external file");
+        map.actions.insert(preset, actions);
+
+        Some(map)
+    }
+
+    fn external_preset_error(&self) -> Option<String> {
+        self.external_preset
+            .as_ref()
+            .and_then(|external| external.error.clone())
+    }
+
+    fn poll_platform_set(&mut self, resources: &Resources, minimap_context: &mut MinimapContext) {
+        let Some(map) = self.map.as_ref() else {
+            return;
+        };
+        let next_index = self.platform_set_index.map_or(0, |index| index + 1);
+        let Some(set) = map.platform_sets.get(next_index) else {
+            return;
+        };
+        let started = *self.platform_set_started.get_or_insert_with(Instant::now);
+        let triggered = match &set.trigger {
+            PlatformSetTrigger::None => false,
+            PlatformSetTrigger::AfterMillis(millis) => {
+                started.elapsed() >= Duration::from_millis(*millis)
+            }
+            PlatformSetTrigger::Template(base64) => {
+                let base64 = base64.clone();
+                let update = update_detection_task(
+                    resources,
+                    PLATFORM_SET_DETECTION_INTERVAL_MILLIS,
+                    &mut self.platform_set_task,
+                    move |detector| {
+                        // Cached to avoid decoding the same template on every detection
+                        let template = to_cached_mat_from_base64_any_color(&base64)?;
+                        let (_, score) = detector.detect_template_match(&template)?;
+                        Ok(score >= PLATFORM_SET_TEMPLATE_THRESHOLD)
+                    },
+                );
+                matches!(update, Update::Ok(true))
+            }
+        };
+        if !triggered {
+            return;
+        }
+
+        info!(target: "map", "switching to platform set {}", set.name);
+        minimap_context.set_platforms(
+            set.platforms
+                .iter()
+                .copied()
+                .map(Platform::from)
+                .collect::<Vec<_>>(),
+        );
+        self.platform_set_index = Some(next_index);
+        self.platform_set_started = Some(Instant::now());
+        self.platform_set_task = None;
+    }
+
+    fn poll_auto_save(&mut self) {
+        let Some(path) = self.auto_save_path.as_ref() else {
+            return;
+        };
+        if !self.dirty
+            || self
+                .last_auto_saved
+                .is_some_and(|instant| instant.elapsed() < AUTO_SAVE_INTERVAL)
+        {
+            return;
+        }
+        self.last_auto_saved = Some(Instant::now());
+
+        let Some(recovery) = self.preset_to_recover() else {
+            self.dirty = false;
+            return;
+        };
+        if is_recovery_stored(&recovery) {
+            self.dirty = false;
+            let _ = fs::remove_file(path);
+            return;
+        }
+
+        // Writes the recovery file first in case storing in the database does not complete
+        if let Err(err) = serde_json::to_vec(&recovery)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| fs::write(path, bytes).map_err(anyhow::Error::from))
+        {
+            warn!(target: "map", "unable to write preset recovery file {err}");
+        }
+        // Fails instead of overwriting if the map was modified since it was applied. The
+        // recovery file is then kept to be recovered on the next start.
+        let mut map = self.map.clone().expect("has map if has preset to recover");
+        match upsert_map(&mut map) {
+            // Removes the recovery file on the next poll once stored
+            Ok(()) => info!(target: "map", "auto-saved preset {}", recovery.preset),
+            Err(err) => {
+                warn!(target: "map", "unable to auto-save preset {err}");
+                self.dirty = false;
+            }
+        }
+    }
+
+    fn recovery(&self) -> Option<PresetRecovery> {
+        self.recovery.clone()
+    }
+
+    fn recover(&mut self, restore: bool) {
+        let Some(recovery) = self.recovery.take() else {
+            return;
+        };
+        if restore {
+            // Applied through `DatabaseEvent::MapUpdated` like any other map update
+            let result = query_map(recovery.map_id).and_then(|map| {
+                let mut map = map.ok_or(anyhow!("map was deleted"))?;
+                upsert_map_with(&mut map, |map| {
+                    map.actions
+                        .insert(recovery.preset.clone(), recovery.actions.clone());
+                })
+            });
+            match result {
+                Ok(()) => info!(target: "map", "restored preset {}", recovery.preset),
+                Err(err) => warn!(target: "map", "unable to restore preset {err}"),
+            }
+        }
+        // Keeps the recovery file if it has been overwritten by newer unsaved edits
+        if !self.dirty
+            && let Some(path) = self.auto_save_path.as_ref()
+        {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Loads the auto-saved preset from `path` if its edits are not stored in the database.
+fn load_recovery(path: &Path) -> Option<PresetRecovery> {
+    let bytes = fs::read(path).ok()?;
+    let recovery = serde_json::from_slice::<PresetRecovery>(&bytes).ok()?;
+
+    (!is_recovery_stored(&recovery)).then_some(recovery)
+}
+
+/// Whether the actions of `recovery` are already stored in the database.
+///
+/// A deleted map is considered stored as there is nothing left to restore to.
+fn is_recovery_stored(recovery: &PresetRecovery) -> bool {
+    match query_map(recovery.map_id) {
+        Ok(Some(map)) => map.actions.get(&recovery.preset) == Some(&recovery.actions),
+        Ok(None) => true,
+        Err(_) => false,
+    }
+}
+
+fn parse_external_actions(bytes: &[u8]) -> Result<Vec<Action>> {
+    let actions = serde_json::from_slice::<Vec<Action>>(bytes)?;
+    if actions
+        .first()
+        .is_some_and(|action| matches!(action.condition(), ActionCondition::Linked))
+    {
+        bail!("first action cannot be a linked action");
+    }
+
+    Ok(actions)
 }
 
 #[cfg(test)]
 mod tests {
-    use std::assert_matches::assert_matches;
+    use std::{assert_matches::assert_matches, env};
 
     use opencv::core::Rect;
 
     use super::*;
     use crate::{
-        Platform as DatabasePlatform,
+        ActionKey, Platform as DatabasePlatform, PlatformSet,
         minimap::{Minimap, MinimapIdle},
         pathing::Platform,
     };
@@ -141,7 +524,11 @@ mod tests {
     fn create_returns_some_when_idle_minimap() {
         let service = DefaultMapService::default();
 
-        let result = service.create(mock_idle_minimap(), "MapData".to_string());
+        let result = service.create(
+            &Resources::new(None, None),
+            mock_idle_minimap(),
+            "MapData".to_string(),
+        );
 
         assert!(result.is_some());
         assert_eq!(
@@ -159,7 +546,11 @@ mod tests {
     fn create_returns_none_when_not_idle_minimap() {
         let service = DefaultMapService::default();
 
-        let result = service.create(Minimap::Detecting, "ShouldNotExist".to_string());
+        let result = service.create(
+            &Resources::new(None, None),
+            Minimap::Detecting,
+            "ShouldNotExist".to_string(),
+        );
 
         assert!(result.is_none());
     }
@@ -230,6 +621,7 @@ mod tests {
         let service = DefaultMapService {
             map: Some(mock_minimap_data()),
             preset: Some("preset".to_string()),
+            ..Default::default()
         };
         let mut minimap_context = MinimapContext::default();
         let mut player_state = PlayerContext::default();
@@ -241,4 +633,120 @@ mod tests {
         assert!(player_state.config.auto_mob_platforms_pathing);
         assert!(player_state.config.auto_mob_platforms_bound);
     }
+
+    #[test]
+    fn parse_external_actions_rejects_invalid_content() {
+        let linked = serde_json::to_vec(&vec![Action::Key(ActionKey {
+            condition: ActionCondition::Linked,
+            ..Default::default()
+        })])
+        .unwrap();
+
+        assert!(parse_external_actions(b"not json").is_err());
+        assert!(parse_external_actions(&linked).is_err());
+        assert!(parse_external_actions(b"[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn poll_external_preset_reloads_changed_file() {
+        let path = env::temp_dir().join("poll_external_preset_reloads_changed_file.json");
+        let actions = vec![Action::Key(ActionKey::default())];
+        fs::write(&path, serde_json::to_vec(&actions).unwrap()).unwrap();
+        let mut map = mock_minimap_data();
+        map.actions_external_files
+            .insert("preset".to_string(), path.to_string_lossy().into_owned());
+        let mut service = DefaultMapService::default();
+        service.update_map_preset(Some(map), Some("preset".to_string()));
+
+        let reloaded = service.poll_external_preset();
+        let polled_again = service.poll_external_preset();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(reloaded.unwrap().actions.get("preset"), Some(&actions));
+        assert!(polled_again.is_none());
+        assert!(service.external_preset_error().is_none());
+    }
+
+    #[test]
+    fn poll_external_preset_sets_error_when_missing_file() {
+        let mut map = mock_minimap_data();
+        map.actions_external_files.insert(
+            "preset".to_string(),
+            "poll_external_preset_sets_error_when_missing_file.json".to_string(),
+        );
+        let mut service = DefaultMapService::default();
+        service.update_map_preset(Some(map), Some("preset".to_string()));
+
+        assert!(service.poll_external_preset().is_none());
+        assert!(service.external_preset_error().is_some());
+    }
+
+    #[test]
+    fn poll_platform_set_switches_in_order() {
+        let set_platform = DatabasePlatform {
+            x_start: 5,
+            x_end: 10,
+            y: 20,
+        };
+        let mut map = mock_minimap_data();
+        map.platform_sets = vec![
+            PlatformSet {
+                name: "Phase 2".to_string(),
+                platforms: vec![set_platform],
+                trigger: PlatformSetTrigger::AfterMillis(0),
+            },
+            PlatformSet {
+                name: "Phase 3".to_string(),
+                platforms: vec![],
+                trigger: PlatformSetTrigger::None,
+            },
+        ];
+        let resources = Resources::new(None, None);
+        let mut minimap_context = MinimapContext::default();
+        let mut player_context = PlayerContext::default();
+        let mut service = DefaultMapService::default();
+        service.update_map_preset(Some(map.clone()), Some("preset".to_string()));
+
+        service.poll_platform_set(&resources, &mut minimap_context);
+        service.poll_platform_set(&resources, &mut minimap_context);
+        assert_eq!(service.platform_set_index, Some(0));
+        assert_eq!(minimap_context.platforms(), &[Platform::from(set_platform)]);
+
+        // Re-applying the same map keeps the active set
+        service.update_map_preset(Some(map), Some("preset".to_string()));
+        service.apply(&mut minimap_context, &mut player_context);
+        assert_eq!(minimap_context.platforms(), &[Platform::from(set_platform)]);
+    }
+
+    #[test]
+    fn update_map_preset_tracks_unsaved_preset_edits() {
+        let mut map = mock_minimap_data();
+        map.id = Some(1);
+        map.actions.insert("preset".to_string(), vec![]);
+        let mut edited = map.clone();
+        edited.actions.insert(
+            "preset".to_string(),
+            vec![Action::Key(ActionKey::default())],
+        );
+        let mut service = DefaultMapService::default();
+
+        service.update_map_preset(Some(map.clone()), Some("preset".to_string()));
+        assert!(!service.dirty);
+
+        service.update_map_preset(Some(edited.clone()), Some("preset".to_string()));
+        assert!(service.dirty);
+        assert_eq!(
+            service.preset_to_recover(),
+            Some(PresetRecovery {
+                map_id: 1,
+                map_name: "MapData".to_string(),
+                preset: "preset".to_string(),
+                actions: vec![Action::Key(ActionKey::default())],
+            })
+        );
+
+        // Switching preset is not an edit
+        service.update_map_preset(Some(edited), Some("other".to_string()));
+        assert!(!service.dirty);
+    }
 }
@@ -14,24 +14,32 @@ use tokio::sync::broadcast::Receiver;
 #[cfg(debug_assertions)]
 use crate::services::debug::DebugService;
 use crate::{
-    Localization, Settings,
+    ErrorEvent, Localization, PRIMARY_INSTANCE, SavedOperation, Settings,
     bridge::{Capture, DefaultInputReceiver, Input},
+    dashboard,
+    database::{query_global_presets, query_input_macros, upsert_map_with},
     ecs::{Resources, World, WorldEvent},
     navigator::Navigator,
     rotator::Rotator,
+    scripting::Scripts,
     services::{
-        character::{CharacterService, DefaultCharacterService},
+        character::{CharacterMismatch, CharacterService, DefaultCharacterService},
         control::{ControlEventHandler, ControlService, DefaultControlService},
         game::{DefaultGameService, GameEventHandler, GameService},
         localization::{DefaultLocalizationService, LocalizationService},
+        low_health::{DefaultLowHealthService, LowHealthEventHandler, LowHealthService},
         map::{DefaultMapService, MapService},
         navigator::{DefaultNavigatorService, NavigatorService},
         operation::{DefaultOperationService, OperationEventHandler, OperationService},
+        potion_stock::{DefaultPotionStockService, PotionStockEventHandler, PotionStockService},
         rotator::{DefaultRotatorService, RotatorService},
+        scripting::{DefaultScriptingService, ScriptingEventHandler, ScriptingService},
         settings::{DefaultSettingsService, SettingsService},
+        stranger::{DefaultStrangerService, StrangerEventHandler, StrangerService},
         ui::{DefaultUiService, UiEventHandler, UiService},
         world::{DefaultWorldService, WorldEventHandler, WorldService},
     },
+    spectate, status,
 };
 
 mod character;
@@ -40,11 +48,15 @@ mod control;
 mod debug;
 mod game;
 mod localization;
+mod low_health;
 mod map;
 mod navigator;
 mod operation;
+mod potion_stock;
 mod rotator;
+mod scripting;
 mod settings;
+mod stranger;
 mod ui;
 mod world;
 
@@ -91,6 +103,7 @@ struct EventContext<'a> {
     pub rotator: &'a mut dyn Rotator,
     pub navigator: &'a mut dyn Navigator,
     pub capture: &'a mut dyn Capture,
+    pub world_service: &'a mut Box<dyn WorldService>,
     pub game_service: &'a mut Box<dyn GameService>,
     pub map_service: &'a mut Box<dyn MapService>,
     pub character_service: &'a mut Box<dyn CharacterService>,
@@ -100,6 +113,7 @@ struct EventContext<'a> {
     pub localization_service: &'a mut Box<dyn LocalizationService>,
     pub control_service: &'a mut Box<dyn ControlService>,
     pub operation_service: &'a mut Box<dyn OperationService>,
+    pub potion_stock_service: &'a mut Box<dyn PotionStockService>,
     pub ui_service: &'a mut Box<dyn UiService>,
     #[cfg(debug_assertions)]
     pub debug_service: &'a mut DebugService,
@@ -107,6 +121,8 @@ struct EventContext<'a> {
 
 #[derive(Debug)]
 pub struct Services {
+    /// The index of the bot instance these services belong to.
+    instance: usize,
     event_bus: EventBus,
     world: Box<dyn WorldService>,
     game: Box<dyn GameService>,
@@ -118,6 +134,10 @@ pub struct Services {
     localization: Box<dyn LocalizationService>,
     control: Box<dyn ControlService>,
     operation: Box<dyn OperationService>,
+    stranger: Box<dyn StrangerService>,
+    low_health: Box<dyn LowHealthService>,
+    potion_stock: Box<dyn PotionStockService>,
+    scripting: Box<dyn ScriptingService>,
     ui: Box<dyn UiService>,
     #[cfg(debug_assertions)]
     debug: DebugService,
@@ -128,12 +148,21 @@ impl Services {
         settings: Rc<RefCell<Settings>>,
         localization: Rc<RefCell<Arc<Localization>>>,
         event_rx: Receiver<WorldEvent>,
+        saved_operation: SavedOperation,
+        instance: usize,
     ) -> Self {
         let settings_service = DefaultSettingsService::new(settings.clone());
         let window = settings_service.selected_window();
         let input_rx = DefaultInputReceiver::new(window, InputKind::Focused);
-        let mut control = DefaultControlService::default();
+        let mut control = if instance == PRIMARY_INSTANCE {
+            DefaultControlService::default()
+        } else {
+            DefaultControlService::without_bot()
+        };
         control.update(&settings_service.settings());
+        let mut rotator = DefaultRotatorService::default();
+        rotator.update_global_presets(query_global_presets().unwrap_or_default());
+        rotator.update_input_macros(query_input_macros().unwrap_or_default());
 
         let mut event_bus = EventBus {
             handlers: HashMap::default(),
@@ -143,20 +172,32 @@ impl Services {
         event_bus.subscribe(ControlEventHandler);
         event_bus.subscribe(WorldEventHandler);
         event_bus.subscribe(OperationEventHandler);
+        event_bus.subscribe(StrangerEventHandler);
+        event_bus.subscribe(LowHealthEventHandler);
+        event_bus.subscribe(PotionStockEventHandler);
+        event_bus.subscribe(ScriptingEventHandler);
 
         Self {
+            instance,
             event_bus,
             world: Box::new(DefaultWorldService::new(event_rx)),
             game: Box::new(DefaultGameService::new(input_rx)),
-            map: Box::new(DefaultMapService::default()),
+            map: Box::new(DefaultMapService::new(instance)),
             character: Box::new(DefaultCharacterService::default()),
-            rotator: Box::new(DefaultRotatorService::default()),
+            rotator: Box::new(rotator),
             navigator: Box::new(DefaultNavigatorService),
             settings: Box::new(settings_service),
             localization: Box::new(DefaultLocalizationService::new(localization)),
             control: Box::new(control),
-            operation: Box::new(DefaultOperationService::default()),
-            ui: Box::new(DefaultUiService::default()),
+            operation: Box::new(DefaultOperationService::new(
+                saved_operation,
+                settings.borrow().operation_schedule,
+            )),
+            stranger: Box::new(DefaultStrangerService::default()),
+            low_health: Box::new(DefaultLowHealthService::default()),
+            potion_stock: Box::new(DefaultPotionStockService::default()),
+            scripting: Box::new(DefaultScriptingService::new(Scripts::load())),
+            ui: Box::new(DefaultUiService::new(instance)),
             #[cfg(debug_assertions)]
             debug: DebugService::default(),
         }
@@ -166,6 +207,32 @@ impl Services {
         self.settings.selected_window()
     }
 
+    /// Serves the web dashboard of game states to browsers connecting to `address`.
+    pub fn serve_dashboard(&mut self, address: String) {
+        self.game.keep_frame();
+        dashboard::serve(address, self.game.subscribe_state());
+    }
+
+    /// Writes the bot status to the file at `path` for streaming and recording tools.
+    pub fn write_status_file(&self, path: String) {
+        status::write(path, self.game.subscribe_state());
+    }
+
+    /// Serves game states and keys to spectating UI instances connecting to `address`.
+    pub fn serve_spectators(&mut self, address: String) {
+        self.game.keep_frame();
+        spectate::serve(
+            address,
+            self.game.subscribe_state(),
+            self.game.subscribe_key(),
+        );
+    }
+
+    /// Keeps encoding the minimap frame for listeners outside of the service (e.g. remote control).
+    pub fn keep_game_state_frame(&mut self) {
+        self.game.keep_frame();
+    }
+
     pub fn update_window(&mut self, input: &mut dyn Input, capture: &mut dyn Capture) {
         self.settings
             .apply_selected_window(input, self.game.input_receiver_mut(), capture);
@@ -199,12 +266,87 @@ impl Services {
         if let Some(event) = self.operation.poll(navigator) {
             events.push(Box::new(event));
         }
+        if let Some(event) = self.stranger.poll(
+            resources,
+            world,
+            self.map.map().map(|map| &map.stranger_policy),
+        ) {
+            events.push(Box::new(event));
+        }
+        if let Some(event) = self.low_health.poll(
+            resources,
+            world,
+            self.character
+                .character()
+                .map(|character| &character.low_health_policy),
+        ) {
+            events.push(Box::new(event));
+        }
+        if let Some(event) = self.potion_stock.poll(
+            resources,
+            world,
+            navigator,
+            self.character
+                .character()
+                .map(|character| &character.potion_stock),
+            self.map.map().and_then(|map| map.id),
+        ) {
+            events.push(Box::new(event));
+        }
+        if let Some(event) = self.scripting.poll(resources, world) {
+            events.push(Box::new(event));
+        }
         if let Some(event) = self.world.poll() {
             events.push(Box::new(event));
         }
         if let Some(event) = self.control.poll() {
             events.push(Box::new(event));
         }
+        if self.settings.settings().enable_character_auto_detect
+            && let Some(character) = self.character.poll_detected_character(resources)
+        {
+            self.ui.queue_update_character(Some(character));
+        }
+        if self.settings.settings().enable_character_verification {
+            let preset = self.map.preset();
+            let actions = self
+                .map
+                .map()
+                .zip(preset.as_ref())
+                .and_then(|(map, preset)| map.actions.get(preset))
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            match self.character.poll_verify_character(resources, actions) {
+                Some(CharacterMismatch::Matched(character)) => {
+                    self.ui.queue_update_character(Some(character));
+                }
+                Some(CharacterMismatch::Unmatched) => {
+                    self.operation.halt(resources, world, rotator, false);
+                }
+                None => (),
+            }
+        }
+        if let Some(mut map) = self.map.poll_external_preset()
+            && let Some(preset) = self.map.preset()
+        {
+            // Applied through `DatabaseEvent::MapUpdated` like any other map update. Re-applied
+            // to the stored map if it was modified but the event has not arrived yet.
+            let actions = map.actions.get(&preset).cloned().unwrap_or_default();
+            let _ = upsert_map_with(&mut map, |map| {
+                map.actions.insert(preset.clone(), actions.clone());
+            });
+        }
+        self.map
+            .poll_platform_set(resources, &mut world.minimap.context);
+        self.map.poll_auto_save();
+        if self.rotator.poll_time_slice(self.map.map()) {
+            self.rotator.apply(
+                rotator,
+                self.map.map(),
+                self.character.character(),
+                &self.settings.settings(),
+            );
+        }
         #[cfg(debug_assertions)]
         self.debug.poll(resources);
 
@@ -214,6 +356,7 @@ impl Services {
             rotator,
             navigator,
             capture,
+            world_service: &mut self.world,
             game_service: &mut self.game,
             map_service: &mut self.map,
             character_service: &mut self.character,
@@ -223,6 +366,7 @@ impl Services {
             localization_service: &mut self.localization,
             control_service: &mut self.control,
             operation_service: &mut self.operation,
+            potion_stock_service: &mut self.potion_stock,
             ui_service: &mut self.ui,
             #[cfg(debug_assertions)]
             debug_service: &mut self.debug,
@@ -231,11 +375,28 @@ impl Services {
             debug!(target: "services", "processing event {event:?}");
             self.event_bus.emit(&mut context, event);
         }
+        // Only the primary instance resumes its operation on restart
+        if self.instance == PRIMARY_INSTANCE
+            && context
+                .operation_service
+                .persist(context.resources.operation)
+                .is_err()
+        {
+            context
+                .resources
+                .errors
+                .report(ErrorEvent::DatabaseWriteFailed);
+        }
 
         context.game_service.broadcast_state(
             context.resources,
             context.world,
             context.map_service.map(),
+            context.rotator.next_priority_action(),
+            context.rotator.pending_normal_positions(),
+            context.rotator.priority_actions_dropped(),
+            context.map_service.external_preset_error(),
+            context.character_service.detected_character_id(),
         );
     }
 }
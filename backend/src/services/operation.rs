@@ -1,13 +1,20 @@
-use std::{fmt::Debug, time::Duration};
+use std::{
+    fmt::Debug,
+    time::{Duration, SystemTime},
+};
 
+use anyhow::Result;
+use log::{info, warn};
 use tokio::{spawn, task::JoinHandle, time::sleep};
 
 use super::EventContext;
 use crate::{
-    BotOperationUpdate, Settings,
+    BotOperationUpdate, OperationSchedule, SavedOperation, Settings,
+    database::{query_or_upsert_operation_state, upsert_operation_state},
     ecs::{Resources, World},
     navigator::Navigator,
-    operation::Operation,
+    notification::NotificationKind,
+    operation::{Operation, ScheduleBoundary, schedule_boundary_crossed, schedule_minute_of_day},
     player::{Panic, PanicTo, PlayerAction},
     rotator::Rotator,
     services::{Event, EventHandler},
@@ -18,6 +25,10 @@ const PENDING_HALT_SECS: u64 = 12;
 #[derive(Debug)]
 pub enum OperationEvent {
     Halt,
+    /// The start boundary of [`OperationSchedule`] is crossed.
+    ScheduledRun,
+    /// The end boundary of [`OperationSchedule`] is crossed.
+    ScheduledHalt,
 }
 
 impl Event for OperationEvent {}
@@ -28,6 +39,8 @@ pub trait OperationService: Debug {
     fn poll(&mut self, navigator: &dyn Navigator) -> Option<OperationEvent>;
 
     /// Applies the provided `update` to other arguments.
+    ///
+    /// [`BotOperationUpdate::Schedule`] only replaces the schedule polled by [`Self::poll`].
     fn apply(
         &mut self,
         resources: &mut Resources,
@@ -48,14 +61,68 @@ pub trait OperationService: Debug {
 
     /// Queues a halt that results in a [`OperationEvent::Halt`] when the timer ends.
     fn queue_halt(&mut self);
+
+    /// Gets the operation saved before the last restart that is pending to be resumed.
+    fn resumable(&self) -> Option<Operation>;
+
+    /// Resumes the operation from [`Self::resumable`] if `resume` is true or discards it.
+    fn resume(&mut self, resources: &mut Resources, resume: bool);
+
+    /// Persists `operation` if it has changed since the last time it was persisted.
+    ///
+    /// While there is a [`Self::resumable`] operation, [`Operation::Halting`] is not persisted
+    /// so that the saved operation is not lost before it is resumed or discarded.
+    ///
+    /// Returns an error if writing to the database failed.
+    fn persist(&mut self, operation: Operation) -> Result<()>;
 }
 
 #[derive(Debug, Default)]
 pub struct DefaultOperationService {
     pending_halt: Option<JoinHandle<()>>,
+    resumable: Option<SavedOperation>,
+    last_persisted: Option<Operation>,
+    schedule: OperationSchedule,
+    /// The minute of the day [`Self::schedule`] was last polled at.
+    schedule_last_minute: Option<u32>,
 }
 
 impl DefaultOperationService {
+    pub fn new(saved: SavedOperation, schedule: OperationSchedule) -> Self {
+        Self {
+            resumable: (!matches!(saved, SavedOperation::Halting)).then_some(saved),
+            schedule,
+            ..Default::default()
+        }
+    }
+
+    fn poll_schedule(&mut self) -> Option<OperationEvent> {
+        let minute = schedule_minute_of_day(self.schedule, SystemTime::now());
+        let last_minute = self.schedule_last_minute.replace(minute)?;
+
+        match schedule_boundary_crossed(self.schedule, last_minute, minute)? {
+            ScheduleBoundary::Start => Some(OperationEvent::ScheduledRun),
+            ScheduleBoundary::End => Some(OperationEvent::ScheduledHalt),
+        }
+    }
+
+    /// Writes `operation` with `write` and remembers it as persisted only if writing succeeded.
+    ///
+    /// A failed write is retried on the next [`OperationService::persist`].
+    fn persist_with(
+        &mut self,
+        operation: Operation,
+        write: impl FnOnce(SavedOperation) -> Result<()>,
+    ) -> Result<()> {
+        if let Err(err) = write(operation.into()) {
+            warn!(target: "operation", "failed to persist operation {err}");
+            return Err(err);
+        }
+        self.last_persisted = Some(operation);
+
+        Ok(())
+    }
+
     fn clear_states(&mut self, world: &mut World, rotator: &mut dyn Rotator, should_idle: bool) {
         rotator.reset_queue();
         world.player.context.clear_actions_aborted(should_idle);
@@ -78,7 +145,7 @@ impl OperationService for DefaultOperationService {
             }
         }
 
-        None
+        self.poll_schedule()
     }
 
     fn apply(
@@ -89,6 +156,15 @@ impl OperationService for DefaultOperationService {
         settings: &Settings,
         update: BotOperationUpdate,
     ) {
+        if let BotOperationUpdate::Schedule(schedule) = update {
+            if self.schedule != schedule {
+                info!(target: "operation", "schedule updated to {schedule:?}");
+                self.schedule = schedule;
+                self.schedule_last_minute = None;
+            }
+            return;
+        }
+
         let cycle_run_stop = settings.cycle_run_stop;
         let cycle_run_duration_millis = settings.cycle_run_duration_millis;
         let cycle_stop_duration_millis = settings.cycle_stop_duration_millis;
@@ -120,10 +196,16 @@ impl OperationService for DefaultOperationService {
 
         if !resources.operation.halting() {
             resources.operation = Operation::Halting;
+            let _ = resources
+                .notification
+                .schedule_notification(NotificationKind::BotHalt);
         }
 
         if go_to_town {
-            rotator.inject_action(PlayerAction::Panic(Panic { to: PanicTo::Town }));
+            rotator.inject_action(PlayerAction::Panic(Panic {
+                to: PanicTo::Town,
+                skip_channels: 0,
+            }));
         }
     }
 
@@ -132,6 +214,40 @@ impl OperationService for DefaultOperationService {
             sleep(Duration::from_secs(PENDING_HALT_SECS)).await;
         }));
     }
+
+    fn resumable(&self) -> Option<Operation> {
+        self.resumable.map(Operation::from)
+    }
+
+    fn resume(&mut self, resources: &mut Resources, resume: bool) {
+        let Some(saved) = self.resumable.take() else {
+            return;
+        };
+
+        if resume {
+            resources.operation = Operation::from(saved);
+            info!(target: "operation", "resumed saved operation {}", resources.operation);
+        }
+    }
+
+    fn persist(&mut self, operation: Operation) -> Result<()> {
+        if self.last_persisted == Some(operation) {
+            return Ok(());
+        }
+        if self.resumable.is_some() {
+            if matches!(operation, Operation::Halting) {
+                return Ok(());
+            }
+            // Operation was changed by the user instead of resuming
+            self.resumable = None;
+        }
+
+        self.persist_with(operation, |saved| {
+            let mut state = query_or_upsert_operation_state()?;
+            state.operation = saved;
+            upsert_operation_state(&mut state)
+        })
+    }
 }
 
 pub struct OperationEventHandler;
@@ -145,6 +261,63 @@ impl EventHandler<OperationEvent> for OperationEventHandler {
                 context.rotator,
                 true,
             ),
+            OperationEvent::ScheduledRun => {
+                if !context.resources.operation.halting()
+                    || context.map_service.map().is_none()
+                    || context.character_service.character().is_none()
+                {
+                    return;
+                }
+                context.character_service.queue_verify_character();
+                info!(target: "operation", "running from schedule");
+                context.operation_service.apply(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    &context.settings_service.settings(),
+                    BotOperationUpdate::Run,
+                );
+            }
+            OperationEvent::ScheduledHalt => {
+                info!(target: "operation", "halting from schedule");
+                context.operation_service.halt(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    false,
+                );
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn persist_with_retries_after_failed_write() {
+        let mut service = DefaultOperationService::default();
+
+        assert!(
+            service
+                .persist_with(Operation::Halting, |_| Err(anyhow!("failed")))
+                .is_err()
+        );
+        assert_eq!(service.last_persisted, None);
+
+        let mut written = None;
+        assert!(
+            service
+                .persist_with(Operation::Halting, |saved| {
+                    written = Some(saved);
+                    Ok(())
+                })
+                .is_ok()
+        );
+        assert_eq!(written, Some(SavedOperation::Halting));
+        assert_eq!(service.last_persisted, Some(Operation::Halting));
+    }
+}
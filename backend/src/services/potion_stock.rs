@@ -0,0 +1,491 @@
+use std::{
+    fmt::Debug,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use log::{info, warn};
+
+use super::EventContext;
+use crate::{
+    ActionKeyDirection, ActionKeyWith, Position, PotionRestock, PotionStock, WaitAfterBuffered,
+    bridge::LinkKeyKind,
+    ecs::{Resources, World},
+    navigator::Navigator,
+    notification::NotificationKind,
+    player::{BuyPotion, Key, PlayerAction},
+    services::{Event, EventHandler},
+    task::{Task, TaskPriority, Update, update_detection_task_with_priority},
+};
+
+const VERIFY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The maximum duration to travel to the potion shop map before the restock fails.
+const RESTOCK_TRAVEL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// The maximum duration to buy potions after arriving before the restock fails.
+const RESTOCK_SHOP_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub enum PotionStockEvent {
+    /// The estimated remaining potion count has reached [`PotionStock::low_count`].
+    Low { remaining: u32, halt: bool },
+    /// Travels to the potion shop map for [`PotionRestock`].
+    Travel(i64),
+    /// Buys `quantity` potions from the shop NPC at `(x, y)`.
+    Shop { x: i32, y: i32, quantity: u32 },
+    /// Travels back to the map the [`PotionRestock`] started from.
+    Return(i64),
+}
+
+impl Event for PotionStockEvent {}
+
+/// A service to estimate the remaining potion count for the character's [`PotionStock`].
+pub trait PotionStockService: Debug {
+    /// Polls for a [`PotionStockEvent`] from the current potion uses.
+    ///
+    /// `map_id` is the id of the current map the [`PotionRestock`] returns to.
+    fn poll(
+        &mut self,
+        resources: &Resources,
+        world: &World,
+        navigator: &dyn Navigator,
+        policy: Option<&PotionStock>,
+        map_id: Option<i64>,
+    ) -> Option<PotionStockEvent>;
+
+    /// Fails the on-going [`PotionRestock`] so that the stock is reported low instead.
+    fn fail_restock(&mut self);
+}
+
+/// Phases of an on-going [`PotionRestock`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Restock {
+    /// Traveling to [`PotionRestock::map_id`].
+    Traveling { return_map_id: i64, since: Instant },
+    /// Buying potions from the shop NPC.
+    ///
+    /// The potions are bought once the number of player potion restocks differs from `restocks`.
+    Shopping {
+        return_map_id: i64,
+        since: Instant,
+        restocks: u32,
+    },
+}
+
+#[derive(Debug, Default)]
+pub struct DefaultPotionStockService {
+    /// The [`PotionStock::starting_count`] the current baseline is from.
+    starting_count: Option<u32>,
+    /// The last known potion count and the number of potion uses at that time.
+    baseline: (u32, u32),
+    /// Whether [`PotionStockEvent::Low`] has been returned or a restock started for the current
+    /// low stock.
+    reported_low: bool,
+    /// The last time the potion count was verified.
+    last_verified: Option<Instant>,
+    /// The task to detect the potion count in the quick slots.
+    verify_task: Option<Task<Result<u32>>>,
+    /// The on-going restock.
+    restock: Option<Restock>,
+    /// Whether the last restock failed for the current low stock.
+    restock_failed: bool,
+}
+
+impl DefaultPotionStockService {
+    fn remaining(&self, uses: u32) -> u32 {
+        let (count, baseline_uses) = self.baseline;
+        count.saturating_sub(uses.wrapping_sub(baseline_uses))
+    }
+
+    fn update(
+        &mut self,
+        policy: &PotionStock,
+        uses: u32,
+        verified_count: Option<u32>,
+    ) -> Option<PotionStockEvent> {
+        if self.starting_count != Some(policy.starting_count) {
+            self.starting_count = Some(policy.starting_count);
+            self.baseline = (policy.starting_count, uses);
+            self.reported_low = false;
+            self.restock_failed = false;
+        }
+        if let Some(count) = verified_count {
+            let estimated = self.remaining(uses);
+            if estimated != count {
+                info!(
+                    target: "potion_stock",
+                    "corrected estimated potion count {estimated} to {count}"
+                );
+            }
+            self.baseline = (count, uses);
+        }
+
+        let remaining = self.remaining(uses);
+        if remaining > policy.low_count {
+            self.reported_low = false;
+            self.restock_failed = false;
+            return None;
+        }
+        if self.reported_low {
+            return None;
+        }
+        self.reported_low = true;
+
+        Some(PotionStockEvent::Low {
+            remaining,
+            halt: policy.halt_on_low,
+        })
+    }
+
+    fn start_restock(
+        &mut self,
+        now: Instant,
+        restock: &PotionRestock,
+        map_id: Option<i64>,
+    ) -> Option<PotionStockEvent> {
+        if !restock.enabled || self.restock_failed {
+            return None;
+        }
+        let shop_map_id = restock.map_id?;
+        let return_map_id = map_id?;
+
+        info!(target: "potion_stock", "potion stock is low, restocking from map {shop_map_id}");
+        self.restock = Some(Restock::Traveling {
+            return_map_id,
+            since: now,
+        });
+        Some(PotionStockEvent::Travel(shop_map_id))
+    }
+
+    fn update_restock(
+        &mut self,
+        now: Instant,
+        restock: &PotionRestock,
+        uses: u32,
+        restocks: u32,
+        arrived: bool,
+    ) -> Option<PotionStockEvent> {
+        match self.restock? {
+            Restock::Traveling {
+                return_map_id,
+                since,
+            } => {
+                if arrived {
+                    self.restock = Some(Restock::Shopping {
+                        return_map_id,
+                        since: now,
+                        restocks,
+                    });
+                    return Some(PotionStockEvent::Shop {
+                        x: restock.npc_x,
+                        y: restock.npc_y,
+                        quantity: restock.quantity,
+                    });
+                }
+                if now.duration_since(since) >= RESTOCK_TRAVEL_TIMEOUT {
+                    warn!(target: "potion_stock", "timed out traveling to the potion shop");
+                    self.fail_restock();
+                    return Some(PotionStockEvent::Return(return_map_id));
+                }
+            }
+            Restock::Shopping {
+                return_map_id,
+                since,
+                restocks: last_restocks,
+            } => {
+                if restocks != last_restocks {
+                    info!(target: "potion_stock", "bought {} potions", restock.quantity);
+                    self.restock = None;
+                    self.baseline = (self.remaining(uses) + restock.quantity, uses);
+                    return Some(PotionStockEvent::Return(return_map_id));
+                }
+                if now.duration_since(since) >= RESTOCK_SHOP_TIMEOUT {
+                    warn!(target: "potion_stock", "timed out buying potions from the shop");
+                    self.fail_restock();
+                    return Some(PotionStockEvent::Return(return_map_id));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn return_map_id(&self) -> Option<i64> {
+        match self.restock? {
+            Restock::Traveling { return_map_id, .. } | Restock::Shopping { return_map_id, .. } => {
+                Some(return_map_id)
+            }
+        }
+    }
+}
+
+impl PotionStockService for DefaultPotionStockService {
+    fn poll(
+        &mut self,
+        resources: &Resources,
+        world: &World,
+        navigator: &dyn Navigator,
+        policy: Option<&PotionStock>,
+        map_id: Option<i64>,
+    ) -> Option<PotionStockEvent> {
+        let Some(policy) = policy.filter(|policy| policy.enabled) else {
+            let return_map_id = self.return_map_id();
+            *self = DefaultPotionStockService::default();
+            return return_map_id.map(PotionStockEvent::Return);
+        };
+        let uses = world.player.context.potion_uses();
+        let now = Instant::now();
+
+        if resources.operation.halting() {
+            // Restocks again when resumed
+            let return_map_id = self.return_map_id()?;
+            self.restock = None;
+            self.reported_low = false;
+            return Some(PotionStockEvent::Return(return_map_id));
+        }
+        if self.restock.is_some() {
+            return self.update_restock(
+                now,
+                &policy.restock,
+                uses,
+                world.player.context.potion_restocks(),
+                navigator.has_reached_destination(),
+            );
+        }
+
+        let should_verify = policy.verify_with_quick_slots
+            && self
+                .last_verified
+                .is_none_or(|instant| instant.elapsed() >= VERIFY_INTERVAL);
+        let verified_count = if should_verify {
+            match update_detection_task_with_priority(
+                resources,
+                TaskPriority::Low,
+                1000,
+                &mut self.verify_task,
+                |detector| detector.detect_quick_slots_potion_count(),
+            ) {
+                Update::Ok(count) => {
+                    self.last_verified = Some(now);
+                    Some(count)
+                }
+                Update::Err(_) => {
+                    self.last_verified = Some(now);
+                    None
+                }
+                Update::Pending => None,
+            }
+        } else {
+            None
+        };
+
+        let event = self.update(policy, uses, verified_count)?;
+        self.start_restock(now, &policy.restock, map_id)
+            .or(Some(event))
+    }
+
+    fn fail_restock(&mut self) {
+        self.restock = None;
+        self.restock_failed = true;
+        self.reported_low = false;
+    }
+}
+
+pub struct PotionStockEventHandler;
+
+impl EventHandler<PotionStockEvent> for PotionStockEventHandler {
+    fn handle(&mut self, context: &mut EventContext<'_>, event: PotionStockEvent) {
+        match event {
+            PotionStockEvent::Low { remaining, halt } => {
+                warn!(target: "potion_stock", "potion stock is low with {remaining} remaining");
+                let _ = context
+                    .resources
+                    .notification
+                    .schedule_notification(NotificationKind::PotionStockLow);
+
+                if halt {
+                    context.operation_service.halt(
+                        context.resources,
+                        context.world,
+                        context.rotator,
+                        false,
+                    );
+                }
+            }
+            PotionStockEvent::Travel(map_id) => {
+                if !context.navigator.navigate_to(map_id) {
+                    warn!(target: "potion_stock", "potion shop map {map_id} is not navigable");
+                    context.potion_stock_service.fail_restock();
+                }
+            }
+            PotionStockEvent::Shop { x, y, quantity } => {
+                let position = Position {
+                    x,
+                    y,
+                    x_random_range: 0,
+                    allow_adjusting: true,
+                };
+                // Opens the shop by talking to the NPC
+                let key = Key {
+                    key: context.world.player.context.config.interact_key,
+                    key_hold_ticks: 0,
+                    key_hold_buffered_to_wait_after: false,
+                    link_key: LinkKeyKind::None,
+                    count: 1,
+                    position: Some(position),
+                    direction: ActionKeyDirection::Any,
+                    with: ActionKeyWith::Stationary,
+                    wait_before_use_ticks: 5,
+                    wait_before_use_ticks_random_range: 0,
+                    wait_after_use_ticks: 30,
+                    wait_after_use_ticks_random_range: 0,
+                    wait_after_buffered: WaitAfterBuffered::None,
+                };
+
+                context.rotator.set_suspended(true);
+                context.rotator.inject_action(PlayerAction::Key(key));
+                context
+                    .rotator
+                    .inject_action(PlayerAction::BuyPotion(BuyPotion { quantity }));
+            }
+            PotionStockEvent::Return(map_id) => {
+                context.rotator.set_suspended(false);
+                if !context.navigator.navigate_to(map_id) {
+                    warn!(target: "potion_stock", "map {map_id} to return to is not navigable");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+
+    fn policy() -> PotionStock {
+        PotionStock {
+            enabled: true,
+            starting_count: 100,
+            low_count: 10,
+            halt_on_low: false,
+            verify_with_quick_slots: false,
+            restock: PotionRestock {
+                enabled: true,
+                map_id: Some(2),
+                npc_x: 50,
+                npc_y: 10,
+                quantity: 200,
+            },
+        }
+    }
+
+    #[test]
+    fn update_counts_uses_since_starting_count() {
+        let policy = policy();
+        let mut service = DefaultPotionStockService::default();
+
+        assert_matches!(service.update(&policy, 5, None), None);
+        assert_eq!(service.remaining(5), 100);
+        assert_matches!(service.update(&policy, 94, None), None);
+        assert_eq!(service.remaining(94), 11);
+        assert_matches!(
+            service.update(&policy, 95, None),
+            Some(PotionStockEvent::Low {
+                remaining: 10,
+                halt: false
+            })
+        );
+        assert_matches!(service.update(&policy, 96, None), None);
+        assert_eq!(service.remaining(96), 9);
+
+        let policy = PotionStock {
+            starting_count: 50,
+            ..policy
+        };
+        assert_matches!(service.update(&policy, 96, None), None);
+        assert_eq!(service.remaining(96), 50);
+    }
+
+    #[test]
+    fn update_replaces_estimate_with_verified_count() {
+        let policy = policy();
+        let mut service = DefaultPotionStockService::default();
+
+        assert_matches!(service.update(&policy, 0, None), None);
+        assert_matches!(
+            service.update(&policy, 20, Some(8)),
+            Some(PotionStockEvent::Low {
+                remaining: 8,
+                halt: false
+            })
+        );
+        assert_matches!(service.update(&policy, 20, Some(200)), None);
+        assert_matches!(service.update(&policy, 30, None), None);
+        assert_eq!(service.remaining(30), 190);
+    }
+
+    #[test]
+    fn update_restock_travels_shops_and_returns() {
+        let policy = policy();
+        let mut service = DefaultPotionStockService::default();
+        let now = Instant::now();
+        assert_matches!(service.update(&policy, 95, None), Some(_));
+
+        assert_matches!(
+            service.start_restock(now, &policy.restock, Some(1)),
+            Some(PotionStockEvent::Travel(2))
+        );
+        assert_matches!(
+            service.update_restock(now, &policy.restock, 95, 0, false),
+            None
+        );
+        assert_matches!(
+            service.update_restock(now, &policy.restock, 95, 0, true),
+            Some(PotionStockEvent::Shop {
+                x: 50,
+                y: 10,
+                quantity: 200
+            })
+        );
+        assert_matches!(
+            service.update_restock(now, &policy.restock, 95, 0, true),
+            None
+        );
+        assert_matches!(
+            service.update_restock(now, &policy.restock, 95, 1, true),
+            Some(PotionStockEvent::Return(1))
+        );
+        assert_eq!(service.restock, None);
+        assert_eq!(service.remaining(95), 205);
+        assert_matches!(service.update(&policy, 95, None), None);
+    }
+
+    #[test]
+    fn update_restock_reports_low_after_failing() {
+        let policy = policy();
+        let mut service = DefaultPotionStockService::default();
+        let now = Instant::now();
+        assert_matches!(service.update(&policy, 95, None), Some(_));
+        assert_matches!(
+            service.start_restock(now, &policy.restock, Some(1)),
+            Some(_)
+        );
+        assert_matches!(
+            service.update_restock(now, &policy.restock, 95, 0, true),
+            Some(PotionStockEvent::Shop { .. })
+        );
+
+        assert_matches!(
+            service.update_restock(now + RESTOCK_SHOP_TIMEOUT, &policy.restock, 95, 0, true),
+            Some(PotionStockEvent::Return(1))
+        );
+        assert_matches!(
+            service.update(&policy, 95, None),
+            Some(PotionStockEvent::Low { remaining: 5, .. })
+        );
+        assert_matches!(service.start_restock(now, &policy.restock, Some(1)), None);
+    }
+}
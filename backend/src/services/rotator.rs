@@ -1,5 +1,9 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    time::{Duration, Instant},
+};
 
+use log::info;
 #[cfg(test)]
 use mockall::{automock, concretize};
 use strum::IntoEnumIterator;
@@ -7,11 +11,12 @@ use strum::IntoEnumIterator;
 use crate::bridge::KeyKind;
 use crate::rotator::{Rotator, RotatorMode};
 use crate::{
-    Action, Character, KeyBinding, Map, RotationMode, Settings, buff::BuffKind,
-    rotator::RotatorBuildArgs,
+    Action, Character, Familiars, GlobalPreset, InputMacro, KeyBinding, Map, RotationMode,
+    Settings, buff::BuffKind, rotator::RotatorBuildArgs,
 };
 use crate::{
-    ActionCondition, ActionConfigurationCondition, ActionKey, KeyBindingConfiguration, PotionMode,
+    ActionCondition, ActionConfigurationCondition, ActionKey, ActionKeyDirection, ActionsTimeSlice,
+    KeyBindingConfiguration, PotionMode,
 };
 
 /// A service to handle [`Rotator`]-related incoming requests.
@@ -29,6 +34,21 @@ pub trait RotatorService: Debug {
     #[cfg_attr(test, concretize)]
     fn update_buffs(&mut self, character: Option<&Character>);
 
+    /// Updates the [`GlobalPreset`]s that map presets can link to.
+    ///
+    /// [`Self::update_actions`] must be called afterward for the change to take effect.
+    fn update_global_presets(&mut self, presets: Vec<GlobalPreset>);
+
+    /// Updates the [`InputMacro`]s that actions can replay.
+    ///
+    /// [`Self::apply`] must be called afterward for the change to take effect.
+    fn update_input_macros(&mut self, input_macros: Vec<InputMacro>);
+
+    /// Rebuilds the actions list when the current slice of [`Map::actions_time_slices`] ends.
+    ///
+    /// Returns `true` if the actions list changed and needs to be applied again.
+    fn poll_time_slice<'a>(&mut self, map: Option<&'a Map>) -> bool;
+
     /// Updates `rotator` with data from `map`, `character`, `settings`, and the currently
     /// in-use actions and buffs.
     fn apply<'a>(
@@ -45,6 +65,14 @@ pub trait RotatorService: Debug {
 pub struct DefaultRotatorService {
     actions: Vec<Action>,
     buffs: Vec<(BuffKind, KeyKind)>,
+    /// Actions from [`Character`] kept for rebuilding [`Self::actions`] on time slice changes.
+    character_actions: Vec<Action>,
+    global_presets: Vec<GlobalPreset>,
+    input_macros: Vec<InputMacro>,
+    /// The in-use [`Map::actions_time_slices`], when they started and the current slice index.
+    time_slices: Option<(Vec<ActionsTimeSlice>, Instant, usize)>,
+    /// The name of the [`Map::familiars_setups`] of the in-use preset.
+    familiars_setup: Option<String>,
 }
 
 impl RotatorService for DefaultRotatorService {
@@ -54,13 +82,38 @@ impl RotatorService for DefaultRotatorService {
         preset: Option<String>,
         character: Option<&'a Character>,
     ) {
-        let character_actions = character.map(actions_from).unwrap_or_default();
+        self.character_actions = character.map(actions_from).unwrap_or_default();
+
+        let time_slices = map
+            .map(|map| map.actions_time_slices.clone())
+            .filter(|slices| time_slice_index(slices, Duration::ZERO).is_some());
+        let preset = match time_slices {
+            Some(slices) => {
+                // Keeps the clock of the same slices running (e.g. when only character changes)
+                let started = match self.time_slices.take() {
+                    Some((current, started, _)) if current == slices => started,
+                    _ => Instant::now(),
+                };
+                let index = time_slice_index(&slices, started.elapsed()).expect("has a slice");
+                let preset = slices[index].preset.clone();
+
+                self.time_slices = Some((slices, started, index));
+                Some(preset)
+            }
+            None => {
+                self.time_slices = None;
+                preset
+            }
+        };
+        self.familiars_setup = map
+            .zip(preset.as_ref())
+            .and_then(|(map, preset)| map.familiars_setups.get(preset).cloned());
         let map_actions = map
             .zip(preset)
-            .and_then(|(minimap, preset)| minimap.actions.get(&preset).cloned())
+            .map(|(map, preset)| preset_actions(map, &preset, &self.global_presets))
             .unwrap_or_default();
 
-        self.actions = [character_actions, map_actions].concat();
+        self.actions = [self.character_actions.clone(), map_actions].concat();
     }
 
     #[cfg_attr(test, concretize)]
@@ -68,6 +121,35 @@ impl RotatorService for DefaultRotatorService {
         self.buffs = character.map(buffs_from).unwrap_or_default();
     }
 
+    fn update_global_presets(&mut self, presets: Vec<GlobalPreset>) {
+        self.global_presets = presets;
+    }
+
+    fn update_input_macros(&mut self, input_macros: Vec<InputMacro>) {
+        self.input_macros = input_macros;
+    }
+
+    fn poll_time_slice<'a>(&mut self, map: Option<&'a Map>) -> bool {
+        let Some((slices, started, index)) = self.time_slices.as_mut() else {
+            return false;
+        };
+        let next_index = time_slice_index(slices, started.elapsed()).expect("has a slice");
+        if next_index == *index {
+            return false;
+        }
+
+        let preset = &slices[next_index].preset;
+        info!(target: "rotator", "switching to preset {preset} time slice");
+        let map_actions = map
+            .map(|map| preset_actions(map, preset, &self.global_presets))
+            .unwrap_or_default();
+
+        self.familiars_setup = map.and_then(|map| map.familiars_setups.get(preset).cloned());
+        *index = next_index;
+        self.actions = [self.character_actions.clone(), map_actions].concat();
+        true
+    }
+
     fn apply<'a>(
         &self,
         rotator: &mut dyn Rotator,
@@ -79,6 +161,22 @@ impl RotatorService for DefaultRotatorService {
         let reset_normal_actions_on_erda = map
             .map(|map| map.actions_any_reset_on_erda_condition)
             .unwrap_or_default();
+        let priority_actions_queue_max_depth = map
+            .map(|map| map.actions_priority_queue_max_depth)
+            .unwrap_or_default();
+        let disabled_tags_with_strangers = map
+            .map(|map| map.actions_disabled_tags_with_strangers.as_slice())
+            .unwrap_or_default();
+        let auto_mob_priority_classes = map
+            .map(|map| map.auto_mob_priority_classes.as_slice())
+            .unwrap_or_default();
+        let enable_auto_mob_tracking = map.map(|map| map.auto_mob_track_mobs).unwrap_or_default();
+        let enable_weighted_random_no_repeat = map
+            .map(|map| map.rotation_weighted_random_no_repeat)
+            .unwrap_or_default();
+        let burst_windows = map
+            .map(|map| map.actions_burst_windows.as_slice())
+            .unwrap_or_default();
         let familiar_essence_key = character
             .map(|character| character.familiar_essence_key.key)
             .unwrap_or_default();
@@ -88,6 +186,12 @@ impl RotatorService for DefaultRotatorService {
         let elite_boss_behavior_key = character
             .map(|character| character.elite_boss_behavior_key)
             .unwrap_or_default();
+        let elite_boss_rotation = character
+            .map(|character| character.elite_boss_rotation.as_slice())
+            .unwrap_or_default();
+        let elite_boss_rotation_move_to_boss = character
+            .map(|character| character.elite_boss_rotation_move_to_boss)
+            .unwrap_or_default();
         let hexa_booster_exchange_condition = character
             .map(|character| character.hexa_booster_exchange_condition)
             .unwrap_or_default();
@@ -104,25 +208,47 @@ impl RotatorService for DefaultRotatorService {
             .map(|character| character.hexa_booster_key.enabled)
             .unwrap_or_default();
         let familiars = character
-            .map(|character| character.familiars.clone())
+            .map(|character| familiars_from(character, self.familiars_setup.as_deref()))
+            .unwrap_or_default();
+        let feed_pet_key = character
+            .map(|character| character.feed_pet_key)
+            .unwrap_or_default();
+        let feed_pet_millis = character
+            .map(|character| character.feed_pet_millis)
             .unwrap_or_default();
+        let feed_pet_count = character
+            .map(|character| character.feed_pet_count)
+            .unwrap_or(1);
         let args = RotatorBuildArgs {
             mode,
             actions: &self.actions,
+            input_macros: &self.input_macros,
+            disabled_tags_with_strangers,
+            auto_mob_priority_classes,
+            burst_windows,
             buffs: &self.buffs,
             familiars,
             familiar_essence_key: familiar_essence_key.into(),
+            feed_pet_key: feed_pet_key.key.into(),
+            feed_pet_millis,
+            feed_pet_count,
             elite_boss_behavior,
             elite_boss_behavior_key: elite_boss_behavior_key.into(),
+            elite_boss_rotation,
+            elite_boss_rotation_move_to_boss,
             hexa_booster_exchange_condition,
             hexa_booster_exchange_amount,
             hexa_booster_exchange_all,
+            enable_feeding_pet: feed_pet_key.enabled,
             enable_panic_mode: settings.enable_panic_mode,
             enable_rune_solving: settings.enable_rune_solving,
             enable_transparent_shape_solving: settings.enable_transparent_shape_solving,
             enable_reset_normal_actions_on_erda: reset_normal_actions_on_erda,
             enable_using_generic_booster,
             enable_using_hexa_booster,
+            enable_auto_mob_tracking,
+            enable_weighted_random_no_repeat,
+            priority_actions_queue_max_depth,
         };
 
         rotator.build_actions(args);
@@ -134,16 +260,91 @@ fn rotator_mode_from(map: Option<&Map>) -> RotatorMode {
     map.map(|map| match map.rotation_mode {
         RotationMode::StartToEnd => RotatorMode::StartToEnd,
         RotationMode::StartToEndThenReverse => RotatorMode::StartToEndThenReverse,
-        RotationMode::AutoMobbing => {
-            RotatorMode::AutoMobbing(map.rotation_mobbing_key, map.rotation_auto_mob_bound)
-        }
+        RotationMode::AutoMobbing => RotatorMode::AutoMobbing(
+            map.rotation_mobbing_key,
+            map.rotation_auto_mob_bound,
+            map.auto_mob_quadrants,
+        ),
         RotationMode::PingPong => {
             RotatorMode::PingPong(map.rotation_mobbing_key, map.rotation_ping_pong_bound)
         }
+        RotationMode::WeightedRandom => RotatorMode::WeightedRandom,
     })
     .unwrap_or_default()
 }
 
+/// Gets the [`Familiars`] of `setup` in `character` or the character's own if there is none.
+fn familiars_from(character: &Character, setup: Option<&str>) -> Familiars {
+    setup
+        .and_then(|name| {
+            character
+                .familiars_setups
+                .iter()
+                .find(|setup| setup.name == name)
+        })
+        .map(|setup| setup.familiars.clone())
+        .unwrap_or_else(|| character.familiars.clone())
+}
+
+/// Gets the actions of `preset` in `map` with the actions of its linked global presets first.
+///
+/// Linked global presets that no longer exist are skipped. Key actions of
+/// [`ActionKeyDirection::Any`] are resolved to the preset's preferred direction if any.
+fn preset_actions(map: &Map, preset: &str, global_presets: &[GlobalPreset]) -> Vec<Action> {
+    let direction = map
+        .actions_preferred_directions
+        .get(preset)
+        .copied()
+        .unwrap_or_default();
+    let global_actions = map
+        .actions_global_presets
+        .get(preset)
+        .into_iter()
+        .flatten()
+        .filter_map(|name| global_presets.iter().find(|global| &global.name == name))
+        .flat_map(|global| global.actions.iter().copied());
+    let actions = map.actions.get(preset).into_iter().flatten().copied();
+
+    global_actions
+        .chain(actions)
+        .map(|action| match action {
+            Action::Key(
+                key @ ActionKey {
+                    direction: ActionKeyDirection::Any,
+                    ..
+                },
+            ) => Action::Key(ActionKey { direction, ..key }),
+            action => action,
+        })
+        .collect()
+}
+
+/// Gets the index of the slice in `slices` at `elapsed` time since the first slice started.
+///
+/// Returns [`None`] if all slices are zero minute.
+fn time_slice_index(slices: &[ActionsTimeSlice], elapsed: Duration) -> Option<usize> {
+    const MILLIS_PER_MINUTE: u128 = 60_000;
+
+    let total_millis = slices
+        .iter()
+        .map(|slice| slice.minutes as u128 * MILLIS_PER_MINUTE)
+        .sum::<u128>();
+    if total_millis == 0 {
+        return None;
+    }
+
+    let mut millis = elapsed.as_millis() % total_millis;
+    for (i, slice) in slices.iter().enumerate() {
+        let slice_millis = slice.minutes as u128 * MILLIS_PER_MINUTE;
+        if millis < slice_millis {
+            return Some(i);
+        }
+        millis -= slice_millis;
+    }
+
+    None
+}
+
 fn actions_from(character: &Character) -> Vec<Action> {
     fn make_key_action(key: KeyBinding, millis: u64, count: u32) -> Action {
         Action::Key(ActionKey {
@@ -158,14 +359,6 @@ fn actions_from(character: &Character) -> Vec<Action> {
 
     let mut vec = Vec::new();
 
-    if let KeyBindingConfiguration { key, enabled: true } = character.feed_pet_key {
-        vec.push(make_key_action(
-            key,
-            character.feed_pet_millis,
-            character.feed_pet_count,
-        ));
-    }
-
     if let KeyBindingConfiguration { key, enabled: true } = character.potion_key
         && let PotionMode::EveryMillis(millis) = character.potion_mode
     {
@@ -282,15 +475,15 @@ fn buffs_from(character: &Character) -> Vec<(BuffKind, KeyKind)> {
 #[cfg(test)]
 mod tests {
     use std::assert_matches::assert_matches;
-    use std::collections::HashSet;
+    use std::collections::{HashMap, HashSet};
 
     use strum::IntoEnumIterator;
 
     use super::*;
     use crate::{ActionCondition, ActionConfiguration, ActionConfigurationCondition, ActionKey};
     use crate::{
-        Bound, EliteBossBehavior, FamiliarRarity, KeyBindingConfiguration, SwappableFamiliars,
-        rotator::MockRotator,
+        Bound, EliteBossBehavior, FamiliarRarity, FamiliarsSetup, KeyBindingConfiguration,
+        SwappableFamiliars, rotator::MockRotator,
     };
 
     #[test]
@@ -323,7 +516,7 @@ mod tests {
                     let original_mode = match args.mode {
                         RotatorMode::StartToEnd => RotationMode::StartToEnd,
                         RotatorMode::StartToEndThenReverse => RotationMode::StartToEndThenReverse,
-                        RotatorMode::AutoMobbing(key, bound) => {
+                        RotatorMode::AutoMobbing(key, bound, _) => {
                             key_bound = Some((key, bound));
                             RotationMode::AutoMobbing
                         }
@@ -331,6 +524,7 @@ mod tests {
                             key_bound = Some((key, bound));
                             RotationMode::PingPong
                         }
+                        RotatorMode::WeightedRandom => RotationMode::WeightedRandom,
                     };
                     let key_bound_match = match key_bound {
                         Some((key, bound)) => {
@@ -419,6 +613,44 @@ mod tests {
         service.apply(&mut rotator, None, Some(&character), &Settings::default());
     }
 
+    #[test]
+    fn apply_uses_familiars_setup_of_preset() {
+        let setup = Familiars {
+            enable_familiars_swapping: true,
+            swappable_familiars: SwappableFamiliars::Last,
+            swappable_rarities: HashSet::from_iter([FamiliarRarity::Epic]),
+            ..Default::default()
+        };
+        let character = Character {
+            familiars_setups: vec![FamiliarsSetup {
+                name: "Meso".to_string(),
+                familiars: setup.clone(),
+            }],
+            ..Default::default()
+        };
+        let map = Map {
+            actions: HashMap::from([("preset".to_string(), vec![])]),
+            familiars_setups: HashMap::from([("preset".to_string(), "Meso".to_string())]),
+            ..Default::default()
+        };
+
+        let mut rotator = MockRotator::new();
+        rotator
+            .expect_build_actions()
+            .withf(move |args| args.familiars == setup)
+            .once()
+            .return_const(());
+
+        let mut service = DefaultRotatorService::default();
+        service.update_actions(Some(&map), Some("preset".to_string()), Some(&character));
+        service.apply(
+            &mut rotator,
+            Some(&map),
+            Some(&character),
+            &Settings::default(),
+        );
+    }
+
     #[test]
     fn update_with_elite_boss_behavior() {
         let character = Character {
@@ -665,4 +897,141 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn time_slice_index_repeats() {
+        let slices = vec![
+            ActionsTimeSlice {
+                preset: "A".to_string(),
+                minutes: 10,
+            },
+            ActionsTimeSlice {
+                preset: "B".to_string(),
+                minutes: 5,
+            },
+        ];
+        let minutes = |minutes: u64| Duration::from_secs(minutes * 60);
+
+        assert_eq!(time_slice_index(&slices, minutes(0)), Some(0));
+        assert_eq!(time_slice_index(&slices, minutes(9)), Some(0));
+        assert_eq!(time_slice_index(&slices, minutes(10)), Some(1));
+        assert_eq!(time_slice_index(&slices, minutes(14)), Some(1));
+        assert_eq!(time_slice_index(&slices, minutes(15)), Some(0));
+        assert_eq!(time_slice_index(&slices, minutes(26)), Some(1));
+        assert_eq!(time_slice_index(&[], minutes(1)), None);
+    }
+
+    #[test]
+    fn poll_time_slice_switches_preset() {
+        let action = |key| {
+            Action::Key(ActionKey {
+                key,
+                ..ActionKey::default()
+            })
+        };
+        let map = Map {
+            actions: HashMap::from_iter([
+                ("A".to_string(), vec![action(KeyBinding::A)]),
+                ("B".to_string(), vec![action(KeyBinding::B)]),
+            ]),
+            actions_time_slices: vec![
+                ActionsTimeSlice {
+                    preset: "A".to_string(),
+                    minutes: 10,
+                },
+                ActionsTimeSlice {
+                    preset: "B".to_string(),
+                    minutes: 5,
+                },
+            ],
+            ..Default::default()
+        };
+        let mut service = DefaultRotatorService::default();
+
+        service.update_actions(Some(&map), Some("B".to_string()), None);
+        assert_eq!(service.actions, vec![action(KeyBinding::A)]);
+        assert!(!service.poll_time_slice(Some(&map)));
+
+        let (_, started, _) = service.time_slices.as_mut().unwrap();
+        *started -= Duration::from_secs(11 * 60);
+        assert!(service.poll_time_slice(Some(&map)));
+        assert_eq!(service.actions, vec![action(KeyBinding::B)]);
+        assert!(!service.poll_time_slice(Some(&map)));
+    }
+
+    #[test]
+    fn update_actions_merges_linked_global_presets() {
+        let action = |key| {
+            Action::Key(ActionKey {
+                key,
+                ..ActionKey::default()
+            })
+        };
+        let map = Map {
+            actions: HashMap::from_iter([("A".to_string(), vec![action(KeyBinding::A)])]),
+            actions_global_presets: HashMap::from_iter([(
+                "A".to_string(),
+                vec!["Buffs".to_string(), "Missing".to_string()],
+            )]),
+            ..Default::default()
+        };
+        let mut service = DefaultRotatorService::default();
+        service.update_global_presets(vec![
+            GlobalPreset {
+                id: Some(1),
+                name: "Buffs".to_string(),
+                actions: vec![action(KeyBinding::B), action(KeyBinding::C)],
+            },
+            GlobalPreset {
+                id: Some(2),
+                name: "Unlinked".to_string(),
+                actions: vec![action(KeyBinding::D)],
+            },
+        ]);
+
+        service.update_actions(Some(&map), Some("A".to_string()), None);
+        assert_eq!(
+            service.actions,
+            vec![
+                action(KeyBinding::B),
+                action(KeyBinding::C),
+                action(KeyBinding::A)
+            ]
+        );
+    }
+
+    #[test]
+    fn update_actions_resolves_any_direction_to_preferred_direction() {
+        let action = |key, direction| {
+            Action::Key(ActionKey {
+                key,
+                direction,
+                ..ActionKey::default()
+            })
+        };
+        let map = Map {
+            actions: HashMap::from_iter([(
+                "A".to_string(),
+                vec![
+                    action(KeyBinding::A, ActionKeyDirection::Any),
+                    action(KeyBinding::B, ActionKeyDirection::Left),
+                ],
+            )]),
+            actions_preferred_directions: HashMap::from_iter([(
+                "A".to_string(),
+                ActionKeyDirection::Right,
+            )]),
+            ..Default::default()
+        };
+        let mut service = DefaultRotatorService::default();
+
+        service.update_actions(Some(&map), Some("A".to_string()), None);
+        assert_eq!(
+            service.actions,
+            vec![
+                action(KeyBinding::A, ActionKeyDirection::Right),
+                action(KeyBinding::B, ActionKeyDirection::Left)
+            ]
+        );
+    }
 }
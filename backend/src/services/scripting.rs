@@ -0,0 +1,123 @@
+use std::fmt::Debug;
+
+use log::info;
+
+use super::EventContext;
+use crate::{
+    ecs::{Resources, World},
+    player::PlayerAction,
+    scripting::{ScriptEvent, ScriptState, Scripts},
+    services::{Event, EventHandler},
+};
+
+#[derive(Debug)]
+pub struct ScriptingEvent(Vec<PlayerAction>);
+
+impl Event for ScriptingEvent {}
+
+/// A service to run user [`Scripts`] on game events.
+pub trait ScriptingService: Debug {
+    /// Runs the scripts subscribing to the [`ScriptEvent`]s that happened since the last poll.
+    ///
+    /// Returns the actions enqueued by the scripts if there is any.
+    fn poll(&mut self, resources: &Resources, world: &World) -> Option<ScriptingEvent>;
+}
+
+#[derive(Debug, Default)]
+pub struct DefaultScriptingService {
+    scripts: Scripts,
+    had_rune: bool,
+    was_dead: bool,
+    was_idle: bool,
+}
+
+impl DefaultScriptingService {
+    pub fn new(scripts: Scripts) -> Self {
+        Self {
+            scripts,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the [`ScriptEvent`]s that happened since the last `state`.
+    fn events(&mut self, state: &ScriptState) -> Vec<ScriptEvent> {
+        let has_rune = state.rune.is_some();
+        let events = [
+            (has_rune && !self.had_rune, ScriptEvent::Rune),
+            (state.is_dead && !self.was_dead, ScriptEvent::Death),
+            (state.is_idle && !self.was_idle, ScriptEvent::Idle),
+        ];
+
+        self.had_rune = has_rune;
+        self.was_dead = state.is_dead;
+        self.was_idle = state.is_idle;
+        if state.halting {
+            return vec![];
+        }
+
+        events
+            .into_iter()
+            .filter_map(|(happened, event)| happened.then_some(event))
+            .collect()
+    }
+}
+
+impl ScriptingService for DefaultScriptingService {
+    fn poll(&mut self, resources: &Resources, world: &World) -> Option<ScriptingEvent> {
+        if self.scripts.is_empty() {
+            return None;
+        }
+
+        let state = ScriptState::new(resources, world);
+        let actions = self
+            .events(&state)
+            .into_iter()
+            .flat_map(|event| self.scripts.run(event, &state))
+            .collect::<Vec<_>>();
+
+        (!actions.is_empty()).then_some(ScriptingEvent(actions))
+    }
+}
+
+pub struct ScriptingEventHandler;
+
+impl EventHandler<ScriptingEvent> for ScriptingEventHandler {
+    fn handle(&mut self, context: &mut EventContext<'_>, event: ScriptingEvent) {
+        for action in event.0 {
+            info!(target: "scripting", "injecting script action {action}");
+            context.rotator.inject_action(action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_only_on_transitions_while_not_halting() {
+        let mut service = DefaultScriptingService::default();
+        let mut state = ScriptState {
+            rune: Some((1, 1)),
+            is_idle: true,
+            ..ScriptState::default()
+        };
+
+        assert_eq!(
+            service.events(&state),
+            vec![ScriptEvent::Rune, ScriptEvent::Idle]
+        );
+        assert!(service.events(&state).is_empty());
+
+        state.is_dead = true;
+        state.is_idle = false;
+        assert_eq!(service.events(&state), vec![ScriptEvent::Death]);
+
+        state.is_dead = false;
+        state.is_idle = true;
+        state.halting = true;
+        assert!(service.events(&state).is_empty());
+        assert!(!service.was_dead);
+        assert!(service.was_idle);
+    }
+}
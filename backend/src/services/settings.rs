@@ -1,16 +1,20 @@
 use std::{
     cell::{Ref, RefCell},
+    collections::{HashMap, HashSet},
     fmt::Debug,
     rc::Rc,
+    time::Duration,
 };
 
+use log::warn;
 #[cfg(test)]
 use mockall::automock;
 use platforms::{Window, capture::query_capture_name_window_pairs, input::InputKind};
 
 use crate::{
-    CaptureMode, InputMethod as DatabaseInputMethod, Settings,
-    bridge::{Capture, Input, InputMethod, InputReceiver},
+    CaptureMode, InputMethod as DatabaseInputMethod, KeyRemap, Settings,
+    bridge::{Capture, Input, InputMethod, InputReceiver, KeyKind, set_keyboard_layout},
+    mat::set_opencl_enabled,
     operation::Operation,
 };
 
@@ -74,7 +78,7 @@ impl DefaultSettingsService {
         // MapleStoryClass <- GMS
         // MapleStoryClassSG <- MSEA
         // MapleStoryClassTW <- TMS
-        if cfg!(windows) {
+        if cfg!(any(windows, target_os = "linux")) {
             let window = Window::new("MapleStoryClass");
 
             return Self {
@@ -95,6 +99,12 @@ impl DefaultSettingsService {
             capture.set_mode(settings.capture_mode);
             capture.set_window(self.selected_window());
         }
+
+        let frame_rate =
+            (!settings.capture_sync_monitor_refresh).then_some(settings.capture_target_fps);
+        if forced || capture.frame_rate() != frame_rate {
+            capture.set_frame_rate(frame_rate);
+        }
     }
 
     fn update_inputs(
@@ -122,6 +132,13 @@ impl DefaultSettingsService {
                 ));
             }
         }
+        input.set_key_remaps(key_remaps_from(&settings.key_remaps));
+        input.set_key_repeat(
+            settings
+                .enable_key_repeat
+                .then_some(Duration::from_millis(settings.key_repeat_interval_millis)),
+        );
+        set_keyboard_layout(settings.keyboard_layout);
     }
 }
 
@@ -149,6 +166,9 @@ impl SettingsService for DefaultSettingsService {
         );
         self.update_capture(capture, false);
         self.update_inputs(input, input_receiver, capture);
+        if set_opencl_enabled(settings.enable_opencl) != settings.enable_opencl {
+            warn!(target: "settings", "OpenCL is not available, detection runs on CPU");
+        }
     }
 
     fn window_names(&self) -> Vec<String> {
@@ -194,6 +214,24 @@ impl SettingsService for DefaultSettingsService {
     }
 }
 
+/// Builds a one-to-one key translation table from `remaps`.
+///
+/// When multiple remaps share the same source or target key, only the first one is kept.
+fn key_remaps_from(remaps: &[KeyRemap]) -> HashMap<KeyKind, KeyKind> {
+    let mut targets = HashSet::new();
+    let mut map = HashMap::new();
+    for remap in remaps {
+        let from = KeyKind::from(remap.from);
+        let to = KeyKind::from(remap.to);
+        if from == to || map.contains_key(&from) || !targets.insert(to) {
+            continue;
+        }
+        map.insert(from, to);
+    }
+
+    map
+}
+
 #[cfg(test)]
 mod tests {
     use std::assert_matches::assert_matches;
@@ -204,7 +242,7 @@ mod tests {
     use crate::bridge::{
         InputMethod as BridgeInputMethod, MockCapture, MockInput, MockInputReceiver,
     };
-    use crate::{CaptureMode, CycleRunStopMode, InputMethod};
+    use crate::{CaptureMode, CycleRunStopMode, InputMethod, KeyBinding};
 
     #[test]
     fn settings_service_initialization() {
@@ -245,6 +283,8 @@ mod tests {
                 *window == Window::new("Bar") && matches!(kind, InputKind::Focused)
             }
         });
+        mock_keys.expect_set_key_remaps().once();
+        mock_keys.expect_set_key_repeat().once();
 
         let mut key_receiver = MockInputReceiver::default();
         key_receiver
@@ -265,6 +305,10 @@ mod tests {
             .expect_set_mode()
             .withf(|mode| *mode == CaptureMode::WindowsGraphicsCapture)
             .once();
+        capture
+            .expect_set_frame_rate()
+            .withf(|frame_rate| *frame_rate == Some(30))
+            .once();
 
         service.update_selected_window(Some(1));
         service.apply_selected_window(&mut mock_keys, &mut key_receiver, &mut capture);
@@ -292,6 +336,8 @@ mod tests {
             }
             BridgeInputMethod::Default(_, _) => false,
         });
+        mock_keys.expect_set_key_remaps().once();
+        mock_keys.expect_set_key_repeat().once();
 
         let mut key_receiver = MockInputReceiver::default();
         key_receiver
@@ -313,6 +359,11 @@ mod tests {
             .expect_mode()
             .times(2)
             .return_const(CaptureMode::BitBlt);
+        capture.expect_frame_rate().once().return_const(None);
+        capture
+            .expect_set_frame_rate()
+            .withf(|frame_rate| *frame_rate == Some(30))
+            .once();
         let mut op = Operation::Running;
 
         service.update_settings(new_settings.clone());
@@ -335,6 +386,8 @@ mod tests {
         };
         let mut mock_keys = MockInput::default();
         mock_keys.expect_set_method().once();
+        mock_keys.expect_set_key_remaps().once();
+        mock_keys.expect_set_key_repeat().once();
         let mut key_receiver = MockInputReceiver::default();
         key_receiver
             .expect_set_window_and_input_kind()
@@ -351,9 +404,43 @@ mod tests {
             .expect_mode()
             .times(2)
             .return_const(CaptureMode::BitBltArea);
+        capture.expect_frame_rate().once().return_const(Some(30));
         let mut op = Operation::Running;
 
         service.update_settings(new_settings.clone());
         service.apply_settings(&mut op, &mut mock_keys, &mut key_receiver, &mut capture);
     }
+
+    #[test]
+    fn key_remaps_from_keeps_one_to_one_remaps() {
+        let remaps = [
+            KeyRemap {
+                from: KeyBinding::A,
+                to: KeyBinding::B,
+            },
+            KeyRemap {
+                from: KeyBinding::A,
+                to: KeyBinding::C,
+            },
+            KeyRemap {
+                from: KeyBinding::D,
+                to: KeyBinding::B,
+            },
+            KeyRemap {
+                from: KeyBinding::E,
+                to: KeyBinding::E,
+            },
+            KeyRemap {
+                from: KeyBinding::B,
+                to: KeyBinding::A,
+            },
+        ];
+
+        let map = key_remaps_from(&remaps);
+
+        assert_eq!(
+            map,
+            HashMap::from([(KeyKind::A, KeyKind::B), (KeyKind::B, KeyKind::A)])
+        );
+    }
 }
@@ -0,0 +1,283 @@
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+
+use super::EventContext;
+use crate::{
+    StrangerPolicy,
+    ecs::{Resources, World},
+    minimap::Minimap,
+    player::{MAX_SKIP_CHANNELS, Panic, PanicTo, Player, PlayerAction},
+    services::{Event, EventHandler},
+};
+
+const CHANGES_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug)]
+pub enum StrangerEvent {
+    /// Changes channel skipping past the provided number of channels.
+    ChangeChannel(u32),
+    /// Halts because channel changes in the last hour are exhausted.
+    Halt,
+}
+
+impl Event for StrangerEvent {}
+
+/// A service to apply the map's [`StrangerPolicy`].
+pub trait StrangerService: Debug {
+    /// Polls for any pending [`StrangerEvent`] from the current strangers in the minimap.
+    fn poll(
+        &mut self,
+        resources: &Resources,
+        world: &World,
+        policy: Option<&StrangerPolicy>,
+    ) -> Option<StrangerEvent>;
+}
+
+#[derive(Debug, Default)]
+pub struct DefaultStrangerService {
+    /// The time strangers started staying in the minimap.
+    lingering_since: Option<Instant>,
+    /// The times of channel changes within the last hour.
+    changes: VecDeque<Instant>,
+    /// The estimated current channel number if known.
+    channel: Option<u32>,
+    /// The [`StrangerPolicy::start_channel`] [`Self::channel`] was estimated from.
+    start_channel: u32,
+    /// The number of skipped channels of the last channel change requested by this service.
+    pending_skip_channels: Option<u32>,
+    /// The last seen [`PlayerContext::channel_changed_count`](crate::player::PlayerContext).
+    channel_changed_count: u32,
+}
+
+impl DefaultStrangerService {
+    fn update_channel(&mut self, policy: &StrangerPolicy, channel_changed_count: u32) {
+        if self.start_channel != policy.start_channel {
+            self.start_channel = policy.start_channel;
+            self.channel = (policy.start_channel > 0).then_some(policy.start_channel);
+        }
+
+        let changed_count = channel_changed_count.wrapping_sub(self.channel_changed_count);
+        self.channel_changed_count = channel_changed_count;
+        if changed_count == 0 {
+            return;
+        }
+
+        let skip_channels = self.pending_skip_channels.take().unwrap_or_default();
+        self.channel = self.channel.map(|channel| {
+            (0..changed_count).fold(channel, |channel, _| {
+                next_channel(channel, skip_channels, policy.channel_count)
+            })
+        });
+    }
+
+    fn update(
+        &mut self,
+        now: Instant,
+        policy: &StrangerPolicy,
+        has_stranger: bool,
+    ) -> Option<StrangerEvent> {
+        if !has_stranger {
+            self.lingering_since = None;
+            return None;
+        }
+
+        let since = *self.lingering_since.get_or_insert(now);
+        if now.duration_since(since) < Duration::from_millis(policy.linger_millis) {
+            return None;
+        }
+        self.lingering_since = None;
+
+        while self
+            .changes
+            .front()
+            .is_some_and(|time| now.duration_since(*time) >= CHANGES_WINDOW)
+        {
+            self.changes.pop_front();
+        }
+        if policy.max_changes_per_hour > 0
+            && self.changes.len() >= policy.max_changes_per_hour as usize
+        {
+            return Some(StrangerEvent::Halt);
+        }
+        self.changes.push_back(now);
+
+        let skip_channels = skip_channels(self.channel, policy);
+        self.pending_skip_channels = Some(skip_channels);
+        Some(StrangerEvent::ChangeChannel(skip_channels))
+    }
+}
+
+impl StrangerService for DefaultStrangerService {
+    fn poll(
+        &mut self,
+        resources: &Resources,
+        world: &World,
+        policy: Option<&StrangerPolicy>,
+    ) -> Option<StrangerEvent> {
+        let policy = policy.filter(|policy| policy.enabled)?;
+
+        self.update_channel(policy, world.player.context.channel_changed_count);
+        if resources.operation.halting() || matches!(world.player.state, Player::Panicking(_)) {
+            self.lingering_since = None;
+            return None;
+        }
+
+        let has_stranger = match world.minimap.state {
+            Minimap::Detecting => false,
+            Minimap::Idle(idle) => idle.stranger_count() > 0,
+        };
+        self.update(Instant::now(), policy, has_stranger)
+    }
+}
+
+pub struct StrangerEventHandler;
+
+impl EventHandler<StrangerEvent> for StrangerEventHandler {
+    fn handle(&mut self, context: &mut EventContext<'_>, event: StrangerEvent) {
+        match event {
+            StrangerEvent::ChangeChannel(skip_channels) => {
+                info!(target: "stranger", "changing channel skipping {skip_channels} channel(s)");
+                context.rotator.inject_action(PlayerAction::Panic(Panic {
+                    to: PanicTo::Channel,
+                    skip_channels,
+                }));
+            }
+            StrangerEvent::Halt => {
+                warn!(target: "stranger", "channel changes exhausted, halting");
+                context.operation_service.halt(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    false,
+                );
+            }
+        }
+    }
+}
+
+/// Returns the channel after `channel` when skipping past `skip_channels` channels.
+#[inline]
+fn next_channel(channel: u32, skip_channels: u32, channel_count: u32) -> u32 {
+    if channel_count == 0 {
+        return channel;
+    }
+
+    (channel + skip_channels) % channel_count + 1
+}
+
+/// Returns the number of channels to skip past from `channel` to avoid
+/// [`StrangerPolicy::avoid_channels`].
+///
+/// The result is clamped to [`MAX_SKIP_CHANNELS`] so the estimated channel matches the one the
+/// panicking state actually changes to.
+fn skip_channels(channel: Option<u32>, policy: &StrangerPolicy) -> u32 {
+    let Some(channel) = channel else {
+        return 0;
+    };
+
+    let mut skip_channels = 0;
+    while skip_channels + 1 < policy.channel_count
+        && skip_channels < MAX_SKIP_CHANNELS
+        && policy.avoid_channels.contains(&next_channel(
+            channel,
+            skip_channels,
+            policy.channel_count,
+        ))
+    {
+        skip_channels += 1;
+    }
+
+    skip_channels
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+
+    fn policy() -> StrangerPolicy {
+        StrangerPolicy {
+            enabled: true,
+            linger_millis: 1000,
+            max_changes_per_hour: 2,
+            avoid_channels: vec![3, 4],
+            start_channel: 2,
+            channel_count: 5,
+        }
+    }
+
+    #[test]
+    fn skip_channels_past_avoided_channels() {
+        let policy = policy();
+
+        assert_eq!(skip_channels(None, &policy), 0);
+        assert_eq!(skip_channels(Some(1), &policy), 0);
+        assert_eq!(skip_channels(Some(2), &policy), 2);
+        assert_eq!(next_channel(2, 2, policy.channel_count), 5);
+        assert_eq!(next_channel(5, 0, policy.channel_count), 1);
+    }
+
+    #[test]
+    fn skip_channels_clamped_to_max_skip_channels() {
+        let policy = StrangerPolicy {
+            avoid_channels: (3..=15).collect(),
+            channel_count: 20,
+            ..policy()
+        };
+
+        assert_eq!(skip_channels(Some(2), &policy), MAX_SKIP_CHANNELS);
+    }
+
+    #[test]
+    fn update_changes_channel_after_lingering_then_halts_when_exhausted() {
+        let policy = policy();
+        let mut service = DefaultStrangerService::default();
+        service.update_channel(&policy, 0);
+        let now = Instant::now();
+
+        assert_matches!(service.update(now, &policy, true), None);
+        assert_matches!(
+            service.update(now + Duration::from_millis(1000), &policy, true),
+            Some(StrangerEvent::ChangeChannel(2))
+        );
+        service.update_channel(&policy, 1);
+        assert_eq!(service.channel, Some(5));
+
+        let now = now + Duration::from_millis(2000);
+        assert_matches!(service.update(now, &policy, true), None);
+        assert_matches!(
+            service.update(now + Duration::from_millis(1000), &policy, true),
+            Some(StrangerEvent::ChangeChannel(0))
+        );
+
+        let now = now + Duration::from_millis(2000);
+        assert_matches!(service.update(now, &policy, true), None);
+        assert_matches!(
+            service.update(now + Duration::from_millis(1000), &policy, true),
+            Some(StrangerEvent::Halt)
+        );
+    }
+
+    #[test]
+    fn update_resets_lingering_when_stranger_leaves() {
+        let policy = policy();
+        let mut service = DefaultStrangerService::default();
+        let now = Instant::now();
+
+        assert_matches!(service.update(now, &policy, true), None);
+        assert_matches!(
+            service.update(now + Duration::from_millis(500), &policy, false),
+            None
+        );
+        assert_matches!(
+            service.update(now + Duration::from_millis(1000), &policy, true),
+            None
+        );
+    }
+}
@@ -6,16 +6,24 @@ use opencv::{
 };
 use tokio::sync::{broadcast::Receiver, oneshot::Sender};
 
-#[cfg(debug_assertions)]
-use crate::DebugState;
 use crate::{
-    BotOperationUpdate, Character, GameState, GameTemplate, KeyBinding, NavigationPath, Request,
-    Response,
+    BotOperation, BotOperationUpdate, Character, GameState, GameTemplate, InputMacroKey,
+    KeyBinding, NavigationPath, Platform, PlatformGraph, PlatformGraphEdge, PlatformGraphMovement,
+    PositionHeatmap, PresetRecovery, QuickSlotsSkill, Request, Response, SelfCheck, TemplateMatch,
     detect::to_base64_from_mat,
+    interlock::RunSnapshot,
+    minimap::Minimap,
     models::Map,
-    poll_request,
+    pathing::{self, PathingThresholds, PlatformMovement},
+    player::{DOUBLE_JUMP_THRESHOLD, GRAPPLING_MAX_THRESHOLD, GRAPPLING_THRESHOLD, JUMP_THRESHOLD},
+    poll_request, quickslots, selfcheck,
     services::{Event, EventContext, EventHandler},
 };
+#[cfg(debug_assertions)]
+use crate::{
+    Bound, DebugState, DetectionBenchmark, MobFrame, MouseAlignmentPreview, ThresholdPreview,
+    models::TunableDetection,
+};
 
 #[derive(Debug)]
 pub enum UiEvent {
@@ -40,11 +48,29 @@ pub trait UiService: Debug {
 
     /// Queues a [`UiEvent`] for map update.
     fn queue_update_map(&mut self, preset: Option<String>, map: Option<Map>);
+
+    /// Gets the [`RunSnapshot`] taken when the bot last started running from the UI.
+    fn run_snapshot(&self) -> Option<&RunSnapshot>;
+
+    /// Replaces the current [`RunSnapshot`] with `snapshot`.
+    fn set_run_snapshot(&mut self, snapshot: RunSnapshot);
 }
 
 #[derive(Debug, Default)]
 pub struct DefaultUiService {
+    /// The index of the bot instance to poll requests for.
+    instance: usize,
     pending_events: VecDeque<UiEvent>,
+    run_snapshot: Option<RunSnapshot>,
+}
+
+impl DefaultUiService {
+    pub fn new(instance: usize) -> Self {
+        Self {
+            instance,
+            ..Self::default()
+        }
+    }
 }
 
 impl UiService for DefaultUiService {
@@ -53,7 +79,8 @@ impl UiService for DefaultUiService {
             return Some(event);
         }
 
-        poll_request().map(|(request, response)| UiEvent::External { request, response })
+        poll_request(self.instance)
+            .map(|(request, response)| UiEvent::External { request, response })
     }
 
     fn queue_update_character(&mut self, character: Option<Character>) {
@@ -67,6 +94,14 @@ impl UiService for DefaultUiService {
             request: Request::UpdateMap(preset, map),
         });
     }
+
+    fn run_snapshot(&self) -> Option<&RunSnapshot> {
+        self.run_snapshot.as_ref()
+    }
+
+    fn set_run_snapshot(&mut self, snapshot: RunSnapshot) {
+        self.run_snapshot = Some(snapshot);
+    }
 }
 
 pub struct UiEventHandler;
@@ -82,6 +117,21 @@ impl EventHandler<UiEvent> for UiEventHandler {
                 update_operation(context, update);
                 Response::UpdateOperation
             }
+            Request::QueryRunChanges => Response::QueryRunChanges(query_run_changes(context)),
+            Request::QueryResumableOperation => {
+                Response::QueryResumableOperation(query_resumable_operation(context))
+            }
+            Request::ResumeOperation(resume) => {
+                resume_operation(context, resume);
+                Response::ResumeOperation
+            }
+            Request::QueryPresetRecovery => {
+                Response::QueryPresetRecovery(query_preset_recovery(context))
+            }
+            Request::RecoverPreset(restore) => {
+                recover_preset(context, restore);
+                Response::RecoverPreset
+            }
             Request::CreateMap(name) => Response::CreateMap(create_map(context, name)),
             Request::UpdateMap(preset, map) => {
                 update_map(context, preset, map);
@@ -98,6 +148,9 @@ impl EventHandler<UiEvent> for UiEventHandler {
                     convert_navigation_path_snapshot_to_grayscale(context, base64),
                 )
             }
+            Request::NavigateToMap(map_id) => {
+                Response::NavigateToMap(context.navigator.navigate_to(map_id))
+            }
             Request::UpdateCharacter(character) => {
                 update_character(context, character);
                 Response::UpdateCharacter
@@ -124,23 +177,41 @@ impl EventHandler<UiEvent> for UiEventHandler {
             Request::QueryTemplate(template) => {
                 Response::QueryTemplate(query_template(context, template))
             }
+            Request::ReloadTemplates => Response::ReloadTemplates(reload_templates(context)),
+            Request::TestTemplate(base64) => Response::TestTemplate(test_template(context, base64)),
             Request::ConvertImageToBase64(image, is_grayscale) => {
                 Response::ConvertImageToBase64(convert_image_to_base64(image, is_grayscale))
             }
             Request::SaveCaptureImage(is_grayscale) => {
-                save_capture_image(context, is_grayscale);
-                Response::SaveCaptureImage
+                Response::SaveCaptureImage(save_capture_image(context, is_grayscale))
+            }
+            Request::CalibratePlayerDot(x, y) => {
+                Response::CalibratePlayerDot(calibrate_player_dot(context, x, y))
+            }
+            Request::RunSelfCheck => Response::RunSelfCheck(run_self_check(context)),
+            Request::ScanQuickSlots => Response::ScanQuickSlots(scan_quick_slots(context)),
+            Request::RecordInputMacro(recording) => {
+                Response::RecordInputMacro(record_input_macro(context, recording))
+            }
+            Request::UpdateDisabledActionTags(tags) => {
+                update_disabled_action_tags(context, tags);
+                Response::UpdateDisabledActionTags
+            }
+            Request::UpdateMinimapVisible(visible) => {
+                update_minimap_visible(context, visible);
+                Response::UpdateMinimapVisible
+            }
+            Request::QueryPlatformGraph => {
+                Response::QueryPlatformGraph(query_platform_graph(context))
+            }
+            Request::QueryPositionHeatmap => {
+                Response::QueryPositionHeatmap(query_position_heatmap(context))
             }
             #[cfg(debug_assertions)]
             Request::DebugStateReceiver => {
                 Response::DebugStateReceiver(subscribe_debug_state(context))
             }
             #[cfg(debug_assertions)]
-            Request::AutoSaveRune(auto_save) => {
-                update_auto_save_rune(context, auto_save);
-                Response::AutoSaveRune
-            }
-            #[cfg(debug_assertions)]
             Request::InferRune => {
                 infer_rune(context);
                 Response::InferRune
@@ -160,6 +231,24 @@ impl EventHandler<UiEvent> for UiEventHandler {
                 test_spin_rune(context);
                 Response::TestSpinRune
             }
+            #[cfg(debug_assertions)]
+            Request::CaptureMobFrame => Response::CaptureMobFrame(capture_mob_frame(context)),
+            #[cfg(debug_assertions)]
+            Request::SaveMobLabels(mobs) => Response::SaveMobLabels(save_mob_labels(context, mobs)),
+            #[cfg(debug_assertions)]
+            Request::TuneDetectionThreshold(detection, threshold) => {
+                Response::TuneDetectionThreshold(tune_detection_threshold(
+                    context, detection, threshold,
+                ))
+            }
+            #[cfg(debug_assertions)]
+            Request::BenchmarkDetection => {
+                Response::BenchmarkDetection(benchmark_detection(context))
+            }
+            #[cfg(debug_assertions)]
+            Request::TestMouseAlignment(target) => {
+                Response::TestMouseAlignment(test_mouse_alignment(context, target))
+            }
         };
 
         if let Some(response) = response {
@@ -169,8 +258,16 @@ impl EventHandler<UiEvent> for UiEventHandler {
 }
 
 fn update_operation(context: &mut EventContext<'_>, update: BotOperationUpdate) {
-    if context.map_service.map().is_none() || context.character_service.character().is_none() {
+    let (Some(map), Some(character)) = (
+        context.map_service.map(),
+        context.character_service.character(),
+    ) else {
         return;
+    };
+    if matches!(update, BotOperationUpdate::Run) {
+        let snapshot = RunSnapshot::new(map, context.map_service.preset(), character);
+        context.ui_service.set_run_snapshot(snapshot);
+        context.character_service.queue_verify_character();
     }
     context.operation_service.apply(
         context.resources,
@@ -181,10 +278,45 @@ fn update_operation(context: &mut EventContext<'_>, update: BotOperationUpdate)
     );
 }
 
+fn query_run_changes(context: &mut EventContext<'_>) -> Vec<String> {
+    let (Some(snapshot), Some(map), Some(character)) = (
+        context.ui_service.run_snapshot(),
+        context.map_service.map(),
+        context.character_service.character(),
+    ) else {
+        return vec![];
+    };
+    snapshot.changes(map, context.map_service.preset().as_deref(), character)
+}
+
+fn query_resumable_operation(context: &mut EventContext<'_>) -> Option<BotOperation> {
+    context
+        .operation_service
+        .resumable()
+        .map(BotOperation::from)
+}
+
+fn resume_operation(context: &mut EventContext<'_>, resume: bool) {
+    if resume
+        && (context.map_service.map().is_none() || context.character_service.character().is_none())
+    {
+        return;
+    }
+    context.operation_service.resume(context.resources, resume);
+}
+
+fn query_preset_recovery(context: &mut EventContext<'_>) -> Option<PresetRecovery> {
+    context.map_service.recovery()
+}
+
+fn recover_preset(context: &mut EventContext<'_>, restore: bool) {
+    context.map_service.recover(restore);
+}
+
 fn create_map(context: &mut EventContext<'_>, name: String) -> Option<Map> {
     context
         .map_service
-        .create(context.world.minimap.state, name)
+        .create(context.resources, context.world.minimap.state, name)
 }
 
 fn update_map(context: &mut EventContext<'_>, preset: Option<String>, map: Option<Map>) {
@@ -197,6 +329,11 @@ fn update_map(context: &mut EventContext<'_>, preset: Option<String>, map: Optio
     let character_service = &context.character_service;
     let map = map_service.map();
     let preset = map_service.preset();
+    context
+        .resources
+        .notification
+        .set_map_notifications(map.map(|map| map.notifications).unwrap_or_default());
+    context.resources.stats.set_preset(preset.clone());
     let character = character_service.character();
     let settings_service = &context.settings_service;
     let settings = settings_service.settings();
@@ -206,6 +343,10 @@ fn update_map(context: &mut EventContext<'_>, preset: Option<String>, map: Optio
     context
         .navigator
         .mark_dirty_with_destination(map.and_then(|map| map.paths_id_index));
+    context.navigator.set_expected_minimap(
+        map.map(|map| (map.width, map.height)),
+        map.and_then(|map| map.name_snapshot_base64.clone()),
+    );
 }
 
 fn redetect_map_minimap(context: &mut EventContext<'_>) {
@@ -292,12 +433,23 @@ fn select_capture_handle(context: &mut EventContext<'_>, index: Option<usize>) {
         context.game_service.input_receiver_mut(),
         context.capture.deref_mut(),
     );
+    context.character_service.queue_detect_character();
 }
 
 fn query_template(context: &mut EventContext<'_>, template: GameTemplate) -> String {
     context.localization_service.template(template)
 }
 
+fn reload_templates(context: &mut EventContext<'_>) -> Vec<String> {
+    context.localization_service.reload_templates()
+}
+
+fn test_template(context: &mut EventContext<'_>, base64: String) -> Option<TemplateMatch> {
+    context
+        .localization_service
+        .test_template(context.resources, base64)
+}
+
 fn convert_image_to_base64(image: Vec<u8>, is_grayscale: bool) -> Option<String> {
     let flag = if is_grayscale {
         IMREAD_GRAYSCALE
@@ -310,22 +462,119 @@ fn convert_image_to_base64(image: Vec<u8>, is_grayscale: bool) -> Option<String>
     to_base64_from_mat(&mat).ok()
 }
 
-fn save_capture_image(context: &mut EventContext<'_>, is_grayscale: bool) {
+fn save_capture_image(context: &mut EventContext<'_>, is_grayscale: bool) -> Option<Vec<u8>> {
     context
         .localization_service
-        .save_capture_image(context.resources, is_grayscale);
+        .save_capture_image(context.resources, is_grayscale)
 }
 
-#[cfg(debug_assertions)]
-fn subscribe_debug_state(context: &mut EventContext<'_>) -> Receiver<DebugState> {
-    context.debug_service.subscribe_state()
+fn calibrate_player_dot(context: &mut EventContext<'_>, x: i32, y: i32) -> Option<String> {
+    context.localization_service.calibrate_player_dot(
+        context.resources,
+        context.world.minimap.state,
+        (x, y),
+    )
 }
 
-#[cfg(debug_assertions)]
-fn update_auto_save_rune(context: &mut EventContext<'_>, auto_save: bool) {
+fn run_self_check(context: &mut EventContext<'_>) -> Option<SelfCheck> {
     context
-        .debug_service
-        .set_auto_save_rune(context.resources, auto_save);
+        .resources
+        .detector
+        .as_ref()
+        .map(|detector| selfcheck::run_self_check(detector.as_ref()))
+}
+
+fn scan_quick_slots(context: &mut EventContext<'_>) -> Option<Vec<QuickSlotsSkill>> {
+    let detector = context.resources.detector.as_ref()?;
+    let map_service = &context.map_service;
+    let preset = map_service.preset();
+    let actions = map_service
+        .map()
+        .zip(preset.as_ref())
+        .and_then(|(map, preset)| map.actions.get(preset))
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    Some(quickslots::scan_quick_slots(
+        detector.as_ref(),
+        context.character_service.character(),
+        actions,
+    ))
+}
+
+fn record_input_macro(context: &mut EventContext<'_>, recording: bool) -> Vec<InputMacroKey> {
+    if recording {
+        context.game_service.start_recording_input_macro();
+        vec![]
+    } else {
+        context.game_service.stop_recording_input_macro()
+    }
+}
+
+fn update_disabled_action_tags(context: &mut EventContext<'_>, tags: Vec<String>) {
+    context.rotator.set_disabled_tags(tags);
+}
+
+fn update_minimap_visible(context: &mut EventContext<'_>, visible: bool) {
+    context.game_service.set_frame_paused(!visible);
+}
+
+fn query_platform_graph(context: &mut EventContext<'_>) -> Option<PlatformGraph> {
+    let Minimap::Idle(idle) = context.world.minimap.state else {
+        return None;
+    };
+    let platforms = idle
+        .platforms
+        .iter()
+        .map(|platform| Platform {
+            x_start: platform.xs().start,
+            x_end: platform.xs().end,
+            y: platform.y(),
+        })
+        .collect();
+    let thresholds = PathingThresholds {
+        double_jump: DOUBLE_JUMP_THRESHOLD,
+        jump: JUMP_THRESHOLD,
+        grappling: GRAPPLING_THRESHOLD,
+        vertical: GRAPPLING_MAX_THRESHOLD,
+    };
+    let edges = pathing::find_edges(&idle.platforms, thresholds)
+        .into_iter()
+        .map(|edge| PlatformGraphEdge {
+            from: edge.from,
+            to: edge.to,
+            movement: match edge.movement {
+                PlatformMovement::Walk => PlatformGraphMovement::Walk,
+                PlatformMovement::Fall => PlatformGraphMovement::Fall,
+                PlatformMovement::Jump => PlatformGraphMovement::Jump,
+                PlatformMovement::UpJump => PlatformGraphMovement::UpJump,
+                PlatformMovement::Grapple => PlatformGraphMovement::Grapple,
+                PlatformMovement::DoubleJump => PlatformGraphMovement::DoubleJump,
+            },
+            cost: edge.cost,
+        })
+        .collect();
+
+    Some(PlatformGraph { platforms, edges })
+}
+
+fn query_position_heatmap(context: &mut EventContext<'_>) -> Option<PositionHeatmap> {
+    let Minimap::Idle(idle) = context.world.minimap.state else {
+        return None;
+    };
+
+    Some(
+        context
+            .world
+            .minimap
+            .context
+            .position_heatmap(idle.bbox.width, idle.bbox.height),
+    )
+}
+
+#[cfg(debug_assertions)]
+fn subscribe_debug_state(context: &mut EventContext<'_>) -> Receiver<DebugState> {
+    context.debug_service.subscribe_state()
 }
 
 #[cfg(debug_assertions)]
@@ -347,3 +596,44 @@ fn record_images(context: &mut EventContext<'_>, start: bool) {
 fn test_spin_rune(context: &mut EventContext<'_>) {
     context.debug_service.test_spin_rune();
 }
+
+#[cfg(debug_assertions)]
+fn capture_mob_frame(context: &mut EventContext<'_>) -> Option<MobFrame> {
+    context.debug_service.capture_mob_frame(context.resources)
+}
+
+#[cfg(debug_assertions)]
+fn save_mob_labels(context: &mut EventContext<'_>, mobs: Vec<Bound>) -> Option<String> {
+    context.debug_service.save_mob_labels(mobs)
+}
+
+#[cfg(debug_assertions)]
+fn tune_detection_threshold(
+    context: &mut EventContext<'_>,
+    detection: TunableDetection,
+    threshold: f64,
+) -> Option<ThresholdPreview> {
+    context.debug_service.tune_detection_threshold(
+        context.resources,
+        context.world.minimap.state,
+        detection,
+        threshold,
+    )
+}
+
+#[cfg(debug_assertions)]
+fn benchmark_detection(context: &mut EventContext<'_>) -> Option<DetectionBenchmark> {
+    context
+        .debug_service
+        .benchmark_detection(context.resources, context.world.minimap.state)
+}
+
+#[cfg(debug_assertions)]
+fn test_mouse_alignment(
+    context: &mut EventContext<'_>,
+    target: Option<(i32, i32)>,
+) -> Option<MouseAlignmentPreview> {
+    context
+        .debug_service
+        .test_mouse_alignment(context.resources, context.capture, target)
+}
@@ -1,13 +1,23 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 
-use tokio::sync::broadcast::Receiver;
+use log::{error, info, warn};
+use tokio::{
+    spawn,
+    sync::broadcast::Receiver,
+    task::{JoinHandle, spawn_blocking},
+    time::sleep,
+};
 
 use super::EventContext;
 use crate::{
-    BotOperationUpdate,
+    BotOperationUpdate, PlayerRequestPolicy,
+    bridge::MouseKind,
+    database::{query_or_upsert_daily_claims, upsert_daily_claims},
     ecs::WorldEvent,
+    navigator::Navigator,
     notification::NotificationKind,
-    player::{PanicTo, Panicking, Player},
+    operation::HaltReason,
+    player::{Chat, ChattingContent, PanicTo, Panicking, Player, PlayerAction},
     services::EventHandler,
 };
 
@@ -15,23 +25,48 @@ use crate::{
 pub trait WorldService: Debug {
     /// Polls for any pending [`WorldEvent`].
     fn poll(&mut self) -> Option<WorldEvent>;
+
+    /// Queues a [`WorldEvent::AdminChatReady`] to be polled after `delay`.
+    fn queue_admin_chat(&mut self, delay: Duration);
 }
 
 #[derive(Debug)]
 pub struct DefaultWorldService {
     event_rx: Receiver<WorldEvent>,
+    pending_admin_chat: Option<JoinHandle<()>>,
 }
 
 impl DefaultWorldService {
     pub fn new(event_rx: Receiver<WorldEvent>) -> Self {
-        Self { event_rx }
+        Self {
+            event_rx,
+            pending_admin_chat: None,
+        }
     }
 }
 
 impl WorldService for DefaultWorldService {
     fn poll(&mut self) -> Option<WorldEvent> {
+        if self
+            .pending_admin_chat
+            .as_ref()
+            .is_some_and(|handle| handle.is_finished())
+        {
+            self.pending_admin_chat = None;
+            return Some(WorldEvent::AdminChatReady);
+        }
+
         self.event_rx.try_recv().ok()
     }
+
+    fn queue_admin_chat(&mut self, delay: Duration) {
+        if let Some(handle) = self.pending_admin_chat.take() {
+            handle.abort();
+        }
+        self.pending_admin_chat = Some(spawn(async move {
+            sleep(delay).await;
+        }));
+    }
 }
 
 pub struct WorldEventHandler;
@@ -113,6 +148,37 @@ impl EventHandler<WorldEvent> for WorldEventHandler {
 
                 context.operation_service.queue_halt();
             }
+            WorldEvent::MapMismatched => {
+                if context.resources.operation.halting() {
+                    return;
+                }
+
+                let stop_on_fail_or_change_map = context
+                    .settings_service
+                    .settings()
+                    .stop_on_fail_or_change_map;
+                if !should_halt_on_map_mismatch(stop_on_fail_or_change_map, context.navigator) {
+                    warn!(
+                        target: "world",
+                        "current map is not the expected map, re-detecting minimap"
+                    );
+                    context.map_service.redetect(&mut context.world.minimap);
+                    return;
+                }
+
+                warn!(target: "world", "current map is not the expected map, halting all actions");
+                context.operation_service.halt(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    false,
+                );
+                context.resources.halt_reason = Some(HaltReason::MapMismatched);
+                let _ = context
+                    .resources
+                    .notification
+                    .schedule_notification(NotificationKind::FailOrMapChange);
+            }
             WorldEvent::CaptureFailed => {
                 if context.resources.operation.halting() {
                     return;
@@ -144,6 +210,24 @@ impl EventHandler<WorldEvent> for WorldEventHandler {
                         .schedule_notification(NotificationKind::LieDetectorAppear);
                 }
             }
+            WorldEvent::LieDetectorUnsolved => {
+                if context.resources.operation.halting() {
+                    return;
+                }
+
+                warn!(target: "world", "lie detector failed to be solved, halting all actions");
+                context.operation_service.halt(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    false,
+                );
+                context.resources.halt_reason = Some(HaltReason::LieDetectorUnsolved);
+                let _ = context
+                    .resources
+                    .notification
+                    .schedule_notification(NotificationKind::LieDetectorUnsolved);
+            }
             WorldEvent::EliteBossAppeared => {
                 if !context.resources.operation.halting() {
                     let _ = context
@@ -152,6 +236,159 @@ impl EventHandler<WorldEvent> for WorldEventHandler {
                         .schedule_notification(NotificationKind::EliteBossAppear);
                 }
             }
+            WorldEvent::AdminAppeared => {
+                // Admin image is also shown inside booster confirmation popup
+                if matches!(context.world.player.state, Player::UsingBooster(_)) {
+                    return;
+                }
+
+                let reaction = context.settings_service.settings().admin_reaction.clone();
+                if !reaction.enabled {
+                    return;
+                }
+
+                warn!(target: "world", "admin detected, halting all actions");
+                context.operation_service.halt(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    false,
+                );
+                let _ = context
+                    .resources
+                    .notification
+                    .schedule_notification(NotificationKind::AdminAppear);
+
+                if reaction.chat_enabled && !reaction.chat_content.is_empty() {
+                    let delay_millis = reaction.chat_delay_millis
+                        + context
+                            .resources
+                            .rng
+                            .random_range(0..=reaction.chat_delay_millis_random_range);
+
+                    info!(target: "world", "admin reaction chat queued after {delay_millis}ms");
+                    context
+                        .world_service
+                        .queue_admin_chat(Duration::from_millis(delay_millis));
+                }
+            }
+            WorldEvent::AdminChatReady => {
+                let reaction = context.settings_service.settings().admin_reaction.clone();
+                if !reaction.enabled || !reaction.chat_enabled || reaction.chat_content.is_empty() {
+                    return;
+                }
+
+                if reaction.chat_content.chars().count() >= ChattingContent::MAX_LENGTH {
+                    warn!(target: "world", "admin reaction chat content is too long");
+                    return;
+                }
+
+                info!(target: "world", "sending admin reaction chat");
+                context.rotator.inject_action(PlayerAction::Chat(Chat {
+                    content: reaction.chat_content,
+                }));
+            }
+            WorldEvent::PlayerIdleTimedOut => {
+                if context.resources.operation.halting() {
+                    return;
+                }
+
+                warn!(target: "world", "player has not made any progress, unstucking");
+                context.world.player.context.clear_actions_aborted(true);
+                context.rotator.inject_action(PlayerAction::Unstuck);
+                let _ = context
+                    .resources
+                    .notification
+                    .schedule_notification(NotificationKind::PlayerIdle);
+            }
+            WorldEvent::PlayerRequestAppeared(request) => {
+                match context.settings_service.settings().player_request_policy {
+                    PlayerRequestPolicy::Ignore => (),
+                    PlayerRequestPolicy::Decline => {
+                        let button = request.decline;
+                        let x = button.x + button.width / 2;
+                        let y = button.y + button.height / 2;
+
+                        info!(target: "world", "declining {:?} request", request.kind);
+                        context.resources.input.send_mouse(x, y, MouseKind::Click);
+                    }
+                    PlayerRequestPolicy::HaltAndNotify => {
+                        if context.resources.operation.halting() {
+                            return;
+                        }
+
+                        warn!(target: "world", "{:?} request detected, halting", request.kind);
+                        context.operation_service.halt(
+                            context.resources,
+                            context.world,
+                            context.rotator,
+                            false,
+                        );
+                        let _ = context
+                            .resources
+                            .notification
+                            .schedule_notification(NotificationKind::PlayerRequest);
+                    }
+                }
+            }
+            WorldEvent::CashShopFreebiesClaimed(day) => {
+                spawn_blocking(move || {
+                    let result = query_or_upsert_daily_claims().and_then(|mut claims| {
+                        claims.cash_shop_freebies_day = Some(day);
+                        upsert_daily_claims(&mut claims)
+                    });
+                    if let Err(err) = result {
+                        error!(target: "world", "failed to save cash shop freebies claim {err}");
+                    }
+                });
+            }
         }
     }
 }
+
+/// Whether to halt on a mismatched map instead of recovering by re-detecting the minimap.
+///
+/// Recovering is attempted only when `stop_on_fail_or_change_map` is `false` and halts once
+/// the map stays mismatched after the maximum number of recoveries.
+fn should_halt_on_map_mismatch(
+    stop_on_fail_or_change_map: bool,
+    navigator: &mut dyn Navigator,
+) -> bool {
+    stop_on_fail_or_change_map || !navigator.recover_map_mismatch()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigator::MockNavigator;
+
+    #[test]
+    fn should_halt_on_map_mismatch_without_recovering_when_stopping() {
+        let mut navigator = MockNavigator::new();
+        navigator.expect_recover_map_mismatch().never();
+
+        assert!(should_halt_on_map_mismatch(true, &mut navigator));
+    }
+
+    #[test]
+    fn should_halt_on_map_mismatch_recovers_when_not_stopping() {
+        let mut navigator = MockNavigator::new();
+        navigator
+            .expect_recover_map_mismatch()
+            .once()
+            .return_const(true);
+
+        assert!(!should_halt_on_map_mismatch(false, &mut navigator));
+    }
+
+    #[test]
+    fn should_halt_on_map_mismatch_when_not_stopping_and_recoveries_exhausted() {
+        let mut navigator = MockNavigator::new();
+        navigator
+            .expect_recover_map_mismatch()
+            .once()
+            .return_const(false);
+
+        assert!(should_halt_on_map_mismatch(false, &mut navigator));
+    }
+}
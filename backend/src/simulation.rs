@@ -0,0 +1,396 @@
+use std::collections::VecDeque;
+
+use crate::{Action, ActionCondition, ActionKey, ActionMacro, ActionMove, run::MS_PER_TICK};
+
+/// The minimum interval between two [`ActionCondition::ErdaShowerOffCooldown`] actions.
+///
+/// The actual interval depends on the in-game cooldown which cannot be known offline.
+const ERDA_SHOWER_OFF_COOLDOWN_MILLIS: u64 = 20000;
+
+/// Arguments for [`simulate_rotation`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RotationSimulationArgs {
+    /// How long to simulate for.
+    pub duration_millis: u64,
+    /// The nominal time the player takes to move to an action's position.
+    pub travel_millis: u64,
+}
+
+impl Default for RotationSimulationArgs {
+    fn default() -> Self {
+        Self {
+            duration_millis: 3600000, // 1 hour
+            travel_millis: 1000,
+        }
+    }
+}
+
+/// A single simulated execution of a priority action.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SimulatedFire {
+    /// The index of the action inside the preset.
+    pub action_index: usize,
+    /// The time the action was queued.
+    pub queued_millis: u64,
+    /// The time the action started executing.
+    pub start_millis: u64,
+    /// The time the action finished executing.
+    pub end_millis: u64,
+}
+
+/// A priority action that was due while another priority action was executing.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SimulatedCollision {
+    /// The time the collision happened.
+    pub millis: u64,
+    /// The index of the action that had to wait.
+    pub action_index: usize,
+    /// The index of the action that was executing.
+    pub blocking_action_index: usize,
+}
+
+/// The simulated summary of a single priority action.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SimulatedActionSummary {
+    /// The index of the action inside the preset.
+    pub action_index: usize,
+    /// The configured interval.
+    pub interval_millis: u64,
+    /// The nominal time to execute the action including its linked actions.
+    pub duration_millis: u64,
+    /// Number of times the action has fired.
+    pub fire_count: u32,
+    /// The longest time the action waited in queue before executing.
+    pub max_wait_millis: u64,
+    /// Whether the action was starved.
+    ///
+    /// An action is starved when it never fired or waited in queue for at least its interval.
+    pub starved: bool,
+}
+
+/// The result of [`simulate_rotation`].
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct RotationSimulation {
+    /// The timeline of all fired priority actions ordered by start time.
+    pub fires: Vec<SimulatedFire>,
+    /// All the collisions ordered by time.
+    pub collisions: Vec<SimulatedCollision>,
+    /// The summary for each priority action ordered by action index.
+    pub summaries: Vec<SimulatedActionSummary>,
+}
+
+#[derive(Debug)]
+struct SimulatedAction {
+    action_index: usize,
+    interval_millis: u64,
+    duration_millis: u64,
+    queue_to_front: bool,
+    next_due_millis: u64,
+    queued_millis: Option<u64>,
+}
+
+/// Simulates when each [`ActionCondition::EveryMillis`] and
+/// [`ActionCondition::ErdaShowerOffCooldown`] action in `actions` will fire.
+///
+/// This follows the [`crate::rotator::Rotator`] priority queue where an action is only due again
+/// after its interval has passed since it finished executing and only one action executes at
+/// a time. Normal actions, buffs and other detection-based actions are not simulated.
+pub fn simulate_rotation(actions: &[Action], args: RotationSimulationArgs) -> RotationSimulation {
+    let mut simulated = simulated_actions(actions, args.travel_millis);
+    let mut result = RotationSimulation::default();
+    let mut queue = VecDeque::<usize>::new();
+    let mut executing = None::<(usize, usize)>; // (action, index into fires)
+    let mut now = 0;
+
+    while now < args.duration_millis {
+        if let Some((action, fire)) = executing
+            && result.fires[fire].end_millis <= now
+        {
+            simulated[action].next_due_millis = now + simulated[action].interval_millis;
+            executing = None;
+        }
+
+        for i in 0..simulated.len() {
+            let is_queued = simulated[i].queued_millis.is_some();
+            let is_executing = executing.is_some_and(|(action, _)| action == i);
+            if is_queued || is_executing || simulated[i].next_due_millis > now {
+                continue;
+            }
+
+            if let Some((action, _)) = executing.or(queue.front().map(|front| (*front, 0))) {
+                result.collisions.push(SimulatedCollision {
+                    millis: now,
+                    action_index: simulated[i].action_index,
+                    blocking_action_index: simulated[action].action_index,
+                });
+            }
+            simulated[i].queued_millis = Some(now);
+            if simulated[i].queue_to_front {
+                queue.push_front(i);
+            } else {
+                queue.push_back(i);
+            }
+        }
+
+        // Queue to front action overrides the currently executing non-queue to front action
+        if let Some((action, fire)) = executing
+            && !simulated[action].queue_to_front
+            && queue
+                .front()
+                .is_some_and(|front| simulated[*front].queue_to_front)
+        {
+            result.fires.remove(fire);
+            simulated[action].queued_millis = Some(now);
+            executing = None;
+            queue.insert(1, action);
+        }
+
+        if executing.is_none()
+            && let Some(action) = queue.pop_front()
+        {
+            let queued_millis = simulated[action].queued_millis.take().expect("queued");
+            result.fires.push(SimulatedFire {
+                action_index: simulated[action].action_index,
+                queued_millis,
+                start_millis: now,
+                end_millis: now + simulated[action].duration_millis.max(MS_PER_TICK),
+            });
+            executing = Some((action, result.fires.len() - 1));
+        }
+
+        let next_end = executing.map(|(_, fire)| result.fires[fire].end_millis);
+        let next_due = simulated
+            .iter()
+            .filter(|action| action.queued_millis.is_none() && action.next_due_millis > now)
+            .map(|action| action.next_due_millis)
+            .min();
+        let Some(next) = next_end.into_iter().chain(next_due).min() else {
+            break;
+        };
+        now = next;
+    }
+
+    result.summaries = simulated
+        .iter()
+        .map(|action| {
+            let fires = result
+                .fires
+                .iter()
+                .filter(|fire| fire.action_index == action.action_index);
+            let fire_count = fires.clone().count() as u32;
+            let max_wait_millis = fires
+                .map(|fire| fire.start_millis - fire.queued_millis)
+                .chain(action.queued_millis.map(|queued| now.max(queued) - queued))
+                .max()
+                .unwrap_or_default();
+
+            SimulatedActionSummary {
+                action_index: action.action_index,
+                interval_millis: action.interval_millis,
+                duration_millis: action.duration_millis,
+                fire_count,
+                max_wait_millis,
+                starved: fire_count == 0 || max_wait_millis >= action.interval_millis,
+            }
+        })
+        .collect();
+
+    result
+}
+
+/// Groups `actions` into priority actions with its linked actions the same way as the
+/// [`crate::rotator::Rotator`] does.
+fn simulated_actions(actions: &[Action], travel_millis: u64) -> Vec<SimulatedAction> {
+    let mut simulated = vec![];
+    let mut i = 0;
+
+    while i < actions.len() {
+        let action = actions[i];
+        let linked_count = actions
+            .iter()
+            .skip(i + 1)
+            .take_while(|action| matches!(action.condition(), ActionCondition::Linked))
+            .count();
+        let duration_millis = actions[i..=i + linked_count]
+            .iter()
+            .map(|action| nominal_duration_millis(action, travel_millis))
+            .sum();
+        let interval_millis = match action.condition() {
+            ActionCondition::EveryMillis(millis) => Some(millis),
+            ActionCondition::ErdaShowerOffCooldown => Some(ERDA_SHOWER_OFF_COOLDOWN_MILLIS),
+            // Expression actions depend on game state and cannot be simulated
+            ActionCondition::Any | ActionCondition::Linked | ActionCondition::Expression(_) => None,
+        };
+        if let Some(interval_millis) = interval_millis {
+            let queue_to_front = match action {
+                Action::Move(_) => false,
+                Action::Key(ActionKey { queue_to_front, .. })
+                | Action::Macro(ActionMacro { queue_to_front, .. }) => {
+                    queue_to_front.unwrap_or_default()
+                }
+            };
+            simulated.push(SimulatedAction {
+                action_index: i,
+                interval_millis,
+                duration_millis,
+                queue_to_front,
+                next_due_millis: 0,
+                queued_millis: None,
+            });
+        }
+
+        i += 1 + linked_count;
+    }
+
+    simulated
+}
+
+/// Estimates the time to execute `action` assuming the average of all random ranges.
+fn nominal_duration_millis(action: &Action, travel_millis: u64) -> u64 {
+    match *action {
+        Action::Move(ActionMove {
+            wait_after_move_millis,
+            ..
+        }) => travel_millis + wait_after_move_millis,
+        // The recorded keys are not known here
+        Action::Macro(_) => MS_PER_TICK,
+        Action::Key(ActionKey {
+            key_hold_millis,
+            count,
+            position,
+            wait_before_use_millis,
+            wait_before_use_millis_random_range,
+            wait_after_use_millis,
+            wait_after_use_millis_random_range,
+            ..
+        }) => {
+            let travel_millis = if position.is_some() { travel_millis } else { 0 };
+            let use_millis = count.max(1) as u64 * (key_hold_millis + MS_PER_TICK);
+
+            travel_millis
+                + wait_before_use_millis
+                + wait_before_use_millis_random_range / 2
+                + use_millis
+                + wait_after_use_millis
+                + wait_after_use_millis_random_range / 2
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn every_millis_key(millis: u64, wait_after_use_millis: u64) -> Action {
+        Action::Key(ActionKey {
+            condition: ActionCondition::EveryMillis(millis),
+            wait_after_use_millis,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn simulate_rotation_single_action_fires_every_interval() {
+        let actions = [every_millis_key(10000, 1000 - MS_PER_TICK)];
+        let args = RotationSimulationArgs {
+            duration_millis: 60000,
+            travel_millis: 0,
+        };
+
+        let result = simulate_rotation(&actions, args);
+
+        // Interval counts from when the action finished executing
+        let starts = result
+            .fires
+            .iter()
+            .map(|fire| fire.start_millis)
+            .collect::<Vec<_>>();
+        assert_eq!(starts, vec![0, 11000, 22000, 33000, 44000, 55000]);
+        assert!(result.collisions.is_empty());
+        assert_eq!(result.summaries[0].fire_count, 6);
+        assert!(!result.summaries[0].starved);
+    }
+
+    #[test]
+    fn simulate_rotation_collision_when_due_while_executing() {
+        let actions = [
+            every_millis_key(10000, 5000 - MS_PER_TICK),
+            every_millis_key(10000, 1000 - MS_PER_TICK),
+        ];
+        let args = RotationSimulationArgs {
+            duration_millis: 5000,
+            travel_millis: 0,
+        };
+
+        let result = simulate_rotation(&actions, args);
+
+        assert_eq!(
+            result.collisions[0],
+            SimulatedCollision {
+                millis: 0,
+                action_index: 1,
+                blocking_action_index: 0,
+            }
+        );
+        assert_eq!(result.fires[1].queued_millis, 0);
+        assert_eq!(result.fires[1].start_millis, 5000);
+    }
+
+    #[test]
+    fn simulate_rotation_starved_when_waiting_longer_than_interval() {
+        let actions = [
+            every_millis_key(1000, 5000 - MS_PER_TICK),
+            every_millis_key(1000, 5000 - MS_PER_TICK),
+        ];
+        let args = RotationSimulationArgs {
+            duration_millis: 30000,
+            travel_millis: 0,
+        };
+
+        let result = simulate_rotation(&actions, args);
+
+        assert!(result.summaries.iter().all(|summary| summary.starved));
+    }
+
+    #[test]
+    fn simulate_rotation_groups_linked_actions() {
+        let actions = [
+            every_millis_key(10000, 1000 - MS_PER_TICK),
+            Action::Key(ActionKey {
+                condition: ActionCondition::Linked,
+                wait_after_use_millis: 2000 - MS_PER_TICK,
+                ..Default::default()
+            }),
+            Action::Move(ActionMove {
+                condition: ActionCondition::Any,
+                ..Default::default()
+            }),
+        ];
+
+        let result = simulate_rotation(&actions, RotationSimulationArgs::default());
+
+        assert_eq!(result.summaries.len(), 1);
+        assert_eq!(result.summaries[0].duration_millis, 3000);
+    }
+
+    #[test]
+    fn simulate_rotation_queue_to_front_overrides_executing() {
+        let actions = [
+            every_millis_key(60000, 5000 - MS_PER_TICK),
+            Action::Key(ActionKey {
+                condition: ActionCondition::EveryMillis(60000),
+                queue_to_front: Some(true),
+                ..Default::default()
+            }),
+        ];
+        let args = RotationSimulationArgs {
+            duration_millis: 10000,
+            travel_millis: 0,
+        };
+
+        let result = simulate_rotation(&actions, args);
+
+        assert_eq!(result.fires[0].action_index, 1);
+        assert_eq!(result.fires[1].action_index, 0);
+    }
+}
@@ -0,0 +1,271 @@
+//! Read-only spectating of the game state from another UI instance.
+//!
+//! The instance running the bot serves [`GameState`]s and [`KeyBinding`]s as newline-delimited
+//! JSON over TCP. A spectating instance receives them in place of its own game loop.
+
+use std::{
+    sync::OnceLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::{
+        broadcast::{self, Receiver, Sender, error::RecvError},
+        watch,
+    },
+    time::sleep,
+};
+
+use crate::{BotOperation, GameState, KeyBinding, database};
+
+/// The minimum interval between two minimap frames sent to a spectator.
+///
+/// Frames are the bulk of a [`GameState`] so other states are sent without one in between.
+const FRAME_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The delay before reconnecting after the connection to the served instance is lost.
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+static SPECTATOR: OnceLock<Spectator> = OnceLock::new();
+
+#[derive(Debug, Serialize, Deserialize)]
+enum SpectateMessage {
+    GameState(GameState),
+    Key(KeyBinding),
+}
+
+/// The broadcasts received from the served instance when spectating.
+#[derive(Debug)]
+pub struct Spectator {
+    game_state_tx: Sender<GameState>,
+    key_tx: Sender<KeyBinding>,
+}
+
+impl Spectator {
+    pub fn subscribe_state(&self) -> Receiver<GameState> {
+        self.game_state_tx.subscribe()
+    }
+
+    pub fn subscribe_key(&self) -> Receiver<KeyBinding> {
+        self.key_tx.subscribe()
+    }
+}
+
+/// Gets the [`Spectator`] if this instance is spectating.
+#[inline]
+pub fn spectator() -> Option<&'static Spectator> {
+    SPECTATOR.get()
+}
+
+/// Starts spectating the instance serving at `address`.
+///
+/// This is used in place of [`crate::init`]. The database becomes read-only so that nothing can
+/// be modified from the spectating instance.
+pub fn spectate(address: String) {
+    let spectator = Spectator {
+        game_state_tx: broadcast::channel(1).0,
+        key_tx: broadcast::channel(1).0,
+    };
+    if SPECTATOR.set(spectator).is_err() {
+        return;
+    }
+    database::set_read_only();
+
+    thread::spawn(move || {
+        let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        tokio_rt.block_on(receive_loop(address));
+    });
+}
+
+async fn receive_loop(address: String) {
+    let spectator = spectator().expect("initialized");
+
+    loop {
+        match TcpStream::connect(&address).await {
+            Ok(stream) => {
+                info!(target: "spectate", "spectating {address}");
+                let mut lines = BufReader::new(stream).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    match serde_json::from_str::<SpectateMessage>(&line) {
+                        Ok(SpectateMessage::GameState(state)) => {
+                            let _ = spectator.game_state_tx.send(state);
+                        }
+                        Ok(SpectateMessage::Key(key)) => {
+                            let _ = spectator.key_tx.send(key);
+                        }
+                        Err(err) => warn!(target: "spectate", "invalid message {err}"),
+                    }
+                }
+                info!(target: "spectate", "disconnected from {address}");
+            }
+            Err(err) => warn!(target: "spectate", "unable to connect to {address} {err}"),
+        }
+        sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Serves `game_state_rx` and `key_rx` broadcasts to spectators connecting to `address`.
+///
+/// This must be called within a Tokio runtime.
+pub fn serve(
+    address: String,
+    mut game_state_rx: Receiver<GameState>,
+    key_rx: Receiver<KeyBinding>,
+) {
+    // Relays only the latest state so that a slow spectator cannot hold back the game state
+    // broadcast that the local UI also receives
+    let (state_tx, state_rx) = watch::channel(None);
+    tokio::spawn(async move {
+        loop {
+            match game_state_rx.recv().await {
+                Ok(state) => {
+                    state_tx.send_replace(Some(state));
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let listener = match TcpListener::bind(&address).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                warn!(target: "spectate", "unable to serve spectators at {address} {err}");
+                return;
+            }
+        };
+        info!(target: "spectate", "serving spectators at {address}");
+
+        loop {
+            let Ok((stream, peer)) = listener.accept().await else {
+                continue;
+            };
+            info!(target: "spectate", "spectator {peer} connected");
+            tokio::spawn(serve_spectator(
+                stream,
+                state_rx.clone(),
+                key_rx.resubscribe(),
+            ));
+        }
+    });
+}
+
+async fn serve_spectator(
+    mut stream: TcpStream,
+    mut state_rx: watch::Receiver<Option<GameState>>,
+    mut key_rx: Receiver<KeyBinding>,
+) {
+    let mut last_frame_instant = None::<Instant>;
+
+    loop {
+        let message = tokio::select! {
+            changed = state_rx.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let Some(mut state) = state_rx.borrow_and_update().clone() else {
+                    continue;
+                };
+                if state.frame.is_some() {
+                    if last_frame_instant.is_some_and(|instant| instant.elapsed() < FRAME_INTERVAL)
+                    {
+                        state.frame = None;
+                    } else {
+                        last_frame_instant = Some(Instant::now());
+                    }
+                }
+
+                SpectateMessage::GameState(state)
+            }
+            key = key_rx.recv() => match key {
+                Ok(key) => SpectateMessage::Key(key),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        let Ok(mut line) = serde_json::to_vec(&message) else {
+            continue;
+        };
+        line.push(b'\n');
+        if stream.write_all(&line).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// [`BotOperation`] with [`Instant`]s as the remaining [`Duration`]s since instants cannot be
+/// shared across processes.
+#[derive(Serialize, Deserialize)]
+enum RemoteBotOperation {
+    Halting,
+    TemporaryHalting(Duration),
+    HaltUntil(Duration),
+    Running,
+    RunUntil(Duration),
+}
+
+impl Serialize for BotOperation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let now = Instant::now();
+        let operation = match *self {
+            BotOperation::Halting => RemoteBotOperation::Halting,
+            BotOperation::TemporaryHalting(duration) => {
+                RemoteBotOperation::TemporaryHalting(duration)
+            }
+            BotOperation::HaltUntil(instant) => {
+                RemoteBotOperation::HaltUntil(instant.saturating_duration_since(now))
+            }
+            BotOperation::Running => RemoteBotOperation::Running,
+            BotOperation::RunUntil(instant) => {
+                RemoteBotOperation::RunUntil(instant.saturating_duration_since(now))
+            }
+        };
+
+        operation.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BotOperation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let now = Instant::now();
+        let operation = match RemoteBotOperation::deserialize(deserializer)? {
+            RemoteBotOperation::Halting => BotOperation::Halting,
+            RemoteBotOperation::TemporaryHalting(duration) => {
+                BotOperation::TemporaryHalting(duration)
+            }
+            RemoteBotOperation::HaltUntil(remaining) => BotOperation::HaltUntil(now + remaining),
+            RemoteBotOperation::Running => BotOperation::Running,
+            RemoteBotOperation::RunUntil(remaining) => BotOperation::RunUntil(now + remaining),
+        };
+
+        Ok(operation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bot_operation_round_trips_remaining_duration() {
+        let operation = BotOperation::RunUntil(Instant::now() + Duration::from_secs(60));
+
+        let json = serde_json::to_string(&operation).unwrap();
+        let BotOperation::RunUntil(instant) = serde_json::from_str(&json).unwrap() else {
+            panic!("unexpected operation");
+        };
+
+        let remaining = instant.saturating_duration_since(Instant::now());
+        assert!(remaining > Duration::from_secs(59) && remaining <= Duration::from_secs(60));
+    }
+}
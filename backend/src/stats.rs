@@ -0,0 +1,350 @@
+//! Statistics of the current bot session.
+//!
+//! The systems record runes, deaths, channel changes, executed actions, mob detections and EXP into
+//! [`Stats`] as they happen. The accumulated [`SessionStats`] is persisted into the database
+//! periodically so that the current and past sessions can be reviewed from the UI along with
+//! [`rotation_suggestions`] derived from them.
+
+use std::{
+    cell::{Cell, RefCell},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use strum::IntoEnumIterator;
+
+use crate::{
+    ActionDelayStats, ExpSample, RotationSuggestion, SessionStats, database::upsert_session_stats,
+    player::Quadrant,
+};
+
+/// How often the session is persisted into the database.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The minimum interval between two recorded EXP samples.
+const EXP_SAMPLE_INTERVAL_MILLIS: u64 = 60000;
+
+/// The minimum delay for an action start to be counted as delayed.
+const ACTION_DELAYED_THRESHOLD_MILLIS: u64 = 1000;
+
+/// The minimum number of mob detections before suggesting on quadrants.
+const SUGGESTION_MIN_MOB_DETECTIONS: u32 = 200;
+
+/// The minimum percentage of fewer mob detections for a quadrant to be suggested.
+const SUGGESTION_FEWER_MOB_DETECTIONS_PERCENT: u32 = 30;
+
+/// The minimum number of action delay samples before suggesting on an action.
+const SUGGESTION_MIN_ACTION_SAMPLES: u32 = 5;
+
+/// A resource for recording the statistics of the current session.
+#[derive(Debug)]
+pub struct Stats {
+    session: RefCell<SessionStats>,
+    /// The currently in use preset that executed actions are counted towards.
+    preset: RefCell<Option<String>>,
+    /// The [`Instant`] of the last update while the bot was running.
+    last_running: Cell<Option<Instant>>,
+    last_persisted: Cell<Instant>,
+    /// Whether anything was recorded since the last persist.
+    dirty: Cell<bool>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            session: RefCell::new(SessionStats {
+                started_millis: epoch_millis(),
+                ..SessionStats::default()
+            }),
+            preset: RefCell::new(None),
+            last_running: Cell::new(None),
+            last_persisted: Cell::new(Instant::now()),
+            dirty: Cell::new(false),
+        }
+    }
+}
+
+impl Stats {
+    /// Sets the currently in use `preset` that executed actions are counted towards.
+    pub fn set_preset(&self, preset: Option<String>) {
+        *self.preset.borrow_mut() = preset;
+    }
+
+    pub fn record_rune_solved(&self) {
+        self.record(|session| session.runes_solved += 1);
+    }
+
+    pub fn record_rune_failed(&self) {
+        self.record(|session| session.runes_failed += 1);
+    }
+
+    /// Gets the number of runes solved and failed in the current session.
+    pub fn runes(&self) -> (u32, u32) {
+        let session = self.session.borrow();
+        (session.runes_solved, session.runes_failed)
+    }
+
+    pub fn record_death(&self) {
+        self.record(|session| session.deaths += 1);
+    }
+
+    pub fn record_channel_change(&self) {
+        self.record(|session| session.channel_changes += 1);
+    }
+
+    /// Records `count` actions executed for the currently in use preset.
+    pub fn record_actions(&self, count: u32) {
+        if count == 0 {
+            return;
+        }
+        let Some(preset) = self.preset.borrow().clone() else {
+            return;
+        };
+
+        self.record(|session| *session.actions_per_preset.entry(preset).or_default() += count);
+    }
+
+    pub fn record_mob_detections(&self, count: usize) {
+        if count == 0 {
+            return;
+        }
+
+        self.record(|session| session.mob_detections += count as u32);
+    }
+
+    /// Records a mob detected in each of `quadrants` of the auto mob bound.
+    pub fn record_mob_quadrants(&self, quadrants: impl IntoIterator<Item = Quadrant>) {
+        self.record(|session| {
+            for quadrant in quadrants {
+                *session
+                    .mob_detections_per_quadrant
+                    .entry(quadrant.to_string())
+                    .or_default() += 1;
+            }
+        });
+    }
+
+    /// Records the fixed interval action `name` started again `delay` after its `interval`.
+    pub fn record_action_delay(&self, name: String, interval: Duration, delay: Duration) {
+        self.record(|session| {
+            let delays = session.action_delays.entry(name).or_default();
+            let delay_millis = delay.as_millis() as u64;
+
+            delays.interval_millis = interval.as_millis() as u64;
+            delays.samples += 1;
+            if delay_millis >= ACTION_DELAYED_THRESHOLD_MILLIS {
+                delays.delayed_samples += 1;
+                delays.delayed_millis += delay_millis;
+            }
+        });
+    }
+
+    /// Records the EXP `percent` and `level` read through OCR.
+    ///
+    /// Samples closer than [`EXP_SAMPLE_INTERVAL_MILLIS`] to the last one are skipped to keep the
+    /// persisted session small.
+    pub fn record_exp_sample(&self, level: Option<u32>, percent: f32) {
+        let timestamp_millis = epoch_millis();
+        let is_due = self
+            .session
+            .borrow()
+            .exp_samples
+            .last()
+            .is_none_or(|sample| {
+                timestamp_millis.saturating_sub(sample.timestamp_millis)
+                    >= EXP_SAMPLE_INTERVAL_MILLIS
+            });
+        if !is_due {
+            return;
+        }
+
+        self.record(|session| {
+            session.exp_samples.push(ExpSample {
+                timestamp_millis,
+                level,
+                exp_percent: Some(percent),
+            })
+        });
+    }
+
+    /// Accumulates the runtime if `running` and persists the session when due.
+    ///
+    /// Returns an error if persisting the session failed.
+    pub fn update(&self, running: bool) -> Result<()> {
+        self.update_runtime(running, Instant::now());
+
+        if self.dirty.get() && self.last_persisted.get().elapsed() >= PERSIST_INTERVAL {
+            self.persist()?;
+        }
+
+        Ok(())
+    }
+
+    fn update_runtime(&self, running: bool, now: Instant) {
+        if let Some(last_running) = self.last_running.get() {
+            let elapsed = now.saturating_duration_since(last_running).as_millis() as u64;
+            self.record(|session| session.runtime_millis += elapsed);
+        }
+        self.last_running.set(running.then_some(now));
+    }
+
+    fn persist(&self) -> Result<()> {
+        self.last_persisted.set(Instant::now());
+        self.dirty.set(false);
+        upsert_session_stats(&mut self.session.borrow_mut())
+    }
+
+    #[inline]
+    fn record(&self, f: impl FnOnce(&mut SessionStats)) {
+        f(&mut self.session.borrow_mut());
+        self.dirty.set(true);
+    }
+}
+
+/// Generates suggestions for tuning the rotation from the recorded `session`.
+///
+/// Quadrants of the auto mob bound yielding much fewer mob detections than the others are
+/// suggested to be left out of the bound. Fixed interval actions that are delayed in at least half
+/// of their starts are suggested a shorter interval. The interval is only shortened by a quarter
+/// of the average delay to avoid over-correcting as the delay usually comes from other actions
+/// occupying the player.
+pub fn rotation_suggestions(session: &SessionStats) -> Vec<RotationSuggestion> {
+    let mut suggestions = vec![];
+
+    let total = session.mob_detections_per_quadrant.values().sum::<u32>();
+    if total >= SUGGESTION_MIN_MOB_DETECTIONS {
+        for quadrant in Quadrant::iter() {
+            let quadrant = quadrant.to_string();
+            let count = session
+                .mob_detections_per_quadrant
+                .get(&quadrant)
+                .copied()
+                .unwrap_or_default();
+            let others_average = (total - count) as f32 / 3.0;
+            let fewer_percent = ((1.0 - count as f32 / others_average) * 100.0)
+                .round()
+                .max(0.0) as u32;
+
+            if fewer_percent >= SUGGESTION_FEWER_MOB_DETECTIONS_PERCENT {
+                suggestions.push(RotationSuggestion::ShrinkBound {
+                    quadrant,
+                    fewer_percent,
+                });
+            }
+        }
+    }
+
+    let mut action_delays = session
+        .action_delays
+        .iter()
+        .filter(|(_, delays)| delays.samples >= SUGGESTION_MIN_ACTION_SAMPLES)
+        .filter(|(_, delays)| delays.delayed_samples * 2 >= delays.samples)
+        .collect::<Vec<(&String, &ActionDelayStats)>>();
+    action_delays.sort_by(|a, b| a.0.cmp(b.0));
+    for (action, delays) in action_delays {
+        let delay_millis = delays.delayed_millis / delays.delayed_samples as u64;
+        let suggested_interval_millis =
+            delays.interval_millis.saturating_sub(delay_millis / 4) / 1000 * 1000;
+        if suggested_interval_millis == 0 || suggested_interval_millis >= delays.interval_millis {
+            continue;
+        }
+
+        suggestions.push(RotationSuggestion::ShortenInterval {
+            action: action.clone(),
+            interval_millis: delays.interval_millis,
+            delay_millis,
+            suggested_interval_millis,
+        });
+    }
+
+    suggestions
+}
+
+#[inline]
+fn epoch_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_actions_counts_towards_current_preset() {
+        let stats = Stats::default();
+
+        stats.record_actions(2);
+        stats.set_preset(Some("A".to_string()));
+        stats.record_actions(2);
+        stats.record_actions(1);
+        stats.set_preset(Some("B".to_string()));
+        stats.record_actions(4);
+
+        let session = stats.session.borrow();
+        assert_eq!(session.actions_per_preset.len(), 2);
+        assert_eq!(session.actions_per_preset.get("A"), Some(&3));
+        assert_eq!(session.actions_per_preset.get("B"), Some(&4));
+    }
+
+    #[test]
+    fn update_runtime_only_while_running() {
+        let stats = Stats::default();
+        let now = Instant::now();
+
+        stats.update_runtime(true, now);
+        stats.update_runtime(false, now + Duration::from_millis(100));
+        stats.update_runtime(false, now + Duration::from_millis(1000));
+        stats.update_runtime(true, now + Duration::from_millis(2000));
+        stats.update_runtime(true, now + Duration::from_millis(2050));
+
+        assert_eq!(stats.session.borrow().runtime_millis, 150);
+    }
+
+    #[test]
+    fn rotation_suggestions_shrink_bound_and_shorten_interval() {
+        let stats = Stats::default();
+        for (quadrant, count) in [
+            (Quadrant::TopLeft, 100),
+            (Quadrant::TopRight, 100),
+            (Quadrant::BottomRight, 100),
+            (Quadrant::BottomLeft, 60),
+        ] {
+            stats.record_mob_quadrants(std::iter::repeat_n(quadrant, count));
+        }
+        for delay in [20, 20, 20, 0, 20] {
+            stats.record_action_delay(
+                "Key A".to_string(),
+                Duration::from_secs(60),
+                Duration::from_secs(delay),
+            );
+        }
+        for _ in 0..5 {
+            stats.record_action_delay(
+                "Key B".to_string(),
+                Duration::from_secs(30),
+                Duration::from_millis(500),
+            );
+        }
+
+        let suggestions = rotation_suggestions(&stats.session.borrow());
+
+        assert_eq!(
+            suggestions,
+            vec![
+                RotationSuggestion::ShrinkBound {
+                    quadrant: "BottomLeft".to_string(),
+                    fewer_percent: 40,
+                },
+                RotationSuggestion::ShortenInterval {
+                    action: "Key A".to_string(),
+                    interval_millis: 60000,
+                    delay_millis: 20000,
+                    suggested_interval_millis: 55000,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Bot status for streaming and recording tools.
+//!
+//! The status is written to a plain text file that tools such as OBS can show as a text source.
+//! The first line is the operation and starts with [`ON_BREAK`] while the bot halts for a break
+//! so that a script watching the file can pause recording. The UI can also show the status as
+//! the window title in place of the random one.
+
+use std::{
+    env, fs,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+use tokio::sync::broadcast::{Receiver, error::RecvError};
+
+use crate::{BotOperation, GameState, dashboard::duration_text};
+
+/// The minimum interval between two writes of the status file.
+const WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The prefix of the operation line while the bot halts for a break.
+const ON_BREAK: &str = "On break";
+
+/// Writes the status from `game_state_rx` to the file at `path` whenever it changes.
+///
+/// A relative `path` is relative to the executable folder. This must be called within a Tokio
+/// runtime.
+pub fn write(path: String, mut game_state_rx: Receiver<GameState>) {
+    let path = env::current_exe().unwrap().parent().unwrap().join(path);
+    tokio::spawn(async move {
+        info!(target: "status", "writing status to {}", path.display());
+        let mut last_content = None::<String>;
+        let mut last_write_instant = None::<Instant>;
+        loop {
+            let state = match game_state_rx.recv().await {
+                Ok(state) => state,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            if last_write_instant.is_some_and(|instant| instant.elapsed() < WRITE_INTERVAL) {
+                continue;
+            }
+
+            let content = status_file_content(&state, Instant::now());
+            if last_content.as_ref() == Some(&content) {
+                continue;
+            }
+            last_write_instant = Some(Instant::now());
+            if let Err(err) = fs::write(&path, &content) {
+                warn!(target: "status", "unable to write status to {} {err}", path.display());
+            }
+            last_content = Some(content);
+        }
+    });
+}
+
+/// Gets the one-line status of `state` suitable for a window title.
+pub fn status_title(state: &GameState) -> String {
+    format!(
+        "{} - {}",
+        operation_text(state.operation, Instant::now()),
+        state.state
+    )
+}
+
+fn status_file_content(state: &GameState, now: Instant) -> String {
+    let mut lines = vec![
+        operation_text(state.operation, now),
+        format!("State: {}", state.state),
+    ];
+    if let Some((current, max)) = state.health {
+        lines.push(format!("HP: {current} / {max}"));
+    }
+    if let Some(action) = state
+        .priority_action
+        .as_ref()
+        .or(state.normal_action.as_ref())
+    {
+        lines.push(format!("Action: {action}"));
+    }
+
+    lines.join("\n")
+}
+
+fn operation_text(operation: BotOperation, now: Instant) -> String {
+    match operation {
+        BotOperation::Halting => "Halting".to_string(),
+        BotOperation::Running => "Running".to_string(),
+        BotOperation::TemporaryHalting(duration) => {
+            format!("{ON_BREAK} ({} left)", duration_text(duration))
+        }
+        BotOperation::HaltUntil(instant) => format!(
+            "{ON_BREAK} ({} left)",
+            duration_text(instant.saturating_duration_since(now))
+        ),
+        BotOperation::RunUntil(instant) => format!(
+            "Running ({} left)",
+            duration_text(instant.saturating_duration_since(now))
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FrameTimings;
+
+    #[test]
+    fn status_file_content_starts_with_on_break_during_breaks() {
+        let now = Instant::now();
+        let state = GameState {
+            position: None,
+            health: Some((50, 100)),
+            mana: None,
+            state: "Idle".to_string(),
+            normal_action: Some("Key".to_string()),
+            priority_action: None,
+            next_priority_action: None,
+            erda_shower_state: String::default(),
+            destinations: vec![],
+            waypoints: vec![],
+            operation: BotOperation::HaltUntil(now + Duration::from_secs(90)),
+            frame: None,
+            platforms_bound: None,
+            portals: vec![],
+            auto_mob_quadrant: None,
+            stranger_count: 0,
+            crowding_score: 0.0,
+            external_preset_error: None,
+            detected_character_id: None,
+            exp: None,
+            runes_solved: 0,
+            runes_failed: 0,
+            errors: vec![],
+            frame_timings: FrameTimings::default(),
+            priority_actions_dropped: vec![],
+        };
+
+        assert_eq!(
+            status_file_content(&state, now),
+            "On break (00:01:30 left)\nState: Idle\nHP: 50 / 100\nAction: Key"
+        );
+    }
+}
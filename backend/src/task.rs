@@ -1,6 +1,9 @@
 use std::{
+    collections::VecDeque,
     fmt::{self, Debug, Formatter},
-    sync::Arc,
+    panic::{AssertUnwindSafe, catch_unwind},
+    sync::{Arc, Condvar, LazyLock, Mutex},
+    thread::{self, available_parallelism},
     time::Duration,
 };
 
@@ -14,6 +17,123 @@ use tokio::{
 
 use crate::{detect::Detector, ecs::Resources};
 
+/// The shared pool running detection tasks.
+static DETECTION_POOL: LazyLock<Arc<DetectionPool>> = LazyLock::new(|| {
+    let workers = available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or_default()
+        .clamp(2, 8);
+    DetectionPool::new(workers)
+});
+
+/// Priority of a detection task in [`DetectionPool`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskPriority {
+    /// Tasks the player state transitions depend on (e.g. minimap, player).
+    ///
+    /// These tasks are always picked up before [`TaskPriority::Low`] tasks.
+    High,
+    /// Heavy tasks that can lag behind (e.g. mob detection, text recognition).
+    ///
+    /// These tasks never occupy all workers so that a [`TaskPriority::High`] task can always be
+    /// picked up.
+    Low,
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct DetectionQueue {
+    high: VecDeque<Job>,
+    low: VecDeque<Job>,
+    low_running: usize,
+}
+
+/// A fixed-size worker pool for running blocking detections off the tick loop.
+///
+/// Unlike [`spawn_blocking`], the number of concurrent detections is bounded so heavy detections
+/// cannot crowd out minimap and player detections.
+struct DetectionPool {
+    queue: Mutex<DetectionQueue>,
+    condvar: Condvar,
+    max_low_running: usize,
+}
+
+impl DetectionPool {
+    fn new(workers: usize) -> Arc<Self> {
+        let workers = workers.max(2);
+        let pool = Arc::new(Self {
+            queue: Mutex::new(DetectionQueue::default()),
+            condvar: Condvar::new(),
+            max_low_running: workers - 1,
+        });
+        for i in 0..workers {
+            let pool = pool.clone();
+            thread::Builder::new()
+                .name(format!("detection-{i}"))
+                .spawn(move || pool.run_worker())
+                .expect("spawn detection worker");
+        }
+
+        pool
+    }
+
+    fn spawn<T, F>(&self, priority: TaskPriority, f: F) -> Task<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job = Box::new(move || {
+            let _ = tx.send(f());
+        });
+        let mut queue = self.queue.lock().unwrap();
+        match priority {
+            TaskPriority::High => queue.high.push_back(job),
+            TaskPriority::Low => queue.low.push_back(job),
+        }
+        self.condvar.notify_one();
+
+        Task { rx }
+    }
+
+    fn run_worker(&self) {
+        loop {
+            let (job, is_low) = {
+                let mut queue = self.queue.lock().unwrap();
+                loop {
+                    if let Some(job) = queue.high.pop_front() {
+                        break (job, false);
+                    }
+                    if queue.low_running < self.max_low_running
+                        && let Some(job) = queue.low.pop_front()
+                    {
+                        queue.low_running += 1;
+                        break (job, true);
+                    }
+                    queue = self.condvar.wait(queue).unwrap();
+                }
+            };
+
+            // A panicking detection drops its sender so the task completes without a value
+            let _ = catch_unwind(AssertUnwindSafe(job));
+            if is_low {
+                self.queue.lock().unwrap().low_running -= 1;
+                // Wakes up idle workers that were not allowed to pick up low priority tasks
+                self.condvar.notify_all();
+            }
+        }
+    }
+}
+
+impl Debug for DetectionPool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DetectionPool")
+            .field("max_low_running", &self.max_low_running)
+            .finish_non_exhaustive()
+    }
+}
+
 /// An asynchronous task.
 ///
 /// This is a simple wrapper around [`tokio::task::spawn`] and [`tokio::sync::oneshot`] mainly
@@ -74,6 +194,22 @@ pub fn update_task<F, T, A>(
     task_fn_args: impl FnOnce() -> A,
     task_fn: F,
 ) -> Update<T>
+where
+    F: FnOnce(A) -> Result<T> + Send + 'static,
+    T: Debug + Send + 'static,
+    A: Send + 'static,
+{
+    update_task_inner(repeat_delay_millis, task, task_fn_args, task_fn, None)
+}
+
+#[inline]
+fn update_task_inner<F, T, A>(
+    repeat_delay_millis: u64,
+    task: &mut Option<Task<Result<T>>>,
+    task_fn_args: impl FnOnce() -> A,
+    task_fn: F,
+    priority: Option<TaskPriority>,
+) -> Update<T>
 where
     F: FnOnce(A) -> Result<T> + Send + 'static,
     T: Debug + Send + 'static,
@@ -103,9 +239,14 @@ where
             })
         } else {
             let args = task_fn_args();
-            let fut = spawn_blocking(move || task_fn(args));
+            match priority {
+                Some(priority) => DETECTION_POOL.spawn(priority, move || task_fn(args)),
+                None => {
+                    let fut = spawn_blocking(move || task_fn(args));
 
-            Task::spawn(async move { fut.await.unwrap() })
+                    Task::spawn(async move { fut.await.unwrap() })
+                }
+            }
         };
 
         *task = Some(spawned);
@@ -114,6 +255,7 @@ where
     update
 }
 
+/// Updates a detection `task` running with [`TaskPriority::High`] in the detection pool.
 #[inline]
 pub fn update_detection_task<F, T>(
     resources: &Resources,
@@ -125,22 +267,48 @@ where
     F: FnOnce(Arc<dyn Detector>) -> Result<T> + Send + 'static,
     T: Debug + Send + 'static,
 {
-    update_task(
+    update_detection_task_with_priority(
+        resources,
+        TaskPriority::High,
+        repeat_delay_millis,
+        task,
+        task_fn,
+    )
+}
+
+/// Updates a detection `task` running with `priority` in the detection pool.
+#[inline]
+pub fn update_detection_task_with_priority<F, T>(
+    resources: &Resources,
+    priority: TaskPriority,
+    repeat_delay_millis: u64,
+    task: &mut Option<Task<Result<T>>>,
+    task_fn: F,
+) -> Update<T>
+where
+    F: FnOnce(Arc<dyn Detector>) -> Result<T> + Send + 'static,
+    T: Debug + Send + 'static,
+{
+    update_task_inner(
         repeat_delay_millis,
         task,
         || resources.detector_cloned(),
         task_fn,
+        Some(priority),
     )
 }
 
 #[cfg(test)]
 mod tests {
-    use std::assert_matches::assert_matches;
+    use std::{
+        assert_matches::assert_matches,
+        sync::{Arc, Mutex, mpsc},
+    };
 
     use anyhow::Result;
     use tokio::task::yield_now;
 
-    use crate::task::{Task, Update, update_task};
+    use crate::task::{DetectionPool, Task, TaskPriority, Update, update_task};
 
     #[tokio::test(start_paused = true)]
     async fn spawn_state() {
@@ -184,4 +352,37 @@ mod tests {
         );
         assert!(!task.as_ref().unwrap().completed());
     }
+
+    #[tokio::test]
+    async fn detection_pool_high_priority_not_starved() {
+        let pool = DetectionPool::new(2);
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let release_rx = Arc::new(Mutex::new(release_rx));
+        let low_tasks = (0..2)
+            .map(|_| {
+                let release_rx = release_rx.clone();
+                pool.spawn(TaskPriority::Low, move || {
+                    release_rx.lock().unwrap().recv().unwrap();
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut high = pool.spawn(TaskPriority::High, || 1);
+        while !high.completed() {
+            match high.poll_inner() {
+                Some(value) => assert_eq!(value, 1),
+                None => yield_now().await,
+            }
+        }
+        assert!(low_tasks.iter().all(|task| !task.completed()));
+
+        release_tx.send(()).unwrap();
+        release_tx.send(()).unwrap();
+        for mut task in low_tasks {
+            while !task.completed() {
+                let _ = task.poll_inner();
+                yield_now().await;
+            }
+        }
+    }
 }
@@ -0,0 +1,322 @@
+//! Telegram bot for pushing notifications and accepting simple commands.
+//!
+//! Notifications are sent to the configured chat by [`DiscordNotification`] alongside the Discord
+//! webhook. Commands are received by long polling `getUpdates` and only accepted from the
+//! configured chat. Like the UI, commands go to the currently selected instance.
+//!
+//! [`DiscordNotification`]: crate::notification::DiscordNotification
+
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use log::{debug, info, warn};
+use reqwest::{
+    Client,
+    multipart::{Form, Part},
+};
+use serde::Deserialize;
+use tokio::time::{sleep, timeout};
+
+use crate::{
+    BotOperation, BotOperationUpdate, GameState, game_state_receiver, save_capture_image,
+    update_operation,
+};
+
+const API_URL: &str = "https://api.telegram.org";
+
+/// The number of seconds `getUpdates` waits for a new update before returning.
+const POLL_TIMEOUT_SECS: u64 = 30;
+
+/// The delay before polling again after a failed `getUpdates`.
+const POLL_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// The maximum duration to wait for a [`GameState`] when replying to `/status`.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Command {
+    Halt,
+    Run,
+    Status,
+    Screenshot,
+}
+
+#[derive(Debug, Deserialize)]
+struct Updates {
+    ok: bool,
+    #[serde(default)]
+    result: Vec<Update>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    chat: Chat,
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+/// A Telegram bot `token` and the `chat_id` it talks to.
+#[derive(Clone, Debug)]
+pub struct TelegramChat {
+    client: Client,
+    token: String,
+    chat_id: String,
+}
+
+impl TelegramChat {
+    /// Creates a chat from the bot `token` and `chat_id`.
+    ///
+    /// Returns [`None`] if either is empty.
+    pub fn new(token: String, chat_id: String) -> Option<Self> {
+        if token.is_empty() || chat_id.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            client: Client::new(),
+            token,
+            chat_id,
+        })
+    }
+
+    /// Sends a plain `text` message.
+    pub async fn send_message(&self, text: String) -> Result<()> {
+        let response = self
+            .client
+            .post(self.method_url("sendMessage"))
+            .form(&[("chat_id", self.chat_id.as_str()), ("text", text.as_str())])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            bail!("sendMessage failed with status {}", response.status());
+        }
+
+        Ok(())
+    }
+
+    /// Sends a PNG `image` with `caption`.
+    pub async fn send_photo(&self, image: Vec<u8>, caption: String) -> Result<()> {
+        let form = Form::new()
+            .text("chat_id", self.chat_id.clone())
+            .text("caption", caption)
+            .part("photo", Part::bytes(image).file_name("image.png"));
+        let response = self
+            .client
+            .post(self.method_url("sendPhoto"))
+            .multipart(form)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            bail!("sendPhoto failed with status {}", response.status());
+        }
+
+        Ok(())
+    }
+
+    async fn updates(&self, offset: i64) -> Result<Vec<Update>> {
+        let body = self
+            .client
+            .get(self.method_url("getUpdates"))
+            .query(&[("offset", offset), ("timeout", POLL_TIMEOUT_SECS as i64)])
+            .send()
+            .await?
+            .text()
+            .await?;
+        let updates = serde_json::from_str::<Updates>(&body)?;
+        if !updates.ok {
+            bail!("getUpdates failed {body}");
+        }
+
+        Ok(updates.result)
+    }
+
+    fn method_url(&self, method: &str) -> String {
+        format!("{API_URL}/bot{}/{method}", self.token)
+    }
+}
+
+/// Polls commands sent to `chat` in the background.
+///
+/// This must be called within a Tokio runtime.
+pub fn serve(chat: TelegramChat) {
+    tokio::spawn(async move {
+        info!(target: "telegram", "polling Telegram commands");
+        let mut offset = 0;
+        loop {
+            let updates = match chat.updates(offset).await {
+                Ok(updates) => updates,
+                Err(err) => {
+                    warn!(target: "telegram", "polling Telegram commands failed {err}");
+                    sleep(POLL_RETRY_DELAY).await;
+                    continue;
+                }
+            };
+
+            for update in updates {
+                offset = offset.max(update.update_id + 1);
+                let Some(message) = update.message else {
+                    continue;
+                };
+                // Ignores other chats since anyone can message the bot
+                if message.chat.id.to_string() != chat.chat_id {
+                    continue;
+                }
+                let Some(command) = message.text.as_deref().and_then(parse_command) else {
+                    continue;
+                };
+
+                debug!(target: "telegram", "received command {command:?}");
+                if let Err(err) = handle_command(&chat, command).await {
+                    warn!(target: "telegram", "replying to {command:?} failed {err}");
+                }
+            }
+        }
+    });
+}
+
+async fn handle_command(chat: &TelegramChat, command: Command) -> Result<()> {
+    match command {
+        Command::Halt => {
+            update_operation(BotOperationUpdate::Halt).await;
+            chat.send_message("Bot stopped running.".to_string()).await
+        }
+        Command::Run => {
+            update_operation(BotOperationUpdate::Run).await;
+            chat.send_message("Bot attempted to run.".to_string()).await
+        }
+        Command::Status => {
+            let mut game_state_rx = game_state_receiver().await;
+            let status = match timeout(STATUS_TIMEOUT, game_state_rx.recv()).await {
+                Ok(Ok(state)) => status_text(&state),
+                Ok(Err(_)) | Err(_) => "Game state is not available.".to_string(),
+            };
+            chat.send_message(status).await
+        }
+        Command::Screenshot => match save_capture_image(false).await {
+            Some(image) => chat.send_photo(image, "Game snapshot".to_string()).await,
+            None => chat.send_message("Nothing captured yet.".to_string()).await,
+        },
+    }
+}
+
+/// Parses `text` as a command, also accepting the `/command@bot_name` form used in groups.
+fn parse_command(text: &str) -> Option<Command> {
+    let command = text.split_whitespace().next()?;
+    let command = command.split('@').next()?;
+
+    match command {
+        "/halt" => Some(Command::Halt),
+        "/run" => Some(Command::Run),
+        "/status" => Some(Command::Status),
+        "/screenshot" => Some(Command::Screenshot),
+        _ => None,
+    }
+}
+
+fn status_text(state: &GameState) -> String {
+    let operation = match state.operation {
+        BotOperation::Halting => "Halting",
+        BotOperation::TemporaryHalting(_) => "Halting temporarily",
+        BotOperation::HaltUntil(_) => "Halting until next run",
+        BotOperation::Running => "Running",
+        BotOperation::RunUntil(_) => "Running until next stop",
+    };
+    let position = state
+        .position
+        .map(|(x, y)| format!("{x}, {y}"))
+        .unwrap_or_else(|| "Unknown".to_string());
+    let health = state
+        .health
+        .map(|(current, max)| format!("{current} / {max}"))
+        .unwrap_or_else(|| "Unknown".to_string());
+
+    [
+        format!("- Operation: {operation}"),
+        format!("- State: {}", state.state),
+        format!("- Position: {position}"),
+        format!("- Health: {health}"),
+        format!(
+            "- Normal action: {}",
+            state.normal_action.as_deref().unwrap_or("None")
+        ),
+        format!(
+            "- Priority action: {}",
+            state.priority_action.as_deref().unwrap_or("None")
+        ),
+        format!("- Strangers: {}", state.stranger_count),
+    ]
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FrameTimings;
+
+    #[test]
+    fn parse_command_accepts_bot_name_and_arguments() {
+        assert_eq!(parse_command("/halt"), Some(Command::Halt));
+        assert_eq!(parse_command("/run@komari_bot"), Some(Command::Run));
+        assert_eq!(parse_command("/status now"), Some(Command::Status));
+        assert_eq!(parse_command("/screenshot"), Some(Command::Screenshot));
+        assert_eq!(parse_command("halt"), None);
+        assert_eq!(parse_command("/unknown"), None);
+    }
+
+    #[test]
+    fn status_text_lists_game_state() {
+        let state = GameState {
+            position: Some((10, 20)),
+            health: None,
+            mana: None,
+            state: "Idle".to_string(),
+            normal_action: None,
+            priority_action: Some("Buff".to_string()),
+            next_priority_action: None,
+            erda_shower_state: "Detecting".to_string(),
+            destinations: vec![],
+            waypoints: vec![],
+            operation: BotOperation::Running,
+            frame: None,
+            platforms_bound: None,
+            portals: vec![],
+            auto_mob_quadrant: None,
+            stranger_count: 0,
+            crowding_score: 0.0,
+            external_preset_error: None,
+            detected_character_id: None,
+            exp: None,
+            runes_solved: 0,
+            runes_failed: 0,
+            errors: vec![],
+            frame_timings: FrameTimings::default(),
+            priority_actions_dropped: vec![],
+        };
+
+        let text = status_text(&state);
+
+        assert_eq!(
+            text,
+            [
+                "- Operation: Running",
+                "- State: Idle",
+                "- Position: 10, 20",
+                "- Health: Unknown",
+                "- Normal action: None",
+                "- Priority action: Buff",
+                "- Strangers: 0",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -6,7 +6,10 @@ use std::{
     time::{SystemTime, UNIX_EPOCH},
 };
 
-use opencv::{core::ToInputArray, imgcodecs::imwrite_def};
+use opencv::{
+    core::{Rect, Size, ToInputArray},
+    imgcodecs::imwrite_def,
+};
 
 static DATASET_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
     let dir = env::current_exe()
@@ -26,6 +29,12 @@ static DATASET_MINIMAP_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
 });
 
 #[cfg(debug_assertions)]
+static DATASET_MOB_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
+    let dir = DATASET_DIR.join("mob");
+    fs::create_dir_all(dir.clone()).unwrap();
+    dir
+});
+
 static DATASET_RUNE_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
     let dir = DATASET_DIR.join("rune");
     fs::create_dir_all(dir.clone()).unwrap();
@@ -38,16 +47,18 @@ pub enum DatasetDir {
     #[cfg(debug_assertions)]
     Minimap,
     #[cfg(debug_assertions)]
+    Mob,
     Rune,
 }
 
 impl DatasetDir {
-    fn to_folder(&self) -> PathBuf {
+    pub fn to_folder(&self) -> PathBuf {
         match self {
             DatasetDir::Root => DATASET_DIR.clone(),
             #[cfg(debug_assertions)]
             DatasetDir::Minimap => DATASET_MINIMAP_DIR.clone(),
             #[cfg(debug_assertions)]
+            DatasetDir::Mob => DATASET_MOB_DIR.clone(),
             DatasetDir::Rune => DATASET_RUNE_DIR.clone(),
         }
     }
@@ -71,7 +82,6 @@ pub fn save_image_to<P: AsRef<Path>>(mat: &impl ToInputArray, dir: DatasetDir, r
     let _ = imwrite_def(image.to_str().unwrap(), mat);
 }
 
-#[cfg(debug_assertions)]
 pub fn save_file_to<P: AsRef<Path>, C: AsRef<[u8]>>(contents: C, dir: DatasetDir, relative: P) {
     let folder = dir.to_folder();
     let file = folder.join(relative);
@@ -82,7 +92,18 @@ pub fn save_file_to<P: AsRef<Path>, C: AsRef<[u8]>>(contents: C, dir: DatasetDir
     let _ = fs::write(file, contents);
 }
 
-fn epoch_millis_as_string() -> String {
+/// Converts `bbox` in an image of `size` to a YOLO label line with class `label`.
+pub fn to_yolo_format(label: u32, size: Size, bbox: Rect) -> String {
+    let x_center = bbox.x + bbox.width / 2;
+    let y_center = bbox.y + bbox.height / 2;
+    let x_center = x_center as f32 / size.width as f32;
+    let y_center = y_center as f32 / size.height as f32;
+    let width = bbox.width as f32 / size.width as f32;
+    let height = bbox.height as f32 / size.height as f32;
+    format!("{label} {x_center} {y_center} {width} {height}")
+}
+
+pub fn epoch_millis_as_string() -> String {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
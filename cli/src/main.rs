@@ -0,0 +1,258 @@
+//! Headless front end running the bot without the UI for server-style deployments.
+//!
+//! The map, preset, character and capture window are loaded by name from the database and the
+//! bot is then controlled by commands read line by line from stdin:
+//! - `start`: Starts running the bot.
+//! - `stop`: Halts the bot.
+//! - `pause`: Temporarily halts the bot.
+//! - `status`: Prints the current bot state.
+//! - `export-rune-dataset`: Exports the local rune dataset as a zip archive.
+//! - `quit`: Halts the bot and exits.
+//!
+//! Ctrl-C is handled the same as `quit`. When stdin is closed, the bot keeps running until
+//! interrupted.
+
+use std::{
+    env::{self, current_exe},
+    io::stdout,
+    process::ExitCode,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use backend::{
+    BotOperation, BotOperationUpdate, export_rune_dataset, game_state_receiver,
+    query_capture_handles, query_characters, query_maps, refresh_capture_handles,
+    select_capture_handle, update_character, update_map, update_operation,
+};
+use fern::Dispatch;
+use log::{LevelFilter, error, info, warn};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader, stdin},
+    signal::ctrl_c,
+    sync::mpsc::{self, UnboundedReceiver},
+};
+
+/// The name of the map to load from `--map <name>`.
+static MAP: LazyLock<Option<String>> = LazyLock::new(|| arg_value("--map"));
+
+/// The name of the map preset to use from `--preset <name>`.
+///
+/// Defaults to the first preset of the map.
+static PRESET: LazyLock<Option<String>> = LazyLock::new(|| arg_value("--preset"));
+
+/// The name of the character to load from `--character <name>`.
+static CHARACTER: LazyLock<Option<String>> = LazyLock::new(|| arg_value("--character"));
+
+/// The name of the window to capture from `--capture <name>`.
+///
+/// Defaults to the window selected previously.
+static CAPTURE: LazyLock<Option<String>> = LazyLock::new(|| arg_value("--capture"));
+
+/// Whether to start running the bot immediately from `--run`.
+static RUN: LazyLock<bool> = LazyLock::new(|| env::args().any(|arg| arg == "--run"));
+
+/// Whether to run the bot against a simulated game from `--sandbox`.
+static SANDBOX: LazyLock<bool> = LazyLock::new(|| env::args().any(|arg| arg == "--sandbox"));
+
+#[derive(Debug)]
+enum Command {
+    Start,
+    Stop,
+    Pause,
+    Status,
+    ExportRuneDataset,
+    Quit,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let level = if cfg!(debug_assertions) {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Info
+    };
+    Dispatch::new()
+        .format(|out, message, record| {
+            out.finish(format_args!(
+                "[{} {} {}] {}",
+                humantime::format_rfc3339(std::time::SystemTime::now()),
+                record.level(),
+                record.target(),
+                message
+            ))
+        })
+        .level(level)
+        .chain(stdout())
+        .chain(fern::log_file(current_exe().unwrap().parent().unwrap().join("log.txt")).unwrap())
+        .apply()
+        .unwrap();
+    log_panics::init();
+
+    if *SANDBOX {
+        backend::init_sandbox();
+    } else {
+        backend::init();
+    }
+
+    if let Some(name) = CAPTURE.as_ref()
+        && !select_capture(name).await
+    {
+        error!(target: "cli", "capture window {name} not found");
+        return ExitCode::FAILURE;
+    }
+    if let Some(name) = CHARACTER.as_ref()
+        && !load_character(name).await
+    {
+        error!(target: "cli", "character {name} not found");
+        return ExitCode::FAILURE;
+    }
+    if let Some(name) = MAP.as_ref()
+        && !load_map(name, PRESET.clone()).await
+    {
+        error!(target: "cli", "map {name} or its preset not found");
+        return ExitCode::FAILURE;
+    }
+    if *RUN {
+        update_operation(BotOperationUpdate::Run).await;
+    }
+    info!(target: "cli", "ready, available commands: start, stop, pause, status, export-rune-dataset, quit");
+
+    let mut commands = spawn_command_reader();
+    loop {
+        let command = tokio::select! {
+            Some(command) = commands.recv() => command,
+            _ = ctrl_c() => Command::Quit,
+        };
+        match command {
+            Command::Start => update_operation(BotOperationUpdate::Run).await,
+            Command::Stop => update_operation(BotOperationUpdate::Halt).await,
+            Command::Pause => update_operation(BotOperationUpdate::TemporaryHalt).await,
+            Command::Status => print_status().await,
+            Command::ExportRuneDataset => match export_rune_dataset().await {
+                Some(path) => println!("rune dataset exported to {path}"),
+                None => println!("rune dataset is empty or failed to export"),
+            },
+            Command::Quit => {
+                update_operation(BotOperationUpdate::Halt).await;
+                return ExitCode::SUCCESS;
+            }
+        }
+    }
+}
+
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = env::args();
+    args.position(|arg| arg == name)?;
+    args.next()
+}
+
+/// Spawns a task parsing [`Command`]s from stdin until it is closed.
+fn spawn_command_reader() -> UnboundedReceiver<Command> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stdin()).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let command = match line.trim().to_lowercase().as_str() {
+                "" => continue,
+                "start" => Command::Start,
+                "stop" => Command::Stop,
+                "pause" => Command::Pause,
+                "status" => Command::Status,
+                "export-rune-dataset" => Command::ExportRuneDataset,
+                "quit" | "exit" => Command::Quit,
+                other => {
+                    warn!(target: "cli", "unknown command {other}");
+                    continue;
+                }
+            };
+            if tx.send(command).is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+async fn select_capture(name: &str) -> bool {
+    refresh_capture_handles().await;
+    let (names, _) = query_capture_handles().await;
+    let Some(index) = names.iter().position(|handle| handle == name) else {
+        return false;
+    };
+    select_capture_handle(Some(index)).await;
+
+    true
+}
+
+async fn load_character(name: &str) -> bool {
+    let character = query_characters()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .find(|character| character.name == name);
+    let found = character.is_some();
+    if found {
+        update_character(character).await;
+    }
+
+    found
+}
+
+async fn load_map(name: &str, preset: Option<String>) -> bool {
+    let Some(map) = query_maps()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .find(|map| map.name == name)
+    else {
+        return false;
+    };
+    let preset = match preset {
+        Some(preset) if !map.actions.contains_key(&preset) => return false,
+        Some(preset) => Some(preset),
+        None => map.actions.keys().next().cloned(),
+    };
+    update_map(preset, Some(map)).await;
+
+    true
+}
+
+async fn print_status() {
+    let Ok(state) = game_state_receiver().await.recv().await else {
+        return;
+    };
+    let operation = match state.operation {
+        BotOperation::Halting => "halting".to_string(),
+        BotOperation::Running => "running".to_string(),
+        BotOperation::TemporaryHalting(duration) => {
+            format!(
+                "halting temporarily with {} remaining",
+                duration_from(duration)
+            )
+        }
+        BotOperation::HaltUntil(instant) => format!(
+            "halting for {}",
+            duration_from(instant.saturating_duration_since(Instant::now()))
+        ),
+        BotOperation::RunUntil(instant) => format!(
+            "running for {}",
+            duration_from(instant.saturating_duration_since(Instant::now()))
+        ),
+    };
+    println!("operation: {operation}");
+    println!("state: {}", state.state);
+    println!("position: {:?}", state.position);
+    println!("health: {:?}", state.health);
+    println!("normal action: {:?}", state.normal_action);
+    println!("priority action: {:?}", state.priority_action);
+}
+
+fn duration_from(duration: Duration) -> String {
+    let seconds = duration.as_secs() % 60;
+    let minutes = (duration.as_secs() / 60) % 60;
+    let hours = (duration.as_secs() / 60) / 60;
+
+    format!("{hours:0>2}:{minutes:0>2}:{seconds:0>2}")
+}
@@ -1,6 +1,14 @@
+use std::time::{Duration, Instant};
+
+#[cfg(not(any(windows, target_os = "linux")))]
+use crate::Error;
+#[cfg(target_os = "linux")]
+use crate::linux::{X11Capture, query_capture_name_handle_pairs};
 #[cfg(windows)]
-use crate::windows::{BitBltCapture, WgcCapture, WindowBoxCapture, WindowsCapture};
-use crate::{Error, Result, Window, windows::query_capture_name_handle_pairs};
+use crate::windows::{
+    BitBltCapture, WgcCapture, WindowBoxCapture, WindowsCapture, query_capture_name_handle_pairs,
+};
+use crate::{Result, Window};
 
 #[derive(Debug, Clone)]
 pub struct Frame {
@@ -8,6 +16,15 @@ pub struct Frame {
     pub height: i32,
     pub data: Vec<u8>,
     // TODO: Color format? Currently always BGRA
+    /// The instant the frame is captured by the OS or the capture API.
+    ///
+    /// This can be earlier than grabbing for captures that buffer arrived frames.
+    pub captured_at: Instant,
+    /// The factor the frame pixels are scaled by from the window logical pixels.
+    ///
+    /// This is only not `1.0` for captures of the OS composed window (e.g. Windows Graphics
+    /// Capture) when the window is scaled by [`Window::scale_factor`].
+    pub scale: f32,
 }
 
 #[cfg(windows)]
@@ -21,44 +38,101 @@ pub enum WindowsCaptureKind {
 #[derive(Debug)]
 pub struct Capture {
     window: Window,
+    /// The number of frames grabbed by captures that only capture when grabbing.
+    grabbed_frames: u64,
 
     #[cfg(windows)]
     windows: WindowsCapture,
     #[cfg(windows)]
+    windows_min_frame_interval: Option<Duration>,
+    #[cfg(windows)]
     windows_kind: WindowsCaptureKind,
+
+    #[cfg(target_os = "linux")]
+    linux: X11Capture,
 }
 
 impl Capture {
     pub fn new(window: Window) -> Result<Self> {
-        if cfg!(windows) {
-            return Ok(Self {
-                window,
-                windows: WindowsCapture::BitBlt(BitBltCapture::new(window.windows, false)),
-                windows_kind: WindowsCaptureKind::BitBlt,
-            });
-        }
-
+        #[cfg(windows)]
+        return Ok(Self {
+            window,
+            grabbed_frames: 0,
+            windows: WindowsCapture::BitBlt(BitBltCapture::new(window.windows, false)),
+            windows_min_frame_interval: None,
+            windows_kind: WindowsCaptureKind::BitBlt,
+        });
+
+        #[cfg(target_os = "linux")]
+        return Ok(Self {
+            window,
+            grabbed_frames: 0,
+            linux: X11Capture::new(window.linux),
+        });
+
+        #[cfg(not(any(windows, target_os = "linux")))]
         Err(Error::PlatformNotSupported)
     }
 
     #[inline]
     pub fn grab(&mut self) -> Result<Frame> {
-        if cfg!(windows) {
-            return self.windows.grab();
+        #[cfg(windows)]
+        let frame = self.windows.grab();
+
+        #[cfg(target_os = "linux")]
+        let frame = self.linux.grab();
+
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            if frame.is_ok() {
+                self.grabbed_frames += 1;
+            }
+            return frame;
         }
 
+        #[cfg(not(any(windows, target_os = "linux")))]
         Err(Error::PlatformNotSupported)
     }
 
+    /// Sets the minimum interval between captured frames.
+    ///
+    /// This only applies to captures receiving frames on every monitor refresh (e.g. Windows
+    /// Graphics Capture) as others only capture when grabbing. `None` captures on every refresh.
+    pub fn set_min_frame_interval(&mut self, interval: Option<Duration>) {
+        #[cfg(windows)]
+        {
+            self.windows_min_frame_interval = interval;
+            if let WindowsCapture::Wgc(capture) = &mut self.windows {
+                capture.set_min_frame_interval(interval);
+            }
+        }
+        #[cfg(not(windows))]
+        let _ = interval;
+    }
+
+    /// The total number of frames captured.
     #[inline]
-    pub fn window(&self) -> Result<Window> {
-        if cfg!(windows) {
-            return match &self.windows {
-                WindowsCapture::Wgc(_) | WindowsCapture::BitBlt(_) => Ok(self.window),
-                WindowsCapture::BitBltArea(capture) => Ok(capture.handle().into()),
-            };
+    pub fn captured_frames(&self) -> u64 {
+        #[cfg(windows)]
+        if let WindowsCapture::Wgc(capture) = &self.windows {
+            return capture.captured_frames();
         }
 
+        self.grabbed_frames
+    }
+
+    #[inline]
+    pub fn window(&self) -> Result<Window> {
+        #[cfg(windows)]
+        return match &self.windows {
+            WindowsCapture::Wgc(_) | WindowsCapture::BitBlt(_) => Ok(self.window),
+            WindowsCapture::BitBltArea(capture) => Ok(capture.handle().into()),
+        };
+
+        #[cfg(target_os = "linux")]
+        return Ok(self.window);
+
+        #[cfg(not(any(windows, target_os = "linux")))]
         Err(Error::PlatformNotSupported)
     }
 
@@ -66,10 +140,17 @@ impl Capture {
     pub fn set_window(&mut self, window: Window) -> Result<()> {
         self.window = window;
 
-        if cfg!(windows) {
-            return self.windows_capture_kind(self.windows_kind);
+        #[cfg(windows)]
+        return self.windows_capture_kind(self.windows_kind);
+
+        #[cfg(target_os = "linux")]
+        {
+            self.linux = X11Capture::new(window.linux);
         }
+        #[cfg(target_os = "linux")]
+        return Ok(());
 
+        #[cfg(not(any(windows, target_os = "linux")))]
         Err(Error::PlatformNotSupported)
     }
 
@@ -82,7 +163,11 @@ impl Capture {
             WindowsCaptureKind::BitBltArea => {
                 WindowsCapture::BitBltArea(WindowBoxCapture::default())
             }
-            WindowsCaptureKind::Wgc => WindowsCapture::Wgc(WgcCapture::new(self.window.windows)?),
+            WindowsCaptureKind::Wgc => {
+                let mut capture = WgcCapture::new(self.window.windows)?;
+                capture.set_min_frame_interval(self.windows_min_frame_interval);
+                WindowsCapture::Wgc(capture)
+            }
         };
         self.windows_kind = kind;
 
@@ -91,12 +176,12 @@ impl Capture {
 }
 
 pub fn query_capture_name_window_pairs() -> Result<Vec<(String, Window)>> {
-    if cfg!(windows) {
-        return Ok(query_capture_name_handle_pairs()
-            .into_iter()
-            .map(|(name, handle)| (name, handle.into()))
-            .collect::<Vec<_>>());
-    }
+    #[cfg(any(windows, target_os = "linux"))]
+    return Ok(query_capture_name_handle_pairs()
+        .into_iter()
+        .map(|(name, handle)| (name, handle.into()))
+        .collect::<Vec<_>>());
 
+    #[cfg(not(any(windows, target_os = "linux")))]
     Err(Error::PlatformNotSupported)
 }
@@ -1,3 +1,7 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[cfg(target_os = "linux")]
+use crate::linux::{LinuxInput, LinuxInputReceiver};
 use crate::{Error, Result, Window};
 #[cfg(windows)]
 use crate::{windows::WindowsInput, windows::WindowsInputReceiver};
@@ -67,6 +71,18 @@ pub enum KeyKind {
     F10,
     F11,
     F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
 
     Up,
     Down,
@@ -92,6 +108,111 @@ pub enum KeyKind {
     Shift,
     Alt,
     Backspace,
+
+    NumpadZero,
+    NumpadOne,
+    NumpadTwo,
+    NumpadThree,
+    NumpadFour,
+    NumpadFive,
+    NumpadSix,
+    NumpadSeven,
+    NumpadEight,
+    NumpadNine,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+
+    /// The back side mouse button.
+    Mouse4,
+    /// The forward side mouse button.
+    Mouse5,
+}
+
+/// Keyboard layout used to translate [`KeyKind`] to the key pressed.
+///
+/// Only affects keys sent through [`Input`], keys received through [`InputReceiver`] are always
+/// translated with the active layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyboardLayout {
+    /// Translates through the keyboard layout active in the game window so that [`KeyKind::A`]
+    /// presses the key producing `A`.
+    #[default]
+    Active,
+    /// Presses the physical key at the position of [`KeyKind`] on a US QWERTY keyboard
+    /// regardless of the active layout.
+    Qwerty,
+}
+
+static KEYBOARD_LAYOUT: AtomicU8 = AtomicU8::new(KeyboardLayout::Active as u8);
+
+/// Sets the [`KeyboardLayout`] used by all [`Input`]s to translate keys.
+pub fn set_keyboard_layout(layout: KeyboardLayout) {
+    KEYBOARD_LAYOUT.store(layout as u8, Ordering::Relaxed);
+}
+
+#[inline]
+pub(crate) fn keyboard_layout() -> KeyboardLayout {
+    match KEYBOARD_LAYOUT.load(Ordering::Relaxed) {
+        1 => KeyboardLayout::Qwerty,
+        _ => KeyboardLayout::Active,
+    }
+}
+
+/// Gets the set 1 scan code of the physical key at the position of `kind` on a US QWERTY
+/// keyboard.
+///
+/// Returns `None` for keys whose position does not depend on the layout.
+pub(crate) fn qwerty_scan_code(kind: KeyKind) -> Option<u16> {
+    let scan_code = match kind {
+        KeyKind::A => 0x1e,
+        KeyKind::B => 0x30,
+        KeyKind::C => 0x2e,
+        KeyKind::D => 0x20,
+        KeyKind::E => 0x12,
+        KeyKind::F => 0x21,
+        KeyKind::G => 0x22,
+        KeyKind::H => 0x23,
+        KeyKind::I => 0x17,
+        KeyKind::J => 0x24,
+        KeyKind::K => 0x25,
+        KeyKind::L => 0x26,
+        KeyKind::M => 0x32,
+        KeyKind::N => 0x31,
+        KeyKind::O => 0x18,
+        KeyKind::P => 0x19,
+        KeyKind::Q => 0x10,
+        KeyKind::R => 0x13,
+        KeyKind::S => 0x1f,
+        KeyKind::T => 0x14,
+        KeyKind::U => 0x16,
+        KeyKind::V => 0x2f,
+        KeyKind::W => 0x11,
+        KeyKind::X => 0x2d,
+        KeyKind::Y => 0x15,
+        KeyKind::Z => 0x2c,
+        KeyKind::One => 0x02,
+        KeyKind::Two => 0x03,
+        KeyKind::Three => 0x04,
+        KeyKind::Four => 0x05,
+        KeyKind::Five => 0x06,
+        KeyKind::Six => 0x07,
+        KeyKind::Seven => 0x08,
+        KeyKind::Eight => 0x09,
+        KeyKind::Nine => 0x0a,
+        KeyKind::Zero => 0x0b,
+        KeyKind::Tilde => 0x29,
+        KeyKind::Quote => 0x28,
+        KeyKind::Semicolon => 0x27,
+        KeyKind::Comma => 0x33,
+        KeyKind::Period => 0x34,
+        KeyKind::Slash => 0x35,
+        _ => return None,
+    };
+
+    Some(scan_code)
 }
 
 /// Kind of input to send.
@@ -109,43 +230,59 @@ pub enum InputKind {
 pub struct Input {
     #[cfg(windows)]
     windows: WindowsInput,
+    #[cfg(target_os = "linux")]
+    linux: LinuxInput,
 }
 
 impl Input {
     pub fn new(window: Window, kind: InputKind) -> Result<Self> {
-        if cfg!(windows) {
-            return Ok(Self {
-                windows: WindowsInput::new(window.windows, kind),
-            });
-        }
+        #[cfg(windows)]
+        return Ok(Self {
+            windows: WindowsInput::new(window.windows, kind),
+        });
 
+        #[cfg(target_os = "linux")]
+        return Ok(Self {
+            linux: LinuxInput::new(window.linux, kind),
+        });
+
+        #[cfg(not(any(windows, target_os = "linux")))]
         Err(Error::PlatformNotSupported)
     }
 
     /// Sends mouse `kind` with coordinates `x`, `y` in relative to the provided [`Window`].
     pub fn send_mouse(&self, x: i32, y: i32, kind: MouseKind) -> Result<()> {
-        if cfg!(windows) {
-            return self.windows.send_mouse(x, y, kind);
-        }
+        #[cfg(windows)]
+        return self.windows.send_mouse(x, y, kind);
+
+        #[cfg(target_os = "linux")]
+        return self.linux.send_mouse(x, y, kind);
 
+        #[cfg(not(any(windows, target_os = "linux")))]
         Err(Error::PlatformNotSupported)
     }
 
     /// Retrieves the current state of key `kind`.
     pub fn key_state(&self, kind: KeyKind) -> Result<KeyState> {
-        if cfg!(windows) {
-            return self.windows.key_state(kind);
-        }
+        #[cfg(windows)]
+        return self.windows.key_state(kind);
+
+        #[cfg(target_os = "linux")]
+        return self.linux.key_state(kind);
 
+        #[cfg(not(any(windows, target_os = "linux")))]
         Err(Error::PlatformNotSupported)
     }
 
     /// Sends a single key press `kind`.
     pub fn send_key(&self, kind: KeyKind) -> Result<()> {
-        if cfg!(windows) {
-            return self.windows.send_key(kind);
-        }
+        #[cfg(windows)]
+        return self.windows.send_key(kind);
 
+        #[cfg(target_os = "linux")]
+        return self.linux.send_key(kind);
+
+        #[cfg(not(any(windows, target_os = "linux")))]
         Err(Error::PlatformNotSupported)
     }
 
@@ -154,19 +291,25 @@ impl Input {
     /// If `repeatable` is `true`, consecutive calls will continue to send the down stroke even if
     /// the key is already down.
     pub fn send_key_down(&self, kind: KeyKind, repeatable: bool) -> Result<()> {
-        if cfg!(windows) {
-            return self.windows.send_key_down(kind, repeatable);
-        }
+        #[cfg(windows)]
+        return self.windows.send_key_down(kind, repeatable);
+
+        #[cfg(target_os = "linux")]
+        return self.linux.send_key_down(kind, repeatable);
 
+        #[cfg(not(any(windows, target_os = "linux")))]
         Ok(())
     }
 
     /// Releases key `kind`.
     pub fn send_key_up(&self, kind: KeyKind) -> Result<()> {
-        if cfg!(windows) {
-            return self.windows.send_key_up(kind);
-        }
+        #[cfg(windows)]
+        return self.windows.send_key_up(kind);
+
+        #[cfg(target_os = "linux")]
+        return self.linux.send_key_up(kind);
 
+        #[cfg(not(any(windows, target_os = "linux")))]
         Err(Error::PlatformNotSupported)
     }
 }
@@ -175,25 +318,35 @@ impl Input {
 pub struct InputReceiver {
     #[cfg(windows)]
     windows: WindowsInputReceiver,
+    #[cfg(target_os = "linux")]
+    linux: LinuxInputReceiver,
 }
 
 impl InputReceiver {
     pub fn new(window: Window, input_kind: InputKind) -> Result<Self> {
-        if cfg!(windows) {
-            return Ok(Self {
-                windows: WindowsInputReceiver::new(window.windows, input_kind),
-            });
-        }
+        #[cfg(windows)]
+        return Ok(Self {
+            windows: WindowsInputReceiver::new(window.windows, input_kind),
+        });
 
+        #[cfg(target_os = "linux")]
+        return Ok(Self {
+            linux: LinuxInputReceiver::new(window.linux, input_kind),
+        });
+
+        #[cfg(not(any(windows, target_os = "linux")))]
         Err(Error::PlatformNotSupported)
     }
 
     /// Attempts to receive a key stroke previously sent from the OS.
     pub fn try_recv(&mut self) -> Result<KeyKind> {
-        if cfg!(windows) {
-            return self.windows.try_recv().ok_or(Error::KeyNotReceived);
-        }
+        #[cfg(windows)]
+        return self.windows.try_recv().ok_or(Error::KeyNotReceived);
+
+        #[cfg(target_os = "linux")]
+        return self.linux.try_recv().ok_or(Error::KeyNotReceived);
 
+        #[cfg(not(any(windows, target_os = "linux")))]
         Err(Error::PlatformNotSupported)
     }
 }
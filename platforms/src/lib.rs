@@ -2,12 +2,18 @@
 
 use thiserror::Error;
 
+#[cfg(target_os = "linux")]
+use crate::linux::{Handle, HandleKind};
 #[cfg(windows)]
 use crate::windows::{Handle, HandleKind};
 
 pub mod capture;
 pub mod input;
+pub mod power;
+pub mod sound;
 
+#[cfg(target_os = "linux")]
+mod linux;
 #[cfg(windows)]
 mod windows;
 
@@ -36,6 +42,10 @@ pub enum Error {
     #[cfg(windows)]
     #[error("win32 API error {0}: {1}")]
     Win32(u32, String),
+
+    #[cfg(target_os = "linux")]
+    #[error("X11 error: {0}")]
+    X11(String),
 }
 
 /// Relativeness of a point to be converted to.
@@ -48,6 +58,9 @@ pub enum CoordinateRelative {
 }
 
 /// Represents converted coordinates as specified by [`CoordinateRelative`].
+///
+/// The point to convert is in the window logical pixels, which are the pixels of captured frames.
+/// All converted values are in physical pixels.
 #[derive(Debug)]
 pub struct ConvertedCoordinates {
     /// The width of the monitor or client area.
@@ -58,6 +71,8 @@ pub struct ConvertedCoordinates {
     pub x: i32,
     /// y coordinate in relative to the monitor or client area.
     pub y: i32,
+    /// The factor the window logical pixels are scaled by to physical pixels.
+    pub scale: f32,
 }
 
 /// A platform-specific handle to a window on screen.
@@ -65,6 +80,8 @@ pub struct ConvertedCoordinates {
 pub struct Window {
     #[cfg(windows)]
     windows: Handle,
+    #[cfg(target_os = "linux")]
+    linux: Handle,
 }
 
 impl Window {
@@ -75,6 +92,14 @@ impl Window {
         }
     }
 
+    /// Creates a [`Window`] matching `class` against the X11 window class or title.
+    #[cfg(target_os = "linux")]
+    pub fn new(class: &'static str) -> Self {
+        Self {
+            linux: Handle::new(HandleKind::Dynamic(class)),
+        }
+    }
+
     #[inline]
     pub fn convert_coordinate(
         &self,
@@ -82,14 +107,37 @@ impl Window {
         y: i32,
         relative: CoordinateRelative,
     ) -> Result<ConvertedCoordinates> {
-        if cfg!(windows) {
-            return self.windows.convert_coordinate(
-                x,
-                y,
-                matches!(relative, CoordinateRelative::Monitor),
-            );
-        }
+        #[cfg(windows)]
+        return self.windows.convert_coordinate(
+            x,
+            y,
+            matches!(relative, CoordinateRelative::Monitor),
+        );
+
+        #[cfg(target_os = "linux")]
+        return self.linux.convert_coordinate(
+            x,
+            y,
+            matches!(relative, CoordinateRelative::Monitor),
+        );
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        Err(Error::PlatformNotSupported)
+    }
+
+    /// Retrieves the factor the window logical pixels are scaled by to physical pixels.
+    ///
+    /// This is `1.0` unless the OS scales the window for display scaling (e.g. a DPI-unaware game
+    /// on a monitor scaled to 125% or 150%).
+    #[inline]
+    pub fn scale_factor(&self) -> Result<f32> {
+        #[cfg(windows)]
+        return self.windows.scale_factor();
 
+        #[cfg(target_os = "linux")]
+        return self.linux.scale_factor();
+
+        #[cfg(not(any(windows, target_os = "linux")))]
         Err(Error::PlatformNotSupported)
     }
 }
@@ -101,8 +149,20 @@ impl From<Handle> for Window {
     }
 }
 
-pub fn init() {
-    if cfg!(windows) {
-        windows::init();
+#[cfg(target_os = "linux")]
+impl From<Handle> for Window {
+    fn from(value: Handle) -> Self {
+        Self { linux: value }
     }
 }
+
+/// Scales `value` in logical pixels by `scale` to physical pixels.
+#[inline]
+pub(crate) fn to_physical(value: i32, scale: f32) -> i32 {
+    (value as f32 * scale).round() as i32
+}
+
+pub fn init() {
+    #[cfg(windows)]
+    windows::init();
+}
@@ -0,0 +1,180 @@
+use std::{ptr, slice, time::Instant};
+
+use x11rb::{
+    connection::{Connection, RequestConnection as _},
+    protocol::{
+        shm::{self, ConnectionExt as _, Seg},
+        xproto::{ConnectionExt as _, ImageFormat, Window as XWindow},
+    },
+};
+
+use super::{Handle, HandleCell, display};
+use crate::{Error, Result, capture::Frame};
+
+/// Captures a window through the `MIT-SHM` extension, falling back to the core `GetImage`
+/// request when the extension is not available.
+///
+/// The window must be mapped and not obscured by other windows unless a compositor is running.
+#[derive(Debug)]
+pub struct X11Capture {
+    handle: HandleCell,
+    /// The shared memory segment reused across grabs while the window size is unchanged.
+    shm: Option<ShmSegment>,
+    /// Whether `MIT-SHM` failed and [`Self::grab`] should only use `GetImage`.
+    shm_unsupported: bool,
+}
+
+impl X11Capture {
+    pub fn new(handle: Handle) -> Self {
+        Self {
+            handle: HandleCell::new(handle),
+            shm: None,
+            shm_unsupported: false,
+        }
+    }
+
+    pub fn grab(&mut self) -> Result<Frame> {
+        let window = self.handle.as_inner().ok_or(Error::WindowNotFound)?;
+        let display = display()?;
+        let geometry = display.connection.get_geometry(window)?.reply()?;
+        if geometry.width == 0 || geometry.height == 0 {
+            return Err(Error::WindowInvalidSize);
+        }
+
+        let width = geometry.width as i32;
+        let height = geometry.height as i32;
+        let size = width as usize * height as usize * 4;
+        if !self.shm_unsupported && self.shm.as_ref().is_none_or(|shm| shm.size != size) {
+            self.shm = None;
+            match ShmSegment::new(size) {
+                Ok(shm) => self.shm = Some(shm),
+                // Remote connections and some XWayland setups do not support shared memory
+                Err(_) => self.shm_unsupported = true,
+            }
+        }
+        let (mut data, depth) = match self.shm.as_ref() {
+            Some(shm) => grab_shm(shm, window, geometry.width, geometry.height)?,
+            None => grab_image(window, geometry.width, geometry.height)?,
+        };
+        let captured_at = Instant::now();
+        // Only 24/32-bit depth with 32 bits per pixel is supported, which is in BGRX order
+        if data.len() != (width * height * 4) as usize {
+            return Err(Error::X11(format!("unsupported image depth {depth}")));
+        }
+        data.chunks_exact_mut(4).for_each(|pixel| pixel[3] = 255);
+
+        Ok(Frame {
+            width,
+            height,
+            data,
+            captured_at,
+            scale: 1.0,
+        })
+    }
+}
+
+/// Grabs `window` into `shm` through the `MIT-SHM` `GetImage` request.
+fn grab_shm(shm: &ShmSegment, window: XWindow, width: u16, height: u16) -> Result<(Vec<u8>, u8)> {
+    let reply = display()?
+        .connection
+        .shm_get_image(
+            window,
+            0,
+            0,
+            width,
+            height,
+            !0,
+            ImageFormat::Z_PIXMAP.into(),
+            shm.seg,
+            0,
+        )?
+        .reply()?;
+    let len = (reply.size as usize).min(shm.size);
+
+    Ok((shm.as_slice()[..len].to_vec(), reply.depth))
+}
+
+/// Grabs `window` through the core `GetImage` request.
+fn grab_image(window: XWindow, width: u16, height: u16) -> Result<(Vec<u8>, u8)> {
+    let image = display()?
+        .connection
+        .get_image(ImageFormat::Z_PIXMAP, window, 0, 0, width, height, !0)?
+        .reply()?;
+
+    Ok((image.data, image.depth))
+}
+
+/// A System V shared memory segment attached to both this process and the X server.
+#[derive(Debug)]
+struct ShmSegment {
+    seg: Seg,
+    addr: *mut u8,
+    size: usize,
+}
+
+// SAFETY: The segment is only accessed through `&ShmSegment` and the X server only writes to it
+// while a `ShmGetImage` request is being waited on
+unsafe impl Send for ShmSegment {}
+
+impl ShmSegment {
+    fn new(size: usize) -> Result<Self> {
+        let display = display()?;
+        if display
+            .connection
+            .extension_information(shm::X11_EXTENSION_NAME)?
+            .is_none()
+        {
+            return Err(Error::X11("MIT-SHM extension is not available".to_string()));
+        }
+
+        // SAFETY: Creating and attaching a private segment has no preconditions
+        let id = unsafe { libc::shmget(libc::IPC_PRIVATE, size, libc::IPC_CREAT | 0o600) };
+        if id == -1 {
+            return Err(Error::X11(
+                "unable to create shared memory segment".to_string(),
+            ));
+        }
+        let addr = unsafe { libc::shmat(id, ptr::null(), 0) };
+        if addr as isize == -1 {
+            unsafe { libc::shmctl(id, libc::IPC_RMID, ptr::null_mut()) };
+            return Err(Error::X11(
+                "unable to attach shared memory segment".to_string(),
+            ));
+        }
+
+        let seg = display.connection.generate_id()?;
+        let attached = display
+            .connection
+            .shm_attach(seg, id as u32, false)
+            .map_err(Error::from)
+            .and_then(|cookie| cookie.check().map_err(Error::from));
+        // The segment is destroyed once both this process and the X server detach from it
+        unsafe { libc::shmctl(id, libc::IPC_RMID, ptr::null_mut()) };
+        if let Err(error) = attached {
+            unsafe { libc::shmdt(addr) };
+            return Err(error);
+        }
+
+        Ok(Self {
+            seg,
+            addr: addr.cast(),
+            size,
+        })
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        // SAFETY: `addr` points to an attached segment of `size` bytes until dropped
+        unsafe { slice::from_raw_parts(self.addr, self.size) }
+    }
+}
+
+impl Drop for ShmSegment {
+    fn drop(&mut self) {
+        if let Ok(display) = display() {
+            let _ = display.connection.shm_detach(self.seg);
+            let _ = display.connection.flush();
+        }
+        // SAFETY: `addr` was returned by `shmat` and is not used after this
+        unsafe { libc::shmdt(self.addr.cast()) };
+    }
+}
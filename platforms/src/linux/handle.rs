@@ -0,0 +1,244 @@
+use std::cell::Cell;
+
+use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _, Window as XWindow};
+
+use super::{Display, display};
+use crate::{ConvertedCoordinates, Error, Result};
+
+#[derive(Clone, Debug)]
+pub struct HandleCell {
+    inner: Handle,
+    inner_cell: Cell<Option<XWindow>>,
+}
+
+impl HandleCell {
+    pub fn new(handle: Handle) -> Self {
+        Self {
+            inner: handle,
+            inner_cell: Cell::new(None),
+        }
+    }
+
+    #[inline]
+    pub fn as_inner(&self) -> Option<XWindow> {
+        match self.inner.kind {
+            HandleKind::Fixed(window) => Some(window),
+            HandleKind::Dynamic(class) => {
+                if self.inner_cell.get().is_none() {
+                    self.inner_cell.set(query_handle(class));
+                }
+
+                let window = self.inner_cell.get()?;
+                if display().is_ok_and(|display| is_class_matched(display, window, class)) {
+                    Some(window)
+                } else {
+                    self.inner_cell.set(None);
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandleKind {
+    Fixed(XWindow),
+    Dynamic(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handle {
+    kind: HandleKind,
+}
+
+impl Handle {
+    pub fn new(kind: HandleKind) -> Self {
+        Self { kind }
+    }
+
+    pub fn as_inner(&self) -> Option<XWindow> {
+        match self.kind {
+            HandleKind::Fixed(window) => Some(window),
+            HandleKind::Dynamic(class) => query_handle(class),
+        }
+    }
+
+    pub fn convert_coordinate(
+        &self,
+        x: i32,
+        y: i32,
+        monitor_coordinate: bool,
+    ) -> Result<ConvertedCoordinates> {
+        let window = self.as_inner().ok_or(Error::WindowNotFound)?;
+
+        if !monitor_coordinate {
+            let (_, _, width, height) = window_rect(window)?;
+
+            return Ok(ConvertedCoordinates {
+                width,
+                height,
+                x,
+                y,
+                scale: 1.0,
+            });
+        }
+
+        // The root window spans all monitors, which is the best approximation available without
+        // querying RandR
+        let display = display()?;
+        let (left, top, _, _) = window_rect(window)?;
+        let root = display.connection.get_geometry(display.root)?.reply()?;
+
+        Ok(ConvertedCoordinates {
+            width: root.width as i32,
+            height: root.height as i32,
+            x: left + x,
+            y: top + y,
+            scale: 1.0,
+        })
+    }
+
+    /// Retrieves the factor the window logical pixels are scaled by to physical pixels.
+    ///
+    /// X11 does not scale windows, so this is always `1.0`.
+    pub fn scale_factor(&self) -> Result<f32> {
+        self.as_inner().ok_or(Error::WindowNotFound)?;
+        Ok(1.0)
+    }
+}
+
+pub fn query_capture_name_handle_pairs() -> Vec<(String, Handle)> {
+    let Ok(display) = display() else {
+        return vec![];
+    };
+
+    client_windows(display)
+        .into_iter()
+        .filter_map(|window| {
+            let name = window_name(display, window)?;
+            Some((name, Handle::new(HandleKind::Fixed(window))))
+        })
+        .collect()
+}
+
+/// Retrieves the currently active top-level window as reported by the window manager.
+pub(crate) fn active_window(display: &Display) -> Option<XWindow> {
+    display
+        .connection
+        .get_property(
+            false,
+            display.root,
+            display.net_active_window,
+            AtomEnum::WINDOW,
+            0,
+            1,
+        )
+        .ok()?
+        .reply()
+        .ok()?
+        .value32()?
+        .next()
+        .filter(|window| *window != 0)
+}
+
+/// Retrieves the process id owning `window` if the client sets `_NET_WM_PID`.
+pub(crate) fn window_pid(display: &Display, window: XWindow) -> Option<u32> {
+    display
+        .connection
+        .get_property(false, window, display.net_wm_pid, AtomEnum::CARDINAL, 0, 1)
+        .ok()?
+        .reply()
+        .ok()?
+        .value32()?
+        .next()
+}
+
+/// Retrieves the `(x, y, width, height)` of `window` with `x`, `y` relative to the root window.
+pub(crate) fn window_rect(window: XWindow) -> Result<(i32, i32, i32, i32)> {
+    let display = display()?;
+    let geometry = display.connection.get_geometry(window)?.reply()?;
+    let translated = display
+        .connection
+        .translate_coordinates(window, display.root, 0, 0)?
+        .reply()?;
+
+    Ok((
+        translated.dst_x as i32,
+        translated.dst_y as i32,
+        geometry.width as i32,
+        geometry.height as i32,
+    ))
+}
+
+#[inline]
+fn client_windows(display: &Display) -> Vec<XWindow> {
+    display
+        .connection
+        .get_property(
+            false,
+            display.root,
+            display.net_client_list,
+            AtomEnum::WINDOW,
+            0,
+            u32::MAX,
+        )
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+        .and_then(|reply| reply.value32().map(|windows| windows.collect()))
+        .unwrap_or_default()
+}
+
+#[inline]
+fn window_name(display: &Display, window: XWindow) -> Option<String> {
+    let property = |atom: u32| {
+        let reply = display
+            .connection
+            .get_property(false, window, atom, AtomEnum::ANY, 0, 1024)
+            .ok()?
+            .reply()
+            .ok()?;
+        let name = String::from_utf8_lossy(&reply.value).into_owned();
+        (!name.is_empty()).then_some(name)
+    };
+
+    property(display.net_wm_name).or_else(|| property(AtomEnum::WM_NAME.into()))
+}
+
+#[inline]
+fn window_class(display: &Display, window: XWindow) -> Option<String> {
+    let reply = display
+        .connection
+        .get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 1024)
+        .ok()?
+        .reply()
+        .ok()?;
+
+    Some(String::from_utf8_lossy(&reply.value).into_owned())
+}
+
+#[inline]
+fn query_handle(class: &'static str) -> Option<XWindow> {
+    let display = display().ok()?;
+
+    client_windows(display)
+        .into_iter()
+        .find(|window| is_class_matched(display, *window, class))
+}
+
+#[inline]
+fn is_class_matched(display: &Display, window: XWindow, class: &'static str) -> bool {
+    // Wine does not expose the Win32 class name, so the window title is matched against the
+    // class name without the `Class` suffix as a fallback (e.g. `MapleStoryClass` matches
+    // `MapleStory`)
+    let class = class.to_lowercase();
+    let title = class.split("class").next().unwrap_or(&class);
+    let class_matched = window_class(display, window).is_some_and(|wm_class| {
+        wm_class
+            .split('\0')
+            .any(|name| name.to_lowercase().starts_with(&class))
+    });
+
+    class_matched
+        || window_name(display, window)
+            .is_some_and(|name| !title.is_empty() && name.to_lowercase().starts_with(title))
+}
@@ -0,0 +1,713 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    sync::{LazyLock, Mutex, OnceLock},
+    thread,
+    time::Duration,
+};
+
+use bit_vec::BitVec;
+use x11rb::{
+    CURRENT_TIME,
+    connection::Connection,
+    protocol::{
+        xproto::{
+            BUTTON_PRESS_EVENT, BUTTON_RELEASE_EVENT, ConnectionExt as _, KEY_PRESS_EVENT,
+            KEY_RELEASE_EVENT, MOTION_NOTIFY_EVENT, Window as XWindow,
+        },
+        xtest::ConnectionExt as _,
+    },
+};
+
+use super::{Display, Handle, HandleCell, active_window, display, window_pid, window_rect};
+use crate::{
+    Error, Result,
+    input::{
+        InputKind, KeyKind, KeyState, KeyboardLayout, MouseKind, keyboard_layout, qwerty_scan_code,
+    },
+};
+
+/// Key codes sent by this process that have not been released and observed by any
+/// [`LinuxInputReceiver`] yet.
+///
+/// X11 does not tag synthesized key events, so this is used to ignore keys sent by the bot
+/// similar to how the Windows hook ignores injected keys.
+static INJECTED_KEYCODES: LazyLock<Mutex<BitVec>> =
+    LazyLock::new(|| Mutex::new(BitVec::from_elem(256, false)));
+
+/// Offset from evdev key codes to X11 keycodes.
+///
+/// Evdev key codes of the main keyboard block are equal to set 1 scan codes.
+const EVDEV_KEYCODE_OFFSET: u8 = 8;
+
+/// Number of X11 keycodes.
+const KEYCODES_COUNT: usize = 256;
+
+/// A X11 keysym.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Keysym(u32);
+
+const XK_A: Keysym = Keysym(0x0061);
+const XK_B: Keysym = Keysym(0x0062);
+const XK_C: Keysym = Keysym(0x0063);
+const XK_D: Keysym = Keysym(0x0064);
+const XK_E: Keysym = Keysym(0x0065);
+const XK_F: Keysym = Keysym(0x0066);
+const XK_G: Keysym = Keysym(0x0067);
+const XK_H: Keysym = Keysym(0x0068);
+const XK_I: Keysym = Keysym(0x0069);
+const XK_J: Keysym = Keysym(0x006a);
+const XK_K: Keysym = Keysym(0x006b);
+const XK_L: Keysym = Keysym(0x006c);
+const XK_M: Keysym = Keysym(0x006d);
+const XK_N: Keysym = Keysym(0x006e);
+const XK_O: Keysym = Keysym(0x006f);
+const XK_P: Keysym = Keysym(0x0070);
+const XK_Q: Keysym = Keysym(0x0071);
+const XK_R: Keysym = Keysym(0x0072);
+const XK_S: Keysym = Keysym(0x0073);
+const XK_T: Keysym = Keysym(0x0074);
+const XK_U: Keysym = Keysym(0x0075);
+const XK_V: Keysym = Keysym(0x0076);
+const XK_W: Keysym = Keysym(0x0077);
+const XK_X: Keysym = Keysym(0x0078);
+const XK_Y: Keysym = Keysym(0x0079);
+const XK_Z: Keysym = Keysym(0x007a);
+const XK_0: Keysym = Keysym(0x0030);
+const XK_1: Keysym = Keysym(0x0031);
+const XK_2: Keysym = Keysym(0x0032);
+const XK_3: Keysym = Keysym(0x0033);
+const XK_4: Keysym = Keysym(0x0034);
+const XK_5: Keysym = Keysym(0x0035);
+const XK_6: Keysym = Keysym(0x0036);
+const XK_7: Keysym = Keysym(0x0037);
+const XK_8: Keysym = Keysym(0x0038);
+const XK_9: Keysym = Keysym(0x0039);
+const XK_F1: Keysym = Keysym(0xffbe);
+const XK_F2: Keysym = Keysym(0xffbf);
+const XK_F3: Keysym = Keysym(0xffc0);
+const XK_F4: Keysym = Keysym(0xffc1);
+const XK_F5: Keysym = Keysym(0xffc2);
+const XK_F6: Keysym = Keysym(0xffc3);
+const XK_F7: Keysym = Keysym(0xffc4);
+const XK_F8: Keysym = Keysym(0xffc5);
+const XK_F9: Keysym = Keysym(0xffc6);
+const XK_F10: Keysym = Keysym(0xffc7);
+const XK_F11: Keysym = Keysym(0xffc8);
+const XK_F12: Keysym = Keysym(0xffc9);
+const XK_F13: Keysym = Keysym(0xffca);
+const XK_F14: Keysym = Keysym(0xffcb);
+const XK_F15: Keysym = Keysym(0xffcc);
+const XK_F16: Keysym = Keysym(0xffcd);
+const XK_F17: Keysym = Keysym(0xffce);
+const XK_F18: Keysym = Keysym(0xffcf);
+const XK_F19: Keysym = Keysym(0xffd0);
+const XK_F20: Keysym = Keysym(0xffd1);
+const XK_F21: Keysym = Keysym(0xffd2);
+const XK_F22: Keysym = Keysym(0xffd3);
+const XK_F23: Keysym = Keysym(0xffd4);
+const XK_F24: Keysym = Keysym(0xffd5);
+const XK_UP: Keysym = Keysym(0xff52);
+const XK_DOWN: Keysym = Keysym(0xff54);
+const XK_LEFT: Keysym = Keysym(0xff51);
+const XK_RIGHT: Keysym = Keysym(0xff53);
+const XK_HOME: Keysym = Keysym(0xff50);
+const XK_END: Keysym = Keysym(0xff57);
+const XK_PRIOR: Keysym = Keysym(0xff55);
+const XK_NEXT: Keysym = Keysym(0xff56);
+const XK_INSERT: Keysym = Keysym(0xff63);
+const XK_DELETE: Keysym = Keysym(0xffff);
+const XK_CONTROL_L: Keysym = Keysym(0xffe3);
+const XK_RETURN: Keysym = Keysym(0xff0d);
+const XK_SPACE: Keysym = Keysym(0x0020);
+const XK_GRAVE: Keysym = Keysym(0x0060);
+const XK_APOSTROPHE: Keysym = Keysym(0x0027);
+const XK_SEMICOLON: Keysym = Keysym(0x003b);
+const XK_COMMA: Keysym = Keysym(0x002c);
+const XK_PERIOD: Keysym = Keysym(0x002e);
+const XK_SLASH: Keysym = Keysym(0x002f);
+const XK_ESCAPE: Keysym = Keysym(0xff1b);
+const XK_SHIFT_L: Keysym = Keysym(0xffe1);
+const XK_ALT_L: Keysym = Keysym(0xffe9);
+const XK_BACKSPACE: Keysym = Keysym(0xff08);
+const XK_KP_0: Keysym = Keysym(0xffb0);
+const XK_KP_1: Keysym = Keysym(0xffb1);
+const XK_KP_2: Keysym = Keysym(0xffb2);
+const XK_KP_3: Keysym = Keysym(0xffb3);
+const XK_KP_4: Keysym = Keysym(0xffb4);
+const XK_KP_5: Keysym = Keysym(0xffb5);
+const XK_KP_6: Keysym = Keysym(0xffb6);
+const XK_KP_7: Keysym = Keysym(0xffb7);
+const XK_KP_8: Keysym = Keysym(0xffb8);
+const XK_KP_9: Keysym = Keysym(0xffb9);
+const XK_KP_ADD: Keysym = Keysym(0xffab);
+const XK_KP_SUBTRACT: Keysym = Keysym(0xffad);
+const XK_KP_MULTIPLY: Keysym = Keysym(0xffaa);
+const XK_KP_DIVIDE: Keysym = Keysym(0xffaf);
+const XK_KP_DECIMAL: Keysym = Keysym(0xffae);
+const XK_VOID_SYMBOL: Keysym = Keysym(0xffffff);
+
+/// Mapping between key codes and their first keysym of the current keyboard layout.
+#[derive(Debug)]
+struct Keymap {
+    keycodes: HashMap<Keysym, u8>,
+    keysyms: HashMap<u8, Keysym>,
+}
+
+impl Keymap {
+    fn get() -> Result<&'static Keymap> {
+        static KEYMAP: OnceLock<Keymap> = OnceLock::new();
+
+        if let Some(keymap) = KEYMAP.get() {
+            return Ok(keymap);
+        }
+
+        let display = display()?;
+        let setup = display.connection.setup();
+        let count = setup.max_keycode - setup.min_keycode + 1;
+        let reply = display
+            .connection
+            .get_keyboard_mapping(setup.min_keycode, count)?
+            .reply()?;
+        let per_keycode = (reply.keysyms_per_keycode as usize).max(1);
+        let mut keycodes = HashMap::new();
+        let mut keysyms = HashMap::new();
+        for (i, syms) in reply.keysyms.chunks(per_keycode).enumerate() {
+            let keycode = setup.min_keycode + i as u8;
+            let Some(keysym) = syms.first().copied().filter(|keysym| *keysym != 0) else {
+                continue;
+            };
+            // Letters are reported as uppercase keysyms in some layouts
+            let keysym = if (0x41..=0x5a).contains(&keysym) {
+                Keysym(keysym + 0x20)
+            } else {
+                Keysym(keysym)
+            };
+            keycodes.entry(keysym).or_insert(keycode);
+            keysyms.insert(keycode, keysym);
+        }
+        // Keypad digits and decimal are on the second level when Num Lock is off (e.g. `KP_End`
+        // and `KP_1`)
+        for (i, syms) in reply.keysyms.chunks(per_keycode).enumerate() {
+            let keycode = setup.min_keycode + i as u8;
+            for keysym in syms.iter().skip(1).copied().map(Keysym) {
+                if (XK_KP_0.0..=XK_KP_9.0).contains(&keysym.0) || keysym == XK_KP_DECIMAL {
+                    keycodes.entry(keysym).or_insert(keycode);
+                }
+            }
+        }
+
+        Ok(KEYMAP.get_or_init(|| Keymap { keycodes, keysyms }))
+    }
+
+    #[inline]
+    fn keycode(&self, kind: KeyKind) -> Result<u8> {
+        self.keycodes
+            .get(&Keysym::from(kind))
+            .copied()
+            .ok_or(Error::KeyNotFound)
+    }
+}
+
+#[derive(Debug)]
+pub struct LinuxInputReceiver {
+    handle: HandleCell,
+    input_kind: InputKind,
+    keys: [u8; 32],
+    pending_keys: VecDeque<KeyKind>,
+}
+
+impl LinuxInputReceiver {
+    pub fn new(handle: Handle, input_kind: InputKind) -> Self {
+        Self {
+            handle: HandleCell::new(handle),
+            input_kind,
+            keys: [0; 32],
+            pending_keys: VecDeque::new(),
+        }
+    }
+
+    /// Polls the keyboard state and returns keys released since the last call.
+    ///
+    /// Unlike the Windows hook, key strokes shorter than the polling interval can be missed.
+    pub fn try_recv(&mut self) -> Option<KeyKind> {
+        if let Some(key) = self.pending_keys.pop_front() {
+            return Some(key);
+        }
+
+        let display = display().ok()?;
+        let keymap = Keymap::get().ok()?;
+        let keys = display.connection.query_keymap().ok()?.reply().ok()?.keys;
+        let mut injected = INJECTED_KEYCODES.lock().unwrap();
+        for keycode in 0..=u8::MAX {
+            if !is_key_down(&self.keys, keycode) || is_key_down(&keys, keycode) {
+                continue;
+            }
+            if injected[keycode as usize] {
+                injected.set(keycode as usize, false);
+                continue;
+            }
+            if let Some(key) = keymap
+                .keysyms
+                .get(&keycode)
+                .and_then(|keysym| KeyKind::try_from(*keysym).ok())
+            {
+                self.pending_keys.push_back(key);
+            }
+        }
+        self.keys = keys;
+
+        if !self.can_process_key(display) {
+            self.pending_keys.clear();
+            return None;
+        }
+        self.pending_keys.pop_front()
+    }
+
+    fn can_process_key(&self, display: &Display) -> bool {
+        if active_window(display)
+            .and_then(|window| window_pid(display, window))
+            .is_some_and(|pid| pid == std::process::id())
+        {
+            return true;
+        }
+
+        self.handle
+            .as_inner()
+            .map(|window| is_foreground(display, window, self.input_kind))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+enum InputKeyStroke {
+    Up,
+    Down,
+    DownRepeatable,
+}
+
+/// Sends inputs through the XTest extension.
+#[derive(Debug)]
+pub struct LinuxInput {
+    handle: HandleCell,
+    input_kind: InputKind,
+    /// Down states of keycodes followed by side mouse buttons.
+    key_down: RefCell<BitVec>,
+}
+
+impl LinuxInput {
+    pub fn new(handle: Handle, kind: InputKind) -> Self {
+        Self {
+            handle: HandleCell::new(handle),
+            input_kind: kind,
+            key_down: RefCell::new(BitVec::from_elem(KEYCODES_COUNT + 2, false)),
+        }
+    }
+
+    pub fn send_mouse(&self, x: i32, y: i32, kind: MouseKind) -> Result<()> {
+        let display = display()?;
+        let mut window = self.get_handle()?;
+        if !is_foreground(display, window, self.input_kind) {
+            return Err(Error::WindowNotFound);
+        }
+        if matches!(self.input_kind, InputKind::Foreground) {
+            window = active_window(display).ok_or(Error::WindowNotFound)?;
+        }
+
+        let (left, top, _, _) = window_rect(window)?;
+        let (x, y) = ((left + x) as i16, (top + y) as i16);
+        let fake_input = |kind: u8, detail: u8| -> Result<()> {
+            display.connection.xtest_fake_input(
+                kind,
+                detail,
+                CURRENT_TIME,
+                display.root,
+                x,
+                y,
+                0,
+            )?;
+            display.connection.flush()?;
+            Ok(())
+        };
+
+        fake_input(MOTION_NOTIFY_EVENT, 0)?;
+        match kind {
+            MouseKind::Move => Ok(()),
+            MouseKind::Click => {
+                fake_input(BUTTON_PRESS_EVENT, 1)?;
+                // TODO: Hack or double-click won't work...
+                thread::sleep(Duration::from_millis(80));
+                fake_input(BUTTON_RELEASE_EVENT, 1)
+            }
+            MouseKind::Scroll => {
+                // Button 5 is scrolling down
+                fake_input(BUTTON_PRESS_EVENT, 5)?;
+                fake_input(BUTTON_RELEASE_EVENT, 5)
+            }
+        }
+    }
+
+    pub fn key_state(&self, kind: KeyKind) -> Result<KeyState> {
+        // The core protocol does not report side mouse button states so the sent state is used
+        let is_down = if let Some(button) = side_button(kind) {
+            self.key_down.borrow()[side_button_index(button)]
+        } else {
+            let keycode = layout_keycode(kind)?;
+            let keys = display()?.connection.query_keymap()?.reply()?.keys;
+            is_key_down(&keys, keycode)
+        };
+        let state = if is_down {
+            KeyState::Pressed
+        } else {
+            KeyState::Released
+        };
+
+        Ok(state)
+    }
+
+    pub fn send_key(&self, kind: KeyKind) -> Result<()> {
+        self.send_key_down(kind, false)?;
+        self.send_key_up(kind)?;
+        Ok(())
+    }
+
+    pub fn send_key_up(&self, kind: KeyKind) -> Result<()> {
+        self.send_input(kind, InputKeyStroke::Up)
+    }
+
+    pub fn send_key_down(&self, kind: KeyKind, repeatable: bool) -> Result<()> {
+        let stroke = if repeatable {
+            InputKeyStroke::DownRepeatable
+        } else {
+            InputKeyStroke::Down
+        };
+
+        self.send_input(kind, stroke)
+    }
+
+    #[inline]
+    fn send_input(&self, kind: KeyKind, stroke: InputKeyStroke) -> Result<()> {
+        let display = display()?;
+        let window = self.get_handle()?;
+        let is_down = matches!(
+            stroke,
+            InputKeyStroke::Down | InputKeyStroke::DownRepeatable
+        );
+        if is_down && !is_foreground(display, window, self.input_kind) {
+            return Err(Error::KeyNotSent);
+        }
+
+        let button = side_button(kind);
+        let (index, detail) = match button {
+            Some(button) => (side_button_index(button), button),
+            None => {
+                let keycode = layout_keycode(kind)?;
+                (keycode as usize, keycode)
+            }
+        };
+        let mut key_down = self.key_down.borrow_mut();
+        let was_key_down = key_down[index];
+        match (is_down, was_key_down) {
+            (true, true) => {
+                if !matches!(stroke, InputKeyStroke::DownRepeatable) {
+                    return Err(Error::KeyNotSent);
+                }
+            }
+            (false, false) => return Err(Error::KeyNotSent),
+            _ => {
+                key_down.set(index, is_down);
+            }
+        }
+        if is_down && button.is_none() {
+            INJECTED_KEYCODES.lock().unwrap().set(index, true);
+        }
+
+        let event = match (is_down, button.is_some()) {
+            (true, false) => KEY_PRESS_EVENT,
+            (false, false) => KEY_RELEASE_EVENT,
+            (true, true) => BUTTON_PRESS_EVENT,
+            (false, true) => BUTTON_RELEASE_EVENT,
+        };
+        display
+            .connection
+            .xtest_fake_input(event, detail, CURRENT_TIME, display.root, 0, 0, 0)?;
+        display.connection.flush()?;
+        Ok(())
+    }
+
+    #[inline]
+    fn get_handle(&self) -> Result<XWindow> {
+        self.handle.as_inner().ok_or(Error::WindowNotFound)
+    }
+}
+
+/// Gets the X11 pointer button of side mouse button `kind`.
+#[inline]
+fn side_button(kind: KeyKind) -> Option<u8> {
+    match kind {
+        KeyKind::Mouse4 => Some(8),
+        KeyKind::Mouse5 => Some(9),
+        _ => None,
+    }
+}
+
+#[inline]
+fn side_button_index(button: u8) -> usize {
+    KEYCODES_COUNT + (button - 8) as usize
+}
+
+/// Gets the keycode to send for `kind` under the current [`KeyboardLayout`].
+#[inline]
+fn layout_keycode(kind: KeyKind) -> Result<u8> {
+    if matches!(keyboard_layout(), KeyboardLayout::Qwerty)
+        && let Some(scan_code) = qwerty_scan_code(kind)
+    {
+        return Ok(scan_code as u8 + EVDEV_KEYCODE_OFFSET);
+    }
+
+    Keymap::get()?.keycode(kind)
+}
+
+impl TryFrom<Keysym> for KeyKind {
+    type Error = Error;
+
+    fn try_from(value: Keysym) -> Result<Self> {
+        Ok(match value {
+            XK_A => KeyKind::A,
+            XK_B => KeyKind::B,
+            XK_C => KeyKind::C,
+            XK_D => KeyKind::D,
+            XK_E => KeyKind::E,
+            XK_F => KeyKind::F,
+            XK_G => KeyKind::G,
+            XK_H => KeyKind::H,
+            XK_I => KeyKind::I,
+            XK_J => KeyKind::J,
+            XK_K => KeyKind::K,
+            XK_L => KeyKind::L,
+            XK_M => KeyKind::M,
+            XK_N => KeyKind::N,
+            XK_O => KeyKind::O,
+            XK_P => KeyKind::P,
+            XK_Q => KeyKind::Q,
+            XK_R => KeyKind::R,
+            XK_S => KeyKind::S,
+            XK_T => KeyKind::T,
+            XK_U => KeyKind::U,
+            XK_V => KeyKind::V,
+            XK_W => KeyKind::W,
+            XK_X => KeyKind::X,
+            XK_Y => KeyKind::Y,
+            XK_Z => KeyKind::Z,
+            XK_0 => KeyKind::Zero,
+            XK_1 => KeyKind::One,
+            XK_2 => KeyKind::Two,
+            XK_3 => KeyKind::Three,
+            XK_4 => KeyKind::Four,
+            XK_5 => KeyKind::Five,
+            XK_6 => KeyKind::Six,
+            XK_7 => KeyKind::Seven,
+            XK_8 => KeyKind::Eight,
+            XK_9 => KeyKind::Nine,
+            XK_F1 => KeyKind::F1,
+            XK_F2 => KeyKind::F2,
+            XK_F3 => KeyKind::F3,
+            XK_F4 => KeyKind::F4,
+            XK_F5 => KeyKind::F5,
+            XK_F6 => KeyKind::F6,
+            XK_F7 => KeyKind::F7,
+            XK_F8 => KeyKind::F8,
+            XK_F9 => KeyKind::F9,
+            XK_F10 => KeyKind::F10,
+            XK_F11 => KeyKind::F11,
+            XK_F12 => KeyKind::F12,
+            XK_F13 => KeyKind::F13,
+            XK_F14 => KeyKind::F14,
+            XK_F15 => KeyKind::F15,
+            XK_F16 => KeyKind::F16,
+            XK_F17 => KeyKind::F17,
+            XK_F18 => KeyKind::F18,
+            XK_F19 => KeyKind::F19,
+            XK_F20 => KeyKind::F20,
+            XK_F21 => KeyKind::F21,
+            XK_F22 => KeyKind::F22,
+            XK_F23 => KeyKind::F23,
+            XK_F24 => KeyKind::F24,
+            XK_UP => KeyKind::Up,
+            XK_DOWN => KeyKind::Down,
+            XK_LEFT => KeyKind::Left,
+            XK_RIGHT => KeyKind::Right,
+            XK_HOME => KeyKind::Home,
+            XK_END => KeyKind::End,
+            XK_PRIOR => KeyKind::PageUp,
+            XK_NEXT => KeyKind::PageDown,
+            XK_INSERT => KeyKind::Insert,
+            XK_DELETE => KeyKind::Delete,
+            XK_CONTROL_L => KeyKind::Ctrl,
+            XK_RETURN => KeyKind::Enter,
+            XK_SPACE => KeyKind::Space,
+            XK_GRAVE => KeyKind::Tilde,
+            XK_APOSTROPHE => KeyKind::Quote,
+            XK_SEMICOLON => KeyKind::Semicolon,
+            XK_COMMA => KeyKind::Comma,
+            XK_PERIOD => KeyKind::Period,
+            XK_SLASH => KeyKind::Slash,
+            XK_ESCAPE => KeyKind::Esc,
+            XK_SHIFT_L => KeyKind::Shift,
+            XK_ALT_L => KeyKind::Alt,
+            XK_BACKSPACE => KeyKind::Backspace,
+            XK_KP_0 => KeyKind::NumpadZero,
+            XK_KP_1 => KeyKind::NumpadOne,
+            XK_KP_2 => KeyKind::NumpadTwo,
+            XK_KP_3 => KeyKind::NumpadThree,
+            XK_KP_4 => KeyKind::NumpadFour,
+            XK_KP_5 => KeyKind::NumpadFive,
+            XK_KP_6 => KeyKind::NumpadSix,
+            XK_KP_7 => KeyKind::NumpadSeven,
+            XK_KP_8 => KeyKind::NumpadEight,
+            XK_KP_9 => KeyKind::NumpadNine,
+            XK_KP_ADD => KeyKind::NumpadAdd,
+            XK_KP_SUBTRACT => KeyKind::NumpadSubtract,
+            XK_KP_MULTIPLY => KeyKind::NumpadMultiply,
+            XK_KP_DIVIDE => KeyKind::NumpadDivide,
+            XK_KP_DECIMAL => KeyKind::NumpadDecimal,
+            _ => return Err(Error::KeyNotFound),
+        })
+    }
+}
+
+impl From<KeyKind> for Keysym {
+    fn from(value: KeyKind) -> Self {
+        match value {
+            KeyKind::A => XK_A,
+            KeyKind::B => XK_B,
+            KeyKind::C => XK_C,
+            KeyKind::D => XK_D,
+            KeyKind::E => XK_E,
+            KeyKind::F => XK_F,
+            KeyKind::G => XK_G,
+            KeyKind::H => XK_H,
+            KeyKind::I => XK_I,
+            KeyKind::J => XK_J,
+            KeyKind::K => XK_K,
+            KeyKind::L => XK_L,
+            KeyKind::M => XK_M,
+            KeyKind::N => XK_N,
+            KeyKind::O => XK_O,
+            KeyKind::P => XK_P,
+            KeyKind::Q => XK_Q,
+            KeyKind::R => XK_R,
+            KeyKind::S => XK_S,
+            KeyKind::T => XK_T,
+            KeyKind::U => XK_U,
+            KeyKind::V => XK_V,
+            KeyKind::W => XK_W,
+            KeyKind::X => XK_X,
+            KeyKind::Y => XK_Y,
+            KeyKind::Z => XK_Z,
+            KeyKind::Zero => XK_0,
+            KeyKind::One => XK_1,
+            KeyKind::Two => XK_2,
+            KeyKind::Three => XK_3,
+            KeyKind::Four => XK_4,
+            KeyKind::Five => XK_5,
+            KeyKind::Six => XK_6,
+            KeyKind::Seven => XK_7,
+            KeyKind::Eight => XK_8,
+            KeyKind::Nine => XK_9,
+            KeyKind::F1 => XK_F1,
+            KeyKind::F2 => XK_F2,
+            KeyKind::F3 => XK_F3,
+            KeyKind::F4 => XK_F4,
+            KeyKind::F5 => XK_F5,
+            KeyKind::F6 => XK_F6,
+            KeyKind::F7 => XK_F7,
+            KeyKind::F8 => XK_F8,
+            KeyKind::F9 => XK_F9,
+            KeyKind::F10 => XK_F10,
+            KeyKind::F11 => XK_F11,
+            KeyKind::F12 => XK_F12,
+            KeyKind::F13 => XK_F13,
+            KeyKind::F14 => XK_F14,
+            KeyKind::F15 => XK_F15,
+            KeyKind::F16 => XK_F16,
+            KeyKind::F17 => XK_F17,
+            KeyKind::F18 => XK_F18,
+            KeyKind::F19 => XK_F19,
+            KeyKind::F20 => XK_F20,
+            KeyKind::F21 => XK_F21,
+            KeyKind::F22 => XK_F22,
+            KeyKind::F23 => XK_F23,
+            KeyKind::F24 => XK_F24,
+            KeyKind::Up => XK_UP,
+            KeyKind::Down => XK_DOWN,
+            KeyKind::Left => XK_LEFT,
+            KeyKind::Right => XK_RIGHT,
+            KeyKind::Home => XK_HOME,
+            KeyKind::End => XK_END,
+            KeyKind::PageUp => XK_PRIOR,
+            KeyKind::PageDown => XK_NEXT,
+            KeyKind::Insert => XK_INSERT,
+            KeyKind::Delete => XK_DELETE,
+            KeyKind::Ctrl => XK_CONTROL_L,
+            KeyKind::Enter => XK_RETURN,
+            KeyKind::Space => XK_SPACE,
+            KeyKind::Tilde => XK_GRAVE,
+            KeyKind::Quote => XK_APOSTROPHE,
+            KeyKind::Semicolon => XK_SEMICOLON,
+            KeyKind::Comma => XK_COMMA,
+            KeyKind::Period => XK_PERIOD,
+            KeyKind::Slash => XK_SLASH,
+            KeyKind::Esc => XK_ESCAPE,
+            KeyKind::Shift => XK_SHIFT_L,
+            KeyKind::Alt => XK_ALT_L,
+            KeyKind::Backspace => XK_BACKSPACE,
+            KeyKind::NumpadZero => XK_KP_0,
+            KeyKind::NumpadOne => XK_KP_1,
+            KeyKind::NumpadTwo => XK_KP_2,
+            KeyKind::NumpadThree => XK_KP_3,
+            KeyKind::NumpadFour => XK_KP_4,
+            KeyKind::NumpadFive => XK_KP_5,
+            KeyKind::NumpadSix => XK_KP_6,
+            KeyKind::NumpadSeven => XK_KP_7,
+            KeyKind::NumpadEight => XK_KP_8,
+            KeyKind::NumpadNine => XK_KP_9,
+            KeyKind::NumpadAdd => XK_KP_ADD,
+            KeyKind::NumpadSubtract => XK_KP_SUBTRACT,
+            KeyKind::NumpadMultiply => XK_KP_MULTIPLY,
+            KeyKind::NumpadDivide => XK_KP_DIVIDE,
+            KeyKind::NumpadDecimal => XK_KP_DECIMAL,
+            // Mouse buttons are sent as pointer buttons instead
+            KeyKind::Mouse4 | KeyKind::Mouse5 => XK_VOID_SYMBOL,
+        }
+    }
+}
+
+#[inline]
+fn is_key_down(keys: &[u8; 32], keycode: u8) -> bool {
+    keys[keycode as usize / 8] & (1 << (keycode % 8)) != 0
+}
+
+#[inline]
+fn is_foreground(display: &Display, window: XWindow, kind: InputKind) -> bool {
+    let Some(active) = active_window(display) else {
+        return false;
+    };
+
+    match kind {
+        InputKind::Focused => active == window,
+        InputKind::Foreground => {
+            if active == window {
+                return false;
+            }
+
+            let (Ok(active_rect), Ok(rect)) = (window_rect(active), window_rect(window)) else {
+                return false;
+            };
+            let (al, at, aw, ah) = active_rect;
+            let (wl, wt, ww, wh) = rect;
+
+            al < wl + ww && wl < al + aw && at < wt + wh && wt < at + ah
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::OnceLock;
+
+use x11rb::{
+    connection::Connection,
+    errors::{ConnectionError, ReplyError, ReplyOrIdError},
+    protocol::xproto::{Atom, ConnectionExt as _, Window as XWindow},
+    rust_connection::RustConnection,
+};
+
+mod capture;
+mod handle;
+mod input;
+
+pub use {capture::*, handle::*, input::*};
+
+use crate::{Error, Result};
+
+/// A shared connection to the X server.
+///
+/// Wayland sessions are supported through XWayland, which is also what Wine/Proton uses for
+/// its windows.
+pub(crate) struct Display {
+    connection: RustConnection,
+    root: XWindow,
+    net_client_list: Atom,
+    net_active_window: Atom,
+    net_wm_name: Atom,
+    net_wm_pid: Atom,
+}
+
+pub(crate) fn display() -> Result<&'static Display> {
+    static DISPLAY: OnceLock<Option<Display>> = OnceLock::new();
+
+    DISPLAY
+        .get_or_init(|| {
+            let (connection, screen) = x11rb::connect(None).ok()?;
+            let root = connection.setup().roots.get(screen)?.root;
+            let atom = |name: &[u8]| {
+                connection
+                    .intern_atom(false, name)
+                    .ok()?
+                    .reply()
+                    .ok()
+                    .map(|reply| reply.atom)
+            };
+            let net_client_list = atom(b"_NET_CLIENT_LIST")?;
+            let net_active_window = atom(b"_NET_ACTIVE_WINDOW")?;
+            let net_wm_name = atom(b"_NET_WM_NAME")?;
+            let net_wm_pid = atom(b"_NET_WM_PID")?;
+
+            Some(Display {
+                connection,
+                root,
+                net_client_list,
+                net_active_window,
+                net_wm_name,
+                net_wm_pid,
+            })
+        })
+        .as_ref()
+        .ok_or(Error::X11("unable to connect to the X server".to_string()))
+}
+
+impl From<ConnectionError> for Error {
+    fn from(error: ConnectionError) -> Self {
+        Error::X11(error.to_string())
+    }
+}
+
+impl From<ReplyError> for Error {
+    fn from(error: ReplyError) -> Self {
+        Error::X11(error.to_string())
+    }
+}
+
+impl From<ReplyOrIdError> for Error {
+    fn from(error: ReplyOrIdError) -> Self {
+        Error::X11(error.to_string())
+    }
+}
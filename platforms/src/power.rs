@@ -0,0 +1,25 @@
+#[cfg(not(windows))]
+use crate::Error;
+use crate::Result;
+#[cfg(windows)]
+use crate::windows::query_windows_power_status;
+
+/// The power status of the machine the bot is running on.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PowerStatus {
+    /// Whether the machine is currently running on battery.
+    pub on_battery: bool,
+    /// The CPU temperature in Celsius if it can be queried.
+    ///
+    /// This is usually not available without administrator privileges.
+    pub cpu_temperature: Option<f32>,
+}
+
+/// Queries the current [`PowerStatus`].
+pub fn query_power_status() -> Result<PowerStatus> {
+    #[cfg(windows)]
+    return query_windows_power_status();
+
+    #[cfg(not(windows))]
+    Err(Error::PlatformNotSupported)
+}
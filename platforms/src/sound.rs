@@ -0,0 +1,14 @@
+#[cfg(not(windows))]
+use crate::Error;
+use crate::Result;
+#[cfg(windows)]
+use crate::windows::play_windows_notification_sound;
+
+/// Plays the system notification sound without waiting for it to finish.
+pub fn play_notification_sound() -> Result<()> {
+    #[cfg(windows)]
+    return play_windows_notification_sound();
+
+    #[cfg(not(windows))]
+    Err(Error::PlatformNotSupported)
+}
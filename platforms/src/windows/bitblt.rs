@@ -1,4 +1,4 @@
-use std::{ffi::c_void, mem, ptr, slice};
+use std::{ffi::c_void, mem, ptr, slice, time::Instant};
 
 use windows::Win32::Foundation::{HWND, RECT};
 use windows::Win32::Graphics::Gdi::{
@@ -118,6 +118,7 @@ impl BitBltCapture {
         if let Err(error) = result {
             return Err(Error::from(error));
         }
+        let captured_at = Instant::now();
         // SAFETY: I swear on the love of Axis Order, this call passed the safety vibe check
         let ptr = unsafe { slice::from_raw_parts(bitmap.buffer, bitmap.size) };
         let data = ptr.to_vec();
@@ -125,6 +126,9 @@ impl BitBltCapture {
             width: bitmap.width,
             height: bitmap.height,
             data,
+            captured_at,
+            // The window device context is in the window logical pixels
+            scale: 1.0,
         })
     }
 }
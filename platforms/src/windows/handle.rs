@@ -10,15 +10,18 @@ use windows::{
                 MonitorFromWindow,
             },
         },
-        UI::WindowsAndMessaging::{
-            EnumWindows, GWL_EXSTYLE, GWL_STYLE, GetClassNameW, GetWindowLongPtrW, GetWindowRect,
-            GetWindowTextW, IsWindowVisible, WS_DISABLED, WS_EX_TOOLWINDOW,
+        UI::{
+            HiDpi::{GetDpiForMonitor, GetDpiForWindow, MDT_EFFECTIVE_DPI},
+            WindowsAndMessaging::{
+                EnumWindows, GWL_EXSTYLE, GWL_STYLE, GetClassNameW, GetWindowLongPtrW,
+                GetWindowRect, GetWindowTextW, IsWindowVisible, WS_DISABLED, WS_EX_TOOLWINDOW,
+            },
         },
     },
     core::BOOL,
 };
 
-use crate::{ConvertedCoordinates, Error, Result};
+use crate::{ConvertedCoordinates, Error, Result, to_physical};
 
 #[derive(Clone, Debug)]
 pub struct HandleCell {
@@ -85,7 +88,11 @@ impl Handle {
         monitor_coordinate: bool,
     ) -> Result<ConvertedCoordinates> {
         let handle = self.as_inner().ok_or(Error::WindowNotFound)?;
-        let mut point = POINT { x, y };
+        let scale = window_scale_factor(handle)?;
+        let mut point = POINT {
+            x: to_physical(x, scale),
+            y: to_physical(y, scale),
+        };
         unsafe { ClientToScreen(handle, &raw mut point).ok()? };
 
         if !monitor_coordinate {
@@ -102,6 +109,7 @@ impl Handle {
                 height,
                 x,
                 y,
+                scale,
             });
         }
 
@@ -127,8 +135,36 @@ impl Handle {
             height,
             x,
             y,
+            scale,
         })
     }
+
+    pub fn scale_factor(&self) -> Result<f32> {
+        let handle = self.as_inner().ok_or(Error::WindowNotFound)?;
+        window_scale_factor(handle)
+    }
+}
+
+/// Retrieves the factor `handle` logical pixels are scaled by to physical pixels.
+///
+/// This process is per-monitor DPI aware and so sees physical pixels. But a window that is not
+/// aware of its monitor DPI (e.g. DPI-unaware games) renders in its own DPI and is scaled up by
+/// the OS by the ratio of the monitor DPI to the window DPI.
+pub(crate) fn window_scale_factor(handle: HWND) -> Result<f32> {
+    let window_dpi = unsafe { GetDpiForWindow(handle) };
+    if window_dpi == 0 {
+        return Err(Error::WindowNotFound);
+    }
+
+    let monitor = unsafe { MonitorFromWindow(handle, MONITOR_DEFAULTTONULL) };
+    if monitor.is_invalid() {
+        return Err(Error::WindowNotFound);
+    }
+    let mut dpi_x = 0;
+    let mut dpi_y = 0;
+    unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &raw mut dpi_x, &raw mut dpi_y)? };
+
+    Ok(dpi_x as f32 / window_dpi as f32)
 }
 
 pub fn query_capture_name_handle_pairs() -> Vec<(String, Handle)> {
@@ -15,34 +15,45 @@ use windows::{
         System::Threading::GetCurrentProcessId,
         UI::{
             Input::KeyboardAndMouse::{
-                GetAsyncKeyState, INPUT, INPUT_0, INPUT_KEYBOARD, INPUT_MOUSE, KEYBD_EVENT_FLAGS,
-                KEYBDINPUT, KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP, MAPVK_VK_TO_VSC_EX,
-                MOUSE_EVENT_FLAGS, MOUSEEVENTF_ABSOLUTE, MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP,
-                MOUSEEVENTF_MOVE, MOUSEEVENTF_VIRTUALDESK, MOUSEEVENTF_WHEEL, MOUSEINPUT,
-                MapVirtualKeyW, SendInput, VIRTUAL_KEY, VK_0, VK_1, VK_2, VK_3, VK_4, VK_5, VK_6,
-                VK_7, VK_8, VK_9, VK_A, VK_B, VK_BACK, VK_C, VK_CONTROL, VK_D, VK_DELETE, VK_DOWN,
-                VK_E, VK_END, VK_ESCAPE, VK_F, VK_F1, VK_F2, VK_F3, VK_F4, VK_F5, VK_F6, VK_F7,
-                VK_F8, VK_F9, VK_F10, VK_F11, VK_F12, VK_G, VK_H, VK_HOME, VK_I, VK_INSERT, VK_J,
-                VK_K, VK_L, VK_LEFT, VK_M, VK_MENU, VK_N, VK_NEXT, VK_O, VK_OEM_1, VK_OEM_2,
-                VK_OEM_3, VK_OEM_7, VK_OEM_COMMA, VK_OEM_PERIOD, VK_P, VK_PRIOR, VK_Q, VK_R,
-                VK_RETURN, VK_RIGHT, VK_S, VK_SHIFT, VK_SPACE, VK_T, VK_U, VK_UP, VK_V, VK_W, VK_X,
-                VK_Y, VK_Z,
+                GetAsyncKeyState, GetKeyboardLayout, HKL, INPUT, INPUT_0, INPUT_KEYBOARD,
+                INPUT_MOUSE, KEYBD_EVENT_FLAGS, KEYBDINPUT, KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP,
+                MAPVK_VK_TO_VSC_EX, MAPVK_VSC_TO_VK_EX, MOUSE_EVENT_FLAGS, MOUSEEVENTF_ABSOLUTE,
+                MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP, MOUSEEVENTF_MOVE,
+                MOUSEEVENTF_VIRTUALDESK, MOUSEEVENTF_WHEEL, MOUSEEVENTF_XDOWN, MOUSEEVENTF_XUP,
+                MOUSEINPUT, MapVirtualKeyExW, SendInput, VIRTUAL_KEY, VK_0, VK_1, VK_2, VK_3, VK_4,
+                VK_5, VK_6, VK_7, VK_8, VK_9, VK_A, VK_ADD, VK_B, VK_BACK, VK_C, VK_CONTROL, VK_D,
+                VK_DECIMAL, VK_DELETE, VK_DIVIDE, VK_DOWN, VK_E, VK_END, VK_ESCAPE, VK_F, VK_F1,
+                VK_F2, VK_F3, VK_F4, VK_F5, VK_F6, VK_F7, VK_F8, VK_F9, VK_F10, VK_F11, VK_F12,
+                VK_F13, VK_F14, VK_F15, VK_F16, VK_F17, VK_F18, VK_F19, VK_F20, VK_F21, VK_F22,
+                VK_F23, VK_F24, VK_G, VK_H, VK_HOME, VK_I, VK_INSERT, VK_J, VK_K, VK_L, VK_LEFT,
+                VK_M, VK_MENU, VK_MULTIPLY, VK_N, VK_NEXT, VK_NUMPAD0, VK_NUMPAD1, VK_NUMPAD2,
+                VK_NUMPAD3, VK_NUMPAD4, VK_NUMPAD5, VK_NUMPAD6, VK_NUMPAD7, VK_NUMPAD8, VK_NUMPAD9,
+                VK_O, VK_OEM_1, VK_OEM_2, VK_OEM_3, VK_OEM_7, VK_OEM_COMMA, VK_OEM_PERIOD, VK_P,
+                VK_PRIOR, VK_Q, VK_R, VK_RETURN, VK_RIGHT, VK_S, VK_SHIFT, VK_SPACE, VK_SUBTRACT,
+                VK_T, VK_U, VK_UP, VK_V, VK_W, VK_X, VK_XBUTTON1, VK_XBUTTON2, VK_Y, VK_Z,
             },
             WindowsAndMessaging::{
                 CallNextHookEx, GetForegroundWindow, GetSystemMetrics, GetWindowRect,
                 GetWindowThreadProcessId, HC_ACTION, HHOOK, KBDLLHOOKSTRUCT, LLKHF_INJECTED,
                 LLKHF_LOWER_IL_INJECTED, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN,
                 SM_YVIRTUALSCREEN, SetWindowsHookExW, WH_KEYBOARD_LL, WM_KEYDOWN, WM_KEYUP,
+                XBUTTON1, XBUTTON2,
             },
         },
     },
     core::Owned,
 };
 
-use super::{HandleCell, handle::Handle};
+use super::{
+    HandleCell,
+    handle::{Handle, window_scale_factor},
+};
 use crate::{
     Error, Result,
-    input::{InputKind, KeyKind, KeyState, MouseKind},
+    input::{
+        InputKind, KeyKind, KeyState, KeyboardLayout, MouseKind, keyboard_layout, qwerty_scan_code,
+    },
+    to_physical,
 };
 
 static KEY_CHANNEL: LazyLock<Sender<KeyKind>> = LazyLock::new(|| broadcast::channel(1).0);
@@ -181,7 +192,11 @@ impl WindowsInput {
     }
 
     pub fn key_state(&self, kind: KeyKind) -> Result<KeyState> {
-        let result = unsafe { GetAsyncKeyState(VIRTUAL_KEY::from(kind).0 as i32) } as u16;
+        let key = self
+            .get_handle()
+            .map(|handle| self.to_layout_key(handle, kind).0)
+            .unwrap_or_else(|_| kind.into());
+        let result = unsafe { GetAsyncKeyState(key.0 as i32) } as u16;
         let is_down = result & 0x8000 != 0;
         let state = if is_down {
             KeyState::Pressed
@@ -223,8 +238,7 @@ impl WindowsInput {
             return Err(Error::KeyNotSent);
         }
 
-        let key = kind.into();
-        let (scan_code, is_extended) = to_scan_code(key);
+        let key = VIRTUAL_KEY::from(kind);
         let mut key_down = self.key_down.borrow_mut();
         // SAFETY: VIRTUAL_KEY is from range 0..254 (inclusive) and BitVec
         // was initialized with 256 elements
@@ -240,7 +254,44 @@ impl WindowsInput {
                 key_down.set(key.0 as usize, is_down);
             }
         }
-        send_input(to_input(key, scan_code, is_extended, is_down))
+        let input = match kind {
+            KeyKind::Mouse4 | KeyKind::Mouse5 => to_mouse_button_input(kind, is_down),
+            _ => {
+                let (layout_key, scan_code, is_extended) = self.to_layout_key(handle, kind);
+                to_input(layout_key, scan_code, is_extended, is_down)
+            }
+        };
+        send_input(input)
+    }
+
+    /// Translates `kind` to the virtual key, scan code and extended flag to send to `handle`
+    /// under the current [`KeyboardLayout`].
+    ///
+    /// Keyboard layouts are per-thread so the layout of the thread owning the game window is used
+    /// instead of this thread.
+    fn to_layout_key(&self, mut handle: HWND, kind: KeyKind) -> (VIRTUAL_KEY, u16, bool) {
+        if matches!(self.input_kind, InputKind::Foreground) {
+            handle = unsafe { GetForegroundWindow() };
+        }
+        let thread_id = unsafe { GetWindowThreadProcessId(handle, None) };
+        let layout = unsafe { GetKeyboardLayout(thread_id) };
+        let key = VIRTUAL_KEY::from(kind);
+
+        if matches!(keyboard_layout(), KeyboardLayout::Qwerty)
+            && let Some(scan_code) = qwerty_scan_code(kind)
+        {
+            let layout_key =
+                unsafe { MapVirtualKeyExW(scan_code as u32, MAPVK_VSC_TO_VK_EX, Some(layout)) };
+            let layout_key = if layout_key == 0 {
+                key
+            } else {
+                VIRTUAL_KEY(layout_key as u16)
+            };
+            return (layout_key, scan_code, false);
+        }
+
+        let (scan_code, is_extended) = to_scan_code(key, layout);
+        (key, scan_code, is_extended)
     }
 
     #[inline]
@@ -302,6 +353,18 @@ impl TryFrom<VIRTUAL_KEY> for KeyKind {
             VK_F10 => KeyKind::F10,
             VK_F11 => KeyKind::F11,
             VK_F12 => KeyKind::F12,
+            VK_F13 => KeyKind::F13,
+            VK_F14 => KeyKind::F14,
+            VK_F15 => KeyKind::F15,
+            VK_F16 => KeyKind::F16,
+            VK_F17 => KeyKind::F17,
+            VK_F18 => KeyKind::F18,
+            VK_F19 => KeyKind::F19,
+            VK_F20 => KeyKind::F20,
+            VK_F21 => KeyKind::F21,
+            VK_F22 => KeyKind::F22,
+            VK_F23 => KeyKind::F23,
+            VK_F24 => KeyKind::F24,
             VK_UP => KeyKind::Up,
             VK_DOWN => KeyKind::Down,
             VK_LEFT => KeyKind::Left,
@@ -324,6 +387,23 @@ impl TryFrom<VIRTUAL_KEY> for KeyKind {
             VK_ESCAPE => KeyKind::Esc,
             VK_SHIFT => KeyKind::Shift,
             VK_MENU => KeyKind::Alt,
+            VK_NUMPAD0 => KeyKind::NumpadZero,
+            VK_NUMPAD1 => KeyKind::NumpadOne,
+            VK_NUMPAD2 => KeyKind::NumpadTwo,
+            VK_NUMPAD3 => KeyKind::NumpadThree,
+            VK_NUMPAD4 => KeyKind::NumpadFour,
+            VK_NUMPAD5 => KeyKind::NumpadFive,
+            VK_NUMPAD6 => KeyKind::NumpadSix,
+            VK_NUMPAD7 => KeyKind::NumpadSeven,
+            VK_NUMPAD8 => KeyKind::NumpadEight,
+            VK_NUMPAD9 => KeyKind::NumpadNine,
+            VK_ADD => KeyKind::NumpadAdd,
+            VK_SUBTRACT => KeyKind::NumpadSubtract,
+            VK_MULTIPLY => KeyKind::NumpadMultiply,
+            VK_DIVIDE => KeyKind::NumpadDivide,
+            VK_DECIMAL => KeyKind::NumpadDecimal,
+            VK_XBUTTON1 => KeyKind::Mouse4,
+            VK_XBUTTON2 => KeyKind::Mouse5,
             _ => return Err(Error::KeyNotFound),
         })
     }
@@ -380,6 +460,18 @@ impl From<KeyKind> for VIRTUAL_KEY {
             KeyKind::F10 => VK_F10,
             KeyKind::F11 => VK_F11,
             KeyKind::F12 => VK_F12,
+            KeyKind::F13 => VK_F13,
+            KeyKind::F14 => VK_F14,
+            KeyKind::F15 => VK_F15,
+            KeyKind::F16 => VK_F16,
+            KeyKind::F17 => VK_F17,
+            KeyKind::F18 => VK_F18,
+            KeyKind::F19 => VK_F19,
+            KeyKind::F20 => VK_F20,
+            KeyKind::F21 => VK_F21,
+            KeyKind::F22 => VK_F22,
+            KeyKind::F23 => VK_F23,
+            KeyKind::F24 => VK_F24,
             KeyKind::Up => VK_UP,
             KeyKind::Down => VK_DOWN,
             KeyKind::Left => VK_LEFT,
@@ -403,12 +495,33 @@ impl From<KeyKind> for VIRTUAL_KEY {
             KeyKind::Shift => VK_SHIFT,
             KeyKind::Alt => VK_MENU,
             KeyKind::Backspace => VK_BACK,
+            KeyKind::NumpadZero => VK_NUMPAD0,
+            KeyKind::NumpadOne => VK_NUMPAD1,
+            KeyKind::NumpadTwo => VK_NUMPAD2,
+            KeyKind::NumpadThree => VK_NUMPAD3,
+            KeyKind::NumpadFour => VK_NUMPAD4,
+            KeyKind::NumpadFive => VK_NUMPAD5,
+            KeyKind::NumpadSix => VK_NUMPAD6,
+            KeyKind::NumpadSeven => VK_NUMPAD7,
+            KeyKind::NumpadEight => VK_NUMPAD8,
+            KeyKind::NumpadNine => VK_NUMPAD9,
+            KeyKind::NumpadAdd => VK_ADD,
+            KeyKind::NumpadSubtract => VK_SUBTRACT,
+            KeyKind::NumpadMultiply => VK_MULTIPLY,
+            KeyKind::NumpadDivide => VK_DIVIDE,
+            KeyKind::NumpadDecimal => VK_DECIMAL,
+            KeyKind::Mouse4 => VK_XBUTTON1,
+            KeyKind::Mouse5 => VK_XBUTTON2,
         }
     }
 }
 
 fn client_to_absolute_coordinate_raw(handle: HWND, x: i32, y: i32) -> Result<(i32, i32)> {
-    let mut point = POINT { x, y };
+    let scale = window_scale_factor(handle)?;
+    let mut point = POINT {
+        x: to_physical(x, scale),
+        y: to_physical(y, scale),
+    };
     unsafe { ClientToScreen(handle, &raw mut point).ok()? };
 
     let virtual_left = unsafe { GetSystemMetrics(SM_XVIRTUALSCREEN) };
@@ -476,8 +589,9 @@ fn send_input(input: [INPUT; 1]) -> Result<()> {
 }
 
 #[inline]
-fn to_scan_code(key: VIRTUAL_KEY) -> (u16, bool) {
-    let scan_code = unsafe { MapVirtualKeyW(key.0 as u32, MAPVK_VK_TO_VSC_EX) } as u16;
+fn to_scan_code(key: VIRTUAL_KEY, layout: HKL) -> (u16, bool) {
+    let scan_code =
+        unsafe { MapVirtualKeyExW(key.0 as u32, MAPVK_VK_TO_VSC_EX, Some(layout)) } as u16;
     let code = scan_code & 0xFF;
     let is_extended = if VK_INSERT == key {
         true
@@ -487,6 +601,32 @@ fn to_scan_code(key: VIRTUAL_KEY) -> (u16, bool) {
     (code, is_extended)
 }
 
+/// Converts side mouse button `kind` to an input pressing the button at the current cursor.
+#[inline]
+fn to_mouse_button_input(kind: KeyKind, is_down: bool) -> [INPUT; 1] {
+    let button = if matches!(kind, KeyKind::Mouse4) {
+        XBUTTON1
+    } else {
+        XBUTTON2
+    };
+    let flags = if is_down {
+        MOUSEEVENTF_XDOWN
+    } else {
+        MOUSEEVENTF_XUP
+    };
+    [INPUT {
+        r#type: INPUT_MOUSE,
+        Anonymous: INPUT_0 {
+            mi: MOUSEINPUT {
+                dwFlags: flags,
+                mouseData: button as u32,
+                dwExtraInfo: *PROCESS_ID as usize,
+                ..MOUSEINPUT::default()
+            },
+        },
+    }]
+}
+
 #[inline]
 fn to_input(key: VIRTUAL_KEY, scan_code: u16, is_extended: bool, is_down: bool) -> [INPUT; 1] {
     let is_extended = if is_extended {
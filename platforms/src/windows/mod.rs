@@ -13,10 +13,12 @@ use windows::Win32::UI::WindowsAndMessaging::{
 mod bitblt;
 mod handle;
 mod input;
+mod power;
+mod sound;
 mod wgc;
 mod window_box;
 
-pub use {bitblt::*, handle::*, input::*, wgc::*, window_box::*};
+pub use {bitblt::*, handle::*, input::*, power::*, sound::*, wgc::*, window_box::*};
 
 use crate::{Error, Result, capture::Frame};
 
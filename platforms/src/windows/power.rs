@@ -0,0 +1,77 @@
+use windows::{
+    Win32::System::{
+        Com::{CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED, CoCreateInstance, CoInitializeEx},
+        Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS},
+        Variant::VARIANT,
+        Wmi::{
+            IWbemClassObject, IWbemLocator, WBEM_FLAG_FORWARD_ONLY, WBEM_FLAG_RETURN_IMMEDIATELY,
+            WBEM_GENERIC_FLAG_TYPE, WBEM_INFINITE, WbemLocator,
+        },
+    },
+    core::{BSTR, w},
+};
+
+use crate::{Result, power::PowerStatus};
+
+/// The `ACLineStatus` value when the system is running on battery.
+const AC_LINE_OFFLINE: u8 = 0;
+
+pub fn query_windows_power_status() -> Result<PowerStatus> {
+    let mut status = SYSTEM_POWER_STATUS::default();
+    unsafe { GetSystemPowerStatus(&raw mut status) }?;
+
+    Ok(PowerStatus {
+        on_battery: status.ACLineStatus == AC_LINE_OFFLINE,
+        cpu_temperature: query_cpu_temperature().ok().flatten(),
+    })
+}
+
+/// Queries the highest thermal zone temperature in Celsius through WMI.
+///
+/// `MSAcpi_ThermalZoneTemperature` requires administrator privileges and is not exposed by all
+/// firmwares, returns `None` when there is no thermal zone.
+fn query_cpu_temperature() -> Result<Option<f32>> {
+    // Either already initialized or initialized with a different concurrency model by this
+    // thread, both are fine for querying
+    let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+
+    let locator: IWbemLocator =
+        unsafe { CoCreateInstance(&WbemLocator, None, CLSCTX_INPROC_SERVER) }?;
+    let services = unsafe {
+        locator.ConnectServer(
+            &BSTR::from("ROOT\\WMI"),
+            &BSTR::new(),
+            &BSTR::new(),
+            &BSTR::new(),
+            0,
+            &BSTR::new(),
+            None,
+        )
+    }?;
+    let enumerator = unsafe {
+        services.ExecQuery(
+            &BSTR::from("WQL"),
+            &BSTR::from("SELECT CurrentTemperature FROM MSAcpi_ThermalZoneTemperature"),
+            WBEM_GENERIC_FLAG_TYPE(WBEM_FLAG_FORWARD_ONLY.0 | WBEM_FLAG_RETURN_IMMEDIATELY.0),
+            None,
+        )
+    }?;
+
+    let mut temperature = None::<f32>;
+    loop {
+        let mut objects: [Option<IWbemClassObject>; 1] = [None];
+        let mut returned = 0;
+        let _ = unsafe { enumerator.Next(WBEM_INFINITE, &mut objects, &raw mut returned) };
+        let Some(object) = objects[0].take().filter(|_| returned > 0) else {
+            break;
+        };
+
+        let mut value = VARIANT::default();
+        unsafe { object.Get(w!("CurrentTemperature"), 0, &raw mut value, None, None) }?;
+        // In tenths of Kelvin
+        let celsius = i32::try_from(&value)? as f32 / 10.0 - 273.15;
+        temperature = Some(temperature.map_or(celsius, |current| current.max(celsius)));
+    }
+
+    Ok(temperature)
+}
@@ -0,0 +1,11 @@
+use windows::Win32::{
+    System::Diagnostics::Debug::MessageBeep, UI::WindowsAndMessaging::MB_ICONASTERISK,
+};
+
+use crate::Result;
+
+pub fn play_windows_notification_sound() -> Result<()> {
+    unsafe { MessageBeep(MB_ICONASTERISK) }?;
+
+    Ok(())
+}
@@ -5,7 +5,12 @@
 use std::{
     cmp::min,
     mem, slice,
-    sync::{Arc, Mutex, mpsc},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+        mpsc,
+    },
+    time::{Duration, Instant},
 };
 
 use windows::{
@@ -49,8 +54,8 @@ use windows::{
     core::{HSTRING, Interface, RuntimeName},
 };
 
-use super::{Handle, HandleCell};
-use crate::{Error, Result, capture::Frame};
+use super::{Handle, HandleCell, handle::window_scale_factor};
+use crate::{Error, Result, capture::Frame, to_physical};
 
 #[derive(Debug)]
 struct SendWrapper<T> {
@@ -94,10 +99,16 @@ struct WgcCaptureInner {
 impl WgcCaptureInner {
     fn grab(&mut self) -> Result<Frame> {
         let handle = *self.handle.as_inner();
-        let message = self.frame_rx.recv().unwrap();
+        let mut message = self.frame_rx.recv().unwrap();
+        // Skips to the latest frame in case frames arrived faster than grabbed
+        while matches!(message, Message::FrameArrived(_, _))
+            && let Ok(next) = self.frame_rx.try_recv()
+        {
+            message = next;
+        }
 
-        let frame = match message {
-            Message::FrameArrived(frame) => frame,
+        let (frame, captured_at) = match message {
+            Message::FrameArrived(frame, captured_at) => (frame, captured_at),
             Message::ItemClosed => return Err(Error::WindowNotFound),
         };
         let frame_content_size = frame.ContentSize().unwrap();
@@ -107,7 +118,9 @@ impl WgcCaptureInner {
         let mut surface_desc = D3D11_TEXTURE2D_DESC::default();
         unsafe { surface_texture.GetDesc(&mut surface_desc) };
 
-        let texture_rect = get_client_rect(handle, surface_desc.Width, surface_desc.Height)?;
+        // The captured surface is composed by the OS and so is in physical pixels
+        let scale = window_scale_factor(handle)?;
+        let texture_rect = get_client_rect(handle, scale, surface_desc.Width, surface_desc.Height)?;
         let texture_width = texture_rect.right - texture_rect.left;
         let texture_height = texture_rect.bottom - texture_rect.top;
         if self.d3d11_texture.as_ref().is_none_or(|texture| {
@@ -194,6 +207,8 @@ impl WgcCaptureInner {
             width: texture_width as i32,
             height: texture_height as i32,
             data: vec,
+            captured_at,
+            scale,
         })
     }
 }
@@ -209,7 +224,8 @@ impl Drop for WgcCaptureInner {
 
 #[derive(Debug)]
 enum Message {
-    FrameArrived(Direct3D11CaptureFrame),
+    /// A frame arrived at the frame pool with the instant it arrived.
+    FrameArrived(Direct3D11CaptureFrame, Instant),
     ItemClosed,
 }
 
@@ -221,6 +237,10 @@ pub struct WgcCapture {
     d3d_device: IDirect3DDevice,
     queue_controller: DispatcherQueueController,
     inner: Arc<Mutex<Option<WgcCaptureInner>>>,
+    /// The minimum interval in nanoseconds between arrived frames with `0` being unlimited.
+    min_frame_interval_nanos: Arc<AtomicU64>,
+    /// The number of frames arrived and not dropped due to [`Self::min_frame_interval_nanos`].
+    captured_frames: Arc<AtomicU64>,
 }
 
 impl WgcCapture {
@@ -236,9 +256,26 @@ impl WgcCapture {
             d3d_device,
             queue_controller,
             inner: Arc::new(Mutex::new(None)),
+            min_frame_interval_nanos: Arc::new(AtomicU64::new(0)),
+            captured_frames: Arc::new(AtomicU64::new(0)),
         })
     }
 
+    /// Sets the minimum interval between captured frames.
+    ///
+    /// Frames arriving sooner than `interval` after the previous one (e.g. from a monitor with a
+    /// high refresh rate) are dropped. `None` captures a frame on every monitor refresh.
+    pub fn set_min_frame_interval(&mut self, interval: Option<Duration>) {
+        let nanos = interval.map_or(0, |interval| interval.as_nanos() as u64);
+        self.min_frame_interval_nanos
+            .store(nanos, Ordering::Relaxed);
+    }
+
+    /// The total number of frames captured.
+    pub fn captured_frames(&self) -> u64 {
+        self.captured_frames.load(Ordering::Relaxed)
+    }
+
     pub fn grab(&mut self) -> Result<Frame> {
         if self.inner.lock().unwrap().is_none()
             && let Some(handle) = self.handle.as_inner()
@@ -271,6 +308,8 @@ impl WgcCapture {
         let d3d_device = SendWrapper {
             inner: self.d3d_device.clone(),
         };
+        let min_frame_interval_nanos = self.min_frame_interval_nanos.clone();
+        let captured_frames = self.captured_frames.clone();
         let (pending_tx, pending_rx) = mpsc::channel::<()>();
 
         let _ = queue.TryEnqueue(&DispatcherQueueHandler::new(move || {
@@ -289,15 +328,30 @@ impl WgcCapture {
             let frame_last_content_size = item.Size()?;
             let (session, frame_pool) =
                 create_capture_session(d3d_device.as_inner(), &item, frame_format)?;
+            let frame_next_due = Mutex::new(None::<Instant>);
             let frame_arrived_token = frame_pool.FrameArrived(&TypedEventHandler::<
                 Direct3D11CaptureFramePool,
                 _,
             >::new(
                 move |frame_pool, _| {
-                    tx.send(Message::FrameArrived(
-                        frame_pool.as_ref().unwrap().TryGetNextFrame().unwrap(),
-                    ))
-                    .unwrap();
+                    let frame = frame_pool.as_ref().unwrap().TryGetNextFrame().unwrap();
+                    let interval =
+                        Duration::from_nanos(min_frame_interval_nanos.load(Ordering::Relaxed));
+                    let now = Instant::now();
+                    let mut next_due = frame_next_due.lock().unwrap();
+                    if next_due.is_some_and(|due| now < due) {
+                        let _ = frame.Close();
+                        return Ok(());
+                    }
+                    // Advances from the previous due instant to keep the average rate unless
+                    // falling behind by more than an interval
+                    *next_due = Some(match *next_due {
+                        Some(due) if now.duration_since(due) < interval => due + interval,
+                        _ => now + interval,
+                    });
+
+                    captured_frames.fetch_add(1, Ordering::Relaxed);
+                    tx.send(Message::FrameArrived(frame, now)).unwrap();
                     Ok(())
                 },
             ))?;
@@ -340,7 +394,7 @@ impl Drop for WgcCapture {
 }
 
 #[inline]
-fn get_client_rect(handle: HWND, width: u32, height: u32) -> Result<D3D11_BOX> {
+fn get_client_rect(handle: HWND, scale: f32, width: u32, height: u32) -> Result<D3D11_BOX> {
     let mut window_rect = RECT::default();
     let mut client_rect = RECT::default();
     unsafe { GetClientRect(handle, &mut client_rect)? };
@@ -353,6 +407,9 @@ fn get_client_rect(handle: HWND, width: u32, height: u32) -> Result<D3D11_BOX> {
         )?
     };
 
+    // Client area is in the window logical pixels unlike the frame bounds
+    let client_right = to_physical(client_rect.right, scale) as u32;
+    let client_bottom = to_physical(client_rect.bottom, scale) as u32;
     let rect_width = (client_rect.right - client_rect.left) as u32;
     let rect_height = (client_rect.bottom - client_rect.top) as u32;
     if rect_width == 0 || rect_height == 0 {
@@ -364,12 +421,12 @@ fn get_client_rect(handle: HWND, width: u32, height: u32) -> Result<D3D11_BOX> {
     let left = (upper_left.x as u32).saturating_sub(window_rect.left as u32);
     let top = (upper_left.y as u32).saturating_sub(window_rect.top as u32);
     let texture_width = if width > left {
-        min(width - left, client_rect.right as u32)
+        min(width - left, client_right)
     } else {
         1
     };
     let texture_height = if height > top {
-        min(height - top, client_rect.bottom as u32)
+        min(height - top, client_bottom)
     } else {
         1
     };
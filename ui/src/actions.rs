@@ -5,9 +5,16 @@ use std::{
 };
 
 use backend::{
-    Action, ActionCondition, ActionKey, ActionKeyDirection, ActionKeyWith, ActionMove, Bound,
-    IntoEnumIterator, KeyBinding, LinkKeyBinding, Map, MobbingKey, Platform, Position,
-    RotationMode, WaitAfterBuffered, key_receiver, update_map, upsert_map,
+    Action, ActionCondition, ActionKey, ActionKeyDirection, ActionKeyWith, ActionMacro, ActionMove,
+    ActionNotify, ActionTags, ActionsBurstWindow, ActionsTimeSlice, AutoMobQuadrantMode,
+    AutoMobQuadrants, Bound, ConditionExpression, DatabaseEvent, GlobalPreset, InputMacro,
+    IntoEnumIterator, KeyBinding, LinkKeyBinding, Map, MapNotifications, MobbingKey,
+    NotificationOverride, Platform, PlatformSet, PlatformSetTrigger, Position, RotationMode,
+    RotationSimulationArgs, RuneRetryPolicy, StrangerPolicy, Upsert, WaitAfterBuffered,
+    convert_image_to_base64, database_event_receiver, delete_global_preset, delete_input_macro,
+    game_state_receiver, key_receiver, query_global_presets, query_input_macros,
+    record_input_macro, simulate_rotation, update_disabled_action_tags, update_map,
+    upsert_global_preset, upsert_input_macro, upsert_map, upsert_map_with,
 };
 use dioxus::{html::FileData, prelude::*};
 use futures_util::StreamExt;
@@ -19,6 +26,7 @@ use crate::{
         ContentAlign, ContentSide,
         button::{Button, ButtonStyle},
         checkbox::Checkbox,
+        conflict::ConflictPopup,
         file::{FileInput, FileOutput},
         icons::{DownArrowIcon, UpArrowIcon, XIcon},
         key::KeyInput,
@@ -29,6 +37,7 @@ use crate::{
         position::PositionInput,
         section::Section,
         select::{Select, SelectOption},
+        text::TextInput,
     },
 };
 
@@ -50,6 +59,7 @@ enum ActionsUpdate {
 struct ActionsContext {
     map: Memo<Map>,
     save_map: Callback<Map>,
+    input_macros: Memo<Vec<InputMacro>>,
 }
 
 #[component]
@@ -83,6 +93,10 @@ pub fn ActionsScreen() -> Element {
         })
     });
 
+    // The rejected map and the stored map when saving conflicts with a newer modification
+    let mut conflict = use_signal::<Option<(Map, Map)>>(|| None);
+    let conflict_open = use_memo(move || conflict().is_some());
+
     // Handles async operations for action-related
     let coroutine = use_coroutine(move |mut rx: UnboundedReceiver<ActionsUpdate>| async move {
         while let Some(message) = rx.next().await {
@@ -91,61 +105,90 @@ pub fn ActionsScreen() -> Element {
                     update_map(map_preset(), map()).await;
                 }
                 ActionsUpdate::Create(preset) => {
-                    let Some(mut current_map) = map() else {
+                    let Some(current_map) = map() else {
                         continue;
                     };
-                    if current_map
-                        .actions
-                        .try_insert(preset.clone(), vec![])
-                        .is_err()
-                    {
+                    if current_map.actions.contains_key(&preset) {
                         continue;
                     }
-                    if let Some(current_map) = upsert_map(current_map).await {
+                    let new_preset = preset.clone();
+                    let edit = move |map: &mut Map| {
+                        map.actions.entry(new_preset.clone()).or_default();
+                    };
+                    if let Some(current_map) = upsert_map_with(current_map, edit).await {
                         map_preset.set(Some(preset));
                         map.set(Some(current_map));
                         update_map(map_preset(), map()).await;
                     }
                 }
                 ActionsUpdate::Delete => {
-                    let Some(mut current_map) = map() else {
+                    let Some(current_map) = map() else {
                         continue;
                     };
                     let Some(preset) = map_preset() else {
                         continue;
                     };
 
-                    if current_map.actions.remove(&preset).is_none() {
+                    if !current_map.actions.contains_key(&preset) {
                         continue;
                     }
-                    if let Some(current_map) = upsert_map(current_map).await {
+                    let edit = move |map: &mut Map| {
+                        map.actions.remove(&preset);
+                        map.actions_time_slices.retain(|slice| slice.preset != preset);
+                    };
+                    if let Some(current_map) = upsert_map_with(current_map, edit).await {
                         map_preset.set(current_map.actions.keys().next().cloned());
                         map.set(Some(current_map));
                         update_map(map_preset(), map()).await;
                     }
                 }
                 ActionsUpdate::Update(actions) => {
-                    let Some(mut current_map) = map() else {
+                    let Some(current_map) = map() else {
                         continue;
                     };
                     let Some(preset) = map_preset() else {
                         continue;
                     };
 
-                    current_map.actions.insert(preset, actions);
-                    if let Some(current_map) = upsert_map(current_map).await {
+                    let edit = move |map: &mut Map| {
+                        map.actions.insert(preset.clone(), actions.clone());
+                    };
+                    if let Some(current_map) = upsert_map_with(current_map, edit).await {
                         map.set(Some(current_map));
                     }
                 }
                 ActionsUpdate::UpdateMinimap(new_map) => {
-                    if let Some(new_map) = upsert_map(new_map).await {
-                        map.set(Some(new_map));
+                    match upsert_map(new_map.clone()).await {
+                        Upsert::Saved(new_map) => map.set(Some(new_map)),
+                        Upsert::Conflict(stored_map) => conflict.set(Some((new_map, stored_map))),
+                        Upsert::Failed => (),
                     }
                 }
             }
         }
     });
 
+    // Reloads the current map when it is modified outside of this screen (e.g. by reloading
+    // an external preset file)
+    use_future(move || async move {
+        let mut rx = database_event_receiver();
+        loop {
+            let event = match rx.recv().await {
+                Ok(value) => value,
+                Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(_)) => continue,
+            };
+            if let DatabaseEvent::MapUpdated(updated) = event
+                && map
+                    .peek()
+                    .as_ref()
+                    .is_some_and(|current| current.id == updated.id && current != &updated)
+            {
+                map.set(Some(updated));
+            }
+        }
+    });
+
     let save_map = use_callback(move |map: Map| {
         coroutine.send(ActionsUpdate::UpdateMinimap(map));
     });
@@ -156,21 +199,68 @@ pub fn ActionsScreen() -> Element {
         coroutine.send(ActionsUpdate::Set);
     });
 
+    let mut input_macros = use_resource(async || query_input_macros().await.unwrap_or_default());
+    let input_macros_view = use_memo(move || input_macros().unwrap_or_default());
+
+    use_future(move || async move {
+        let mut rx = database_event_receiver();
+        loop {
+            let event = match rx.recv().await {
+                Ok(value) => value,
+                Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(_)) => continue,
+            };
+            if matches!(
+                event,
+                DatabaseEvent::InputMacroUpdated | DatabaseEvent::InputMacroDeleted
+            ) {
+                input_macros.restart();
+            }
+        }
+    });
+
     use_context_provider(|| ActionsContext {
         map: map_view,
         save_map,
+        input_macros: input_macros_view,
     });
 
     rsx! {
         div { class: "flex flex-col pb-15 h-full gap-3 overflow-y-auto pr-2",
             SectionRotation { disabled: map().is_none() }
             SectionPlatforms { disabled: map().is_none() }
+            SectionNotifications { disabled: map().is_none() }
+            SectionStrangerPolicy { disabled: map().is_none() }
+            SectionRuneRetryPolicy { disabled: map().is_none() }
+            SectionTimeSlices { disabled: map().is_none() }
+            SectionBurstWindows { disabled: map().is_none() }
             SectionActions {
                 actions: map_preset_actions,
                 disabled: map().is_none() || map_preset().is_none(),
             }
+            SectionGlobalPresets {
+                actions: map_preset_actions,
+                disabled: map().is_none() || map_preset().is_none(),
+            }
+            SectionInputMacros {}
+            SectionSimulation { actions: map_preset_actions }
             SectionLegends {}
         }
+        ConflictPopup {
+            open: conflict_open,
+            name: "map",
+            on_overwrite: move |_| {
+                if let Some((mut new_map, stored_map)) = conflict.take() {
+                    new_map.revision = stored_map.revision;
+                    coroutine.send(ActionsUpdate::UpdateMinimap(new_map));
+                }
+            },
+            on_discard: move |_| {
+                if let Some((_, stored_map)) = conflict.take() {
+                    map.set(Some(stored_map));
+                }
+            },
+        }
 
         div { class: "flex items-center w-full h-10 pr-2 bg-primary-surface absolute bottom-0",
             NamedSelect {
@@ -215,6 +305,38 @@ fn SectionRotation(disabled: bool) -> Element {
     let context = use_context::<ActionsContext>();
     let map = context.map;
     let save_map = context.save_map;
+    let map_preset = use_context::<AppState>().map_preset;
+    let character = use_context::<AppState>().character;
+    let familiars_setups = use_memo(move || {
+        character()
+            .map(|character| character.familiars_setups)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|setup| setup.name)
+            .collect::<Vec<_>>()
+    });
+    let familiars_setup = use_memo(move || {
+        map_preset().and_then(|preset| map().familiars_setups.get(&preset).cloned())
+    });
+    let preferred_direction = use_memo(move || {
+        map_preset()
+            .and_then(|preset| map().actions_preferred_directions.get(&preset).copied())
+            .unwrap_or_default()
+    });
+    let mut priority_classes_text = use_signal(String::default);
+
+    use_effect(move || {
+        let priority_classes = map().auto_mob_priority_classes;
+        if classes_from_text(&priority_classes_text.peek()) != priority_classes {
+            priority_classes_text.set(
+                priority_classes
+                    .into_iter()
+                    .map(|class| class.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
+    });
 
     let update_mobbing_button_disabled = use_memo(move || {
         !matches!(
@@ -234,7 +356,9 @@ fn SectionRotation(disabled: bool) -> Element {
         let mut map = map();
 
         match map.rotation_mode {
-            RotationMode::StartToEnd | RotationMode::StartToEndThenReverse => return,
+            RotationMode::StartToEnd
+            | RotationMode::StartToEndThenReverse
+            | RotationMode::WeightedRandom => return,
             RotationMode::AutoMobbing => {
                 map.rotation_auto_mob_bound = bound;
             }
@@ -267,7 +391,27 @@ fn SectionRotation(disabled: bool) -> Element {
                         },
                         selected: map().rotation_mode,
                     }
-                    div {}
+                    ActionsSelect::<ActionKeyDirection> {
+                        label: "Preset preferred direction",
+                        tooltip: "Actions of the selected preset with any direction face this direction instead of the last known one.",
+                        disabled: disabled || map_preset().is_none(),
+                        on_selected: move |direction| {
+                            let Some(preset) = map_preset.peek().clone() else {
+                                return;
+                            };
+                            let mut map = map.peek().clone();
+                            match direction {
+                                ActionKeyDirection::Any => {
+                                    map.actions_preferred_directions.remove(&preset);
+                                }
+                                ActionKeyDirection::Left | ActionKeyDirection::Right => {
+                                    map.actions_preferred_directions.insert(preset, direction);
+                                }
+                            }
+                            save_map(map);
+                        },
+                        selected: preferred_direction(),
+                    }
                     PopupTrigger {
                         Button {
                             style: ButtonStyle::Primary,
@@ -276,9 +420,9 @@ fn SectionRotation(disabled: bool) -> Element {
                             on_click: move |_| {
                                 let map = map.peek();
                                 let key = match map.rotation_mode {
-                                    RotationMode::StartToEnd | RotationMode::StartToEndThenReverse => {
-                                        unreachable!()
-                                    }
+                                    RotationMode::StartToEnd
+                                    | RotationMode::StartToEndThenReverse
+                                    | RotationMode::WeightedRandom => unreachable!(),
                                     RotationMode::AutoMobbing | RotationMode::PingPong => {
                                         map.rotation_mobbing_key
                                     }
@@ -297,9 +441,9 @@ fn SectionRotation(disabled: bool) -> Element {
                             on_click: move |_| {
                                 let map = map.peek();
                                 let bound = match map.rotation_mode {
-                                    RotationMode::StartToEnd | RotationMode::StartToEndThenReverse => {
-                                        unreachable!()
-                                    }
+                                    RotationMode::StartToEnd
+                                    | RotationMode::StartToEndThenReverse
+                                    | RotationMode::WeightedRandom => unreachable!(),
                                     RotationMode::AutoMobbing => map.rotation_auto_mob_bound,
                                     RotationMode::PingPong => map.rotation_ping_pong_bound,
                                 };
@@ -332,6 +476,120 @@ fn SectionRotation(disabled: bool) -> Element {
                         },
                         value: map().auto_mob_use_key_when_pathing_update_millis,
                     }
+                    ActionsSelect::<AutoMobQuadrantMode> {
+                        label: "Auto mobbing quadrant balancing",
+                        tooltip: "Weighted picks a quadrant with mobs randomly by its weight. Even picks the quadrant with mobs that has been picked the least.",
+                        disabled,
+                        on_selected: move |mode| {
+                            let map = map.peek().clone();
+                            save_map(Map {
+                                auto_mob_quadrants: AutoMobQuadrants {
+                                    mode,
+                                    ..map.auto_mob_quadrants
+                                },
+                                ..map
+                            })
+                        },
+                        selected: map().auto_mob_quadrants.mode,
+                    }
+                    Labeled { label: "Top left weight",
+                        PrimitiveIntegerInput {
+                            disabled: disabled
+                                || map().auto_mob_quadrants.mode != AutoMobQuadrantMode::Weighted,
+                            on_value: move |top_left_weight| {
+                                let map = map.peek().clone();
+                                save_map(Map {
+                                    auto_mob_quadrants: AutoMobQuadrants {
+                                        top_left_weight,
+                                        ..map.auto_mob_quadrants
+                                    },
+                                    ..map
+                                })
+                            },
+                            value: map().auto_mob_quadrants.top_left_weight,
+                        }
+                    }
+                    Labeled { label: "Top right weight",
+                        PrimitiveIntegerInput {
+                            disabled: disabled
+                                || map().auto_mob_quadrants.mode != AutoMobQuadrantMode::Weighted,
+                            on_value: move |top_right_weight| {
+                                let map = map.peek().clone();
+                                save_map(Map {
+                                    auto_mob_quadrants: AutoMobQuadrants {
+                                        top_right_weight,
+                                        ..map.auto_mob_quadrants
+                                    },
+                                    ..map
+                                })
+                            },
+                            value: map().auto_mob_quadrants.top_right_weight,
+                        }
+                    }
+                    Labeled { label: "Bottom right weight",
+                        PrimitiveIntegerInput {
+                            disabled: disabled
+                                || map().auto_mob_quadrants.mode != AutoMobQuadrantMode::Weighted,
+                            on_value: move |bottom_right_weight| {
+                                let map = map.peek().clone();
+                                save_map(Map {
+                                    auto_mob_quadrants: AutoMobQuadrants {
+                                        bottom_right_weight,
+                                        ..map.auto_mob_quadrants
+                                    },
+                                    ..map
+                                })
+                            },
+                            value: map().auto_mob_quadrants.bottom_right_weight,
+                        }
+                    }
+                    Labeled { label: "Bottom left weight",
+                        PrimitiveIntegerInput {
+                            disabled: disabled
+                                || map().auto_mob_quadrants.mode != AutoMobQuadrantMode::Weighted,
+                            on_value: move |bottom_left_weight| {
+                                let map = map.peek().clone();
+                                save_map(Map {
+                                    auto_mob_quadrants: AutoMobQuadrants {
+                                        bottom_left_weight,
+                                        ..map.auto_mob_quadrants
+                                    },
+                                    ..map
+                                })
+                            },
+                            value: map().auto_mob_quadrants.bottom_left_weight,
+                        }
+                    }
+                    Labeled {
+                        label: "Auto mobbing priority classes (comma-separated)",
+                        tooltip: "Mob detection classes picked first from highest to lowest priority. Only useful with a mob model detecting multiple classes.",
+                        TextInput {
+                            class: "h-6",
+                            disabled,
+                            placeholder: "1, 0",
+                            on_value: move |text: String| {
+                                let auto_mob_priority_classes = classes_from_text(&text);
+                                priority_classes_text.set(text);
+                                save_map(Map {
+                                    auto_mob_priority_classes,
+                                    ..map.peek().clone()
+                                });
+                            },
+                            value: priority_classes_text(),
+                        }
+                    }
+                    ActionsCheckbox {
+                        label: "Auto mobbing tracks mobs",
+                        tooltip: "Keeps detecting mobs while moving to chase moving mobs and to avoid mobs that just died. Uses more CPU.",
+                        disabled,
+                        on_checked: move |auto_mob_track_mobs| {
+                            save_map(Map {
+                                auto_mob_track_mobs,
+                                ..map.peek().clone()
+                            })
+                        },
+                        checked: map().auto_mob_track_mobs,
+                    }
                     ActionsCheckbox {
                         label: "Reset normal actions on Erda Shower resets",
                         disabled,
@@ -343,6 +601,66 @@ fn SectionRotation(disabled: bool) -> Element {
                         },
                         checked: map().actions_any_reset_on_erda_condition,
                     }
+                    ActionsCheckbox {
+                        label: "Weighted random avoids repeating actions",
+                        disabled,
+                        on_checked: move |rotation_weighted_random_no_repeat| {
+                            save_map(Map {
+                                rotation_weighted_random_no_repeat,
+                                ..map.peek().clone()
+                            })
+                        },
+                        checked: map().rotation_weighted_random_no_repeat,
+                    }
+                    Labeled {
+                        label: "Priority actions queue max depth",
+                        tooltip: "Drops the oldest queued priority actions when the queue grows beyond this depth. Zero means unlimited.",
+                        PrimitiveIntegerInput {
+                            disabled,
+                            on_value: move |actions_priority_queue_max_depth| {
+                                save_map(Map {
+                                    actions_priority_queue_max_depth,
+                                    ..map.peek().clone()
+                                })
+                            },
+                            value: map().actions_priority_queue_max_depth,
+                            min_value: 0,
+                        }
+                    }
+                    Labeled {
+                        label: "Preset familiars setup",
+                        tooltip: "Familiars swapping uses this setup of the character while the selected preset is active.",
+                        Select::<Option<String>> {
+                            disabled: disabled || map_preset().is_none(),
+                            on_selected: move |setup: Option<String>| {
+                                let Some(preset) = map_preset.peek().clone() else {
+                                    return;
+                                };
+                                let mut map = map.peek().clone();
+                                match setup {
+                                    Some(setup) => {
+                                        map.familiars_setups.insert(preset, setup);
+                                    }
+                                    None => {
+                                        map.familiars_setups.remove(&preset);
+                                    }
+                                }
+                                save_map(map);
+                            },
+                            SelectOption::<Option<String>> {
+                                value: None,
+                                label: "Character default",
+                                selected: familiars_setup().is_none(),
+                            }
+                            for name in familiars_setups() {
+                                SelectOption::<Option<String>> {
+                                    value: Some(name.clone()),
+                                    selected: familiars_setup().as_ref() == Some(&name),
+                                    label: name,
+                                }
+                            }
+                        }
+                    }
                 }
             }
 
@@ -370,9 +688,523 @@ fn SectionRotation(disabled: bool) -> Element {
                             edit_mobbing_key(key);
                             popup_open.set(false);
                         },
-                        value: key,
+                        value: key,
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionNotifications(disabled: bool) -> Element {
+    let context = use_context::<ActionsContext>();
+    let map = context.map;
+    let save_map = context.save_map;
+    let notifications = use_memo(move || map().notifications);
+
+    let save_notifications = use_callback(move |notifications: MapNotifications| {
+        save_map(Map {
+            notifications,
+            ..map.peek().clone()
+        });
+    });
+
+    rsx! {
+        Section { title: "Notifications",
+            div { class: "grid grid-cols-2 gap-3",
+                ActionsSelect::<NotificationOverride> {
+                    label: "Rune appears",
+                    disabled,
+                    on_selected: move |rune_appear| {
+                        save_notifications(MapNotifications {
+                            rune_appear,
+                            ..*notifications.peek()
+                        });
+                    },
+                    selected: notifications().rune_appear,
+                }
+                ActionsSelect::<NotificationOverride> {
+                    label: "Elite boss appears",
+                    disabled,
+                    on_selected: move |elite_boss_appear| {
+                        save_notifications(MapNotifications {
+                            elite_boss_appear,
+                            ..*notifications.peek()
+                        });
+                    },
+                    selected: notifications().elite_boss_appear,
+                }
+                ActionsSelect::<NotificationOverride> {
+                    label: "Guildie appears",
+                    disabled,
+                    on_selected: move |player_guildie_appear| {
+                        save_notifications(MapNotifications {
+                            player_guildie_appear,
+                            ..*notifications.peek()
+                        });
+                    },
+                    selected: notifications().player_guildie_appear,
+                }
+                ActionsSelect::<NotificationOverride> {
+                    label: "Stranger appears",
+                    disabled,
+                    on_selected: move |player_stranger_appear| {
+                        save_notifications(MapNotifications {
+                            player_stranger_appear,
+                            ..*notifications.peek()
+                        });
+                    },
+                    selected: notifications().player_stranger_appear,
+                }
+                ActionsSelect::<NotificationOverride> {
+                    label: "Friend appears",
+                    disabled,
+                    on_selected: move |player_friend_appear| {
+                        save_notifications(MapNotifications {
+                            player_friend_appear,
+                            ..*notifications.peek()
+                        });
+                    },
+                    selected: notifications().player_friend_appear,
+                }
+                ActionsCheckbox {
+                    label: "Notify immediately",
+                    tooltip: "Sends notifications without waiting to capture game snapshots.",
+                    disabled,
+                    on_checked: move |notify_immediately| {
+                        save_notifications(MapNotifications {
+                            notify_immediately,
+                            ..*notifications.peek()
+                        });
+                    },
+                    checked: notifications().notify_immediately,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionStrangerPolicy(disabled: bool) -> Element {
+    let context = use_context::<ActionsContext>();
+    let map = context.map;
+    let save_map = context.save_map;
+    let policy = use_memo(move || map().stranger_policy);
+    let disabled_tags = use_memo(move || map().actions_disabled_tags_with_strangers);
+    let mut avoid_channels_text = use_signal(String::default);
+    let mut disabled_tags_text = use_signal(String::default);
+
+    let save_policy = use_callback(move |stranger_policy: StrangerPolicy| {
+        save_map(Map {
+            stranger_policy,
+            ..map.peek().clone()
+        });
+    });
+
+    use_effect(move || {
+        let avoid_channels = policy().avoid_channels;
+        if channels_from_text(&avoid_channels_text.peek()) != avoid_channels {
+            avoid_channels_text.set(
+                avoid_channels
+                    .into_iter()
+                    .map(|channel| channel.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
+    });
+    use_effect(move || {
+        let disabled_tags = disabled_tags();
+        if tags_from_text(&disabled_tags_text.peek()) != disabled_tags {
+            disabled_tags_text.set(disabled_tags.join(", "));
+        }
+    });
+
+    rsx! {
+        Section { title: "Stranger policy",
+            div { class: "grid grid-cols-2 gap-3",
+                ActionsCheckbox {
+                    label: "Change channel on stranger",
+                    tooltip: "Changes channel when strangers stay in the minimap and halts when the channel changes per hour are exhausted.",
+                    disabled,
+                    on_checked: move |enabled| {
+                        save_policy(StrangerPolicy {
+                            enabled,
+                            ..policy.peek().clone()
+                        });
+                    },
+                    checked: policy().enabled,
+                }
+                ActionsMillisInput {
+                    label: "Stay duration",
+                    disabled,
+                    on_value: move |linger_millis| {
+                        save_policy(StrangerPolicy {
+                            linger_millis,
+                            ..policy.peek().clone()
+                        });
+                    },
+                    value: policy().linger_millis,
+                }
+                Labeled {
+                    label: "Max changes per hour",
+                    tooltip: "A value of 0 means unlimited.",
+                    PrimitiveIntegerInput {
+                        disabled,
+                        on_value: move |max_changes_per_hour| {
+                            save_policy(StrangerPolicy {
+                                max_changes_per_hour,
+                                ..policy.peek().clone()
+                            });
+                        },
+                        value: policy().max_changes_per_hour,
+                    }
+                }
+                ActionsNumberInputU32 {
+                    label: "Channel count",
+                    disabled,
+                    on_value: move |channel_count| {
+                        save_policy(StrangerPolicy {
+                            channel_count,
+                            ..policy.peek().clone()
+                        });
+                    },
+                    value: policy().channel_count,
+                }
+                Labeled {
+                    label: "Start channel",
+                    tooltip: "The channel the bot starts in or 0 if unknown. Avoided channels are only skipped when the start channel is known.",
+                    PrimitiveIntegerInput {
+                        disabled,
+                        on_value: move |start_channel| {
+                            save_policy(StrangerPolicy {
+                                start_channel,
+                                ..policy.peek().clone()
+                            });
+                        },
+                        value: policy().start_channel,
+                    }
+                }
+                Labeled { label: "Avoided channels (comma-separated)",
+                    TextInput {
+                        class: "h-6",
+                        disabled,
+                        placeholder: "1, 2",
+                        on_value: move |text: String| {
+                            let avoid_channels = channels_from_text(&text);
+                            avoid_channels_text.set(text);
+                            save_policy(StrangerPolicy {
+                                avoid_channels,
+                                ..policy.peek().clone()
+                            });
+                        },
+                        value: avoid_channels_text(),
+                    }
+                }
+                Labeled {
+                    label: "Disabled action tags (comma-separated)",
+                    tooltip: "Actions having any of these tags are not used while strangers are in the minimap.",
+                    TextInput {
+                        class: "h-6",
+                        disabled,
+                        placeholder: "burst",
+                        on_value: move |text: String| {
+                            let actions_disabled_tags_with_strangers = tags_from_text(&text);
+                            disabled_tags_text.set(text);
+                            save_map(Map {
+                                actions_disabled_tags_with_strangers,
+                                ..map.peek().clone()
+                            });
+                        },
+                        value: disabled_tags_text(),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionRuneRetryPolicy(disabled: bool) -> Element {
+    let context = use_context::<ActionsContext>();
+    let map = context.map;
+    let save_map = context.save_map;
+    let policy = use_memo(move || map().rune_retry_policy);
+
+    let save_policy = use_callback(move |rune_retry_policy: RuneRetryPolicy| {
+        save_map(Map {
+            rune_retry_policy,
+            ..map.peek().clone()
+        });
+    });
+
+    rsx! {
+        Section { title: "Rune solving",
+            div { class: "grid grid-cols-2 gap-3",
+                ActionsNumberInputU32 {
+                    label: "Max attempts",
+                    disabled,
+                    on_value: move |max_attempts| {
+                        save_policy(RuneRetryPolicy {
+                            max_attempts,
+                            ..*policy.peek()
+                        });
+                    },
+                    value: policy().max_attempts,
+                }
+                ActionsMillisInput {
+                    label: "Cooldown before retry",
+                    disabled,
+                    on_value: move |cooldown_millis| {
+                        save_policy(RuneRetryPolicy {
+                            cooldown_millis,
+                            ..*policy.peek()
+                        });
+                    },
+                    value: policy().cooldown_millis,
+                }
+                ActionsCheckbox {
+                    label: "Move to rune before retry",
+                    tooltip: "Retries in place without moving to the rune again when disabled.",
+                    disabled,
+                    on_checked: move |reapproach| {
+                        save_policy(RuneRetryPolicy {
+                            reapproach,
+                            ..*policy.peek()
+                        });
+                    },
+                    checked: policy().reapproach,
+                }
+                ActionsCheckbox {
+                    label: "Change channel on give up",
+                    tooltip: "Enters the cash shop instead when disabled. Requires the change channel key.",
+                    disabled,
+                    on_checked: move |change_channel_on_give_up| {
+                        save_policy(RuneRetryPolicy {
+                            change_channel_on_give_up,
+                            ..*policy.peek()
+                        });
+                    },
+                    checked: policy().change_channel_on_give_up,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionTimeSlices(disabled: bool) -> Element {
+    let context = use_context::<ActionsContext>();
+    let map = context.map;
+    let save_map = context.save_map;
+    let presets = use_memo(move || map().actions.into_keys().collect::<Vec<String>>());
+
+    let save_time_slices = use_callback(move |actions_time_slices: Vec<ActionsTimeSlice>| {
+        save_map(Map {
+            actions_time_slices,
+            ..map.peek().clone()
+        });
+    });
+    let edit_time_slice = use_callback(move |(index, slice): (usize, Option<ActionsTimeSlice>)| {
+        let mut slices = map.peek().actions_time_slices.clone();
+        match slice {
+            Some(slice) => slices[index] = slice,
+            None => {
+                slices.remove(index);
+            }
+        }
+        save_time_slices(slices);
+    });
+
+    rsx! {
+        Section { title: "Time slices",
+            div { class: "grid grid-cols-3 gap-3",
+                for (index , slice) in map().actions_time_slices.into_iter().enumerate() {
+                    Labeled {
+                        label: "Preset",
+                        tooltip: "Runs each preset for its minutes one after another and repeats from the first. Overrides the selected preset when not empty.",
+                        Select::<String> {
+                            disabled,
+                            on_selected: {
+                                let slice = slice.clone();
+                                move |preset| {
+                                    edit_time_slice((
+                                        index,
+                                        Some(ActionsTimeSlice {
+                                            preset,
+                                            ..slice.clone()
+                                        }),
+                                    ));
+                                }
+                            },
+                            for preset in presets() {
+                                SelectOption::<String> {
+                                    value: preset.clone(),
+                                    label: preset.clone(),
+                                    selected: preset == slice.preset,
+                                }
+                            }
+                        }
+                    }
+                    Labeled { label: "Minutes",
+                        PrimitiveIntegerInput {
+                            disabled,
+                            on_value: {
+                                let slice = slice.clone();
+                                move |minutes| {
+                                    edit_time_slice((
+                                        index,
+                                        Some(ActionsTimeSlice {
+                                            minutes,
+                                            ..slice.clone()
+                                        }),
+                                    ));
+                                }
+                            },
+                            value: slice.minutes,
+                            min_value: 1,
+                        }
+                    }
+                    Button {
+                        class: "self-end h-6",
+                        style: ButtonStyle::Danger,
+                        disabled,
+                        on_click: move |_| {
+                            edit_time_slice((index, None));
+                        },
+                        "Remove"
+                    }
+                }
+                Button {
+                    class: "col-span-3",
+                    style: ButtonStyle::Secondary,
+                    disabled: disabled || presets().is_empty(),
+                    on_click: move |_| {
+                        let mut slices = map.peek().actions_time_slices.clone();
+                        slices.push(ActionsTimeSlice {
+                            preset: presets.peek().first().cloned().unwrap_or_default(),
+                            minutes: 10,
+                        });
+                        save_time_slices(slices);
+                    },
+                    "Add time slice"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionBurstWindows(disabled: bool) -> Element {
+    let context = use_context::<ActionsContext>();
+    let map = context.map;
+    let save_map = context.save_map;
+
+    let save_burst_windows = use_callback(move |actions_burst_windows: Vec<ActionsBurstWindow>| {
+        save_map(Map {
+            actions_burst_windows,
+            ..map.peek().clone()
+        });
+    });
+    let edit_burst_window =
+        use_callback(move |(index, window): (usize, Option<ActionsBurstWindow>)| {
+            let mut windows = map.peek().actions_burst_windows.clone();
+            match window {
+                Some(window) => windows[index] = window,
+                None => {
+                    windows.remove(index);
+                }
+            }
+            save_burst_windows(windows);
+        });
+
+    rsx! {
+        Section { title: "Burst windows",
+            div { class: "grid grid-cols-4 gap-3",
+                for (index , window) in map().actions_burst_windows.into_iter().enumerate() {
+                    Labeled {
+                        label: "Action tag",
+                        tooltip: "Every milliseconds actions having this tag are held until all of them are due and then fired back-to-back in order.",
+                        TextInput {
+                            class: "h-6",
+                            disabled,
+                            placeholder: "burst",
+                            on_value: {
+                                let window = window.clone();
+                                move |tag: String| {
+                                    edit_burst_window((
+                                        index,
+                                        Some(ActionsBurstWindow {
+                                            tag: tag.trim().to_string(),
+                                            ..window.clone()
+                                        }),
+                                    ));
+                                }
+                            },
+                            value: window.tag.clone(),
+                        }
+                    }
+                    ActionsMillisInput {
+                        label: "Tolerance",
+                        disabled,
+                        on_value: {
+                            let window = window.clone();
+                            move |tolerance_millis| {
+                                edit_burst_window((
+                                    index,
+                                    Some(ActionsBurstWindow {
+                                        tolerance_millis,
+                                        ..window.clone()
+                                    }),
+                                ));
+                            }
+                        },
+                        value: window.tolerance_millis,
+                    }
+                    ActionsCheckbox {
+                        label: "Boost priority",
+                        tooltip: "Queues the window in front of other priority actions.",
+                        disabled,
+                        on_checked: {
+                            let window = window.clone();
+                            move |boost_priority| {
+                                edit_burst_window((
+                                    index,
+                                    Some(ActionsBurstWindow {
+                                        boost_priority,
+                                        ..window.clone()
+                                    }),
+                                ));
+                            }
+                        },
+                        checked: window.boost_priority,
+                    }
+                    Button {
+                        class: "self-end h-6",
+                        style: ButtonStyle::Danger,
+                        disabled,
+                        on_click: move |_| {
+                            edit_burst_window((index, None));
+                        },
+                        "Remove"
                     }
-                },
+                }
+                Button {
+                    class: "col-span-4",
+                    style: ButtonStyle::Secondary,
+                    disabled,
+                    on_click: move |_| {
+                        let mut windows = map.peek().actions_burst_windows.clone();
+                        windows.push(ActionsBurstWindow {
+                            tag: "burst".to_string(),
+                            tolerance_millis: 2000,
+                            boost_priority: true,
+                        });
+                        save_burst_windows(windows);
+                    },
+                    "Add burst window"
+                }
             }
         }
     }
@@ -430,15 +1262,30 @@ fn SectionPlatforms(disabled: bool) -> Element {
     let map = context.map;
     let save_map = context.save_map;
 
+    // The index of the platform set being edited, `None` for the default platforms
+    let mut platform_set_index = use_signal(|| None::<usize>);
+    let platform_set = use_memo(move || {
+        platform_set_index().and_then(|index| map().platform_sets.get(index).cloned())
+    });
+    let platforms = use_memo(move || match platform_set() {
+        Some(set) => set.platforms,
+        None => map().platforms,
+    });
+
     let add_platform = use_callback(move |platform| {
         let mut map = map();
+        let Some(platforms) = platforms_mut(&mut map, *platform_set_index.peek()) else {
+            return;
+        };
 
-        map.platforms.push(platform);
+        platforms.push(platform);
         coroutine.send(ActionsUpdate::UpdateMinimap(map));
     });
     let edit_platform = use_callback(move |(new_platform, index): (Platform, usize)| {
         let mut map = map();
-        let Some(platform) = map.platforms.get_mut(index) else {
+        let Some(platform) = platforms_mut(&mut map, *platform_set_index.peek())
+            .and_then(|platforms| platforms.get_mut(index))
+        else {
             return;
         };
 
@@ -447,8 +1294,20 @@ fn SectionPlatforms(disabled: bool) -> Element {
     });
     let delete_platform = use_callback(move |index| {
         let mut map = map();
+        let Some(platforms) = platforms_mut(&mut map, *platform_set_index.peek()) else {
+            return;
+        };
+
+        platforms.remove(index);
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
+    });
+    let edit_platform_set = use_callback(move |set: PlatformSet| {
+        let mut map = map();
+        let Some(index) = *platform_set_index.peek() else {
+            return;
+        };
 
-        map.platforms.remove(index);
+        map.platform_sets[index] = set;
         coroutine.send(ActionsUpdate::UpdateMinimap(map));
     });
 
@@ -555,11 +1414,86 @@ fn SectionPlatforms(disabled: bool) -> Element {
                         },
                         checked: map().auto_mob_platforms_bound,
                     }
+                    ActionsCheckbox {
+                        label: "Robust minimap detection",
+                        tooltip: "For maps with screen shake or heavy weather effects. Filters the minimap over the last few frames and tolerates more detection failures.",
+                        disabled,
+                        on_checked: move |robust_detection| {
+                            save_map(Map {
+                                robust_detection,
+                                ..map.peek().clone()
+                            })
+                        },
+                        checked: map().robust_detection,
+                    }
+                }
+                div { class: "grid grid-cols-3 gap-3 mt-2",
+                    Labeled { label: "Platform set",
+                        Select::<Option<usize>> {
+                            disabled,
+                            on_selected: move |index| {
+                                platform_set_index.set(index);
+                            },
+                            SelectOption::<Option<usize>> {
+                                value: None,
+                                label: "Default",
+                                selected: platform_set().is_none(),
+                            }
+                            for (index , set) in map().platform_sets.into_iter().enumerate() {
+                                SelectOption::<Option<usize>> {
+                                    value: Some(index),
+                                    label: set.name,
+                                    selected: platform_set_index() == Some(index),
+                                }
+                            }
+                        }
+                    }
+                    Button {
+                        class: "self-end h-6",
+                        style: ButtonStyle::Secondary,
+                        disabled,
+                        on_click: move |_| {
+                            let mut map = map();
+                            let index = map.platform_sets.len();
+
+                            map.platform_sets
+                                .push(PlatformSet {
+                                    name: format!("Set {}", index + 1),
+                                    ..PlatformSet::default()
+                                });
+                            platform_set_index.set(Some(index));
+                            coroutine.send(ActionsUpdate::UpdateMinimap(map));
+                        },
+                        "Add set"
+                    }
+                    Button {
+                        class: "self-end h-6",
+                        style: ButtonStyle::Danger,
+                        disabled: disabled || platform_set().is_none(),
+                        on_click: move |_| {
+                            let mut map = map();
+                            let Some(index) = *platform_set_index.peek() else {
+                                return;
+                            };
+
+                            map.platform_sets.remove(index);
+                            platform_set_index.set(None);
+                            coroutine.send(ActionsUpdate::UpdateMinimap(map));
+                        },
+                        "Delete set"
+                    }
+                    if let Some(set) = platform_set() {
+                        PlatformSetInput {
+                            disabled,
+                            on_value: edit_platform_set,
+                            value: set,
+                        }
+                    }
                 }
-                if !map().platforms.is_empty() {
+                if !platforms().is_empty() {
                     div { class: "mt-2" }
                 }
-                for (index , platform) in map().platforms.into_iter().enumerate() {
+                for (index , platform) in platforms().into_iter().enumerate() {
                     PopupTrigger {
                         PlatformItem {
                             platform,
@@ -618,6 +1552,149 @@ fn SectionPlatforms(disabled: bool) -> Element {
     }
 }
 
+#[component]
+fn PlatformSetInput(
+    disabled: bool,
+    on_value: Callback<PlatformSet>,
+    value: ReadSignal<PlatformSet>,
+) -> Element {
+    let upload_template = use_callback(move |file: FileData| async move {
+        let Ok(bytes) = file.read_bytes().await else {
+            return;
+        };
+        let Some(base64) = convert_image_to_base64(Vec::from(bytes), false).await else {
+            return;
+        };
+
+        on_value(PlatformSet {
+            trigger: PlatformSetTrigger::Template(base64),
+            ..value.peek().clone()
+        });
+    });
+
+    rsx! {
+        Labeled { label: "Name",
+            TextInput {
+                class: "h-6",
+                disabled,
+                on_value: move |name| {
+                    on_value(PlatformSet {
+                        name,
+                        ..value.peek().clone()
+                    });
+                },
+                value: Some(value().name),
+            }
+        }
+        ActionsSelect::<PlatformSetTrigger> {
+            label: "Switch from previous set",
+            tooltip: "Sets are switched to in order. Each set waits for its trigger after the previous set becomes active.",
+            disabled,
+            on_selected: move |trigger| {
+                on_value(PlatformSet {
+                    trigger,
+                    ..value.peek().clone()
+                });
+            },
+            selected: value().trigger,
+        }
+        match value().trigger {
+            PlatformSetTrigger::None => rsx! {
+                div {} // Spacer
+            },
+            PlatformSetTrigger::AfterMillis(millis) => rsx! {
+                ActionsMillisInput {
+                    label: "Switch after",
+                    disabled,
+                    on_value: move |millis| {
+                        on_value(PlatformSet {
+                            trigger: PlatformSetTrigger::AfterMillis(millis),
+                            ..value.peek().clone()
+                        });
+                    },
+                    value: millis,
+                }
+            },
+            PlatformSetTrigger::Template(base64) => rsx! {
+                div { class: "flex gap-2 items-end",
+                    Labeled { class: "flex-grow", label: "Template",
+                        div { class: "h-6 border-b border-primary-border pb-0.5",
+                            if base64.is_empty() {
+                                p { class: "text-xs text-secondary-text", "Not set" }
+                            } else {
+                                img {
+                                    src: format!("data:image/png;base64,{base64}"),
+                                    class: "h-full",
+                                }
+                            }
+                        }
+                    }
+                    FileInput {
+                        on_file: move |file| async move {
+                            upload_template(file).await;
+                        },
+                        accept: ".png,image/png",
+                        disabled,
+                        Button { class: "w-14", style: ButtonStyle::Primary, "Upload" }
+                    }
+                }
+            },
+        }
+    }
+}
+
+#[component]
+fn SectionSimulation(actions: Memo<Vec<Action>>) -> Element {
+    let mut travel_millis = use_signal(|| RotationSimulationArgs::default().travel_millis);
+    let simulation = use_memo(move || {
+        let args = RotationSimulationArgs {
+            travel_millis: travel_millis(),
+            ..RotationSimulationArgs::default()
+        };
+
+        simulate_rotation(&actions(), args)
+    });
+
+    rsx! {
+        Section { title: "Priority actions simulation",
+            div { class: "grid grid-cols-2 gap-3",
+                ActionsMillisInput {
+                    label: "Nominal travel time",
+                    on_value: move |millis| {
+                        travel_millis.set(millis);
+                    },
+                    value: travel_millis(),
+                }
+                div {}
+            }
+            p { class: "text-xs text-secondary-text mt-2",
+                "Simulates one hour of priority actions. Collisions: {simulation().collisions.len()}"
+            }
+            div { class: "flex flex-col text-xs text-primary-text mt-2",
+                for summary in simulation().summaries {
+                    p { class: if summary.starved { "text-danger-text" },
+                        {
+                            let label = match actions.peek().get(summary.action_index) {
+                                Some(Action::Key(ActionKey { key, .. })) => key.to_string(),
+                                Some(Action::Macro(_)) => "Macro".to_string(),
+                                Some(Action::Move(_)) | None => "Move".to_string(),
+                            };
+                            let starved = if summary.starved { " - Starved" } else { "" };
+                            format!(
+                                "#{} {label} - Every {}ms - Fired {} times - Max wait {}ms{starved}",
+                                summary.action_index + 1,
+                                summary.interval_millis,
+                                summary.fire_count,
+                                summary.max_wait_millis,
+                            )
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn SectionLegends() -> Element {
     rsx! {
@@ -649,6 +1726,31 @@ fn SectionActions(actions: Memo<Vec<Action>>, disabled: bool) -> Element {
 
     let coroutine = use_coroutine_handle::<ActionsUpdate>();
     let map = use_context::<ActionsContext>().map;
+    let mut disabled_tags = use_context::<AppState>().disabled_action_tags;
+    let mut tag_filter = use_signal(|| None::<String>);
+    let tags = use_memo(move || {
+        let mut tags = Vec::<String>::new();
+        for action in actions() {
+            for tag in action.tags().iter() {
+                if !tags.iter().any(|other| other == tag) {
+                    tags.push(tag.to_string());
+                }
+            }
+        }
+        tags
+    });
+
+    let toggle_disabled_tag = use_callback(move |tag: String| async move {
+        let mut tags = disabled_tags();
+        if let Some(index) = tags.iter().position(|other| *other == tag) {
+            tags.remove(index);
+        } else {
+            tags.push(tag);
+        }
+
+        update_disabled_action_tags(tags.clone()).await;
+        disabled_tags.set(tags);
+    });
 
     let export_name = use_memo(move || format!("{}.json", map().name));
     let export_content = move |_| serde_json::to_vec_pretty(&*actions.peek()).unwrap_or_default();
@@ -842,6 +1944,47 @@ fn SectionActions(actions: Memo<Vec<Action>>, disabled: bool) -> Element {
             on_open: move |open: bool| {
                 popup_open.set(open);
             },
+            if !tags().is_empty() {
+                Section { title: "Tags",
+                    Labeled {
+                        label: "Filter",
+                        tooltip: "Only shows actions having the selected tag.",
+                        div { class: "flex flex-wrap gap-2",
+                            for tag in tags() {
+                                ActionTagChip {
+                                    active: tag_filter().as_ref() == Some(&tag),
+                                    active_style: ButtonStyle::Primary,
+                                    on_click: move |tag: String| {
+                                        let tag = Some(tag);
+                                        if *tag_filter.peek() == tag {
+                                            tag_filter.set(None);
+                                        } else {
+                                            tag_filter.set(tag);
+                                        }
+                                    },
+                                    tag,
+                                }
+                            }
+                        }
+                    }
+                    Labeled {
+                        label: "Disable",
+                        tooltip: "Disables all actions having the selected tags until the app is restarted. Actions already queued or executing still run to completion.",
+                        div { class: "flex flex-wrap gap-2",
+                            for tag in tags() {
+                                ActionTagChip {
+                                    active: disabled_tags().contains(&tag),
+                                    active_style: ButtonStyle::Danger,
+                                    on_click: move |tag| async move {
+                                        toggle_disabled_tag(tag).await;
+                                    },
+                                    tag,
+                                }
+                            }
+                        }
+                    }
+                }
+            }
             Section { title: "Normal actions",
                 ActionList {
                     on_add_click: move |_| {
@@ -858,6 +2001,7 @@ fn SectionActions(actions: Memo<Vec<Action>>, disabled: bool) -> Element {
                     },
                     condition_filter: ActionCondition::Any,
                     disabled,
+                    tag_filter: tag_filter(),
                     actions: actions(),
                 }
             }
@@ -877,6 +2021,7 @@ fn SectionActions(actions: Memo<Vec<Action>>, disabled: bool) -> Element {
                     },
                     condition_filter: ActionCondition::ErdaShowerOffCooldown,
                     disabled,
+                    tag_filter: tag_filter(),
                     actions: actions(),
                 }
             }
@@ -896,6 +2041,27 @@ fn SectionActions(actions: Memo<Vec<Action>>, disabled: bool) -> Element {
                     },
                     condition_filter: ActionCondition::EveryMillis(0),
                     disabled,
+                    tag_filter: tag_filter(),
+                    actions: actions(),
+                }
+            }
+            Section { title: "Expression priority actions",
+                ActionList {
+                    on_add_click: move |_| {
+                        handle_add_action_click(ActionCondition::Expression(Default::default()));
+                    },
+                    on_item_click: move |(action, index)| {
+                        handle_edit_action_click(action, index);
+                    },
+                    on_item_move: move |(index, condition, up)| {
+                        move_action((index, condition, up));
+                    },
+                    on_item_delete: move |index| {
+                        delete_action(index);
+                    },
+                    condition_filter: ActionCondition::Expression(Default::default()),
+                    disabled,
+                    tag_filter: tag_filter(),
                     actions: actions(),
                 }
             }
@@ -927,6 +2093,7 @@ fn SectionActions(actions: Memo<Vec<Action>>, disabled: bool) -> Element {
                         }
                     }
                 }
+                ActionsExternalFileInput { disabled }
             }
 
             match popup_content() {
@@ -968,11 +2135,390 @@ fn SectionActions(actions: Memo<Vec<Action>>, disabled: bool) -> Element {
                             popup_open.set(false);
                             popup_content.set(PopupContent::None);
                         },
-                        value: action,
+                        value: action,
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[component]
+fn ActionTagChip(
+    active: bool,
+    active_style: ButtonStyle,
+    on_click: Callback<String>,
+    tag: String,
+) -> Element {
+    let style = if active {
+        active_style
+    } else {
+        ButtonStyle::OutlineSecondary
+    };
+    let text = tag.clone();
+
+    rsx! {
+        Button {
+            style,
+            on_click: move |_| {
+                on_click(tag.clone());
+            },
+            "{text}"
+        }
+    }
+}
+
+#[component]
+fn SectionGlobalPresets(actions: Memo<Vec<Action>>, disabled: bool) -> Element {
+    let coroutine = use_coroutine_handle::<ActionsUpdate>();
+    let context = use_context::<ActionsContext>();
+    let map = context.map;
+    let save_map = context.save_map;
+    let map_preset = use_context::<AppState>().map_preset;
+    let mut global_presets =
+        use_resource(async || query_global_presets().await.unwrap_or_default());
+    let global_presets_view = use_memo(move || global_presets().unwrap_or_default());
+    let mut selected = use_signal(|| 0usize);
+    let selected_preset = use_memo(move || global_presets_view().get(selected()).cloned());
+    // The names of global presets linked to the currently selected `map_preset`
+    let linked = use_memo(move || {
+        map_preset()
+            .and_then(|preset| map().actions_global_presets.get(&preset).cloned())
+            .unwrap_or_default()
+    });
+    let selected_linked = use_memo(move || {
+        selected_preset().is_some_and(|preset| linked().contains(&preset.name))
+    });
+    let mut name = use_signal(String::default);
+
+    use_future(move || async move {
+        let mut rx = database_event_receiver();
+        loop {
+            let event = match rx.recv().await {
+                Ok(value) => value,
+                Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(_)) => continue,
+            };
+            if matches!(
+                event,
+                DatabaseEvent::GlobalPresetUpdated | DatabaseEvent::GlobalPresetDeleted
+            ) {
+                global_presets.restart();
+            }
+        }
+    });
+
+    let save_as_global = move |_| async move {
+        let name = name.peek().trim().to_string();
+        // Overwrites the global preset with the same name
+        let id = global_presets_view
+            .peek()
+            .iter()
+            .find(|preset| preset.name == name)
+            .and_then(|preset| preset.id);
+        let preset = GlobalPreset {
+            id,
+            name,
+            actions: actions.peek().clone(),
+        };
+        upsert_global_preset(preset).await;
+    };
+    let import_from_global = move |_| {
+        let Some(preset) = selected_preset.peek().clone() else {
+            return;
+        };
+        let mut actions = actions.peek().clone();
+        actions.extend(preset.actions);
+        coroutine.send(ActionsUpdate::Update(actions));
+    };
+    let toggle_link = move |_| {
+        let Some(preset) = map_preset.peek().clone() else {
+            return;
+        };
+        let Some(global_preset) = selected_preset.peek().clone() else {
+            return;
+        };
+        let mut map = map.peek().clone();
+        let names = map.actions_global_presets.entry(preset.clone()).or_default();
+        if names.contains(&global_preset.name) {
+            names.retain(|name| name != &global_preset.name);
+        } else {
+            names.push(global_preset.name);
+        }
+        if names.is_empty() {
+            map.actions_global_presets.remove(&preset);
+        }
+        save_map(map);
+    };
+    let delete = move |_| async move {
+        let Some(preset) = selected_preset.peek().clone() else {
+            return;
+        };
+        if delete_global_preset(preset).await {
+            selected.set(0);
+        }
+    };
+
+    rsx! {
+        Section { title: "Global presets",
+            div { class: "flex items-end gap-2",
+                Labeled {
+                    class: "flex-grow",
+                    label: "Save as global preset",
+                    tooltip: "Saves this preset's actions as a global preset that any map can import or link. A global preset with the same name is overwritten.",
+                    TextInput {
+                        class: "h-6",
+                        disabled,
+                        placeholder: "Global preset name",
+                        on_value: move |value| {
+                            name.set(value);
+                        },
+                        value: name(),
+                    }
+                }
+                Button {
+                    class: "w-20 h-6",
+                    style: ButtonStyle::Primary,
+                    disabled: disabled || name().trim().is_empty(),
+                    on_click: save_as_global,
+                    "Save"
+                }
+            }
+            div { class: "grid grid-cols-4 gap-2 mt-2",
+                Labeled {
+                    class: "col-span-4",
+                    label: "Global preset",
+                    tooltip: "Importing copies the actions into this preset. Linking runs the global preset's actions together with this preset's actions and follows later changes to the global preset.",
+                    Select::<usize> {
+                        placeholder: "No global preset saved",
+                        disabled: disabled || global_presets_view().is_empty(),
+                        on_selected: move |index| {
+                            selected.set(index);
+                        },
+
+                        for (i , preset) in global_presets_view().into_iter().enumerate() {
+                            SelectOption::<usize> {
+                                value: i,
+                                selected: selected() == i,
+                                label: if linked().contains(&preset.name) {
+                                    format!("{} (linked)", preset.name)
+                                } else {
+                                    preset.name
+                                },
+                            }
+                        }
+                    }
+                }
+                Button {
+                    class: "col-span-2",
+                    style: ButtonStyle::Secondary,
+                    disabled: disabled || selected_preset().is_none(),
+                    on_click: import_from_global,
+                    "Import"
+                }
+                Button {
+                    style: ButtonStyle::Secondary,
+                    disabled: disabled || selected_preset().is_none(),
+                    on_click: toggle_link,
+                    if selected_linked() {
+                        "Unlink"
+                    } else {
+                        "Link"
+                    }
+                }
+                Button {
+                    style: ButtonStyle::Danger,
+                    disabled: selected_preset().is_none(),
+                    on_click: delete,
+                    "Delete"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionInputMacros() -> Element {
+    let input_macros = use_context::<ActionsContext>().input_macros;
+    let mut recording = use_signal(|| false);
+    let mut name = use_signal(String::default);
+    let mut selected = use_signal(|| 0usize);
+    let selected_macro = use_memo(move || input_macros().get(selected()).cloned());
+
+    let toggle_recording = move |_| async move {
+        if !*recording.peek() {
+            record_input_macro(true).await;
+            recording.set(true);
+            return;
+        }
+
+        let keys = record_input_macro(false).await;
+        recording.set(false);
+        let name = name.peek().trim().to_string();
+        // Overwrites the input macro with the same name
+        let id = input_macros
+            .peek()
+            .iter()
+            .find(|input_macro| input_macro.name == name)
+            .and_then(|input_macro| input_macro.id);
+        upsert_input_macro(InputMacro { id, name, keys }).await;
+    };
+    let delete = move |_| async move {
+        let Some(input_macro) = selected_macro.peek().clone() else {
+            return;
+        };
+        if delete_input_macro(input_macro).await {
+            selected.set(0);
+        }
+    };
+
+    rsx! {
+        Section { title: "Input macros",
+            div { class: "flex items-end gap-2",
+                Labeled {
+                    class: "flex-grow",
+                    label: "Record input macro",
+                    tooltip: "Records the keys pressed in game together with their timings until stopped. Actions can then replay the recorded keys. An input macro with the same name is overwritten. The key to toggle actions is not recorded.",
+                    TextInput {
+                        class: "h-6",
+                        disabled: recording(),
+                        placeholder: "Input macro name",
+                        on_value: move |value| {
+                            name.set(value);
+                        },
+                        value: name(),
+                    }
+                }
+                Button {
+                    class: "w-20 h-6",
+                    style: ButtonStyle::Primary,
+                    disabled: name().trim().is_empty(),
+                    on_click: toggle_recording,
+                    if recording() {
+                        "Stop"
+                    } else {
+                        "Record"
+                    }
+                }
+            }
+            div { class: "flex items-end gap-2 mt-2",
+                Labeled { class: "flex-grow", label: "Input macro",
+                    Select::<usize> {
+                        placeholder: "No input macro recorded",
+                        disabled: input_macros().is_empty(),
+                        on_selected: move |index| {
+                            selected.set(index);
+                        },
+
+                        for (i , input_macro) in input_macros().into_iter().enumerate() {
+                            SelectOption::<usize> {
+                                value: i,
+                                selected: selected() == i,
+                                label: format!(
+                                    "{} ({} keys)",
+                                    input_macro.name,
+                                    input_macro.keys.len(),
+                                ),
+                            }
+                        }
                     }
-                },
+                }
+                Button {
+                    class: "w-20 h-6",
+                    style: ButtonStyle::Danger,
+                    disabled: selected_macro().is_none(),
+                    on_click: delete,
+                    "Delete"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ActionsExternalFileInput(disabled: bool) -> Element {
+    let context = use_context::<ActionsContext>();
+    let map = context.map;
+    let save_map = context.save_map;
+    let map_preset = use_context::<AppState>().map_preset;
+    let external_file = use_memo(move || {
+        map_preset().and_then(|preset| map().actions_external_files.get(&preset).cloned())
+    });
+    let mut text = use_signal(String::default);
+    let mut error = use_signal(|| None::<String>);
+
+    use_effect(move || text.set(external_file().unwrap_or_default()));
+    use_future(move || async move {
+        let mut rx = game_state_receiver().await;
+        loop {
+            let state = match rx.recv().await {
+                Ok(value) => value,
+                Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(_)) => continue,
+            };
+            if *error.peek() != state.external_preset_error {
+                error.set(state.external_preset_error);
             }
         }
+    });
+
+    let save_external_file = move |path: Option<String>| {
+        let Some(preset) = map_preset.peek().clone() else {
+            return;
+        };
+        let mut map = map.peek().clone();
+        match path {
+            Some(path) => {
+                map.actions_external_files.insert(preset, path);
+            }
+            None => {
+                map.actions_external_files.remove(&preset);
+            }
+        }
+        save_map(map);
+    };
+
+    rsx! {
+        div { class: "flex items-end gap-2 mt-2",
+            Labeled {
+                class: "flex-grow",
+                label: "Watch external file",
+                tooltip: "Reloads this preset's actions from the exported JSON file whenever the file changes.",
+                TextInput {
+                    class: "h-6",
+                    disabled: disabled || external_file().is_some(),
+                    placeholder: "Path to an exported actions JSON file",
+                    on_value: move |path| {
+                        text.set(path);
+                    },
+                    value: text(),
+                }
+            }
+            if external_file().is_some() {
+                Button {
+                    class: "w-20 h-6",
+                    style: ButtonStyle::Primary,
+                    disabled,
+                    on_click: move |_| {
+                        save_external_file(None);
+                    },
+                    "Unwatch"
+                }
+            } else {
+                Button {
+                    class: "w-20 h-6",
+                    style: ButtonStyle::Primary,
+                    disabled: disabled || text().trim().is_empty(),
+                    on_click: move |_| {
+                        save_external_file(Some(text.peek().trim().to_string()));
+                    },
+                    "Watch"
+                }
+            }
+        }
+        if let Some(error) = error().filter(|_| external_file().is_some()) {
+            p { class: "text-xs text-danger-text mt-1", {error} }
+        }
     }
 }
 
@@ -1142,7 +2688,7 @@ fn PopupMobbingKeyInputContent(
                 on_cancel,
                 on_value: move |(action, _)| {
                     let action = match action {
-                        Action::Move(_) => unreachable!(),
+                        Action::Move(_) | Action::Macro(_) => unreachable!(),
                         Action::Key(action) => action,
                     };
                     let key = MobbingKey {
@@ -1178,6 +2724,7 @@ fn PopupActionInputContent(
         backend::ActionCondition::EveryMillis(_) => "every milliseconds",
         backend::ActionCondition::ErdaShowerOffCooldown => "Erda Shower off cooldown",
         backend::ActionCondition::Linked => "linked",
+        backend::ActionCondition::Expression(_) => "expression",
     };
     let title = if modifying {
         format!("Modify a {name} action")
@@ -1217,12 +2764,10 @@ fn ActionInput(
     value: ReadSignal<Action>,
 ) -> Element {
     let mut action = use_signal(&*value);
-    let button_text = use_memo(move || {
-        if matches!(action(), Action::Move(_)) {
-            "Switch to key"
-        } else {
-            "Switch to move"
-        }
+    let button_text = use_memo(move || match action() {
+        Action::Key(_) => "Switch to move",
+        Action::Move(_) => "Switch to macro",
+        Action::Macro(_) => "Switch to key",
     });
 
     use_effect(move || {
@@ -1238,24 +2783,26 @@ fn ActionInput(
                             style: ButtonStyle::Primary,
                             on_click: move |_| {
                                 let value = *value.peek();
-                                if discriminant(&value) != discriminant(&*action.peek()) {
+                                let condition = value.condition();
+                                let next = match *action.peek() {
+                                    Action::Key(_) => Action::Move(ActionMove {
+                                        condition,
+                                        ..ActionMove::default()
+                                    }),
+                                    Action::Move(_) => Action::Macro(ActionMacro {
+                                        condition,
+                                        ..ActionMacro::default()
+                                    }),
+                                    Action::Macro(_) => Action::Key(ActionKey {
+                                        condition,
+                                        ..ActionKey::default()
+                                    }),
+                                };
+                                // Restores the original action when cycling back to its kind
+                                if discriminant(&value) == discriminant(&next) {
                                     action.set(value);
-                                } else if matches!(value, Action::Move(_)) {
-                                    action
-                                        .set(
-                                            Action::Key(ActionKey {
-                                                condition: value.condition(),
-                                                ..ActionKey::default()
-                                            }),
-                                        );
                                 } else {
-                                    action
-                                        .set(
-                                            Action::Move(ActionMove {
-                                                condition: value.condition(),
-                                                ..ActionMove::default()
-                                            }),
-                                        );
+                                    action.set(next);
                                 }
                             },
                             class: "text-xxs",
@@ -1300,6 +2847,17 @@ fn ActionInput(
                         value: action,
                     }
                 },
+                Action::Macro(action) => rsx! {
+                    ActionMacroInput {
+                        modifying,
+                        linkable,
+                        on_cancel,
+                        on_value: move |(action, condition)| {
+                            on_value((Action::Macro(action), condition));
+                        },
+                        value: action,
+                    }
+                },
             }
         }
     }
@@ -1314,6 +2872,8 @@ fn ActionMoveInput(
     value: ReadSignal<ActionMove>,
 ) -> Element {
     let position = use_context::<AppState>().position;
+    let map = use_context::<ActionsContext>().map;
+    let weighted = use_memo(move || matches!(map().rotation_mode, RotationMode::WeightedRandom));
     let mut action = use_signal(&*value);
     let action_condition = value().condition;
 
@@ -1388,6 +2948,24 @@ fn ActionMoveInput(
                     checked: matches!(action().condition, ActionCondition::Linked),
                 }
             }
+            if weighted() && matches!(action().condition, ActionCondition::Any) {
+                ActionsNumberInputU32 {
+                    label: "Weight",
+                    on_value: move |weight| {
+                        let mut action = action.write();
+                        action.weight = Some(weight);
+                    },
+                    value: action().weight.unwrap_or(1),
+                    max_value: Action::MAX_WEIGHT,
+                }
+            }
+            ActionsTagsInput {
+                on_value: move |tags| {
+                    let mut action = action.write();
+                    action.tags = tags;
+                },
+                value: action().tags,
+            }
         }
         div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
             Button {
@@ -1426,6 +3004,8 @@ fn ActionKeyInput(
     value: ReadSignal<ActionKey>,
 ) -> Element {
     let position = use_context::<AppState>().position;
+    let map = use_context::<ActionsContext>().map;
+    let weighted = use_memo(move || matches!(map().rotation_mode, RotationMode::WeightedRandom));
     let mut action = use_signal(&*value);
     let action_condition = value().condition;
 
@@ -1621,7 +3201,9 @@ fn ActionKeyInput(
             }
             if matches!(
                 action().condition,
-                ActionCondition::EveryMillis(_) | ActionCondition::ErdaShowerOffCooldown
+                ActionCondition::EveryMillis(_)
+                    | ActionCondition::ErdaShowerOffCooldown
+                    | ActionCondition::Expression(_)
             )
             {
                 ActionsCheckbox {
@@ -1644,7 +3226,55 @@ fn ActionKeyInput(
                     },
                     value: millis,
                 }
-                div { class: "col-span-2" }
+                ActionsNumberInputU32 {
+                    label: "Max queued instances",
+                    on_value: move |instances| {
+                        let mut action = action.write();
+                        action.max_queued_instances = Some(instances);
+                    },
+                    value: action().max_queued_instances.unwrap_or(1),
+                }
+                div {} // Spacer
+            }
+            if let ActionCondition::Expression(expression) = action().condition {
+                Labeled {
+                    class: "col-span-2",
+                    label: "Use when",
+                    tooltip: "Queues the action whenever the expression is true, checked at most once per second. Supports health (in percentage), rune, strangers and buff(Kind) combined with comparisons, !, && and ||. For example: health < 50% && buff(Rune) == false.",
+                    TextInput {
+                        class: "h-6",
+                        placeholder: "health < 50% && buff(Rune) == false",
+                        max_length: ConditionExpression::MAX_LEN,
+                        on_value: move |text: String| {
+                            let mut action = action.write();
+                            action.condition = ActionCondition::Expression(
+                                ConditionExpression::new(&text).unwrap_or(expression),
+                            );
+                        },
+                        value: Some(expression.to_string()),
+                    }
+                }
+                ActionsNumberInputU32 {
+                    label: "Max queued instances",
+                    on_value: move |instances| {
+                        let mut action = action.write();
+                        action.max_queued_instances = Some(instances);
+                    },
+                    value: action().max_queued_instances.unwrap_or(1),
+                }
+            }
+            if weighted() && matches!(action().condition, ActionCondition::Any) {
+                ActionsNumberInputU32 {
+                    label: "Weight",
+                    on_value: move |weight| {
+                        let mut action = action.write();
+                        action.weight = Some(weight);
+                    },
+                    value: action().weight.unwrap_or(1),
+                    max_value: Action::MAX_WEIGHT,
+                }
+                div {} // Spacer
+                div {} // Spacer
             }
 
             // Wait before use
@@ -1694,6 +3324,160 @@ fn ActionKeyInput(
                     },
                     selected: action().wait_after_buffered,
                 }
+            } else {
+                div {} // Spacer
+            }
+
+            // Notify
+            ActionsSelect::<ActionNotify> {
+                label: "Notify on use",
+                tooltip: "Logs or plays a sound when this action starts executing. This can be useful for confirming that a rarely used action actually triggers.",
+                disabled: false,
+                on_selected: move |notify| {
+                    let mut action = action.write();
+                    action.notify = notify;
+                },
+                selected: action().notify,
+            }
+            ActionsTagsInput {
+                on_value: move |tags| {
+                    let mut action = action.write();
+                    action.tags = tags;
+                },
+                value: action().tags,
+            }
+        }
+        div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
+            Button {
+                class: "flex-grow",
+                style: ButtonStyle::OutlinePrimary,
+                on_click: move |_| {
+                    on_value((*action.peek(), action_condition));
+                },
+                if modifying {
+                    "Save"
+                } else {
+                    "Add"
+                }
+            }
+            Button {
+                class: "flex-grow",
+                style: ButtonStyle::OutlineSecondary,
+                on_click: move |_| {
+                    on_cancel(());
+                },
+                "Cancel"
+            }
+        }
+    }
+}
+
+#[component]
+fn ActionMacroInput(
+    modifying: bool,
+    linkable: bool,
+    on_cancel: Callback,
+    on_value: Callback<(ActionMacro, ActionCondition)>,
+    value: ReadSignal<ActionMacro>,
+) -> Element {
+    let input_macros = use_context::<ActionsContext>().input_macros;
+    let mut action = use_signal(&*value);
+    let action_condition = value().condition;
+
+    use_effect(move || {
+        action.set(value());
+    });
+
+    rsx! {
+        div { class: "grid grid-cols-3 gap-3",
+            Labeled {
+                class: "col-span-2",
+                label: "Input macro",
+                tooltip: "Replays the keys of an input macro with their recorded timings. Input macros can be recorded in the Input macros section.",
+                Select::<Option<i64>> {
+                    placeholder: "No input macro recorded",
+                    disabled: input_macros().is_empty(),
+                    on_selected: move |macro_id| {
+                        let mut action = action.write();
+                        action.macro_id = macro_id;
+                    },
+
+                    for input_macro in input_macros() {
+                        SelectOption::<Option<i64>> {
+                            value: input_macro.id,
+                            selected: action().macro_id == input_macro.id,
+                            label: input_macro.name,
+                        }
+                    }
+                }
+            }
+            if linkable {
+                ActionsCheckbox {
+                    label: "Linked action",
+                    on_checked: move |is_linked: bool| {
+                        let mut action = action.write();
+                        action.condition = if is_linked {
+                            ActionCondition::Linked
+                        } else {
+                            action_condition
+                        };
+                        action.queue_to_front = None;
+                    },
+                    checked: matches!(action().condition, ActionCondition::Linked),
+                }
+            } else {
+                div {} // Spacer
+            }
+            if matches!(
+                action().condition,
+                ActionCondition::EveryMillis(_)
+                    | ActionCondition::ErdaShowerOffCooldown
+                    | ActionCondition::Expression(_)
+            )
+            {
+                ActionsCheckbox {
+                    label: "Queue to front",
+                    on_checked: move |queue_to_front: bool| {
+                        let mut action = action.write();
+                        action.queue_to_front = Some(queue_to_front);
+                    },
+                    checked: action().queue_to_front.is_some(),
+                }
+            }
+            if let ActionCondition::EveryMillis(millis) = action().condition {
+                ActionsMillisInput {
+                    label: "Use every",
+                    on_value: move |millis| {
+                        let mut action = action.write();
+                        action.condition = ActionCondition::EveryMillis(millis);
+                    },
+                    value: millis,
+                }
+            }
+            if let ActionCondition::Expression(expression) = action().condition {
+                Labeled {
+                    class: "col-span-2",
+                    label: "Use when",
+                    TextInput {
+                        class: "h-6",
+                        placeholder: "health < 50% && buff(Rune) == false",
+                        max_length: ConditionExpression::MAX_LEN,
+                        on_value: move |text: String| {
+                            let mut action = action.write();
+                            action.condition = ActionCondition::Expression(
+                                ConditionExpression::new(&text).unwrap_or(expression),
+                            );
+                        },
+                        value: Some(expression.to_string()),
+                    }
+                }
+            }
+            ActionsTagsInput {
+                on_value: move |tags| {
+                    let mut action = action.write();
+                    action.tags = tags;
+                },
+                value: action().tags,
             }
         }
         div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
@@ -1729,6 +3513,7 @@ fn ActionList(
     on_item_delete: Callback<usize>,
     condition_filter: ActionCondition,
     disabled: bool,
+    #[props(default)] tag_filter: Option<String>,
     actions: Vec<Action>,
 ) -> Element {
     #[component]
@@ -1777,7 +3562,17 @@ fn ActionList(
         }
     }
 
-    let filtered = filter_actions(actions, condition_filter);
+    let mut filtered = filter_actions(actions, condition_filter);
+    if let Some(tag) = tag_filter {
+        // Linked actions are shown along with the non-linked action they are linked to
+        let mut head_matched = false;
+        filtered.retain(|(action, _)| {
+            if !matches!(action.condition(), ActionCondition::Linked) {
+                head_matched = action.tags().iter().any(|other| other == tag);
+            }
+            head_matched
+        });
+    }
 
     rsx! {
         div { class: "flex flex-col",
@@ -1797,6 +3592,9 @@ fn ActionList(
                             Action::Key(action) => rsx! {
                                 ActionKeyItem { action }
                             },
+                            Action::Macro(action) => rsx! {
+                                ActionMacroItem { action }
+                            },
                         }
                     }
 
@@ -1838,6 +3636,7 @@ fn ActionMoveItem(action: ActionMove) -> Element {
             },
         condition,
         wait_after_move_millis,
+        ..
     } = action;
 
     let x_min = (x - x_random_range).max(0);
@@ -1866,6 +3665,45 @@ fn ActionMoveItem(action: ActionMove) -> Element {
     }
 }
 
+#[component]
+fn ActionMacroItem(action: ActionMacro) -> Element {
+    let input_macros = use_context::<ActionsContext>().input_macros;
+    let ActionMacro {
+        macro_id,
+        condition,
+        queue_to_front,
+        ..
+    } = action;
+
+    let name = input_macros()
+        .into_iter()
+        .find(|input_macro| macro_id.is_some() && input_macro.id == macro_id)
+        .map(|input_macro| input_macro.name)
+        .unwrap_or_else(|| "Missing input macro".to_string());
+    let queue_to_front = if queue_to_front.unwrap_or_default() {
+        "⇈ / "
+    } else {
+        ""
+    };
+    let linked_action = if matches!(condition, ActionCondition::Linked) {
+        ""
+    } else {
+        "mt-2"
+    };
+    let millis = if let ActionCondition::EveryMillis(millis) = condition {
+        format!("⟳ {:.2}s", millis as f32 / 1000.0)
+    } else {
+        "".to_string()
+    };
+
+    rsx! {
+        div { class: "grid grid-cols-[140px_auto] h-6 text-xs text-secondary-text group-hover:bg-secondary-surface {linked_action}",
+            div { class: "{ITEM_BORDER_CLASS} {ITEM_TEXT_CLASS}", "{queue_to_front}Macro" }
+            div { class: "pl-1 pr-13 {ITEM_TEXT_CLASS}", "{name} {millis}" }
+        }
+    }
+}
+
 #[component]
 fn ActionKeyItem(action: ActionKey) -> Element {
     let ActionKey {
@@ -1991,6 +3829,26 @@ fn ActionsSelect<T: 'static + Clone + PartialEq + Display + IntoEnumIterator>(
     }
 }
 
+#[component]
+fn ActionsTagsInput(on_value: Callback<ActionTags>, value: ActionTags) -> Element {
+    rsx! {
+        Labeled {
+            class: "col-span-3",
+            label: "Tags",
+            tooltip: "Comma-separated tags such as buff, burst or travel. All actions with a tag can be disabled at once from the Tags section or while strangers are present. Linked actions use the tags of the action they are linked to.",
+            TextInput {
+                class: "h-6",
+                placeholder: "buff, burst",
+                max_length: ActionTags::MAX_LEN,
+                on_value: move |text: String| {
+                    on_value(ActionTags::new(&text).unwrap_or(value));
+                },
+                value: Some(value.to_string()),
+            }
+        }
+    }
+}
+
 #[component]
 fn ActionsPositionInput(
     label: &'static str,
@@ -2031,6 +3889,7 @@ fn ActionsNumberInputU32(
     #[props(default)] disabled: bool,
     on_value: Callback<u32>,
     value: u32,
+    #[props(default)] max_value: Option<u32>,
 ) -> Element {
     rsx! {
         Labeled { label,
@@ -2039,6 +3898,7 @@ fn ActionsNumberInputU32(
                 on_value,
                 value,
                 min_value: 1,
+                max_value,
             }
         }
     }
@@ -2145,6 +4005,33 @@ fn find_last_linked_action_index(
 ///
 /// Returns a [`Vec<(Action, usize)>`] where [`usize`] is the index of the action inside the
 /// original `actions`.
+fn platforms_mut(map: &mut Map, platform_set_index: Option<usize>) -> Option<&mut Vec<Platform>> {
+    match platform_set_index {
+        Some(index) => map.platform_sets.get_mut(index).map(|set| &mut set.platforms),
+        None => Some(&mut map.platforms),
+    }
+}
+
+fn channels_from_text(text: &str) -> Vec<u32> {
+    text.split(',')
+        .filter_map(|channel| channel.trim().parse::<u32>().ok())
+        .collect()
+}
+
+fn classes_from_text(text: &str) -> Vec<u32> {
+    text.split(',')
+        .filter_map(|class| class.trim().parse::<u32>().ok())
+        .collect()
+}
+
+fn tags_from_text(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 fn filter_actions(actions: Vec<Action>, condition_filter: ActionCondition) -> Vec<(Action, usize)> {
     let condition_filter = discriminant(&condition_filter);
     let mut filtered = Vec::with_capacity(actions.len());
@@ -2,9 +2,11 @@ use std::{fmt::Display, mem};
 
 use backend::{
     ActionConfiguration, ActionConfigurationCondition, ActionKeyWith, Character, EliteBossBehavior,
-    ExchangeHexaBoosterCondition, FamiliarRarity, Familiars, IntoEnumIterator, KeyBinding,
-    KeyBindingConfiguration, LinkKeyBinding, PotionMode, SwappableFamiliars, WaitAfterBuffered,
-    delete_character, query_characters, update_character, upsert_character,
+    EliteBossRotationKey, ExchangeHexaBoosterCondition, FamiliarRarity, Familiars, FamiliarsSetup,
+    IntoEnumIterator, KeyBinding, KeyBindingConfiguration, LinkKeyBinding, LowHealthAction,
+    LowHealthPolicy, PotionMode, PotionRestock, PotionStock, QuickSlotsSkill, SwappableFamiliars,
+    Upsert, WaitAfterBuffered, delete_character, query_characters, query_maps, scan_quick_slots,
+    update_character, upsert_character,
 };
 use dioxus::{html::FileData, prelude::*};
 use futures_util::StreamExt;
@@ -15,6 +17,7 @@ use crate::{
         ContentAlign, ContentSide,
         button::{Button, ButtonStyle},
         checkbox::Checkbox,
+        conflict::ConflictPopup,
         file::{FileInput, FileOutput},
         icons::XIcon,
         key::KeyInput,
@@ -24,6 +27,7 @@ use crate::{
         popup::{PopupContent, PopupContext, PopupTrigger},
         section::Section,
         select::{Select, SelectOption},
+        text::TextInput,
     },
 };
 
@@ -68,14 +72,24 @@ pub fn CharactersScreen() -> Element {
     });
     // Default character if `character` is `None`
     let character_view = use_memo(move || character().unwrap_or_default());
+    // The rejected character and the stored character when saving conflicts with a newer
+    // modification
+    let mut conflict = use_signal::<Option<(Character, Character)>>(|| None);
+    let conflict_open = use_memo(move || conflict().is_some());
 
     // Handles async operations for character-related
     let coroutine = use_coroutine(
         move |mut rx: UnboundedReceiver<CharactersUpdate>| async move {
             let mut save_character = async move |new_character: Character| {
-                if let Some(new_character) = upsert_character(new_character).await {
-                    character.set(Some(new_character));
-                    characters.restart();
+                match upsert_character(new_character.clone()).await {
+                    Upsert::Saved(new_character) => {
+                        character.set(Some(new_character));
+                        characters.restart();
+                    }
+                    Upsert::Conflict(stored_character) => {
+                        conflict.set(Some((new_character, stored_character)));
+                    }
+                    Upsert::Failed => (),
                 }
             };
 
@@ -146,12 +160,29 @@ pub fn CharactersScreen() -> Element {
             SectionKeyBindings {}
             SectionUsePotionAndFeedPet {}
             SectionUseBooster {}
+            SectionQuickSlots {}
             SectionMovement {}
             SectionFamiliars {}
             SectionBuffs {}
             SectionFixedActions {}
             SectionOthers {}
         }
+        ConflictPopup {
+            open: conflict_open,
+            name: "character",
+            on_overwrite: move |_| {
+                if let Some((mut new_character, stored_character)) = conflict.take() {
+                    new_character.revision = stored_character.revision;
+                    coroutine.send(CharactersUpdate::Update(new_character));
+                }
+            },
+            on_discard: move |_| {
+                if let Some((_, stored_character)) = conflict.take() {
+                    character.set(Some(stored_character));
+                    characters.restart();
+                }
+            },
+        }
 
         div { class: "flex items-center w-full h-10 bg-primary-surface absolute bottom-0 pr-2",
             NamedSelect {
@@ -266,6 +297,18 @@ fn SectionKeyBindings() -> Element {
                     },
                     value: character().cash_shop_key,
                 }
+                CharactersCheckbox {
+                    label: "Claim cash shop free items",
+                    tooltip: "Claims daily free items once a day when entering cash shop. Requires the cash shop templates in Localization tab.",
+                    checked: character().cash_shop_claim_freebies,
+                    on_checked: move |cash_shop_claim_freebies| {
+                        save_character(Character {
+                            cash_shop_claim_freebies,
+                            ..character.peek().clone()
+                        });
+                    },
+                    disabled: character().id.is_none() || character().cash_shop_key.is_none(),
+                }
                 CharactersKeyBindingConfigurationInput {
                     label: "To town",
                     optional: true,
@@ -315,6 +358,8 @@ fn SectionUsePotionAndFeedPet() -> Element {
         Section { title: "Use potion and feed pet",
             div { class: "flex flex-col gap-4",
                 UsePotion {}
+                PotionStockTracking {}
+                LowHealth {}
                 FeedPet {}
             }
         }
@@ -382,6 +427,255 @@ fn FeedPet() -> Element {
     }
 }
 
+#[component]
+fn PotionStockTracking() -> Element {
+    let context = use_context::<CharactersContext>();
+    let character = context.character;
+    let save_character = context.save_character;
+
+    let disabled = use_memo(move || character().id.is_none());
+    let maps = use_resource(async || query_maps().await.unwrap_or_default());
+
+    let save_restock = use_callback(move |restock: PotionRestock| {
+        let character = character.peek().clone();
+        save_character(Character {
+            potion_stock: PotionStock {
+                restock,
+                ..character.potion_stock
+            },
+            ..character
+        });
+    });
+
+    rsx! {
+        div { class: "grid grid-cols-3 gap-4",
+            CharactersCheckbox {
+                label: "Track potion stock",
+                tooltip: "Estimates the remaining potion count by counting potion key uses since the starting count and notifies when it is at or below the low count. Changing the starting count restarts counting.",
+                disabled,
+                on_checked: move |enabled| {
+                    let character = character.peek().clone();
+                    save_character(Character {
+                        potion_stock: PotionStock {
+                            enabled,
+                            ..character.potion_stock
+                        },
+                        ..character
+                    });
+                },
+                checked: character().potion_stock.enabled,
+            }
+            CharactersNumberU32Input {
+                label: "Starting count",
+                disabled: disabled(),
+                on_value: move |starting_count| {
+                    let character = character.peek().clone();
+                    save_character(Character {
+                        potion_stock: PotionStock {
+                            starting_count,
+                            ..character.potion_stock
+                        },
+                        ..character
+                    });
+                },
+                value: character().potion_stock.starting_count,
+            }
+            CharactersNumberU32Input {
+                label: "Low count",
+                disabled: disabled(),
+                on_value: move |low_count| {
+                    let character = character.peek().clone();
+                    save_character(Character {
+                        potion_stock: PotionStock {
+                            low_count,
+                            ..character.potion_stock
+                        },
+                        ..character
+                    });
+                },
+                value: character().potion_stock.low_count,
+            }
+            CharactersCheckbox {
+                label: "Verify with quick slots",
+                tooltip: "Periodically reads the potion item count in the quick slots to correct the estimate. Requires the quick slots potion template in localization.",
+                disabled,
+                on_checked: move |verify_with_quick_slots| {
+                    let character = character.peek().clone();
+                    save_character(Character {
+                        potion_stock: PotionStock {
+                            verify_with_quick_slots,
+                            ..character.potion_stock
+                        },
+                        ..character
+                    });
+                },
+                checked: character().potion_stock.verify_with_quick_slots,
+            }
+            CharactersCheckbox {
+                label: "Stop when low",
+                disabled,
+                on_checked: move |halt_on_low| {
+                    let character = character.peek().clone();
+                    save_character(Character {
+                        potion_stock: PotionStock {
+                            halt_on_low,
+                            ..character.potion_stock
+                        },
+                        ..character
+                    });
+                },
+                checked: character().potion_stock.halt_on_low,
+            }
+            CharactersCheckbox {
+                label: "Restock when low",
+                tooltip: "Travels to the shop map, talks to the NPC at the position and buys potions when low. Requires the shop potion template in localization and navigation paths to the shop map.",
+                disabled,
+                on_checked: move |enabled| {
+                    save_restock(PotionRestock {
+                        enabled,
+                        ..character.peek().potion_stock.restock
+                    });
+                },
+                checked: character().potion_stock.restock.enabled,
+            }
+            Labeled { label: "Shop map",
+                Select::<Option<i64>> {
+                    disabled: disabled(),
+                    on_selected: move |map_id| {
+                        save_restock(PotionRestock {
+                            map_id,
+                            ..character.peek().potion_stock.restock
+                        });
+                    },
+                    SelectOption::<Option<i64>> {
+                        value: None,
+                        label: "None",
+                        selected: character().potion_stock.restock.map_id.is_none(),
+                    }
+                    for map in maps().unwrap_or_default() {
+                        SelectOption::<Option<i64>> {
+                            value: map.id,
+                            selected: character().potion_stock.restock.map_id == map.id,
+                            label: map.name,
+                        }
+                    }
+                }
+            }
+            CharactersNumberU32Input {
+                label: "Restock quantity",
+                max_value: 9999,
+                disabled: disabled(),
+                on_value: move |quantity| {
+                    save_restock(PotionRestock {
+                        quantity,
+                        ..character.peek().potion_stock.restock
+                    });
+                },
+                value: character().potion_stock.restock.quantity,
+            }
+            Labeled { label: "Shop NPC x",
+                PrimitiveIntegerInput {
+                    value: character().potion_stock.restock.npc_x,
+                    on_value: move |npc_x| {
+                        save_restock(PotionRestock {
+                            npc_x,
+                            ..character.peek().potion_stock.restock
+                        });
+                    },
+                    disabled: disabled(),
+                }
+            }
+            Labeled { label: "Shop NPC y",
+                PrimitiveIntegerInput {
+                    value: character().potion_stock.restock.npc_y,
+                    on_value: move |npc_y| {
+                        save_restock(PotionRestock {
+                            npc_y,
+                            ..character.peek().potion_stock.restock
+                        });
+                    },
+                    disabled: disabled(),
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn LowHealth() -> Element {
+    let context = use_context::<CharactersContext>();
+    let character = context.character;
+    let save_character = context.save_character;
+
+    let disabled = use_memo(move || character().id.is_none());
+
+    rsx! {
+        div { class: "grid grid-cols-3 gap-4",
+            CharactersCheckbox {
+                label: "Low health policy",
+                tooltip: "Executes the selected action and stops the bot when health stays below the critical percentage for the provided duration, such as when potions have run out.",
+                disabled,
+                on_checked: move |enabled| {
+                    let character = character.peek().clone();
+                    save_character(Character {
+                        low_health_policy: LowHealthPolicy {
+                            enabled,
+                            ..character.low_health_policy
+                        },
+                        ..character
+                    });
+                },
+                checked: character().low_health_policy.enabled,
+            }
+            CharactersPercentageInput {
+                label: "Below health",
+                disabled: disabled(),
+                on_value: move |percent| {
+                    let character = character.peek().clone();
+                    save_character(Character {
+                        low_health_policy: LowHealthPolicy {
+                            critical_percent: percent as f32,
+                            ..character.low_health_policy
+                        },
+                        ..character
+                    });
+                },
+                value: character().low_health_policy.critical_percent as u32,
+            }
+            CharactersMillisInput {
+                label: "For",
+                disabled: disabled(),
+                on_value: move |duration_millis| {
+                    let character = character.peek().clone();
+                    save_character(Character {
+                        low_health_policy: LowHealthPolicy {
+                            duration_millis,
+                            ..character.low_health_policy
+                        },
+                        ..character
+                    });
+                },
+                value: character().low_health_policy.duration_millis,
+            }
+            CharactersSelect::<LowHealthAction> {
+                label: "Action",
+                disabled,
+                on_selected: move |action| {
+                    let character = character.peek().clone();
+                    save_character(Character {
+                        low_health_policy: LowHealthPolicy {
+                            action,
+                            ..character.low_health_policy
+                        },
+                        ..character
+                    });
+                },
+                selected: character().low_health_policy.action,
+            }
+        }
+    }
+}
+
 #[component]
 fn UsePotion() -> Element {
     let context = use_context::<CharactersContext>();
@@ -469,6 +763,66 @@ fn UsePotion() -> Element {
                     }
                 },
             }
+            CharactersKeyBindingConfigurationInput {
+                label: "Mana potion key",
+                label_class: "col-span-2",
+                disabled: character().id.is_none(),
+                on_value: move |key_config: Option<KeyBindingConfiguration>| {
+                    save_character(Character {
+                        mana_potion_key: key_config.expect("not optional"),
+                        ..character.peek().clone()
+                    });
+                },
+                value: character().mana_potion_key,
+            }
+            CharactersCheckbox {
+                label: "Enabled",
+                disabled: character().id.is_none(),
+                on_checked: move |enabled| {
+                    let character = character.peek().clone();
+                    save_character(Character {
+                        mana_potion_key: KeyBindingConfiguration {
+                            enabled,
+                            ..character.mana_potion_key
+                        },
+                        ..character
+                    });
+                },
+                checked: character().mana_potion_key.enabled,
+            }
+            CharactersPercentageInput {
+                label: "Below mana",
+                disabled: character().id.is_none(),
+                on_value: move |percent| {
+                    save_character(Character {
+                        mana_potion_percent: percent as f32,
+                        ..character.peek().clone()
+                    });
+                },
+                value: character().mana_potion_percent as u32,
+            }
+            CharactersMillisInput {
+                label: "Potion cooldown",
+                disabled: character().id.is_none(),
+                on_value: move |millis| {
+                    save_character(Character {
+                        potion_cooldown_millis: millis,
+                        ..character.peek().clone()
+                    });
+                },
+                value: character().potion_cooldown_millis,
+            }
+            CharactersCheckbox {
+                label: "Pet auto potion",
+                disabled: character().id.is_none(),
+                on_checked: move |pet_auto_potion| {
+                    save_character(Character {
+                        pet_auto_potion,
+                        ..character.peek().clone()
+                    });
+                },
+                checked: character().pet_auto_potion,
+            }
         }
     }
 }
@@ -576,6 +930,51 @@ fn SectionUseBooster() -> Element {
     }
 }
 
+#[component]
+fn SectionQuickSlots() -> Element {
+    let context = use_context::<CharactersContext>();
+    let character = context.character;
+    let mut skills = use_signal(|| None::<Vec<QuickSlotsSkill>>);
+
+    rsx! {
+        Section { title: "Quick slots",
+            div { class: "flex flex-col gap-2 text-xs text-primary-text",
+                p { class: "text-secondary-text",
+                    "Scans the quick slots of the current frame for known skill icons and checks them against the key bindings of this character and the current map preset's actions."
+                }
+                if let Some(skills) = skills() {
+                    for skill in skills {
+                        p {
+                            class: if skill.is_mismatched() { "text-danger-text" },
+                            {quick_slots_skill_status(&skill)}
+                        }
+                    }
+                }
+                Button {
+                    class: "w-full",
+                    style: ButtonStyle::Primary,
+                    on_click: move |_| async move {
+                        skills.set(scan_quick_slots().await);
+                    },
+                    disabled: character().id.is_none(),
+                    "Scan quick slots"
+                }
+            }
+        }
+    }
+}
+
+fn quick_slots_skill_status(skill: &QuickSlotsSkill) -> String {
+    let status = match (skill.found, skill.bound) {
+        (true, true) => "found and bound",
+        (true, false) => "found but no key or action bound",
+        (false, true) => "bound but not found in quick slots",
+        (false, false) => "not found and not bound",
+    };
+
+    format!("{}: {status}", skill.name)
+}
+
 #[component]
 fn SectionMovement() -> Element {
     let context = use_context::<CharactersContext>();
@@ -656,98 +1055,156 @@ fn SectionFamiliars() -> Element {
     let context = use_context::<CharactersContext>();
     let character = context.character;
     let save_character = context.save_character;
-    let familiars = use_memo(move || character().familiars);
+    let familiars_setups = use_memo(move || character().familiars_setups);
+    let mut setup_name = use_signal(String::default);
+
+    let save_familiars_setups = use_callback(move |familiars_setups: Vec<FamiliarsSetup>| {
+        save_character(Character {
+            familiars_setups,
+            ..character.peek().clone()
+        });
+    });
 
     rsx! {
         Section { title: "Familiars",
-            CharactersCheckbox {
-                label: "Enable swapping",
-                on_checked: move |enable_familiars_swapping| {
+            FamiliarsInput {
+                on_value: move |familiars| {
                     save_character(Character {
-                        familiars: Familiars {
-                            enable_familiars_swapping,
-                            ..familiars.peek().clone()
-                        },
+                        familiars,
                         ..character.peek().clone()
                     });
                 },
-                checked: familiars().enable_familiars_swapping,
+                value: character().familiars,
             }
-            div { class: "grid grid-cols-2 gap-3 mt-2",
-                CharactersSelect::<SwappableFamiliars> {
-                    label: "Swappable slots",
-                    disabled: !familiars().enable_familiars_swapping,
-                    on_selected: move |swappable_familiars| async move {
-                        save_character(Character {
-                            familiars: Familiars {
-                                swappable_familiars,
-                                ..familiars.peek().clone()
-                            },
-                            ..character.peek().clone()
-                        });
-                    },
-                    selected: familiars().swappable_familiars,
+        }
+        Section { title: "Familiars setups",
+            for (index , setup) in familiars_setups().into_iter().enumerate() {
+                div { class: "flex items-center justify-between mt-2",
+                    div { class: "text-xs text-primary-text", {setup.name} }
+                    Button {
+                        style: ButtonStyle::Danger,
+                        on_click: move |_| {
+                            let mut setups = familiars_setups.peek().clone();
+                            setups.remove(index);
+                            save_familiars_setups(setups);
+                        },
+                        "Delete"
+                    }
                 }
-                CharactersMillisInput {
-                    label: "Swap check every",
-                    disabled: !familiars().enable_familiars_swapping,
-                    on_value: move |swap_check_millis| {
-                        save_character(Character {
-                            familiars: Familiars {
-                                swap_check_millis,
-                                ..familiars.peek().clone()
-                            },
-                            ..character.peek().clone()
-                        });
+                FamiliarsInput {
+                    on_value: move |familiars| {
+                        let mut setups = familiars_setups.peek().clone();
+                        setups[index].familiars = familiars;
+                        save_familiars_setups(setups);
                     },
-                    value: familiars().swap_check_millis,
+                    value: setup.familiars,
                 }
-
-                CharactersCheckbox {
-                    label: "Can swap rare familiars",
-                    disabled: !familiars().enable_familiars_swapping,
-                    on_checked: move |allowed| {
-                        let mut rarities = familiars.peek().swappable_rarities.clone();
-                        if allowed {
-                            rarities.insert(FamiliarRarity::Rare);
-                        } else {
-                            rarities.remove(&FamiliarRarity::Rare);
-                        }
-                        save_character(Character {
-                            familiars: Familiars {
-                                swappable_rarities: rarities,
-                                ..familiars.peek().clone()
-                            },
-                            ..character.peek().clone()
-                        });
-                    },
-                    checked: familiars().swappable_rarities.contains(&FamiliarRarity::Rare),
+            }
+            div { class: "flex items-end gap-3 mt-2",
+                Labeled {
+                    class: "flex-grow",
+                    label: "New setup name",
+                    tooltip: "Map presets can switch to a named setup instead of the familiars configuration above.",
+                    TextInput {
+                        class: "h-6",
+                        on_value: move |name| {
+                            setup_name.set(name);
+                        },
+                        value: setup_name(),
+                    }
                 }
-                CharactersCheckbox {
-                    label: "Can swap epic familiars",
-                    disabled: !familiars().enable_familiars_swapping,
-                    on_checked: move |allowed| {
-                        let mut rarities = familiars.peek().swappable_rarities.clone();
-                        if allowed {
-                            rarities.insert(FamiliarRarity::Epic);
-                        } else {
-                            rarities.remove(&FamiliarRarity::Epic);
-                        }
-                        save_character(Character {
-                            familiars: Familiars {
-                                swappable_rarities: rarities,
-                                ..familiars.peek().clone()
-                            },
-                            ..character.peek().clone()
+                Button {
+                    style: ButtonStyle::Secondary,
+                    disabled: setup_name().trim().is_empty()
+                        || familiars_setups()
+                            .iter()
+                            .any(|setup| setup.name == setup_name().trim()),
+                    on_click: move |_| {
+                        let mut setups = familiars_setups.peek().clone();
+                        setups.push(FamiliarsSetup {
+                            name: setup_name.peek().trim().to_string(),
+                            familiars: Familiars::default(),
                         });
+                        save_familiars_setups(setups);
+                        setup_name.set(String::default());
                     },
-                    checked: familiars().swappable_rarities.contains(&FamiliarRarity::Epic),
+                    "Add setup"
                 }
             }
         }
     }
 }
 
+#[component]
+fn FamiliarsInput(on_value: Callback<Familiars>, value: ReadSignal<Familiars>) -> Element {
+    let save_rarity = use_callback(move |(rarity, allowed): (FamiliarRarity, bool)| {
+        let mut swappable_rarities = value.peek().swappable_rarities.clone();
+        if allowed {
+            swappable_rarities.insert(rarity);
+        } else {
+            swappable_rarities.remove(&rarity);
+        }
+        on_value(Familiars {
+            swappable_rarities,
+            ..value.peek().clone()
+        });
+    });
+
+    rsx! {
+        CharactersCheckbox {
+            label: "Enable swapping",
+            on_checked: move |enable_familiars_swapping| {
+                on_value(Familiars {
+                    enable_familiars_swapping,
+                    ..value.peek().clone()
+                });
+            },
+            checked: value().enable_familiars_swapping,
+        }
+        div { class: "grid grid-cols-2 gap-3 mt-2",
+            CharactersSelect::<SwappableFamiliars> {
+                label: "Swappable slots",
+                disabled: !value().enable_familiars_swapping,
+                on_selected: move |swappable_familiars| async move {
+                    on_value(Familiars {
+                        swappable_familiars,
+                        ..value.peek().clone()
+                    });
+                },
+                selected: value().swappable_familiars,
+            }
+            CharactersMillisInput {
+                label: "Swap check every",
+                disabled: !value().enable_familiars_swapping,
+                on_value: move |swap_check_millis| {
+                    on_value(Familiars {
+                        swap_check_millis,
+                        ..value.peek().clone()
+                    });
+                },
+                value: value().swap_check_millis,
+            }
+
+            CharactersCheckbox {
+                label: "Can swap rare familiars",
+                disabled: !value().enable_familiars_swapping,
+                on_checked: move |allowed| {
+                    save_rarity((FamiliarRarity::Rare, allowed));
+                },
+                checked: value().swappable_rarities.contains(&FamiliarRarity::Rare),
+            }
+            CharactersCheckbox {
+                label: "Can swap epic familiars",
+                disabled: !value().enable_familiars_swapping,
+                on_checked: move |allowed| {
+                    save_rarity((FamiliarRarity::Epic, allowed));
+                },
+                checked: value().swappable_rarities.contains(&FamiliarRarity::Epic),
+            }
+        }
+    }
+}
+
 #[component]
 fn SectionBuffs() -> Element {
     #[component]
@@ -1104,6 +1561,15 @@ fn SectionOthers() -> Element {
     });
 
     let disabled = use_memo(move || character().id.is_none());
+    let elite_boss_rotation = use_memo(move || character().elite_boss_rotation);
+
+    let save_elite_boss_rotation =
+        use_callback(move |elite_boss_rotation: Vec<EliteBossRotationKey>| {
+            save_character(Character {
+                elite_boss_rotation,
+                ..character.peek().clone()
+            });
+        });
 
     rsx! {
         Section { title: "Others",
@@ -1144,6 +1610,67 @@ fn SectionOthers() -> Element {
                     value: Some(character().elite_boss_behavior_key),
                 }
                 div {}
+                for (index , key) in elite_boss_rotation().into_iter().enumerate() {
+                    CharactersKeyInput {
+                        label: format!("Rotation key #{}", index + 1),
+                        disabled,
+                        on_value: move |value: Option<KeyBinding>| {
+                            let mut rotation = elite_boss_rotation.peek().clone();
+                            rotation[index].key = value.expect("not optional");
+                            save_elite_boss_rotation(rotation);
+                        },
+                        value: Some(key.key),
+                    }
+                    CharactersMillisInput {
+                        label: "Cooldown",
+                        disabled: disabled(),
+                        on_value: move |cooldown_millis| {
+                            let mut rotation = elite_boss_rotation.peek().clone();
+                            rotation[index].cooldown_millis = cooldown_millis;
+                            save_elite_boss_rotation(rotation);
+                        },
+                        value: key.cooldown_millis,
+                    }
+                    div { class: "flex items-end",
+                        Button {
+                            class: "w-full",
+                            style: ButtonStyle::Danger,
+                            disabled,
+                            on_click: move |_| {
+                                let mut rotation = elite_boss_rotation.peek().clone();
+                                rotation.remove(index);
+                                save_elite_boss_rotation(rotation);
+                            },
+                            "Delete"
+                        }
+                    }
+                }
+                CharactersCheckbox {
+                    label: "Move toward elite boss",
+                    tooltip: "When the behavior is rotation, moves toward the largest mob on screen before using the rotation keys.",
+                    disabled,
+                    on_checked: move |elite_boss_rotation_move_to_boss| {
+                        save_character(Character {
+                            elite_boss_rotation_move_to_boss,
+                            ..character.peek().clone()
+                        });
+                    },
+                    checked: character().elite_boss_rotation_move_to_boss,
+                }
+                div {}
+                div { class: "flex items-end",
+                    Button {
+                        class: "w-full",
+                        style: ButtonStyle::Secondary,
+                        disabled,
+                        on_click: move |_| {
+                            let mut rotation = elite_boss_rotation.peek().clone();
+                            rotation.push(EliteBossRotationKey::default());
+                            save_elite_boss_rotation(rotation);
+                        },
+                        "Add rotation key"
+                    }
+                }
                 div { class: "flex gap-2 col-span-3",
                     FileInput {
                         on_file: move |file| async move {
@@ -0,0 +1,41 @@
+use dioxus::prelude::*;
+
+use crate::components::{
+    button::{Button, ButtonStyle},
+    popup::{PopupContent, PopupContext},
+};
+
+/// A popup prompting whether to overwrite or discard changes that conflict with a newer save.
+#[component]
+pub fn ConflictPopup(
+    open: ReadSignal<bool>,
+    name: &'static str,
+    on_overwrite: Callback,
+    on_discard: Callback,
+) -> Element {
+    rsx! {
+        PopupContext { open,
+            PopupContent { title: "Conflicting changes",
+                div { class: "flex flex-col w-xs pb-12 text-xs text-primary-text",
+                    p {
+                        "The {name} was modified elsewhere after it was loaded. Overwrite with your changes or discard them to load the modified {name}."
+                    }
+                }
+                div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
+                    Button {
+                        class: "flex-grow",
+                        style: ButtonStyle::OutlinePrimary,
+                        on_click: on_overwrite,
+                        "Overwrite"
+                    }
+                    Button {
+                        class: "flex-grow",
+                        style: ButtonStyle::OutlineSecondary,
+                        on_click: on_discard,
+                        "Discard"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::rc::Rc;
 
 use backend::KeyBinding;
-use dioxus::prelude::*;
+use dioxus::{html::input_data::MouseButton, prelude::*};
 use tw_merge::tw_merge;
 
 use crate::components::{icons::XIcon, use_controlled};
@@ -50,9 +50,8 @@ pub fn KeyInput(props: KeyInputProps) -> Element {
         error.set(false);
     };
 
-    let handle_key_down = move |e: Event<KeyboardData>| async move {
-        e.prevent_default();
-        if let Some(key) = map_key(e.key()) {
+    let handle_key = move |key: Option<KeyBinding>| async move {
+        if let Some(key) = key {
             if let Some(input) = input().as_ref() {
                 let _ = input.set_focus(false).await;
             }
@@ -65,6 +64,25 @@ pub fn KeyInput(props: KeyInputProps) -> Element {
         }
     };
 
+    let handle_key_down = move |e: Event<KeyboardData>| async move {
+        e.prevent_default();
+        handle_key(map_code(e.code()).or_else(|| map_key(e.key()))).await;
+    };
+
+    let handle_mouse_down = move |e: Event<MouseData>| async move {
+        if !active() {
+            return;
+        }
+        let key = match e.trigger_button() {
+            Some(MouseButton::Fourth) => KeyBinding::Mouse4,
+            Some(MouseButton::Fifth) => KeyBinding::Mouse5,
+            _ => return,
+        };
+
+        e.prevent_default();
+        handle_key(Some(key)).await;
+    };
+
     rsx! {
         div { class: tw_merge!(DIV_CLASS, class),
             input {
@@ -78,6 +96,7 @@ pub fn KeyInput(props: KeyInputProps) -> Element {
                 onfocus: handle_focus,
                 onblur: handle_blur,
                 onkeydown: handle_key_down,
+                onmousedown: handle_mouse_down,
                 placeholder: "Click to set",
                 value: value().map(|key| key.to_string()),
             }
@@ -102,6 +121,28 @@ pub fn KeyInput(props: KeyInputProps) -> Element {
     }
 }
 
+/// Maps keys that share [`Key`] with other keys and can only be told apart by `code`.
+fn map_code(code: Code) -> Option<KeyBinding> {
+    Some(match code {
+        Code::Numpad0 => KeyBinding::NumpadZero,
+        Code::Numpad1 => KeyBinding::NumpadOne,
+        Code::Numpad2 => KeyBinding::NumpadTwo,
+        Code::Numpad3 => KeyBinding::NumpadThree,
+        Code::Numpad4 => KeyBinding::NumpadFour,
+        Code::Numpad5 => KeyBinding::NumpadFive,
+        Code::Numpad6 => KeyBinding::NumpadSix,
+        Code::Numpad7 => KeyBinding::NumpadSeven,
+        Code::Numpad8 => KeyBinding::NumpadEight,
+        Code::Numpad9 => KeyBinding::NumpadNine,
+        Code::NumpadAdd => KeyBinding::NumpadAdd,
+        Code::NumpadSubtract => KeyBinding::NumpadSubtract,
+        Code::NumpadMultiply => KeyBinding::NumpadMultiply,
+        Code::NumpadDivide => KeyBinding::NumpadDivide,
+        Code::NumpadDecimal => KeyBinding::NumpadDecimal,
+        _ => return None,
+    })
+}
+
 fn map_key(key: Key) -> Option<KeyBinding> {
     Some(match key {
         Key::Character(s) => match s.to_lowercase().as_str() {
@@ -162,6 +203,18 @@ fn map_key(key: Key) -> Option<KeyBinding> {
         Key::F10 => KeyBinding::F10,
         Key::F11 => KeyBinding::F11,
         Key::F12 => KeyBinding::F12,
+        Key::F13 => KeyBinding::F13,
+        Key::F14 => KeyBinding::F14,
+        Key::F15 => KeyBinding::F15,
+        Key::F16 => KeyBinding::F16,
+        Key::F17 => KeyBinding::F17,
+        Key::F18 => KeyBinding::F18,
+        Key::F19 => KeyBinding::F19,
+        Key::F20 => KeyBinding::F20,
+        Key::F21 => KeyBinding::F21,
+        Key::F22 => KeyBinding::F22,
+        Key::F23 => KeyBinding::F23,
+        Key::F24 => KeyBinding::F24,
         Key::ArrowUp => KeyBinding::Up,
         Key::ArrowLeft => KeyBinding::Left,
         Key::ArrowRight => KeyBinding::Right,
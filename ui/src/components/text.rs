@@ -16,6 +16,9 @@ pub struct TextInputProps {
     placeholder: Option<String>,
     #[props(default)]
     class: String,
+    /// The maximum number of characters the input accepts.
+    #[props(default)]
+    max_length: Option<usize>,
 }
 
 #[component]
@@ -28,6 +31,7 @@ pub fn TextInput(props: TextInputProps) -> Element {
     let disabled = props.disabled;
     let sensitive = props.sensitive;
     let class = props.class;
+    let max_length = props.max_length.map(|len| len as i64);
     let (value, set_value) = use_controlled(props.value, String::default(), props.on_value);
 
     rsx! {
@@ -36,6 +40,7 @@ pub fn TextInput(props: TextInputProps) -> Element {
                 class: INPUT_CLASS,
                 disabled,
                 placeholder,
+                maxlength: max_length,
                 r#type: if sensitive { "password" } else { "text" },
                 oninput: move |e| {
                     set_value(e.parsed::<String>().unwrap());
@@ -1,15 +1,50 @@
+use std::time::Duration;
+
 use backend::{
-    DebugState, auto_save_rune, debug_state_receiver, infer_minimap, infer_rune, record_images,
-    test_spin_rune,
+    Bound, DebugState, DetectionBenchmark, FrameTimings, IntoEnumIterator, MobFrame,
+    MouseAlignmentPreview, ThresholdPreview, TunableDetection, benchmark_detection,
+    capture_mob_frame, debug_state_receiver, game_state_receiver, infer_minimap, infer_rune,
+    record_images, save_mob_labels, test_mouse_alignment, test_spin_rune, tune_detection_threshold,
+    upsert_localization,
 };
 use dioxus::prelude::*;
-use tokio::sync::broadcast::error::RecvError;
+use tokio::{sync::broadcast::error::RecvError, time::sleep};
 
-use crate::components::{
-    button::{Button, ButtonStyle},
-    section::Section,
+use crate::{
+    AppState,
+    components::{
+        button::{Button, ButtonStyle},
+        labeled::Labeled,
+        section::Section,
+        select::{Select, SelectOption},
+    },
 };
 
+/// The minimum width and height in pixels of a drawn mob box.
+const MIN_MOB_SIZE: i32 = 4;
+
+/// How often the threshold preview re-runs the detection.
+const THRESHOLD_PREVIEW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often the mouse alignment test refreshes the frame.
+const MOUSE_ALIGNMENT_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The size in pixels of the mouse alignment marker.
+const MOUSE_ALIGNMENT_MARKER_SIZE: i32 = 16;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum MobLabelKind {
+    Detected,
+    FalsePositive,
+    FalseNegative,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct MobLabel {
+    bound: Bound,
+    kind: MobLabelKind,
+}
+
 #[component]
 pub fn DebugScreen() -> Element {
     let mut state = use_signal(DebugState::default);
@@ -68,16 +103,531 @@ pub fn DebugScreen() -> Element {
                             "Start recording"
                         }
                     }
+                }
+            }
+            SectionMobLabeler {}
+            SectionThresholdTuner {}
+            SectionDetectionBenchmark {}
+            SectionMouseAlignment {}
+            SectionFrameTimings {}
+            SectionPriorityActionsDropped {}
+            Section { title: "Memory",
+                div { class: "grid grid-cols-2 gap-2",
+                    p { class: "text-sm text-primary-text font-mono", "Localization templates" }
+                    p { class: "text-sm text-primary-text text-right font-mono",
+                        {memory_usage(state().templates_memory)}
+                    }
+                    p { class: "text-sm text-primary-text font-mono", "Notification frames" }
+                    p { class: "text-sm text-primary-text text-right font-mono",
+                        {memory_usage(state().notification_frames_memory)}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionFrameTimings() -> Element {
+    let mut timings = use_signal(FrameTimings::default);
+
+    use_future(move || async move {
+        let mut rx = game_state_receiver().await;
+        loop {
+            let state = match rx.recv().await {
+                Ok(state) => state,
+                Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(_)) => continue,
+            };
+            if state.frame_timings != *timings.peek() {
+                timings.set(state.frame_timings);
+            }
+        }
+    });
+
+    rsx! {
+        Section { title: "Frame timings",
+            div { class: "grid grid-cols-2 gap-2",
+                p { class: "text-sm text-primary-text font-mono", "Processed FPS" }
+                p { class: "text-sm text-primary-text text-right font-mono", "{timings().fps}" }
+                p { class: "text-sm text-primary-text font-mono", "Captured FPS" }
+                p { class: "text-sm text-primary-text text-right font-mono",
+                    "{timings().capture_fps}"
+                }
+                p { class: "text-sm text-primary-text font-mono", "Skipped frames per second" }
+                p { class: "text-sm text-primary-text text-right font-mono",
+                    "{timings().skipped_frames}"
+                }
+                p { class: "text-sm text-primary-text font-mono", "Capture to detection" }
+                p { class: "text-sm text-primary-text text-right font-mono",
+                    {format!("{:.2} ms", timings().capture_to_detection.as_secs_f64() * 1000.0)}
+                }
+                p { class: "text-sm text-primary-text font-mono", "Capture to dispatch" }
+                p { class: "text-sm text-primary-text text-right font-mono",
+                    {format!("{:.2} ms", timings().capture_to_dispatch.as_secs_f64() * 1000.0)}
+                }
+                for (stage , duration) in timings().stages {
+                    p { class: "text-sm text-primary-text font-mono", "{stage}" }
+                    p { class: "text-sm text-primary-text text-right font-mono",
+                        {format!("{:.2} ms", duration.as_secs_f64() * 1000.0)}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionPriorityActionsDropped() -> Element {
+    let mut dropped = use_signal(Vec::<(String, u32)>::new);
+
+    use_future(move || async move {
+        let mut rx = game_state_receiver().await;
+        loop {
+            let state = match rx.recv().await {
+                Ok(state) => state,
+                Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(_)) => continue,
+            };
+            if state.priority_actions_dropped != *dropped.peek() {
+                dropped.set(state.priority_actions_dropped);
+            }
+        }
+    });
+    let total = use_memo(move || dropped().iter().map(|(_, count)| *count).sum::<u32>());
+
+    rsx! {
+        Section { title: "Dropped priority actions",
+            div { class: "grid grid-cols-2 gap-2",
+                p { class: "text-sm text-primary-text font-mono", "Total" }
+                p { class: "text-sm text-primary-text text-right font-mono", "{total()}" }
+                for (name , count) in dropped() {
+                    p { class: "text-sm text-primary-text font-mono", "{name}" }
+                    p { class: "text-sm text-primary-text text-right font-mono", "{count}" }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionDetectionBenchmark() -> Element {
+    let mut benchmark = use_signal(|| None::<DetectionBenchmark>);
+    let mut running = use_signal(|| false);
+    let opencl_text = use_memo(move || {
+        benchmark()
+            .and_then(|benchmark| benchmark.opencl_millis)
+            .map_or("Unavailable".to_string(), |millis| format!("{millis:.2} ms"))
+    });
+
+    rsx! {
+        Section { title: "Detection benchmark",
+            div { class: "grid grid-cols-2 gap-2",
+                Button {
+                    class: "col-span-2",
+                    style: ButtonStyle::Secondary,
+                    disabled: running(),
+                    on_click: move |_| async move {
+                        running.set(true);
+                        benchmark.set(benchmark_detection().await);
+                        running.set(false);
+                    },
+
+                    if running() {
+                        "Benchmarking..."
+                    } else {
+                        "Benchmark current frame"
+                    }
+                }
+                if let Some(benchmark) = benchmark() {
+                    p { class: "text-sm text-primary-text font-mono", "CPU per frame" }
+                    p { class: "text-sm text-primary-text text-right font-mono",
+                        "{benchmark.cpu_millis:.2} ms"
+                    }
+                    p { class: "text-sm text-primary-text font-mono", "OpenCL per frame" }
+                    p { class: "text-sm text-primary-text text-right font-mono", {opencl_text()} }
+                    p { class: "text-xs text-secondary-text col-span-2",
+                        "Averaged over {benchmark.frames} frames"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionMobLabeler() -> Element {
+    let mut frame = use_signal(|| None::<MobFrame>);
+    let mut labels = use_signal(Vec::<MobLabel>::new);
+    let mut drawing = use_signal(|| None::<((i32, i32), (i32, i32))>);
+    let mut status = use_signal(String::default);
+
+    let capture = move |_| async move {
+        let Some(captured) = capture_mob_frame().await else {
+            status.set("No frame to capture".to_string());
+            return;
+        };
+        labels.set(
+            captured
+                .detections
+                .iter()
+                .map(|bound| MobLabel {
+                    bound: *bound,
+                    kind: MobLabelKind::Detected,
+                })
+                .collect(),
+        );
+        frame.set(Some(captured));
+        status.set(String::default());
+    };
+    let save = move |_| async move {
+        let mobs = labels
+            .peek()
+            .iter()
+            .filter(|label| label.kind != MobLabelKind::FalsePositive)
+            .map(|label| label.bound)
+            .collect();
+        let name = save_mob_labels(mobs).await;
+        frame.set(None);
+        labels.set(vec![]);
+        status.set(match name {
+            Some(name) => format!("Saved {name} to the mob dataset"),
+            None => "Nothing to save".to_string(),
+        });
+    };
+    let count = move |kind: MobLabelKind| {
+        labels().iter().filter(|label| label.kind == kind).count()
+    };
+
+    rsx! {
+        Section { title: "Mob labeler",
+            div { class: "grid grid-cols-2 gap-3",
+                Button { style: ButtonStyle::Secondary, on_click: capture, "Capture frame" }
+                Button {
+                    style: ButtonStyle::Secondary,
+                    disabled: frame().is_none(),
+                    on_click: save,
+                    "Save labels"
+                }
+            }
+            p { class: "text-xs text-secondary-text mt-2",
+                "Click a detected mob to mark it as a false positive and drag over a missed mob to add it as a false negative. Saved labels are stored in YOLO format in the mob dataset folder for retraining the mob model."
+            }
+            if let Some(frame) = frame() {
+                p { class: "text-xs text-primary-text mt-2",
+                    {
+                        format!(
+                            "{} detected, {} false positives, {} false negatives",
+                            count(MobLabelKind::Detected),
+                            count(MobLabelKind::FalsePositive),
+                            count(MobLabelKind::FalseNegative),
+                        )
+                    }
+                }
+                div { class: "overflow-auto mt-2 max-h-120",
+                    div {
+                        class: "relative select-none",
+                        style: "width: {frame.width}px; height: {frame.height}px;",
+                        img {
+                            src: format!("data:image/png;base64,{}", frame.base64),
+                            class: "absolute inset-0 max-w-none",
+                            draggable: false,
+                        }
+                        div {
+                            class: "absolute inset-0 cursor-crosshair",
+                            onmousedown: move |e| {
+                                let point = e.element_coordinates();
+                                let point = (point.x as i32, point.y as i32);
+                                drawing.set(Some((point, point)));
+                            },
+                            onmousemove: move |e| {
+                                let Some((start, _)) = *drawing.peek() else {
+                                    return;
+                                };
+                                let point = e.element_coordinates();
+                                drawing.set(Some((start, (point.x as i32, point.y as i32))));
+                            },
+                            onmouseup: move |_| {
+                                let Some((start, end)) = drawing.write().take() else {
+                                    return;
+                                };
+                                let bound = bound_from_corners(start, end);
+                                if bound.width >= MIN_MOB_SIZE && bound.height >= MIN_MOB_SIZE {
+                                    labels.write().push(MobLabel {
+                                        bound,
+                                        kind: MobLabelKind::FalseNegative,
+                                    });
+                                }
+                            },
+                            onmouseleave: move |_| {
+                                drawing.set(None);
+                            },
+                        }
+                        for (index , label) in labels().into_iter().enumerate() {
+                            div {
+                                key: "{index}",
+                                class: "absolute border-2 cursor-pointer",
+                                class: match label.kind {
+                                    MobLabelKind::Detected => "border-green-400",
+                                    MobLabelKind::FalsePositive => "border-red-400 border-dashed",
+                                    MobLabelKind::FalseNegative => "border-blue-400",
+                                },
+                                class: if drawing().is_some() { "pointer-events-none" },
+                                style: bound_style(label.bound),
+                                onclick: move |_| {
+                                    let mut labels = labels.write();
+                                    match labels[index].kind {
+                                        MobLabelKind::Detected => {
+                                            labels[index].kind = MobLabelKind::FalsePositive;
+                                        }
+                                        MobLabelKind::FalsePositive => {
+                                            labels[index].kind = MobLabelKind::Detected;
+                                        }
+                                        MobLabelKind::FalseNegative => {
+                                            labels.remove(index);
+                                        }
+                                    }
+                                },
+                            }
+                        }
+                        if let Some((start, end)) = drawing() {
+                            div {
+                                class: "absolute border-2 border-blue-400 border-dashed pointer-events-none",
+                                style: bound_style(bound_from_corners(start, end)),
+                            }
+                        }
+                    }
+                }
+            }
+            if !status().is_empty() {
+                p { class: "text-xs text-primary-text mt-2", {status()} }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionThresholdTuner() -> Element {
+    let mut localization = use_context::<AppState>().localization;
+    let localization_view = use_memo(move || localization().unwrap_or_default());
+    let mut detection = use_signal(|| TunableDetection::Player);
+    // The dragged threshold not yet saved or `None` to use the saved threshold
+    let mut threshold = use_signal(|| None::<f64>);
+    let current_threshold = use_memo(move || {
+        threshold().unwrap_or_else(|| localization_view().detection_threshold(detection()))
+    });
+    let mut previewing = use_signal(|| false);
+    let mut preview = use_signal(|| None::<ThresholdPreview>);
+
+    use_future(move || async move {
+        loop {
+            if *previewing.peek() {
+                let result =
+                    tune_detection_threshold(*detection.peek(), *current_threshold.peek()).await;
+                preview.set(result);
+            }
+            sleep(THRESHOLD_PREVIEW_INTERVAL).await;
+        }
+    });
+
+    let save = move |_| async move {
+        let mut new_localization = localization_view.peek().clone();
+        new_localization
+            .detection_thresholds
+            .insert(*detection.peek(), *current_threshold.peek());
+        localization.set(Some(upsert_localization(new_localization).await));
+        threshold.set(None);
+    };
+    let has_saved_threshold = use_memo(move || {
+        localization_view()
+            .detection_thresholds
+            .contains_key(&detection())
+    });
+    let reset = move |_| async move {
+        let mut new_localization = localization_view.peek().clone();
+        new_localization
+            .detection_thresholds
+            .remove(&*detection.peek());
+        localization.set(Some(upsert_localization(new_localization).await));
+        threshold.set(None);
+    };
+
+    rsx! {
+        Section { title: "Threshold tuner",
+            div { class: "grid grid-cols-2 gap-3",
+                Labeled { label: "Detection",
+                    Select::<TunableDetection> {
+                        on_selected: move |value| {
+                            detection.set(value);
+                            threshold.set(None);
+                        },
+
+                        for value in TunableDetection::iter() {
+                            SelectOption::<TunableDetection> {
+                                value,
+                                label: value.to_string(),
+                                selected: value == detection(),
+                            }
+                        }
+                    }
+                }
+                Labeled { label: format!("Threshold {:.2}", current_threshold()),
+                    input {
+                        class: "h-6 accent-gray-400",
+                        r#type: "range",
+                        min: 0.5,
+                        max: 1.0,
+                        step: 0.01,
+                        value: current_threshold(),
+                        oninput: move |e| {
+                            if let Ok(value) = e.value().parse::<f64>() {
+                                threshold.set(Some(value));
+                            }
+                        },
+                    }
+                }
+                Button {
+                    style: ButtonStyle::Secondary,
+                    on_click: move |_| {
+                        let value = !*previewing.peek();
+                        previewing.set(value);
+                        if !value {
+                            preview.set(None);
+                        }
+                    },
+
+                    if previewing() {
+                        "Stop preview"
+                    } else {
+                        "Start preview"
+                    }
+                }
+                div { class: "grid grid-cols-2 gap-3",
                     Button {
                         style: ButtonStyle::Secondary,
-                        on_click: move |_| async move {
-                            auto_save_rune(!state.peek().is_rune_auto_saving).await;
-                        },
+                        disabled: threshold().is_none(),
+                        on_click: save,
+                        "Save"
+                    }
+                    Button {
+                        style: ButtonStyle::Secondary,
+                        disabled: !has_saved_threshold(),
+                        on_click: reset,
+                        "Reset"
+                    }
+                }
+            }
+            p { class: "text-xs text-secondary-text mt-2",
+                "Drag the threshold and preview the match on the current frame. A saved threshold overrides the default one used by the bot for the selected detection."
+            }
+            if let Some(preview) = preview() {
+                if preview.bbox.is_none() {
+                    p { class: "text-xs text-primary-text mt-2", "No match" }
+                }
+                div { class: "overflow-auto mt-2 max-h-120",
+                    div {
+                        class: "relative select-none",
+                        style: "width: {preview.width}px; height: {preview.height}px;",
+                        img {
+                            src: format!("data:image/png;base64,{}", preview.base64),
+                            class: "absolute inset-0 max-w-none",
+                            draggable: false,
+                        }
+                        if let Some(bbox) = preview.bbox {
+                            div {
+                                class: "absolute border-2 border-green-400 pointer-events-none",
+                                style: bound_style(bbox),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
 
-                        if state().is_rune_auto_saving {
-                            "Stop auto saving rune"
-                        } else {
-                            "Start auto saving rune"
+#[component]
+fn SectionMouseAlignment() -> Element {
+    let mut testing = use_signal(|| false);
+    let mut preview = use_signal(|| None::<MouseAlignmentPreview>);
+    // The target clicked on the frame that has not been sent yet
+    let mut pending_target = use_signal(|| None::<(i32, i32)>);
+    let mut target = use_signal(|| None::<(i32, i32)>);
+
+    use_future(move || async move {
+        loop {
+            if *testing.peek() {
+                let result = test_mouse_alignment(pending_target.write().take()).await;
+                preview.set(result);
+            }
+            sleep(MOUSE_ALIGNMENT_INTERVAL).await;
+        }
+    });
+
+    rsx! {
+        Section { title: "Mouse alignment",
+            div { class: "grid grid-cols-2 gap-3",
+                Button {
+                    style: ButtonStyle::Secondary,
+                    on_click: move |_| {
+                        let value = !*testing.peek();
+                        testing.set(value);
+                        if !value {
+                            preview.set(None);
+                            target.set(None);
+                        }
+                    },
+
+                    if testing() {
+                        "Stop test"
+                    } else {
+                        "Start test"
+                    }
+                }
+                if let Some(preview) = preview() {
+                    p { class: "text-sm text-primary-text text-right font-mono self-center",
+                        {
+                            preview
+                                .scale
+                                .map_or("Window not found".to_string(), |scale| {
+                                    format!("Window scale {:.0}%", scale * 100.0)
+                                })
+                        }
+                    }
+                }
+            }
+            p { class: "text-xs text-secondary-text mt-2",
+                "Click on the frame to move the mouse there. The game cursor should follow the marker, an offset cursor means mouse coordinates are not aligned with the game window (e.g. because of display scaling)."
+            }
+            if let Some(preview) = preview() {
+                div { class: "overflow-auto mt-2 max-h-120",
+                    div {
+                        class: "relative select-none",
+                        style: "width: {preview.width}px; height: {preview.height}px;",
+                        img {
+                            src: format!("data:image/png;base64,{}", preview.base64),
+                            class: "absolute inset-0 max-w-none",
+                            draggable: false,
+                        }
+                        div {
+                            class: "absolute inset-0 cursor-crosshair",
+                            onclick: move |e| {
+                                let point = e.element_coordinates();
+                                let point = (point.x as i32, point.y as i32);
+                                pending_target.set(Some(point));
+                                target.set(Some(point));
+                            },
+                        }
+                        if let Some((x, y)) = target() {
+                            div {
+                                class: "absolute border-2 border-red-400 rounded-full pointer-events-none",
+                                style: bound_style(Bound {
+                                    x: x - MOUSE_ALIGNMENT_MARKER_SIZE / 2,
+                                    y: y - MOUSE_ALIGNMENT_MARKER_SIZE / 2,
+                                    width: MOUSE_ALIGNMENT_MARKER_SIZE,
+                                    height: MOUSE_ALIGNMENT_MARKER_SIZE,
+                                }),
+                            }
                         }
                     }
                 }
@@ -85,3 +635,29 @@ pub fn DebugScreen() -> Element {
         }
     }
 }
+
+fn bound_from_corners((x1, y1): (i32, i32), (x2, y2): (i32, i32)) -> Bound {
+    Bound {
+        x: x1.min(x2),
+        y: y1.min(y2),
+        width: (x1 - x2).abs(),
+        height: (y1 - y2).abs(),
+    }
+}
+
+fn bound_style(bound: Bound) -> String {
+    format!(
+        "left: {}px; top: {}px; width: {}px; height: {}px;",
+        bound.x, bound.y, bound.width, bound.height
+    )
+}
+
+fn memory_usage((used_bytes, budget_bytes): (usize, usize)) -> String {
+    const BYTES_PER_MIB: f32 = 1024.0 * 1024.0;
+
+    format!(
+        "{:.1} / {:.1} MiB",
+        used_bytes as f32 / BYTES_PER_MIB,
+        budget_bytes as f32 / BYTES_PER_MIB
+    )
+}
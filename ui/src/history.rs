@@ -0,0 +1,83 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use backend::{ConfigChange, query_config_changes, revert_config_change};
+use dioxus::prelude::*;
+use tokio::time::sleep;
+
+use crate::components::{
+    button::{Button, ButtonStyle},
+    section::Section,
+};
+
+/// How often the recorded changes are queried again.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+#[component]
+pub fn HistoryScreen() -> Element {
+    let mut changes = use_signal(Vec::<ConfigChange>::new);
+
+    use_future(move || async move {
+        loop {
+            if let Some(queried) = query_config_changes().await {
+                changes.set(queried);
+            }
+            sleep(REFRESH_INTERVAL).await;
+        }
+    });
+
+    rsx! {
+        div { class: "flex flex-col h-full overflow-y-auto",
+            Section { title: "Configuration changes",
+                if changes().is_empty() {
+                    p { class: "text-xs text-secondary-text", "No change recorded yet." }
+                }
+                div { class: "flex flex-col gap-2",
+                    for change in changes() {
+                        ChangeItem {
+                            key: "{change.id:?}",
+                            change,
+                            on_revert: move |change| async move {
+                                if revert_config_change(change).await
+                                    && let Some(queried) = query_config_changes().await
+                                {
+                                    changes.set(queried);
+                                }
+                            },
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ChangeItem(change: ConfigChange, on_revert: EventHandler<ConfigChange>) -> Element {
+    let timestamp = humantime::format_rfc3339_seconds(
+        UNIX_EPOCH + Duration::from_millis(change.timestamp_millis),
+    );
+    let title = match &change.entity_name {
+        Some(name) => format!("{} {name}", change.kind),
+        None => change.kind.to_string(),
+    };
+
+    rsx! {
+        div { class: "flex gap-2 items-start border-b border-secondary-border pb-2",
+            div { class: "flex flex-col flex-grow min-w-0",
+                p { class: "text-xs text-primary-text", "{title}" }
+                p { class: "text-xs text-secondary-text", "{timestamp}" }
+                for line in change.summary.iter() {
+                    p { class: "text-xs text-secondary-text break-all", "{line}" }
+                }
+            }
+            Button {
+                class: "w-20",
+                style: ButtonStyle::OutlineSecondary,
+                on_click: move |_| {
+                    on_revert(change.clone());
+                },
+                "Revert"
+            }
+        }
+    }
+}
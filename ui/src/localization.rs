@@ -1,6 +1,7 @@
 use backend::{
-    GameTemplate, Localization, convert_image_to_base64, query_localization, query_template,
-    save_capture_image, upsert_localization,
+    DEFAULT_POPUP_ROI_HEIGHT_PERCENT, DEFAULT_POPUP_ROI_WIDTH_PERCENT, GameTemplate, Localization,
+    convert_image_to_base64, query_localization, query_template, reload_templates,
+    save_capture_image, test_template, upsert_localization,
 };
 use dioxus::{html::FileData, prelude::*};
 use futures_util::{StreamExt, future::OptionFuture};
@@ -11,6 +12,7 @@ use crate::{
         button::{Button, ButtonStyle},
         file::FileInput,
         labeled::Labeled,
+        numbers::PercentageInput,
         section::Section,
     },
 };
@@ -64,6 +66,8 @@ pub fn LocalizationScreen() -> Element {
             SectionPopups {}
             SectionFamiliars {}
             SectionHexa {}
+            SectionCashShop {}
+            SectionParty {}
             SectionOthers {}
         }
     }
@@ -91,6 +95,9 @@ fn SectionInfo() -> Element {
         }
     }
 
+    // The file names of the user templates loaded by the last reload
+    let mut reloaded_templates = use_signal(|| None::<Vec<String>>);
+
     rsx! {
         Section { title: "Info",
             table { class: "table-fixed",
@@ -154,6 +161,28 @@ fn SectionInfo() -> Element {
                         Data { description: "Detect whether Generic/HEXA booster is in use." }
                         Data { description: "Timer text." }
                     }
+                    tr {
+                        Data { description: "Cash shop", rowspan: 3 }
+                        Data { description: "Open the tab containing daily free items." }
+                        Data { description: "Free items tab (no default)." }
+                    }
+                    tr {
+                        Data { description: "Select and verify an unclaimed daily free item." }
+                        Data { description: "Free item (no default)." }
+                    }
+                    tr {
+                        Data { description: "Claim the selected free item." }
+                        Data { description: "Buy button (no default)." }
+                    }
+                    tr {
+                        Data { description: "Party", rowspan: 2 }
+                        Data { description: "Detect party invite popup and its inviter name." }
+                        Data { description: "Party invite popup title (no default)." }
+                    }
+                    tr {
+                        Data { description: "Accept or decline party invite." }
+                        Data { description: "Accept and decline buttons (no default)." }
+                    }
                 }
             }
             div { class: "grid grid-cols-2 gap-3 mt-3",
@@ -171,6 +200,25 @@ fn SectionInfo() -> Element {
                     },
                     "Capture grayscale"
                 }
+                Button {
+                    class: "col-span-2",
+                    style: ButtonStyle::Secondary,
+                    on_click: move |_| async move {
+                        reloaded_templates.set(Some(reload_templates().await));
+                    },
+                    "Reload templates folder"
+                }
+                if let Some(names) = reloaded_templates() {
+                    p { class: "col-span-2 text-xs text-secondary-text",
+                        if names.is_empty() {
+                            "No PNG found in the templates folder next to the executable."
+                        } else {
+                            "Overriding built-in templates with "
+                            {names.join(", ")}
+                            "."
+                        }
+                    }
+                }
             }
         }
     }
@@ -273,6 +321,36 @@ fn SectionPopups() -> Element {
                     },
                     value: localization().popup_cancel_old_base64,
                 }
+                Labeled {
+                    label: "Search width",
+                    tooltip: "The width of the screen center band popup buttons are searched in.",
+                    PercentageInput {
+                        on_value: move |percent: u32| {
+                            save_localization(Localization {
+                                popup_roi_width_percent: Some(percent as f32),
+                                ..localization()
+                            });
+                        },
+                        value: localization()
+                            .popup_roi_width_percent
+                            .unwrap_or(DEFAULT_POPUP_ROI_WIDTH_PERCENT) as u32,
+                    }
+                }
+                Labeled {
+                    label: "Search height",
+                    tooltip: "The height of the screen center band popup buttons are searched in.",
+                    PercentageInput {
+                        on_value: move |percent: u32| {
+                            save_localization(Localization {
+                                popup_roi_height_percent: Some(percent as f32),
+                                ..localization()
+                            });
+                        },
+                        value: localization()
+                            .popup_roi_height_percent
+                            .unwrap_or(DEFAULT_POPUP_ROI_HEIGHT_PERCENT) as u32,
+                    }
+                }
             }
         }
     }
@@ -372,6 +450,124 @@ fn SectionFamiliars() -> Element {
     }
 }
 
+#[component]
+fn SectionCashShop() -> Element {
+    let context = use_context::<LocalizationContext>();
+    let localization = context.localization;
+    let save_localization = context.save_localization;
+
+    rsx! {
+        Section { title: "Cash shop",
+            div { class: "grid grid-cols-2 gap-4",
+                LocalizationTemplateInput {
+                    label: "Free items tab",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            cash_shop_free_tab_base64: to_base64(image, false).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().cash_shop_free_tab_base64,
+                }
+                LocalizationTemplateInput {
+                    label: "Free item",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            cash_shop_free_item_base64: to_base64(image, false).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().cash_shop_free_item_base64,
+                }
+                LocalizationTemplateInput {
+                    label: "Buy button",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            cash_shop_buy_button_base64: to_base64(image, false).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().cash_shop_buy_button_base64,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionParty() -> Element {
+    let context = use_context::<LocalizationContext>();
+    let localization = context.localization;
+    let save_localization = context.save_localization;
+
+    rsx! {
+        Section { title: "Party",
+            div { class: "grid grid-cols-2 gap-4",
+                LocalizationTemplateInput {
+                    label: "Invite popup title",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            party_invite_base64: to_base64(image, false).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().party_invite_base64,
+                }
+                LocalizationTemplateInput {
+                    label: "Accept button",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            party_invite_accept_button_base64: to_base64(image, false).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().party_invite_accept_button_base64,
+                }
+                LocalizationTemplateInput {
+                    label: "Decline button",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            party_invite_decline_button_base64: to_base64(image, false).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().party_invite_decline_button_base64,
+                }
+                LocalizationTemplateInput {
+                    label: "Trade request popup title",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            trade_request_base64: to_base64(image, false).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().trade_request_base64,
+                }
+                LocalizationTemplateInput {
+                    label: "Duel request popup title",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            duel_request_base64: to_base64(image, false).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().duel_request_base64,
+                }
+                LocalizationTemplateInput {
+                    label: "Trade or duel decline button",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            player_request_decline_button_base64: to_base64(image, false).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().player_request_decline_button_base64,
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn SectionOthers() -> Element {
     let context = use_context::<LocalizationContext>();
@@ -416,6 +612,39 @@ fn SectionOthers() -> Element {
                     },
                     value: localization().timer_base64,
                 }
+                LocalizationTemplateInput {
+                    label: "Player dot",
+                    tooltip: "This template is in color. It can also be calibrated on the minimap.",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            player_dot_base64: to_base64(image, false).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().player_dot_base64,
+                }
+                LocalizationTemplateInput {
+                    label: "Quick slots potion",
+                    tooltip: "This template is in color. Crop only the top part of the potion icon in the quick slots above its item count. Used for verifying the potion stock.",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            quick_slots_potion_base64: to_base64(image, false).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().quick_slots_potion_base64,
+                }
+                LocalizationTemplateInput {
+                    label: "Shop potion",
+                    tooltip: "This template is in color. Crop the potion item icon in the NPC shop list. Used for restocking potions.",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            shop_potion_base64: to_base64(image, false).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().shop_potion_base64,
+                }
             }
         }
     }
@@ -424,7 +653,7 @@ fn SectionOthers() -> Element {
 #[component]
 fn LocalizationTemplateInput(
     label: &'static str,
-    template: GameTemplate,
+    #[props(default)] template: Option<GameTemplate>,
     #[props(default)] tooltip: Option<String>,
     on_value: Callback<Option<Vec<u8>>>,
     value: ReadSignal<Option<String>>,
@@ -433,14 +662,18 @@ fn LocalizationTemplateInput(
         on_value(file.read_bytes().await.ok().map(Vec::from));
     });
     let mut base64 = use_signal(String::default);
+    let mut test_result = use_signal(String::default);
 
     use_effect(move || {
+        test_result.set(String::default());
         if let Some(value) = value() {
             base64.set(value);
-        } else {
+        } else if let Some(template) = template {
             spawn(async move {
                 base64.set(query_template(template).await);
             });
+        } else {
+            base64.set(String::default());
         }
     });
 
@@ -449,13 +682,45 @@ fn LocalizationTemplateInput(
             div { class: "flex-grow",
                 Labeled { label, tooltip,
                     div { class: "h-6 border-b border-primary-border pb-0.5",
-                        img {
-                            src: format!("data:image/png;base64,{}", base64()),
-                            class: "h-full",
+                        if base64().is_empty() {
+                            p { class: "text-xs text-secondary-text", "Not set" }
+                        } else {
+                            div { class: "flex h-full gap-2 items-center",
+                                img {
+                                    src: format!("data:image/png;base64,{}", base64()),
+                                    class: "h-full",
+                                }
+                                p { class: "text-xs text-secondary-text", {test_result()} }
+                            }
                         }
                     }
                 }
             }
+            div { class: "flex items-end",
+                Button {
+                    class: "w-14",
+                    style: ButtonStyle::Primary,
+                    disabled: base64().is_empty(),
+                    on_click: move |_| async move {
+                        let result = test_template(base64.peek().clone()).await;
+                        test_result
+                            .set(
+                                result
+                                    .map(|result| {
+                                        format!(
+                                            "Score {:.2} at ({}, {})",
+                                            result.score,
+                                            result.bbox.x,
+                                            result.bbox.y,
+                                        )
+                                    })
+                                    .unwrap_or("Unable to test".to_string()),
+                            );
+                    },
+
+                    "Test"
+                }
+            }
             div { class: "flex items-end",
                 Button {
                     class: "w-14",
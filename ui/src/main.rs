@@ -1,12 +1,19 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 #![feature(variant_count)]
-#![feature(map_try_insert)]
 #![feature(iter_intersperse)]
 
-use std::{env::current_exe, io::stdout, string::ToString, sync::LazyLock};
+use std::{
+    env::{self, current_exe},
+    io::stdout,
+    string::ToString,
+    sync::LazyLock,
+};
 
 use actions::ActionsScreen;
-use backend::{Character, Localization, Map, Settings};
+use backend::{
+    Character, DatabaseEvent, Localization, Map, Settings, database_event_receiver,
+    game_state_receiver, query_characters, query_settings, selected_instance, status_title,
+};
 use characters::CharactersScreen;
 #[cfg(debug_assertions)]
 use debug::DebugScreen;
@@ -19,11 +26,13 @@ use dioxus::{
     prelude::*,
 };
 use fern::Dispatch;
+use history::HistoryScreen;
 use log::LevelFilter;
-use minimap::MinimapScreen;
+use minimap::{MinimapScreen, SpectateScreen};
 use navigation::NavigationScreen;
 use rand::distr::{Alphanumeric, SampleString};
 use settings::SettingsScreen;
+use stats::StatsScreen;
 
 use crate::localization::LocalizationScreen;
 
@@ -32,10 +41,12 @@ mod characters;
 mod components;
 #[cfg(debug_assertions)]
 mod debug;
+mod history;
 mod localization;
 mod minimap;
 mod navigation;
 mod settings;
+mod stats;
 
 const TAILWIND_CSS: Asset = asset!("public/tailwind.css");
 const AUTO_NUMERIC_JS: Asset = asset!("public/autoNumeric.min.js");
@@ -44,9 +55,24 @@ const TAB_CHARACTERS: &str = "Characters";
 const TAB_NAVIGATION: &str = "Navigation";
 const TAB_SETTINGS: &str = "Settings";
 const TAB_LOCALIZATION: &str = "Localization";
+const TAB_STATS: &str = "Stats";
+const TAB_HISTORY: &str = "History";
 #[cfg(debug_assertions)]
 const TAB_DEBUG: &str = "Debug";
 
+/// The address of the instance to spectate in read-only mode from `--spectate <address>`.
+static SPECTATE_ADDRESS: LazyLock<Option<String>> = LazyLock::new(|| {
+    let mut args = env::args();
+    args.position(|arg| arg == "--spectate")?;
+    args.next()
+});
+
+/// Whether to run the bot against a simulated game from `--sandbox`.
+static SANDBOX: LazyLock<bool> = LazyLock::new(|| env::args().any(|arg| arg == "--sandbox"));
+/// The random window title used when the bot status is not shown as the title.
+static RANDOM_TITLE: LazyLock<String> =
+    LazyLock::new(|| Alphanumeric.sample_string(&mut rand::rng(), 16));
+
 static TABS: LazyLock<Vec<String>> = LazyLock::new(|| {
     vec![
         TAB_ACTIONS.to_string(),
@@ -54,6 +80,8 @@ static TABS: LazyLock<Vec<String>> = LazyLock::new(|| {
         TAB_NAVIGATION.to_string(),
         TAB_SETTINGS.to_string(),
         TAB_LOCALIZATION.to_string(),
+        TAB_STATS.to_string(),
+        TAB_HISTORY.to_string(),
         #[cfg(debug_assertions)]
         TAB_DEBUG.to_string(),
     ]
@@ -82,16 +110,25 @@ fn main() {
         .unwrap();
     log_panics::init();
 
-    backend::init();
+    let app: fn() -> Element = if let Some(address) = SPECTATE_ADDRESS.clone() {
+        backend::spectate(address);
+        SpectateApp
+    } else if *SANDBOX {
+        backend::init_sandbox();
+        App
+    } else {
+        backend::init();
+        App
+    };
     let window = WindowBuilder::new()
         .with_drag_and_drop(false)
         .with_inner_size(Size::new(PhysicalSize::new(1024, 483)))
         .with_min_inner_size(Size::new(PhysicalSize::new(320, 483)))
-        .with_title(Alphanumeric.sample_string(&mut rand::rng(), 16));
+        .with_title(RANDOM_TITLE.as_str());
     let cfg = dioxus::desktop::Config::default()
         .with_menu(None)
         .with_window(window);
-    dioxus::LaunchBuilder::desktop().with_cfg(cfg).launch(App);
+    dioxus::LaunchBuilder::desktop().with_cfg(cfg).launch(app);
 }
 
 #[derive(Clone, Copy)]
@@ -102,21 +139,51 @@ pub struct AppState {
     settings: Signal<Option<Settings>>,
     localization: Signal<Option<Localization>>,
     position: Signal<(i32, i32)>,
+    /// Action tags currently disabled in the rotator.
+    disabled_action_tags: Signal<Vec<String>>,
+}
+
+impl AppState {
+    fn new() -> Self {
+        Self {
+            map: Signal::new(None),
+            map_preset: Signal::new(None),
+            character: Signal::new(None),
+            settings: Signal::new(None),
+            localization: Signal::new(None),
+            position: Signal::new((0, 0)),
+            disabled_action_tags: Signal::new(vec![]),
+        }
+    }
+}
+
+/// The bot instance the UI is controlling.
+///
+/// This is provided above [`AppState`] so that switching instances recreates every screen with
+/// the states of the newly selected instance.
+#[derive(Clone, Copy)]
+pub struct InstanceState {
+    instance: Signal<usize>,
+}
+
+#[component]
+fn SpectateApp() -> Element {
+    use_context_provider(AppState::new);
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: TAILWIND_CSS }
+        div { class: "flex min-h-120 h-full", SpectateScreen {} }
+    }
 }
 
 #[component]
 fn App() -> Element {
-    let mut selected_tab = use_signal(|| TAB_CHARACTERS.to_string());
+    let selected_tab = use_signal(|| TAB_CHARACTERS.to_string());
     let mut script_loaded = use_signal(|| false);
-
-    use_context_provider(|| AppState {
-        map: Signal::new(None),
-        map_preset: Signal::new(None),
-        character: Signal::new(None),
-        settings: Signal::new(None),
-        localization: Signal::new(None),
-        position: Signal::new((0, 0)),
-    });
+    let instance = use_context_provider(|| InstanceState {
+        instance: Signal::new(selected_instance()),
+    })
+    .instance;
 
     // Thanks dioxus
     use_future(move || async move {
@@ -139,39 +206,113 @@ fn App() -> Element {
         document::Link { rel: "stylesheet", href: TAILWIND_CSS }
         document::Script { src: AUTO_NUMERIC_JS }
         if script_loaded() {
-            div { class: "flex min-w-3xl lg:min-w-5xl min-h-120 h-full",
-                MinimapScreen {}
-                div { class: "flex-grow flex flex-col lg:flex-row z-1",
-                    Tabs {
-                        tabs: TABS.clone(),
-                        on_select_tab: move |tab| {
-                            selected_tab.set(tab);
-                        },
-                        selected_tab: selected_tab(),
+            // Keyed so that the screens are recreated when switching instances
+            for instance in [instance()] {
+                InstanceApp { key: "{instance}", selected_tab }
+            }
+        }
+    }
+}
+
+#[component]
+fn InstanceApp(mut selected_tab: Signal<String>) -> Element {
+    let mut character = use_context_provider(AppState::new).character;
+
+    // Shows the bot status as the window title for streaming tools if enabled
+    use_future(move || async move {
+        let mut enabled = query_settings().await.enable_status_window_title;
+        let mut settings_rx = database_event_receiver();
+        let mut receiver = game_state_receiver().await;
+        loop {
+            while let Ok(event) = settings_rx.try_recv() {
+                if let DatabaseEvent::SettingsUpdated(settings) = event {
+                    enabled = settings.enable_status_window_title;
+                    if !enabled {
+                        dioxus::desktop::window().set_title(&RANDOM_TITLE);
                     }
-                    div { class: "relative w-full h-full overflow-x-hidden overflow-y-auto pl-2 lg:pl-0",
-                        match selected_tab().as_str() {
-                            TAB_ACTIONS => rsx! {
-                                ActionsScreen {}
-                            },
-                            TAB_CHARACTERS => rsx! {
-                                CharactersScreen {}
-                            },
-                            TAB_SETTINGS => rsx! {
-                                SettingsScreen {}
-                            },
-                            TAB_NAVIGATION => rsx! {
-                                NavigationScreen {}
-                            },
-                            TAB_LOCALIZATION => rsx! {
-                                LocalizationScreen {}
-                            },
-                            #[cfg(debug_assertions)]
-                            TAB_DEBUG => rsx! {
-                                DebugScreen {}
-                            },
-                            _ => unreachable!(),
-                        }
+                }
+            }
+            let Ok(state) = receiver.recv().await else {
+                continue;
+            };
+            if enabled {
+                let title = status_title(&state);
+                if dioxus::desktop::window().title() != title {
+                    dioxus::desktop::window().set_title(&title);
+                }
+            }
+        }
+    });
+
+    // Selects the character automatically detected from the in-game name
+    use_future(move || async move {
+        let mut receiver = game_state_receiver().await;
+        let mut last_detected_id = None;
+        loop {
+            let Ok(state) = receiver.recv().await else {
+                continue;
+            };
+            if state.detected_character_id == last_detected_id {
+                continue;
+            }
+            last_detected_id = state.detected_character_id;
+
+            let Some(id) = last_detected_id else {
+                continue;
+            };
+            if character.peek().as_ref().and_then(|character| character.id) == Some(id) {
+                continue;
+            }
+            let detected = query_characters()
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .find(|character| character.id == Some(id));
+            if let Some(detected) = detected {
+                character.set(Some(detected));
+            }
+        }
+    });
+
+    rsx! {
+        div { class: "flex min-w-3xl lg:min-w-5xl min-h-120 h-full",
+            MinimapScreen {}
+            div { class: "flex-grow flex flex-col lg:flex-row z-1",
+                Tabs {
+                    tabs: TABS.clone(),
+                    on_select_tab: move |tab| {
+                        selected_tab.set(tab);
+                    },
+                    selected_tab: selected_tab(),
+                }
+                div { class: "relative w-full h-full overflow-x-hidden overflow-y-auto pl-2 lg:pl-0",
+                    match selected_tab().as_str() {
+                        TAB_ACTIONS => rsx! {
+                            ActionsScreen {}
+                        },
+                        TAB_CHARACTERS => rsx! {
+                            CharactersScreen {}
+                        },
+                        TAB_SETTINGS => rsx! {
+                            SettingsScreen {}
+                        },
+                        TAB_NAVIGATION => rsx! {
+                            NavigationScreen {}
+                        },
+                        TAB_LOCALIZATION => rsx! {
+                            LocalizationScreen {}
+                        },
+                        TAB_STATS => rsx! {
+                            StatsScreen {}
+                        },
+                        TAB_HISTORY => rsx! {
+                            HistoryScreen {}
+                        },
+                        #[cfg(debug_assertions)]
+                        TAB_DEBUG => rsx! {
+                            DebugScreen {}
+                        },
+                        _ => unreachable!(),
                     }
                 }
             }
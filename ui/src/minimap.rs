@@ -1,14 +1,23 @@
 use std::{
     ops::Deref,
+    rc::Rc,
     time::{Duration, Instant},
 };
 
 use backend::{
-    Action, ActionKey, ActionMove, BotOperation, BotOperationUpdate, DatabaseEvent, Map, Position,
-    RotationMode, create_map, database_event_receiver, delete_map, game_state_receiver, query_maps,
-    redetect_minimap, update_map, update_operation, upsert_map,
+    Action, ActionKey, ActionMove, BotOperation, BotOperationUpdate, Bound, DatabaseEvent,
+    ErrorEvent, ExpRate, KeyBinding, Localization, Map, Platform, Position, RotationMode,
+    SelfCheck, calibrate_player_dot, create_map, database_event_receiver, delete_map,
+    game_state_receiver, key_receiver, query_localization, query_maps, query_platform_graph,
+    query_position_heatmap, query_preset_recovery, query_resumable_operation, query_run_changes,
+    recover_preset, redetect_minimap, resume_operation, run_self_check, update_map,
+    update_minimap_visible, update_operation, upsert_localization, upsert_map, upsert_map_with,
+};
+use dioxus::{
+    document::EvalError,
+    html::{FileData, input_data::MouseButton},
+    prelude::*,
 };
-use dioxus::{document::EvalError, html::FileData, prelude::*};
 use futures_util::StreamExt;
 use serde::Serialize;
 use tokio::{sync::broadcast::error::RecvError, time::sleep};
@@ -19,6 +28,7 @@ use crate::{
         button::{Button, ButtonStyle},
         file::{FileInput, FileOutput},
         named_select::NamedSelect,
+        popup::{PopupContent, PopupContext},
         select::{Select, SelectOption},
     },
 };
@@ -33,7 +43,8 @@ const MINIMAP_JS: &str = r#"
     const canvasCtx = canvas.getContext("2d");
 
     while (true) {
-        const [buffer, width, height, destinations, bound, quadrant, portals] = await dioxus.recv();
+        const [buffer, width, height, destinations, bound, quadrant, portals, waypoints] =
+            await dioxus.recv();
         const data = new ImageData(new Uint8ClampedArray(buffer), width, height);
         const bitmap = await createImageBitmap(data);
 
@@ -76,6 +87,27 @@ const MINIMAP_JS: &str = r#"
             canvasCtx.strokeRect(x, y, w, h);
         }
 
+        // Numbered in rotation order with the executing action highlighted
+        const waypointRadius = 5;
+        canvasCtx.setLineDash([]);
+        canvasCtx.font = "bold 7px sans-serif";
+        canvasCtx.textAlign = "center";
+        canvasCtx.textBaseline = "middle";
+        for (let i = 0; i < waypoints.length; i++) {
+            const waypoint = waypoints[i];
+            const x = (waypoint.x / width) * canvas.width;
+            const y = ((height - waypoint.y) / height) * canvas.height;
+
+            canvasCtx.fillStyle = waypoint.current ? "rgb(255, 221, 128)" : "rgb(255, 153, 128)";
+            canvasCtx.beginPath();
+            canvasCtx.arc(x, y, waypointRadius, 0, 2 * Math.PI);
+            canvasCtx.fill();
+            canvasCtx.fillStyle = "rgb(0, 0, 0)";
+            canvasCtx.fillText((i + 1).toString(), x, y);
+        }
+        canvasCtx.textAlign = "start";
+        canvasCtx.textBaseline = "alphabetic";
+
         if (quadrant !== null && bound !== null) {
             canvasCtx.strokeStyle = "rgb(254, 71, 57)";
 
@@ -277,6 +309,114 @@ const MINIMAP_ACTIONS_JS: &str = r#"
     }
 "#;
 
+const MINIMAP_HEATMAP_JS: &str = r#"
+    const canvas = document.getElementById("canvas-map-heatmap");
+    const canvasCtx = canvas.getContext("2d");
+    const [width, height, heatmap] = await dioxus.recv();
+    canvasCtx.clearRect(0, 0, canvas.width, canvas.height);
+    if (heatmap !== null) {
+        drawHeatmap(heatmap);
+    }
+
+    function drawHeatmap(heatmap) {
+        const max = Math.max(...heatmap.counts);
+        if (max === 0) {
+            return;
+        }
+        const cellWidth = (heatmap.cell_size / width) * canvas.width;
+        const cellHeight = (heatmap.cell_size / height) * canvas.height;
+
+        for (let row = 0; row < heatmap.rows; row++) {
+            for (let column = 0; column < heatmap.columns; column++) {
+                const count = heatmap.counts[row * heatmap.columns + column];
+                if (count === 0) {
+                    continue;
+                }
+                // Square root so that rarely visited cells are still visible next to hot spots
+                const ratio = Math.sqrt(count / max);
+                const hue = 240 * (1 - ratio);
+                const x = ((column * heatmap.cell_size) / width) * canvas.width;
+                const y = ((height - (row + 1) * heatmap.cell_size) / height) * canvas.height;
+
+                canvasCtx.fillStyle = `hsla(${hue}, 100%, 50%, 0.6)`;
+                canvasCtx.fillRect(x, y, cellWidth, cellHeight);
+            }
+        }
+    }
+"#;
+
+const MINIMAP_GRAPH_JS: &str = r#"
+    const canvas = document.getElementById("canvas-map-graph");
+    const canvasCtx = canvas.getContext("2d");
+    const [width, height, graph] = await dioxus.recv();
+    canvasCtx.clearRect(0, 0, canvas.width, canvas.height);
+    if (graph !== null) {
+        drawGraph(graph);
+    }
+
+    function drawGraph(graph) {
+        const colors = {
+            Walk: "rgb(255, 255, 255)",
+            Fall: "rgb(128, 255, 204)",
+            Jump: "rgb(179, 198, 255)",
+            UpJump: "rgb(128, 212, 255)",
+            Grapple: "rgb(255, 221, 128)",
+            DoubleJump: "rgb(230, 153, 255)",
+        };
+        const centers = graph.platforms.map((platform) => [
+            (((platform.x_start + platform.x_end) / 2) / width) * canvas.width,
+            ((height - platform.y) / height) * canvas.height,
+        ]);
+
+        canvasCtx.font = "8px sans-serif";
+        canvasCtx.lineWidth = 1;
+        for (const edge of graph.edges) {
+            const [fromX, fromY] = centers[edge.from];
+            const [toX, toY] = centers[edge.to];
+            // Refused edges are drawn in red so it is clear the path finder never takes them
+            const color = edge.cost === null ? "rgb(255, 77, 77)" : colors[edge.movement];
+
+            canvasCtx.setLineDash(edge.cost === null ? [4] : []);
+            canvasCtx.strokeStyle = color;
+            canvasCtx.fillStyle = color;
+            canvasCtx.beginPath();
+            canvasCtx.moveTo(fromX, fromY);
+            canvasCtx.lineTo(toX, toY);
+            canvasCtx.stroke();
+            drawArrowHead(fromX, fromY, toX, toY);
+            canvasCtx.fillText(
+                edge.cost === null ? "x" : edge.cost.toString(),
+                (fromX + toX) / 2 + 2,
+                (fromY + toY) / 2 - 2,
+            );
+        }
+
+        canvasCtx.setLineDash([]);
+        canvasCtx.fillStyle = "rgb(255, 160, 37)";
+        for (const [x, y] of centers) {
+            canvasCtx.fillRect(x - 2, y - 2, 4, 4);
+        }
+    }
+
+    function drawArrowHead(fromX, fromY, toX, toY) {
+        const headSize = 5;
+        const angle = Math.atan2(toY - fromY, toX - fromX);
+        canvasCtx.setLineDash([]);
+        canvasCtx.beginPath();
+        canvasCtx.moveTo(toX, toY);
+        canvasCtx.lineTo(
+            toX - headSize * Math.cos(angle - Math.PI / 6),
+            toY - headSize * Math.sin(angle - Math.PI / 6),
+        );
+        canvasCtx.moveTo(toX, toY);
+        canvasCtx.lineTo(
+            toX - headSize * Math.cos(angle + Math.PI / 6),
+            toY - headSize * Math.sin(angle + Math.PI / 6),
+        );
+        canvasCtx.stroke();
+    }
+"#;
+
 #[derive(Clone, PartialEq, Serialize)]
 struct ActionView {
     x: i32,
@@ -288,10 +428,18 @@ struct ActionView {
 struct MinimapState {
     position: Option<(i32, i32)>,
     health: Option<(u32, u32)>,
+    mana: Option<(u32, u32)>,
     state: String,
     normal_action: Option<String>,
     priority_action: Option<String>,
+    next_priority_action: Option<(String, Duration)>,
     erda_shower_state: String,
+    stranger_count: usize,
+    crowding_score: f32,
+    exp: Option<ExpRate>,
+    runes_solved: u32,
+    runes_failed: u32,
+    errors: Vec<ErrorEvent>,
     operation: BotOperation,
     detected_size: Option<(usize, usize)>,
 }
@@ -303,6 +451,55 @@ enum MinimapUpdate {
     Create(String),
     Import(Map),
     Delete,
+    /// Saves platforms and bounds edited on the canvas.
+    Edit(Map),
+}
+
+/// What dragging on the canvas edits.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+enum EditMode {
+    #[default]
+    None,
+    Platforms,
+    AutoMobBound,
+    PingPongBound,
+}
+
+impl EditMode {
+    fn next(self) -> EditMode {
+        match self {
+            EditMode::None => EditMode::Platforms,
+            EditMode::Platforms => EditMode::AutoMobBound,
+            EditMode::AutoMobBound => EditMode::PingPongBound,
+            EditMode::PingPongBound => EditMode::None,
+        }
+    }
+}
+
+/// The part of a platform under the cursor.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum PlatformHit {
+    Start(usize),
+    End(usize),
+    Body(usize),
+}
+
+/// The edges of a bound under the cursor or none of them when the cursor is inside the bound.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+struct BoundHit {
+    left: bool,
+    right: bool,
+    top: bool,
+    bottom: bool,
+}
+
+/// An in-progress drag on the canvas.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum EditDrag {
+    /// Drawing a new platform or bound.
+    Draw,
+    Platform(PlatformHit),
+    Bound(BoundHit),
 }
 
 #[component]
@@ -331,6 +528,19 @@ pub fn MinimapScreen() -> Element {
 
     // Game state for displaying info
     let state = use_signal::<Option<MinimapState>>(|| None);
+    // Pauses the minimap frame broadcast while the window is minimized or hidden
+    use_future(move || async move {
+        let mut last_visible = None;
+        loop {
+            let window = dioxus::desktop::window();
+            let visible = window.is_visible() && !window.is_minimized();
+            if last_visible != Some(visible) {
+                last_visible = Some(visible);
+                update_minimap_visible(visible).await;
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+    });
     // Handles async operations for map-related
     let coroutine = use_coroutine(move |mut rx: UnboundedReceiver<MinimapUpdate>| async move {
         while let Some(message) = rx.next().await {
@@ -342,7 +552,7 @@ pub fn MinimapScreen() -> Element {
                     let Some(new_map) = create_map(name).await else {
                         continue;
                     };
-                    let Some(new_map) = upsert_map(new_map).await else {
+                    let Some(new_map) = upsert_map(new_map).await.saved() else {
                         continue;
                     };
 
@@ -361,6 +571,19 @@ pub fn MinimapScreen() -> Element {
                         map_preset.set(None);
                     }
                 }
+                MinimapUpdate::Edit(edited_map) => {
+                    let Some(current_map) = map() else {
+                        continue;
+                    };
+                    let edit = move |map: &mut Map| {
+                        map.platforms = edited_map.platforms.clone();
+                        map.rotation_auto_mob_bound = edited_map.rotation_auto_mob_bound;
+                        map.rotation_ping_pong_bound = edited_map.rotation_ping_pong_bound;
+                    };
+                    if let Some(current_map) = upsert_map_with(current_map, edit).await {
+                        map.set(Some(current_map));
+                    }
+                }
             }
         }
     });
@@ -413,8 +636,12 @@ pub fn MinimapScreen() -> Element {
                 map,
                 map_preset,
                 position,
+                on_edit: move |edited_map| {
+                    coroutine.send(MinimapUpdate::Edit(edited_map));
+                },
             }
             Buttons { state, map }
+            Status { state }
             Info { state, map }
             div { class: "flex-grow flex items-end px-2",
                 div { class: "flex flex-col items-end w-full",
@@ -462,20 +689,84 @@ pub fn MinimapScreen() -> Element {
     }
 }
 
+/// A read-only view of the minimap and game information of a spectated instance.
+#[component]
+pub fn SpectateScreen() -> Element {
+    let map = use_signal(|| None);
+    let map_preset = use_signal(|| None);
+    let position = use_context::<AppState>().position;
+    let state = use_signal::<Option<MinimapState>>(|| None);
+    let mut last_key = use_signal::<Option<KeyBinding>>(|| None);
+
+    use_future(move || async move {
+        let mut receiver = key_receiver().await;
+        loop {
+            match receiver.recv().await {
+                Ok(key) => last_key.set(Some(key)),
+                Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(_)) => continue,
+            }
+        }
+    });
+
+    rsx! {
+        div { class: "relative flex flex-col flex-none w-xs xl:w-md z-0",
+            Canvas {
+                state,
+                map,
+                map_preset,
+                position,
+                read_only: true,
+            }
+            Status { state }
+            Info { state, map }
+            div { class: "grid grid-cols-2 items-center justify-center px-4 gap-1",
+                InfoItem {
+                    name: "Last key",
+                    value: last_key().map(|key| key.to_string()).unwrap_or("None".to_string()),
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn Canvas(
     state: Signal<Option<MinimapState>>,
     map: ReadSignal<Option<Map>>,
     map_preset: ReadSignal<Option<String>>,
     position: Signal<(i32, i32)>,
+    #[props(default)] read_only: bool,
+    #[props(default)] on_edit: Option<Callback<Map>>,
 ) -> Element {
+    let mut localization = use_context::<AppState>().localization;
     let mut platforms_bound = use_signal(|| None);
+    // Whether the next click on the canvas confirms the player position for calibration
+    let mut calibrating = use_signal(|| false);
+    let mut canvas_actions = use_signal::<Option<Rc<MountedData>>>(|| None);
+    // Whether to draw the platform graph used for path finding
+    let mut show_graph = use_signal(|| false);
+    // Whether to draw the heatmap of recorded player positions
+    let mut show_heatmap = use_signal(|| false);
+    let mut edit_mode = use_signal(EditMode::default);
+    // The current drag with the canvas size and the map and point it started from
+    let mut edit_drag = use_signal::<Option<(EditDrag, (f64, f64), Map, (i32, i32))>>(|| None);
+    // The edited map previewed while dragging
+    let mut edit_draft = use_signal::<Option<Map>>(|| None);
     let rotation_bound_and_type = use_memo(move || {
         let platforms_bound = platforms_bound();
-        let map = map()?;
+        let map = edit_draft().or(map())?;
+
+        match edit_mode() {
+            EditMode::AutoMobBound => return Some((map.rotation_auto_mob_bound, "AutoMobbing")),
+            EditMode::PingPongBound => return Some((map.rotation_ping_pong_bound, "PingPong")),
+            EditMode::None | EditMode::Platforms => (),
+        }
 
         match map.rotation_mode {
-            RotationMode::StartToEnd | RotationMode::StartToEndThenReverse => None,
+            RotationMode::StartToEnd
+            | RotationMode::StartToEndThenReverse
+            | RotationMode::WeightedRandom => None,
             RotationMode::AutoMobbing => Some((
                 platforms_bound.unwrap_or(map.rotation_auto_mob_bound),
                 "AutoMobbing",
@@ -487,7 +778,7 @@ fn Canvas(
     use_effect(move || {
         let bound_and_type = rotation_bound_and_type();
         let preset = map_preset();
-        let Some(map) = map() else {
+        let Some(map) = edit_draft().or(map()) else {
             return;
         };
         let actions = preset
@@ -524,6 +815,42 @@ fn Canvas(
             ));
         });
     });
+    // Draw or clear the platform graph
+    use_effect(move || {
+        let show_graph = show_graph();
+        let Some(map) = map() else {
+            return;
+        };
+
+        spawn(async move {
+            let graph = if show_graph {
+                query_platform_graph().await
+            } else {
+                None
+            };
+            let canvas = document::eval(MINIMAP_GRAPH_JS);
+            let _ = canvas.send((map.width, map.height, graph));
+        });
+    });
+    // Draw or clear the position heatmap, refreshing it while shown
+    use_future(move || async move {
+        let mut drawn = false;
+        loop {
+            let size = map.peek().as_ref().map(|map| (map.width, map.height));
+            let heatmap = match size {
+                Some(_) if *show_heatmap.peek() => query_position_heatmap().await,
+                _ => None,
+            };
+            if let Some((width, height)) = size
+                && (heatmap.is_some() || drawn)
+            {
+                drawn = heatmap.is_some();
+                let canvas = document::eval(MINIMAP_HEATMAP_JS);
+                let _ = canvas.send((width, height, heatmap));
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+    });
     // Draw map and update game state
     use_future(move || async move {
         let mut canvas = document::eval(MINIMAP_JS);
@@ -539,13 +866,22 @@ fn Canvas(
                 .map(|quadrant| quadrant.to_string());
             let frame = current_state.frame;
             let portals = current_state.portals;
+            let waypoints = current_state.waypoints;
             let current_state = MinimapState {
                 position: current_state.position,
                 health: current_state.health,
+                mana: current_state.mana,
                 state: current_state.state,
                 normal_action: current_state.normal_action,
                 priority_action: current_state.priority_action,
+                next_priority_action: current_state.next_priority_action,
                 erda_shower_state: current_state.erda_shower_state,
+                stranger_count: current_state.stranger_count,
+                crowding_score: current_state.crowding_score,
+                exp: current_state.exp,
+                runes_solved: current_state.runes_solved,
+                runes_failed: current_state.runes_failed,
+                errors: current_state.errors,
                 operation: current_state.operation,
                 detected_size: frame.as_ref().map(|(_, width, height)| (*width, *height)),
             };
@@ -566,9 +902,16 @@ fn Canvas(
             let Some((frame, width, height)) = frame else {
                 continue;
             };
-            let Err(error) =
-                canvas.send((frame, width, height, destinations, bound, quadrant, portals))
-            else {
+            let Err(error) = canvas.send((
+                frame,
+                width,
+                height,
+                destinations,
+                bound,
+                quadrant,
+                portals,
+                waypoints,
+            )) else {
                 continue;
             };
             if matches!(error, EvalError::Finished) {
@@ -578,6 +921,124 @@ fn Canvas(
         }
     });
 
+    // Samples the player dot at the clicked position as a custom player template
+    let calibrate = move |e: MouseEvent| async move {
+        if !*calibrating.peek() {
+            return;
+        }
+        calibrating.set(false);
+
+        let Some((width, height)) = state.peek().as_ref().and_then(|state| state.detected_size)
+        else {
+            return;
+        };
+        let Some(element) = canvas_actions.peek().clone() else {
+            return;
+        };
+        let Ok(rect) = element.get_client_rect().await else {
+            return;
+        };
+        let point = e.element_coordinates();
+        let x = (point.x / rect.width() * width as f64) as i32;
+        let y = height as i32 - (point.y / rect.height() * height as f64) as i32;
+        let Some(base64) = calibrate_player_dot(x, y).await else {
+            return;
+        };
+
+        let current = match localization.peek().clone() {
+            Some(localization) => localization,
+            None => query_localization().await,
+        };
+        let new_localization = Localization {
+            player_dot_base64: Some(base64),
+            ..current
+        };
+        localization.set(Some(upsert_localization(new_localization).await));
+    };
+
+    // Starts drawing a new platform or bound, or dragging the one under the cursor
+    let start_edit = move |e: MouseEvent| async move {
+        let mode = *edit_mode.peek();
+        if mode == EditMode::None
+            || *calibrating.peek()
+            || e.trigger_button() != Some(MouseButton::Primary)
+        {
+            return;
+        }
+        let point = e.element_coordinates();
+        let Some(map) = map.peek().clone() else {
+            return;
+        };
+        let Some(element) = canvas_actions.peek().clone() else {
+            return;
+        };
+        let Ok(rect) = element.get_client_rect().await else {
+            return;
+        };
+        let size = (rect.width(), rect.height());
+        let (x, y) = to_map_point((point.x, point.y), size, &map);
+        let drag = match mode {
+            EditMode::None => return,
+            EditMode::Platforms => hit_platform(&map.platforms, x, map.height - y)
+                .map(EditDrag::Platform)
+                .unwrap_or(EditDrag::Draw),
+            EditMode::AutoMobBound => hit_bound(map.rotation_auto_mob_bound, x, y)
+                .map(EditDrag::Bound)
+                .unwrap_or(EditDrag::Draw),
+            EditMode::PingPongBound => hit_bound(map.rotation_ping_pong_bound, x, y)
+                .map(EditDrag::Bound)
+                .unwrap_or(EditDrag::Draw),
+        };
+
+        edit_drag.set(Some((drag, size, map, (x, y))));
+    };
+    let update_edit = move |e: MouseEvent| {
+        let Some((drag, size, map, from)) = edit_drag.peek().clone() else {
+            return;
+        };
+        let point = e.element_coordinates();
+        let to = to_map_point((point.x, point.y), size, &map);
+        let mode = *edit_mode.peek();
+
+        edit_draft.set(Some(apply_edit_drag(mode, drag, map, from, to)));
+    };
+    let mut finish_edit = move || {
+        edit_drag.set(None);
+        if let Some(edited_map) = edit_draft.take()
+            && let Some(on_edit) = on_edit
+        {
+            on_edit(edited_map);
+        }
+    };
+    // Deletes the platform under the cursor
+    let delete_platform = move |e: MouseEvent| async move {
+        if *edit_mode.peek() != EditMode::Platforms {
+            return;
+        }
+        e.prevent_default();
+        let point = e.element_coordinates();
+        let Some(mut map) = map.peek().clone() else {
+            return;
+        };
+        let Some(element) = canvas_actions.peek().clone() else {
+            return;
+        };
+        let Ok(rect) = element.get_client_rect().await else {
+            return;
+        };
+        let (x, y) = to_map_point((point.x, point.y), (rect.width(), rect.height()), &map);
+        let Some(PlatformHit::Start(index) | PlatformHit::End(index) | PlatformHit::Body(index)) =
+            hit_platform(&map.platforms, x, map.height - y)
+        else {
+            return;
+        };
+
+        map.platforms.remove(index);
+        if let Some(on_edit) = on_edit {
+            on_edit(map);
+        }
+    };
+
     rsx! {
         div { class: "relative h-31 xl:h-38 rounded-2xl bg-secondary-surface",
             canvas {
@@ -586,7 +1047,112 @@ fn Canvas(
             }
             canvas {
                 class: "absolute inset-0 rounded-2xl w-full h-full",
+                id: "canvas-map-heatmap",
+            }
+            canvas {
+                class: "absolute inset-0 rounded-2xl w-full h-full",
+                id: "canvas-map-graph",
+            }
+            canvas {
+                class: "absolute inset-0 rounded-2xl w-full h-full",
+                class: if calibrating() || edit_mode() != EditMode::None { "cursor-crosshair" },
                 id: "canvas-map-actions",
+                onmounted: move |e| {
+                    canvas_actions.set(Some(e.data()));
+                },
+                onclick: calibrate,
+                onmousedown: start_edit,
+                onmousemove: update_edit,
+                onmouseup: move |_| {
+                    finish_edit();
+                },
+                onmouseleave: move |_| {
+                    finish_edit();
+                },
+                oncontextmenu: delete_platform,
+            }
+            if !read_only {
+                Button {
+                    class: "absolute top-1 left-2 rounded",
+                    style: ButtonStyle::Secondary,
+                    on_click: move |_| {
+                        show_graph.toggle();
+                    },
+                    if show_graph() {
+                        "Hide graph"
+                    } else {
+                        "Show graph"
+                    }
+                }
+                Button {
+                    class: "absolute top-1 right-2 rounded",
+                    style: ButtonStyle::Secondary,
+                    disabled: state().and_then(|state| state.detected_size).is_none(),
+                    on_click: move |_| {
+                        calibrating.toggle();
+                    },
+                    if calibrating() {
+                        "Click player dot"
+                    } else {
+                        "Calibrate dot"
+                    }
+                }
+                Button {
+                    class: "absolute bottom-1 right-2 rounded",
+                    style: ButtonStyle::Secondary,
+                    disabled: map().is_none(),
+                    on_click: move |_| {
+                        show_heatmap.toggle();
+                    },
+                    if show_heatmap() {
+                        "Hide heatmap"
+                    } else {
+                        "Show heatmap"
+                    }
+                }
+                Button {
+                    class: "absolute bottom-1 left-2 rounded",
+                    style: ButtonStyle::Secondary,
+                    disabled: map().is_none(),
+                    on_click: move |_| {
+                        edit_mode.set(edit_mode.peek().next());
+                        edit_drag.set(None);
+                        edit_draft.set(None);
+                    },
+                    {
+                        match edit_mode() {
+                            EditMode::None => "Edit map",
+                            EditMode::Platforms => "Editing platforms",
+                            EditMode::AutoMobBound => "Editing mobbing bound",
+                            EditMode::PingPongBound => "Editing ping pong bound",
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn Status(state: ReadSignal<Option<MinimapState>>) -> Element {
+    let errors = use_memo(move || {
+        state()
+            .map(|state| state.errors)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>()
+    });
+
+    rsx! {
+        div { class: "flex items-center px-4 pt-3 gap-2",
+            p { class: "text-sm text-primary-text font-mono", "Status" }
+            if errors().is_empty() {
+                p { class: "flex-grow text-sm text-primary-text text-right font-mono", "OK" }
+            } else {
+                p { class: "flex-grow text-sm text-danger-text text-right font-mono",
+                    {errors().join(", ")}
+                }
             }
         }
     }
@@ -598,10 +1164,16 @@ fn Info(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>) -
     struct GameStateInfo {
         position: String,
         health: String,
+        mana: String,
         state: String,
         normal_action: String,
         priority_action: String,
+        next_priority_action: String,
         erda_shower_state: String,
+        strangers: String,
+        runes: String,
+        exp: String,
+        time_to_level: String,
         detected_map_size: String,
         selected_map_size: String,
         cycle_duration: String,
@@ -611,10 +1183,16 @@ fn Info(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>) -
         let mut info = GameStateInfo {
             position: "Unknown".to_string(),
             health: "Unknown".to_string(),
+            mana: "Unknown".to_string(),
             state: "Unknown".to_string(),
             normal_action: "None".to_string(),
             priority_action: "None".to_string(),
+            next_priority_action: "None".to_string(),
             erda_shower_state: "Unknown".to_string(),
+            strangers: "Unknown".to_string(),
+            runes: "Unknown".to_string(),
+            exp: "Unknown".to_string(),
+            time_to_level: "Unknown".to_string(),
             detected_map_size: "Unknown".to_string(),
             selected_map_size: "Unknown".to_string(),
             cycle_duration: "None".to_string(),
@@ -627,6 +1205,14 @@ fn Info(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>) -
         if let Some(state) = state() {
             info.state = state.state;
             info.erda_shower_state = state.erda_shower_state;
+            info.strangers = format!(
+                "{} (crowding {:.2})",
+                state.stranger_count, state.crowding_score
+            );
+            info.runes = format!(
+                "{} solved / {} failed",
+                state.runes_solved, state.runes_failed
+            );
             info.cycle_duration = match state.operation {
                 BotOperation::Halting | BotOperation::Running => "None".to_string(),
                 BotOperation::TemporaryHalting(duration) => duration_from(duration),
@@ -640,12 +1226,29 @@ fn Info(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>) -
             if let Some((current, max)) = state.health {
                 info.health = format!("{current} / {max}");
             }
+            if let Some((current, max)) = state.mana {
+                info.mana = format!("{current} / {max}");
+            }
             if let Some(action) = state.normal_action {
                 info.normal_action = action;
             }
             if let Some(action) = state.priority_action {
                 info.priority_action = action;
             }
+            if let Some((action, remaining)) = state.next_priority_action {
+                info.next_priority_action = format!("{action} in {}", duration_from(remaining));
+            }
+            if let Some(exp) = state.exp {
+                let level = exp.level.map(|level| format!("Lv. {level} ")).unwrap_or_default();
+                let rate = exp
+                    .percent_per_hour
+                    .map(|rate| format!(" ({rate:.2}%/h)"))
+                    .unwrap_or_default();
+                info.exp = format!("{level}{:.3}%{rate}", exp.percent);
+                if let Some(duration) = exp.time_to_level {
+                    info.time_to_level = duration_from(duration);
+                }
+            }
             if let Some((width, height)) = state.detected_size {
                 info.detected_map_size = format!("{width}px x {height}px")
             }
@@ -659,9 +1262,15 @@ fn Info(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>) -
             InfoItem { name: "State", value: info().state }
             InfoItem { name: "Position", value: info().position }
             InfoItem { name: "Health", value: info().health }
+            InfoItem { name: "Mana", value: info().mana }
             InfoItem { name: "Priority action", value: info().priority_action }
+            InfoItem { name: "Next priority action", value: info().next_priority_action }
             InfoItem { name: "Normal action", value: info().normal_action }
             InfoItem { name: "Erda Shower", value: info().erda_shower_state }
+            InfoItem { name: "Strangers", value: info().strangers }
+            InfoItem { name: "Runes", value: info().runes }
+            InfoItem { name: "EXP", value: info().exp }
+            InfoItem { name: "Time to level", value: info().time_to_level }
             InfoItem { name: "Detected size", value: info().detected_map_size }
             InfoItem { name: "Selected size", value: info().selected_map_size }
             InfoItem { name: "Run/stop cycle", value: info().cycle_duration }
@@ -714,6 +1323,48 @@ fn Buttons(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>
             })
             .unwrap_or("Suspend")
     });
+    // Operation saved before the last restart
+    let mut resumable = use_resource(query_resumable_operation);
+    let resumable_text = use_memo(move || {
+        resumable().flatten().map(|operation| match operation {
+            BotOperation::Halting => "halting".to_string(),
+            BotOperation::Running => "running".to_string(),
+            BotOperation::TemporaryHalting(duration) => {
+                format!("halting temporarily with {} remaining", duration_from(duration))
+            }
+            BotOperation::HaltUntil(instant) => format!(
+                "halting for {}",
+                duration_from(instant.saturating_duration_since(Instant::now()))
+            ),
+            BotOperation::RunUntil(instant) => format!(
+                "running for {}",
+                duration_from(instant.saturating_duration_since(Instant::now()))
+            ),
+        })
+    });
+    let resumable_open = use_memo(move || resumable_text().is_some());
+    // Preset with unsaved edits auto-saved before the last abnormal termination
+    let mut recovery = use_resource(query_preset_recovery);
+    let recovery_open = use_memo(move || recovery().flatten().is_some());
+    // Significant changes since the last run pending confirmation before running again
+    let mut run_changes = use_signal(Vec::<String>::new);
+    let run_changes_open = use_memo(move || !run_changes().is_empty());
+    // Essential detections checked on startup to report ones broken by a game patch
+    let mut self_check = use_signal(|| None::<SelfCheck>);
+    let self_check_failed = use_memo(move || {
+        self_check()
+            .map(|check| {
+                check
+                    .items
+                    .into_iter()
+                    .filter(|item| !item.passed)
+                    .map(|item| item.name)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    });
+    let self_check_open = use_memo(move || !self_check_failed().is_empty());
+    let self_check_score = use_memo(move || self_check().map(|check| check.score()));
     let suspend_resume_disabled = use_memo(move || {
         if disabled() {
             return true;
@@ -728,6 +1379,17 @@ fn Buttons(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>
             .unwrap_or_default()
     });
 
+    use_future(move || async move {
+        // Waits for the first frame to be captured
+        loop {
+            if let Some(check) = run_self_check().await {
+                self_check.set(Some(check));
+                break;
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+    });
+
     rsx! {
         div { class: "flex h-10 justify-center items-center gap-4",
             Button {
@@ -736,11 +1398,20 @@ fn Buttons(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>
                 disabled: disabled(),
                 on_click: move || async move {
                     let kind = match *kind.peek() {
-                        BotOperationUpdate::Halt => BotOperationUpdate::Run,
+                        BotOperationUpdate::Halt | BotOperationUpdate::Schedule(_) => {
+                            BotOperationUpdate::Run
+                        }
                         BotOperationUpdate::TemporaryHalt | BotOperationUpdate::Run => {
                             BotOperationUpdate::Halt
                         }
                     };
+                    if matches!(kind, BotOperationUpdate::Run) {
+                        let changes = query_run_changes().await;
+                        if !changes.is_empty() {
+                            run_changes.set(changes);
+                            return;
+                        }
+                    }
                     update_operation(kind).await;
                 },
                 {start_stop_text()}
@@ -752,9 +1423,9 @@ fn Buttons(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>
                 on_click: move || async move {
                     let kind = match *kind.peek() {
                         BotOperationUpdate::Run => BotOperationUpdate::TemporaryHalt,
-                        BotOperationUpdate::TemporaryHalt | BotOperationUpdate::Halt => {
-                            BotOperationUpdate::Run
-                        }
+                        BotOperationUpdate::TemporaryHalt
+                        | BotOperationUpdate::Halt
+                        | BotOperationUpdate::Schedule(_) => BotOperationUpdate::Run,
                     };
                     update_operation(kind).await;
                 },
@@ -769,6 +1440,127 @@ fn Buttons(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>
                 "Re-detect"
             }
         }
+        PopupContext { open: resumable_open,
+            PopupContent { title: "Resume previous operation",
+                div { class: "flex flex-col w-xs pb-12 text-xs text-primary-text",
+                    p {
+                        "The bot was {resumable_text().unwrap_or_default()} before the app was closed. Select a map and a character to resume."
+                    }
+                }
+                div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
+                    Button {
+                        class: "flex-grow",
+                        style: ButtonStyle::OutlinePrimary,
+                        disabled: disabled(),
+                        on_click: move |_| async move {
+                            resume_operation(true).await;
+                            resumable.restart();
+                        },
+                        "Resume"
+                    }
+                    Button {
+                        class: "flex-grow",
+                        style: ButtonStyle::OutlineSecondary,
+                        on_click: move |_| async move {
+                            resume_operation(false).await;
+                            resumable.restart();
+                        },
+                        "Discard"
+                    }
+                }
+            }
+        }
+        PopupContext { open: recovery_open,
+            PopupContent { title: "Recover unsaved edits",
+                div { class: "flex flex-col w-xs pb-12 text-xs text-primary-text",
+                    if let Some(recovery) = recovery().flatten() {
+                        p {
+                            "The preset {recovery.preset} of map {recovery.map_name} has edits that were not saved before the app was closed. Restore the edits or discard them to keep the saved preset."
+                        }
+                    }
+                }
+                div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
+                    Button {
+                        class: "flex-grow",
+                        style: ButtonStyle::OutlinePrimary,
+                        on_click: move |_| async move {
+                            recover_preset(true).await;
+                            recovery.restart();
+                        },
+                        "Restore"
+                    }
+                    Button {
+                        class: "flex-grow",
+                        style: ButtonStyle::OutlineSecondary,
+                        on_click: move |_| async move {
+                            recover_preset(false).await;
+                            recovery.restart();
+                        },
+                        "Discard"
+                    }
+                }
+            }
+        }
+        PopupContext { open: run_changes_open,
+            PopupContent { title: "Confirm run",
+                div { class: "flex flex-col w-xs pb-12 gap-1 text-xs text-primary-text",
+                    p { "The following changed since the last run:" }
+                    for change in run_changes() {
+                        p { "- {change}" }
+                    }
+                }
+                div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
+                    Button {
+                        class: "flex-grow",
+                        style: ButtonStyle::OutlinePrimary,
+                        on_click: move |_| async move {
+                            run_changes.set(vec![]);
+                            update_operation(BotOperationUpdate::Run).await;
+                        },
+                        "Run"
+                    }
+                    Button {
+                        class: "flex-grow",
+                        style: ButtonStyle::OutlineSecondary,
+                        on_click: move |_| {
+                            run_changes.set(vec![]);
+                        },
+                        "Cancel"
+                    }
+                }
+            }
+        }
+        PopupContext { open: self_check_open,
+            PopupContent { title: "Self-check",
+                div { class: "flex flex-col w-xs pb-12 gap-1 text-xs text-primary-text",
+                    p { "Health score: {self_check_score().unwrap_or_default()}%" }
+                    p {
+                        "The following detections failed on the current frame. If the game is in a map, they may be broken by a game patch:"
+                    }
+                    for name in self_check_failed() {
+                        p { "- {name}" }
+                    }
+                }
+                div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
+                    Button {
+                        class: "flex-grow",
+                        style: ButtonStyle::OutlinePrimary,
+                        on_click: move |_| async move {
+                            self_check.set(run_self_check().await);
+                        },
+                        "Re-run"
+                    }
+                    Button {
+                        class: "flex-grow",
+                        style: ButtonStyle::OutlineSecondary,
+                        on_click: move |_| {
+                            self_check.set(None);
+                        },
+                        "Close"
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -830,3 +1622,150 @@ fn duration_from(duration: Duration) -> String {
 
     format!("{hours:0>2}:{minutes:0>2}:{seconds:0>2}")
 }
+
+/// The distance in minimap pixels within which the cursor hits a platform or bound edge.
+const EDIT_HIT_DISTANCE: i32 = 3;
+
+/// Converts `point` on a canvas of `size` to a minimap point with the origin at the top-left.
+fn to_map_point(point: (f64, f64), size: (f64, f64), map: &Map) -> (i32, i32) {
+    let x = (point.0 / size.0 * map.width as f64) as i32;
+    let y = (point.1 / size.1 * map.height as f64) as i32;
+
+    (x.clamp(0, map.width), y.clamp(0, map.height))
+}
+
+/// Finds the platform under `(x, y)` with `y` having the origin at the bottom-left like platforms.
+///
+/// Platform ends are preferred over the body so that short platforms can still be resized.
+fn hit_platform(platforms: &[Platform], x: i32, y: i32) -> Option<PlatformHit> {
+    let on_platform = |platform: &Platform| (platform.y - y).abs() <= EDIT_HIT_DISTANCE;
+
+    platforms
+        .iter()
+        .enumerate()
+        .filter(|(_, platform)| on_platform(platform))
+        .find_map(|(index, platform)| {
+            if (platform.x_start - x).abs() <= EDIT_HIT_DISTANCE {
+                Some(PlatformHit::Start(index))
+            } else if (platform.x_end - x).abs() <= EDIT_HIT_DISTANCE {
+                Some(PlatformHit::End(index))
+            } else {
+                None
+            }
+        })
+        .or_else(|| {
+            platforms
+                .iter()
+                .position(|platform| {
+                    on_platform(platform) && (platform.x_start..=platform.x_end).contains(&x)
+                })
+                .map(PlatformHit::Body)
+        })
+}
+
+/// Finds the edges of `bound` under `(x, y)` with the origin at the top-left.
+fn hit_bound(bound: Bound, x: i32, y: i32) -> Option<BoundHit> {
+    let (left, top) = (bound.x, bound.y);
+    let (right, bottom) = (bound.x + bound.width, bound.y + bound.height);
+    let within_x = (left - EDIT_HIT_DISTANCE..=right + EDIT_HIT_DISTANCE).contains(&x);
+    let within_y = (top - EDIT_HIT_DISTANCE..=bottom + EDIT_HIT_DISTANCE).contains(&y);
+    if bound.width == 0 || bound.height == 0 || !within_x || !within_y {
+        return None;
+    }
+
+    Some(BoundHit {
+        left: (left - x).abs() <= EDIT_HIT_DISTANCE,
+        right: (right - x).abs() <= EDIT_HIT_DISTANCE,
+        top: (top - y).abs() <= EDIT_HIT_DISTANCE,
+        bottom: (bottom - y).abs() <= EDIT_HIT_DISTANCE,
+    })
+}
+
+/// Applies dragging `from` a point `to` another point to a copy of `map`.
+///
+/// Both points have the origin at the top-left.
+fn apply_edit_drag(
+    mode: EditMode,
+    drag: EditDrag,
+    mut map: Map,
+    from: (i32, i32),
+    to: (i32, i32),
+) -> Map {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let bound = match mode {
+        EditMode::None => return map,
+        EditMode::Platforms => {
+            match drag {
+                EditDrag::Draw => map.platforms.push(Platform {
+                    x_start: from.0.min(to.0),
+                    x_end: from.0.max(to.0),
+                    y: map.height - from.1,
+                }),
+                EditDrag::Platform(PlatformHit::Start(index)) => {
+                    let platform = &mut map.platforms[index];
+                    platform.x_start = to.0;
+                    (platform.x_start, platform.x_end) = (
+                        platform.x_start.min(platform.x_end),
+                        platform.x_start.max(platform.x_end),
+                    );
+                }
+                EditDrag::Platform(PlatformHit::End(index)) => {
+                    let platform = &mut map.platforms[index];
+                    platform.x_end = to.0;
+                    (platform.x_start, platform.x_end) = (
+                        platform.x_start.min(platform.x_end),
+                        platform.x_start.max(platform.x_end),
+                    );
+                }
+                EditDrag::Platform(PlatformHit::Body(index)) => {
+                    let platform = &mut map.platforms[index];
+                    platform.x_start += dx;
+                    platform.x_end += dx;
+                    platform.y -= dy;
+                }
+                EditDrag::Bound(_) => (),
+            }
+            return map;
+        }
+        EditMode::AutoMobBound => &mut map.rotation_auto_mob_bound,
+        EditMode::PingPongBound => &mut map.rotation_ping_pong_bound,
+    };
+
+    let (mut left, mut top) = (bound.x, bound.y);
+    let (mut right, mut bottom) = (bound.x + bound.width, bound.y + bound.height);
+    match drag {
+        EditDrag::Draw => {
+            (left, top) = (from.0, from.1);
+            (right, bottom) = (to.0, to.1);
+        }
+        EditDrag::Bound(hit) if hit == BoundHit::default() => {
+            left += dx;
+            right += dx;
+            top += dy;
+            bottom += dy;
+        }
+        EditDrag::Bound(hit) => {
+            if hit.left {
+                left = to.0;
+            }
+            if hit.right {
+                right = to.0;
+            }
+            if hit.top {
+                top = to.1;
+            }
+            if hit.bottom {
+                bottom = to.1;
+            }
+        }
+        EditDrag::Platform(_) => (),
+    }
+    *bound = Bound {
+        x: left.min(right),
+        y: top.min(bottom),
+        width: (right - left).abs(),
+        height: (bottom - top).abs(),
+    };
+
+    map
+}
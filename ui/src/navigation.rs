@@ -1,10 +1,10 @@
 use std::fmt::Display;
 
 use backend::{
-    DatabaseEvent, NavigationPath, NavigationPaths, NavigationPoint, NavigationTransition,
+    DatabaseEvent, Map, NavigationPath, NavigationPaths, NavigationPoint, NavigationTransition,
     create_navigation_path, database_event_receiver, delete_navigation_paths,
     navigation_snapshot_as_grayscale, query_navigation_paths, recapture_navigation_path,
-    upsert_map, upsert_navigation_paths,
+    upsert_map_with, upsert_navigation_paths,
 };
 use dioxus::prelude::*;
 use futures_util::StreamExt;
@@ -85,11 +85,13 @@ pub fn NavigationScreen() -> Element {
                         }
                     }
                     NavigationUpdate::Attach(paths_id_index) => {
-                        let Some(mut current_map) = map() else {
+                        let Some(current_map) = map() else {
                             continue;
                         };
-                        current_map.paths_id_index = paths_id_index;
-                        if let Some(current_map) = upsert_map(current_map).await {
+                        let edit = move |map: &mut Map| {
+                            map.paths_id_index = paths_id_index;
+                        };
+                        if let Some(current_map) = upsert_map_with(current_map, edit).await {
                             map.set(Some(current_map));
                         }
                     }
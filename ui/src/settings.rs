@@ -1,15 +1,17 @@
 use std::{fmt::Display, mem};
 
 use backend::{
-    CaptureMode, CycleRunStopMode, InputMethod, IntoEnumIterator, KeyBinding,
-    KeyBindingConfiguration, Notifications, Settings, query_capture_handles, query_settings,
-    refresh_capture_handles, select_capture_handle, upsert_settings,
+    AdminReaction, CaptureMode, CycleRunStopMode, DuoMode, InputMethod, IntoEnumIterator,
+    KeyBinding, KeyBindingConfiguration, KeyRemap, KeyboardLayout, MAX_INSTANCES, Notifications,
+    OperationSchedule, OperationScheduleMode, PRIMARY_INSTANCE, PlayerRequestPolicy, Settings,
+    add_instance, export_rune_dataset, instance_count, query_capture_handles, query_settings,
+    refresh_capture_handles, select_capture_handle, select_instance, upsert_settings,
 };
 use dioxus::{html::FileData, prelude::*};
 use futures_util::StreamExt;
 
 use crate::{
-    AppState,
+    AppState, InstanceState,
     components::{
         button::{Button, ButtonStyle},
         checkbox::Checkbox,
@@ -17,7 +19,7 @@ use crate::{
         icons::{EyePasswordHideIcon, EyePasswordShowIcon},
         key::KeyInput,
         labeled::Labeled,
-        numbers::MillisInput,
+        numbers::{MillisInput, PercentageInput, PrimitiveIntegerInput},
         section::Section,
         select::{Select, SelectOption},
         text::TextInput,
@@ -74,7 +76,19 @@ pub fn SettingsScreen() -> Element {
             SectionInput {}
             SectionControlAndNotifications {}
             SectionHotkeys {}
+            SectionKeyRemaps {}
             SectionRunStopCycle {}
+            SectionOperationSchedule {}
+            SectionParty {}
+            SectionAdminReaction {}
+            SectionIdleWatchdog {}
+            SectionPowerSaving {}
+            SectionSpectate {}
+            SectionDashboard {}
+            SectionStreaming {}
+            SectionDuo {}
+            SectionRemoteControl {}
+            SectionRuneDataset {}
             SectionOthers {}
         }
     }
@@ -86,6 +100,14 @@ fn SectionCapture() -> Element {
     let settings = context.settings;
     let save_settings = context.save_settings;
 
+    let mut instance = use_context::<InstanceState>().instance;
+    let mut count = use_signal(instance_count);
+    let instance_names = use_memo(move || {
+        (1..=count())
+            .map(|index| format!("Instance {index}"))
+            .collect::<Vec<_>>()
+    });
+
     let mut selected_handle_index = use_signal(|| None);
     let mut handle_names = use_resource(move || async move {
         let (names, selected) = query_capture_handles().await;
@@ -102,6 +124,27 @@ fn SectionCapture() -> Element {
     rsx! {
         Section { title: "Capture",
             div { class: "grid grid-cols-2 gap-3",
+                SettingsSelect {
+                    label: "Instance",
+                    options: instance_names(),
+                    on_selected: move |index| {
+                        select_instance(index);
+                        instance.set(index);
+                    },
+                    selected: instance(),
+                }
+                Button {
+                    style: ButtonStyle::Secondary,
+                    on_click: move |_| {
+                        if let Some(index) = add_instance() {
+                            count.set(index + 1);
+                        }
+                    },
+                    disabled: count() >= MAX_INSTANCES,
+                    class: "self-end",
+
+                    "Add instance"
+                }
                 SettingsSelect {
                     label: "Handle",
                     options: handle_names_with_default(),
@@ -126,6 +169,32 @@ fn SectionCapture() -> Element {
                     },
                     selected: settings().capture_mode,
                 }
+                SettingsCheckbox {
+                    label: "Sync to monitor refresh",
+                    on_checked: move |capture_sync_monitor_refresh| {
+                        save_settings(Settings {
+                            capture_sync_monitor_refresh,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().capture_sync_monitor_refresh,
+                }
+                Labeled {
+                    label: "Target FPS",
+                    tooltip: "Only applies to Windows Graphics Capture. Frames arriving faster than this rate are dropped.",
+                    PrimitiveIntegerInput {
+                        on_value: move |capture_target_fps| {
+                            save_settings(Settings {
+                                capture_target_fps,
+                                ..settings.peek().clone()
+                            });
+                        },
+                        value: settings().capture_target_fps,
+                        min_value: 1,
+                        max_value: 240,
+                        disabled: settings().capture_sync_monitor_refresh,
+                    }
+                }
             }
             Button {
                 style: ButtonStyle::Secondary,
@@ -137,6 +206,11 @@ fn SectionCapture() -> Element {
 
                 "Refresh handles"
             }
+            if count() > 1 {
+                p { class: "text-xs text-secondary-text mt-2",
+                    "Only Instance 1 serves spectators, the dashboard, the status file and remote control, joins duo mode, runs the Discord and Telegram bots and resumes its operation on restart. These settings are disabled while another instance is selected."
+                }
+            }
         }
     }
 }
@@ -171,6 +245,37 @@ fn SectionInput() -> Element {
                     },
                     value: settings().input_method_rpc_server_url,
                 }
+                SettingsEnumSelect::<KeyboardLayout> {
+                    label: "Keyboard layout",
+                    on_selected: move |keyboard_layout| async move {
+                        save_settings(Settings {
+                            keyboard_layout,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    selected: settings().keyboard_layout,
+                }
+                SettingsCheckbox {
+                    label: "Repeat held keys",
+                    on_checked: move |enable_key_repeat| {
+                        save_settings(Settings {
+                            enable_key_repeat,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_key_repeat,
+                }
+                SettingsMillisInput {
+                    label: "Key repeat interval",
+                    on_value: move |key_repeat_interval_millis| {
+                        save_settings(Settings {
+                            key_repeat_interval_millis,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().key_repeat_interval_millis,
+                    disabled: !settings().enable_key_repeat,
+                }
             }
         }
     }
@@ -227,6 +332,35 @@ fn SectionControlAndNotifications() -> Element {
                     },
                     value: notifications().discord_user_id,
                 }
+                SettingsTextInput {
+                    text_label: "Telegram bot token",
+                    button_label: "Update",
+                    sensitive: true,
+                    on_value: move |telegram_bot_token| {
+                        save_settings(Settings {
+                            notifications: Notifications {
+                                telegram_bot_token,
+                                ..notifications.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: notifications().telegram_bot_token,
+                }
+                SettingsTextInput {
+                    text_label: "Telegram chat ID",
+                    button_label: "Update",
+                    on_value: move |telegram_chat_id| {
+                        save_settings(Settings {
+                            notifications: Notifications {
+                                telegram_chat_id,
+                                ..notifications.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: notifications().telegram_chat_id,
+                }
             }
             div { class: "grid grid-cols-3 gap-3",
                 SettingsCheckbox {
@@ -346,6 +480,111 @@ fn SectionControlAndNotifications() -> Element {
                     },
                     checked: notifications().notify_on_cycle_run_stop,
                 }
+                SettingsCheckbox {
+                    label: "Admin appears",
+                    on_checked: move |notify_on_admin_appear| {
+                        save_settings(Settings {
+                            notifications: Notifications {
+                                notify_on_admin_appear,
+                                ..notifications.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: notifications().notify_on_admin_appear,
+                }
+                SettingsCheckbox {
+                    label: "Player idle",
+                    on_checked: move |notify_on_player_idle| {
+                        save_settings(Settings {
+                            notifications: Notifications {
+                                notify_on_player_idle,
+                                ..notifications.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: notifications().notify_on_player_idle,
+                }
+                SettingsCheckbox {
+                    label: "Rune solve fails",
+                    on_checked: move |notify_on_rune_solve_fail| {
+                        save_settings(Settings {
+                            notifications: Notifications {
+                                notify_on_rune_solve_fail,
+                                ..notifications.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: notifications().notify_on_rune_solve_fail,
+                }
+                SettingsCheckbox {
+                    label: "Bot stops",
+                    on_checked: move |notify_on_bot_halt| {
+                        save_settings(Settings {
+                            notifications: Notifications {
+                                notify_on_bot_halt,
+                                ..notifications.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: notifications().notify_on_bot_halt,
+                }
+                SettingsCheckbox {
+                    label: "Health is low",
+                    on_checked: move |notify_on_player_health_low| {
+                        save_settings(Settings {
+                            notifications: Notifications {
+                                notify_on_player_health_low,
+                                ..notifications.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: notifications().notify_on_player_health_low,
+                }
+                Labeled { label: "Low health threshold",
+                    PercentageInput {
+                        on_value: move |player_health_low_percent| {
+                            save_settings(Settings {
+                                notifications: Notifications {
+                                    player_health_low_percent,
+                                    ..notifications.peek().clone()
+                                },
+                                ..settings.peek().clone()
+                            });
+                        },
+                        value: notifications().player_health_low_percent,
+                    }
+                }
+                SettingsCheckbox {
+                    label: "Potion stock is low",
+                    on_checked: move |notify_on_potion_stock_low| {
+                        save_settings(Settings {
+                            notifications: Notifications {
+                                notify_on_potion_stock_low,
+                                ..notifications.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: notifications().notify_on_potion_stock_low,
+                }
+                SettingsCheckbox {
+                    label: "Stopped by trade or duel request",
+                    on_checked: move |notify_on_player_request| {
+                        save_settings(Settings {
+                            notifications: Notifications {
+                                notify_on_player_request,
+                                ..notifications.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: notifications().notify_on_player_request,
+                }
             }
         }
     }
@@ -438,6 +677,70 @@ fn SectionHotkeys() -> Element {
     }
 }
 
+#[component]
+fn SectionKeyRemaps() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+
+    let save_key_remaps = use_callback(move |key_remaps: Vec<KeyRemap>| {
+        save_settings(Settings {
+            key_remaps,
+            ..settings.peek().clone()
+        });
+    });
+    let update_key_remap = use_callback(move |(key_remap, index): (KeyRemap, usize)| {
+        let mut key_remaps = settings.peek().key_remaps.clone();
+        key_remaps[index] = key_remap;
+        save_key_remaps(key_remaps);
+    });
+
+    rsx! {
+        Section { title: "Key remaps",
+            div { class: "grid grid-cols-3 gap-3",
+                for (index , key_remap) in settings().key_remaps.into_iter().enumerate() {
+                    SettingsKeyInput {
+                        label: "Stored key",
+                        class: "",
+                        on_value: move |from| {
+                            update_key_remap((KeyRemap { from, ..key_remap }, index));
+                        },
+                        value: key_remap.from,
+                    }
+                    SettingsKeyInput {
+                        label: "In-game key",
+                        class: "",
+                        on_value: move |to| {
+                            update_key_remap((KeyRemap { to, ..key_remap }, index));
+                        },
+                        value: key_remap.to,
+                    }
+                    Button {
+                        class: "self-end",
+                        style: ButtonStyle::Danger,
+                        on_click: move |_| {
+                            let mut key_remaps = settings.peek().key_remaps.clone();
+                            key_remaps.remove(index);
+                            save_key_remaps(key_remaps);
+                        },
+                        "Delete"
+                    }
+                }
+            }
+            Button {
+                class: "mt-3",
+                style: ButtonStyle::Secondary,
+                on_click: move |_| {
+                    let mut key_remaps = settings.peek().key_remaps.clone();
+                    key_remaps.push(KeyRemap::default());
+                    save_key_remaps(key_remaps);
+                },
+                "Add remap"
+            }
+        }
+    }
+}
+
 #[component]
 fn SectionRunStopCycle() -> Element {
     let context = use_context::<SettingsContext>();
@@ -483,77 +786,721 @@ fn SectionRunStopCycle() -> Element {
 }
 
 #[component]
-fn SectionOthers() -> Element {
+fn SectionOperationSchedule() -> Element {
     let context = use_context::<SettingsContext>();
     let settings = context.settings;
     let save_settings = context.save_settings;
+    let schedule = use_memo(move || settings().operation_schedule);
+    let start_disabled =
+        use_memo(move || !matches!(schedule().mode, OperationScheduleMode::Window));
+    let end_disabled = use_memo(move || matches!(schedule().mode, OperationScheduleMode::None));
 
-    let import_settings = use_callback(move |file: FileData| async move {
-        let Some(id) = settings.peek().id else {
-            return;
-        };
-        let Ok(bytes) = file.read_bytes().await else {
-            return;
-        };
-        let Ok(mut settings) = serde_json::from_slice::<'_, Settings>(&bytes) else {
-            return;
-        };
-        settings.id = Some(id);
-        save_settings(settings);
+    let save_schedule = use_callback(move |operation_schedule: OperationSchedule| {
+        save_settings(Settings {
+            operation_schedule,
+            ..settings.peek().clone()
+        });
     });
 
     rsx! {
-        Section { title: "Others",
-            div { class: "grid grid-cols-2 gap-3",
+        Section { title: "Schedule",
+            div { class: "grid grid-cols-2 gap-3 mb-2",
+                SettingsEnumSelect::<OperationScheduleMode> {
+                    label: "Mode",
+                    on_selected: move |mode| {
+                        save_schedule(OperationSchedule {
+                            mode,
+                            ..*schedule.peek()
+                        });
+                    },
+                    selected: schedule().mode,
+                }
+                Labeled {
+                    label: "UTC offset (minutes)",
+                    tooltip: "Offset of the time zone the schedule times are in from UTC.",
+                    PrimitiveIntegerInput {
+                        on_value: move |utc_offset_minutes| {
+                            save_schedule(OperationSchedule {
+                                utc_offset_minutes,
+                                ..*schedule.peek()
+                            });
+                        },
+                        value: schedule().utc_offset_minutes,
+                        min_value: -720,
+                        max_value: 840,
+                        disabled: end_disabled(),
+                    }
+                }
+            }
+            div { class: "grid grid-cols-4 gap-3",
+                ScheduleTimeInputs {
+                    label: "Start",
+                    disabled: start_disabled(),
+                    on_value: move |start_minute| {
+                        save_schedule(OperationSchedule {
+                            start_minute,
+                            ..*schedule.peek()
+                        });
+                    },
+                    value: schedule().start_minute,
+                }
+                ScheduleTimeInputs {
+                    label: "End",
+                    disabled: end_disabled(),
+                    on_value: move |end_minute| {
+                        save_schedule(OperationSchedule {
+                            end_minute,
+                            ..*schedule.peek()
+                        });
+                    },
+                    value: schedule().end_minute,
+                }
+            }
+        }
+    }
+}
+
+/// Hour and minute inputs for a minute of the day.
+#[component]
+fn ScheduleTimeInputs(
+    label: &'static str,
+    disabled: bool,
+    on_value: Callback<u32>,
+    value: u32,
+) -> Element {
+    let hour = value / 60;
+    let minute = value % 60;
+
+    rsx! {
+        Labeled { label: format!("{label} hour"),
+            PrimitiveIntegerInput {
+                on_value: move |hour: u32| {
+                    on_value(hour * 60 + minute);
+                },
+                value: hour,
+                max_value: 23,
+                disabled,
+            }
+        }
+        Labeled { label: format!("{label} minute"),
+            PrimitiveIntegerInput {
+                on_value: move |minute: u32| {
+                    on_value(hour * 60 + minute);
+                },
+                value: minute,
+                max_value: 59,
+                disabled,
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionParty() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+
+    rsx! {
+        Section { title: "Party",
+            div { class: "grid grid-cols-3 gap-3",
                 SettingsCheckbox {
-                    label: "Enable rune solving",
-                    on_checked: move |enable_rune_solving| {
+                    label: "Auto-accept invites",
+                    on_checked: move |enable_party_invite_auto_accept| {
                         save_settings(Settings {
-                            enable_rune_solving,
+                            enable_party_invite_auto_accept,
                             ..settings.peek().clone()
                         });
                     },
-                    checked: settings().enable_rune_solving,
+                    checked: settings().enable_party_invite_auto_accept,
                 }
-                SettingsCheckbox {
-                    label: "Enable transparent shape solving",
-                    on_checked: move |enable_transparent_shape_solving| {
+                SettingsTextInput {
+                    text_label: "Whitelisted names (comma-separated)",
+                    button_label: "Update",
+                    on_value: move |names: String| {
                         save_settings(Settings {
-                            enable_transparent_shape_solving,
+                            party_invite_whitelist: names
+                                .split(',')
+                                .map(|name| name.trim().to_string())
+                                .filter(|name| !name.is_empty())
+                                .collect(),
                             ..settings.peek().clone()
                         });
                     },
-                    checked: settings().enable_transparent_shape_solving,
+                    value: settings().party_invite_whitelist.join(", "),
                 }
-                SettingsCheckbox {
-                    label: "Enable panic mode",
-                    on_checked: move |enable_panic_mode| {
+                SettingsEnumSelect::<PlayerRequestPolicy> {
+                    label: "Trade or duel requests",
+                    on_selected: move |player_request_policy| {
                         save_settings(Settings {
-                            enable_panic_mode,
+                            player_request_policy,
                             ..settings.peek().clone()
                         });
                     },
-                    checked: settings().enable_panic_mode,
+                    selected: settings().player_request_policy,
                 }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionAdminReaction() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let reaction = use_memo(move || settings().admin_reaction);
+
+    rsx! {
+        Section { title: "Admin reaction",
+            div { class: "grid grid-cols-2 gap-3 mb-2",
                 SettingsCheckbox {
-                    label: "Stop actions on fail or map changed",
-                    on_checked: move |stop_on_fail_or_change_map| {
+                    label: "Stop actions on admin detected",
+                    on_checked: move |enabled| {
                         save_settings(Settings {
-                            stop_on_fail_or_change_map,
+                            admin_reaction: AdminReaction {
+                                enabled,
+                                ..reaction.peek().clone()
+                            },
                             ..settings.peek().clone()
                         });
                     },
-                    checked: settings().stop_on_fail_or_change_map,
+                    checked: reaction().enabled,
                 }
                 SettingsCheckbox {
-                    label: "Stop actions on player dies",
-                    on_checked: move |stop_on_player_die| {
+                    label: "Send chat after stopping",
+                    disabled: !reaction().enabled,
+                    on_checked: move |chat_enabled| {
                         save_settings(Settings {
-                            stop_on_player_die,
+                            admin_reaction: AdminReaction {
+                                chat_enabled,
+                                ..reaction.peek().clone()
+                            },
                             ..settings.peek().clone()
                         });
                     },
-                    checked: settings().stop_on_player_die,
+                    checked: reaction().chat_enabled,
+                }
+                SettingsMillisInput {
+                    label: "Chat delay",
+                    disabled: !reaction().enabled || !reaction().chat_enabled,
+                    on_value: move |chat_delay_millis| {
+                        save_settings(Settings {
+                            admin_reaction: AdminReaction {
+                                chat_delay_millis,
+                                ..reaction.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: reaction().chat_delay_millis,
+                }
+                SettingsMillisInput {
+                    label: "Chat delay random range",
+                    disabled: !reaction().enabled || !reaction().chat_enabled,
+                    on_value: move |chat_delay_millis_random_range| {
+                        save_settings(Settings {
+                            admin_reaction: AdminReaction {
+                                chat_delay_millis_random_range,
+                                ..reaction.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: reaction().chat_delay_millis_random_range,
+                }
+            }
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsTextInput {
+                    text_label: "Chat content",
+                    button_label: "Update",
+                    on_value: move |chat_content| {
+                        save_settings(Settings {
+                            admin_reaction: AdminReaction {
+                                chat_content,
+                                ..reaction.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: reaction().chat_content,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionIdleWatchdog() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+
+    rsx! {
+        Section { title: "Idle watchdog",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsCheckbox {
+                    label: "Unstuck when player is idle",
+                    on_checked: move |enable_idle_watchdog| {
+                        save_settings(Settings {
+                            enable_idle_watchdog,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_idle_watchdog,
+                }
+                SettingsMillisInput {
+                    label: "Idle duration",
+                    on_value: move |idle_watchdog_millis| {
+                        save_settings(Settings {
+                            idle_watchdog_millis,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().idle_watchdog_millis,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionPowerSaving() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+
+    rsx! {
+        Section { title: "Power saving",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsCheckbox {
+                    label: "Throttle on battery or high CPU temperature",
+                    on_checked: move |enable_power_saving| {
+                        save_settings(Settings {
+                            enable_power_saving,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_power_saving,
+                }
+                Labeled {
+                    label: "CPU temperature threshold (°C)",
+                    tooltip: "CPU temperature is only available when running as administrator.",
+                    PrimitiveIntegerInput {
+                        on_value: move |power_saving_cpu_temperature_threshold| {
+                            save_settings(Settings {
+                                power_saving_cpu_temperature_threshold,
+                                ..settings.peek().clone()
+                            });
+                        },
+                        value: settings().power_saving_cpu_temperature_threshold,
+                        min_value: 1,
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionSpectate() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let non_primary = *use_context::<InstanceState>().instance.read() != PRIMARY_INSTANCE;
+
+    rsx! {
+        Section { title: "Spectate",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsCheckbox {
+                    label: "Serve spectators (requires restart)",
+                    disabled: non_primary,
+                    on_checked: move |enable_spectate_server| {
+                        save_settings(Settings {
+                            enable_spectate_server,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_spectate_server,
+                }
+                SettingsTextInput {
+                    text_label: "Address",
+                    button_label: "Update",
+                    on_value: move |spectate_server_address| {
+                        save_settings(Settings {
+                            spectate_server_address,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().spectate_server_address,
+                }
+            }
+            p { class: "text-xs text-secondary-text mt-2",
+                "Launch another instance with `--spectate <address>` to watch in read-only mode."
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionDashboard() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let non_primary = *use_context::<InstanceState>().instance.read() != PRIMARY_INSTANCE;
+
+    rsx! {
+        Section { title: "Dashboard",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsCheckbox {
+                    label: "Serve web dashboard (requires restart)",
+                    disabled: non_primary,
+                    on_checked: move |enable_dashboard| {
+                        save_settings(Settings {
+                            enable_dashboard,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_dashboard,
+                }
+                SettingsTextInput {
+                    text_label: "Address",
+                    button_label: "Update",
+                    on_value: move |dashboard_address| {
+                        save_settings(Settings {
+                            dashboard_address,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().dashboard_address,
+                }
+            }
+            p { class: "text-xs text-secondary-text mt-2",
+                "Open `http://<address>` in a browser to monitor the minimap, operation, position and health. There is no authentication so only use addresses reachable from a trusted network."
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionStreaming() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let non_primary = *use_context::<InstanceState>().instance.read() != PRIMARY_INSTANCE;
+
+    rsx! {
+        Section { title: "Streaming",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsCheckbox {
+                    label: "Write status file (requires restart)",
+                    disabled: non_primary,
+                    on_checked: move |enable_status_file| {
+                        save_settings(Settings {
+                            enable_status_file,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_status_file,
+                }
+                SettingsTextInput {
+                    text_label: "Path",
+                    button_label: "Update",
+                    on_value: move |status_file_path| {
+                        save_settings(Settings {
+                            status_file_path,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().status_file_path,
+                }
+                SettingsCheckbox {
+                    label: "Show status as window title",
+                    on_checked: move |enable_status_window_title| {
+                        save_settings(Settings {
+                            enable_status_window_title,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_status_window_title,
+                }
+            }
+            p { class: "text-xs text-secondary-text mt-2",
+                "Use the status file as a text source in streaming or recording tools. Its first line starts with `On break` while the bot halts for a break so that a script watching the file can pause recording. A relative path is relative to the app folder."
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionDuo() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let non_primary = *use_context::<InstanceState>().instance.read() != PRIMARY_INSTANCE;
+
+    rsx! {
+        Section { title: "Duo",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsEnumSelect::<DuoMode> {
+                    label: "Mode (requires restart)",
+                    disabled: non_primary,
+                    on_selected: move |duo_mode| {
+                        save_settings(Settings {
+                            duo_mode,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    selected: settings().duo_mode,
+                }
+                SettingsTextInput {
+                    text_label: "Host address",
+                    button_label: "Update",
+                    on_value: move |duo_address| {
+                        save_settings(Settings {
+                            duo_address,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().duo_address,
+                }
+                SettingsTextInput {
+                    text_label: "Host token",
+                    button_label: "Update",
+                    sensitive: true,
+                    on_value: move |duo_token| {
+                        save_settings(Settings {
+                            duo_token,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().duo_token,
+                }
+            }
+            p { class: "text-xs text-secondary-text mt-2",
+                "Coordinates with another instance on the same map to stagger buffs, split auto mobbing quadrants and take turns solving runes. The host accepts the peer through its remote control server, which must be served. The joining instance connects to the host's remote control address with its token and does not support TLS."
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionRemoteControl() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let non_primary = *use_context::<InstanceState>().instance.read() != PRIMARY_INSTANCE;
+
+    rsx! {
+        Section { title: "Remote control",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsCheckbox {
+                    label: "Serve remote control (requires restart)",
+                    disabled: non_primary,
+                    on_checked: move |enable_remote_control| {
+                        save_settings(Settings {
+                            enable_remote_control,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_remote_control,
+                }
+                SettingsTextInput {
+                    text_label: "Address",
+                    button_label: "Update",
+                    on_value: move |remote_control_address| {
+                        save_settings(Settings {
+                            remote_control_address,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().remote_control_address,
+                }
+                SettingsTextInput {
+                    text_label: "Token",
+                    button_label: "Update",
+                    sensitive: true,
+                    on_value: move |remote_control_token| {
+                        save_settings(Settings {
+                            remote_control_token,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().remote_control_token,
+                }
+                div {}
+                SettingsTextInput {
+                    text_label: "TLS certificate path",
+                    button_label: "Update",
+                    on_value: move |remote_control_tls_cert_path| {
+                        save_settings(Settings {
+                            remote_control_tls_cert_path,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().remote_control_tls_cert_path,
+                }
+                SettingsTextInput {
+                    text_label: "TLS private key path",
+                    button_label: "Update",
+                    on_value: move |remote_control_tls_key_path| {
+                        save_settings(Settings {
+                            remote_control_tls_key_path,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().remote_control_tls_key_path,
+                }
+            }
+            p { class: "text-xs text-secondary-text mt-2",
+                "Serves the gRPC service from `proto/control.proto` to start and stop the bot, select the capture window and stream the game state from another device. Clients must send the token as `authorization: Bearer <token>`. Without a token, only loopback addresses are served. TLS is used when both PEM paths are set."
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionRuneDataset() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let mut exported = use_signal(String::default);
+
+    rsx! {
+        Section { title: "Rune dataset",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsCheckbox {
+                    label: "Save solved rune crops",
+                    on_checked: move |enable_rune_dataset| {
+                        save_settings(Settings {
+                            enable_rune_dataset,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_rune_dataset,
+                }
+                Button {
+                    style: ButtonStyle::Secondary,
+                    on_click: move |_| async move {
+                        let path = export_rune_dataset().await;
+                        exported.set(path.unwrap_or_else(|| "Nothing to export".to_string()));
+                    },
+
+                    "Export"
+                }
+            }
+            p { class: "text-xs text-secondary-text mt-2",
+                "Saves the rune arrows cropped from the game with the solver output and whether the rune buff appeared to a local dataset folder. Export and share the dataset to help improve the rune model."
+            }
+            if !exported().is_empty() {
+                p { class: "text-xs text-primary-text mt-2 break-all", {exported()} }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionOthers() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+
+    let import_settings = use_callback(move |file: FileData| async move {
+        let Some(id) = settings.peek().id else {
+            return;
+        };
+        let Ok(bytes) = file.read_bytes().await else {
+            return;
+        };
+        let Ok(mut settings) = serde_json::from_slice::<'_, Settings>(&bytes) else {
+            return;
+        };
+        settings.id = Some(id);
+        save_settings(settings);
+    });
+
+    rsx! {
+        Section { title: "Others",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsCheckbox {
+                    label: "Enable rune solving",
+                    on_checked: move |enable_rune_solving| {
+                        save_settings(Settings {
+                            enable_rune_solving,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_rune_solving,
+                }
+                SettingsCheckbox {
+                    label: "Enable transparent shape solving",
+                    on_checked: move |enable_transparent_shape_solving| {
+                        save_settings(Settings {
+                            enable_transparent_shape_solving,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_transparent_shape_solving,
+                }
+                SettingsCheckbox {
+                    label: "Enable panic mode",
+                    on_checked: move |enable_panic_mode| {
+                        save_settings(Settings {
+                            enable_panic_mode,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_panic_mode,
+                }
+                SettingsCheckbox {
+                    label: "Stop actions on fail or map changed",
+                    on_checked: move |stop_on_fail_or_change_map| {
+                        save_settings(Settings {
+                            stop_on_fail_or_change_map,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().stop_on_fail_or_change_map,
+                }
+                SettingsCheckbox {
+                    label: "Stop actions on player dies",
+                    on_checked: move |stop_on_player_die| {
+                        save_settings(Settings {
+                            stop_on_player_die,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().stop_on_player_die,
+                }
+                SettingsCheckbox {
+                    label: "Auto-detect character from in-game name",
+                    on_checked: move |enable_character_auto_detect| {
+                        save_settings(Settings {
+                            enable_character_auto_detect,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_character_auto_detect,
+                }
+                SettingsCheckbox {
+                    label: "Verify character on run",
+                    on_checked: move |enable_character_verification| {
+                        save_settings(Settings {
+                            enable_character_verification,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_character_verification,
+                }
+                SettingsCheckbox {
+                    label: "Match templates on GPU (OpenCL)",
+                    on_checked: move |enable_opencl| {
+                        save_settings(Settings {
+                            enable_opencl,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_opencl,
                 }
                 div {}
                 FileInput {
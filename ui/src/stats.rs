@@ -0,0 +1,178 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use backend::{SessionStats, query_session_stats, rotation_suggestions};
+use dioxus::prelude::*;
+use tokio::time::sleep;
+
+use crate::components::section::Section;
+
+/// How often the recorded sessions are queried again.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+#[component]
+pub fn StatsScreen() -> Element {
+    let mut sessions = use_signal(Vec::<SessionStats>::new);
+
+    use_future(move || async move {
+        loop {
+            if let Some(queried) = query_session_stats().await {
+                sessions.set(queried);
+            }
+            sleep(REFRESH_INTERVAL).await;
+        }
+    });
+
+    rsx! {
+        div { class: "flex flex-col h-full overflow-y-auto",
+            SectionLatestSession { session: sessions().into_iter().next() }
+            SectionSuggestions { session: sessions().into_iter().next() }
+            SectionSessions { sessions: sessions() }
+        }
+    }
+}
+
+#[component]
+fn SectionLatestSession(session: Option<SessionStats>) -> Element {
+    let Some(session) = session else {
+        return rsx! {
+            Section { title: "Latest session",
+                p { class: "text-xs text-secondary-text", "No session recorded yet." }
+            }
+        };
+    };
+    let mut actions = session
+        .actions_per_preset
+        .iter()
+        .map(|(preset, count)| (preset.clone(), *count))
+        .collect::<Vec<_>>();
+    actions.sort_by(|a, b| a.0.cmp(&b.0));
+    let exp = session.exp_samples.last().map(|sample| {
+        let level = sample
+            .level
+            .map_or("?".to_string(), |level| level.to_string());
+        let percent = sample
+            .exp_percent
+            .map_or("?".to_string(), |percent| format!("{percent:.3}"));
+        format!("Lv. {level} ({percent}%)")
+    });
+
+    rsx! {
+        Section { title: "Latest session",
+            div { class: "grid grid-cols-2 gap-1",
+                Stat { name: "Started", value: format_started(session.started_millis) }
+                Stat { name: "Runtime", value: format_runtime(session.runtime_millis) }
+                Stat { name: "Runes solved", value: session.runes_solved.to_string() }
+                Stat { name: "Runes failed", value: session.runes_failed.to_string() }
+                Stat { name: "Deaths", value: session.deaths.to_string() }
+                Stat { name: "Channel changes", value: session.channel_changes.to_string() }
+                Stat { name: "Mobs detected", value: session.mob_detections.to_string() }
+                Stat { name: "EXP", value: exp.unwrap_or("Unknown".to_string()) }
+                for (preset , count) in actions {
+                    Stat { name: "Actions of {preset}", value: count.to_string() }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionSuggestions(session: Option<SessionStats>) -> Element {
+    let suggestions = session
+        .map(|session| rotation_suggestions(&session))
+        .unwrap_or_default();
+
+    rsx! {
+        Section { title: "Rotation suggestions",
+            if suggestions.is_empty() {
+                p { class: "text-xs text-secondary-text", "No suggestion yet." }
+            }
+            div { class: "flex flex-col gap-1",
+                for suggestion in suggestions {
+                    p { class: "text-xs text-secondary-text", {suggestion.to_string()} }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionSessions(sessions: Vec<SessionStats>) -> Element {
+    #[component]
+    fn Header(title: &'static str) -> Element {
+        rsx! {
+            th { class: "text-xs text-primary-text text-left font-medium border-b border-primary-border",
+                {title}
+            }
+        }
+    }
+
+    #[component]
+    fn Data(value: String) -> Element {
+        rsx! {
+            td { class: "text-xs text-secondary-text border-b border-secondary-border pt-2 pr-1",
+                {value}
+            }
+        }
+    }
+
+    let rows = sessions
+        .into_iter()
+        .map(|session| {
+            [
+                format_started(session.started_millis),
+                format_runtime(session.runtime_millis),
+                format!("{} / {}", session.runes_solved, session.runes_failed),
+                session.deaths.to_string(),
+                session.channel_changes.to_string(),
+                session.actions_per_preset.values().sum::<u32>().to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    rsx! {
+        Section { title: "Sessions",
+            table { class: "table-fixed",
+                thead {
+                    tr {
+                        Header { title: "Started" }
+                        Header { title: "Runtime" }
+                        Header { title: "Runes solved / failed" }
+                        Header { title: "Deaths" }
+                        Header { title: "Channels" }
+                        Header { title: "Actions" }
+                    }
+                }
+                tbody {
+                    for row in rows {
+                        tr {
+                            for value in row {
+                                Data { value }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn Stat(name: String, value: String) -> Element {
+    rsx! {
+        div { class: "flex flex-col",
+            p { class: "text-xs text-primary-text", {name} }
+            p { class: "text-xs text-secondary-text", {value} }
+        }
+    }
+}
+
+fn format_started(started_millis: u64) -> String {
+    humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_millis(started_millis))
+        .to_string()
+}
+
+fn format_runtime(runtime_millis: u64) -> String {
+    let secs = runtime_millis / 1000;
+
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}